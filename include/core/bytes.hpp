@@ -0,0 +1,29 @@
+#ifndef CORE_BYTES_HPP_
+#define CORE_BYTES_HPP_
+
+#include <cstddef>
+#include <cstdint>
+#include <vector>
+
+namespace steganography {
+
+/* multi-byte integers are always serialized big endian */
+template <typename T>
+void AppendInt(std::vector<uint8_t>& buffer, T value) {
+    for (int shift = (sizeof(T) - 1) * 8; shift >= 0; shift -= 8) {
+        buffer.push_back(static_cast<uint8_t>(value >> shift));
+    }
+}
+
+template <typename T>
+T ReadInt(const std::vector<uint8_t>& buffer, std::size_t offset) {
+    T value = 0;
+    for (std::size_t i = 0; i < sizeof(T); ++i) {
+        value = static_cast<T>((value << 8) | buffer[offset + i]);
+    }
+    return value;
+}
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,282 @@
+#ifndef CORE_CARRIER_HPP_
+#define CORE_CARRIER_HPP_
+
+#include <array>
+#include <cstddef>
+#include <cstdint>
+#include <vector>
+
+namespace steganography {
+
+/* Storage a payload is hidden in, one byte to a cell. The framing,
+ * checksums, error correction, and copies of frame.hpp are written against
+ * it once, and each kind of container only supplies its cells. Carriers
+ * over pixels can also be built over const pixels, for reading only, and
+ * such a carrier must only be passed on as a const Carrier. */
+class Carrier {
+   public:
+    virtual ~Carrier() = default;
+
+    virtual std::size_t CellCount() const = 0;
+    virtual uint8_t ReadCell(std::size_t cell) const = 0;
+    virtual void WriteCell(std::size_t cell, uint8_t byte) = 0;
+
+    /* the row of the container a cell is stored in, to tell where damage
+     * lies, containers without rows keep every cell in row 0 */
+    virtual std::size_t CellRow(std::size_t cell) const;
+
+    /* cells stored in each row, 0 for containers without rows */
+    virtual double CellsPerRow() const;
+};
+
+/* the low nibbles of consecutive channels of interleaved 8 bit RGB pixels,
+ * two to a cell, in a framebuffer whose rows start stride bytes apart. Pixels
+ * of more than three bytes, such as RGBA, carry data in their first three. */
+class FramebufferCarrier : public Carrier {
+   public:
+    FramebufferCarrier(uint8_t* pixels, std::size_t width, std::size_t height,
+                       std::size_t stride, std::size_t pixel_size = 3);
+    FramebufferCarrier(const uint8_t* pixels, std::size_t width,
+                       std::size_t height, std::size_t stride,
+                       std::size_t pixel_size = 3);
+
+    std::size_t CellCount() const override;
+    uint8_t ReadCell(std::size_t cell) const override;
+    void WriteCell(std::size_t cell, uint8_t byte) override;
+    std::size_t CellRow(std::size_t cell) const override;
+    double CellsPerRow() const override;
+
+   private:
+    uint8_t& Channel(std::size_t slot) const;
+
+    uint8_t* pixels_;
+    std::size_t width_;
+    std::size_t height_;
+    std::size_t stride_;
+    std::size_t pixel_size_;
+};
+
+/* the low nibbles of consecutive channels of interleaved 8 bit RGB pixels,
+ * two to a cell, as FramebufferCarrier lays them out but visiting the pixels
+ * in the order of the row-major indices given instead of row by row */
+class OrderedCarrier : public Carrier {
+   public:
+    OrderedCarrier(uint8_t* pixels, std::size_t width, std::size_t stride,
+                   std::vector<std::size_t> order);
+    OrderedCarrier(const uint8_t* pixels, std::size_t width,
+                   std::size_t stride, std::vector<std::size_t> order);
+
+    std::size_t CellCount() const override;
+    uint8_t ReadCell(std::size_t cell) const override;
+    void WriteCell(std::size_t cell, uint8_t byte) override;
+    std::size_t CellRow(std::size_t cell) const override;
+
+   private:
+    uint8_t& Channel(std::size_t slot) const;
+
+    uint8_t* pixels_;
+    std::size_t width_;
+    std::size_t stride_;
+    std::vector<std::size_t> order_;
+};
+
+/* the low nibble of a single channel of each interleaved 8 bit RGB pixel,
+ * two pixels to a cell, in a framebuffer whose rows start stride bytes
+ * apart. channels names the channel carrying data, 0 to 2, for every pixel
+ * row by row, the other two are never changed. */
+class RotationCarrier : public Carrier {
+   public:
+    RotationCarrier(uint8_t* pixels, std::size_t width, std::size_t height,
+                    std::size_t stride, std::vector<uint8_t> channels);
+    RotationCarrier(const uint8_t* pixels, std::size_t width,
+                    std::size_t height, std::size_t stride,
+                    std::vector<uint8_t> channels);
+
+    std::size_t CellCount() const override;
+    uint8_t ReadCell(std::size_t cell) const override;
+    void WriteCell(std::size_t cell, uint8_t byte) override;
+    std::size_t CellRow(std::size_t cell) const override;
+    double CellsPerRow() const override;
+
+   private:
+    uint8_t& Channel(std::size_t pixel) const;
+
+    uint8_t* pixels_;
+    std::size_t width_;
+    std::size_t height_;
+    std::size_t stride_;
+    std::vector<uint8_t> channels_;
+};
+
+/* One bit to each channel of interleaved 8 bit RGB pixels, eight to a cell,
+ * set where the channel differs from the same channel of a reference only
+ * the reader and writer have. Setting a bit moves the channel one step from
+ * the reference, up or down by its position, so no channel ever changes by
+ * more than 1. Both framebuffers have the same size and stride. */
+class DifferenceCarrier : public Carrier {
+   public:
+    DifferenceCarrier(uint8_t* pixels, const uint8_t* reference,
+                      std::size_t width, std::size_t height,
+                      std::size_t stride);
+    DifferenceCarrier(const uint8_t* pixels, const uint8_t* reference,
+                      std::size_t width, std::size_t height,
+                      std::size_t stride);
+
+    std::size_t CellCount() const override;
+    uint8_t ReadCell(std::size_t cell) const override;
+    void WriteCell(std::size_t cell, uint8_t byte) override;
+    std::size_t CellRow(std::size_t cell) const override;
+    double CellsPerRow() const override;
+
+   private:
+    std::size_t Offset(std::size_t slot) const;
+
+    uint8_t* pixels_;
+    const uint8_t* reference_;
+    std::size_t width_;
+    std::size_t height_;
+    std::size_t stride_;
+};
+
+/* One bit plane of interleaved 8 bit RGB pixels, bit plane of every
+ * channel in row-major order, eight channels to a cell. Plane 0 is the
+ * least significant, the other planes are never changed, so each plane can
+ * carry a payload of its own. */
+class BitPlaneCarrier : public Carrier {
+   public:
+    BitPlaneCarrier(uint8_t* pixels, std::size_t width, std::size_t height,
+                    std::size_t stride, int plane);
+    BitPlaneCarrier(const uint8_t* pixels, std::size_t width,
+                    std::size_t height, std::size_t stride, int plane);
+
+    std::size_t CellCount() const override;
+    uint8_t ReadCell(std::size_t cell) const override;
+    void WriteCell(std::size_t cell, uint8_t byte) override;
+    std::size_t CellRow(std::size_t cell) const override;
+    double CellsPerRow() const override;
+
+   private:
+    uint8_t& Channel(std::size_t slot) const;
+
+    uint8_t* pixels_;
+    std::size_t width_;
+    std::size_t height_;
+    std::size_t stride_;
+    int plane_;
+};
+
+/* The order of the same channel of two side by side pixels of interleaved 8
+ * bit RGB, the first pixel of a row paired with the second and so on, eight
+ * pairs to a cell. A pair carries a 1 when its first value is the larger,
+ * and writing a bit swaps the two values when needed, so the image keeps its
+ * histogram exactly. Pairs of equal values, and the last pixel of an odd
+ * width row, carry nothing and are never changed. */
+class PairSwapCarrier : public Carrier {
+   public:
+    PairSwapCarrier(uint8_t* pixels, std::size_t width, std::size_t height,
+                    std::size_t stride);
+    PairSwapCarrier(const uint8_t* pixels, std::size_t width,
+                    std::size_t height, std::size_t stride);
+
+    std::size_t CellCount() const override;
+    uint8_t ReadCell(std::size_t cell) const override;
+    void WriteCell(std::size_t cell, uint8_t byte) override;
+    std::size_t CellRow(std::size_t cell) const override;
+
+   private:
+    uint8_t* pixels_;
+    std::size_t stride_;
+    std::vector<std::size_t> pairs_; /* the first value of each unequal pair */
+};
+
+/* One bit to each channel of interleaved 8 bit RGB pixels whose value is the
+ * peak of its histogram or one step from it towards the empty bin, after
+ * the values in between were shifted by one to make room, eight to a cell.
+ * A channel at the peak carries a 0 and one a step off a 1, so shifting the
+ * values back restores the cover exactly. Channels are visited in row-major
+ * order from first_slot on, and those whose peak equals their empty bin
+ * carry nothing. */
+class HistogramShiftCarrier : public Carrier {
+   public:
+    HistogramShiftCarrier(uint8_t* pixels, std::size_t width,
+                          std::size_t height, std::size_t stride,
+                          const std::array<uint8_t, 3>& peaks,
+                          const std::array<uint8_t, 3>& empties,
+                          std::size_t first_slot = 0);
+    HistogramShiftCarrier(const uint8_t* pixels, std::size_t width,
+                          std::size_t height, std::size_t stride,
+                          const std::array<uint8_t, 3>& peaks,
+                          const std::array<uint8_t, 3>& empties,
+                          std::size_t first_slot = 0);
+
+    std::size_t CellCount() const override;
+    uint8_t ReadCell(std::size_t cell) const override;
+    void WriteCell(std::size_t cell, uint8_t byte) override;
+    std::size_t CellRow(std::size_t cell) const override;
+
+   private:
+    uint8_t* pixels_;
+    std::size_t stride_;
+    std::array<uint8_t, 3> peaks_;
+    std::array<uint8_t, 3> ones_;   /* the value carrying a 1 per channel */
+    std::vector<std::size_t> slots_; /* offsets of the channels carrying */
+};
+
+/* The parity of palette indices, eight pixels to a cell, for containers
+ * whose palette has to stay as it is. The entries are ranked by luminance
+ * and paired off in rank order, so flipping a bit moves a pixel to the entry
+ * closest in brightness to its own. Pixels of the reserved entry, such as a
+ * transparent one, and of the brightest entry of an odd sized palette are
+ * never changed and carry nothing. */
+class PaletteCarrier : public Carrier {
+   public:
+    /* palette holds RGB triples, indices one entry per pixel row by row */
+    PaletteCarrier(std::vector<uint8_t>& indices,
+                   const std::vector<uint8_t>& palette, std::size_t width,
+                   int reserved = -1);
+
+    std::size_t CellCount() const override;
+    uint8_t ReadCell(std::size_t cell) const override;
+    void WriteCell(std::size_t cell, uint8_t byte) override;
+    std::size_t CellRow(std::size_t cell) const override;
+
+   private:
+    std::vector<uint8_t>& indices_;
+    std::size_t width_;
+    std::vector<int> rank_;    /* per entry, -1 for entries carrying nothing */
+    std::vector<int> partner_; /* the entry of the other rank in its pair */
+    std::vector<std::size_t> pixels_; /* the pixels carrying a bit each */
+};
+
+/* the low nibbles of consecutive 16 bit audio samples, two to a cell, the
+ * channels of a frame being consecutive samples */
+class SampleCarrier : public Carrier {
+   public:
+    explicit SampleCarrier(std::vector<int16_t>& samples);
+
+    std::size_t CellCount() const override;
+    uint8_t ReadCell(std::size_t cell) const override;
+    void WriteCell(std::size_t cell, uint8_t byte) override;
+
+   private:
+    std::vector<int16_t>& samples_;
+};
+
+/* whole bytes of a buffer, for space a container sets aside from its
+ * content such as a PNG chunk or a file trailer. The buffer is sized up
+ * front, PayloadCells tells how large a payload needs it to be. */
+class ByteCarrier : public Carrier {
+   public:
+    explicit ByteCarrier(std::vector<uint8_t>& bytes);
+
+    std::size_t CellCount() const override;
+    uint8_t ReadCell(std::size_t cell) const override;
+    void WriteCell(std::size_t cell, uint8_t byte) override;
+
+   private:
+    std::vector<uint8_t>& bytes_;
+};
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,14 @@
+#ifndef CRC32_HPP_
+#define CRC32_HPP_
+
+#include <cstddef>
+#include <cstdint>
+
+namespace steganography {
+
+/* the CRC-32 of zlib and PNG, computed without either */
+uint32_t Crc32(const uint8_t* data, std::size_t size);
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,41 @@
+#ifndef ECC_HPP_
+#define ECC_HPP_
+
+#include <cstddef>
+#include <cstdint>
+#include <vector>
+
+namespace steganography {
+
+enum class EccScheme {
+    kNone,
+    kHamming, /* Hamming(7,4), corrects one flipped bit per 7 bit codeword */
+};
+
+/* two 7 bit codewords per byte */
+const std::size_t kHammingBitsPerByte = 14;
+
+/* bytes needed to hold size bytes once every nibble is a 7 bit codeword */
+std::size_t HammingEncodedSize(std::size_t size);
+
+std::vector<uint8_t> HammingEncode(const std::vector<uint8_t>& data);
+
+/* decodes size bytes from coded, corrected is the number of codewords that
+ * had a flipped bit repaired */
+std::vector<uint8_t> HammingDecode(const std::vector<uint8_t>& coded,
+                                   std::size_t size, std::size_t& corrected);
+
+/* a block interleaver writes the bits row by row into rows of depth bits and
+ * reads them column by column, so a burst of up to n / depth damaged bits
+ * touches every row at most once, index maps bit i of an n bit stream */
+std::size_t InterleavedIndex(std::size_t i, std::size_t n, std::size_t depth);
+
+std::vector<uint8_t> Interleave(const std::vector<uint8_t>& data,
+                                std::size_t depth);
+
+std::vector<uint8_t> Deinterleave(const std::vector<uint8_t>& data,
+                                  std::size_t depth);
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,146 @@
+#ifndef FRAME_HPP_
+#define FRAME_HPP_
+
+#include <cstddef>
+#include <cstdint>
+#include <string>
+#include <vector>
+
+#include "core/carrier.hpp"
+#include "core/ret_code.hpp"
+
+namespace steganography {
+
+/* see docs/FORMAT.md for the on-disk layout of embedded payloads */
+const uint8_t kFormatVersion = 4;
+
+/* magic, version, flags, method, header size, length */
+const std::size_t kPayloadHeaderSize = 12;
+
+/* payload bytes covered by each CRC32 when kFlagChecksums is set */
+const std::size_t kChecksumBlockSize = 256;
+const std::size_t kChecksumSize = 4;
+
+enum PayloadFlags : uint8_t {
+    kFlagCompressed = 0x01,
+    kFlagChecksums = 0x02,
+    kFlagHamming = 0x04,
+    kFlagInterleaved = 0x08,
+    kFlagCopies = 0x10,
+    kFlagSync = 0x20, /* implies kFlagChecksums, excludes kFlagCopies */
+    kFlagPipeline = 0x40, /* the payload starts with its pipeline stages */
+    kFlagContentType = 0x80, /* then with its MIME type and file name */
+};
+
+/* the copy count is stored in a single header byte */
+const int kMaxCopies = 255;
+
+/* the pipeline stage count is stored in a single byte too */
+const std::size_t kMaxPipelineStages = 255;
+
+/* as are the sizes of the MIME type and file name */
+const std::size_t kMaxContentTypeSize = 255;
+
+/* bits per interleaver row, one per bit of a Hamming(7,4) codeword */
+const std::size_t kInterleaveDepth = 7;
+
+enum class EmbedMethodId : uint8_t {
+    kSequentialLsb = 0,
+};
+
+struct PayloadHeader {
+    uint8_t version = kFormatVersion;
+    uint8_t flags = 0;
+    EmbedMethodId method = EmbedMethodId::kSequentialLsb;
+    uint32_t length = 0;
+    uint8_t copies = 1;        /* complete copies embedded, see kFlagCopies */
+    bool keyed_copies = false; /* copies were placed using a key */
+    /* with kFlagPipeline, the stages the payload went through in order, see
+     * pipeline.hpp, length then counts their record too */
+    std::vector<uint8_t> stages;
+    /* with kFlagContentType, what the payload is and the name of the file
+     * it came from, either may be empty */
+    std::string content_type;
+    std::string filename;
+};
+
+/* a checksum block that failed verification, rows are in the container */
+struct DamagedBlock {
+    std::size_t offset = 0; /* first payload byte of the block */
+    std::size_t length = 0;
+    std::size_t first_row = 0;
+    std::size_t last_row = 0;
+};
+
+/* what the framing could tell about damage to the container */
+struct FrameDamage {
+    std::vector<DamagedBlock> blocks;
+    std::size_t corrected_bits = 0; /* flipped bits repaired by the ECC */
+    std::size_t outvoted_bits = 0;  /* copy bits disagreeing with the vote */
+    /* set when the payload was located through its sync markers */
+    bool resynced = false;
+    std::ptrdiff_t shift_cells = 0; /* positive when rows were added above */
+    double shift_rows = 0.0;
+};
+
+/* where keyed copies go, drawn from a key stream the core has no cipher to
+ * produce itself. A fresh one starting from the same key must be given to
+ * extract as was given to embed. */
+class CopyPlacement {
+   public:
+    virtual ~CopyPlacement() = default;
+
+    /* uniformly distributed value in [0, bound) */
+    virtual uint64_t NextBelow(uint64_t bound) = 0;
+};
+
+struct FrameConfig {
+    uint8_t flags = 0;
+    int copies = 1; /* embedded copies voted on bit by bit when extracting */
+    CopyPlacement* placement = nullptr; /* else evenly spaced */
+    std::vector<uint8_t> stages; /* recorded with kFlagPipeline when given */
+    /* recorded with kFlagContentType when either is given */
+    std::string content_type;
+    std::string filename;
+};
+
+std::size_t PayloadCapacity(const Carrier& carrier);
+
+/* cells a payload of length bytes takes up with the given flags, for sizing
+ * a ByteCarrier, copies take that many times as much and a backup header and
+ * pipeline stages one byte each and one for their count */
+std::size_t PayloadCells(std::size_t length, uint8_t flags = 0);
+
+RetCode EmbedFrame(Carrier& carrier, const std::vector<uint8_t>& payload,
+                   const FrameConfig& config = {});
+
+/* returns kPayloadDamaged when checksummed blocks fail verification, the
+ * payload still holds every block as read so intact ones can be used, and
+ * kInvalidArgument when the copies were placed with a key but no placement
+ * is given, payloads with kFlagSync are found even after rows were cropped
+ * or added */
+RetCode ExtractFrame(const Carrier& carrier, std::vector<uint8_t>& payload,
+                     PayloadHeader& header, FrameDamage& damage,
+                     CopyPlacement* placement = nullptr);
+
+/* the pieces payloads are framed from, for embedding and extracting a
+ * payload a piece at a time */
+
+/* the fields every version of the header starts with */
+void AppendFrameHeader(std::vector<uint8_t>& framed, uint8_t flags,
+                       std::size_t header_size, uint32_t length);
+
+/* parses the header starting at cell, header_size is its size in cells */
+RetCode ReadFrameHeader(const Carrier& carrier, std::size_t cell,
+                        PayloadHeader& header, std::size_t& header_size);
+
+/* bytes the payload occupies after the header once the flags are applied */
+std::size_t FrameBodySize(std::size_t length, uint8_t flags);
+
+/* read count cells starting at the given cell, cell is advanced past them */
+std::vector<uint8_t> ReadCells(const Carrier& carrier, std::size_t& cell,
+                               std::size_t count);
+
+}  // namespace steganography
+
+#endif
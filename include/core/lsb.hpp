@@ -0,0 +1,30 @@
+#ifndef CORE_LSB_HPP_
+#define CORE_LSB_HPP_
+
+#include <cstddef>
+#include <cstdint>
+
+namespace steganography {
+
+/* the default and valid range of bits per channel holding the secret */
+const int kDefaultBits = 4;
+const int kMinBits = 1;
+const int kMaxBits = 8;
+
+/* replaces the low bits of each cover byte with the high bits of the
+ * matching secret byte over a row of interleaved channels, such as a row of
+ * a raw RGB framebuffer, in place over the cover, the pointers must not
+ * overlap */
+void MergeRow(uint8_t* cover, const uint8_t* secret, std::size_t bytes,
+              int bits);
+
+/* the same with a black secret */
+void ClearLowBits(uint8_t* cover, std::size_t bytes, int bits);
+
+/* moves the low bits of each byte back up to the high bits */
+void UnmergeRow(const uint8_t* pixels, uint8_t* unmerged, std::size_t bytes,
+                int bits);
+
+}  // namespace steganography
+
+#endif
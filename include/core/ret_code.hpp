@@ -0,0 +1,36 @@
+#ifndef RET_CODE_HPP_
+#define RET_CODE_HPP_
+
+namespace steganography {
+
+enum class RetCode {
+    kSuccess,
+    kInvalidFileFormat,
+    kFileNotFound,
+    kInvalidDimensions,
+    kInsufficientCapacity,
+    kPayloadNotFound,
+    kInvalidPayload,
+    kIoError,
+    kUnsupportedFormat,
+    kFileExists,
+    kInvalidKey,
+    kInvalidArgument,
+    kKeyMismatch,
+    kPasswordMismatch,
+    kAuthenticationFailed,
+    kNetworkError,
+    kClipboardUnavailable,
+    kPayloadDamaged,
+    kOutOfBandChanges,
+    kSameInput,
+    kOutputIsInput,
+    kImageTooLarge,
+    kSealBroken,
+    kConversionRefused,
+    kIncompleteParts,
+};
+
+}  // namespace steganography
+
+#endif
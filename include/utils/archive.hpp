@@ -0,0 +1,292 @@
+#ifndef ARCHIVE_HPP_
+#define ARCHIVE_HPP_
+
+#include <cstddef>
+#include <cstdint>
+#include <optional>
+#include <string>
+#include <vector>
+
+#include "core/ecc.hpp"
+#include "utils/payload.hpp"
+#include "utils/recipient.hpp"
+#include "utils/steganography_util.hpp"
+#include "utils/traversal.hpp"
+
+namespace steganography {
+
+/* entry name used for messages hidden with PackMessage */
+const char* const kMessageName = "message.txt";
+
+/* written next to the recovered files when unpacking a damaged payload */
+const char* const kDamageReportName = "steganography-damage.txt";
+
+struct PackConfig {
+    std::string password; /* encrypts the archive and hides its presence */
+    std::vector<std::string> decoy_inputs; /* optional decoy archive */
+    std::string decoy_password;            /* unlocks only the decoy */
+    /* one per input, each packed as an archive of its own in a bit plane of
+     * its own that only its password unlocks, excludes a password and the
+     * options it excludes */
+    std::vector<std::string> layer_passwords;
+    /* with a password, draws the salts, nonces, and filler from this seed
+     * so the image can be reproduced, without one they are drawn at random */
+    std::optional<uint64_t> seed;
+    bool create_dirs = false; /* create missing parents of the output */
+    /* only a reader holding the untouched cover can unpack, excludes a
+     * password */
+    bool differential = false;
+    /* swaps pixel pairs to keep the cover's histogram */
+    bool pair_swap = false;
+    /* lets unpacking restore the exact cover, like differential and
+     * pair_swap it excludes a password and the other two */
+    bool reversible = false;
+    /* a single channel of each pixel carries data, rotating on a schedule
+     * drawn from keyfile, which it needs, excludes the other three */
+    bool rotate_channels = false;
+    /* the order the usual layout visits the pixels in, unpacking needs the
+     * same, excludes a password and the other layouts */
+    Traversal traversal;
+    /* encrypts to their identity files, excludes a password */
+    std::vector<Recipient> recipients;
+    /* each unlocks the archive on its own, alongside the recipients */
+    std::vector<std::string> recipient_passwords;
+    /* the following would reveal the archive so they exclude a password */
+    bool checksums = false; /* CRC32 per block to localize damage */
+    EccScheme ecc = EccScheme::kNone;
+    bool interleave = false; /* spread burst damage so the ECC can repair it */
+    int copies = 1;          /* complete copies voted on when unpacking */
+    std::string keyfile;     /* optional key scattering the copies */
+    bool sync = false; /* markers to find the payload in a cropped image */
+    OutputFormat output_format = OutputFormat::kAuto;
+};
+
+struct UnpackConfig {
+    std::string password; /* the real, the decoy, or a layer's password */
+    std::string keyfile;  /* must match the key file used to pack */
+    std::string reference; /* the cover of a differential pack */
+    Traversal traversal;   /* must match the traversal used to pack */
+    std::string restore_cover; /* where to write a reversible pack's cover */
+    std::string identity; /* decrypts a pack sent to recipients */
+    std::string recipient_password; /* or one of the recipient passwords */
+};
+
+/* each input is named relative to the directory holding it, inputs whose
+ * names clash, such as a/notes.txt and b/notes.txt, are kInvalidArgument */
+RetCode Pack(const std::string& cover, const std::string& outfile,
+             const std::vector<std::string>& inputs);
+
+RetCode Pack(const std::string& cover, const std::string& outfile,
+             const std::vector<std::string>& inputs, const PackConfig& config);
+
+RetCode Unpack(const std::string& image, const std::string& outdir);
+
+RetCode Unpack(const std::string& image, const std::string& outdir,
+               const UnpackConfig& config);
+
+/* on kPayloadDamaged the intact files are still written, files cut short by
+ * the damage get a .partial suffix and kDamageReportName lists what was lost */
+RetCode Unpack(const std::string& image, const std::string& outdir,
+               const UnpackConfig& config, DamageReport& damage);
+
+struct PackDirConfig {
+    bool compress = false;    /* gzip the tar before embedding it */
+    bool create_dirs = false; /* create missing parents of the output */
+    bool checksums = false;   /* CRC32 per block to localize damage */
+    EccScheme ecc = EccScheme::kNone;
+    bool interleave = false;
+    int copies = 1;
+    std::string keyfile;
+    bool sync = false;
+    bool differential = false;
+    bool pair_swap = false;
+    bool reversible = false;
+    bool rotate_channels = false;
+    Traversal traversal;
+    std::vector<Recipient> recipients;
+    std::vector<std::string> recipient_passwords;
+    OutputFormat output_format = OutputFormat::kAuto;
+};
+
+struct UnpackDirConfig {
+    std::string keyfile;   /* must match the key file used to pack */
+    std::string reference; /* the cover of a differential pack */
+    Traversal traversal;   /* must match the traversal used to pack */
+    std::string restore_cover; /* where to write a reversible pack's cover */
+    std::string identity; /* decrypts a pack sent to recipients */
+    std::string recipient_password; /* or one of the recipient passwords */
+};
+
+/* hides a text message as a single file archive, see kMessageName */
+RetCode PackMessage(const std::string& cover, const std::string& outfile,
+                    const std::string& message, const PackConfig& config);
+
+RetCode UnpackMessage(const std::string& image, std::string& message,
+                      const UnpackConfig& config);
+
+RetCode PackDirectory(const std::string& cover, const std::string& dir,
+                      const std::string& outfile, bool compress);
+
+RetCode PackDirectory(const std::string& cover, const std::string& dir,
+                      const std::string& outfile, const PackDirConfig& config);
+
+RetCode UnpackDirectory(const std::string& image, const std::string& outdir);
+
+/* salvages a damaged payload the same way Unpack does */
+RetCode UnpackDirectory(const std::string& image, const std::string& outdir,
+                        const UnpackDirConfig& config, DamageReport& damage);
+
+struct PackDataConfig {
+    std::string content_type; /* MIME type, guessed from the input if empty */
+    bool compress = false;    /* gzip the data before embedding it */
+    bool create_dirs = false; /* create missing parents of the output */
+    bool checksums = false;   /* CRC32 per block to localize damage */
+    EccScheme ecc = EccScheme::kNone;
+    bool interleave = false;
+    int copies = 1;
+    std::string keyfile;
+    bool sync = false;
+    bool differential = false;
+    bool pair_swap = false;
+    bool reversible = false;
+    bool rotate_channels = false;
+    Traversal traversal;
+    /* the type and name are left out of the header when encrypting, so
+     * nothing about the data is in the clear */
+    std::vector<Recipient> recipients;
+    std::vector<std::string> recipient_passwords;
+    OutputFormat output_format = OutputFormat::kAuto;
+};
+
+struct UnpackDataConfig {
+    std::string keyfile;   /* must match the key file used to pack */
+    std::string reference; /* the cover of a differential pack */
+    Traversal traversal;   /* must match the traversal used to pack */
+    std::string restore_cover; /* where to write a reversible pack's cover */
+    std::string identity; /* decrypts a pack sent to recipients */
+    std::string recipient_password; /* or one of the recipient passwords */
+};
+
+/* what a payload is, as recorded by PackData or guessed from its bytes */
+struct DataInfo {
+    std::string content_type;
+    bool guessed = false;  /* no type was recorded with the payload */
+    std::string filename;  /* the input's name, empty if none was recorded */
+    std::size_t size = 0;  /* bytes once decrypted and decompressed */
+    std::string path;      /* where UnpackData wrote it */
+};
+
+/* hides a single file as it is, its MIME type and name recorded in the
+ * payload header so it can be written back out under a fitting name */
+RetCode PackData(const std::string& cover, const std::string& input,
+                 const std::string& outfile, const PackDataConfig& config);
+
+/* writes the payload to out. A directory gets it under its recorded name,
+ * and a path without an extension gets the one of its type. Any payload
+ * can be unpacked this way, one without a recorded type has it guessed. */
+RetCode UnpackData(const std::string& image, const std::string& out,
+                   const UnpackDataConfig& config, DataInfo& info);
+
+/* describes the payload without writing it anywhere */
+RetCode InspectData(const std::string& image, const UnpackDataConfig& config,
+                    DataInfo& info);
+
+/* where a payload is kept in the image, see docs/FORMAT.md */
+enum class PayloadLayout {
+    kUsual,        /* the low nibble of every channel */
+    kDifferential, /* pack --differential */
+    kPairSwap,     /* pack --pair-swap */
+    kReversible,   /* pack --reversible */
+    kRotation,     /* pack --rotate-channels */
+    kReordered,    /* the usual layout visited in another order or in part */
+    kPalette,      /* GIF palette index parity */
+    kIcon,         /* a copy in every image of an ICO */
+    kDeniable,     /* pack --password, without a header */
+};
+
+struct InspectConfig {
+    std::string password;  /* tried on an image without a header */
+    std::string keyfile;   /* needed for copies placed with a key */
+    std::string reference; /* the cover of a differential pack */
+    Traversal traversal;   /* the order the pixels were packed in */
+};
+
+struct InspectReport {
+    /* a header was found, or the password opened an archive */
+    bool found = false;
+    bool supported = true; /* false for a header written by a newer release */
+    PayloadLayout layout = PayloadLayout::kUsual;
+    int bits_per_channel = 0; /* low bits carrying data, 0 if not by channel */
+    PayloadHeader header;     /* flags, copies, stages, and content type */
+    std::size_t payload_size = 0; /* stored bytes after the header */
+    bool compressed = false;
+    DamageReport damage; /* blocks failing their checksums and repairs */
+    /* how it is encrypted, if at all */
+    bool deniable = false;           /* pack --password */
+    bool pipeline_encrypted = false; /* an encrypt stage of a pipeline */
+    std::size_t recipients = 0;      /* public keys it was encrypted to */
+    std::size_t recipient_passwords = 0;
+    /* "file archive", "directory archive", a recorded MIME type, or empty
+     * when encryption or compression hides what it is */
+    std::string contents;
+    /* the names in an archive that can be read without a key, the table of
+     * contents Append adds to */
+    std::vector<std::string> entries;
+};
+
+/* reads the payload header and reports how the payload was packed without
+ * decrypting it or writing anything, an image without one is not an error
+ * but leaves found false */
+RetCode Inspect(const std::string& image, const InspectConfig& config,
+                InspectReport& report);
+
+const char* PayloadLayoutName(PayloadLayout layout);
+
+struct AppendConfig {
+    std::string keyfile;  /* must match the key file used to pack */
+    Traversal traversal;  /* and the traversal, kept when packing back */
+    std::string identity; /* unlocks a payload packed for recipients */
+    std::string recipient_password; /* or one of the recipient passwords */
+    /* an encrypted payload is encrypted again to these, which like Rekey
+     * are the complete new set */
+    std::vector<Recipient> recipients;
+    std::vector<std::string> recipient_passwords;
+    bool create_dirs = false; /* create missing parents of the output */
+    OutputFormat output_format = OutputFormat::kAuto;
+};
+
+/* Adds files to the payload already in an image, the image serving as its
+ * own cover so the clean one is not needed. A pack archive or a pack-dir
+ * tree gets the new entries after its own, a merge-data file becomes the
+ * first entry of a new archive. The result is packed back with the options
+ * the payload was packed with, kInvalidArgument for a name already in the
+ * archive and for layouts other than the usual, reordered, and rotation
+ * ones, kInsufficientCapacity when the image cannot hold it all. */
+RetCode Append(const std::string& image, const std::string& outfile,
+               const std::vector<std::string>& inputs,
+               const AppendConfig& config);
+
+struct RekeyConfig {
+    std::string keyfile;  /* must match the key file used to pack */
+    Traversal traversal;  /* and the traversal, kept when packing back */
+    std::string identity; /* unlocks the current payload, as does */
+    std::string recipient_password;
+    /* the complete new set, anyone left out can no longer unpack */
+    std::vector<Recipient> recipients;
+    std::vector<std::string> recipient_passwords;
+    bool create_dirs = false; /* create missing parents of the output */
+    OutputFormat output_format = OutputFormat::kAuto;
+};
+
+/* re-encrypts a payload packed with recipients to a new set of them under a
+ * new file key, and packs it back into the image with the options it was
+ * packed with, in the usual layout unless it was found reordered or rotated.
+ * kInvalidArgument for a payload in the pair swap, reversible, or
+ * differential layout or with a pipeline, as Append gives, since packing it
+ * again would lose its layout */
+RetCode Rekey(const std::string& image, const std::string& outfile,
+              const RekeyConfig& config);
+
+}  // namespace steganography
+
+#endif
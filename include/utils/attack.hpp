@@ -0,0 +1,33 @@
+#ifndef ATTACK_HPP_
+#define ATTACK_HPP_
+
+#include <cstddef>
+#include <string>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* transformations are applied in the order scale, jpeg, noise */
+struct AttackConfig {
+    /* 1-100, 0 skips recompression, which is all a build without JPEG
+     * support accepts */
+    int jpeg_quality = 0;
+    double scale = 1.0;   /* downscale factor, the image is scaled back up */
+    double noise = 0.0;   /* standard deviation of additive gaussian noise */
+};
+
+struct AttackReport {
+    double bits_intact = 0.0;   /* fraction of low nibble bits unchanged */
+    double bytes_intact = 0.0;  /* fraction of embedded bytes unchanged */
+    double secret_psnr = 0.0;   /* quality of the secret extracted by unmerge */
+    bool had_payload = false;   /* a pack payload was present before */
+    bool payload_intact = false;
+};
+
+RetCode SimulateAttack(const std::string& image, const AttackConfig& config,
+                       AttackReport& report);
+
+}  // namespace steganography
+
+#endif
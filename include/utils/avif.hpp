@@ -0,0 +1,21 @@
+#ifndef AVIF_HPP_
+#define AVIF_HPP_
+
+#include <boost/gil.hpp>
+#include <cstdint>
+#include <string>
+
+namespace steganography {
+
+/* reads the primary image of an AVIF as 8 bit RGB, alpha is dropped, false
+ * when it cannot be decoded */
+bool ReadAvif(const std::string& filename, boost::gil::rgb8_image_t& image);
+
+/* the dimensions and decoded bytes per pixel of the primary image without
+ * decoding it */
+bool ReadAvifHeader(const std::string& filename, uint64_t& width,
+                    uint64_t& height, uint64_t& pixel_bytes);
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,121 @@
+#ifndef BATCH_HPP_
+#define BATCH_HPP_
+
+#include <cstddef>
+#include <cstdint>
+#include <string>
+#include <vector>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* what a manifest row does with its secret */
+enum class BatchAction {
+    kMerge,     /* merges a secret image as merge does */
+    kMergeData, /* hides a file as merge-data does */
+};
+
+/* one row of a manifest, relative paths are taken from the manifest's
+ * directory */
+struct BatchJob {
+    std::size_t line = 0; /* of the row in the manifest, from 1 */
+    std::string cover;
+    std::string secret;
+    std::string output;
+    BatchAction action = BatchAction::kMerge;
+    /* the optional columns, left at their defaults when a row is blank */
+    std::string keyfile;
+    std::string password; /* merge only, requires a key file */
+    int bits = 4;         /* merge only */
+    std::string method = "lsb"; /* merge only */
+    bool compress = false;      /* merge-data only */
+    OutputFormat output_format = OutputFormat::kAuto;
+};
+
+struct BatchConfig {
+    bool create_dirs = false; /* create missing parents of the outputs */
+    unsigned threads = 1;     /* rows run at once, 0 for one per core */
+    bool fail_fast = false;   /* start no more rows once one has failed */
+    /* rows are started no faster than either limit allows on average, the
+     * bytes being those of each row's cover and secret, 0 for no limit */
+    uint64_t max_bytes_per_second = 0;
+    double max_images_per_minute = 0.0;
+    bool low_priority = false; /* run at the lowest CPU and I/O priority */
+    /* Records every row done with the SHA-256 of its output, a row found
+     * there unchanged, inputs and password included, whose output still
+     * hashes the same is not run again, empty keeps no state */
+    std::string state;
+};
+
+struct BatchResult {
+    BatchJob job;
+    RetCode rc = RetCode::kSuccess;
+    double milliseconds = 0.0; /* taken by the row */
+    /* merge rows, see MergeReport */
+    double psnr = 0.0;
+    double capacity_used = 0.0;
+    std::size_t pixels_modified = 0;
+    std::vector<std::string> warnings; /* codes of the warnings raised */
+    uint64_t bytes = 0; /* merge-data rows, size of the file hidden */
+    bool resumed = false; /* done by an earlier run, so without stats */
+};
+
+/* how a batch went as a whole */
+enum class BatchOutcome {
+    kSuccess, /* every row succeeded */
+    kPartial, /* some rows failed, the others were written */
+    kFailure, /* no row succeeded */
+};
+
+/* the rows that failed, for reporting after the batch */
+struct BatchError {
+    std::size_t line = 0;
+    std::string path; /* the row's cover */
+    RetCode rc = RetCode::kSuccess;
+};
+
+struct BatchSummary {
+    BatchOutcome outcome = BatchOutcome::kSuccess;
+    std::size_t succeeded = 0;
+    std::size_t failed = 0;
+    std::size_t skipped = 0; /* left unrun by fail_fast */
+    std::size_t resumed = 0; /* done by an earlier run, among succeeded */
+    std::vector<BatchError> errors; /* in manifest order */
+};
+
+struct BatchManifest {
+    char separator = ','; /* '\t' for a TSV manifest */
+    std::vector<BatchJob> jobs;
+};
+
+/* Reads a manifest of comma separated values, or tab separated ones when its
+ * header has a tab. The header names the columns, cover, secret, and output
+ * are required and action, keyfile, password, bits, method, compress, and
+ * format are optional. A row without an action merges an image secret and
+ * hides any other file. Fields may be double quoted. kInvalidArgument for a
+ * missing or unknown column or a value that cannot be read, with line set to
+ * the offending line. */
+RetCode ReadManifest(const std::string& path, BatchManifest& manifest,
+                     std::size_t& line);
+
+/* Runs the rows on config.threads workers, each row on its own so one that
+ * fails, even on a corrupt image, leaves the rest to carry on. results holds
+ * the rows that ran in manifest order and summary how the batch went, a
+ * failed row is reported there rather than returned. Rows writing the same
+ * output must not run at once, kInvalidArgument when a manifest has them. */
+RetCode RunBatch(const BatchManifest& manifest, const BatchConfig& config,
+                 std::vector<BatchResult>& results, BatchSummary& summary);
+
+/* writes a result manifest with the manifest's separator, a row per result
+ * with its paths, status, and stats */
+RetCode WriteBatchResults(const std::string& path, char separator,
+                          const std::vector<BatchResult>& results);
+
+/* the status written to the result manifest, "ok" for success and
+ * "resumed" for a row done by an earlier run */
+const char* BatchStatus(const BatchResult& result);
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,61 @@
+#ifndef BYTES_HPP_
+#define BYTES_HPP_
+
+#include <cstddef>
+#include <cstdint>
+#include <string>
+#include <vector>
+
+#include "core/bytes.hpp"
+
+namespace steganography {
+
+inline void AppendString(std::vector<uint8_t>& buffer,
+                         const std::string& str) {
+    AppendInt<uint16_t>(buffer, static_cast<uint16_t>(str.size()));
+    buffer.insert(buffer.end(), str.cbegin(), str.cend());
+}
+
+/* lowercase hex, two digits to a byte */
+inline std::string ToHex(const uint8_t* bytes, std::size_t size) {
+    static const char kDigits[] = "0123456789abcdef";
+    std::string hex;
+    for (std::size_t i = 0; i < size; ++i) {
+        hex += kDigits[bytes[i] >> 4];
+        hex += kDigits[bytes[i] & 0x0F];
+    }
+    return hex;
+}
+
+/* exactly size bytes of hex in either case, false for anything else */
+inline bool FromHex(const std::string& hex, uint8_t* bytes,
+                    std::size_t size) {
+    if (hex.size() != 2 * size) {
+        return false;
+    }
+    auto digit = [](char c) {
+        if ((c >= '0') && (c <= '9')) {
+            return c - '0';
+        }
+        if ((c >= 'a') && (c <= 'f')) {
+            return c - 'a' + 10;
+        }
+        if ((c >= 'A') && (c <= 'F')) {
+            return c - 'A' + 10;
+        }
+        return -1;
+    };
+    for (std::size_t i = 0; i < size; ++i) {
+        int high = digit(hex[2 * i]);
+        int low = digit(hex[2 * i + 1]);
+        if ((high < 0) || (low < 0)) {
+            return false;
+        }
+        bytes[i] = static_cast<uint8_t>((high << 4) | low);
+    }
+    return true;
+}
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,21 @@
+#ifndef CARRIER_HPP_
+#define CARRIER_HPP_
+
+#include <boost/gil.hpp>
+
+#include "core/carrier.hpp"
+
+namespace steganography {
+
+/* the pixels of an image as a framebuffer */
+class ImageCarrier : public FramebufferCarrier {
+   public:
+    explicit ImageCarrier(boost::gil::rgb8_image_t& image);
+
+    /* for reading only, pass it on as a const Carrier */
+    explicit ImageCarrier(const boost::gil::rgb8_image_t& image);
+};
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,44 @@
+#ifndef CHANNELS_HPP_
+#define CHANNELS_HPP_
+
+#include <array>
+#include <boost/gil.hpp>
+#include <string>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* the cover channel holding one of up to three secrets */
+enum class Channel {
+    kRed,
+    kGreen,
+    kBlue,
+};
+
+struct ChannelConfig {
+    /* low bits of each cover channel holding its secret, split between the
+     * secret's red, green, and blue as the value channel splits them in HSV */
+    int bits = 4;
+    bool create_dirs = false; /* create missing parents of the output */
+};
+
+/* Hides secrets[0] in the red channel of cover, secrets[1] in the green,
+ * and secrets[2] in the blue, each at its own size in the top left corner.
+ * An empty path leaves its channel as it is, at least one must be given.
+ * kInvalidDimensions when a secret is larger than the cover. */
+RetCode MergeChannels(const std::string& cover,
+                      const std::array<std::string, 3>& secrets,
+                      const std::string& outfile,
+                      const ChannelConfig& config);
+
+/* Recovers the secret hidden in channel as large as the image, anything
+ * past the secret comes out black. There is no telling an unused channel
+ * from a used one, its extraction is noise. */
+RetCode UnmergeChannel(const std::string& image, Channel channel,
+                       const std::string& outfile,
+                       const ChannelConfig& config);
+
+}  // namespace steganography
+
+#endif
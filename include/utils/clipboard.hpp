@@ -0,0 +1,19 @@
+#ifndef CLIPBOARD_HPP_
+#define CLIPBOARD_HPP_
+
+#include <string>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* the clipboard is accessed through wl-paste/wl-copy under Wayland,
+ * xclip or xsel under X11, and pbpaste/pbcopy on macOS, returns
+ * kClipboardUnavailable if none of them can be used */
+RetCode ReadClipboard(std::string& text);
+
+RetCode WriteClipboard(const std::string& text);
+
+}  // namespace steganography
+
+#endif
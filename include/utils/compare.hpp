@@ -0,0 +1,33 @@
+#ifndef COMPARE_HPP_
+#define COMPARE_HPP_
+
+#include <boost/gil.hpp>
+#include <string>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+struct CompareConfig {
+    /* what the difference is multiplied by, 0 stretches the largest
+     * difference to 255 */
+    int amplify = 0;
+    int gap = 8;              /* white pixels between the panels */
+    bool create_dirs = false; /* create missing parents of the output */
+};
+
+/* the cover, the stego image, and the difference between them side by side,
+ * left to right. The difference is taken per channel, so a channel left
+ * alone stays black, and amplified so changes to the low bits can be seen.
+ * kInvalidDimensions when the images differ in size. */
+RetCode RenderComparison(const boost::gil::rgb8_image_t& cover,
+                         const boost::gil::rgb8_image_t& stego,
+                         const CompareConfig& config,
+                         boost::gil::rgb8_image_t& rendered);
+
+RetCode Compare(const std::string& cover, const std::string& stego,
+                const std::string& outfile, const CompareConfig& config);
+
+}  // namespace steganography
+
+#endif
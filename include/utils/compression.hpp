@@ -0,0 +1,17 @@
+#ifndef COMPRESSION_HPP_
+#define COMPRESSION_HPP_
+
+#include <cstdint>
+#include <vector>
+
+namespace steganography {
+
+std::vector<uint8_t> Compress(const std::vector<uint8_t>& data);
+
+bool Decompress(const std::vector<uint8_t>& data, std::vector<uint8_t>& out);
+
+bool IsCompressed(const std::vector<uint8_t>& data);
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,31 @@
+#ifndef COVER_GEN_HPP_
+#define COVER_GEN_HPP_
+
+#include <cstddef>
+#include <cstdint>
+#include <optional>
+#include <string>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+enum class CoverStyle {
+    kNoise,
+    kGradient,
+    kPlasma,
+};
+
+struct CoverGenConfig {
+    std::ptrdiff_t width = 1920;
+    std::ptrdiff_t height = 1080;
+    CoverStyle style = CoverStyle::kPlasma;
+    std::optional<uint64_t> seed; /* none picks a random seed */
+    bool create_dirs = false;     /* create missing parents of the output */
+};
+
+RetCode GenerateCover(const std::string& outfile, const CoverGenConfig& config);
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,28 @@
+#ifndef COVER_RANK_HPP_
+#define COVER_RANK_HPP_
+
+#include <cstddef>
+#include <string>
+#include <vector>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+struct CoverScore {
+    std::string path;
+    std::size_t capacity = 0; /* bytes available to pack */
+    double utilization = 0.0; /* fraction of capacity the payload uses */
+    double entropy = 0.0;     /* luminance entropy normalized to [0, 1] */
+    double texture = 0.0;     /* local gradient strength normalized to [0, 1] */
+    double detectability = 0.0;
+    double score = 0.0; /* 0 when the payload does not fit */
+};
+
+/* scores, sorted best first, for every supported image directly in dir */
+RetCode RankCovers(const std::string& dir, std::size_t payload_size,
+                   std::vector<CoverScore>& scores);
+
+}  // namespace steganography
+
+#endif
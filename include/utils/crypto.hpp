@@ -0,0 +1,46 @@
+#ifndef CRYPTO_HPP_
+#define CRYPTO_HPP_
+
+#include <cstddef>
+#include <cstdint>
+#include <string>
+#include <vector>
+
+#include "utils/key.hpp"
+
+namespace steganography {
+
+const std::size_t kSaltSize = 16;
+const std::size_t kNonceSize = 12;
+const std::size_t kAeadTagSize = 16;
+const std::size_t kSealOverhead = kNonceSize + kAeadTagSize;
+
+/* empty when the system's generator fails, which callers must treat as an
+ * error rather than use what they got */
+std::vector<uint8_t> RandomBytes(std::size_t count);
+
+/* stretches a password into a key with PBKDF2-HMAC-SHA256 */
+StegKey DerivePasswordKey(const std::string& password,
+                          const std::vector<uint8_t>& salt);
+
+/* stretches the password and throws the key away, so a check that fails
+ * before a password was ever tried takes as long as a wrong password does */
+void SpendPasswordKey(const std::string& password);
+
+/* AES-256-GCM, the sealed message is nonce || ciphertext || tag, empty when
+ * no nonce could be drawn */
+std::vector<uint8_t> Seal(const StegKey& key,
+                          const std::vector<uint8_t>& plaintext);
+
+/* the same with a given nonce of kNonceSize bytes, which must never be used
+ * twice under one key */
+std::vector<uint8_t> Seal(const StegKey& key,
+                          const std::vector<uint8_t>& plaintext,
+                          const std::vector<uint8_t>& nonce);
+
+bool Open(const StegKey& key, const std::vector<uint8_t>& sealed,
+          std::vector<uint8_t>& plaintext);
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,54 @@
+#ifndef DENIABLE_HPP_
+#define DENIABLE_HPP_
+
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <optional>
+#include <string>
+#include <vector>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* layers go in the planes of the low nibble, the bits a pack changes */
+const std::size_t kMaxLayers = 4;
+
+struct DeniablePayload {
+    std::vector<uint8_t> data;
+    std::string password;
+};
+
+/* The cover's cells are split into two interleaved halves, each holding at
+ * most one password protected payload. A half without a payload is filled
+ * with random bytes so it is indistinguishable from one holding data. The
+ * first payload (the decoy when there are two) goes into the first half.
+ * A seed, 0 as much as any other, draws the salts, nonces, and filler from a
+ * stream keyed by the seed, the payloads, and their passwords instead, so the
+ * same inputs give the same image. kIoError when the system's generator
+ * fails, the image is then partly overwritten and must be thrown away. */
+RetCode EmbedDeniable(boost::gil::rgb8_image_t& image,
+                      const std::vector<DeniablePayload>& payloads,
+                      std::optional<uint64_t> seed = std::nullopt);
+
+RetCode ExtractDeniable(const boost::gil::rgb8_image_t& image,
+                        const std::string& password,
+                        std::vector<uint8_t>& payload);
+
+/* The same in layers: each payload gets a bit plane of every channel to
+ * itself, the first plane 0, the least significant, the next plane 1, and so
+ * on up to plane 3. Planes without a payload are filled with random bytes,
+ * so the image tells neither how many layers it holds nor which one a
+ * password opens. Up to four payloads, each under its own password. */
+RetCode EmbedLayered(boost::gil::rgb8_image_t& image,
+                     const std::vector<DeniablePayload>& payloads,
+                     std::optional<uint64_t> seed = std::nullopt);
+
+RetCode ExtractLayered(const boost::gil::rgb8_image_t& image,
+                       const std::string& password,
+                       std::vector<uint8_t>& payload);
+
+}  // namespace steganography
+
+#endif
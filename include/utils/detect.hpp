@@ -0,0 +1,45 @@
+#ifndef DETECT_HPP_
+#define DETECT_HPP_
+
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <string>
+#include <vector>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* a payload fraction is the fraction of color channels carrying hidden data
+ * in their low bits, 0 for a clean image and 1 for a full one */
+struct DetectorScore {
+    std::string name;
+    double payload_fraction = 0.0;
+    double confidence = 0.0; /* that any data is hidden, 0-1 */
+};
+
+struct DetectionReport {
+    std::vector<DetectorScore> detectors; /* chi-square, rs, then spa */
+    double payload_fraction = 0.0;        /* median of the detectors */
+    double confidence = 0.0;              /* median of the detectors */
+    int bit_depth = 0; /* low bits replaced in each channel, 0 when clean */
+    std::size_t estimated_bytes = 0;
+};
+
+/* assumes the channels are filled in order from the top left as pack does */
+DetectorScore ChiSquareDetector(const boost::gil::rgb8_image_t& image);
+
+DetectorScore RsDetector(const boost::gil::rgb8_image_t& image);
+
+DetectorScore SpaDetector(const boost::gil::rgb8_image_t& image);
+
+DetectionReport Detect(const boost::gil::rgb8_image_t& image);
+
+RetCode Detect(const std::string& image, DetectionReport& report);
+
+/* a single line JSON object with the fields of the report */
+std::string DetectionReportJson(const DetectionReport& report);
+
+}  // namespace steganography
+
+#endif
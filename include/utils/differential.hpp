@@ -0,0 +1,37 @@
+#ifndef DIFFERENTIAL_HPP_
+#define DIFFERENTIAL_HPP_
+
+#include <boost/gil.hpp>
+#include <cstdint>
+#include <optional>
+#include <vector>
+
+#include "utils/key.hpp"
+#include "utils/payload.hpp"
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* payload bytes a cover carries when embedding against it, a bit for each
+ * channel less the header */
+std::size_t DifferentialCapacity(const boost::gil::rgb8_image_t& reference);
+
+/* makes image a copy of the reference cover carrying the payload in which
+ * set bits are channels one step off the reference, so nothing changes by
+ * more than 1. Only the reference tells which channels were changed, the
+ * image alone reads as no payload at all. */
+RetCode EmbedDifferential(const boost::gil::rgb8_image_t& reference,
+                          boost::gil::rgb8_image_t& image,
+                          const std::vector<uint8_t>& payload,
+                          const EmbedConfig& config = {});
+
+/* kInvalidDimensions when the image and the reference differ in size */
+RetCode ExtractDifferential(const boost::gil::rgb8_image_t& image,
+                            const boost::gil::rgb8_image_t& reference,
+                            std::vector<uint8_t>& payload,
+                            PayloadHeader& header, DamageReport& damage,
+                            const std::optional<StegKey>& key = std::nullopt);
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,73 @@
+#ifndef EMBED_METHOD_HPP_
+#define EMBED_METHOD_HPP_
+
+#include <boost/gil.hpp>
+#include <memory>
+#include <string>
+#include <vector>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* the method merge and unmerge use unless told otherwise */
+const char kDefaultMethod[] = "lsb";
+
+/* An algorithm hiding a secret image in a cover image. Merge and Unmerge
+ * read, resize, and write the images, and hand the pixels in between to the
+ * method their config names. */
+class EmbedMethod {
+   public:
+    virtual ~EmbedMethod() = default;
+
+    /* Hides the secret in merged, which starts out as a copy of the cover
+     * and is the same size. The secret is no larger than the cover. A
+     * method may append its own phases to report.phases, otherwise its
+     * whole run is recorded as the embed phase. */
+    virtual RetCode Embed(const boost::gil::rgb8c_view_t& cover,
+                          const boost::gil::rgb8c_view_t& secret,
+                          const boost::gil::rgb8_view_t& merged,
+                          const MergeConfig& config,
+                          MergeReport& report) const = 0;
+
+    /* recovers the secret hidden in merged, as large as Embed was given it
+     * when it can tell and as large as merged otherwise */
+    virtual RetCode Extract(const boost::gil::rgb8c_view_t& merged,
+                            const UnmergeConfig& config,
+                            boost::gil::rgb8_image_t& secret) const = 0;
+
+    /* the bits of each secret pixel a cover pixel holds */
+    virtual int Capacity(const MergeConfig& config) const = 0;
+};
+
+/* least significant bit replacement, the built-in default */
+class LsbMethod : public EmbedMethod {
+   public:
+    RetCode Embed(const boost::gil::rgb8c_view_t& cover,
+                  const boost::gil::rgb8c_view_t& secret,
+                  const boost::gil::rgb8_view_t& merged,
+                  const MergeConfig& config,
+                  MergeReport& report) const override;
+
+    RetCode Extract(const boost::gil::rgb8c_view_t& merged,
+                    const UnmergeConfig& config,
+                    boost::gil::rgb8_image_t& secret) const override;
+
+    int Capacity(const MergeConfig& config) const override;
+};
+
+/* Makes a method available under name to MergeConfig::method,
+ * UnmergeConfig::method, and --method. Returns false when the name is
+ * taken. Register methods before merging or unmerging on other threads. */
+bool RegisterEmbedMethod(const std::string& name,
+                         std::shared_ptr<const EmbedMethod> method);
+
+/* the method registered under name, null when there is none */
+std::shared_ptr<const EmbedMethod> FindEmbedMethod(const std::string& name);
+
+/* every registered name in alphabetical order */
+std::vector<std::string> EmbedMethodNames();
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,35 @@
+#ifndef EXR_HPP_
+#define EXR_HPP_
+
+#include <boost/gil.hpp>
+#include <string>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* whether the file starts with the OpenEXR magic number */
+bool IsExr(const std::string& filename);
+
+/* Hides the secret in the low mantissa bits of an OpenEXR cover's red,
+ * green, and blue channels and writes an OpenEXR file with every other
+ * channel and attribute unchanged. 32 bit float channels take all 8 bits of
+ * each secret channel, 16 bit half channels the top config.bits. Key files,
+ * color spaces, dithering, resizing the secret, and embed methods other than
+ * the default are not supported. */
+RetCode MergeExr(const std::string& cover, const std::string& secret,
+                 const std::string& outfile, const MergeConfig& config,
+                 MergeReport& report);
+
+/* the same for a secret already in memory */
+RetCode MergeExr(const std::string& cover,
+                 const boost::gil::rgb8_image_t& secret_img,
+                 const std::string& outfile, const MergeConfig& config,
+                 MergeReport& report);
+
+RetCode UnmergeExr(const std::string& secret, const std::string& outfile,
+                   const UnmergeConfig& config);
+
+}  // namespace steganography
+
+#endif
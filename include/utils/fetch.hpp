@@ -0,0 +1,30 @@
+#ifndef FETCH_HPP_
+#define FETCH_HPP_
+
+#include <cstddef>
+#include <string>
+#include <vector>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+struct FetchConfig {
+    std::size_t max_bytes = 64 * 1024 * 1024; /* larger downloads fail */
+    long timeout_seconds = 30;                 /* for the whole transfer */
+    std::vector<std::string> headers;          /* extra 'Name: value' lines */
+};
+
+/* true for http:// and https:// URLs */
+bool IsUrl(const std::string& path);
+
+RetCode FetchUrl(const std::string& url, const std::string& outfile,
+                 const FetchConfig& config);
+
+/* uploads body to url with an HTTP PUT */
+RetCode PutUrl(const std::string& url, const std::string& body,
+               const FetchConfig& config);
+
+}  // namespace steganography
+
+#endif
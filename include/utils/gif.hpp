@@ -0,0 +1,39 @@
+#ifndef GIF_HPP_
+#define GIF_HPP_
+
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <string>
+#include <vector>
+
+namespace steganography {
+
+/* the first frame of a GIF as entries of its palette, the other frames and
+ * extensions are not kept */
+struct IndexedImage {
+    std::size_t width = 0;
+    std::size_t height = 0;
+    std::vector<uint8_t> palette; /* RGB triples, a power of two of them */
+    std::vector<uint8_t> indices; /* one per pixel, row by row */
+    int transparent = -1;         /* the transparent entry, -1 for none */
+};
+
+/* reads the first frame of a GIF as 8 bit RGB, transparent pixels take the
+ * color of their entry, false when it cannot be decoded */
+bool ReadGif(const std::string& filename, boost::gil::rgb8_image_t& image);
+
+/* the larger of the screen and first frame dimensions and the decoded bytes
+ * per pixel without decoding the image */
+bool ReadGifHeader(const std::string& filename, uint64_t& width,
+                   uint64_t& height, uint64_t& pixel_bytes);
+
+bool ReadIndexedGif(const std::string& filename, IndexedImage& image);
+
+/* writes a single frame GIF with the image's palette as its global color
+ * table, false when encoding fails */
+bool WriteIndexedGif(const IndexedImage& image, const std::string& filename);
+
+}  // namespace steganography
+
+#endif
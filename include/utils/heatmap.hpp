@@ -0,0 +1,45 @@
+#ifndef HEATMAP_HPP_
+#define HEATMAP_HPP_
+
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <string>
+#include <vector>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+struct HeatmapConfig {
+    int block = 32;           /* side of the square blocks in pixels */
+    double opacity = 0.6;     /* of the heatmap drawn over the image, 0-1 */
+    bool create_dirs = false; /* create missing parents of the output */
+};
+
+/* entropy of each block's LSBs, 0 when every LSB is predictable from its
+ * neighbor and 1 when they look random as hidden data does */
+struct EntropyMap {
+    std::ptrdiff_t cols = 0;
+    std::ptrdiff_t rows = 0;
+    std::vector<double> entropy; /* row major, cols x rows */
+
+    double At(std::ptrdiff_t col, std::ptrdiff_t row) const {
+        return entropy[static_cast<std::size_t>(row * cols + col)];
+    }
+};
+
+EntropyMap BlockEntropy(const boost::gil::rgb8_image_t& image, int block);
+
+/* the image in grayscale under blocks colored by how their entropy compares
+ * to the rest of the image, green for typical blocks, red for blocks whose
+ * LSBs are more random than usual, and blue for less random ones */
+RetCode RenderHeatmap(const boost::gil::rgb8_image_t& image,
+                      const HeatmapConfig& config,
+                      boost::gil::rgb8_image_t& rendered);
+
+RetCode Heatmap(const std::string& image, const std::string& outfile,
+                const HeatmapConfig& config);
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,23 @@
+#ifndef ICC_HPP_
+#define ICC_HPP_
+
+#include <cstdint>
+#include <string>
+#include <vector>
+
+namespace steganography {
+
+/* the ICC profile embedded in a PNG's iCCP chunk or a JPEG's APP2 segments,
+ * empty when the file has none or it does not describe RGB data. Pixels are
+ * never converted out of the profile's space, so it still describes them
+ * once the secret is embedded. */
+std::vector<uint8_t> ReadIccProfile(const std::string& filename);
+
+/* rewrites a PNG file with an iCCP chunk carrying the profile in place of
+ * any iCCP or sRGB chunk it had, false when the file is not a PNG */
+bool AttachIccProfile(const std::string& filename,
+                      const std::vector<uint8_t>& profile);
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,42 @@
+#ifndef ICO_HPP_
+#define ICO_HPP_
+
+#include <boost/gil.hpp>
+#include <cstdint>
+#include <optional>
+#include <string>
+#include <vector>
+
+#include "utils/key.hpp"
+#include "utils/payload.hpp"
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* reads the largest image of an ICO as 8 bit RGB, alpha is dropped, false
+ * when it cannot be decoded */
+bool ReadIco(const std::string& filename, boost::gil::rgb8_image_t& image);
+
+/* the dimensions and decoded bytes per pixel of the largest image, taken
+ * from each image's own header rather than the icon's directory */
+bool ReadIcoHeader(const std::string& filename, uint64_t& width,
+                   uint64_t& height, uint64_t& pixel_bytes);
+
+/* embeds the payload into every image of the icon on its own, so each size
+ * carries all of it. PNG images are written again as 8 bit RGBA, BMP ones
+ * are changed in place and paletted ones through their palette indices.
+ * kInsufficientCapacity when any image is too small, kUnsupportedFormat for
+ * an image stored in a way that cannot carry data. */
+RetCode EmbedIco(const std::string& cover, const std::string& outfile,
+                 const std::vector<uint8_t>& payload,
+                 const EmbedConfig& config = {});
+
+/* extracts from the largest image, smaller ones are only read when the
+ * larger ones fail or are damaged */
+RetCode ExtractIco(const std::string& image, std::vector<uint8_t>& payload,
+                   PayloadHeader& header, DamageReport& damage,
+                   const std::optional<StegKey>& key = std::nullopt);
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,36 @@
+#ifndef IMAGE_INFO_HPP_
+#define IMAGE_INFO_HPP_
+
+#include <cstddef>
+#include <cstdint>
+#include <string>
+#include <vector>
+
+#include "utils/image_io.hpp"
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* what an image holds when a number of its low bits are given over */
+struct BitsCapacity {
+    int bits = 0;       /* low bits per channel */
+    uint64_t bytes = 0; /* they hold across every pixel and channel */
+    /* of the image once they are overwritten with random data, in dB */
+    double expected_psnr = 0.0;
+};
+
+struct ImageInfo {
+    std::string format; /* as SniffImageFormat names it */
+    ImageLayout layout;
+    /* bytes pack and merge-data fit once their header is taken out */
+    std::size_t payload_capacity = 0;
+    std::vector<BitsCapacity> capacities; /* for 1 to 8 bits */
+};
+
+/* Describes the image as a carrier. The capacities count 8 bit RGB, which
+ * every input is converted to before anything is hidden in it. */
+RetCode GetImageInfo(const std::string& filename, ImageInfo& info);
+
+}  // namespace steganography
+
+#endif
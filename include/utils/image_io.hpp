@@ -0,0 +1,114 @@
+#ifndef IMAGE_IO_HPP_
+#define IMAGE_IO_HPP_
+
+#include <boost/gil.hpp>
+#include <cstdint>
+#include <string>
+#include <vector>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+enum class ImageType {
+    kJpeg,
+    kPng,
+    kBmp,  /* lossless output too, written as 24 bit */
+    kPnm,  /* PBM, PGM, and PPM, lossless output too, as the name asks */
+    kIco,  /* input, and output of pack, which embeds in every image */
+    kTiff, /* input only, recognized when built with STEG_WITH_TIFF */
+    kAvif, /* input only, recognized when built with STEG_WITH_AVIF */
+    kHeif, /* input only, recognized when built with STEG_WITH_HEIF */
+    kRaw,  /* input only, recognized when built with STEG_WITH_RAW */
+    kJxl,  /* lossless output too, recognized when built with STEG_WITH_JXL */
+    kGif,  /* input, and output of pack, recognized when built with
+            * STEG_WITH_GIF */
+    kUnknown,
+};
+
+ImageType GetImageType(const std::string& filename);
+
+/* the input formats this build decodes, as a list for a sentence */
+std::string AcceptedFormats();
+
+/* what a file's leading bytes say it is, whether or not this build reads it */
+struct SniffedFormat {
+    std::string name;    /* such as "WebP", empty when not recognized */
+    std::string feature; /* the build option that reads it, empty for none */
+};
+
+/* names the format of any file by its content, never its extension, where
+ * GetImageType only knows the formats this build decodes */
+SniffedFormat SniffImageFormat(const std::string& filename);
+
+boost::gil::rgb8_image_t ReadImage(const std::string& filename,
+                                   ImageType type);
+
+/* a non empty ICC profile is attached to PNG and JPEG XL outputs, JPEG,
+ * BMP, and PNM outputs are written without one */
+void WriteImage(const boost::gil::rgb8_image_t& image,
+                const std::string& filename, ImageType type,
+                const std::vector<uint8_t>& icc_profile = {});
+
+bool HasJpegExtension(const std::string& filename);
+
+/* the lossless format to write outputs carrying hidden data in, BMP for a
+ * .bmp file, PNM for a .pbm, .pgm, .ppm, or .pnm file, JPEG XL for a .jxl
+ * file when built with STEG_WITH_JXL, and PNG otherwise */
+ImageType LosslessImageType(const std::string& filename);
+
+/* the type to write outfile as, given the type of the image it was made
+ * from. Outputs carrying hidden data refuse JPEG, and for them the same as
+ * cover format falls back to PNG when the cover was lossy or cannot be
+ * written. kInvalidArgument for a format this build cannot write, and for
+ * hidden data written to a .pbm or .pgm, which cannot keep RGB low bits. */
+RetCode ResolveOutputType(OutputFormat format, const std::string& outfile,
+                          ImageType cover_type, bool carries_data,
+                          ImageType& type);
+
+/* caps on the images decoded from files, checked against the dimensions in
+ * the file's header before any decoding so a small file claiming huge
+ * dimensions is turned away, 0 disables a limit */
+struct ImageLimits {
+    uint64_t max_pixels = 100000000;
+    /* the larger of the image at its own channel count and bit depth and
+     * the image converted to 8 bit RGB */
+    uint64_t max_decoded_bytes = 1024ULL * 1024 * 1024;
+};
+
+/* the limits apply process wide, set them before reading any images */
+void SetImageLimits(const ImageLimits& limits);
+
+const ImageLimits& GetImageLimits();
+
+/* the larger of the bytes decoding the image takes and its size as 8 bit
+ * RGB, read from the header, kInvalidFileFormat when it cannot be read */
+RetCode DecodedImageBytes(const std::string& filename, ImageType type,
+                          uint64_t& bytes);
+
+/* what a file's header says about the samples of the image it holds */
+struct ImageLayout {
+    uint64_t width = 0;
+    uint64_t height = 0;
+    /* "gray", "gray+alpha", "RGB", "RGBA", "palette", or "CMYK" */
+    std::string color_type;
+    int bit_depth = 8;       /* per sample */
+    bool interlaced = false; /* PNGs only */
+};
+
+/* kInvalidFileFormat when the header cannot be read */
+RetCode ReadImageLayout(const std::string& filename, ImageType type,
+                        ImageLayout& layout);
+
+/* kImageTooLarge when the header declares an image over the limits and
+ * kInvalidFileFormat when it cannot be read */
+RetCode CheckImageLimits(const std::string& filename, ImageType type);
+
+/* checks the file exists, is a supported format, and is within the limits
+ * before reading it */
+RetCode LoadImage(const std::string& filename,
+                  boost::gil::rgb8_image_t& image);
+
+}  // namespace steganography
+
+#endif
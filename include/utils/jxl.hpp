@@ -0,0 +1,27 @@
+#ifndef JXL_HPP_
+#define JXL_HPP_
+
+#include <boost/gil.hpp>
+#include <cstdint>
+#include <string>
+#include <vector>
+
+namespace steganography {
+
+/* reads the first frame of a JPEG XL as 8 bit RGB, alpha is dropped, false
+ * when it cannot be decoded */
+bool ReadJxl(const std::string& filename, boost::gil::rgb8_image_t& image);
+
+/* the dimensions and decoded bytes per pixel without decoding the image */
+bool ReadJxlHeader(const std::string& filename, uint64_t& width,
+                   uint64_t& height, uint64_t& pixel_bytes);
+
+/* writes the image as a lossless JPEG XL tagged with the ICC profile, or as
+ * sRGB when it is empty, false when encoding fails */
+bool WriteJxl(const boost::gil::rgb8_image_t& image,
+              const std::string& filename,
+              const std::vector<uint8_t>& icc_profile = {});
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,88 @@
+#ifndef KEY_HPP_
+#define KEY_HPP_
+
+#include <array>
+#include <cstddef>
+#include <cstdint>
+#include <string>
+#include <vector>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+const std::size_t kKeySize = 32;
+const std::size_t kKeyTagSize = 8;
+
+/* the material is wiped when the key goes out of scope */
+struct StegKey {
+    std::array<uint8_t, kKeySize> material{};
+
+    ~StegKey();
+};
+
+/* overwrites memory that held key material or decrypted data in a way the
+ * compiler cannot optimize out */
+void WipeBytes(uint8_t* bytes, std::size_t size);
+
+/* the same for a buffer, spare capacity included, which is then emptied */
+void Wipe(std::vector<uint8_t>& bytes);
+
+template <std::size_t N>
+void Wipe(std::array<uint8_t, N>& bytes) {
+    WipeBytes(bytes.data(), bytes.size());
+}
+
+RetCode GenerateKeyFile(const std::string& path);
+
+RetCode GenerateKeyFile(const std::string& path, bool create_dirs);
+
+RetCode LoadKeyFile(const std::string& path, StegKey& key);
+
+/* derives an independent subkey for each purpose the key is used for */
+std::array<uint8_t, kKeySize> DeriveSubkey(const StegKey& key,
+                                           const std::string& label);
+
+/* second factor: key = HKDF(key file material || password) */
+StegKey CombineKey(const StegKey& key, const std::string& password);
+
+/* stands in for randomness that has to be reproducible: key = HKDF(seed ||
+ * context), the context being the data it is used on so that one seed never
+ * gives the same bytes, such as nonces, for different data */
+StegKey SeededKey(uint64_t seed, const std::vector<uint8_t>& context);
+
+/* short authenticator proving possession of the key */
+std::array<uint8_t, kKeyTagSize> KeyTag(const StegKey& key,
+                                        const std::string& label);
+
+bool TagsEqual(const std::array<uint8_t, kKeyTagSize>& lhs,
+               const std::array<uint8_t, kKeyTagSize>& rhs);
+
+/* deterministic AES-256-CTR byte stream seeded from a subkey */
+class KeyStream {
+   public:
+    KeyStream(const StegKey& key, const std::string& label);
+    ~KeyStream();
+
+    uint8_t NextByte();
+    uint64_t NextWord();
+
+    /* uniformly distributed value in [0, bound) */
+    uint64_t NextBelow(uint64_t bound);
+
+   private:
+    void Refill();
+
+    std::array<uint8_t, kKeySize> subkey_;
+    uint64_t counter_ = 0;
+    std::vector<uint8_t> buffer_;
+    std::size_t pos_ = 0;
+};
+
+/* keyed Fisher-Yates shuffle of the indices [0, count) */
+std::vector<std::size_t> KeyedPermutation(std::size_t count,
+                                          KeyStream& stream);
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,38 @@
+#ifndef LSB_HPP_
+#define LSB_HPP_
+
+#include <array>
+#include <boost/gil.hpp>
+#include <cstdint>
+
+#include "core/lsb.hpp"
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* replaces the low bits of each cover channel with the high bits of the
+ * matching secret channel */
+boost::gil::rgb8_pixel_t MergePixels(const boost::gil::rgb8_pixel_t& cover_pix,
+                                     const boost::gil::rgb8_pixel_t& secret_pix,
+                                     int bits);
+
+/* moves the low bits of each channel back up to the high bits */
+boost::gil::rgb8_pixel_t UnmergePixels(const boost::gil::rgb8_pixel_t& pixel,
+                                       int bits);
+
+/* the high bits of each secret channel kept in the given color space of the
+ * cover, in YCbCr the chroma channels hold bits each so the secret keeps
+ * 2 * bits per pixel and in HSV only the value channel does */
+std::array<int, 3> SecretBits(int bits, ColorSpace space);
+
+/* the same within the given color space */
+boost::gil::rgb8_pixel_t MergePixels(const boost::gil::rgb8_pixel_t& cover_pix,
+                                     const boost::gil::rgb8_pixel_t& secret_pix,
+                                     int bits, ColorSpace space);
+
+boost::gil::rgb8_pixel_t UnmergePixels(const boost::gil::rgb8_pixel_t& pixel,
+                                       int bits, ColorSpace space);
+
+}  // namespace steganography
+
+#endif
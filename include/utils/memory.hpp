@@ -0,0 +1,19 @@
+#ifndef MEMORY_HPP_
+#define MEMORY_HPP_
+
+#include <cstdint>
+
+namespace steganography {
+
+/* the most memory the process has had resident, in bytes, 0 where the
+ * platform does not tell */
+uint64_t PeakRss();
+
+/* starts the peak over from what is resident now, so it covers only what
+ * follows, false where the kernel does not allow it. The peak is the whole
+ * process's, work on other threads counts towards it too. */
+bool ResetPeakRss();
+
+}  // namespace steganography
+
+#endif
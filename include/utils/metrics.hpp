@@ -0,0 +1,14 @@
+#ifndef METRICS_HPP_
+#define METRICS_HPP_
+
+#include <boost/gil.hpp>
+
+namespace steganography {
+
+/* peak signal to noise ratio in dB, infinite for identical images */
+double Psnr(const boost::gil::rgb8c_view_t& lhs,
+            const boost::gil::rgb8c_view_t& rhs);
+
+}  // namespace steganography
+
+#endif
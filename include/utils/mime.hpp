@@ -0,0 +1,24 @@
+#ifndef MIME_HPP_
+#define MIME_HPP_
+
+#include <cstdint>
+#include <string>
+#include <vector>
+
+namespace steganography {
+
+/* the type of data nothing more is known about */
+const char* const kDefaultContentType = "application/octet-stream";
+
+/* the MIME type of the data going by its leading bytes, then by the file
+ * name's extension, kDefaultContentType when neither is recognized */
+std::string GuessContentType(const std::vector<uint8_t>& data,
+                             const std::string& filename = "");
+
+/* the usual extension of a MIME type with its dot, ".bin" for types it does
+ * not know */
+std::string ContentTypeExtension(const std::string& content_type);
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,48 @@
+#ifndef NEST_HPP_
+#define NEST_HPP_
+
+#include <cstddef>
+#include <string>
+
+#include "utils/image_io.hpp"
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* the content type recorded for a stego image hidden inside another */
+const char* const kNestedContentType = "image/x-steganography-nested";
+
+struct NestConfig {
+    bool create_dirs = false; /* create missing parents of the output */
+    OutputFormat output_format = OutputFormat::kAuto;
+};
+
+/* Hides the file stego, an image that may hold a payload of its own, byte
+ * for byte inside cover with kNestedContentType recorded in its header, so
+ * nothing hidden in it is lost. Nesting the output again adds a level.
+ * kInvalidFileFormat when stego is not an image this build decodes,
+ * kSameInput when it is the cover. */
+RetCode Nest(const std::string& cover, const std::string& stego,
+             const std::string& outfile, const NestConfig& config);
+
+struct UnnestConfig {
+    /* levels to unwrap, 0 unwraps until an image holds no nested one */
+    std::size_t levels = 0;
+    bool create_dirs = false; /* create missing parents of the output */
+};
+
+struct UnnestReport {
+    std::size_t levels = 0; /* levels unwrapped */
+    std::string filename;   /* the innermost image's name when it was nested */
+};
+
+/* Unwraps the images Nest hid in image, one level after another, and writes
+ * the innermost one reached to outfile as it was nested. Unwrapping stops
+ * early, and still succeeds, when an image holds no nested one before
+ * config.levels are unwrapped. kPayloadNotFound when image holds none. */
+RetCode Unnest(const std::string& image, const std::string& outfile,
+               const UnnestConfig& config, UnnestReport& report);
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,42 @@
+#ifndef NORMALIZE_HPP_
+#define NORMALIZE_HPP_
+
+#include <boost/gil.hpp>
+#include <string>
+#include <vector>
+
+#include "utils/image_io.hpp"
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* the conversions an input can need on its way to 8 bit RGB */
+enum class InputConversion {
+    kCmyk,      /* CMYK or YCCK samples */
+    kDepth,     /* samples deeper than 8 bits */
+    kInterlace, /* interlaced rows */
+};
+
+struct InputWarning {
+    InputConversion conversion;
+    std::string message;
+};
+
+/* the stable code of a conversion, "cmyk-input", "deep-input", or
+ * "interlaced-input", as MergeWarning codes go */
+const char* InputConversionCode(InputConversion conversion);
+
+/* Decodes a PNG, or a JPEG when built with STEG_WITH_JPEG, to 8 bit RGB
+ * under policy, with a warning for each conversion made or refused. Palette,
+ * gray, and alpha PNGs are converted as they always were, alpha multiplying
+ * the color. Other types are read by ReadImage. kConversionRefused when
+ * policy rejects a conversion the input needs or, for a 12 bit JPEG, when
+ * libjpeg cannot decode it, and kInvalidFileFormat for a damaged file. */
+RetCode ReadNormalizedImage(const std::string& filename, ImageType type,
+                            const InputPolicy& policy,
+                            boost::gil::rgb8_image_t& image,
+                            std::vector<InputWarning>& warnings);
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,36 @@
+#ifndef ORIENTATION_HPP_
+#define ORIENTATION_HPP_
+
+#include <boost/gil.hpp>
+
+namespace steganography {
+
+/* the ways an image can be turned without losing pixels, a mirror flips left
+ * to right and is applied before the clockwise rotation */
+enum class Orientation {
+    kNormal,
+    kRotated90,
+    kRotated180,
+    kRotated270,
+    kMirrored,
+    kMirroredRotated90,
+    kMirroredRotated180, /* flipped upside down */
+    kMirroredRotated270,
+};
+
+const int kOrientationCount = 8;
+
+/* describes what was done to the image, e.g. "rotated 90 degrees clockwise" */
+const char* OrientationName(Orientation orientation);
+
+/* returns image turned by orientation */
+boost::gil::rgb8_image_t Reorient(const boost::gil::rgb8_image_t& image,
+                                  Orientation orientation);
+
+/* undoes Reorient, i.e. returns the image as it was before being turned */
+boost::gil::rgb8_image_t Normalize(const boost::gil::rgb8_image_t& image,
+                                   Orientation orientation);
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,32 @@
+#ifndef PAIR_SWAP_HPP_
+#define PAIR_SWAP_HPP_
+
+#include <boost/gil.hpp>
+#include <cstdint>
+#include <optional>
+#include <vector>
+
+#include "utils/key.hpp"
+#include "utils/payload.hpp"
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* payload bytes the image holds when only swapping pixel pairs, fewer the
+ * more flat areas it has */
+std::size_t PairSwapCapacity(const boost::gil::rgb8_image_t& image);
+
+/* embeds the payload by swapping the values of side by side pixels instead
+ * of overwriting bits, so the image keeps its exact histogram */
+RetCode EmbedPairSwap(boost::gil::rgb8_image_t& image,
+                      const std::vector<uint8_t>& payload,
+                      const EmbedConfig& config = {});
+
+RetCode ExtractPairSwap(const boost::gil::rgb8_image_t& image,
+                        std::vector<uint8_t>& payload, PayloadHeader& header,
+                        DamageReport& damage,
+                        const std::optional<StegKey>& key = std::nullopt);
+
+}  // namespace steganography
+
+#endif
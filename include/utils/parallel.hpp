@@ -0,0 +1,26 @@
+#ifndef PARALLEL_HPP_
+#define PARALLEL_HPP_
+
+#include <cstddef>
+#include <functional>
+
+namespace steganography {
+
+/* the workers to use for a request of threads, 0 means one per core */
+unsigned WorkerCount(unsigned threads);
+
+/* splits rows into contiguous bands and calls body(first, last) for each on
+ * its own thread, the bands must not write to the same pixels. Runs on the
+ * calling thread when only one worker is wanted. */
+void ParallelRows(std::ptrdiff_t rows, unsigned threads,
+                  const std::function<void(std::ptrdiff_t, std::ptrdiff_t)>&
+                      body);
+
+/* drops the calling thread, and the threads it starts from then on, to the
+ * lowest CPU priority and the idle I/O class, so long runs give way to other
+ * work. false when the platform refuses either. */
+bool LowerPriority();
+
+}  // namespace steganography
+
+#endif
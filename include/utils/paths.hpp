@@ -0,0 +1,50 @@
+#ifndef PATHS_HPP_
+#define PATHS_HPP_
+
+#include <cstdint>
+#include <filesystem>
+#include <string>
+#include <vector>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* creates any missing directories leading up to the file at path */
+RetCode CreateParentDirectories(const std::string& path);
+
+/* whether both paths name the same file, following links for files that
+ * exist and comparing the normalized paths for those that don't */
+bool SamePath(const std::string& lhs, const std::string& rhs);
+
+/* whether both files exist and hold the same bytes */
+bool SameContents(const std::string& lhs, const std::string& rhs);
+
+/* creates path readable and writable by its owner alone and writes contents,
+ * the file never exists with a wider mode. kFileExists when path is already
+ * there, kIoError when it cannot be written or its mode set, in which case
+ * nothing is left behind. */
+RetCode WritePrivateFile(const std::string& path,
+                         const std::vector<uint8_t>& contents);
+
+/* a private temporary directory removed along with everything in it when
+ * the object goes out of scope */
+class ScratchDir {
+   public:
+    explicit ScratchDir(const std::string& prefix);
+    ~ScratchDir();
+    ScratchDir(const ScratchDir&) = delete;
+    ScratchDir& operator=(const ScratchDir&) = delete;
+
+    bool Valid() const { return !path_.empty(); }
+    std::string File(const std::string& name) const {
+        return (path_ / name).string();
+    }
+
+   private:
+    std::filesystem::path path_;
+};
+
+}  // namespace steganography
+
+#endif
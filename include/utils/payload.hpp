@@ -0,0 +1,169 @@
+#ifndef PAYLOAD_HPP_
+#define PAYLOAD_HPP_
+
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <memory>
+#include <optional>
+#include <streambuf>
+#include <string>
+#include <vector>
+
+#include "core/frame.hpp"
+#include "utils/carrier.hpp"
+#include "utils/key.hpp"
+#include "utils/orientation.hpp"
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+struct EmbedConfig {
+    uint8_t flags = 0;
+    int copies = 1; /* embedded copies voted on bit by bit when extracting */
+    std::optional<StegKey> key; /* scatters the copies, else evenly spaced */
+    std::vector<uint8_t> stages; /* recorded with kFlagPipeline when given */
+    /* recorded with kFlagContentType when either is given */
+    std::string content_type;
+    std::string filename;
+};
+
+struct DamageReport : FrameDamage {
+    /* filled when unpacking salvages what it can from a damaged archive */
+    std::vector<std::string> partial_files; /* written with a .partial suffix */
+    std::vector<std::string> lost_files;    /* damaged before their first byte */
+    bool truncated = false; /* entries past the damage could not be listed */
+    /* how the image was turned after packing, rows above are counted in the
+     * image once the turn is undone */
+    Orientation orientation = Orientation::kNormal;
+};
+
+/* a cell is one byte of storage spread over consecutive channel nibbles */
+std::size_t CellCount(const boost::gil::rgb8_image_t& image);
+
+uint8_t ReadCell(const boost::gil::rgb8_image_t::const_view_t& view,
+                 std::size_t cell);
+
+void WriteCell(const boost::gil::rgb8_image_t::view_t& view, std::size_t cell,
+               uint8_t byte);
+
+std::size_t PayloadCapacity(const boost::gil::rgb8_image_t& image);
+
+RetCode EmbedPayload(boost::gil::rgb8_image_t& image,
+                     const std::vector<uint8_t>& payload, uint8_t flags = 0);
+
+RetCode EmbedPayload(boost::gil::rgb8_image_t& image,
+                     const std::vector<uint8_t>& payload,
+                     const EmbedConfig& config);
+
+/* the same over any carrier, the image overloads embed in an ImageCarrier */
+RetCode EmbedPayload(Carrier& carrier, const std::vector<uint8_t>& payload,
+                     const EmbedConfig& config = {});
+
+/* embeds a payload fed to it a piece at a time, so one streamed from a file
+ * or socket never has to be held in memory whole. The image ends up as
+ * EmbedPayload would leave it with the same flags. Only kFlagCompressed and
+ * kFlagChecksums can be streamed, the other flags encode the payload as a
+ * whole or need its length up front and give kInvalidArgument. */
+class Embedder {
+   public:
+    Embedder(boost::gil::rgb8_image_t& image, uint8_t flags = 0);
+
+    /* the carrier must outlive the embedder */
+    Embedder(Carrier& carrier, uint8_t flags = 0);
+
+    /* kInsufficientCapacity once the payload outgrows the image, the image
+     * then holds a partial payload without a header */
+    RetCode Write(const uint8_t* data, std::size_t size);
+    RetCode Write(const std::vector<uint8_t>& data);
+
+    /* writes any partial checksum block and then the header, after which
+     * the embedder accepts no more data */
+    RetCode Finish();
+
+   private:
+    Embedder(std::unique_ptr<Carrier> carrier, uint8_t flags);
+
+    RetCode WriteBody(const uint8_t* data, std::size_t size);
+    RetCode FlushBlock();
+
+    std::unique_ptr<Carrier> owned_; /* the image's carrier */
+    Carrier* carrier_;
+    uint8_t flags_;
+    std::size_t cells_;
+    std::size_t next_cell_;
+    uint64_t length_ = 0;
+    std::vector<uint8_t> block_; /* payload bytes awaiting their checksum */
+    RetCode status_ = RetCode::kSuccess;
+    bool finished_ = false;
+};
+
+/* reads the payload in an image a piece at a time, to pipe it straight into
+ * a decompressor or parser. Payloads an Embedder could have written are read
+ * out of the image as they are consumed, any other is extracted whole up
+ * front. As a std::streambuf it also backs a std::istream. The image must
+ * outlive the extractor. */
+class Extractor : public std::streambuf {
+   public:
+    explicit Extractor(const boost::gil::rgb8_image_t& image,
+                       const std::optional<StegKey>& key = std::nullopt);
+
+    /* the carrier must outlive the extractor too, and unlike an image its
+     * payload is not looked for turned */
+    explicit Extractor(const Carrier& carrier,
+                       const std::optional<StegKey>& key = std::nullopt);
+
+    /* kSuccess or kPayloadDamaged while the payload can be read, a damaged
+     * block's bytes are still returned as they were read, and why nothing
+     * can be read otherwise */
+    RetCode Status() const { return status_; }
+    const PayloadHeader& Header() const { return header_; }
+
+    /* copies up to size bytes of the payload, fewer only at its end or when
+     * it cannot be read */
+    std::size_t Read(uint8_t* data, std::size_t size);
+
+   protected:
+    int_type underflow() override;
+
+   private:
+    void Start(const std::optional<StegKey>& key);
+    bool Fill();
+
+    std::unique_ptr<const Carrier> owned_; /* the image's carrier */
+    const Carrier* carrier_;
+    const boost::gil::rgb8_image_t* image_ = nullptr;
+    PayloadHeader header_;
+    RetCode status_ = RetCode::kSuccess;
+    std::size_t next_cell_ = 0;
+    std::size_t remaining_ = 0; /* payload bytes still in the image */
+    std::vector<uint8_t> buffer_;
+};
+
+RetCode ExtractPayload(const boost::gil::rgb8_image_t& image,
+                       std::vector<uint8_t>& payload);
+
+RetCode ExtractPayload(const boost::gil::rgb8_image_t& image,
+                       std::vector<uint8_t>& payload, PayloadHeader& header);
+
+/* returns kPayloadDamaged when checksummed blocks fail verification, the
+ * payload still holds every block as read so intact ones can be used, and
+ * kInvalidArgument when the copies were placed with a key but none is given,
+ * payloads with kFlagSync are found even after rows were cropped or added
+ * and rotated or mirrored images are turned back before extracting */
+RetCode ExtractPayload(const boost::gil::rgb8_image_t& image,
+                       std::vector<uint8_t>& payload, PayloadHeader& header,
+                       DamageReport& damage,
+                       const std::optional<StegKey>& key = std::nullopt);
+
+/* the same over any carrier, only images are turned and damage rows are
+ * those the carrier reports */
+RetCode ExtractPayload(const Carrier& carrier, std::vector<uint8_t>& payload);
+
+RetCode ExtractPayload(const Carrier& carrier, std::vector<uint8_t>& payload,
+                       PayloadHeader& header, DamageReport& damage,
+                       const std::optional<StegKey>& key = std::nullopt);
+
+}  // namespace steganography
+
+#endif
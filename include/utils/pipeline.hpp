@@ -0,0 +1,99 @@
+#ifndef PIPELINE_HPP_
+#define PIPELINE_HPP_
+
+#include <boost/gil.hpp>
+#include <cstdint>
+#include <optional>
+#include <string>
+#include <vector>
+
+#include "utils/carrier.hpp"
+#include "core/ecc.hpp"
+#include "utils/key.hpp"
+#include "utils/payload.hpp"
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* the ids recorded in the header, see docs/FORMAT.md */
+enum class PipelineStage : uint8_t {
+    kCompress = 1,   /* gzip */
+    kEncrypt = 2,    /* AES-256-GCM under a key stretched from a password */
+    kHamming = 3,    /* Hamming(7,4), see EccScheme::kHamming */
+    kInterleave = 4, /* bit interleaving kInterleaveDepth bits deep */
+};
+
+/* Transforms a payload goes through before it is embedded, in the order they
+ * are added, for example Pipeline().Compress().Encrypt(pw).Ecc(). The
+ * stages are recorded alongside the payload, so ExtractPipeline undoes them
+ * in reverse without being told what they were. Unlike kFlagHamming and
+ * kFlagInterleaved these transform the payload alone, so any stage can come
+ * before or after any other and they combine with the EmbedConfig flags. */
+class Pipeline {
+   public:
+    Pipeline& Compress();
+
+    /* each encrypt stage draws its own salt, a pipeline holds one password
+     * so adding another replaces it for every stage */
+    Pipeline& Encrypt(const std::string& password);
+
+    /* EccScheme::kNone adds no stage */
+    Pipeline& Ecc(EccScheme scheme = EccScheme::kHamming);
+
+    Pipeline& Interleave();
+
+    const std::vector<PipelineStage>& Stages() const { return stages_; }
+
+    /* runs data through every stage, kInvalidArgument for more than
+     * kMaxPipelineStages of them and kIoError when an encrypt stage cannot
+     * draw its salt or nonce */
+    RetCode Apply(const std::vector<uint8_t>& data,
+                  std::vector<uint8_t>& out) const;
+
+    /* applies the stages and embeds the result with their record, the
+     * config's own stages are replaced */
+    RetCode Embed(Carrier& carrier, const std::vector<uint8_t>& payload,
+                  const EmbedConfig& config = {}) const;
+
+    RetCode Embed(boost::gil::rgb8_image_t& image,
+                  const std::vector<uint8_t>& payload,
+                  const EmbedConfig& config = {}) const;
+
+   private:
+    std::vector<PipelineStage> stages_;
+    std::string password_;
+};
+
+/* undoes stages in reverse, kUnsupportedFormat for a stage this release
+ * does not know, kAuthenticationFailed when an encrypt stage does not open
+ * with the password, and kInvalidPayload when a stage cannot be undone */
+RetCode UndoPipeline(const std::vector<uint8_t>& stages,
+                     const std::vector<uint8_t>& data,
+                     std::vector<uint8_t>& out,
+                     const std::string& password = "");
+
+/* extracts a payload and undoes the stages recorded with it, payloads
+ * embedded without a pipeline come back as ExtractPayload returns them. A
+ * damaged payload is still undone as far as it can be and kPayloadDamaged
+ * returned, unless a stage fails on the damage. */
+RetCode ExtractPipeline(const Carrier& carrier, std::vector<uint8_t>& payload,
+                        const std::string& password = "");
+
+RetCode ExtractPipeline(const Carrier& carrier, std::vector<uint8_t>& payload,
+                        PayloadHeader& header, DamageReport& damage,
+                        const std::string& password = "",
+                        const std::optional<StegKey>& key = std::nullopt);
+
+RetCode ExtractPipeline(const boost::gil::rgb8_image_t& image,
+                        std::vector<uint8_t>& payload,
+                        const std::string& password = "");
+
+RetCode ExtractPipeline(const boost::gil::rgb8_image_t& image,
+                        std::vector<uint8_t>& payload, PayloadHeader& header,
+                        DamageReport& damage,
+                        const std::string& password = "",
+                        const std::optional<StegKey>& key = std::nullopt);
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,28 @@
+#ifndef PNG_CHUNK_HPP_
+#define PNG_CHUNK_HPP_
+
+#include <cstdint>
+#include <string>
+#include <vector>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* a private ancillary chunk, which decoders skip without complaint. It is
+ * marked safe to copy so editors that know nothing of it may keep it. */
+const char kCarrierChunkType[] = "stEg";
+
+/* the data of a PNG file's carrier chunk, for a ByteCarrier to read the
+ * payload from, kPayloadNotFound when it has none */
+RetCode ReadCarrierChunk(const std::string& filename,
+                         std::vector<uint8_t>& data);
+
+/* rewrites a PNG file with a carrier chunk holding data in place of any it
+ * had, straight before the end of the image */
+RetCode WriteCarrierChunk(const std::string& filename,
+                          const std::vector<uint8_t>& data);
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,31 @@
+#ifndef PNM_HPP_
+#define PNM_HPP_
+
+#include <boost/gil.hpp>
+#include <cstdint>
+#include <string>
+
+namespace steganography {
+
+/* reads a PBM, PGM, or PPM in either its plain or raw form as 8 bit RGB,
+ * samples above 8 bits are scaled down and gray spread over all three
+ * channels, false when it cannot be decoded */
+bool ReadPnm(const std::string& filename, boost::gil::rgb8_image_t& image);
+
+/* the dimensions and decoded bytes per pixel without decoding the image */
+bool ReadPnmHeader(const std::string& filename, uint64_t& width,
+                   uint64_t& height, uint64_t& pixel_bytes);
+
+/* the raw kind a file of this name is written as, '4' (PBM) for a .pbm,
+ * '5' (PGM) for a .pgm, and '6' (PPM) for anything else */
+char PnmKind(const std::string& filename);
+
+/* writes the raw kind PnmKind picks from the name, a PGM keeps each pixel's
+ * luma and a PBM whether it is darker than mid gray, so neither keeps the
+ * low bits of the channels. False when the file cannot be written. */
+bool WritePnm(const boost::gil::rgb8_image_t& image,
+              const std::string& filename);
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,39 @@
+#ifndef PREVIEW_HPP_
+#define PREVIEW_HPP_
+
+#include <boost/gil.hpp>
+#include <ostream>
+#include <string>
+#include <vector>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+enum class PreviewProtocol {
+    kAuto, /* picked from TERM, TERM_PROGRAM, and KITTY_WINDOW_ID */
+    kSixel,
+    kKitty,
+    kIterm,
+    kHalfblock, /* ANSI truecolor '▀' characters, works almost anywhere */
+};
+
+struct PreviewConfig {
+    int bits = 4;    /* low bits per channel holding the hidden image, 1-8 */
+    int columns = 0; /* preview width in terminal cells, 0 fills the terminal */
+    PreviewProtocol protocol = PreviewProtocol::kAuto;
+};
+
+/* extracts the hidden image in memory and writes a downscaled rendition of
+ * it to out using the terminal's graphics protocol */
+RetCode Preview(const std::string& image, const PreviewConfig& config,
+                std::ostream& out);
+
+/* lines of ANSI truecolor half block characters drawing the image scaled
+ * down to fit within columns x rows terminal cells */
+std::vector<std::string> RenderHalfblocks(const boost::gil::rgb8c_view_t& view,
+                                          int columns, int rows);
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,35 @@
+#ifndef QR_HPP_
+#define QR_HPP_
+
+#include <string>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* error correction levels, each recovers about 7%, 15%, 25%, and 30% of
+ * the symbol respectively */
+enum class QrEcc {
+    kLow,
+    kMedium,
+    kQuartile,
+    kHigh,
+};
+
+struct QrMergeConfig {
+    QrEcc ecc = QrEcc::kQuartile;
+    MergeConfig merge; /* key file and password used for the merge */
+};
+
+/* renders text as a QR code filling the cover and merges it, the largest
+ * whole number of pixels per module is used so the code survives blur */
+RetCode MergeQr(const std::string& cover, const std::string& outfile,
+                const std::string& text, const QrMergeConfig& config);
+
+/* unmerges the image and decodes the QR code it holds back to text */
+RetCode UnmergeQr(const std::string& image, std::string& text,
+                  const UnmergeConfig& config);
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,21 @@
+#ifndef RAW_HPP_
+#define RAW_HPP_
+
+#include <boost/gil.hpp>
+#include <cstdint>
+#include <string>
+
+namespace steganography {
+
+/* demosaics a camera raw file to 8 bit sRGB with the camera's white balance
+ * and orientation applied, false when it cannot be decoded */
+bool ReadRaw(const std::string& filename, boost::gil::rgb8_image_t& image);
+
+/* the output dimensions and decoded bytes per pixel without decoding the
+ * sensor data */
+bool ReadRawHeader(const std::string& filename, uint64_t& width,
+                   uint64_t& height, uint64_t& pixel_bytes);
+
+}  // namespace steganography
+
+#endif
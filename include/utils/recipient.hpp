@@ -0,0 +1,72 @@
+#ifndef RECIPIENT_HPP_
+#define RECIPIENT_HPP_
+
+#include <array>
+#include <cstddef>
+#include <cstdint>
+#include <string>
+#include <vector>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* an X25519 public key payloads can be encrypted to */
+const std::size_t kRecipientSize = 32;
+
+using Recipient = std::array<uint8_t, kRecipientSize>;
+
+/* recipients and passwords, the count is stored in a single byte */
+const std::size_t kMaxRecipients = 255;
+
+/* the private half, kept in an identity file */
+struct Identity {
+    std::array<uint8_t, kRecipientSize> secret{};
+    Recipient recipient{};
+};
+
+/* writes a new identity file readable by its owner alone, never
+ * overwriting one, and sets recipient to its public key. kIoError when the
+ * file cannot be written with that mode. */
+RetCode GenerateIdentityFile(const std::string& path, bool create_dirs,
+                             Recipient& recipient);
+
+RetCode LoadIdentityFile(const std::string& path, Identity& identity);
+
+/* Encrypts to every recipient and password, each of which can decrypt on
+ * its own. A random file key encrypts the plaintext and is itself wrapped
+ * once per recipient or password, in the manner of age. kInvalidArgument
+ * when a recipient is not a usable public key or a password is empty, there
+ * must be 1 to kMaxRecipients of them together, and kIoError when the
+ * system's generator fails. envelope is left empty on failure. */
+RetCode EncryptToRecipients(const std::vector<Recipient>& recipients,
+                            const std::vector<std::string>& passwords,
+                            const std::vector<uint8_t>& plaintext,
+                            std::vector<uint8_t>& envelope);
+
+/* whether the bytes start like EncryptToRecipients output */
+bool IsRecipientEnvelope(const std::vector<uint8_t>& envelope);
+
+/* counts the public keys and passwords an envelope was encrypted to
+ * without decrypting anything, false when it is malformed or newer */
+bool CountRecipients(const std::vector<uint8_t>& envelope,
+                     std::size_t& recipients, std::size_t& passwords);
+
+/* kKeyMismatch when the identity is not among the recipients,
+ * kInvalidPayload when the envelope is malformed or was tampered with and
+ * kUnsupportedFormat when a newer release wrote it */
+RetCode DecryptWithIdentity(const Identity& identity,
+                            const std::vector<uint8_t>& envelope,
+                            std::vector<uint8_t>& plaintext);
+
+/* the same for a password the envelope was encrypted to */
+RetCode DecryptWithPassword(const std::string& password,
+                            const std::vector<uint8_t>& envelope,
+                            std::vector<uint8_t>& plaintext);
+
+std::string RecipientToHex(const Recipient& recipient);
+bool RecipientFromHex(const std::string& hex, Recipient& recipient);
+
+}  // namespace steganography
+
+#endif
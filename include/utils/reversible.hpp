@@ -0,0 +1,38 @@
+#ifndef REVERSIBLE_HPP_
+#define REVERSIBLE_HPP_
+
+#include <boost/gil.hpp>
+#include <cstdint>
+#include <optional>
+#include <vector>
+
+#include "utils/key.hpp"
+#include "utils/payload.hpp"
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* payload bytes the image holds reversibly, roughly the pixels at the most
+ * common value of each channel, 0 when no channel has an unused value */
+std::size_t ReversibleCapacity(const boost::gil::rgb8_image_t& image);
+
+/* embeds the payload by histogram shifting, so RestoreReversibleCover can
+ * later give back the image exactly as it was. kInsufficientCapacity when
+ * every channel uses all 256 values or the payload does not fit. */
+RetCode EmbedReversible(boost::gil::rgb8_image_t& image,
+                        const std::vector<uint8_t>& payload,
+                        const EmbedConfig& config = {});
+
+RetCode ExtractReversible(const boost::gil::rgb8_image_t& image,
+                          std::vector<uint8_t>& payload,
+                          PayloadHeader& header, DamageReport& damage,
+                          const std::optional<StegKey>& key = std::nullopt);
+
+/* the image EmbedReversible was given, bit for bit, kPayloadNotFound when
+ * the image holds no reversibly embedded payload */
+RetCode RestoreReversibleCover(const boost::gil::rgb8_image_t& image,
+                               boost::gil::rgb8_image_t& cover);
+
+}  // namespace steganography
+
+#endif
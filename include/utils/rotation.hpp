@@ -0,0 +1,37 @@
+#ifndef ROTATION_HPP_
+#define ROTATION_HPP_
+
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <optional>
+#include <vector>
+
+#include "utils/key.hpp"
+#include "utils/payload.hpp"
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* payload bytes the image holds when a single channel of each pixel carries
+ * data, a third of the usual capacity less the header */
+std::size_t RotationCapacity(const boost::gil::rgb8_image_t& image);
+
+/* embeds the payload in the low nibble of one channel per pixel, rotating
+ * through red, green, and blue in an order the key draws anew for every run
+ * of three pixels, so each channel takes an even share of the changes and
+ * none of them alone holds the payload. The key is taken from the config
+ * and is required, kInvalidArgument without it. */
+RetCode EmbedRotation(boost::gil::rgb8_image_t& image,
+                      const std::vector<uint8_t>& payload,
+                      const EmbedConfig& config);
+
+/* kInvalidArgument without the key the payload was embedded with */
+RetCode ExtractRotation(const boost::gil::rgb8_image_t& image,
+                        std::vector<uint8_t>& payload, PayloadHeader& header,
+                        DamageReport& damage,
+                        const std::optional<StegKey>& key);
+
+}  // namespace steganography
+
+#endif
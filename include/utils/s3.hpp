@@ -0,0 +1,33 @@
+#ifndef S3_HPP_
+#define S3_HPP_
+
+#include <string>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+struct S3Config {
+    std::string region = "us-east-1";
+    std::string endpoint;   /* e.g. http://localhost:9000, uses path style */
+    std::string access_key; /* requests are sent unsigned when empty */
+    std::string secret_key;
+    std::string session_token; /* only for temporary credentials */
+};
+
+/* reads AWS_ACCESS_KEY_ID, AWS_SECRET_ACCESS_KEY, AWS_SESSION_TOKEN,
+ * AWS_REGION and AWS_ENDPOINT_URL */
+S3Config S3ConfigFromEnvironment();
+
+/* true for s3://bucket/key paths */
+bool IsS3Url(const std::string& path);
+
+RetCode S3Download(const std::string& url, const std::string& outfile,
+                   const S3Config& config);
+
+RetCode S3Upload(const std::string& infile, const std::string& url,
+                 const S3Config& config);
+
+}  // namespace steganography
+
+#endif
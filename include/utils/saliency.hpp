@@ -0,0 +1,21 @@
+#ifndef SALIENCY_HPP_
+#define SALIENCY_HPP_
+
+#include <boost/gil.hpp>
+#include <vector>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* Marks the pixels a viewer looks at closely, faces, lines of text, and what
+ * stands out from the rest of the image, widened by a small margin, true to
+ * avoid in row-major order. Only the high 4 bits of each channel are looked
+ * at, so an image gives the same mask after the usual layout is embedded in
+ * it. kFileNotFound when the face cascade cannot be loaded. */
+RetCode AvoidanceMask(const boost::gil::rgb8_image_t& image,
+                      std::vector<bool>& mask);
+
+}  // namespace steganography
+
+#endif
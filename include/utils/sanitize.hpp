@@ -0,0 +1,46 @@
+#ifndef SANITIZE_HPP_
+#define SANITIZE_HPP_
+
+#include <cstdint>
+#include <optional>
+#include <string>
+#include <vector>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+struct SanitizeConfig {
+    /* mirrors the tree, empty rewrites in place */
+    std::string outdir;
+    bool recursive = false;       /* descend into subdirectories */
+    int bits = 4;                 /* low bits of every channel to scrub */
+    std::optional<uint64_t> seed; /* none picks a random seed */
+};
+
+struct SanitizeResult {
+    std::string path;   /* the image as found */
+    std::string output; /* the sanitized image, empty when it failed */
+    RetCode rc = RetCode::kSuccess;
+    /* what was found and removed */
+    bool had_payload = false;       /* a packed payload in the low bits */
+    bool had_trailer = false;       /* data appended past the image */
+    bool had_carrier_chunk = false; /* a PNG chunk carrying a payload */
+};
+
+/* Decodes the image and writes back only its pixels, with their low bits
+ * scrubbed, so metadata, ICC profiles, trailers, and ancillary chunks are
+ * all left behind. JPEG, BMP, PNM, and JPEG XL images keep their type, the
+ * others become PNGs with outfile's extension changed to match. */
+RetCode SanitizeImage(const std::string& image, const std::string& outfile,
+                      const SanitizeConfig& config, SanitizeResult& result);
+
+/* sanitizes every supported image in dir, an image that fails is reported
+ * in its result and the rest carry on. In place, an image that changes type
+ * replaces the original under its new name. */
+RetCode SanitizeDirectory(const std::string& dir, const SanitizeConfig& config,
+                          std::vector<SanitizeResult>& results);
+
+}  // namespace steganography
+
+#endif
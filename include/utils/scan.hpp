@@ -0,0 +1,38 @@
+#ifndef SCAN_HPP_
+#define SCAN_HPP_
+
+#include <ostream>
+#include <string>
+#include <vector>
+
+#include "utils/detect.hpp"
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+enum class ScanFormat {
+    kCsv,
+    kJson,
+};
+
+struct ScanConfig {
+    bool recursive = false; /* descend into subdirectories */
+    double threshold = 0.5; /* minimum detection confidence to report */
+};
+
+struct ScanResult {
+    std::string path;
+    DetectionReport report;
+};
+
+/* runs Detect on every supported image in dir, results at or above the
+ * threshold are sorted most suspicious first */
+RetCode ScanDirectory(const std::string& dir, const ScanConfig& config,
+                      std::vector<ScanResult>& results);
+
+void WriteScanReport(const std::vector<ScanResult>& results, ScanFormat format,
+                     std::ostream& os);
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,40 @@
+#ifndef SCRUB_HPP_
+#define SCRUB_HPP_
+
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <optional>
+#include <string>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+struct ScrubConfig {
+    /* low bits of every channel to overwrite, 4 covers all that is embedded
+     * in the usual layout */
+    int bits = 4;
+    std::optional<uint64_t> seed; /* none picks a random seed */
+    bool create_dirs = false;     /* create missing parents of the output */
+    OutputFormat output_format = OutputFormat::kAuto;
+};
+
+struct ScrubReport {
+    bool had_payload = false; /* a pack payload was present before */
+    double changed = 0.0;     /* fraction of channel values that changed */
+};
+
+/* Replaces the low bits of every channel with noise around what the high
+ * bits of the pixel and its neighbours suggest, so flat areas stay flat and
+ * anything hidden in the low bits is gone. The high bits are left as they
+ * are, a value moves by less than 2^bits. */
+RetCode ScrubImage(boost::gil::rgb8_image_t& image, const ScrubConfig& config,
+                   ScrubReport& report);
+
+RetCode Scrub(const std::string& image, const std::string& outfile,
+              const ScrubConfig& config, ScrubReport& report);
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,40 @@
+#ifndef SEAL_HPP_
+#define SEAL_HPP_
+
+#include <array>
+#include <cstddef>
+#include <cstdint>
+#include <string>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* an Ed25519 public key */
+const std::size_t kSignerSize = 32;
+
+using SealSigner = std::array<uint8_t, kSignerSize>;
+
+struct SealConfig {
+    std::string keyfile;      /* the signing key is derived from it */
+    bool create_dirs = false; /* create missing parents of the output */
+};
+
+/* Signs everything above the low nibble of each channel, with the image's
+ * dimensions, using an Ed25519 key derived from the key file and hides the
+ * signature in the low nibbles. The sealed image is written losslessly and
+ * signer set to the public key to hand to whoever verifies it. */
+RetCode SealImage(const std::string& image, const std::string& outfile,
+                  const SealConfig& config, SealSigner& signer);
+
+/* kSealBroken when the visible content changed after sealing, signer is set
+ * whenever a seal is found, kPayloadNotFound when there is none */
+RetCode VerifySeal(const std::string& image, SealSigner& signer);
+
+/* the public key as lowercase hex, and back, false when it is malformed */
+std::string SignerToHex(const SealSigner& signer);
+bool SignerFromHex(const std::string& hex, SealSigner& signer);
+
+}  // namespace steganography
+
+#endif
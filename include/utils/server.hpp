@@ -0,0 +1,30 @@
+#ifndef SERVER_HPP_
+#define SERVER_HPP_
+
+#include <cstddef>
+#include <cstdint>
+#include <string>
+
+#include "utils/image_io.hpp"
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+struct ServerConfig {
+    uint16_t port = 8080;
+    std::string address = "127.0.0.1"; /* 0.0.0.0 listens on all interfaces */
+    std::size_t max_request = 64 * 1024 * 1024; /* larger uploads get a 413 */
+    ImageLimits image_limits; /* larger images in an upload get a 413 */
+    unsigned threads = 0; /* workers per merge or unmerge, 0 for every core */
+    /* bytes the images of one request may take decoded, over it gets a 413,
+     * 0 for no budget */
+    uint64_t memory_budget = 0;
+};
+
+/* serves POST /merge and POST /unmerge until the process is terminated,
+ * only returns if the listening socket cannot be set up */
+RetCode Serve(const ServerConfig& config);
+
+}  // namespace steganography
+
+#endif
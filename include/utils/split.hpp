@@ -0,0 +1,65 @@
+#ifndef SPLIT_HPP_
+#define SPLIT_HPP_
+
+#include <cstdint>
+#include <string>
+#include <vector>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* name of the manifest image Split writes next to the parts */
+const char* const kManifestName = "manifest.png";
+
+struct SplitConfig {
+    /* also write kManifestName listing the parts and their hashes */
+    bool manifest = false;
+};
+
+/* the share of a split payload one cover holds */
+struct SplitPart {
+    std::string file;  /* name in the output directory */
+    std::string cover; /* the cover it was packed into */
+    uint64_t offset = 0;
+    uint64_t length = 0;
+    std::string sha256; /* of the part's bytes, in hex */
+};
+
+/* Splits the file input across the covers, in the order given, each
+ * taking as much as it holds, and writes outdir/part-001.png onwards,
+ * creating outdir when it does not exist. Covers past the end of the payload
+ * are left out, as are covers too small to hold anything. Each part records
+ * its index, the part count, and the length and SHA-256 of the whole file,
+ * see docs/FORMAT.md. kInsufficientCapacity when the covers together cannot
+ * hold the file. */
+RetCode Split(const std::string& input, const std::string& outdir,
+              const std::vector<std::string>& covers,
+              const SplitConfig& config, std::vector<SplitPart>& parts);
+
+struct JoinConfig {
+    std::string manifest; /* the manifest image Split wrote, optional */
+    bool create_dirs = false; /* create missing parents of the output */
+};
+
+/* what joining found wrong with the images it was given */
+struct JoinReport {
+    /* parts no image holds, by the file name the manifest gives them or
+     * as "part N of M" without one */
+    std::vector<std::string> missing;
+    /* images holding no part of the payload, another payload's part, a part
+     * already seen, or a part whose hash the manifest does not list */
+    std::vector<std::string> unexpected;
+};
+
+/* Reassembles the file the parts were split from in the order their
+ * records give, whatever order they are passed in, and checks it against
+ * the recorded length and SHA-256. With a manifest each part is checked
+ * against the hash it lists too. kIncompleteParts when a part is missing,
+ * kPayloadDamaged when the reassembled file does not match. */
+RetCode Join(const std::vector<std::string>& parts, const std::string& outfile,
+             const JoinConfig& config, JoinReport& report);
+
+}  // namespace steganography
+
+#endif
@@ -1,22 +1,165 @@
 #ifndef STEGANOGRAPHY_UTIL_HPP_
 #define STEGANOGRAPHY_UTIL_HPP_
 
+#include <array>
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
 #include <string>
+#include <vector>
+
+#include "core/ret_code.hpp"
 
 namespace steganography {
 
-enum class RetCode {
-    kSuccess,
-    kInvalidFileFormat,
-    kFileNotFound,
-    kInvalidDimensions,
+/* the channels of the cover that hold the secret */
+enum class ColorSpace {
+    kRgb,   /* red, green, and blue */
+    kYCbCr, /* only the chroma channels, at a lower secret precision */
+    kHsv,   /* only the value channel, at a lower secret precision still */
+};
+
+/* the encoded format of an output image */
+enum class OutputFormat {
+    kAuto,        /* by the output's extension */
+    kPng,
+    kJpeg,        /* only for images that carry no hidden data */
+    kJxl,         /* lossless, when built with STEG_WITH_JXL */
+    kSameAsCover, /* the input image's format when it can be written */
+};
+
+/* what becomes of an input that cannot be held as 8 bit RGB as it is */
+enum class ConversionPolicy {
+    kConvert, /* convert it and warn */
+    kReject,  /* fail with kConversionRefused */
+};
+
+/* the policy for each kind of input needing a conversion */
+struct InputPolicy {
+    /* CMYK and YCCK JPEGs, converted without color management */
+    ConversionPolicy cmyk = ConversionPolicy::kConvert;
+    /* 12 bit JPEGs and 16 bit PNGs, rounded to 8 bits */
+    ConversionPolicy depth = ConversionPolicy::kConvert;
+    /* interlaced PNGs, the outputs made from them are not interlaced */
+    ConversionPolicy interlace = ConversionPolicy::kConvert;
+};
+
+struct MergeConfig {
+    std::string keyfile;  /* optional key driving pixel and channel layout */
+    std::string password; /* optional second factor, requires a key file */
+    bool create_dirs = false; /* create missing parents of the output */
+    int bits = 4; /* low bits per cover channel replaced by the secret, 1-8 */
+    ColorSpace color_space = ColorSpace::kRgb;
+    bool fit = false; /* shrink a secret larger than the cover to fit it */
+    double max_shrink = 4.0; /* shrink factor beyond which fit warns */
+    bool strict = false;     /* fail instead of warning */
+    bool linear_resize = false; /* fit in linear light rather than sRGB */
+    double sharpen = 0.0; /* unsharp mask amount for the secret, 0 skips it */
+    /* diffuse the error from replacing the low bits into the neighboring
+     * high bits so flat gradients don't band, RGB only */
+    bool dither = false;
+    /* draw each 16x16 block of the cover's bits from half of bits up to
+     * bits with the key so no bit depth is used throughout, needs a key file
+     * and excludes dither */
+    bool block_bits = false;
+    /* resize the secret to this and record it so unmerge crops the padding
+     * back off, 0 keeps the secret's size unrecorded */
+    std::ptrdiff_t secret_width = 0;
+    std::ptrdiff_t secret_height = 0;
+    OutputFormat output_format = OutputFormat::kAuto;
+    unsigned threads = 0; /* workers embedding the secret, 0 for every core */
+    /* bytes the decoded images may take together, checked against their
+     * headers before decoding, 0 for no budget */
+    uint64_t memory_budget = 0;
+    std::string method = "lsb"; /* registered EmbedMethod doing the hiding */
+    InputPolicy input; /* for the cover and secret, strict rejects them all */
+    /* optional PNG as large as the cover marking the bits merging changed,
+     * bit i of a channel lights bit 7 - i of the mask's, not for OpenEXR */
+    std::string mask;
+};
+
+struct UnmergeConfig {
+    std::string keyfile;  /* must match the key file used to merge */
+    std::string password; /* must match the password used to merge */
+    bool create_dirs = false; /* create missing parents of the output */
+    int bits = 4; /* must match the bits used to merge */
+    ColorSpace color_space = ColorSpace::kRgb; /* must match as well */
+    bool block_bits = false;                   /* and so must this */
+    OutputFormat output_format = OutputFormat::kAuto;
+    unsigned threads = 0; /* workers extracting the secret, 0 for every core */
+    uint64_t memory_budget = 0; /* as for merging, 0 for no budget */
+    std::string method = "lsb"; /* must match the method used to merge */
+};
+
+struct MergePhase {
+    std::string name;
+    double milliseconds = 0.0;
+    uint64_t peak_rss = 0; /* most bytes resident by the end of the phase */
+};
+
+/* something that did not stop the merge but likely spoils the result */
+struct MergeWarning {
+    std::string code; /* stable identifier, e.g. "secret-shrunk" */
+    std::string message;
+};
+
+struct MergeReport {
+    std::size_t pixels_modified = 0; /* cover pixels whose value changed */
+    /* channel values whose bit i changed, 0 the least significant, left
+     * at 0 for OpenEXR covers */
+    std::array<std::size_t, 8> bits_modified{};
+    std::size_t bits_embedded = 0;   /* secret image bits stored in the cover */
+    double capacity_used = 0.0;      /* percent of cover pixels holding secret */
+    double psnr = 0.0;               /* output vs. cover in dB */
+    std::vector<MergePhase> phases;  /* elapsed time in execution order */
+    double shrink = 1.0; /* factor fit divided the secret's dimensions by */
+    std::vector<MergeWarning> warnings;
+    /* most bytes the process had resident, 0 where the platform does not
+     * tell. It covers only the merge when rss_since_merge is set and the
+     * process so far otherwise, other threads' work counts towards it. */
+    uint64_t peak_rss = 0;
+    bool rss_since_merge = false;
+    /* bytes of the decoded cover, secret, and output held at once, what
+     * memory_budget is checked against */
+    uint64_t image_bytes = 0;
 };
 
 RetCode Merge(const std::string& cover, const std::string& secret,
               const std::string& outfile);
 
+RetCode Merge(const std::string& cover, const std::string& secret,
+              const std::string& outfile, const MergeConfig& config);
+
+RetCode Merge(const std::string& cover, const std::string& secret,
+              const std::string& outfile, const MergeConfig& config,
+              MergeReport& report);
+
+/* Merges as above and hands back the merged image as well, so it can be
+ * processed further without reading it back from disk. An empty outfile
+ * skips writing it. OpenEXR covers have no 8 bit merged image and must be
+ * written to an outfile, which leaves merged empty. */
+RetCode Merge(const std::string& cover, const std::string& secret,
+              const std::string& outfile, const MergeConfig& config,
+              MergeReport& report, boost::gil::rgb8_image_t& merged);
+
+/* Merges a secret that is already in memory, such as one rendered from text,
+ * so it never has to be written to disk first. */
+RetCode Merge(const std::string& cover, const boost::gil::rgb8_image_t& secret,
+              const std::string& outfile, const MergeConfig& config,
+              MergeReport& report);
+
 RetCode Unmerge(const std::string& secret, const std::string& outfile);
 
+RetCode Unmerge(const std::string& secret, const std::string& outfile,
+                const UnmergeConfig& config);
+
+/* Unmerges as above and hands back the extracted secret as well, an empty
+ * outfile skips writing it. As for merging, OpenEXR images must be written
+ * to an outfile and leave unmerged empty. */
+RetCode Unmerge(const std::string& secret, const std::string& outfile,
+                const UnmergeConfig& config,
+                boost::gil::rgb8_image_t& unmerged);
+
 }  // namespace steganography
 
 #endif
@@ -0,0 +1,20 @@
+#ifndef SVG_HPP_
+#define SVG_HPP_
+
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <string>
+
+namespace steganography {
+
+/* whether the file is SVG markup, an svg element near its start */
+bool IsSvg(const std::string& filename);
+
+/* draws the SVG scaled to fit inside width by height with its aspect ratio
+ * kept, centered on a white background, false when it cannot be parsed */
+bool RasterizeSvg(const std::string& filename, std::ptrdiff_t width,
+                  std::ptrdiff_t height, boost::gil::rgb8_image_t& image);
+
+}  // namespace steganography
+
+#endif
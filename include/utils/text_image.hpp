@@ -0,0 +1,21 @@
+#ifndef TEXT_IMAGE_HPP_
+#define TEXT_IMAGE_HPP_
+
+#include <string>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* renders a plain text or markdown file as dark monospace text on a light
+ * image the size of the cover and merges it, so the secret unmerge returns
+ * can be read without any other tools. Long lines are wrapped at spaces,
+ * tabs stop every 4 columns, characters outside ASCII are drawn as '?', and
+ * the largest whole number of pixels per glyph pixel that fits is used.
+ * kInsufficientCapacity when the text does not fit even at one. */
+RetCode MergeText(const std::string& cover, const std::string& textfile,
+                  const std::string& outfile, const MergeConfig& config);
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,21 @@
+#ifndef TIFF_HPP_
+#define TIFF_HPP_
+
+#include <boost/gil.hpp>
+#include <cstdint>
+#include <string>
+
+namespace steganography {
+
+/* reads a TIFF as 8 bit RGB, CMYK images are converted to sRGB through the
+ * ICC profile embedded in the file, false when it cannot be decoded */
+bool ReadTiff(const std::string& filename, boost::gil::rgb8_image_t& image);
+
+/* the dimensions and decoded bytes per pixel of the first image in the file
+ * without decoding it */
+bool ReadTiffHeader(const std::string& filename, uint64_t& width,
+                    uint64_t& height, uint64_t& pixel_bytes);
+
+}  // namespace steganography
+
+#endif
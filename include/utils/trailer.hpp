@@ -0,0 +1,25 @@
+#ifndef TRAILER_HPP_
+#define TRAILER_HPP_
+
+#include <cstdint>
+#include <string>
+#include <vector>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* the data of a file's trailer, bytes appended past the end of its own data
+ * that readers of most formats stop short of, for a ByteCarrier to read the
+ * payload from, kPayloadNotFound when it has none */
+RetCode ReadTrailer(const std::string& filename, std::vector<uint8_t>& data);
+
+/* appends data to a file followed by its count and a magic number, in place
+ * of any trailer it had. Any file can carry one, but an editor saving the
+ * file drops it. */
+RetCode WriteTrailer(const std::string& filename,
+                     const std::vector<uint8_t>& data);
+
+}  // namespace steganography
+
+#endif
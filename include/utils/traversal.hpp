@@ -0,0 +1,66 @@
+#ifndef TRAVERSAL_HPP_
+#define TRAVERSAL_HPP_
+
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <optional>
+#include <vector>
+
+#include "utils/key.hpp"
+#include "utils/payload.hpp"
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* the order the usual layout visits the pixels of an image in */
+enum class TraversalOrder {
+    kRowMajor,    /* row by row, left to right */
+    kSerpentine,  /* row by row, every other row right to left */
+    kColumnMajor, /* column by column, top to bottom */
+    kSpiral,      /* clockwise from the top left corner inwards */
+};
+
+struct Traversal {
+    TraversalOrder order = TraversalOrder::kRowMajor;
+    /* visits every stride-th pixel of the order, wrapping around, and must
+     * share no factor with the pixels visited so every one of them is */
+    std::size_t stride = 1;
+    /* only visits the pixels at most border pixels in from an edge, leaving
+     * the middle of the image as it is, 0 visits them all */
+    std::size_t border = 0;
+    /* leaves out faces, text, and salient regions, found again from the
+     * image when reading, needs a build with STEG_WITH_SALIENCY */
+    bool avoid_salient = false;
+};
+
+/* whether the traversal visits the pixels as the usual layout does */
+bool IsRowMajor(const Traversal& traversal);
+
+/* the row-major index of every pixel in the order visited, leaving out those
+ * set in avoid when it is not empty. kInvalidArgument for a stride of 0 or one
+ * sharing a factor with the number of pixels visited */
+RetCode TraversalPixels(std::size_t width, std::size_t height,
+                        const Traversal& traversal,
+                        std::vector<std::size_t>& order,
+                        const std::vector<bool>& avoid = {});
+
+/* embeds the payload in the usual layout with the pixels visited in the
+ * traversal's order, the capacity stays the same unless a border or the
+ * salient regions leave pixels out. kUnsupportedFormat when avoiding salient
+ * regions was not built in */
+RetCode EmbedTraversal(boost::gil::rgb8_image_t& image,
+                       const std::vector<uint8_t>& payload,
+                       const Traversal& traversal,
+                       const EmbedConfig& config = {});
+
+/* the traversal must be the one the payload was embedded with */
+RetCode ExtractTraversal(const boost::gil::rgb8_image_t& image,
+                         const Traversal& traversal,
+                         std::vector<uint8_t>& payload, PayloadHeader& header,
+                         DamageReport& damage,
+                         const std::optional<StegKey>& key = std::nullopt);
+
+}  // namespace steganography
+
+#endif
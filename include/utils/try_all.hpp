@@ -0,0 +1,53 @@
+#ifndef TRY_ALL_HPP_
+#define TRY_ALL_HPP_
+
+#include <boost/gil.hpp>
+#include <string>
+#include <vector>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+struct TryAllConfig {
+    /* key file, password, and block bits to extract with, bits, color
+     * space, and method are tried in turn */
+    UnmergeConfig unmerge;
+    int thumbnail_size = 192; /* longest side of a contact sheet cell */
+    int gap = 4;              /* white pixels between the cells */
+};
+
+/* the parameters of one extraction and whether it produced an image */
+struct TryAllCandidate {
+    std::string method;
+    ColorSpace color_space = ColorSpace::kRgb;
+    int bits = 0;
+    RetCode status = RetCode::kSuccess;
+    std::string file; /* name in the output directory, empty on failure */
+};
+
+/* the rows of a contact sheet, one per method and color space, each
+ * labelled "<method> <space>" */
+std::vector<std::string> ContactSheetRows();
+
+/* Lays the images extracted for candidates out in a grid, one row per
+ * method and color space and one column per bits value from 1 to 8, each
+ * shrunk to fit a thumbnail_size square. Failed candidates leave their
+ * cell gray. */
+RetCode RenderContactSheet(
+    const std::vector<TryAllCandidate>& candidates,
+    const std::vector<boost::gil::rgb8_image_t>& extracted,
+    const TryAllConfig& config, boost::gil::rgb8_image_t& sheet);
+
+/* Extracts the secret from image with every registered method, color
+ * space, and bits value, writing each result that decodes to
+ * outdir/<method>-<space>-<bits>.png and all of them as thumbnails to
+ * outdir/contact-sheet.png. When no combination produces an image,
+ * fails as unmerge with the defaults would have. */
+RetCode TryAll(const std::string& image, const std::string& outdir,
+               const TryAllConfig& config,
+               std::vector<TryAllCandidate>& candidates);
+
+}  // namespace steganography
+
+#endif
@@ -0,0 +1,25 @@
+#ifndef TUI_HPP_
+#define TUI_HPP_
+
+#include <functional>
+#include <string>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+struct TuiConfig {
+    std::string cover; /* initial field values, any may be empty */
+    std::string secret;
+    std::string output;
+    /* turns a failed merge or unmerge into a message for the status line */
+    std::function<std::string(RetCode)> describe_error;
+};
+
+/* runs the interactive terminal UI on stdin/stdout until the user quits,
+ * returns kIoError if the terminal cannot be switched to raw mode */
+RetCode RunTui(const TuiConfig& config);
+
+}  // namespace steganography
+
+#endif
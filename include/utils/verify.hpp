@@ -0,0 +1,38 @@
+#ifndef VERIFY_HPP_
+#define VERIFY_HPP_
+
+#include <cstddef>
+#include <cstdint>
+#include <string>
+#include <vector>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* a channel that changed above the declared low bits */
+struct OutOfBandChange {
+    std::ptrdiff_t x = 0;
+    std::ptrdiff_t y = 0;
+    int channel = 0; /* 0 red, 1 green, 2 blue */
+    uint8_t cover = 0;
+    uint8_t stego = 0;
+};
+
+struct PairReport {
+    std::size_t changed_channels = 0; /* channels differing at all */
+    std::size_t out_of_band_channels = 0;
+    std::size_t out_of_band_pixels = 0;
+    int bits_needed = 0;   /* fewest low bits accounting for every change */
+    double psnr = 0.0;     /* of the stego image against the cover */
+    std::vector<OutOfBandChange> examples; /* the first few, row by row */
+};
+
+/* compares cover and stego channel by channel, returns kOutOfBandChanges
+ * with report filled in when anything above the low bits differs */
+RetCode VerifyPair(const std::string& cover, const std::string& stego,
+                   int bits, PairReport& report);
+
+}  // namespace steganography
+
+#endif
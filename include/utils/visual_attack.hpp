@@ -0,0 +1,33 @@
+#ifndef VISUAL_ATTACK_HPP_
+#define VISUAL_ATTACK_HPP_
+
+#include <boost/gil.hpp>
+#include <string>
+#include <vector>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+struct VisualAttackConfig {
+    /* bit planes to show, 0 is the LSB, the first listed is the most
+     * significant bit of the rendered value */
+    std::vector<int> planes = {0};
+    /* 0 red, 1 green, 2 blue, a single channel is rendered in grayscale and
+     * channels left out are black */
+    std::vector<int> channels = {0, 1, 2};
+    bool create_dirs = false; /* create missing parents of the output */
+};
+
+/* stretches the selected bit planes to the full 0-255 range, hidden data
+ * shows up as noise where the picture's own structure should be visible */
+RetCode RenderBitPlanes(const boost::gil::rgb8_image_t& image,
+                        const VisualAttackConfig& config,
+                        boost::gil::rgb8_image_t& rendered);
+
+RetCode VisualAttack(const std::string& image, const std::string& outfile,
+                     const VisualAttackConfig& config);
+
+}  // namespace steganography
+
+#endif
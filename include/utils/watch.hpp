@@ -0,0 +1,38 @@
+#ifndef WATCH_HPP_
+#define WATCH_HPP_
+
+#include <functional>
+#include <string>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+enum class WatchMode {
+    kMerge,   /* new files are covers, the secret is merged into each */
+    kUnmerge, /* new files are merged images, their secret is extracted */
+};
+
+struct WatchConfig {
+    std::string indir;
+    std::string outdir;
+    WatchMode mode = WatchMode::kMerge;
+    std::string secret;   /* required in merge mode */
+    std::string keyfile;  /* optional, see MergeConfig */
+    std::string password; /* optional, see MergeConfig */
+    int debounce_ms = 500; /* quiet period before a new file is processed */
+
+    /* called after each file with the output path and the result */
+    std::function<void(const std::string& infile, const std::string& outfile,
+                       RetCode rc)>
+        on_processed;
+};
+
+/* processes files already in indir and any that appear later until the
+ * process is terminated, files listed in the ledger kept in outdir are
+ * never processed twice */
+RetCode Watch(const WatchConfig& config);
+
+}  // namespace steganography
+
+#endif
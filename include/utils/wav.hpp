@@ -0,0 +1,28 @@
+#ifndef WAV_HPP_
+#define WAV_HPP_
+
+#include <cstdint>
+#include <string>
+#include <vector>
+
+#include "utils/steganography_util.hpp"
+
+namespace steganography {
+
+/* 16 bit PCM audio, the samples of each frame stored channel by channel */
+struct WavAudio {
+    uint16_t channels = 0;
+    uint32_t sample_rate = 0;
+    std::vector<int16_t> samples;
+};
+
+/* reads a 16 bit PCM WAV file, kUnsupportedFormat for any other encoding */
+RetCode ReadWav(const std::string& filename, WavAudio& audio);
+
+/* writes a WAV file holding only the format and the samples, any other
+ * chunks of the file it was read from are dropped */
+RetCode WriteWav(const WavAudio& audio, const std::string& filename);
+
+}  // namespace steganography
+
+#endif
@@ -1,6 +1,8 @@
 #!/bin/bash
 
 BUILD_TYPE="Release"
+CMAKE_OPTIONS=""
+RUN_TESTS="OFF"
 
 source config.sh
 
@@ -11,6 +13,21 @@ Help()
     echo "usage: build.sh [OPTION]..."
     echo "options:"
     echo -e "\tg    enable debug info"
+    echo -e "\tp    PNG only, build without JPEG support (no libjpeg needed)"
+    echo -e "\ts    build the serve command (HTTP service mode)"
+    echo -e "\tu    accept http(s):// URLs as input images (needs libcurl)"
+    echo -e "\to    accept s3://bucket/key paths (needs libcurl)"
+    echo -e "\tc    pack/unpack text through the system clipboard"
+    echo -e "\tt    accept TIFF input images (needs libtiff and lcms2)"
+    echo -e "\te    merge into OpenEXR images (needs OpenEXR 3)"
+    echo -e "\ta    accept AVIF input images (needs libavif)"
+    echo -e "\tf    accept HEIC/HEIF input images (needs libheif)"
+    echo -e "\tr    accept camera raw input images (needs LibRaw)"
+    echo -e "\tv    accept SVG secrets for merge (needs librsvg)"
+    echo -e "\tj    accept and write JPEG XL images (needs libjxl)"
+    echo -e "\ti    accept GIF images and pack into GIFs (needs giflib)"
+    echo -e "\tb    build the throughput benchmarks (needs Google Benchmark)"
+    echo -e "\tx    build and run the unit tests (needs GoogleTest)"
     echo -e "\th    print this help message"
 }
 
@@ -22,9 +39,11 @@ Main()
     pushd $STEG_BUILD_DIR > /dev/null
         cmake ../ \
               -DCMAKE_EXPORT_COMPILE_COMMANDS=ON \
-              -DCMAKE_BUILD_TYPE=$BUILD_TYPE && \
+              -DCMAKE_BUILD_TYPE=$BUILD_TYPE \
+              $CMAKE_OPTIONS                 && \
         make -j$(nproc) all                  && \
-        make install
+        make install                         && \
+        if [ "$RUN_TESTS" = "ON" ]; then ctest --output-on-failure; fi
 
         # Exit if any of the above commands fails.
         if [ $? -ne 0 ];
@@ -34,10 +53,26 @@ Main()
     popd > /dev/null
 }
 
-while getopts ":hg" flag
+while getopts ":hgpsuocteafjirvbx" flag
 do
     case "$flag" in
         g) BUILD_TYPE="Debug";;
+        p) CMAKE_OPTIONS="$CMAKE_OPTIONS -DSTEG_WITH_JPEG=OFF";;
+        s) CMAKE_OPTIONS="$CMAKE_OPTIONS -DSTEG_WITH_SERVER=ON";;
+        u) CMAKE_OPTIONS="$CMAKE_OPTIONS -DSTEG_WITH_HTTP=ON";;
+        o) CMAKE_OPTIONS="$CMAKE_OPTIONS -DSTEG_WITH_S3=ON";;
+        c) CMAKE_OPTIONS="$CMAKE_OPTIONS -DSTEG_WITH_CLIPBOARD=ON";;
+        t) CMAKE_OPTIONS="$CMAKE_OPTIONS -DSTEG_WITH_TIFF=ON";;
+        e) CMAKE_OPTIONS="$CMAKE_OPTIONS -DSTEG_WITH_EXR=ON";;
+        a) CMAKE_OPTIONS="$CMAKE_OPTIONS -DSTEG_WITH_AVIF=ON";;
+        f) CMAKE_OPTIONS="$CMAKE_OPTIONS -DSTEG_WITH_HEIF=ON";;
+        r) CMAKE_OPTIONS="$CMAKE_OPTIONS -DSTEG_WITH_RAW=ON";;
+        v) CMAKE_OPTIONS="$CMAKE_OPTIONS -DSTEG_WITH_SVG=ON";;
+        j) CMAKE_OPTIONS="$CMAKE_OPTIONS -DSTEG_WITH_JXL=ON";;
+        i) CMAKE_OPTIONS="$CMAKE_OPTIONS -DSTEG_WITH_GIF=ON";;
+        b) CMAKE_OPTIONS="$CMAKE_OPTIONS -DSTEG_WITH_BENCH=ON";;
+        x) CMAKE_OPTIONS="$CMAKE_OPTIONS -DSTEG_WITH_TESTS=ON"
+           RUN_TESTS="ON";;
         h) Help
            exit;;
        \?) echo "error: invalid option '$OPTARG'"
@@ -0,0 +1,152 @@
+#include <benchmark/benchmark.h>
+
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <random>
+#include <stdexcept>
+#include <string>
+
+#include "utils/image_io.hpp"
+#include "utils/paths.hpp"
+#include "utils/steganography_util.hpp"
+
+/* each benchmark runs over square images of the given side, every bit depth
+ * merge accepts at the ends and in the middle, and one thread against one
+ * per core */
+static void Arguments(benchmark::internal::Benchmark* bench) {
+    for (int64_t side : {512, 2048}) {
+        for (int64_t bits : {1, 4, 8}) {
+            for (int64_t threads : {1, 0}) {
+                bench->Args({side, bits, threads});
+            }
+        }
+    }
+    bench->ArgNames({"side", "bits", "threads"});
+}
+
+/* uniform noise, the hardest case for the PNG encoder and close to the low
+ * bits of a photograph */
+static void WriteNoise(const std::string& filename, std::ptrdiff_t side,
+                       uint32_t seed) {
+    std::mt19937 rng(seed);
+    std::uniform_int_distribution<int> channel(0, 255);
+    boost::gil::rgb8_image_t image(side, side);
+    auto view = boost::gil::view(image);
+    for (std::ptrdiff_t y = 0; y < side; ++y) {
+        for (std::ptrdiff_t x = 0; x < side; ++x) {
+            view(x, y) = boost::gil::rgb8_pixel_t(
+                static_cast<uint8_t>(channel(rng)),
+                static_cast<uint8_t>(channel(rng)),
+                static_cast<uint8_t>(channel(rng)));
+        }
+    }
+    steganography::WriteImage(image, filename, steganography::ImageType::kPng);
+}
+
+/* a cover and a secret as large as it, written once per benchmark */
+class Inputs {
+   public:
+    explicit Inputs(std::ptrdiff_t side) : scratch_("steganography-bench") {
+        if (!scratch_.Valid()) {
+            throw std::runtime_error("failed to create a scratch directory");
+        }
+        WriteNoise(Cover(), side, 1);
+        WriteNoise(Secret(), side, 2);
+    }
+
+    std::string Cover() const { return scratch_.File("cover.png"); }
+    std::string Secret() const { return scratch_.File("secret.png"); }
+    std::string File(const std::string& name) const {
+        return scratch_.File(name);
+    }
+
+   private:
+    steganography::ScratchDir scratch_;
+};
+
+static steganography::MergeConfig MergeConfigFor(
+    const benchmark::State& state) {
+    steganography::MergeConfig config;
+    config.bits = static_cast<int>(state.range(1));
+    config.threads = static_cast<unsigned>(state.range(2));
+    return config;
+}
+
+static void SetThroughput(benchmark::State& state) {
+    const double kMegapixels =
+        static_cast<double>(state.range(0) * state.range(0)) / 1e6;
+    state.counters["MP/s"] = benchmark::Counter(
+        kMegapixels, benchmark::Counter::kIsIterationInvariantRate);
+}
+
+/* merge from end to end, decoding and encoding the PNGs included */
+static void MergeThroughput(benchmark::State& state) {
+    const Inputs kInputs(state.range(0));
+    const steganography::MergeConfig kConfig = MergeConfigFor(state);
+    const std::string kMerged = kInputs.File("merged.png");
+    for (auto _ : state) {
+        if (steganography::Merge(kInputs.Cover(), kInputs.Secret(), kMerged,
+                                 kConfig) != steganography::RetCode::kSuccess) {
+            state.SkipWithError("merge failed");
+            break;
+        }
+    }
+    SetThroughput(state);
+}
+
+/* only the pixel loop, timed by the merge's own embed phase, where a
+ * regression would otherwise hide behind the codecs */
+static void EmbedThroughput(benchmark::State& state) {
+    const Inputs kInputs(state.range(0));
+    const steganography::MergeConfig kConfig = MergeConfigFor(state);
+    const std::string kMerged = kInputs.File("merged.png");
+    for (auto _ : state) {
+        steganography::MergeReport report;
+        if (steganography::Merge(kInputs.Cover(), kInputs.Secret(), kMerged,
+                                 kConfig,
+                                 report) != steganography::RetCode::kSuccess) {
+            state.SkipWithError("merge failed");
+            break;
+        }
+        for (const auto& phase : report.phases) {
+            if (phase.name == "embed") {
+                state.SetIterationTime(phase.milliseconds / 1000.0);
+            }
+        }
+    }
+    SetThroughput(state);
+}
+
+/* unmerge from end to end out of an image merged at the same bit depth */
+static void UnmergeThroughput(benchmark::State& state) {
+    const Inputs kInputs(state.range(0));
+    const std::string kMerged = kInputs.File("merged.png");
+    const std::string kUnmerged = kInputs.File("unmerged.png");
+    if (steganography::Merge(kInputs.Cover(), kInputs.Secret(), kMerged,
+                             MergeConfigFor(state)) !=
+        steganography::RetCode::kSuccess) {
+        state.SkipWithError("merge failed");
+        return;
+    }
+    steganography::UnmergeConfig config;
+    config.bits = static_cast<int>(state.range(1));
+    config.threads = static_cast<unsigned>(state.range(2));
+    for (auto _ : state) {
+        if (steganography::Unmerge(kMerged, kUnmerged, config) !=
+            steganography::RetCode::kSuccess) {
+            state.SkipWithError("unmerge failed");
+            break;
+        }
+    }
+    SetThroughput(state);
+}
+
+BENCHMARK(MergeThroughput)->Apply(Arguments)->Unit(benchmark::kMillisecond);
+BENCHMARK(EmbedThroughput)
+    ->Apply(Arguments)
+    ->UseManualTime()
+    ->Unit(benchmark::kMillisecond);
+BENCHMARK(UnmergeThroughput)->Apply(Arguments)->Unit(benchmark::kMillisecond);
+
+BENCHMARK_MAIN();
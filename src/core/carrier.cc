@@ -0,0 +1,523 @@
+#include "core/carrier.hpp"
+
+#include <algorithm>
+#include <cstddef>
+#include <cstdint>
+#include <utility>
+#include <vector>
+
+namespace steganography {
+
+std::size_t Carrier::CellRow([[maybe_unused]] std::size_t cell) const {
+    return 0;
+}
+
+double Carrier::CellsPerRow() const { return 0.0; }
+
+/* payload bits are stored in the low nibble of each color channel */
+static const int kBitsPerChannel = 4;
+static const std::size_t kChannelCount = 3;
+static const std::size_t kSlotsPerCell = 8 / kBitsPerChannel;
+
+/* The pointer the constructors over const pixels hand on. Only WriteCell
+ * writes through it, and such a carrier is only ever used as a const
+ * Carrier, which cannot reach WriteCell. */
+static uint8_t* Writable(const uint8_t* pixels) {
+    return const_cast<uint8_t*>(pixels);
+}
+
+FramebufferCarrier::FramebufferCarrier(uint8_t* pixels, std::size_t width,
+                                       std::size_t height, std::size_t stride,
+                                       std::size_t pixel_size)
+    : pixels_(pixels),
+      width_(width),
+      height_(height),
+      stride_(stride),
+      pixel_size_(pixel_size) {}
+
+FramebufferCarrier::FramebufferCarrier(const uint8_t* pixels,
+                                       std::size_t width, std::size_t height,
+                                       std::size_t stride,
+                                       std::size_t pixel_size)
+    : FramebufferCarrier(Writable(pixels), width, height, stride,
+                         pixel_size) {}
+
+std::size_t FramebufferCarrier::CellCount() const {
+    return width_ * height_ * kChannelCount / kSlotsPerCell;
+}
+
+uint8_t& FramebufferCarrier::Channel(std::size_t slot) const {
+    std::size_t pixel = slot / kChannelCount;
+    return pixels_[pixel / width_ * stride_ + pixel % width_ * pixel_size_ +
+                   slot % kChannelCount];
+}
+
+uint8_t FramebufferCarrier::ReadCell(std::size_t cell) const {
+    const int kLowMask = (1 << kBitsPerChannel) - 1;
+    uint8_t byte = 0;
+    for (std::size_t slot = cell * kSlotsPerCell;
+         slot < (cell + 1) * kSlotsPerCell; ++slot) {
+        byte = static_cast<uint8_t>((byte << kBitsPerChannel) |
+                                    (Channel(slot) & kLowMask));
+    }
+    return byte;
+}
+
+/* the high bits of the byte go into the first slot of the cell */
+void FramebufferCarrier::WriteCell(std::size_t cell, uint8_t byte) {
+    const int kHighMask = 0xFF << kBitsPerChannel;
+    const int kLowMask = (1 << kBitsPerChannel) - 1;
+    int shift = 8 - kBitsPerChannel;
+    for (std::size_t slot = cell * kSlotsPerCell;
+         slot < (cell + 1) * kSlotsPerCell; ++slot, shift -= kBitsPerChannel) {
+        uint8_t& channel = Channel(slot);
+        channel = static_cast<uint8_t>((channel & kHighMask) |
+                                       ((byte >> shift) & kLowMask));
+    }
+}
+
+std::size_t FramebufferCarrier::CellRow(std::size_t cell) const {
+    return cell * kSlotsPerCell / kChannelCount / width_;
+}
+
+double FramebufferCarrier::CellsPerRow() const {
+    return static_cast<double>(width_) * kChannelCount / kSlotsPerCell;
+}
+
+OrderedCarrier::OrderedCarrier(uint8_t* pixels, std::size_t width,
+                               std::size_t stride,
+                               std::vector<std::size_t> order)
+    : pixels_(pixels),
+      width_(width),
+      stride_(stride),
+      order_(std::move(order)) {}
+
+OrderedCarrier::OrderedCarrier(const uint8_t* pixels, std::size_t width,
+                               std::size_t stride,
+                               std::vector<std::size_t> order)
+    : OrderedCarrier(Writable(pixels), width, stride,
+                     std::move(order)) {}
+
+std::size_t OrderedCarrier::CellCount() const {
+    return order_.size() * kChannelCount / kSlotsPerCell;
+}
+
+uint8_t& OrderedCarrier::Channel(std::size_t slot) const {
+    std::size_t pixel = order_[slot / kChannelCount];
+    return pixels_[pixel / width_ * stride_ + pixel % width_ * kChannelCount +
+                   slot % kChannelCount];
+}
+
+uint8_t OrderedCarrier::ReadCell(std::size_t cell) const {
+    const int kLowMask = (1 << kBitsPerChannel) - 1;
+    uint8_t byte = 0;
+    for (std::size_t slot = cell * kSlotsPerCell;
+         slot < (cell + 1) * kSlotsPerCell; ++slot) {
+        byte = static_cast<uint8_t>((byte << kBitsPerChannel) |
+                                    (Channel(slot) & kLowMask));
+    }
+    return byte;
+}
+
+/* the high bits of the byte go into the first slot of the cell */
+void OrderedCarrier::WriteCell(std::size_t cell, uint8_t byte) {
+    const int kHighMask = 0xFF << kBitsPerChannel;
+    const int kLowMask = (1 << kBitsPerChannel) - 1;
+    int shift = 8 - kBitsPerChannel;
+    for (std::size_t slot = cell * kSlotsPerCell;
+         slot < (cell + 1) * kSlotsPerCell; ++slot, shift -= kBitsPerChannel) {
+        uint8_t& channel = Channel(slot);
+        channel = static_cast<uint8_t>((channel & kHighMask) |
+                                       ((byte >> shift) & kLowMask));
+    }
+}
+
+std::size_t OrderedCarrier::CellRow(std::size_t cell) const {
+    return order_[cell * kSlotsPerCell / kChannelCount] / width_;
+}
+
+/* a pixel carries what a single channel of the framebuffer layout does */
+static const std::size_t kPixelsPerCell = kSlotsPerCell;
+
+RotationCarrier::RotationCarrier(uint8_t* pixels, std::size_t width,
+                                 std::size_t height, std::size_t stride,
+                                 std::vector<uint8_t> channels)
+    : pixels_(pixels),
+      width_(width),
+      height_(height),
+      stride_(stride),
+      channels_(std::move(channels)) {}
+
+RotationCarrier::RotationCarrier(const uint8_t* pixels, std::size_t width,
+                                 std::size_t height, std::size_t stride,
+                                 std::vector<uint8_t> channels)
+    : RotationCarrier(Writable(pixels), width, height, stride,
+                      std::move(channels)) {}
+
+std::size_t RotationCarrier::CellCount() const {
+    return std::min(width_ * height_, channels_.size()) / kPixelsPerCell;
+}
+
+uint8_t& RotationCarrier::Channel(std::size_t pixel) const {
+    return pixels_[pixel / width_ * stride_ + pixel % width_ * kChannelCount +
+                   channels_[pixel] % kChannelCount];
+}
+
+uint8_t RotationCarrier::ReadCell(std::size_t cell) const {
+    const int kLowMask = (1 << kBitsPerChannel) - 1;
+    uint8_t byte = 0;
+    for (std::size_t pixel = cell * kPixelsPerCell;
+         pixel < (cell + 1) * kPixelsPerCell; ++pixel) {
+        byte = static_cast<uint8_t>((byte << kBitsPerChannel) |
+                                    (Channel(pixel) & kLowMask));
+    }
+    return byte;
+}
+
+/* the high bits of the byte go into the first pixel of the cell */
+void RotationCarrier::WriteCell(std::size_t cell, uint8_t byte) {
+    const int kHighMask = 0xFF << kBitsPerChannel;
+    const int kLowMask = (1 << kBitsPerChannel) - 1;
+    int shift = 8 - kBitsPerChannel;
+    for (std::size_t pixel = cell * kPixelsPerCell;
+         pixel < (cell + 1) * kPixelsPerCell;
+         ++pixel, shift -= kBitsPerChannel) {
+        uint8_t& channel = Channel(pixel);
+        channel = static_cast<uint8_t>((channel & kHighMask) |
+                                       ((byte >> shift) & kLowMask));
+    }
+}
+
+std::size_t RotationCarrier::CellRow(std::size_t cell) const {
+    return cell * kPixelsPerCell / width_;
+}
+
+double RotationCarrier::CellsPerRow() const {
+    return static_cast<double>(width_) / kPixelsPerCell;
+}
+
+DifferenceCarrier::DifferenceCarrier(uint8_t* pixels,
+                                     const uint8_t* reference,
+                                     std::size_t width, std::size_t height,
+                                     std::size_t stride)
+    : pixels_(pixels),
+      reference_(reference),
+      width_(width),
+      height_(height),
+      stride_(stride) {}
+
+DifferenceCarrier::DifferenceCarrier(const uint8_t* pixels,
+                                     const uint8_t* reference,
+                                     std::size_t width, std::size_t height,
+                                     std::size_t stride)
+    : DifferenceCarrier(Writable(pixels), reference, width,
+                        height, stride) {}
+
+std::size_t DifferenceCarrier::CellCount() const {
+    return width_ * height_ * kChannelCount / 8;
+}
+
+std::size_t DifferenceCarrier::Offset(std::size_t slot) const {
+    std::size_t pixel = slot / kChannelCount;
+    return pixel / width_ * stride_ + pixel % width_ * kChannelCount +
+           slot % kChannelCount;
+}
+
+uint8_t DifferenceCarrier::ReadCell(std::size_t cell) const {
+    uint8_t byte = 0;
+    for (std::size_t slot = cell * 8; slot < (cell + 1) * 8; ++slot) {
+        const std::size_t kOffset = Offset(slot);
+        byte = static_cast<uint8_t>(
+            (byte << 1) | (pixels_[kOffset] != reference_[kOffset]));
+    }
+    return byte;
+}
+
+/* the high bit of the byte goes into the first channel of the cell */
+void DifferenceCarrier::WriteCell(std::size_t cell, uint8_t byte) {
+    int shift = 7;
+    for (std::size_t slot = cell * 8; slot < (cell + 1) * 8;
+         ++slot, --shift) {
+        const std::size_t kOffset = Offset(slot);
+        const uint8_t kReference = reference_[kOffset];
+        if (((byte >> shift) & 1) == 0) {
+            pixels_[kOffset] = kReference;
+            continue;
+        }
+
+        /* half the channels step up and half down, scattered by a
+         * multiplicative hash of their position, so the image keeps its
+         * brightness. The ends of the range can only step inwards. */
+        const uint64_t kGolden = 0x9E3779B97F4A7C15ULL;
+        bool up = ((slot * kGolden) >> 63) != 0;
+        if (kReference == 0) {
+            up = true;
+        } else if (kReference == 0xFF) {
+            up = false;
+        }
+        pixels_[kOffset] = static_cast<uint8_t>(up ? kReference + 1
+                                                   : kReference - 1);
+    }
+}
+
+std::size_t DifferenceCarrier::CellRow(std::size_t cell) const {
+    return cell * 8 / kChannelCount / width_;
+}
+
+double DifferenceCarrier::CellsPerRow() const {
+    return static_cast<double>(width_) * kChannelCount / 8;
+}
+
+BitPlaneCarrier::BitPlaneCarrier(uint8_t* pixels, std::size_t width,
+                                 std::size_t height, std::size_t stride,
+                                 int plane)
+    : pixels_(pixels),
+      width_(width),
+      height_(height),
+      stride_(stride),
+      plane_(plane) {}
+
+BitPlaneCarrier::BitPlaneCarrier(const uint8_t* pixels, std::size_t width,
+                                 std::size_t height, std::size_t stride,
+                                 int plane)
+    : BitPlaneCarrier(Writable(pixels), width, height, stride,
+                      plane) {}
+
+std::size_t BitPlaneCarrier::CellCount() const {
+    return width_ * height_ * kChannelCount / 8;
+}
+
+uint8_t& BitPlaneCarrier::Channel(std::size_t slot) const {
+    std::size_t pixel = slot / kChannelCount;
+    return pixels_[pixel / width_ * stride_ + pixel % width_ * kChannelCount +
+                   slot % kChannelCount];
+}
+
+uint8_t BitPlaneCarrier::ReadCell(std::size_t cell) const {
+    uint8_t byte = 0;
+    for (std::size_t slot = cell * 8; slot < (cell + 1) * 8; ++slot) {
+        byte = static_cast<uint8_t>((byte << 1) |
+                                    ((Channel(slot) >> plane_) & 1));
+    }
+    return byte;
+}
+
+/* the high bit of the byte goes into the first channel of the cell */
+void BitPlaneCarrier::WriteCell(std::size_t cell, uint8_t byte) {
+    int shift = 7;
+    for (std::size_t slot = cell * 8; slot < (cell + 1) * 8;
+         ++slot, --shift) {
+        uint8_t& channel = Channel(slot);
+        channel = static_cast<uint8_t>((channel & ~(1 << plane_)) |
+                                       (((byte >> shift) & 1) << plane_));
+    }
+}
+
+std::size_t BitPlaneCarrier::CellRow(std::size_t cell) const {
+    return cell * 8 / kChannelCount / width_;
+}
+
+double BitPlaneCarrier::CellsPerRow() const {
+    return static_cast<double>(width_) * kChannelCount / 8;
+}
+
+PairSwapCarrier::PairSwapCarrier(uint8_t* pixels, std::size_t width,
+                                 std::size_t height, std::size_t stride)
+    : pixels_(pixels), stride_(stride) {
+    /* swapping keeps equal pairs equal, so the reader finds the same ones */
+    for (std::size_t y = 0; y < height; ++y) {
+        for (std::size_t x = 0; x + 1 < width; x += 2) {
+            for (std::size_t c = 0; c < kChannelCount; ++c) {
+                const std::size_t kOffset = y * stride + x * kChannelCount + c;
+                if (pixels[kOffset] != pixels[kOffset + kChannelCount]) {
+                    pairs_.push_back(kOffset);
+                }
+            }
+        }
+    }
+}
+
+PairSwapCarrier::PairSwapCarrier(const uint8_t* pixels, std::size_t width,
+                                 std::size_t height, std::size_t stride)
+    : PairSwapCarrier(Writable(pixels), width, height, stride) {}
+
+std::size_t PairSwapCarrier::CellCount() const { return pairs_.size() / 8; }
+
+uint8_t PairSwapCarrier::ReadCell(std::size_t cell) const {
+    uint8_t byte = 0;
+    for (std::size_t i = cell * 8; i < (cell + 1) * 8; ++i) {
+        const uint8_t* pair = pixels_ + pairs_[i];
+        byte = static_cast<uint8_t>((byte << 1) |
+                                    (pair[0] > pair[kChannelCount]));
+    }
+    return byte;
+}
+
+/* the high bit of the byte goes into the first pair of the cell */
+void PairSwapCarrier::WriteCell(std::size_t cell, uint8_t byte) {
+    int shift = 7;
+    for (std::size_t i = cell * 8; i < (cell + 1) * 8; ++i, --shift) {
+        uint8_t* pair = pixels_ + pairs_[i];
+        if ((pair[0] > pair[kChannelCount]) != (((byte >> shift) & 1) != 0)) {
+            std::swap(pair[0], pair[kChannelCount]);
+        }
+    }
+}
+
+std::size_t PairSwapCarrier::CellRow(std::size_t cell) const {
+    return pairs_[cell * 8] / stride_;
+}
+
+HistogramShiftCarrier::HistogramShiftCarrier(
+    uint8_t* pixels, std::size_t width, std::size_t height, std::size_t stride,
+    const std::array<uint8_t, 3>& peaks, const std::array<uint8_t, 3>& empties,
+    std::size_t first_slot)
+    : pixels_(pixels), stride_(stride), peaks_(peaks) {
+    for (std::size_t c = 0; c < kChannelCount; ++c) {
+        ones_[c] = static_cast<uint8_t>(
+            (empties[c] > peaks[c]) ? peaks[c] + 1 : peaks[c] - 1);
+    }
+    for (std::size_t slot = first_slot; slot < width * height * kChannelCount;
+         ++slot) {
+        const std::size_t kPixel = slot / kChannelCount;
+        const std::size_t kChannel = slot % kChannelCount;
+        if (peaks[kChannel] == empties[kChannel]) {
+            continue;
+        }
+        const std::size_t kOffset = kPixel / width * stride +
+                                    kPixel % width * kChannelCount + kChannel;
+        if ((pixels[kOffset] == peaks_[kChannel]) ||
+            (pixels[kOffset] == ones_[kChannel])) {
+            slots_.push_back(kOffset);
+        }
+    }
+}
+
+HistogramShiftCarrier::HistogramShiftCarrier(
+    const uint8_t* pixels, std::size_t width, std::size_t height,
+    std::size_t stride, const std::array<uint8_t, 3>& peaks,
+    const std::array<uint8_t, 3>& empties, std::size_t first_slot)
+    : HistogramShiftCarrier(Writable(pixels), width, height,
+                            stride, peaks, empties, first_slot) {}
+
+std::size_t HistogramShiftCarrier::CellCount() const {
+    return slots_.size() / 8;
+}
+
+uint8_t HistogramShiftCarrier::ReadCell(std::size_t cell) const {
+    uint8_t byte = 0;
+    for (std::size_t i = cell * 8; i < (cell + 1) * 8; ++i) {
+        const std::size_t kChannel = slots_[i] % stride_ % kChannelCount;
+        byte = static_cast<uint8_t>((byte << 1) |
+                                    (pixels_[slots_[i]] != peaks_[kChannel]));
+    }
+    return byte;
+}
+
+/* the high bit of the byte goes into the first channel of the cell */
+void HistogramShiftCarrier::WriteCell(std::size_t cell, uint8_t byte) {
+    int shift = 7;
+    for (std::size_t i = cell * 8; i < (cell + 1) * 8; ++i, --shift) {
+        const std::size_t kChannel = slots_[i] % stride_ % kChannelCount;
+        pixels_[slots_[i]] =
+            ((byte >> shift) & 1) ? ones_[kChannel] : peaks_[kChannel];
+    }
+}
+
+std::size_t HistogramShiftCarrier::CellRow(std::size_t cell) const {
+    return slots_[cell * 8] / stride_;
+}
+
+PaletteCarrier::PaletteCarrier(std::vector<uint8_t>& indices,
+                               const std::vector<uint8_t>& palette,
+                               std::size_t width, int reserved)
+    : indices_(indices), width_(width) {
+    /* Rec. 601 luma in integers, ties keep the palette order */
+    std::size_t colors = palette.size() / 3;
+    std::vector<int> luma(colors);
+    for (std::size_t i = 0; i < colors; ++i) {
+        luma[i] = 299 * palette[3 * i] + 587 * palette[3 * i + 1] +
+                  114 * palette[3 * i + 2];
+    }
+    std::vector<int> order;
+    for (std::size_t i = 0; i < colors; ++i) {
+        if (static_cast<int>(i) != reserved) {
+            order.push_back(static_cast<int>(i));
+        }
+    }
+    std::stable_sort(order.begin(), order.end(),
+                     [&luma](int a, int b) { return luma[a] < luma[b]; });
+
+    rank_.assign(colors, -1);
+    partner_.assign(colors, -1);
+    for (std::size_t i = 0; i + 1 < order.size(); i += 2) {
+        rank_[order[i]] = static_cast<int>(i);
+        rank_[order[i + 1]] = static_cast<int>(i + 1);
+        partner_[order[i]] = order[i + 1];
+        partner_[order[i + 1]] = order[i];
+    }
+
+    /* a pixel only ever moves within its pair, so the pixels carrying bits
+     * are the same before and after embedding */
+    for (std::size_t pixel = 0; pixel < indices_.size(); ++pixel) {
+        if ((indices_[pixel] < colors) && (rank_[indices_[pixel]] >= 0)) {
+            pixels_.push_back(pixel);
+        }
+    }
+}
+
+std::size_t PaletteCarrier::CellCount() const { return pixels_.size() / 8; }
+
+uint8_t PaletteCarrier::ReadCell(std::size_t cell) const {
+    uint8_t byte = 0;
+    for (std::size_t i = cell * 8; i < (cell + 1) * 8; ++i) {
+        byte = static_cast<uint8_t>((byte << 1) |
+                                    (rank_[indices_[pixels_[i]]] & 1));
+    }
+    return byte;
+}
+
+/* the high bit of the byte goes into the first pixel of the cell */
+void PaletteCarrier::WriteCell(std::size_t cell, uint8_t byte) {
+    int shift = 7;
+    for (std::size_t i = cell * 8; i < (cell + 1) * 8; ++i, --shift) {
+        uint8_t& index = indices_[pixels_[i]];
+        if ((rank_[index] & 1) != ((byte >> shift) & 1)) {
+            index = static_cast<uint8_t>(partner_[index]);
+        }
+    }
+}
+
+std::size_t PaletteCarrier::CellRow(std::size_t cell) const {
+    return pixels_[cell * 8] / width_;
+}
+
+SampleCarrier::SampleCarrier(std::vector<int16_t>& samples)
+    : samples_(samples) {}
+
+std::size_t SampleCarrier::CellCount() const { return samples_.size() / 2; }
+
+uint8_t SampleCarrier::ReadCell(std::size_t cell) const {
+    return static_cast<uint8_t>(((samples_[2 * cell] & 0x0F) << 4) |
+                                (samples_[2 * cell + 1] & 0x0F));
+}
+
+/* the high nibble of the byte goes into the first sample */
+void SampleCarrier::WriteCell(std::size_t cell, uint8_t byte) {
+    int16_t& first = samples_[2 * cell];
+    int16_t& second = samples_[2 * cell + 1];
+    first = static_cast<int16_t>((first & ~0x0F) | (byte >> 4));
+    second = static_cast<int16_t>((second & ~0x0F) | (byte & 0x0F));
+}
+
+ByteCarrier::ByteCarrier(std::vector<uint8_t>& bytes) : bytes_(bytes) {}
+
+std::size_t ByteCarrier::CellCount() const { return bytes_.size(); }
+
+uint8_t ByteCarrier::ReadCell(std::size_t cell) const { return bytes_[cell]; }
+
+void ByteCarrier::WriteCell(std::size_t cell, uint8_t byte) {
+    bytes_[cell] = byte;
+}
+
+}  // namespace steganography
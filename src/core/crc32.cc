@@ -0,0 +1,34 @@
+#include "core/crc32.hpp"
+
+#include <array>
+#include <cstddef>
+#include <cstdint>
+
+namespace steganography {
+
+/* the reflected form of the IEEE 802.3 polynomial */
+static const uint32_t kCrcPolynomial = 0xEDB88320;
+
+static constexpr std::array<uint32_t, 256> CrcTable() {
+    std::array<uint32_t, 256> table{};
+    for (uint32_t i = 0; i < table.size(); ++i) {
+        uint32_t crc = i;
+        for (int bit = 0; bit < 8; ++bit) {
+            crc = (crc & 1) ? ((crc >> 1) ^ kCrcPolynomial) : (crc >> 1);
+        }
+        table[i] = crc;
+    }
+    return table;
+}
+
+static constexpr std::array<uint32_t, 256> kCrcTable = CrcTable();
+
+uint32_t Crc32(const uint8_t* data, std::size_t size) {
+    uint32_t crc = 0xFFFFFFFF;
+    for (std::size_t i = 0; i < size; ++i) {
+        crc = kCrcTable[(crc ^ data[i]) & 0xFF] ^ (crc >> 8);
+    }
+    return crc ^ 0xFFFFFFFF;
+}
+
+}  // namespace steganography
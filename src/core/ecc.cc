@@ -0,0 +1,124 @@
+#include "core/ecc.hpp"
+
+#include <algorithm>
+#include <cstddef>
+#include <cstdint>
+#include <vector>
+
+namespace steganography {
+
+static const int kCodewordBits = 7;
+static const int kCodewordsPerByte = 2;
+
+static bool GetBit(const std::vector<uint8_t>& data, std::size_t pos) {
+    return (data[pos / 8] >> (7 - pos % 8)) & 1;
+}
+
+static void SetBit(std::vector<uint8_t>& data, std::size_t pos) {
+    data[pos / 8] |= static_cast<uint8_t>(0x80 >> (pos % 8));
+}
+
+/* codeword bits are p1 p2 d1 p3 d2 d3 d4 from the most significant down, so
+ * the syndrome is the 1 based position of a flipped bit */
+static uint8_t EncodeNibble(uint8_t nibble) {
+    int d1 = (nibble >> 3) & 1;
+    int d2 = (nibble >> 2) & 1;
+    int d3 = (nibble >> 1) & 1;
+    int d4 = nibble & 1;
+    int p1 = d1 ^ d2 ^ d4;
+    int p2 = d1 ^ d3 ^ d4;
+    int p3 = d2 ^ d3 ^ d4;
+    return static_cast<uint8_t>((p1 << 6) | (p2 << 5) | (d1 << 4) | (p3 << 3) |
+                                (d2 << 2) | (d3 << 1) | d4);
+}
+
+static uint8_t DecodeCodeword(uint8_t codeword, bool& corrected) {
+    auto bit = [&codeword](int pos) { return (codeword >> (7 - pos)) & 1; };
+    int syndrome = (bit(1) ^ bit(3) ^ bit(5) ^ bit(7)) |
+                   ((bit(2) ^ bit(3) ^ bit(6) ^ bit(7)) << 1) |
+                   ((bit(4) ^ bit(5) ^ bit(6) ^ bit(7)) << 2);
+    corrected = (syndrome != 0);
+    if (corrected) {
+        codeword = static_cast<uint8_t>(codeword ^ (1 << (7 - syndrome)));
+    }
+    return static_cast<uint8_t>((bit(3) << 3) | (bit(5) << 2) | (bit(6) << 1) |
+                                bit(7));
+}
+
+std::size_t HammingEncodedSize(std::size_t size) {
+    return (size * kHammingBitsPerByte + 7) / 8;
+}
+
+/* codewords are packed back to back, most significant bit first */
+std::vector<uint8_t> HammingEncode(const std::vector<uint8_t>& data) {
+    std::vector<uint8_t> coded(HammingEncodedSize(data.size()), 0);
+    std::size_t pos = 0;
+    for (uint8_t byte : data) {
+        for (int nibble : {byte >> 4, byte & 0x0F}) {
+            uint8_t codeword = EncodeNibble(static_cast<uint8_t>(nibble));
+            for (int i = kCodewordBits - 1; i >= 0; --i, ++pos) {
+                if ((codeword >> i) & 1) {
+                    SetBit(coded, pos);
+                }
+            }
+        }
+    }
+    return coded;
+}
+
+std::vector<uint8_t> HammingDecode(const std::vector<uint8_t>& coded,
+                                   std::size_t size, std::size_t& corrected) {
+    std::vector<uint8_t> data(size, 0);
+    corrected = 0;
+    std::size_t pos = 0;
+    for (uint8_t& byte : data) {
+        for (int half = 0; half < kCodewordsPerByte; ++half) {
+            uint8_t codeword = 0;
+            for (int i = 0; i < kCodewordBits; ++i, ++pos) {
+                int bit = (pos / 8 < coded.size()) ? GetBit(coded, pos) : 0;
+                codeword = static_cast<uint8_t>((codeword << 1) | bit);
+            }
+            bool fixed = false;
+            byte = static_cast<uint8_t>((byte << 4) |
+                                        DecodeCodeword(codeword, fixed));
+            corrected += fixed ? 1 : 0;
+        }
+    }
+    return data;
+}
+
+/* the last row may be short, its missing bits are skipped when reading the
+ * columns rather than padded */
+std::size_t InterleavedIndex(std::size_t i, std::size_t n, std::size_t depth) {
+    std::size_t full_rows = n / depth;
+    std::size_t long_columns = n % depth;
+    std::size_t row = i / depth;
+    std::size_t col = i % depth;
+    return col * full_rows + std::min(col, long_columns) + row;
+}
+
+std::vector<uint8_t> Interleave(const std::vector<uint8_t>& data,
+                                std::size_t depth) {
+    std::size_t n = data.size() * 8;
+    std::vector<uint8_t> out(data.size(), 0);
+    for (std::size_t i = 0; i < n; ++i) {
+        if (GetBit(data, i)) {
+            SetBit(out, InterleavedIndex(i, n, depth));
+        }
+    }
+    return out;
+}
+
+std::vector<uint8_t> Deinterleave(const std::vector<uint8_t>& data,
+                                  std::size_t depth) {
+    std::size_t n = data.size() * 8;
+    std::vector<uint8_t> out(data.size(), 0);
+    for (std::size_t i = 0; i < n; ++i) {
+        if (GetBit(data, InterleavedIndex(i, n, depth))) {
+            SetBit(out, i);
+        }
+    }
+    return out;
+}
+
+}  // namespace steganography
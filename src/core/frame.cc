@@ -0,0 +1,648 @@
+#include "core/frame.hpp"
+
+#include <algorithm>
+#include <cstddef>
+#include <cstdint>
+#include <string>
+#include <utility>
+#include <vector>
+
+#include "core/bytes.hpp"
+#include "core/carrier.hpp"
+#include "core/crc32.hpp"
+#include "core/ecc.hpp"
+
+namespace steganography {
+
+/* current header: magic, version, flags, method, header size, length */
+static const uint32_t kPayloadMagic = 0x53544746; /* "STGF" */
+
+/* version 0 header written before the format was versioned: magic, length */
+static const uint32_t kLegacyPayloadMagic = 0x53544547; /* "STEG" */
+static const std::size_t kLegacyPayloadHeaderSize = 8;
+
+/* version 1 readers know nothing of the flags introduced by version 2 */
+static const uint8_t kVersion2Flags = kFlagChecksums | kFlagHamming |
+                                      kFlagInterleaved | kFlagCopies |
+                                      kFlagSync;
+
+/* nor do version 2 readers know of the flags introduced by version 3 */
+static const uint8_t kVersion3Flags = kFlagPipeline;
+
+/* and so on for version 4 */
+static const uint8_t kVersion4Flags = kFlagContentType;
+
+/* the header version a reader needs to understand the flags */
+static uint8_t FlagsVersion(uint8_t flags) {
+    if (flags & kVersion4Flags) {
+        return 4;
+    }
+    if (flags & kVersion3Flags) {
+        return 3;
+    }
+    return (flags & kVersion2Flags) ? 2 : 1;
+}
+
+/* appended to the header with kFlagCopies: copy count, keyed placement, and
+ * the CRC32 of the header up to here */
+static const std::size_t kCopiesFieldsSize = 6;
+static const std::size_t kCopiesHeaderSize =
+    kPayloadHeaderSize + kCopiesFieldsSize;
+
+/* with kFlagSync the stored body restarts every kSyncInterval cells with a
+ * marker: magic, segment index, flags, payload length, and their CRC32 */
+static const uint32_t kSyncMagic = 0x53594E43; /* "SYNC" */
+static const std::size_t kSyncInterval = 1024;
+static const std::size_t kSyncMarkerSize = 17;
+static const std::size_t kSyncDataSize = kSyncInterval - kSyncMarkerSize;
+
+std::vector<uint8_t> ReadCells(const Carrier& carrier, std::size_t& cell,
+                               std::size_t count) {
+    std::vector<uint8_t> bytes(count, 0);
+    for (uint8_t& byte : bytes) {
+        byte = carrier.ReadCell(cell++);
+    }
+    return bytes;
+}
+
+/* bytes of payload and checksums, before any error correction */
+static std::size_t ChecksummedSize(std::size_t length, uint8_t flags) {
+    if (flags & kFlagChecksums) {
+        std::size_t blocks =
+            (length + kChecksumBlockSize - 1) / kChecksumBlockSize;
+        return length + blocks * kChecksumSize;
+    }
+    return length;
+}
+
+/* stored body bytes once a sync marker precedes every kSyncDataSize bytes */
+static std::size_t SyncedSize(std::size_t size) {
+    return size + (size + kSyncDataSize - 1) / kSyncDataSize * kSyncMarkerSize;
+}
+
+/* position of an encoded body byte once the sync markers are inserted */
+static std::size_t SyncedIndex(std::size_t pos) {
+    return pos + (pos / kSyncDataSize + 1) * kSyncMarkerSize;
+}
+
+std::size_t FrameBodySize(std::size_t length, uint8_t flags) {
+    std::size_t size = ChecksummedSize(length, flags);
+    if (flags & kFlagHamming) {
+        size = HammingEncodedSize(size);
+    }
+    return (flags & kFlagSync) ? SyncedSize(size) : size;
+}
+
+static std::vector<uint8_t> SyncMarker(uint32_t index, uint8_t flags,
+                                       uint32_t length) {
+    std::vector<uint8_t> marker;
+    AppendInt<uint32_t>(marker, kSyncMagic);
+    AppendInt<uint32_t>(marker, index);
+    AppendInt<uint8_t>(marker, flags);
+    AppendInt<uint32_t>(marker, length);
+    AppendInt<uint32_t>(marker, Crc32(marker.data(), marker.size()));
+    return marker;
+}
+
+static std::vector<uint8_t> AddSyncMarkers(const std::vector<uint8_t>& body,
+                                           uint8_t flags, uint32_t length) {
+    std::vector<uint8_t> synced;
+    synced.reserve(SyncedSize(body.size()));
+    for (std::size_t offset = 0; offset < body.size();
+         offset += kSyncDataSize) {
+        std::vector<uint8_t> marker = SyncMarker(
+            static_cast<uint32_t>(offset / kSyncDataSize), flags, length);
+        synced.insert(synced.end(), marker.cbegin(), marker.cend());
+        synced.insert(synced.end(),
+                      body.cbegin() + static_cast<std::ptrdiff_t>(offset),
+                      body.cbegin() + static_cast<std::ptrdiff_t>(std::min(
+                                          offset + kSyncDataSize, body.size())));
+    }
+    return synced;
+}
+
+static std::vector<uint8_t> RemoveSyncMarkers(
+    const std::vector<uint8_t>& synced) {
+    std::vector<uint8_t> body;
+    for (std::size_t offset = 0; offset < synced.size();
+         offset += kSyncInterval) {
+        std::size_t begin = std::min(offset + kSyncMarkerSize, synced.size());
+        std::size_t end = std::min(offset + kSyncInterval, synced.size());
+        body.insert(body.end(),
+                    synced.cbegin() + static_cast<std::ptrdiff_t>(begin),
+                    synced.cbegin() + static_cast<std::ptrdiff_t>(end));
+    }
+    return body;
+}
+
+/* first and last stored body byte holding bits of the checksummed bytes in
+ * [begin, end), interleaving scatters them over most of the body */
+static std::pair<std::size_t, std::size_t> StoredRange(std::size_t begin,
+                                                       std::size_t end,
+                                                       std::size_t stored_size,
+                                                       uint8_t flags) {
+    std::size_t first_bit = begin * 8;
+    std::size_t last_bit = end * 8 - 1;
+    if (flags & kFlagHamming) {
+        first_bit = begin * kHammingBitsPerByte;
+        last_bit = end * kHammingBitsPerByte - 1;
+    }
+    if (!(flags & kFlagInterleaved)) {
+        return {first_bit / 8, last_bit / 8};
+    }
+    std::size_t n = stored_size * 8;
+    std::size_t first = n;
+    std::size_t last = 0;
+    for (std::size_t bit = first_bit; bit <= last_bit; ++bit) {
+        std::size_t pos = InterleavedIndex(bit, n, kInterleaveDepth);
+        first = std::min(first, pos);
+        last = std::max(last, pos);
+    }
+    return {first / 8, last / 8};
+}
+
+/* every block is followed by the CRC32 of its bytes, the result is then
+ * error correction coded and interleaved */
+static std::vector<uint8_t> EncodeBody(const std::vector<uint8_t>& payload,
+                                       uint8_t flags) {
+    std::vector<uint8_t> body;
+    if (flags & kFlagChecksums) {
+        for (std::size_t offset = 0; offset < payload.size();
+             offset += kChecksumBlockSize) {
+            std::size_t len =
+                std::min(kChecksumBlockSize, payload.size() - offset);
+            body.insert(body.end(),
+                        payload.cbegin() + static_cast<std::ptrdiff_t>(offset),
+                        payload.cbegin() +
+                            static_cast<std::ptrdiff_t>(offset + len));
+            AppendInt<uint32_t>(body, Crc32(payload.data() + offset, len));
+        }
+    } else {
+        body = payload;
+    }
+    if (flags & kFlagHamming) {
+        body = HammingEncode(body);
+    }
+    return (flags & kFlagInterleaved) ? Interleave(body, kInterleaveDepth)
+                                      : body;
+}
+
+/* undoes EncodeBody on the body read at first_cell, blocks that fail
+ * verification are recorded along with the rows they were read from */
+static std::vector<uint8_t> DecodeBody(const Carrier& carrier,
+                                       const std::vector<uint8_t>& stored,
+                                       std::ptrdiff_t first_cell,
+                                       std::size_t length, uint8_t flags,
+                                       FrameDamage& damage) {
+    /* cells cropped off the container are reported as its first or last row */
+    const std::ptrdiff_t kLastCell =
+        static_cast<std::ptrdiff_t>(carrier.CellCount()) - 1;
+    auto stored_row = [&](std::size_t pos) {
+        if (flags & kFlagSync) {
+            pos = SyncedIndex(pos);
+        }
+        std::ptrdiff_t cell = first_cell + static_cast<std::ptrdiff_t>(pos);
+        return carrier.CellRow(static_cast<std::size_t>(
+            std::clamp<std::ptrdiff_t>(cell, 0, kLastCell)));
+    };
+    std::vector<uint8_t> body = (flags & kFlagInterleaved)
+                                    ? Deinterleave(stored, kInterleaveDepth)
+                                    : stored;
+    if (flags & kFlagHamming) {
+        body = HammingDecode(body, ChecksummedSize(length, flags),
+                             damage.corrected_bits);
+    }
+    if (!(flags & kFlagChecksums)) {
+        return body;
+    }
+    std::vector<uint8_t> payload;
+    payload.reserve(length);
+    std::size_t pos = 0;
+    for (std::size_t offset = 0; offset < length;
+         offset += kChecksumBlockSize) {
+        std::size_t len = std::min(kChecksumBlockSize, length - offset);
+        uint32_t expected = ReadInt<uint32_t>(body, pos + len);
+        if (Crc32(body.data() + pos, len) != expected) {
+            DamagedBlock block;
+            block.offset = offset;
+            block.length = len;
+            auto [first, last] = StoredRange(pos, pos + len + kChecksumSize,
+                                             stored.size(), flags);
+            block.first_row = stored_row(first);
+            block.last_row = stored_row(last);
+            damage.blocks.push_back(block);
+        }
+        payload.insert(payload.end(),
+                       body.cbegin() + static_cast<std::ptrdiff_t>(pos),
+                       body.cbegin() + static_cast<std::ptrdiff_t>(pos + len));
+        pos += len + kChecksumSize;
+    }
+    return payload;
+}
+
+std::size_t PayloadCapacity(const Carrier& carrier) {
+    std::size_t total = carrier.CellCount();
+    return (total > kPayloadHeaderSize) ? (total - kPayloadHeaderSize) : 0;
+}
+
+std::size_t PayloadCells(std::size_t length, uint8_t flags) {
+    if (flags & kFlagSync) {
+        flags |= kFlagChecksums;
+    }
+    return kPayloadHeaderSize + FrameBodySize(length, flags);
+}
+
+/* the first copy always starts at cell 0 where readers look for the header,
+ * the others start somewhere in their own equal share of the cells */
+static std::vector<std::size_t> CopyOffsets(std::size_t capacity,
+                                            std::size_t size, int copies,
+                                            CopyPlacement* placement) {
+    std::size_t slot = capacity / static_cast<std::size_t>(copies);
+    std::vector<std::size_t> offsets = {0};
+    if (!placement) {
+        for (int i = 1; i < copies; ++i) {
+            offsets.push_back(static_cast<std::size_t>(i) * slot);
+        }
+        return offsets;
+    }
+    for (int i = 1; i < copies; ++i) {
+        offsets.push_back(static_cast<std::size_t>(i) * slot +
+                          placement->NextBelow(slot - size + 1));
+    }
+    return offsets;
+}
+
+/* each bit takes the value held by most copies, ties go to the first copy */
+static std::vector<uint8_t> VoteCopies(
+    const std::vector<std::vector<uint8_t>>& copies, std::size_t& outvoted) {
+    std::vector<uint8_t> voted = copies.front();
+    for (std::size_t i = 0; i < voted.size(); ++i) {
+        for (int bit = 0; bit < 8; ++bit) {
+            std::size_t ones = 0;
+            for (const auto& copy : copies) {
+                ones += (copy[i] >> bit) & 1;
+            }
+            bool one = (ones * 2 == copies.size()) ? ((voted[i] >> bit) & 1)
+                                                   : (ones * 2 > copies.size());
+            std::size_t agree = one ? ones : copies.size() - ones;
+            outvoted += copies.size() - agree;
+            voted[i] = static_cast<uint8_t>(one ? (voted[i] | (1 << bit))
+                                                : (voted[i] & ~(1 << bit)));
+        }
+    }
+    return voted;
+}
+
+void AppendFrameHeader(std::vector<uint8_t>& framed, uint8_t flags,
+                       std::size_t header_size, uint32_t length) {
+    AppendInt<uint32_t>(framed, kPayloadMagic);
+    AppendInt<uint8_t>(framed, FlagsVersion(flags));
+    AppendInt<uint8_t>(framed, flags);
+    AppendInt<uint8_t>(framed,
+                       static_cast<uint8_t>(EmbedMethodId::kSequentialLsb));
+    AppendInt<uint8_t>(framed, static_cast<uint8_t>(header_size));
+    AppendInt<uint32_t>(framed, length);
+}
+
+RetCode EmbedFrame(Carrier& carrier, const std::vector<uint8_t>& payload,
+                   const FrameConfig& config) {
+    if ((config.copies < 1) || (config.copies > kMaxCopies) ||
+        (config.stages.size() > kMaxPipelineStages) ||
+        (config.content_type.size() > kMaxContentTypeSize) ||
+        (config.filename.size() > kMaxContentTypeSize)) {
+        return RetCode::kInvalidArgument;
+    }
+    uint8_t flags = config.flags;
+    if (!config.content_type.empty() || !config.filename.empty()) {
+        /* in front of the stage record, and like it protected as payload */
+        flags |= kFlagContentType;
+        std::vector<uint8_t> described;
+        described.reserve(2 + config.content_type.size() +
+                          config.filename.size() + payload.size());
+        for (const std::string* field : {&config.content_type,
+                                         &config.filename}) {
+            AppendInt<uint8_t>(described, static_cast<uint8_t>(field->size()));
+            described.insert(described.end(), field->cbegin(), field->cend());
+        }
+        FrameConfig framed_config = config;
+        framed_config.flags = flags;
+        framed_config.content_type.clear();
+        framed_config.filename.clear();
+        if (!config.stages.empty()) {
+            /* the stage record has to follow, so it is framed here */
+            framed_config.flags |= kFlagPipeline;
+            AppendInt<uint8_t>(described,
+                               static_cast<uint8_t>(config.stages.size()));
+            described.insert(described.end(), config.stages.cbegin(),
+                             config.stages.cend());
+            framed_config.stages.clear();
+        }
+        described.insert(described.end(), payload.cbegin(), payload.cend());
+        return EmbedFrame(carrier, described, framed_config);
+    }
+    if (!config.stages.empty()) {
+        /* the stage record goes through the same checksums and error
+         * correction as the payload it describes */
+        flags |= kFlagPipeline;
+        std::vector<uint8_t> staged;
+        staged.reserve(1 + config.stages.size() + payload.size());
+        AppendInt<uint8_t>(staged, static_cast<uint8_t>(config.stages.size()));
+        staged.insert(staged.end(), config.stages.cbegin(),
+                      config.stages.cend());
+        staged.insert(staged.end(), payload.cbegin(), payload.cend());
+        FrameConfig framed_config = config;
+        framed_config.flags = flags;
+        framed_config.stages.clear();
+        return EmbedFrame(carrier, staged, framed_config);
+    }
+    if (flags & kFlagSync) {
+        if (config.copies > 1) {
+            return RetCode::kInvalidArgument;
+        }
+        flags |= kFlagChecksums; /* so cropped rows show up as damage */
+    }
+    std::size_t header_size = kPayloadHeaderSize;
+    if (config.copies > 1) {
+        flags |= kFlagCopies;
+        header_size = kCopiesHeaderSize;
+    }
+
+    /* frame the payload with the oldest version of the header able to
+     * describe it so older readers keep working where they can */
+    std::vector<uint8_t> framed;
+    framed.reserve(header_size + FrameBodySize(payload.size(), flags));
+    AppendFrameHeader(framed, flags, header_size,
+                 static_cast<uint32_t>(payload.size()));
+    if (flags & kFlagCopies) {
+        AppendInt<uint8_t>(framed, static_cast<uint8_t>(config.copies));
+        AppendInt<uint8_t>(framed, config.placement ? 1 : 0);
+        AppendInt<uint32_t>(framed, Crc32(framed.data(), framed.size()));
+    }
+    std::vector<uint8_t> body = EncodeBody(payload, flags);
+    if (flags & kFlagSync) {
+        body = AddSyncMarkers(body, flags,
+                              static_cast<uint32_t>(payload.size()));
+    }
+    framed.insert(framed.end(), body.cbegin(), body.cend());
+
+    std::size_t capacity = carrier.CellCount();
+    if (flags & kFlagCopies) {
+        capacity -= std::min(capacity, header_size); /* the backup header */
+    }
+    if (framed.size() > capacity / static_cast<std::size_t>(config.copies)) {
+        return RetCode::kInsufficientCapacity;
+    }
+
+    /* the backup header sits in the cells following the copies' share */
+    if (flags & kFlagCopies) {
+        for (std::size_t cell = 0; cell < header_size; ++cell) {
+            carrier.WriteCell(capacity + cell, framed[cell]);
+        }
+    }
+
+    /* write each copy of the framed payload into consecutive cells */
+    for (std::size_t offset :
+         CopyOffsets(capacity, framed.size(), config.copies,
+                     config.placement)) {
+        for (std::size_t cell = 0; cell < framed.size(); ++cell) {
+            carrier.WriteCell(offset + cell, framed[cell]);
+        }
+    }
+    return RetCode::kSuccess;
+}
+
+RetCode ReadFrameHeader(const Carrier& carrier, std::size_t cell,
+                        PayloadHeader& header, std::size_t& header_size) {
+    std::size_t first_cell = cell;
+    uint32_t magic = ReadInt<uint32_t>(ReadCells(carrier, cell, 4), 0);
+    if (magic == kLegacyPayloadMagic) {
+        /* version 0 payloads carry nothing but their length */
+        header = PayloadHeader{};
+        header.version = 0;
+        header.length = ReadInt<uint32_t>(ReadCells(carrier, cell, 4), 0);
+        header_size = kLegacyPayloadHeaderSize;
+        return RetCode::kSuccess;
+    }
+    if (magic != kPayloadMagic) {
+        return RetCode::kPayloadNotFound;
+    }
+
+    header = PayloadHeader{};
+    std::vector<uint8_t> fields = ReadCells(carrier, cell, 8);
+    header.version = fields[0];
+    header.flags = fields[1];
+    header.method = static_cast<EmbedMethodId>(fields[2]);
+    header_size = fields[3];
+    header.length = ReadInt<uint32_t>(fields, 4);
+    /* these bits were reserved and meant nothing back then */
+    if (header.version < 2) {
+        header.flags &= static_cast<uint8_t>(~kVersion2Flags);
+    }
+    if (header.version < 3) {
+        header.flags &= static_cast<uint8_t>(~kVersion3Flags);
+    }
+    if (header.version < 4) {
+        header.flags &= static_cast<uint8_t>(~kVersion4Flags);
+    }
+
+    /* a newer version may give the flags and body new meanings, so it is
+     * refused, while a header longer than the fields read here is skipped
+     * through header_size */
+    if ((header.version > kFormatVersion) ||
+        (header.method != EmbedMethodId::kSequentialLsb)) {
+        return RetCode::kUnsupportedFormat;
+    }
+    if (header_size < kPayloadHeaderSize) {
+        return RetCode::kInvalidPayload;
+    }
+    if (header.flags & kFlagCopies) {
+        if (header_size < kCopiesHeaderSize) {
+            return RetCode::kInvalidPayload;
+        }
+        std::vector<uint8_t> copies = ReadCells(carrier, cell, kCopiesFieldsSize);
+        std::size_t checked_cell = first_cell;
+        std::vector<uint8_t> checked =
+            ReadCells(carrier, checked_cell, kCopiesHeaderSize - kChecksumSize);
+        if (Crc32(checked.data(), checked.size()) !=
+            ReadInt<uint32_t>(copies, 2)) {
+            return RetCode::kInvalidPayload;
+        }
+        header.copies = copies[0];
+        header.keyed_copies = (copies[1] != 0);
+        if (header.copies < 1) {
+            return RetCode::kInvalidPayload;
+        }
+    }
+    return RetCode::kSuccess;
+}
+
+/* scans for the first intact sync marker, start is where the framed payload
+ * begins in this container, negative when rows above it were cropped */
+static bool FindSync(const Carrier& carrier, PayloadHeader& header,
+                     std::ptrdiff_t& start) {
+    const std::size_t kCells = carrier.CellCount();
+    const uint8_t kFirstByte = static_cast<uint8_t>(kSyncMagic >> 24);
+    for (std::size_t cell = 0; cell + kSyncMarkerSize <= kCells; ++cell) {
+        if (carrier.ReadCell(cell) != kFirstByte) {
+            continue;
+        }
+        std::size_t next = cell;
+        std::vector<uint8_t> marker = ReadCells(carrier, next, kSyncMarkerSize);
+        std::size_t checked = kSyncMarkerSize - kChecksumSize;
+        if ((ReadInt<uint32_t>(marker, 0) != kSyncMagic) ||
+            (Crc32(marker.data(), checked) !=
+             ReadInt<uint32_t>(marker, checked))) {
+            continue;
+        }
+        uint8_t flags = marker[8];
+        if (!(flags & kFlagSync) || (flags & kFlagCopies)) {
+            continue;
+        }
+        header = PayloadHeader{};
+        header.version = FlagsVersion(flags);
+        header.flags = flags;
+        header.length = ReadInt<uint32_t>(marker, 9);
+        std::size_t index = ReadInt<uint32_t>(marker, 4);
+        start = static_cast<std::ptrdiff_t>(cell) -
+                static_cast<std::ptrdiff_t>(kPayloadHeaderSize +
+                                            index * kSyncInterval);
+        return true;
+    }
+    return false;
+}
+
+/* reads count cells from start on, cells outside the container read as zero */
+static std::vector<uint8_t> ReadShifted(const Carrier& carrier,
+                                        std::ptrdiff_t start,
+                                        std::size_t count) {
+    const std::ptrdiff_t kCells =
+        static_cast<std::ptrdiff_t>(carrier.CellCount());
+    std::vector<uint8_t> bytes(count, 0);
+    for (std::size_t i = 0; i < count; ++i) {
+        std::ptrdiff_t cell = start + static_cast<std::ptrdiff_t>(i);
+        if ((cell >= 0) && (cell < kCells)) {
+            bytes[i] = carrier.ReadCell(static_cast<std::size_t>(cell));
+        }
+    }
+    return bytes;
+}
+
+
+RetCode ExtractFrame(const Carrier& carrier, std::vector<uint8_t>& payload,
+                     PayloadHeader& header, FrameDamage& damage,
+                     CopyPlacement* placement) {
+    const std::size_t kCapacity = carrier.CellCount();
+    if (kCapacity < kPayloadHeaderSize) {
+        return RetCode::kPayloadNotFound;
+    }
+
+    /* with copies a damaged header falls back to the backup in the last
+     * cells, which only counts if it vouches for the copies itself */
+    std::size_t header_size = 0;
+    RetCode rc = ReadFrameHeader(carrier, 0, header, header_size);
+    if ((rc != RetCode::kSuccess) && (kCapacity >= kCopiesHeaderSize)) {
+        PayloadHeader backup;
+        std::size_t backup_size = 0;
+        if ((ReadFrameHeader(carrier, kCapacity - kCopiesHeaderSize, backup,
+                        backup_size) == RetCode::kSuccess) &&
+            (backup.flags & kFlagCopies)) {
+            rc = RetCode::kSuccess;
+            header = backup;
+            header_size = backup_size;
+        }
+    }
+
+    /* rows cropped or added above the payload move it away from cell 0,
+     * its sync markers tell where it went */
+    std::ptrdiff_t start = 0;
+    bool synced = ((rc != RetCode::kSuccess) || (header.flags & kFlagSync)) &&
+                  FindSync(carrier, header, start);
+    if (synced) {
+        rc = RetCode::kSuccess;
+        header_size = kPayloadHeaderSize;
+    }
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    if (header.keyed_copies && !placement) {
+        return RetCode::kInvalidArgument; /* needs the key */
+    }
+
+    std::size_t capacity = kCapacity;
+    if (header.flags & kFlagCopies) {
+        capacity -= std::min(capacity, header_size); /* the backup header */
+    }
+    std::size_t body_size = FrameBodySize(header.length, header.flags);
+    std::size_t framed_size = header_size + body_size;
+    if (synced) {
+        /* at most half of a synced payload may have been cropped away */
+        if ((header.length > 2 * kCapacity) ||
+            (framed_size > 2 * kCapacity)) {
+            return RetCode::kInvalidPayload;
+        }
+    } else if ((header_size > capacity) || (header.length > capacity) ||
+               (body_size > capacity - header_size) ||
+               (framed_size > capacity / header.copies)) {
+        return RetCode::kInvalidPayload;
+    }
+
+    /* copies hold the whole framed payload, the header included */
+    std::vector<std::vector<uint8_t>> copies;
+    if (synced) {
+        copies.push_back(ReadShifted(carrier, start, framed_size));
+    } else {
+        for (std::size_t offset :
+             CopyOffsets(capacity, framed_size, header.copies,
+                         header.keyed_copies ? placement : nullptr)) {
+            copies.push_back(ReadCells(carrier, offset, framed_size));
+        }
+    }
+    damage = FrameDamage{};
+    if (start != 0) {
+        damage.resynced = true;
+        damage.shift_cells = start;
+        if (carrier.CellsPerRow() > 0.0) {
+            damage.shift_rows =
+                static_cast<double>(start) / carrier.CellsPerRow();
+        }
+    }
+    std::vector<uint8_t> framed = VoteCopies(copies, damage.outvoted_bits);
+    std::vector<uint8_t> body(
+        framed.cbegin() + static_cast<std::ptrdiff_t>(header_size),
+        framed.cend());
+    if (header.flags & kFlagSync) {
+        body = RemoveSyncMarkers(body);
+    }
+    payload = DecodeBody(carrier, body,
+                         start + static_cast<std::ptrdiff_t>(header_size),
+                         header.length, header.flags, damage);
+    if (header.flags & kFlagContentType) {
+        std::size_t pos = 0;
+        for (std::string* field : {&header.content_type, &header.filename}) {
+            if ((pos >= payload.size()) ||
+                (payload[pos] >= payload.size() - pos)) {
+                return RetCode::kInvalidPayload;
+            }
+            auto first =
+                payload.cbegin() + static_cast<std::ptrdiff_t>(pos + 1);
+            field->assign(first, first + payload[pos]);
+            pos += 1 + payload[pos];
+        }
+        payload.erase(payload.cbegin(),
+                      payload.cbegin() + static_cast<std::ptrdiff_t>(pos));
+    }
+    if (header.flags & kFlagPipeline) {
+        if (payload.empty() || (payload[0] >= payload.size())) {
+            return RetCode::kInvalidPayload;
+        }
+        auto first = payload.cbegin() + 1;
+        auto last = first + payload[0];
+        header.stages.assign(first, last);
+        payload.erase(payload.cbegin(), last);
+    }
+    return damage.blocks.empty() ? RetCode::kSuccess
+                                 : RetCode::kPayloadDamaged;
+}
+
+
+}  // namespace steganography
@@ -0,0 +1,69 @@
+#include "core/lsb.hpp"
+
+#include <algorithm>
+#include <array>
+#include <cstddef>
+#include <cstdint>
+
+namespace steganography {
+
+/* mask selecting the high bits of a channel */
+static int HighBits(int bits) { return (0xFF << (8 - bits)) & 0xFF; }
+
+/* the row kernels work through fixed size lanes, a 256 bit register's
+ * worth, and finish the remainder a byte at a time. Each lane is computed
+ * into a local array so the compiler needs no proof that the rows don't
+ * overlap before turning it into a few vector instructions. */
+static const std::size_t kLaneBytes = 32;
+
+void MergeRow(uint8_t* cover, const uint8_t* secret, std::size_t bytes,
+              int bits) {
+    const uint8_t kKeep = static_cast<uint8_t>(HighBits(8 - bits));
+    const int kShift = 8 - bits;
+    std::size_t i = 0;
+    for (; i + kLaneBytes <= bytes; i += kLaneBytes) {
+        std::array<uint8_t, kLaneBytes> lane;
+        for (std::size_t j = 0; j < kLaneBytes; ++j) {
+            lane[j] = static_cast<uint8_t>((cover[i + j] & kKeep) |
+                                           (secret[i + j] >> kShift));
+        }
+        std::copy(lane.cbegin(), lane.cend(), cover + i);
+    }
+    for (; i < bytes; ++i) {
+        cover[i] =
+            static_cast<uint8_t>((cover[i] & kKeep) | (secret[i] >> kShift));
+    }
+}
+
+void ClearLowBits(uint8_t* cover, std::size_t bytes, int bits) {
+    const uint8_t kKeep = static_cast<uint8_t>(HighBits(8 - bits));
+    std::size_t i = 0;
+    for (; i + kLaneBytes <= bytes; i += kLaneBytes) {
+        for (std::size_t j = i; j < i + kLaneBytes; ++j) {
+            cover[j] &= kKeep;
+        }
+    }
+    for (; i < bytes; ++i) {
+        cover[i] &= kKeep;
+    }
+}
+
+void UnmergeRow(const uint8_t* pixels, uint8_t* unmerged, std::size_t bytes,
+                int bits) {
+    const uint8_t kLowBits = static_cast<uint8_t>(0xFF >> (8 - bits));
+    const int kShift = 8 - bits;
+    std::size_t i = 0;
+    for (; i + kLaneBytes <= bytes; i += kLaneBytes) {
+        std::array<uint8_t, kLaneBytes> lane;
+        for (std::size_t j = 0; j < kLaneBytes; ++j) {
+            lane[j] =
+                static_cast<uint8_t>((pixels[i + j] & kLowBits) << kShift);
+        }
+        std::copy(lane.cbegin(), lane.cend(), unmerged + i);
+    }
+    for (; i < bytes; ++i) {
+        unmerged[i] = static_cast<uint8_t>((pixels[i] & kLowBits) << kShift);
+    }
+}
+
+}  // namespace steganography
@@ -1,25 +1,806 @@
+#include <unistd.h>
+
+#include <algorithm>
+#include <array>
+#include <cctype>
+#include <cmath>
+#include <cstddef>
+#include <cstdint>
 #include <cstdlib>
+#include <filesystem>
+#include <fstream>
+#include <iomanip>
 #include <iostream>
+#include <limits>
+#include <map>
+#include <optional>
+#include <random>
+#include <set>
+#include <sstream>
+#include <stdexcept>
 #include <string>
+#include <system_error>
+#include <utility>
+#include <vector>
 
+#include "utils/archive.hpp"
+#include "utils/attack.hpp"
+#include "utils/batch.hpp"
+#include "utils/channels.hpp"
+#ifdef STEG_WITH_CLIPBOARD
+#include "utils/clipboard.hpp"
+#endif
+#include "utils/compare.hpp"
+#include "utils/cover_gen.hpp"
+#include "utils/cover_rank.hpp"
+#include "utils/deniable.hpp"
+#include "utils/detect.hpp"
+#include "utils/embed_method.hpp"
+#include "utils/heatmap.hpp"
+#include "utils/image_info.hpp"
+#include "utils/image_io.hpp"
+#include "utils/key.hpp"
+#include "utils/nest.hpp"
+#include "utils/paths.hpp"
+#include "utils/pnm.hpp"
+#include "utils/preview.hpp"
+#include "utils/qr.hpp"
+#ifdef STEG_WITH_HTTP
+#include "utils/fetch.hpp"
+#endif
+#ifdef STEG_WITH_S3
+#include "utils/s3.hpp"
+#endif
+#include "utils/sanitize.hpp"
+#include "utils/scan.hpp"
+#include "utils/scrub.hpp"
+#include "utils/seal.hpp"
+#ifdef STEG_WITH_SERVER
+#include "utils/server.hpp"
+#endif
+#include "utils/split.hpp"
 #include "utils/steganography_util.hpp"
+#include "utils/text_image.hpp"
+#include "utils/try_all.hpp"
+#include "utils/tui.hpp"
+#include "utils/verify.hpp"
+#include "utils/visual_attack.hpp"
+#include "utils/watch.hpp"
+
+struct Command {
+    std::size_t min_args;
+    std::size_t max_args;
+    std::set<std::string> options; /* options accepted by the command */
+    std::string roles; /* 'i'nput, 'o'utput, or '-' for a plain value per
+                          positional arg, the last role applies to any
+                          remaining args */
+};
+
+struct Diagnostic {
+    std::string message;
+    std::string path;  /* offending file, empty when it cannot be told */
+    std::string phase; /* operation that was running when the error hit */
+    std::string hint;  /* what the user can do about it */
+};
+
+struct ParsedArgs {
+    std::vector<std::string> positional;
+    std::map<std::string, std::string> options;
+};
 
 void PrintUsage() {
-    std::cout << "usage: steganography CMD IN_IMG... OUT_IMG" << std::endl;
-    std::cout << "\tCMD" << std::endl;
-    std::cout << "\t\tone of 'merge', 'unmerge', or 'help'" << std::endl;
-    std::cout << "\tIN_IMG\n\t\ta jpeg or png image" << std::endl;
+    std::cout << "usage: steganography merge COVER_IMG SECRET_IMG OUT_IMG "
+                 "[--keyfile KEY [--password PW] [--block-bits]]\n"
+                 "            [--stats]"
+                 " [--fit | --secret-size WxH] [--max-shrink F] "
+                 "[--strict] [--linear]\n"
+                 "            [--sharpen AMOUNT] [--color-space SPACE] "
+                 "[--dither] [--output-format FMT]\n"
+                 "            [--threads N] [--memory-budget SIZE] "
+                 "[--method NAME] [--mask MASK_IMG]"
+              << std::endl;
+    std::cout << "       steganography unmerge IN_IMG OUT_IMG "
+                 "[--keyfile KEY [--password PW] [--block-bits]]\n"
+                 "            [--color-space SPACE]"
+                 " [--output-format FMT] [--threads N]\n"
+                 "            [--memory-budget SIZE] [--method NAME]"
+              << std::endl;
+    std::cout << "       steganography unmerge IN_IMG OUT_DIR --try-all "
+                 "[--keyfile KEY [--password PW] [--block-bits]]"
+              << std::endl;
+    std::cout << "       steganography unmerge IN_IMG OUT_IMG --channel r|g|b "
+                 "[--bits N]"
+              << std::endl;
+    std::cout << "       steganography merge-channels COVER_IMG OUT_IMG "
+                 "[--red IMG] [--green IMG] [--blue IMG]\n"
+                 "            [--bits N]"
+              << std::endl;
+    std::cout << "       steganography merge-qr COVER_IMG OUT_IMG TEXT "
+                 "[--keyfile KEY [--password PW]]"
+              << std::endl;
+    std::cout << "       steganography unmerge-qr IN_IMG "
+                 "[--keyfile KEY [--password PW]]"
+              << std::endl;
+    std::cout << "       steganography merge-text COVER_IMG TEXT_FILE OUT_IMG "
+                 "[--keyfile KEY [--password PW]]"
+              << std::endl;
+    std::cout << "       steganography pack COVER_IMG OUT_IMG FILE... "
+                 "[--password PW [--decoy FILE --decoy-password PW]\n"
+                 "            [--seed N]]"
+              << std::endl;
+    std::cout << "       steganography pack COVER_IMG OUT_IMG FILE... "
+                 "--layer-passwords FILE [--seed N]"
+              << std::endl;
+    std::cout << "       steganography pack COVER_IMG OUT_IMG FILE... "
+                 "[--checksums] [--ecc SCHEME] [--interleave]\n"
+                 "            [--copies K [--keyfile KEY] | --sync]\n"
+                 "            [--traversal ORDER] [--stride N] [--border N]"
+              << std::endl;
+    std::cout << "       steganography pack COVER_IMG OUT_IMG FILE... "
+                 "[--differential | --pair-swap | --reversible]\n"
+                 "            [--rotate-channels --keyfile KEY] "
+                 "[--recipient HEX[,HEX...]]\n"
+                 "            [--recipient-passwords FILE]"
+              << std::endl;
+    std::cout << "       steganography unpack IN_IMG OUT_DIR "
+                 "[--password PW | --keyfile KEY | --reference COVER_IMG]\n"
+                 "            [--restore-cover OUT_IMG] [--identity ID] "
+                 "[--recipient-password PW]\n"
+                 "            [--traversal ORDER] [--stride N] [--border N]"
+              << std::endl;
+#ifdef STEG_WITH_CLIPBOARD
+    std::cout << "       steganography pack COVER_IMG OUT_IMG "
+                 "--from-clipboard [--password PW]"
+              << std::endl;
+    std::cout << "       steganography unpack IN_IMG --to-clipboard "
+                 "[--password PW]"
+              << std::endl;
+#endif
+#ifdef STEG_WITH_SALIENCY
+    std::cout << "       steganography pack COVER_IMG OUT_IMG FILE... "
+                 "--avoid-salient [--traversal ORDER]\n"
+                 "            [--stride N] [--border N]"
+              << std::endl;
+    std::cout << "       steganography unpack IN_IMG OUT_DIR --avoid-salient "
+                 "[--traversal ORDER] [--stride N]\n"
+                 "            [--border N]"
+              << std::endl;
+#endif
+    std::cout << "       steganography pack-dir COVER_IMG OUT_IMG DIR "
+                 "[--compress] [--checksums] [--ecc SCHEME] [--interleave]\n"
+                 "            [--copies K [--keyfile KEY] | --sync]\n"
+                 "            [--traversal ORDER] [--stride N] [--border N]\n"
+                 "            [--differential | --pair-swap | --reversible |\n"
+                 "             --rotate-channels --keyfile KEY]\n"
+                 "            [--recipient HEX[,HEX...]] "
+                 "[--recipient-passwords FILE]"
+              << std::endl;
+    std::cout << "       steganography unpack-dir IN_IMG OUT_DIR "
+                 "[--keyfile KEY] [--reference COVER_IMG]\n"
+                 "            [--restore-cover OUT_IMG] [--identity ID] "
+                 "[--recipient-password PW]\n"
+                 "            [--traversal ORDER] [--stride N] [--border N]"
+              << std::endl;
+    std::cout << "       steganography merge-data COVER_IMG OUT_IMG FILE "
+                 "[--type MIME] [--compress] [--checksums]\n"
+                 "            [--ecc SCHEME] [--interleave] "
+                 "[--copies K [--keyfile KEY] | --sync]\n"
+                 "            [--differential | --pair-swap | --reversible |\n"
+                 "             --rotate-channels --keyfile KEY]\n"
+                 "            [--traversal ORDER] [--stride N] [--border N]\n"
+                 "            [--recipient HEX[,HEX...]]\n"
+                 "            [--recipient-passwords FILE]"
+              << std::endl;
+    std::cout << "       steganography unmerge-data IN_IMG OUT [--keyfile KEY] "
+                 "[--reference COVER_IMG]\n"
+                 "            [--restore-cover OUT_IMG] [--identity ID] "
+                 "[--recipient-password PW]\n"
+                 "            [--traversal ORDER] [--stride N] [--border N]"
+              << std::endl;
+    std::cout << "       steganography unmerge-data IN_IMG --inspect "
+                 "[--keyfile KEY] [--identity ID]"
+              << std::endl;
+    std::cout << "       steganography append IN_IMG OUT_IMG FILE... "
+                 "[--keyfile KEY]\n"
+                 "            [--identity ID | --recipient-password PW] "
+                 "[--recipient HEX[,HEX...]]\n"
+                 "            [--recipient-passwords FILE]\n"
+                 "            [--traversal ORDER] [--stride N] [--border N]"
+              << std::endl;
+    std::cout << "       steganography nest COVER_IMG STEGO_IMG OUT_IMG "
+                 "[--output-format FMT]"
+              << std::endl;
+    std::cout << "       steganography unnest IN_IMG OUT_IMG [--levels N]"
+              << std::endl;
+    std::cout << "       steganography split IN_FILE OUT_DIR COVER_IMG... "
+                 "[--with-manifest]"
+              << std::endl;
+    std::cout << "       steganography join OUT_FILE PART_IMG... "
+                 "[--manifest MANIFEST_IMG] [--create-dirs]"
+              << std::endl;
+    std::cout << "       steganography inspect IN_IMG [--password PW] "
+                 "[--keyfile KEY] [--reference COVER_IMG]\n"
+                 "            [--traversal ORDER] [--stride N] [--border N]"
+              << std::endl;
+    std::cout << "       steganography info IN_IMG" << std::endl;
+    std::cout << "       steganography keygen KEY" << std::endl;
+    std::cout << "       steganography keygen-identity ID" << std::endl;
+    std::cout << "       steganography rekey IN_IMG OUT_IMG "
+                 "[--identity ID | --recipient-password PW]\n"
+                 "            [--recipient HEX[,HEX...]] "
+                 "[--recipient-passwords FILE] [--keyfile KEY]\n"
+                 "            [--traversal ORDER] [--stride N] [--border N]"
+              << std::endl;
+    std::cout << "       steganography attack IN_IMG [--jpeg-quality Q] "
+                 "[--scale F] [--noise S]"
+              << std::endl;
+    std::cout << "       steganography detect IN_IMG [--format text|json]"
+              << std::endl;
+    std::cout << "       steganography visual-attack IN_IMG OUT_IMG "
+                 "[--plane N[,N...]] [--channel r|g|b|rgb]"
+              << std::endl;
+    std::cout << "       steganography verify-pair COVER_IMG STEGO_IMG "
+                 "[--bits N]"
+              << std::endl;
+    std::cout << "       steganography compare COVER_IMG STEGO_IMG OUT_IMG "
+                 "[--amplify N]"
+              << std::endl;
+    std::cout << "       steganography scrub IN_IMG OUT_IMG [--bits N] "
+                 "[--seed N] [--output-format FMT]"
+              << std::endl;
+    std::cout << "       steganography seal IN_IMG OUT_IMG --keyfile KEY"
+              << std::endl;
+    std::cout << "       steganography verify-seal IN_IMG [--signer HEX]"
+              << std::endl;
+    std::cout << "       steganography heatmap IN_IMG OUT_IMG [--block N] "
+                 "[--opacity A]"
+              << std::endl;
+    std::cout << "       steganography scan DIR [--recursive] "
+                 "[--threshold T] [--format csv|json]"
+              << std::endl;
+    std::cout << "       steganography sanitize DIR [--out DIR] [--recursive] "
+                 "[--bits N] [--seed N]"
+              << std::endl;
+    std::cout << "       steganography rank-covers DIR [--payload-size SIZE]"
+              << std::endl;
+    std::cout << "       steganography batch MANIFEST RESULTS [--create-dirs] "
+                 "[--threads N] [--fail-fast]\n"
+                 "            [--state FILE] [--max-rate SIZE] "
+                 "[--max-images N] [--low-priority]"
+              << std::endl;
+    std::cout << "       steganography gen-cover OUT_IMG [--size WxH] "
+                 "[--style STYLE] [--seed N]"
+              << std::endl;
+    std::cout << "       steganography preview IN_IMG [--bits N] "
+                 "[--protocol PROTO] [--width COLS]"
+              << std::endl;
+    std::cout << "       steganography watch --in DIR --out DIR [--mode MODE] "
+                 "[--secret SECRET_IMG] [--debounce MS]"
+              << std::endl;
+    std::cout << "       steganography tui [COVER_IMG [SECRET_IMG [OUT_IMG]]]"
+              << std::endl;
+#ifdef STEG_WITH_SERVER
+    std::cout << "       steganography serve [--port PORT] [--bind ADDR] "
+                 "[--max-pixels N] [--max-image-bytes N]\n"
+                 "            [--threads N] [--memory-budget SIZE]"
+              << std::endl;
+#endif
+    std::cout << "       steganography help" << std::endl;
+    std::cout << "\tCOVER_IMG, SECRET_IMG, IN_IMG\n\t\ta jpeg or png image"
+              << std::endl;
     std::cout << "\tOUT_IMG\n\t\ta jpeg or png image containing the result of "
-                 "executing the parameter\n\t\tCMD"
+                 "the command"
+              << std::endl;
+    std::cout << "OPTIONS" << std::endl;
+    std::cout << "\t--keyfile KEY\n\t\tscatter and encrypt the secret using a "
+                 "key created by keygen"
+              << std::endl;
+    std::cout << "\t--password PW\n\t\tmerge/unmerge: combine the key file "
+                 "with a password so both are\n\t\tneeded to unmerge\n\t\t"
+                 "pack/unpack: encrypt the archive and fill unused space with "
+                 "noise"
+              << std::endl;
+    std::cout << "\t--create-dirs\n\t\tcommands writing a file: create "
+                 "missing parent directories of\n\t\tthe output instead of "
+                 "failing"
+              << std::endl;
+    std::cout << "\t--mask MASK_IMG\n\t\tmerge: also write a PNG marking "
+                 "every bit merging changed,\n\t\ta changed bit i of a "
+                 "channel sets bit 7 - i of the mask's"
+              << std::endl;
+    std::cout << "\t--stats\n\t\tmerge: print pixels modified, bits "
+                 "changed per bit plane,\n\t\tbits embedded, capacity "
+                 "utilization, PSNR vs. the cover,\n\t\tthe memory used, "
+                 "and the time and peak memory of each\n\t\tphase"
+              << std::endl;
+    std::cout << "\t--fit\n\t\tmerge: shrink a secret larger than the "
+                 "cover to fit inside it"
+              << std::endl;
+    std::cout << "\t--max-shrink F\n\t\tmerge: warn when --fit or --secret-size "
+                 "shrink the secret by\n\t\tmore than F, default 4"
+              << std::endl;
+    std::cout << "\t--strict\n\t\tmerge: fail instead of warning when the "
+                 "secret shrinks too much\n\t\tor an input is CMYK, deeper "
+                 "than 8 bits, or interlaced"
+              << std::endl;
+    std::cout << "\t--secret-size WxH\n\t\tmerge: resize the secret to "
+                 "WxH and record it so unmerge\n\t\tcrops the rest of the "
+                 "cover off"
+              << std::endl;
+    std::cout << "\t--linear\n\t\tmerge: resize the secret in linear "
+                 "light, keeping high contrast\n\t\tsecrets from darkening"
+              << std::endl;
+    std::cout << "\t--sharpen AMOUNT\n\t\tmerge: sharpen the secret before "
+                 "embedding it, 0.5-1 suits most\n\t\tshrunk secrets"
+              << std::endl;
+    std::cout << "\t--color-space SPACE\n\t\tmerge, unmerge: 'rgb' "
+                 "(default) hides the secret in every\n\t\tchannel, "
+                 "'ycbcr' only in the chroma channels at a\n\t\tlower "
+                 "secret precision, 'hsv' only in the value\n\t\tchannel "
+                 "at a lower precision still"
+              << std::endl;
+    std::cout << "\t--output-format FMT\n\t\tmerge, unmerge, pack, pack-dir, "
+                 "nest, scrub: "
+                 "the encoded format\n\t\tregardless of OUT_IMG's extension, "
+                 "'auto' (default),\n\t\t'png', 'jxl', 'same' as the input "
+                 "image, or 'jpeg' for\n\t\tunmerge and scrub only"
+              << std::endl;
+    std::cout << "\t--threads N\n\t\tmerge, unmerge, serve: worker threads "
+                 "for images merged\n\t\twithout a key file, default 0 for "
+                 "one per core\n\t\tbatch: manifest rows run at once, "
+                 "default 1"
+              << std::endl;
+    std::cout << "\t--fail-fast\n\t\tbatch: start no more rows once one "
+                 "has failed"
+              << std::endl;
+    std::cout << "\t--state FILE\n\t\tbatch: where the rows done are "
+                 "recorded, default RESULTS.state,\n\t\trunning again skips "
+                 "those whose output still matches"
+              << std::endl;
+    std::cout << "\t--max-rate SIZE\n\t\tbatch: read no more than SIZE of "
+                 "covers and secrets a\n\t\tsecond on average, e.g. 20MB"
+              << std::endl;
+    std::cout << "\t--max-images N\n\t\tbatch: start no more than N rows "
+                 "a minute"
+              << std::endl;
+    std::cout << "\t--low-priority\n\t\tbatch: run at the lowest CPU and "
+                 "I/O priority so other work\n\t\tcomes first"
+              << std::endl;
+    std::cout << "\t--memory-budget SIZE\n\t\tmerge, unmerge, serve: "
+                 "refuse images taking more than SIZE\n\t\tto decode and "
+                 "process together, e.g. 512MB, default no\n\t\tbudget"
+              << std::endl;
+    std::cout << "\t--method NAME\n\t\tmerge, unmerge: the embedding "
+                 "algorithm, 'lsb' (default)\n\t\tor one a build has "
+                 "registered, unmerge needs the\n\t\tsame one as merge"
+              << std::endl;
+    std::cout << "\t--block-bits\n\t\tmerge, unmerge: vary the bits per "
+                 "channel between 2 and 4\n\t\tfrom one 16x16 block to the "
+                 "next with the key file,\n\t\tunmerge needs it too"
+              << std::endl;
+    std::cout << "\t--try-all\n\t\tunmerge: extract with every method, "
+                 "color space, and bits\n\t\tvalue into OUT_DIR, plus "
+                 "contact-sheet.png showing\n\t\tthem all as thumbnails"
+              << std::endl;
+    std::cout << "\t--dither\n\t\tmerge: diffuse the error from replacing "
+                 "the low bits so\n\t\tsmooth gradients in the cover don't "
+                 "band, rgb only"
+              << std::endl;
+    std::cout << "\t--decoy FILE\n\t\tfile or directory packed as a decoy "
+                 "alongside the real archive"
+              << std::endl;
+    std::cout << "\t--decoy-password PW\n\t\tpassword revealing only the "
+                 "decoy"
+              << std::endl;
+#ifdef STEG_WITH_CLIPBOARD
+    std::cout << "\t--from-clipboard\n\t\tpack: hide the text in the "
+                 "clipboard instead of FILEs"
+              << std::endl;
+    std::cout << "\t--to-clipboard\n\t\tunpack: copy a message hidden with "
+                 "--from-clipboard to the\n\t\tclipboard instead of writing "
+                 "OUT_DIR"
+              << std::endl;
+#endif
+    std::cout << "\t--compress\n\t\tgzip the directory archive before "
+                 "embedding it"
+              << std::endl;
+    std::cout << "\t--type MIME\n\t\tmerge-data: the MIME type recorded for "
+                 "FILE, guessed from its\n\t\tcontents and extension by "
+                 "default"
+              << std::endl;
+    std::cout << "\t--inspect\n\t\tunmerge-data: print the type, name, and "
+                 "size of the payload\n\t\tinstead of writing it"
+              << std::endl;
+    std::cout << "\t--checksums\n\t\tpack/pack-dir: add a CRC32 to every "
+                 "256 byte block so unpacking\n\t\treports which rows of the "
+                 "image were damaged and still\n\t\twrites the files that "
+                 "survived"
+              << std::endl;
+    std::cout << "\t--ecc SCHEME\n\t\tpack/pack-dir: error correction, "
+                 "'none' (default) or 'hamming' to\n\t\trepair one flipped "
+                 "bit in every 7 at 75% size overhead"
+              << std::endl;
+    std::cout << "\t--interleave\n\t\tpack/pack-dir: spread the payload "
+                 "bits across the image so a\n\t\tdamaged strip becomes "
+                 "isolated bit flips the ECC can repair"
+              << std::endl;
+    std::cout << "\t--copies K\n\t\tpack/pack-dir: embed K complete copies "
+                 "of the payload and take a\n\t\tbit by bit majority vote when "
+                 "unpacking, --keyfile scatters the\n\t\tcopies and is then "
+                 "needed to unpack"
+              << std::endl;
+    std::cout << "\t--sync\n\t\tpack/pack-dir: add sync markers so "
+                 "unpacking finds the payload\n\t\tafter rows were cropped "
+                 "or added, implies --checksums"
+              << std::endl;
+    std::cout << "\t--differential\n\t\tpack/pack-dir: change each channel "
+                 "by at most 1 from the cover,\n\t\tunpacking then needs the "
+                 "untouched cover as --reference"
+              << std::endl;
+    std::cout << "\t--reference COVER_IMG\n\t\tunpack/unpack-dir: the cover "
+                 "a --differential pack was made\n\t\tfrom"
+              << std::endl;
+    std::cout << "\t--pair-swap\n\t\tpack/pack-dir: swap the values of side "
+                 "by side pixels instead of\n\t\toverwriting bits, keeping "
+                 "the exact histogram of the cover at about\n\t\tan eighth "
+                 "of the capacity"
+              << std::endl;
+    std::cout << "\t--reversible\n\t\tpack/pack-dir: embed by histogram "
+                 "shifting so unpacking can\n\t\trestore the exact cover, "
+                 "needs a value each channel never takes"
+              << std::endl;
+    std::cout << "\t--rotate-channels\n\t\tpack/pack-dir: hide data in "
+                 "one channel of each pixel,\n\t\trotating through red, green, "
+                 "and blue in an order drawn from\n\t\tthe --keyfile, at a "
+                 "third of the capacity, unpacking needs the\n\t\tsame key "
+                 "file"
+              << std::endl;
+    std::cout << "\t--traversal ORDER\n\t\tpack/pack-dir: visit the pixels "
+                 "in the order row-major\n\t\t(default), serpentine, "
+                 "column-major, or spiral, unpacking and\n\t\tinspect need "
+                 "the same order"
+              << std::endl;
+    std::cout << "\t--stride N\n\t\tpack/pack-dir: visit every Nth pixel "
+                 "of the --traversal order,\n\t\twrapping around, N must "
+                 "share no factor with the number of\n\t\tpixels visited"
+              << std::endl;
+    std::cout << "\t--border N\n\t\tpack/pack-dir: only hide data in "
+                 "the N pixels nearest each\n\t\tedge, leaving the middle "
+                 "of the image untouched, unpacking\n\t\tneeds the same N"
+              << std::endl;
+#ifdef STEG_WITH_SALIENCY
+    std::cout << "\t--avoid-salient\n\t\tpack/pack-dir: keep the data "
+                 "away from faces, text, and\n\t\tregions that stand out, "
+                 "unpacking finds them again and\n\t\tneeds the option too"
+              << std::endl;
+#endif
+    std::cout << "\t--restore-cover OUT_IMG\n\t\tunpack/unpack-dir: also "
+                 "write the cover of a --reversible\n\t\tpack as it was "
+                 "before packing"
+              << std::endl;
+    std::cout << "\t--recipient HEX[,HEX...]\n\t\tpack/pack-dir: encrypt to "
+                 "the public keys keygen-identity\n\t\tprinted, any one of "
+                 "their identity files can unpack"
+              << std::endl;
+    std::cout << "\t--identity ID\n\t\tunpack/unpack-dir/append/rekey: the "
+                 "identity file of a\n\t\trecipient the image was packed for"
+              << std::endl;
+    std::cout << "\t--recipient-passwords FILE\n\t\tpack/pack-dir/append/"
+                 "rekey: also encrypt to every line of\n\t\tFILE, any one "
+                 "of them can unpack"
+              << std::endl;
+    std::cout << "\t--layer-passwords FILE\n\t\tpack: a line of FILE for "
+                 "each FILE arg, packing each\n\t\tinto a bit plane of "
+                 "its own that only that password\n\t\tunpacks, 1-4 "
+                 "layers, excludes --password"
+              << std::endl;
+    std::cout << "\t--recipient-password PW\n\t\tunpack/unpack-dir/append/"
+                 "rekey: one of the lines of\n\t\tthe --recipient-passwords "
+                 "file the image was packed with"
+              << std::endl;
+    std::cout << "\t--jpeg-quality Q\n\t\tattack: recompress as a JPEG of "
+                 "quality Q (1-100)"
+              << std::endl;
+    std::cout << "\t--scale F\n\t\tattack: downscale by F (0-1] and scale "
+                 "back up"
+              << std::endl;
+    std::cout << "\t--noise S\n\t\tattack: add gaussian noise with "
+                 "standard deviation S"
+              << std::endl;
+    std::cout << "\t--plane N[,N...]\n\t\tvisual-attack: bit planes to "
+                 "render, 0 is the LSB, default 0.\n\t\tSeveral planes are "
+                 "combined most significant first"
+              << std::endl;
+    std::cout << "\t--channel CHANNELS\n\t\tvisual-attack: any of 'r', "
+                 "'g', and 'b', default 'rgb'.\n\t\tA single channel is "
+                 "rendered in grayscale\n\t\tunmerge: the one of 'r', 'g', "
+                 "or 'b' to extract the\n\t\tsecret merge-channels hid in"
+              << std::endl;
+    std::cout << "\t--red IMG, --green IMG, --blue IMG\n\t\tmerge-channels: "
+                 "the secret to hide in each channel,\n\t\tat least one "
+                 "is needed"
+              << std::endl;
+    std::cout << "\t--levels N\n\t\tunnest: levels to unwrap, default 0 "
+                 "unwraps until the\n\t\timage holds no nested image"
+              << std::endl;
+    std::cout << "\t--with-manifest\n\t\tsplit: also write manifest.png "
+                 "listing every part and its\n\t\tSHA-256"
+              << std::endl;
+    std::cout << "\t--manifest MANIFEST_IMG\n\t\tjoin: the manifest split "
+                 "wrote, names missing parts and\n\t\tchecks each part "
+                 "against its hash"
+              << std::endl;
+    std::cout << "\t--amplify N\n\t\tcompare: multiply the difference "
+                 "by N, by default the largest\n\t\tdifference is stretched "
+                 "to white"
+              << std::endl;
+    std::cout << "\t--block N\n\t\theatmap: side of the blocks in pixels, "
+                 "default 32"
+              << std::endl;
+    std::cout << "\t--opacity A\n\t\theatmap: opacity of the heatmap over "
+                 "the image (0-1, default 0.6)"
+              << std::endl;
+    std::cout << "\t--recursive\n\t\tscan, sanitize: include images in "
+                 "subdirectories"
+              << std::endl;
+    std::cout << "\t--threshold T\n\t\tscan: report images detected with "
+                 "a confidence of at least T\n\t\t(0-1, default 0.5)"
+              << std::endl;
+    std::cout << "\t--format FORMAT\n\t\tdetect: 'text' (default) or "
+                 "'json'\n\t\tscan: 'csv' (default) or 'json'"
+              << std::endl;
+    std::cout << "\t--payload-size SIZE\n\t\trank-covers: payload size in "
+                 "bytes, accepts KB, MB, and GB suffixes"
+              << std::endl;
+    std::cout << "\t--size WxH\n\t\tgen-cover: dimensions of the generated "
+                 "cover, default 1920x1080"
+              << std::endl;
+    std::cout << "\t--style STYLE\n\t\tgen-cover: one of 'noise', "
+                 "'gradient', or 'plasma' (default)"
+              << std::endl;
+    std::cout << "\t--seed N\n\t\tgen-cover, scrub, sanitize: seed for "
+                 "reproducible output,\n\t\trandom by default\n\t\tpack: "
+                 "with --password, seed for the salts, nonces, and\n\t\t"
+                 "filler, so the same archive gives the same image"
+              << std::endl;
+    std::cout << "\t--in DIR, --out DIR\n\t\twatch: directory to monitor and "
+                 "directory receiving the results\n\t\tsanitize: directory "
+                 "receiving the sanitized tree instead\n\t\tof rewriting "
+                 "DIR in place"
+              << std::endl;
+    std::cout << "\t--mode MODE\n\t\twatch: 'merge' (default) merges "
+                 "--secret into each new file,\n\t\t'unmerge' extracts the "
+                 "secret from each new file"
+              << std::endl;
+    std::cout << "\t--bits N\n\t\tpreview: low bits per channel holding "
+                 "the hidden image, default 4\n\t\tverify-pair: low bits "
+                 "per channel allowed to differ, default 4\n\t\tscrub: low "
+                 "bits per channel to overwrite, default 4\n\t\tsanitize: "
+                 "the same for every image\n\t\tmerge-channels, unmerge "
+                 "--channel: low bits of the channel\n\t\tholding each "
+                 "secret, default 4"
+              << std::endl;
+    std::cout << "\t--signer HEX\n\t\tverify-seal: the public key seal "
+                 "printed, the seal must be\n\t\tmade with its key file"
+              << std::endl;
+    std::cout << "\t--protocol PROTO\n\t\tpreview: one of 'sixel', 'kitty', "
+                 "'iterm', or 'ansi', detected\n\t\tfrom the terminal by "
+                 "default"
+              << std::endl;
+    std::cout << "\t--width COLS\n\t\tpreview: width in terminal cells, "
+                 "the terminal width by default"
               << std::endl;
+    std::cout << "\t--debounce MS\n\t\twatch: quiet time before a new "
+                 "file is processed, default 500"
+              << std::endl;
+#ifdef STEG_WITH_SERVER
+    std::cout << "\t--port PORT\n\t\tserve: TCP port to listen on, default "
+                 "8080"
+              << std::endl;
+    std::cout << "\t--bind ADDR\n\t\tserve: IPv4 address to listen on, "
+                 "default 127.0.0.1"
+              << std::endl;
+    std::cout << "\t--max-pixels N\n\t\tserve: reject uploaded images with "
+                 "more pixels, default\n\t\t100000000, 0 for no limit"
+              << std::endl;
+    std::cout << "\t--max-image-bytes N\n\t\tserve: reject uploaded images "
+                 "taking more bytes to decode,\n\t\tdefault 1073741824 (1 "
+                 "GiB), 0 for no limit"
+              << std::endl;
+#endif
     std::cout << "EXAMPLES" << std::endl;
     std::cout << "\tsteganography merge container.png secret.jpg out.png"
               << std::endl;
     std::cout << "\tsteganography unmerge out.png secret.jpg" << std::endl;
+    std::cout << "\tsteganography merge container.png secret.jpg out.png "
+                 "--mask changed.png --stats"
+              << std::endl;
+    std::cout << "\tsteganography merge-qr container.png out.png "
+                 "\"meet at noon\""
+              << std::endl;
+    std::cout << "\tsteganography unmerge-qr out.png" << std::endl;
+    std::cout << "\tsteganography merge-text container.png notes.md out.png"
+              << std::endl;
+    std::cout << "\tsteganography pack container.png out.png notes.txt docs/"
+              << std::endl;
+    std::cout << "\tsteganography unpack out.png extracted/" << std::endl;
+    std::cout << "\tsteganography pack container.png out.png secret.txt "
+                 "--password real --decoy\n\t\tshopping.txt --decoy-password "
+                 "fake"
+              << std::endl;
+    std::cout << "\tsteganography unpack out.png extracted/ --password fake"
+              << std::endl;
+    std::cout << "\tsteganography pack container.png out.png public.txt "
+                 "staff.txt --layer-passwords\n\t\tpasswords.txt"
+              << std::endl;
+    std::cout << "\tsteganography pack-dir container.png out.png docs/ "
+                 "--compress"
+              << std::endl;
+    std::cout << "\tsteganography unpack-dir out.png extracted/" << std::endl;
+    std::cout << "\tsteganography pack container.png out.png notes.txt "
+                 "--differential"
+              << std::endl;
+    std::cout << "\tsteganography unpack out.png extracted/ --reference "
+                 "container.png"
+              << std::endl;
+    std::cout << "\tsteganography merge-data container.png out.png "
+                 "report.pdf"
+              << std::endl;
+    std::cout << "\tsteganography unmerge-data out.png extracted/" << std::endl;
+    std::cout << "\tsteganography append out.png more.png later.txt"
+              << std::endl;
+    std::cout << "\tsteganography nest outer.png inner-stego.png out.png"
+              << std::endl;
+    std::cout << "\tsteganography unnest out.png inner-stego.png --levels 1"
+              << std::endl;
+    std::cout << "\tsteganography split report.pdf parts/ a.png b.png c.png "
+                 "--with-manifest"
+              << std::endl;
+    std::cout << "\tsteganography join report.pdf parts/part-*.png "
+                 "--manifest parts/manifest.png"
+              << std::endl;
+    std::cout << "\tsteganography inspect out.png" << std::endl;
+    std::cout << "\tsteganography info cover.jpg" << std::endl;
+    std::cout << "\tsteganography keygen my.stegkey" << std::endl;
+    std::cout << "\tsteganography keygen-identity alice.stegid" << std::endl;
+    std::cout << "\tsteganography pack container.png out.png notes.txt "
+                 "--recipient 5f1c09a2...,d04e77b3..."
+              << std::endl;
+    std::cout << "\tsteganography unpack out.png extracted/ --identity "
+                 "alice.stegid"
+              << std::endl;
+    std::cout << "\tsteganography rekey out.png rotated.png --identity "
+                 "alice.stegid --recipient 5f1c09a2..."
+              << std::endl;
+    std::cout << "\tsteganography merge container.png secret.jpg out.png "
+                 "--keyfile my.stegkey"
+              << std::endl;
+    std::cout << "\tsteganography unmerge out.png secret.jpg --keyfile "
+                 "my.stegkey --password hunter2"
+              << std::endl;
+    std::cout << "\tsteganography unmerge out.png candidates/ --try-all"
+              << std::endl;
+    std::cout << "\tsteganography merge-channels container.png out.png --red "
+                 "logo.png --green icon.png --bits 6"
+              << std::endl;
+    std::cout << "\tsteganography unmerge out.png icon.png --channel g --bits 6"
+              << std::endl;
+    std::cout << "\tsteganography attack out.png --jpeg-quality 85 --scale 0.9 "
+                 "--noise 2"
+              << std::endl;
+    std::cout << "\tsteganography detect suspect.png --format json"
+              << std::endl;
+    std::cout << "\tsteganography visual-attack suspect.png planes.png "
+                 "--plane 1,0 --channel g"
+              << std::endl;
+    std::cout << "\tsteganography verify-pair cover.png out.png --bits 4"
+              << std::endl;
+    std::cout << "\tsteganography compare cover.png out.png side-by-side.png"
+              << std::endl;
+    std::cout << "\tsteganography scrub suspect.png clean.png --bits 4"
+              << std::endl;
+    std::cout << "\tsteganography seal photo.png sealed.png --keyfile "
+                 "my.stegkey"
+              << std::endl;
+    std::cout << "\tsteganography verify-seal sealed.png --signer 3b6a27bc..."
+              << std::endl;
+    std::cout << "\tsteganography heatmap suspect.png heat.png --block 64"
+              << std::endl;
+    std::cout << "\tsteganography scan evidence/ --recursive --threshold 0.7 "
+                 "--format json"
+              << std::endl;
+    std::cout << "\tsteganography sanitize uploads/ --recursive" << std::endl;
+    std::cout << "\tsteganography rank-covers photos/ --payload-size 1MB"
+              << std::endl;
+    std::cout << "\tsteganography batch jobs.csv results.csv --create-dirs"
+              << std::endl;
+    std::cout << "\tsteganography gen-cover --size 1920x1080 --style noise "
+                 "cover.png"
+              << std::endl;
+    std::cout << "\tsteganography preview out.png --bits 4" << std::endl;
+    std::cout << "\tsteganography tui c.png s.png out.png" << std::endl;
+    std::cout << "\tsteganography watch --in incoming/ --out processed/ "
+                 "--mode merge --secret s.png"
+              << std::endl;
     std::cout << "NOTES" << std::endl;
     std::cout << "\tThe output of the merge command and input to the unmerge "
                  "command must\n\talways be a PNG!"
               << std::endl;
+    std::cout << "\tThe merge-qr command hides TEXT as a QR code scaled to "
+                 "fill the cover,\n\tunmerge-qr prints the decoded text."
+              << std::endl;
+    std::cout << "\tThe merge-text command hides TEXT_FILE drawn as monospace "
+                 "text, unmerge\n\treturns an image of it anyone can read."
+              << std::endl;
+    std::cout << "\tThe tui command previews the merge at each bit depth with "
+                 "its PSNR before\n\twriting anything, press q to quit."
+              << std::endl;
+    std::cout << "\tThe pack command accepts files and directories. Directories "
+                 "are\n\tadded recursively and unpack recreates them under "
+                 "OUT_DIR."
+              << std::endl;
+    std::cout << "\tThe merge-data command hides FILE as it is with its MIME "
+                 "type and name,\n\tunmerge-data writes it into an OUT "
+                 "directory under that name or adds\n\tthe type's extension "
+                 "to an OUT without one."
+              << std::endl;
+    std::cout << "\tThe nest command hides STEGO_IMG byte for byte, so "
+                 "whatever it holds\n\tsurvives, and unnest gives it back "
+                 "as it was. Nest the output again to\n\tadd a level."
+              << std::endl;
+    std::cout << "\tThe split command fills the covers in the order given, "
+                 "covers it does not\n\tneed are left out. join accepts the "
+                 "parts in any order."
+              << std::endl;
+    std::cout << "\tThe inspect command reads only the payload header, a "
+                 "--password pack has\n\tnone and is only found with its "
+                 "password."
+              << std::endl;
+    std::cout << "\tThe info command prints what IN_IMG holds at each bit "
+                 "depth, merge hides\n\ta secret of the cover's size at "
+                 "--bits, the expected PSNR assumes\n\tthe low bits are "
+                 "overwritten with random data."
+              << std::endl;
+    std::cout << "\tThe pack-dir command stores DIR as a tar archive, gzip "
+                 "compressed when\n\t--compress is given."
+              << std::endl;
+    std::cout << "\tThe batch command runs a CSV or TSV MANIFEST with cover, "
+                 "secret, and output\n\tcolumns and optional action, "
+                 "keyfile, password, bits, method,\n\tcompress, and format "
+                 "columns, and writes each row's status and\n\tstats to "
+                 "RESULTS. A row that fails leaves the others to carry on,\n"
+                 "\tthe exit status is 2 when only some of them failed."
+              << std::endl;
+    std::cout << "\tThe --mask of merge is black wherever the cover was left "
+                 "as it was, so it\n\tcan be checked against the bits "
+                 "merging was meant to touch."
+              << std::endl;
+    std::cout << "\tAn image merged with --keyfile can only be unmerged with "
+                 "the same key file."
+              << std::endl;
+    std::cout << "\tThe contact sheet of unmerge --try-all has a row per "
+                 "method and color space\n\tand a column per bits value "
+                 "from 1 to 8, gray cells failed to extract."
+              << std::endl;
+    std::cout << "\tEach secret of merge-channels keeps only --bits bits per "
+                 "pixel in total, so\n\tit comes out coarser than one "
+                 "merged on its own at the same bits."
+              << std::endl;
+    std::cout << "\tThe seal command authenticates rather than hides, "
+                 "verify-seal tells whether\n\tanything but the low bits "
+                 "changed since and who sealed the image."
+              << std::endl;
+    std::cout << "\tUnpacking with the decoy password reveals only the decoy, "
+                 "there is no way\n\tto tell whether a second archive exists."
+              << std::endl;
+    std::cout << "\tA layered pack fills every layer it was not given with "
+                 "noise, unpacking\n\twith --password opens only the layer "
+                 "of that password."
+              << std::endl;
+    std::cout << "\tA --differential pack holds a quarter of the usual payload "
+                 "and is only as\n\tsafe as its cover, never share or "
+                 "publish the cover."
+              << std::endl;
 }
 
 void PrintErrAndExit(const std::string& err) {
@@ -28,52 +809,2552 @@ void PrintErrAndExit(const std::string& err) {
     exit(EXIT_FAILURE);
 }
 
-int main(int argc, char** argv) {
-    const int kMergeCmdArgCount = 5;
-    const int kUnmergeCmdArgCount = 4;
-    const std::string kMergeCmd("merge");
-    const std::string kUnmergeCmd("unmerge");
-    const std::string kHelpCmd("help");
+std::string ErrorMessage(steganography::RetCode rc) {
+    switch (rc) {
+        case steganography::RetCode::kSuccess:
+            break;
+        case steganography::RetCode::kInvalidFileFormat:
+            return "invalid format, only " + steganography::AcceptedFormats() +
+                   " images are accepted";
+        case steganography::RetCode::kFileNotFound:
+            return "one or more input files do not exist";
+        case steganography::RetCode::kInvalidDimensions:
+            return "secret image does not fit inside cover image";
+        case steganography::RetCode::kInsufficientCapacity:
+            return "payload is too large for the cover image";
+        case steganography::RetCode::kPayloadNotFound:
+            return "no hidden payload found in the input image";
+        case steganography::RetCode::kInvalidPayload:
+            return "hidden payload is corrupt or malformed";
+        case steganography::RetCode::kIoError:
+            return "failed to read or write a file";
+        case steganography::RetCode::kUnsupportedFormat:
+            return "hidden payload was written by a newer, unsupported "
+                   "version";
+        case steganography::RetCode::kFileExists:
+            return "output file already exists";
+        case steganography::RetCode::kInvalidKey:
+            return "key file is malformed or not a steganography key";
+        case steganography::RetCode::kInvalidArgument:
+            return "invalid combination of options, see help";
+        case steganography::RetCode::kKeyMismatch:
+            return "authentication failed: the key file does not match the "
+                   "image";
+        case steganography::RetCode::kPasswordMismatch:
+            return "authentication failed: the key file matches but the "
+                   "password is wrong";
+        case steganography::RetCode::kAuthenticationFailed:
+            return "wrong password or no hidden payload";
+        case steganography::RetCode::kNetworkError:
+            return "failed to transfer a remote file";
+        case steganography::RetCode::kClipboardUnavailable:
+            return "failed to access the clipboard";
+        case steganography::RetCode::kPayloadDamaged:
+            return "hidden payload failed its checksums";
+        case steganography::RetCode::kOutOfBandChanges:
+            return "the images differ outside the declared low bits";
+        case steganography::RetCode::kSameInput:
+            return "the cover and secret are the same image";
+        case steganography::RetCode::kOutputIsInput:
+            return "the output would overwrite an input";
+        case steganography::RetCode::kImageTooLarge:
+            return "an input image is larger than the limit";
+        case steganography::RetCode::kSealBroken:
+            return "the image was altered after it was sealed";
+        case steganography::RetCode::kConversionRefused:
+            return "an input needs a conversion that was refused or cannot "
+                   "be made";
+        case steganography::RetCode::kIncompleteParts:
+            return "parts of the split file are missing";
+    }
+    return "";
+}
 
-    if (argc < 2) { /* missing the program command arg */
-        PrintErrAndExit("missing command");
+void PrintDiagnosticAndExit(const Diagnostic& diag) {
+    std::cerr << "error: " << diag.message << std::endl;
+    if (!diag.path.empty()) {
+        std::cerr << "  path:  " << diag.path << std::endl;
     }
+    std::cerr << "  phase: " << diag.phase << std::endl;
+    std::cerr << "  hint:  " << diag.hint << std::endl;
+    exit(EXIT_FAILURE);
+}
 
-    /* did the user specify a valid command? */
-    std::string cmd(argv[1]);
-    if ((kMergeCmd != cmd) && (kUnmergeCmd != cmd) && (kHelpCmd != cmd)) {
-        PrintErrAndExit("unknown CMD value");
-    } else { /* we have a valid command but do we have the right arg count? */
-        if ((kMergeCmd == cmd) && (kMergeCmdArgCount != argc)) {
-            PrintErrAndExit("invalid arg count for merge command");
-        } else if ((kUnmergeCmd == cmd) && (kUnmergeCmdArgCount != argc)) {
-            PrintErrAndExit("invalid arg count for unmerge command");
+ParsedArgs ParseArgs(int argc, char** argv, const std::string& cmd,
+                     const Command& command) {
+    /* options that consume the argument following them */
+    const std::set<std::string> kValueOptions = {
+        "--keyfile", "--password",     "--decoy", "--decoy-password",
+        "--jpeg-quality", "--scale", "--noise", "--payload-size",
+        "--size",         "--style", "--seed", "--port", "--bind",
+        "--in",           "--out",   "--mode", "--secret", "--debounce",
+        "--bits",         "--protocol", "--width", "--ecc", "--copies",
+        "--threshold",    "--format", "--plane", "--channel",
+        "--block",        "--opacity", "--max-shrink",
+        "--sharpen",      "--secret-size", "--color-space",
+        "--max-pixels",   "--max-image-bytes", "--output-format",
+        "--reference",    "--restore-cover",
+        "--threads",      "--memory-budget", "--method", "--signer",
+        "--recipient",    "--identity", "--recipient-passwords",
+        "--layer-passwords",
+        "--recipient-password", "--type", "--traversal", "--stride",
+        "--border", "--state", "--max-rate", "--max-images", "--amplify",
+        "--red", "--green", "--blue", "--manifest", "--levels", "--mask"};
+
+    ParsedArgs args;
+    for (int i = 2; i < argc; ++i) {
+        std::string arg(argv[i]);
+        if (!arg.starts_with("--")) {
+            args.positional.push_back(arg);
+            continue;
+        }
+        if (!command.options.contains(arg)) {
+            PrintErrAndExit("unknown option '" + arg + "' for " + cmd +
+                            " command");
+        }
+        if (kValueOptions.contains(arg)) {
+            if (i + 1 >= argc) {
+                PrintErrAndExit("missing value for option '" + arg + "'");
+            }
+            args.options[arg] = argv[++i];
+        } else {
+            args.options[arg] = "";
         }
     }
 
-    /* execute the merge/unmerge command */
-    steganography::RetCode rc = steganography::RetCode::kSuccess;
-    if (kMergeCmd == cmd) {
-        rc = steganography::Merge(argv[2], argv[3], argv[4]);
-    } else if (kUnmergeCmd == cmd) {
-        rc = steganography::Unmerge(argv[2], argv[3]);
-    } else if (kHelpCmd == cmd) {
-        PrintUsage();
+    if ((args.positional.size() < command.min_args) ||
+        (args.positional.size() > command.max_args)) {
+        PrintErrAndExit("invalid arg count for " + cmd + " command");
     }
+    return args;
+}
 
-    /* report errors if there are any */
-    switch (rc) {
-        case steganography::RetCode::kSuccess:
-            break;
-        case steganography::RetCode::kInvalidFileFormat:
-            PrintErrAndExit("invalid format, only JPEG and PNG are accepted");
-            break;
-        case steganography::RetCode::kFileNotFound:
-            PrintErrAndExit("one or more input files do not exist");
-            break;
-        case steganography::RetCode::kInvalidDimensions:
-            PrintErrAndExit("secret image does not fit inside cover image");
+std::string GetOption(const ParsedArgs& args, const std::string& option) {
+    auto it = args.options.find(option);
+    return (it == args.options.end()) ? "" : it->second;
+}
+
+double GetNumericOption(const ParsedArgs& args, const std::string& option,
+                        double fallback) {
+    auto it = args.options.find(option);
+    if (it == args.options.end()) {
+        return fallback;
+    }
+    try {
+        std::size_t parsed = 0;
+        double value = std::stod(it->second, &parsed);
+        if (parsed == it->second.size()) {
+            return value;
+        }
+    } catch (const std::exception&) {
+    }
+    PrintErrAndExit("invalid numeric value for option '" + option + "'");
+    return fallback;
+}
+
+/* counts and seeds span all 64 bits, which a double cannot hold, so they
+ * are parsed as integers and only plain decimal digits are taken, stoull
+ * would otherwise wrap a leading '-' around */
+bool ParseUnsigned(const std::string& value, uint64_t& number) {
+    if (value.empty() || !std::isdigit(static_cast<unsigned char>(value[0]))) {
+        return false;
+    }
+    try {
+        std::size_t parsed = 0;
+        unsigned long long parsed_number = std::stoull(value, &parsed, 10);
+        if (parsed == value.size()) {
+            number = static_cast<uint64_t>(parsed_number);
+            return true;
+        }
+    } catch (const std::exception&) {
+    }
+    return false;
+}
+
+std::optional<uint64_t> GetSeedOption(const ParsedArgs& args) {
+    auto it = args.options.find("--seed");
+    if (it == args.options.end()) {
+        return std::nullopt;
+    }
+    uint64_t seed = 0;
+    if (ParseUnsigned(it->second, seed)) {
+        return seed;
+    }
+    PrintErrAndExit("invalid value for option '--seed', expected an integer "
+                    "from 0 to 18446744073709551615");
+    return std::nullopt;
+}
+
+std::size_t GetSizeOption(const ParsedArgs& args, const std::string& option) {
+    /* sizes are byte counts with an optional binary unit suffix */
+    const std::vector<std::pair<std::string, std::size_t>> kUnits = {
+        {"GB", 1UL << 30}, {"MB", 1UL << 20}, {"KB", 1UL << 10}, {"B", 1}};
+    if (!args.options.contains(option)) {
+        return 0;
+    }
+    std::string value = GetOption(args, option);
+    std::size_t multiplier = 1;
+    for (const auto& [suffix, factor] : kUnits) {
+        if (value.ends_with(suffix)) {
+            value.resize(value.size() - suffix.size());
+            multiplier = factor;
             break;
+        }
+    }
+    try {
+        std::size_t parsed = 0;
+        double number = std::stod(value, &parsed);
+        /* 2^64, casting anything from there up, or inf, is undefined */
+        const double kLimit = 18446744073709551616.0;
+        const double kBytes = number * static_cast<double>(multiplier);
+        if ((parsed == value.size()) && (number >= 0) &&
+            std::isfinite(kBytes) && (kBytes < kLimit)) {
+            return static_cast<std::size_t>(kBytes);
+        }
+    } catch (const std::exception&) {
+    }
+    PrintErrAndExit("invalid size for option '" + option + "'");
+    return 0;
+}
+
+void GetDimensionsOption(const ParsedArgs& args, const std::string& option,
+                         std::ptrdiff_t& width, std::ptrdiff_t& height) {
+    long parsed_width = 0;
+    long parsed_height = 0;
+    char sep = 0;
+    std::istringstream iss(GetOption(args, option));
+    if (!(iss >> parsed_width >> sep >> parsed_height) || (sep != 'x') ||
+        !iss.eof()) {
+        PrintErrAndExit("invalid value for option '" + option +
+                        "', expected WxH");
+    }
+    width = parsed_width;
+    height = parsed_height;
+}
+
+/* the public keys given to --recipient, none when it is absent */
+std::vector<steganography::Recipient> GetRecipientsOption(
+    const ParsedArgs& args) {
+    std::vector<steganography::Recipient> recipients;
+    if (!args.options.contains("--recipient")) {
+        return recipients;
+    }
+    std::istringstream iss(GetOption(args, "--recipient"));
+    std::string hex;
+    while (std::getline(iss, hex, ',')) {
+        steganography::Recipient recipient;
+        if (!steganography::RecipientFromHex(hex, recipient)) {
+            PrintErrAndExit("invalid value for option '--recipient', "
+                            "expected 64 hex digits per recipient");
+        }
+        recipients.push_back(recipient);
+    }
+    if (recipients.empty() ||
+        (recipients.size() > steganography::kMaxRecipients)) {
+        PrintErrAndExit("invalid value for option '--recipient', expected 1-" +
+                        std::to_string(steganography::kMaxRecipients) +
+                        " recipients");
+    }
+    return recipients;
+}
+
+/* the non-empty lines of the file an option names, none when it is absent */
+std::vector<std::string> GetPasswordFileOption(const ParsedArgs& args,
+                                               const std::string& option) {
+    std::vector<std::string> passwords;
+    if (!args.options.contains(option)) {
+        return passwords;
+    }
+    std::ifstream ifs(GetOption(args, option));
+    if (!ifs.is_open()) {
+        PrintErrAndExit("unable to read '" + GetOption(args, option) + "'");
+    }
+    std::string line;
+    while (std::getline(ifs, line)) {
+        if (line.ends_with('\r')) {
+            line.pop_back();
+        }
+        if (!line.empty()) {
+            passwords.push_back(line);
+        }
+    }
+    return passwords;
+}
+
+/* the lines of the --recipient-passwords file, none when it is absent */
+std::vector<std::string> GetRecipientPasswordsOption(const ParsedArgs& args) {
+    std::vector<std::string> passwords =
+        GetPasswordFileOption(args, "--recipient-passwords");
+    if (!args.options.contains("--recipient-passwords")) {
+        return passwords;
+    }
+    if (passwords.empty() ||
+        (passwords.size() > steganography::kMaxRecipients)) {
+        PrintErrAndExit("invalid value for option '--recipient-passwords', "
+                        "expected 1-" +
+                        std::to_string(steganography::kMaxRecipients) +
+                        " passwords");
+    }
+    return passwords;
+}
+
+/* the lines of the --layer-passwords file, one per FILE arg */
+std::vector<std::string> GetLayerPasswordsOption(
+    const ParsedArgs& args, const std::vector<std::string>& inputs) {
+    std::vector<std::string> passwords =
+        GetPasswordFileOption(args, "--layer-passwords");
+    if (!args.options.contains("--layer-passwords")) {
+        return passwords;
+    }
+    if (passwords.empty() ||
+        (passwords.size() > steganography::kMaxLayers)) {
+        PrintErrAndExit("invalid value for option '--layer-passwords', "
+                        "expected 1-" +
+                        std::to_string(steganography::kMaxLayers) +
+                        " passwords");
+    }
+    if (passwords.size() != inputs.size()) {
+        PrintErrAndExit("'--layer-passwords' needs a line for each FILE arg");
+    }
+    return passwords;
+}
+
+int GetCopiesOption(const ParsedArgs& args) {
+    double copies = GetNumericOption(args, "--copies", 1);
+    if ((copies < 1) || (copies > steganography::kMaxCopies) ||
+        (copies != std::floor(copies))) {
+        PrintErrAndExit("invalid value for option '--copies', expected 1-" +
+                        std::to_string(steganography::kMaxCopies));
+    }
+    return static_cast<int>(copies);
+}
+
+/* a whole number of pixels or bytes, 0 disables the limit */
+uint64_t GetLimitOption(const ParsedArgs& args, const std::string& option,
+                        uint64_t default_value) {
+    auto it = args.options.find(option);
+    if (it == args.options.end()) {
+        return default_value;
+    }
+    uint64_t limit = 0;
+    if (!ParseUnsigned(it->second, limit)) {
+        PrintErrAndExit("invalid value for option '" + option +
+                        "', expected a whole number");
+    }
+    return limit;
+}
+
+/* worker threads, 0 for one per core */
+unsigned GetThreadsOption(const ParsedArgs& args) {
+    const uint64_t kMaxThreads = 1024;
+    uint64_t threads = GetLimitOption(args, "--threads", 0);
+    if (threads > kMaxThreads) {
+        PrintErrAndExit("invalid value for option '--threads', expected at "
+                        "most " +
+                        std::to_string(kMaxThreads));
+    }
+    return static_cast<unsigned>(threads);
+}
+
+steganography::EccScheme GetEccOption(const ParsedArgs& args) {
+    const std::map<std::string, steganography::EccScheme> kSchemes = {
+        {"none", steganography::EccScheme::kNone},
+        {"hamming", steganography::EccScheme::kHamming},
+    };
+    if (!args.options.contains("--ecc")) {
+        return steganography::EccScheme::kNone;
+    }
+    auto scheme = kSchemes.find(GetOption(args, "--ecc"));
+    if (scheme == kSchemes.end()) {
+        PrintErrAndExit("invalid value for option '--ecc'");
+    }
+    return scheme->second;
+}
+
+steganography::ColorSpace GetColorSpaceOption(const ParsedArgs& args) {
+    const std::map<std::string, steganography::ColorSpace> kSpaces = {
+        {"rgb", steganography::ColorSpace::kRgb},
+        {"hsv", steganography::ColorSpace::kHsv},
+        {"ycbcr", steganography::ColorSpace::kYCbCr},
+    };
+    if (!args.options.contains("--color-space")) {
+        return steganography::ColorSpace::kRgb;
+    }
+    auto space = kSpaces.find(GetOption(args, "--color-space"));
+    if (space == kSpaces.end()) {
+        PrintErrAndExit("invalid value for option '--color-space'");
+    }
+    return space->second;
+}
+
+steganography::Traversal GetTraversalOption(const ParsedArgs& args) {
+    const std::map<std::string, steganography::TraversalOrder> kOrders = {
+        {"row-major", steganography::TraversalOrder::kRowMajor},
+        {"serpentine", steganography::TraversalOrder::kSerpentine},
+        {"column-major", steganography::TraversalOrder::kColumnMajor},
+        {"spiral", steganography::TraversalOrder::kSpiral},
+    };
+    steganography::Traversal traversal;
+    if (args.options.contains("--traversal")) {
+        auto order = kOrders.find(GetOption(args, "--traversal"));
+        if (order == kOrders.end()) {
+            PrintErrAndExit("invalid value for option '--traversal'");
+        }
+        traversal.order = order->second;
+    }
+    traversal.stride =
+        static_cast<std::size_t>(GetLimitOption(args, "--stride", 1));
+    if (traversal.stride == 0) {
+        PrintErrAndExit("invalid value for option '--stride', expected at "
+                        "least 1");
+    }
+    traversal.border =
+        static_cast<std::size_t>(GetLimitOption(args, "--border", 0));
+    traversal.avoid_salient = args.options.contains("--avoid-salient");
+#ifndef STEG_WITH_SALIENCY
+    if (traversal.avoid_salient) {
+        PrintErrAndExit("--avoid-salient needs a build with "
+                        "STEG_WITH_SALIENCY");
+    }
+#endif
+    return traversal;
+}
+
+std::string GetMethodOption(const ParsedArgs& args) {
+    if (!args.options.contains("--method")) {
+        return steganography::kDefaultMethod;
+    }
+    std::string method = GetOption(args, "--method");
+    if (!steganography::FindEmbedMethod(method)) {
+        std::string names;
+        for (const std::string& name : steganography::EmbedMethodNames()) {
+            names += (names.empty() ? "" : ", ") + name;
+        }
+        PrintErrAndExit("invalid value for option '--method', expected one "
+                        "of " + names);
+    }
+    return method;
+}
+
+/* carries_data is whether the output holds hidden data that lossy
+ * compression would destroy */
+steganography::OutputFormat GetOutputFormatOption(const ParsedArgs& args,
+                                                  bool carries_data) {
+    const std::map<std::string, steganography::OutputFormat> kFormats = {
+        {"auto", steganography::OutputFormat::kAuto},
+        {"png", steganography::OutputFormat::kPng},
+        {"jpeg", steganography::OutputFormat::kJpeg},
+        {"jxl", steganography::OutputFormat::kJxl},
+        {"same", steganography::OutputFormat::kSameAsCover},
+    };
+    if (!args.options.contains("--output-format")) {
+        return steganography::OutputFormat::kAuto;
+    }
+    auto format = kFormats.find(GetOption(args, "--output-format"));
+    if (format == kFormats.end()) {
+        PrintErrAndExit("invalid value for option '--output-format'");
+    }
+    if (carries_data &&
+        (format->second == steganography::OutputFormat::kJpeg)) {
+        PrintErrAndExit("'--output-format jpeg' would destroy the hidden "
+                        "data, use png or jxl");
+    }
+#ifndef STEG_WITH_JPEG
+    if (format->second == steganography::OutputFormat::kJpeg) {
+        PrintErrAndExit("'--output-format jpeg' needs a build with JPEG "
+                        "support");
+    }
+#endif
+#ifndef STEG_WITH_JXL
+    if (format->second == steganography::OutputFormat::kJxl) {
+        PrintErrAndExit("'--output-format jxl' needs a build with JPEG XL "
+                        "support");
+    }
+#endif
+    return format->second;
+}
+
+steganography::ScanFormat GetScanFormat(const ParsedArgs& args) {
+    const std::map<std::string, steganography::ScanFormat> kFormats = {
+        {"csv", steganography::ScanFormat::kCsv},
+        {"json", steganography::ScanFormat::kJson},
+    };
+    if (!args.options.contains("--format")) {
+        return steganography::ScanFormat::kCsv;
+    }
+    auto format = kFormats.find(GetOption(args, "--format"));
+    if (format == kFormats.end()) {
+        PrintErrAndExit("invalid value for option '--format'");
+    }
+    return format->second;
+}
+
+steganography::PreviewConfig GetPreviewConfig(const ParsedArgs& args) {
+    const std::map<std::string, steganography::PreviewProtocol> kProtocols = {
+        {"sixel", steganography::PreviewProtocol::kSixel},
+        {"kitty", steganography::PreviewProtocol::kKitty},
+        {"iterm", steganography::PreviewProtocol::kIterm},
+        {"ansi", steganography::PreviewProtocol::kHalfblock},
+    };
+
+    steganography::PreviewConfig config;
+    double bits = GetNumericOption(args, "--bits", config.bits);
+    if ((bits < 1) || (bits > 8) || (bits != std::floor(bits))) {
+        PrintErrAndExit("invalid value for option '--bits', expected 1-8");
+    }
+    config.bits = static_cast<int>(bits);
+    double width = GetNumericOption(args, "--width", config.columns);
+    if (((width < 1) || !std::isfinite(width) ||
+         (width > std::numeric_limits<int>::max())) &&
+        args.options.contains("--width")) {
+        PrintErrAndExit("invalid value for option '--width'");
+    }
+    config.columns = static_cast<int>(width);
+    if (args.options.contains("--protocol")) {
+        auto protocol = kProtocols.find(GetOption(args, "--protocol"));
+        if (protocol == kProtocols.end()) {
+            PrintErrAndExit("invalid value for option '--protocol'");
+        }
+        config.protocol = protocol->second;
+    }
+    return config;
+}
+
+steganography::CoverGenConfig GetCoverGenConfig(const ParsedArgs& args) {
+    const std::map<std::string, steganography::CoverStyle> kStyles = {
+        {"noise", steganography::CoverStyle::kNoise},
+        {"gradient", steganography::CoverStyle::kGradient},
+        {"plasma", steganography::CoverStyle::kPlasma},
+    };
+
+    steganography::CoverGenConfig config;
+    if (args.options.contains("--size")) {
+        GetDimensionsOption(args, "--size", config.width, config.height);
+    }
+    if (args.options.contains("--style")) {
+        auto style = kStyles.find(GetOption(args, "--style"));
+        if (style == kStyles.end()) {
+            PrintErrAndExit("invalid value for option '--style'");
+        }
+        config.style = style->second;
+    }
+    config.create_dirs = args.options.contains("--create-dirs");
+    config.seed = GetSeedOption(args);
+    return config;
+}
+
+steganography::VisualAttackConfig GetVisualAttackConfig(
+    const ParsedArgs& args) {
+    steganography::VisualAttackConfig config;
+    if (args.options.contains("--plane")) {
+        config.planes.clear();
+        std::istringstream iss(GetOption(args, "--plane"));
+        std::string plane;
+        while (std::getline(iss, plane, ',')) {
+            if ((plane.size() != 1) || (plane[0] < '0') || (plane[0] > '7')) {
+                PrintErrAndExit(
+                    "invalid value for option '--plane', expected 0-7");
+            }
+            config.planes.push_back(plane[0] - '0');
+        }
+        if (config.planes.empty() || (config.planes.size() > 8)) {
+            PrintErrAndExit("invalid value for option '--plane', expected 0-7");
+        }
+    }
+    if (args.options.contains("--channel")) {
+        const std::string kChannels = "rgb";
+        config.channels.clear();
+        for (char c : GetOption(args, "--channel")) {
+            std::size_t channel = kChannels.find(c);
+            if (channel == std::string::npos) {
+                PrintErrAndExit("invalid value for option '--channel'");
+            }
+            config.channels.push_back(static_cast<int>(channel));
+        }
+        if (config.channels.empty()) {
+            PrintErrAndExit("invalid value for option '--channel'");
+        }
+    }
+    config.create_dirs = args.options.contains("--create-dirs");
+    return config;
+}
+
+steganography::WatchConfig GetWatchConfig(const ParsedArgs& args) {
+    steganography::WatchConfig config;
+    config.indir = GetOption(args, "--in");
+    config.outdir = GetOption(args, "--out");
+    if (config.indir.empty() || config.outdir.empty()) {
+        PrintErrAndExit("watch requires both '--in' and '--out'");
+    }
+    std::string mode = GetOption(args, "--mode");
+    if (mode.empty() || (mode == "merge")) {
+        config.mode = steganography::WatchMode::kMerge;
+        config.secret = GetOption(args, "--secret");
+        if (config.secret.empty()) {
+            PrintErrAndExit("watch in merge mode requires '--secret'");
+        }
+    } else if (mode == "unmerge") {
+        config.mode = steganography::WatchMode::kUnmerge;
+    } else {
+        PrintErrAndExit("invalid value for option '--mode'");
+    }
+    config.keyfile = GetOption(args, "--keyfile");
+    config.password = GetOption(args, "--password");
+    const uint64_t kDebounce = GetLimitOption(
+        args, "--debounce", static_cast<uint64_t>(config.debounce_ms));
+    if (kDebounce > static_cast<uint64_t>(std::numeric_limits<int>::max())) {
+        PrintErrAndExit("invalid value for option '--debounce'");
+    }
+    config.debounce_ms = static_cast<int>(kDebounce);
+    config.on_processed = [](const std::string& infile,
+                             const std::string& outfile,
+                             steganography::RetCode rc) {
+        if (rc == steganography::RetCode::kSuccess) {
+            std::cout << infile << " -> " << outfile << std::endl;
+        } else {
+            std::cerr << infile << ": " << ErrorMessage(rc) << std::endl;
+        }
+    };
+    return config;
+}
+
+void PrintCoverRanking(const std::vector<steganography::CoverScore>& scores) {
+    std::cout << std::fixed << std::setprecision(2);
+    std::cout << std::left << std::setw(10) << "score" << std::setw(14)
+              << "capacity" << std::setw(10) << "entropy" << std::setw(10)
+              << "texture" << std::setw(15) << "detectability"
+              << "path" << std::endl;
+    for (const auto& score : scores) {
+        std::cout << std::setw(10) << score.score << std::setw(14)
+                  << score.capacity << std::setw(10) << score.entropy
+                  << std::setw(10) << score.texture << std::setw(15)
+                  << score.detectability << score.path << std::endl;
+    }
+    if (scores.empty() || (scores.front().score == 0.0)) {
+        std::cout << "no cover can hold the payload" << std::endl;
+    } else {
+        std::cout << "recommended cover: " << scores.front().path << std::endl;
+    }
+}
+
+void PrintMergeReport(const steganography::MergeReport& report) {
+    std::cout << std::fixed << std::setprecision(2);
+    std::cout << "pixels modified:      " << report.pixels_modified
+              << std::endl;
+    for (std::size_t bit = 0; bit < report.bits_modified.size(); ++bit) {
+        if (report.bits_modified[bit] > 0) {
+            std::cout << std::left << std::setw(22)
+                      << ("bit " + std::to_string(bit) + " changed:")
+                      << std::right << report.bits_modified[bit] << std::endl;
+        }
+    }
+    std::cout << "bits embedded:        " << report.bits_embedded << std::endl;
+    std::cout << "capacity utilization: " << report.capacity_used << "%"
+              << std::endl;
+    std::cout << "PSNR vs. cover:       " << report.psnr << " dB" << std::endl;
+    if (report.shrink > 1.0) {
+        std::cout << "secret shrunk by:     " << report.shrink << "x"
+                  << std::endl;
+    }
+    const double kMegabyte = 1024.0 * 1024.0;
+    std::cout << "decoded images:       "
+              << static_cast<double>(report.image_bytes) / kMegabyte << " MB"
+              << std::endl;
+    if (report.peak_rss > 0) {
+        std::cout << "peak memory:          "
+                  << static_cast<double>(report.peak_rss) / kMegabyte << " MB"
+                  << (report.rss_since_merge ? "" : " (since start)")
+                  << std::endl;
+    }
+    for (const auto& phase : report.phases) {
+        std::cout << std::left << std::setw(22) << (phase.name + " time:")
+                  << std::right << phase.milliseconds << " ms";
+        if (phase.peak_rss > 0) {
+            std::cout << ", peak "
+                      << static_cast<double>(phase.peak_rss) / kMegabyte
+                      << " MB";
+        }
+        std::cout << std::endl;
+    }
+}
+
+void PrintDamageReport(const steganography::DamageReport& damage) {
+    if (damage.orientation != steganography::Orientation::kNormal) {
+        std::cerr << "the image was "
+                  << steganography::OrientationName(damage.orientation)
+                  << " after packing and was turned back" << std::endl;
+    }
+    if (damage.resynced) {
+        std::cerr << "sync markers found the payload shifted by "
+                  << std::fixed << std::setprecision(1) << damage.shift_rows
+                  << " rows (" << damage.shift_cells << " cells)" << std::endl;
+    }
+    if (damage.outvoted_bits > 0) {
+        std::cerr << "outvoted " << damage.outvoted_bits
+                  << " bits that disagreed between copies" << std::endl;
+    }
+    if (damage.corrected_bits > 0) {
+        std::cerr << "corrected " << damage.corrected_bits << " flipped bits"
+                  << std::endl;
+    }
+    for (const auto& block : damage.blocks) {
+        std::cerr << "damaged: payload bytes " << block.offset << "-"
+                  << block.offset + block.length - 1 << ", image rows "
+                  << block.first_row << "-" << block.last_row << std::endl;
+    }
+    for (const auto& name : damage.partial_files) {
+        std::cerr << "partial: " << name << std::endl;
+    }
+    for (const auto& name : damage.lost_files) {
+        std::cerr << "lost: " << name << std::endl;
+    }
+    if (damage.truncated) {
+        std::cerr << "lost: any entries after the damage" << std::endl;
+    }
+}
+
+void PrintDetectReport(const steganography::DetectionReport& report) {
+    std::cout << std::fixed << std::setprecision(2);
+    for (const steganography::DetectorScore& score : report.detectors) {
+        std::cout << std::left << std::setw(12) << score.name + ":"
+                  << std::right << score.payload_fraction * 100.0
+                  << "% of the channels, confidence "
+                  << score.confidence * 100.0 << "%" << std::endl;
+    }
+    std::cout << std::left << std::setw(12) << "consensus:" << std::right
+              << report.payload_fraction * 100.0
+              << "% of the channels, confidence "
+              << report.confidence * 100.0 << "%" << std::endl;
+    if (report.bit_depth > 0) {
+        std::cout << "bit depth:  " << report.bit_depth
+                  << " low bits per channel, about " << report.estimated_bytes
+                  << " bytes" << std::endl;
+    }
+}
+
+void PrintInspectReport(const steganography::InspectReport& report) {
+    if (!report.found) {
+        std::cout << "header:      none found" << std::endl;
+        return;
+    }
+    const steganography::PayloadHeader& header = report.header;
+    if (report.deniable) {
+        std::cout << "header:      none, opened with the password"
+                  << std::endl;
+    } else {
+        std::cout << "header:      version " << static_cast<int>(header.version)
+                  << (report.supported ? "" : ", newer than this release")
+                  << std::endl;
+        if (!report.supported) {
+            return;
+        }
+        std::cout << "method:      "
+                  << ((header.method ==
+                       steganography::EmbedMethodId::kSequentialLsb)
+                          ? "sequential LSB"
+                          : "unknown")
+                  << std::endl;
+    }
+    std::cout << "layout:      "
+              << steganography::PayloadLayoutName(report.layout) << std::endl;
+    std::cout << "bit depth:   ";
+    if (report.bits_per_channel > 0) {
+        std::cout << report.bits_per_channel << " low bits per channel"
+                  << std::endl;
+    } else {
+        std::cout << "one bit per pixel or pixel pair" << std::endl;
+    }
+    std::cout << "payload:     " << report.payload_size << " bytes"
+              << std::endl;
+    std::cout << "compression: " << (report.compressed ? "gzip" : "none")
+              << std::endl;
+    std::cout << "encryption:  ";
+    if (report.deniable) {
+        std::cout << "password, deniable" << std::endl;
+    } else if (report.pipeline_encrypted) {
+        std::cout << "password, pipeline stage" << std::endl;
+    } else if (report.recipients + report.recipient_passwords > 0) {
+        std::cout << report.recipients << " recipients, "
+                  << report.recipient_passwords << " recipient passwords"
+                  << std::endl;
+    } else {
+        std::cout << "none" << std::endl;
+    }
+
+    std::vector<std::string> ecc;
+    if (header.flags & steganography::kFlagHamming) {
+        ecc.push_back("hamming(7,4)");
+    }
+    if (header.flags & steganography::kFlagInterleaved) {
+        ecc.push_back("interleaved " +
+                      std::to_string(steganography::kInterleaveDepth) +
+                      " deep");
+    }
+    if (header.flags & steganography::kFlagChecksums) {
+        ecc.push_back("CRC32 per " +
+                      std::to_string(steganography::kChecksumBlockSize) +
+                      " bytes");
+    }
+    if (header.flags & steganography::kFlagCopies) {
+        ecc.push_back(std::to_string(header.copies) +
+                      (header.keyed_copies ? " keyed copies" : " copies"));
+    }
+    if (header.flags & steganography::kFlagSync) {
+        ecc.push_back("sync markers");
+    }
+    std::cout << "ecc:         ";
+    for (std::size_t i = 0; i < ecc.size(); ++i) {
+        std::cout << (i > 0 ? ", " : "") << ecc[i];
+    }
+    std::cout << (ecc.empty() ? "none" : "") << std::endl;
+    if (!header.stages.empty()) {
+        static const char* const kStageNames[] = {"unknown", "compress",
+                                                  "encrypt", "ecc",
+                                                  "interleave"};
+        std::cout << "pipeline:    ";
+        for (std::size_t i = 0; i < header.stages.size(); ++i) {
+            const uint8_t kStage = header.stages[i];
+            std::cout << (i > 0 ? " -> " : "")
+                      << kStageNames[(kStage <= 4) ? kStage : 0];
+        }
+        std::cout << std::endl;
+    }
+    if (!report.contents.empty()) {
+        std::cout << "contents:    " << report.contents;
+        if (!header.filename.empty()) {
+            std::cout << " \"" << header.filename << "\"";
+        }
+        std::cout << std::endl;
+    }
+    for (std::size_t i = 0; i < report.entries.size(); ++i) {
+        std::cout << ((i == 0) ? "entries:     " : "             ")
+                  << report.entries[i] << std::endl;
+    }
+    std::cout << "integrity:   "
+              << (report.damage.blocks.empty()
+                      ? "intact"
+                      : std::to_string(report.damage.blocks.size()) +
+                            " damaged blocks")
+              << std::endl;
+}
+
+void PrintImageInfo(const steganography::ImageInfo& info) {
+    const steganography::ImageLayout& layout = info.layout;
+    std::cout << "format:      " << info.format << std::endl;
+    std::cout << "dimensions:  " << layout.width << "x" << layout.height
+              << std::endl;
+    std::cout << "color type:  " << layout.color_type << std::endl;
+    std::cout << "bit depth:   " << layout.bit_depth
+              << (layout.interlaced ? ", interlaced" : "") << std::endl;
+    std::cout << "pack fits:   " << info.payload_capacity << " bytes"
+              << std::endl;
+    std::cout << std::endl;
+    std::cout << std::fixed << std::setprecision(2);
+    std::cout << std::left << std::setw(6) << "bits" << std::setw(16)
+              << "capacity" << "expected PSNR" << std::endl;
+    for (const auto& capacity : info.capacities) {
+        std::cout << std::setw(6) << capacity.bits << std::setw(16)
+                  << (std::to_string(capacity.bytes) + " bytes")
+                  << capacity.expected_psnr << " dB" << std::endl;
+    }
+}
+
+void PrintContactSheetLegend(
+    const std::vector<steganography::TryAllCandidate>& candidates) {
+    const std::vector<std::string> kRows = steganography::ContactSheetRows();
+    std::cout << "contact sheet columns: bits 1 to 8, rows:" << std::endl;
+    for (std::size_t i = 0; i < kRows.size(); ++i) {
+        std::cout << "  " << i + 1 << ". " << kRows[i] << std::endl;
+    }
+    std::size_t extracted = 0;
+    for (const auto& candidate : candidates) {
+        extracted += candidate.file.empty() ? 0 : 1;
+    }
+    std::cout << extracted << " of " << candidates.size()
+              << " combinations extracted" << std::endl;
+}
+
+void PrintSplitParts(const std::vector<steganography::SplitPart>& parts) {
+    for (const auto& part : parts) {
+        std::cout << part.file << "  " << part.cover << "  " << part.length
+                  << " bytes  sha256 " << part.sha256 << std::endl;
+    }
+}
+
+void PrintJoinReport(const steganography::JoinReport& report) {
+    for (const auto& part : report.missing) {
+        std::cerr << "missing: " << part << std::endl;
+    }
+    for (const auto& image : report.unexpected) {
+        std::cerr << "unexpected: " << image << std::endl;
+    }
+}
+
+void PrintAttackReport(const steganography::AttackReport& report) {
+    std::cout << std::fixed << std::setprecision(2);
+    std::cout << "embedded bits intact:  " << report.bits_intact * 100.0 << "%"
+              << std::endl;
+    std::cout << "embedded bytes intact: " << report.bytes_intact * 100.0
+              << "%" << std::endl;
+    std::cout << "merged secret PSNR:    " << report.secret_psnr << " dB"
+              << std::endl;
+    std::cout << "packed payload:        ";
+    if (!report.had_payload) {
+        std::cout << "none present" << std::endl;
+    } else {
+        std::cout << (report.payload_intact ? "survived" : "lost")
+                  << std::endl;
+    }
+}
+
+void PrintPairReport(const steganography::PairReport& report, int bits) {
+    std::cout << std::fixed << std::setprecision(2);
+    std::cout << "changed channels:     " << report.changed_channels
+              << std::endl;
+    std::cout << "low bits needed:      " << report.bits_needed << " of "
+              << bits << " declared" << std::endl;
+    std::cout << "PSNR:                 " << report.psnr << " dB" << std::endl;
+    std::cout << "out of band channels: " << report.out_of_band_channels
+              << " in " << report.out_of_band_pixels << " pixels"
+              << std::endl;
+    const char* kChannelNames[] = {"red", "green", "blue"};
+    for (const auto& change : report.examples) {
+        std::cerr << "out of band: (" << change.x << ", " << change.y << ") "
+                  << kChannelNames[change.channel] << " "
+                  << static_cast<int>(change.cover) << " -> "
+                  << static_cast<int>(change.stego) << std::endl;
+    }
+    if (report.out_of_band_channels > report.examples.size()) {
+        std::cerr << "out of band: and "
+                  << report.out_of_band_channels - report.examples.size()
+                  << " more" << std::endl;
+    }
+}
+
+int GetChannelBitsOption(const ParsedArgs& args) {
+    double bits = GetNumericOption(args, "--bits", 4);
+    if ((bits < 1) || (bits > 8) || (bits != std::floor(bits))) {
+        PrintErrAndExit("invalid value for option '--bits', expected 1-8");
+    }
+    return static_cast<int>(bits);
+}
+
+steganography::RetCode RunCommand(const std::string& cmd,
+                                  const ParsedArgs& args) {
+    const std::vector<std::string>& pos = args.positional;
+    if (cmd == "merge") {
+        steganography::MergeConfig config;
+        config.keyfile = GetOption(args, "--keyfile");
+        config.password = GetOption(args, "--password");
+        config.create_dirs = args.options.contains("--create-dirs");
+        config.fit = args.options.contains("--fit");
+        config.max_shrink =
+            GetNumericOption(args, "--max-shrink", config.max_shrink);
+        if (config.max_shrink < 1.0) {
+            PrintErrAndExit(
+                "invalid value for option '--max-shrink', expected at least 1");
+        }
+        config.strict = args.options.contains("--strict");
+        config.color_space = GetColorSpaceOption(args);
+        config.dither = args.options.contains("--dither");
+        config.block_bits = args.options.contains("--block-bits");
+        if (config.block_bits && config.keyfile.empty()) {
+            PrintErrAndExit("'--block-bits' needs a --keyfile");
+        }
+        if (config.block_bits && config.dither) {
+            PrintErrAndExit("'--block-bits' cannot be combined with "
+                            "'--dither'");
+        }
+        if (config.dither &&
+            (config.color_space != steganography::ColorSpace::kRgb)) {
+            PrintErrAndExit("'--dither' cannot be combined with "
+                            "'--color-space " +
+                            GetOption(args, "--color-space") + "'");
+        }
+        config.linear_resize = args.options.contains("--linear");
+        if (args.options.contains("--secret-size")) {
+            GetDimensionsOption(args, "--secret-size", config.secret_width,
+                                config.secret_height);
+            if ((config.secret_width < 1) || (config.secret_height < 1)) {
+                PrintErrAndExit("invalid value for option '--secret-size'");
+            }
+        }
+        config.sharpen = GetNumericOption(args, "--sharpen", config.sharpen);
+        if (config.sharpen < 0.0) {
+            PrintErrAndExit("invalid value for option '--sharpen'");
+        }
+        config.output_format = GetOutputFormatOption(args, true);
+        config.threads = GetThreadsOption(args);
+        config.memory_budget = GetSizeOption(args, "--memory-budget");
+        config.method = GetMethodOption(args);
+        config.mask = GetOption(args, "--mask");
+        steganography::MergeReport report;
+        steganography::RetCode rc =
+            steganography::Merge(pos[0], pos[1], pos[2], config, report);
+        for (const auto& warning : report.warnings) {
+            std::cerr << "warning: " << warning.message << " ["
+                      << warning.code << "]" << std::endl;
+        }
+        if ((rc == steganography::RetCode::kSuccess) &&
+            args.options.contains("--stats")) {
+            PrintMergeReport(report);
+        }
+        return rc;
+    } else if (cmd == "unmerge") {
+        steganography::UnmergeConfig config;
+        config.keyfile = GetOption(args, "--keyfile");
+        config.password = GetOption(args, "--password");
+        config.create_dirs = args.options.contains("--create-dirs");
+        config.color_space = GetColorSpaceOption(args);
+        config.block_bits = args.options.contains("--block-bits");
+        if (config.block_bits && config.keyfile.empty()) {
+            PrintErrAndExit("'--block-bits' needs a --keyfile");
+        }
+        config.output_format = GetOutputFormatOption(args, false);
+        config.threads = GetThreadsOption(args);
+        config.memory_budget = GetSizeOption(args, "--memory-budget");
+        config.method = GetMethodOption(args);
+        if (args.options.contains("--channel")) {
+            for (const char* option :
+                 {"--keyfile", "--color-space", "--block-bits",
+                  "--output-format", "--method", "--try-all"}) {
+                if (args.options.contains(option)) {
+                    PrintErrAndExit(std::string("'") + option +
+                                    "' cannot be combined with --channel");
+                }
+            }
+            const std::string kChannels = "rgb";
+            const std::string kChannel = GetOption(args, "--channel");
+            if ((kChannel.size() != 1) ||
+                (kChannels.find(kChannel[0]) == std::string::npos)) {
+                PrintErrAndExit(
+                    "invalid value for option '--channel', expected r, g, "
+                    "or b");
+            }
+            steganography::ChannelConfig channel_config;
+            channel_config.bits = GetChannelBitsOption(args);
+            channel_config.create_dirs = config.create_dirs;
+            return steganography::UnmergeChannel(
+                pos[0],
+                static_cast<steganography::Channel>(
+                    kChannels.find(kChannel[0])),
+                pos[1], channel_config);
+        }
+        if (args.options.contains("--bits")) {
+            PrintErrAndExit("'--bits' only applies to unmerge --channel");
+        }
+        if (args.options.contains("--try-all")) {
+            for (const char* option :
+                 {"--color-space", "--method", "--output-format",
+                  "--memory-budget", "--create-dirs"}) {
+                if (args.options.contains(option)) {
+                    PrintErrAndExit(std::string("'") + option +
+                                    "' cannot be combined with --try-all");
+                }
+            }
+            steganography::TryAllConfig try_all;
+            try_all.unmerge = config;
+            std::vector<steganography::TryAllCandidate> candidates;
+            steganography::RetCode rc =
+                steganography::TryAll(pos[0], pos[1], try_all, candidates);
+            if (rc == steganography::RetCode::kSuccess) {
+                PrintContactSheetLegend(candidates);
+            }
+            return rc;
+        }
+        return steganography::Unmerge(pos[0], pos[1], config);
+    } else if (cmd == "merge-channels") {
+        steganography::ChannelConfig config;
+        config.bits = GetChannelBitsOption(args);
+        config.create_dirs = args.options.contains("--create-dirs");
+        const std::array<std::string, 3> kSecrets = {
+            GetOption(args, "--red"), GetOption(args, "--green"),
+            GetOption(args, "--blue")};
+        if (kSecrets[0].empty() && kSecrets[1].empty() &&
+            kSecrets[2].empty()) {
+            PrintErrAndExit("merge-channels needs at least one of --red, "
+                            "--green, and --blue");
+        }
+        return steganography::MergeChannels(pos[0], kSecrets, pos[1], config);
+    } else if (cmd == "merge-qr") {
+        steganography::QrMergeConfig config;
+        config.merge.keyfile = GetOption(args, "--keyfile");
+        config.merge.password = GetOption(args, "--password");
+        config.merge.create_dirs = args.options.contains("--create-dirs");
+        return steganography::MergeQr(pos[0], pos[1], pos[2], config);
+    } else if (cmd == "merge-text") {
+        steganography::MergeConfig config;
+        config.keyfile = GetOption(args, "--keyfile");
+        config.password = GetOption(args, "--password");
+        config.create_dirs = args.options.contains("--create-dirs");
+        return steganography::MergeText(pos[0], pos[1], pos[2], config);
+    } else if (cmd == "unmerge-qr") {
+        steganography::UnmergeConfig config;
+        config.keyfile = GetOption(args, "--keyfile");
+        config.password = GetOption(args, "--password");
+        std::string text;
+        steganography::RetCode rc =
+            steganography::UnmergeQr(pos[0], text, config);
+        if (rc == steganography::RetCode::kSuccess) {
+            std::cout << text << std::endl;
+        }
+        return rc;
+    } else if (cmd == "pack") {
+        std::vector<std::string> inputs(pos.cbegin() + 2, pos.cend());
+        steganography::PackConfig config;
+        config.password = GetOption(args, "--password");
+        config.decoy_password = GetOption(args, "--decoy-password");
+        config.layer_passwords = GetLayerPasswordsOption(args, inputs);
+        config.seed = GetSeedOption(args);
+        if (args.options.contains("--seed") && config.password.empty() &&
+            config.layer_passwords.empty()) {
+            PrintErrAndExit("'--seed' only applies to a pack with --password "
+                            "or --layer-passwords, the others are "
+                            "reproducible already");
+        }
+        config.create_dirs = args.options.contains("--create-dirs");
+        config.checksums = args.options.contains("--checksums");
+        config.ecc = GetEccOption(args);
+        config.interleave = args.options.contains("--interleave");
+        config.copies = GetCopiesOption(args);
+        config.keyfile = GetOption(args, "--keyfile");
+        config.sync = args.options.contains("--sync");
+        config.differential = args.options.contains("--differential");
+        config.pair_swap = args.options.contains("--pair-swap");
+        config.reversible = args.options.contains("--reversible");
+        config.rotate_channels = args.options.contains("--rotate-channels");
+        config.traversal = GetTraversalOption(args);
+        config.recipients = GetRecipientsOption(args);
+        config.recipient_passwords = GetRecipientPasswordsOption(args);
+        config.output_format = GetOutputFormatOption(args, true);
+        if (args.options.contains("--decoy")) {
+            config.decoy_inputs.push_back(GetOption(args, "--decoy"));
+        }
+#ifdef STEG_WITH_CLIPBOARD
+        if (args.options.contains("--from-clipboard")) {
+            if (!inputs.empty()) {
+                PrintErrAndExit("FILE args cannot be combined with "
+                                "--from-clipboard");
+            }
+            std::string message;
+            steganography::RetCode rc = steganography::ReadClipboard(message);
+            if (rc != steganography::RetCode::kSuccess) {
+                return rc;
+            }
+            return steganography::PackMessage(pos[0], pos[1], message, config);
+        }
+        if (inputs.empty()) {
+            PrintErrAndExit("invalid arg count for pack command");
+        }
+#endif
+        return steganography::Pack(pos[0], pos[1], inputs, config);
+    } else if (cmd == "unpack") {
+        steganography::UnpackConfig config;
+        config.password = GetOption(args, "--password");
+        config.keyfile = GetOption(args, "--keyfile");
+        config.reference = GetOption(args, "--reference");
+        config.traversal = GetTraversalOption(args);
+        config.restore_cover = GetOption(args, "--restore-cover");
+        config.identity = GetOption(args, "--identity");
+        config.recipient_password = GetOption(args, "--recipient-password");
+#ifdef STEG_WITH_CLIPBOARD
+        if (args.options.contains("--to-clipboard")) {
+            if (pos.size() > 1) {
+                PrintErrAndExit("OUT_DIR cannot be combined with "
+                                "--to-clipboard");
+            }
+            std::string message;
+            steganography::RetCode rc =
+                steganography::UnpackMessage(pos[0], message, config);
+            if (rc != steganography::RetCode::kSuccess) {
+                return rc;
+            }
+            return steganography::WriteClipboard(message);
+        }
+        if (pos.size() < 2) {
+            PrintErrAndExit("invalid arg count for unpack command");
+        }
+#endif
+        steganography::DamageReport damage;
+        steganography::RetCode rc =
+            steganography::Unpack(pos[0], pos[1], config, damage);
+        PrintDamageReport(damage);
+        return rc;
+    } else if (cmd == "pack-dir") {
+        steganography::PackDirConfig config;
+        config.compress = args.options.contains("--compress");
+        config.create_dirs = args.options.contains("--create-dirs");
+        config.checksums = args.options.contains("--checksums");
+        config.ecc = GetEccOption(args);
+        config.interleave = args.options.contains("--interleave");
+        config.copies = GetCopiesOption(args);
+        config.keyfile = GetOption(args, "--keyfile");
+        config.sync = args.options.contains("--sync");
+        config.differential = args.options.contains("--differential");
+        config.pair_swap = args.options.contains("--pair-swap");
+        config.reversible = args.options.contains("--reversible");
+        config.rotate_channels = args.options.contains("--rotate-channels");
+        config.traversal = GetTraversalOption(args);
+        config.recipients = GetRecipientsOption(args);
+        config.recipient_passwords = GetRecipientPasswordsOption(args);
+        config.output_format = GetOutputFormatOption(args, true);
+        return steganography::PackDirectory(pos[0], pos[2], pos[1], config);
+    } else if (cmd == "unpack-dir") {
+        steganography::UnpackDirConfig config;
+        config.keyfile = GetOption(args, "--keyfile");
+        config.reference = GetOption(args, "--reference");
+        config.traversal = GetTraversalOption(args);
+        config.restore_cover = GetOption(args, "--restore-cover");
+        config.identity = GetOption(args, "--identity");
+        config.recipient_password = GetOption(args, "--recipient-password");
+        steganography::DamageReport damage;
+        steganography::RetCode rc =
+            steganography::UnpackDirectory(pos[0], pos[1], config, damage);
+        PrintDamageReport(damage);
+        return rc;
+    } else if (cmd == "merge-data") {
+        steganography::PackDataConfig config;
+        config.content_type = GetOption(args, "--type");
+        config.compress = args.options.contains("--compress");
+        config.create_dirs = args.options.contains("--create-dirs");
+        config.checksums = args.options.contains("--checksums");
+        config.ecc = GetEccOption(args);
+        config.interleave = args.options.contains("--interleave");
+        config.copies = GetCopiesOption(args);
+        config.keyfile = GetOption(args, "--keyfile");
+        config.sync = args.options.contains("--sync");
+        config.differential = args.options.contains("--differential");
+        config.pair_swap = args.options.contains("--pair-swap");
+        config.reversible = args.options.contains("--reversible");
+        config.rotate_channels = args.options.contains("--rotate-channels");
+        config.traversal = GetTraversalOption(args);
+        config.recipients = GetRecipientsOption(args);
+        config.recipient_passwords = GetRecipientPasswordsOption(args);
+        config.output_format = GetOutputFormatOption(args, true);
+        if (config.content_type.size() > steganography::kMaxContentTypeSize) {
+            PrintErrAndExit("'--type' is limited to " +
+                            std::to_string(
+                                steganography::kMaxContentTypeSize) +
+                            " characters");
+        }
+        return steganography::PackData(pos[0], pos[2], pos[1], config);
+    } else if (cmd == "unmerge-data") {
+        steganography::UnpackDataConfig config;
+        config.keyfile = GetOption(args, "--keyfile");
+        config.reference = GetOption(args, "--reference");
+        config.traversal = GetTraversalOption(args);
+        config.restore_cover = GetOption(args, "--restore-cover");
+        config.identity = GetOption(args, "--identity");
+        config.recipient_password = GetOption(args, "--recipient-password");
+        const bool kInspect = args.options.contains("--inspect");
+        if (kInspect != (pos.size() == 1)) {
+            PrintErrAndExit(kInspect ? "OUT cannot be combined with --inspect"
+                                     : "invalid arg count for unmerge-data "
+                                       "command");
+        }
+        steganography::DataInfo info;
+        steganography::RetCode rc =
+            kInspect ? steganography::InspectData(pos[0], config, info)
+                     : steganography::UnpackData(pos[0], pos[1], config, info);
+        if (rc != steganography::RetCode::kSuccess) {
+            return rc;
+        }
+        if (kInspect) {
+            std::cout << "type: " << info.content_type
+                      << (info.guessed ? " (guessed)" : "") << std::endl;
+            std::cout << "name: "
+                      << (info.filename.empty() ? "none recorded"
+                                                : info.filename)
+                      << std::endl;
+            std::cout << "size: " << info.size << " bytes" << std::endl;
+        } else {
+            std::cout << "wrote " << info.path << " (" << info.content_type
+                      << ")" << std::endl;
+        }
+        return rc;
+    } else if (cmd == "append") {
+        steganography::AppendConfig config;
+        config.keyfile = GetOption(args, "--keyfile");
+        config.traversal = GetTraversalOption(args);
+        config.identity = GetOption(args, "--identity");
+        config.recipient_password = GetOption(args, "--recipient-password");
+        config.recipients = GetRecipientsOption(args);
+        config.recipient_passwords = GetRecipientPasswordsOption(args);
+        config.create_dirs = args.options.contains("--create-dirs");
+        config.output_format = GetOutputFormatOption(args, true);
+        if (config.recipients.size() + config.recipient_passwords.size() >
+            steganography::kMaxRecipients) {
+            PrintErrAndExit("at most " +
+                            std::to_string(steganography::kMaxRecipients) +
+                            " recipients and recipient passwords together");
+        }
+        return steganography::Append(
+            pos[0], pos[1],
+            std::vector<std::string>(pos.cbegin() + 2, pos.cend()), config);
+    } else if (cmd == "nest") {
+        steganography::NestConfig config;
+        config.create_dirs = args.options.contains("--create-dirs");
+        config.output_format = GetOutputFormatOption(args, true);
+        return steganography::Nest(pos[0], pos[1], pos[2], config);
+    } else if (cmd == "unnest") {
+        steganography::UnnestConfig config;
+        config.levels =
+            static_cast<std::size_t>(GetLimitOption(args, "--levels", 0));
+        config.create_dirs = args.options.contains("--create-dirs");
+        steganography::UnnestReport report;
+        steganography::RetCode rc =
+            steganography::Unnest(pos[0], pos[1], config, report);
+        if (rc == steganography::RetCode::kSuccess) {
+            std::cout << "unwrapped " << report.levels
+                      << ((report.levels == 1) ? " level" : " levels");
+            if (!report.filename.empty()) {
+                std::cout << ", innermost nested as " << report.filename;
+            }
+            std::cout << std::endl;
+        }
+        return rc;
+    } else if (cmd == "split") {
+        steganography::SplitConfig config;
+        config.manifest = args.options.contains("--with-manifest");
+        std::vector<steganography::SplitPart> parts;
+        steganography::RetCode rc = steganography::Split(
+            pos[0], pos[1],
+            std::vector<std::string>(pos.cbegin() + 2, pos.cend()), config,
+            parts);
+        if (rc == steganography::RetCode::kSuccess) {
+            PrintSplitParts(parts);
+        }
+        return rc;
+    } else if (cmd == "join") {
+        steganography::JoinConfig config;
+        config.manifest = GetOption(args, "--manifest");
+        config.create_dirs = args.options.contains("--create-dirs");
+        steganography::JoinReport report;
+        steganography::RetCode rc = steganography::Join(
+            std::vector<std::string>(pos.cbegin() + 1, pos.cend()), pos[0],
+            config, report);
+        PrintJoinReport(report);
+        return rc;
+    } else if (cmd == "inspect") {
+        steganography::InspectConfig config;
+        config.password = GetOption(args, "--password");
+        config.keyfile = GetOption(args, "--keyfile");
+        config.reference = GetOption(args, "--reference");
+        config.traversal = GetTraversalOption(args);
+        steganography::InspectReport report;
+        steganography::RetCode rc =
+            steganography::Inspect(pos[0], config, report);
+        if (rc == steganography::RetCode::kSuccess) {
+            PrintInspectReport(report);
+            PrintDamageReport(report.damage);
+        }
+        return rc;
+    } else if (cmd == "info") {
+        steganography::ImageInfo info;
+        steganography::RetCode rc = steganography::GetImageInfo(pos[0], info);
+        if (rc == steganography::RetCode::kSuccess) {
+            PrintImageInfo(info);
+        }
+        return rc;
+    } else if (cmd == "keygen") {
+        return steganography::GenerateKeyFile(
+            pos[0], args.options.contains("--create-dirs"));
+    } else if (cmd == "keygen-identity") {
+        steganography::Recipient recipient;
+        steganography::RetCode rc = steganography::GenerateIdentityFile(
+            pos[0], args.options.contains("--create-dirs"), recipient);
+        if (rc == steganography::RetCode::kSuccess) {
+            std::cout << "recipient: "
+                      << steganography::RecipientToHex(recipient)
+                      << std::endl;
+        }
+        return rc;
+    } else if (cmd == "rekey") {
+        steganography::RekeyConfig config;
+        config.keyfile = GetOption(args, "--keyfile");
+        config.traversal = GetTraversalOption(args);
+        config.identity = GetOption(args, "--identity");
+        config.recipient_password = GetOption(args, "--recipient-password");
+        config.recipients = GetRecipientsOption(args);
+        config.recipient_passwords = GetRecipientPasswordsOption(args);
+        config.create_dirs = args.options.contains("--create-dirs");
+        config.output_format = GetOutputFormatOption(args, true);
+        if (config.recipients.size() + config.recipient_passwords.size() >
+            steganography::kMaxRecipients) {
+            PrintErrAndExit("at most " +
+                            std::to_string(steganography::kMaxRecipients) +
+                            " recipients and recipient passwords together");
+        }
+        if (config.recipients.empty() && config.recipient_passwords.empty()) {
+            PrintErrAndExit("rekey needs the new --recipient or "
+                            "--recipient-passwords");
+        }
+        return steganography::Rekey(pos[0], pos[1], config);
+    } else if (cmd == "attack") {
+        steganography::AttackConfig config;
+#ifndef STEG_WITH_JPEG
+        if (args.options.contains("--jpeg-quality")) {
+            PrintErrAndExit("'--jpeg-quality' needs a build with JPEG "
+                            "support");
+        }
+#endif
+        const uint64_t kQuality = GetLimitOption(args, "--jpeg-quality", 0);
+        if (kQuality > 100) {
+            PrintErrAndExit("invalid value for option '--jpeg-quality', "
+                            "expected 0-100");
+        }
+        config.jpeg_quality = static_cast<int>(kQuality);
+        config.scale = GetNumericOption(args, "--scale", 1.0);
+        config.noise = GetNumericOption(args, "--noise", 0.0);
+        steganography::AttackReport report;
+        steganography::RetCode rc =
+            steganography::SimulateAttack(pos[0], config, report);
+        if (rc == steganography::RetCode::kSuccess) {
+            PrintAttackReport(report);
+        }
+        return rc;
+    } else if (cmd == "detect") {
+        std::string format = args.options.contains("--format")
+                                 ? GetOption(args, "--format")
+                                 : "text";
+        if ((format != "text") && (format != "json")) {
+            PrintErrAndExit("invalid value for option '--format'");
+        }
+        steganography::DetectionReport report;
+        steganography::RetCode rc = steganography::Detect(pos[0], report);
+        if (rc != steganography::RetCode::kSuccess) {
+            return rc;
+        }
+        if (format == "json") {
+            std::cout << steganography::DetectionReportJson(report)
+                      << std::endl;
+        } else {
+            PrintDetectReport(report);
+        }
+        return rc;
+    } else if (cmd == "visual-attack") {
+        return steganography::VisualAttack(pos[0], pos[1],
+                                           GetVisualAttackConfig(args));
+    } else if (cmd == "verify-pair") {
+        double bits = GetNumericOption(args, "--bits", 4);
+        if ((bits < 1) || (bits > 8) || (bits != std::floor(bits))) {
+            PrintErrAndExit("invalid value for option '--bits', expected 1-8");
+        }
+        steganography::PairReport report;
+        steganography::RetCode rc = steganography::VerifyPair(
+            pos[0], pos[1], static_cast<int>(bits), report);
+        if (rc == steganography::RetCode::kInvalidDimensions) {
+            PrintErrAndExit("the images have different dimensions");
+        }
+        if ((rc == steganography::RetCode::kSuccess) ||
+            (rc == steganography::RetCode::kOutOfBandChanges)) {
+            PrintPairReport(report, static_cast<int>(bits));
+        }
+        return rc;
+    } else if (cmd == "scrub") {
+        steganography::ScrubConfig config;
+        double bits = GetNumericOption(args, "--bits", config.bits);
+        if ((bits < 1) || (bits > 8) || (bits != std::floor(bits))) {
+            PrintErrAndExit("invalid value for option '--bits', expected 1-8");
+        }
+        config.bits = static_cast<int>(bits);
+        config.seed = GetSeedOption(args);
+        config.create_dirs = args.options.contains("--create-dirs");
+        config.output_format = GetOutputFormatOption(args, false);
+        steganography::ScrubReport report;
+        steganography::RetCode rc =
+            steganography::Scrub(pos[0], pos[1], config, report);
+        if (rc == steganography::RetCode::kSuccess) {
+            if (report.had_payload) {
+                std::cout << "removed a packed payload" << std::endl;
+            }
+            std::cout << "changed " << std::fixed << std::setprecision(1)
+                      << report.changed * 100.0 << "% of channel values"
+                      << std::endl;
+        }
+        return rc;
+    } else if (cmd == "seal") {
+        steganography::SealConfig config;
+        config.keyfile = GetOption(args, "--keyfile");
+        config.create_dirs = args.options.contains("--create-dirs");
+        if (config.keyfile.empty()) {
+            PrintErrAndExit("seal needs the --keyfile to sign with");
+        }
+        steganography::SealSigner signer;
+        steganography::RetCode rc =
+            steganography::SealImage(pos[0], pos[1], config, signer);
+        if (rc == steganography::RetCode::kSuccess) {
+            std::cout << "signer: " << steganography::SignerToHex(signer)
+                      << std::endl;
+        }
+        return rc;
+    } else if (cmd == "verify-seal") {
+        steganography::SealSigner expected;
+        if (args.options.contains("--signer") &&
+            !steganography::SignerFromHex(GetOption(args, "--signer"),
+                                          expected)) {
+            PrintErrAndExit("invalid value for option '--signer', expected "
+                            "64 hex digits");
+        }
+        steganography::SealSigner signer;
+        steganography::RetCode rc = steganography::VerifySeal(pos[0], signer);
+        if ((rc == steganography::RetCode::kSuccess) ||
+            (rc == steganography::RetCode::kSealBroken)) {
+            std::cout << "seal:   "
+                      << ((rc == steganography::RetCode::kSuccess) ? "intact"
+                                                                   : "broken")
+                      << std::endl;
+            std::cout << "signer: " << steganography::SignerToHex(signer)
+                      << std::endl;
+        }
+        if ((rc == steganography::RetCode::kSuccess) &&
+            args.options.contains("--signer") && (signer != expected)) {
+            return steganography::RetCode::kKeyMismatch;
+        }
+        return rc;
+    } else if (cmd == "compare") {
+        steganography::CompareConfig config;
+        if (args.options.contains("--amplify")) {
+            double amplify = GetNumericOption(args, "--amplify", 0);
+            if ((amplify < 1) || (amplify > 255) ||
+                (amplify != std::floor(amplify))) {
+                PrintErrAndExit(
+                    "invalid value for option '--amplify', expected 1-255");
+            }
+            config.amplify = static_cast<int>(amplify);
+        }
+        config.create_dirs = args.options.contains("--create-dirs");
+        steganography::RetCode rc =
+            steganography::Compare(pos[0], pos[1], pos[2], config);
+        if (rc == steganography::RetCode::kInvalidDimensions) {
+            PrintErrAndExit("the images have different dimensions");
+        }
+        return rc;
+    } else if (cmd == "heatmap") {
+        steganography::HeatmapConfig config;
+        double block = GetNumericOption(args, "--block", config.block);
+        if ((block < 2) || (block != std::floor(block)) ||
+            (block > std::numeric_limits<int>::max())) {
+            PrintErrAndExit("invalid value for option '--block'");
+        }
+        config.block = static_cast<int>(block);
+        config.opacity = GetNumericOption(args, "--opacity", config.opacity);
+        if ((config.opacity < 0.0) || (config.opacity > 1.0)) {
+            PrintErrAndExit(
+                "invalid value for option '--opacity', expected 0-1");
+        }
+        config.create_dirs = args.options.contains("--create-dirs");
+        return steganography::Heatmap(pos[0], pos[1], config);
+    } else if (cmd == "scan") {
+        steganography::ScanConfig config;
+        config.recursive = args.options.contains("--recursive");
+        config.threshold =
+            GetNumericOption(args, "--threshold", config.threshold);
+        std::vector<steganography::ScanResult> results;
+        steganography::RetCode rc =
+            steganography::ScanDirectory(pos[0], config, results);
+        if (rc == steganography::RetCode::kSuccess) {
+            steganography::WriteScanReport(results, GetScanFormat(args),
+                                           std::cout);
+        }
+        return rc;
+    } else if (cmd == "sanitize") {
+        steganography::SanitizeConfig config;
+        config.outdir = GetOption(args, "--out");
+        config.recursive = args.options.contains("--recursive");
+        double bits = GetNumericOption(args, "--bits", config.bits);
+        if ((bits < 1) || (bits > 8) || (bits != std::floor(bits))) {
+            PrintErrAndExit("invalid value for option '--bits', expected 1-8");
+        }
+        config.bits = static_cast<int>(bits);
+        config.seed = GetSeedOption(args);
+        std::vector<steganography::SanitizeResult> results;
+        steganography::RetCode rc =
+            steganography::SanitizeDirectory(pos[0], config, results);
+        for (const auto& result : results) {
+            if (result.rc != steganography::RetCode::kSuccess) {
+                std::cerr << result.path << ": " << ErrorMessage(result.rc)
+                          << std::endl;
+                rc = result.rc;
+                continue;
+            }
+            std::vector<std::string> removed;
+            if (result.had_payload) {
+                removed.push_back("payload");
+            }
+            if (result.had_trailer) {
+                removed.push_back("trailer");
+            }
+            if (result.had_carrier_chunk) {
+                removed.push_back("carrier chunk");
+            }
+            std::cout << result.output;
+            for (std::size_t i = 0; i < removed.size(); ++i) {
+                std::cout << ((i == 0) ? ": removed " : ", ") << removed[i];
+            }
+            std::cout << std::endl;
+        }
+        return rc;
+    } else if (cmd == "batch") {
+        steganography::BatchManifest manifest;
+        std::size_t line = 0;
+        steganography::RetCode rc =
+            steganography::ReadManifest(pos[0], manifest, line);
+        if (rc == steganography::RetCode::kInvalidArgument) {
+            PrintErrAndExit("invalid manifest at line " +
+                            std::to_string(line));
+        }
+        if (rc != steganography::RetCode::kSuccess) {
+            return rc;
+        }
+        steganography::BatchConfig config;
+        config.create_dirs = args.options.contains("--create-dirs");
+        if (args.options.contains("--threads")) {
+            config.threads = GetThreadsOption(args);
+        }
+        config.fail_fast = args.options.contains("--fail-fast");
+        config.max_bytes_per_second = GetSizeOption(args, "--max-rate");
+        config.max_images_per_minute =
+            GetNumericOption(args, "--max-images", 0.0);
+        if (config.max_images_per_minute < 0.0) {
+            PrintErrAndExit("invalid value for option '--max-images'");
+        }
+        config.low_priority = args.options.contains("--low-priority");
+        config.state = args.options.contains("--state")
+                           ? GetOption(args, "--state")
+                           : pos[1] + ".state";
+        if (config.create_dirs) {
+            rc = steganography::CreateParentDirectories(pos[1]);
+            if (rc != steganography::RetCode::kSuccess) {
+                return rc;
+            }
+        }
+        std::vector<steganography::BatchResult> results;
+        steganography::BatchSummary summary;
+        rc = steganography::RunBatch(manifest, config, results, summary);
+        if (rc == steganography::RetCode::kInvalidArgument) {
+            PrintErrAndExit("two manifest rows write the same output");
+        }
+        if (rc != steganography::RetCode::kSuccess) {
+            return rc;
+        }
+        for (const auto& result : results) {
+            if ((result.rc == steganography::RetCode::kSuccess) &&
+                !result.resumed) {
+                std::cout << result.job.cover << " -> " << result.job.output
+                          << std::endl;
+            }
+        }
+        if (summary.resumed > 0) {
+            std::cout << summary.resumed << " rows done by an earlier run"
+                      << std::endl;
+        }
+        rc = steganography::WriteBatchResults(pos[1], manifest.separator,
+                                              results);
+        if (rc != steganography::RetCode::kSuccess) {
+            return rc;
+        }
+        for (const auto& error : summary.errors) {
+            std::cerr << "line " << error.line << ", " << error.path << ": "
+                      << ErrorMessage(error.rc) << std::endl;
+        }
+        if (summary.outcome == steganography::BatchOutcome::kFailure) {
+            return summary.errors.empty() ? steganography::RetCode::kSuccess
+                                          : summary.errors.front().rc;
+        }
+        if (summary.outcome == steganography::BatchOutcome::kPartial) {
+            /* set apart from a failed run, the outputs that did succeed
+             * are all in place */
+            const int kExitPartial = 2;
+            std::cerr << summary.succeeded << " rows succeeded, "
+                      << summary.failed << " failed";
+            if (summary.skipped > 0) {
+                std::cerr << ", " << summary.skipped << " skipped";
+            }
+            std::cerr << ", see " << pos[1] << std::endl;
+            exit(kExitPartial);
+        }
+        return steganography::RetCode::kSuccess;
+    } else if (cmd == "watch") {
+        return steganography::Watch(GetWatchConfig(args));
+    } else if (cmd == "preview") {
+        return steganography::Preview(pos[0], GetPreviewConfig(args),
+                                      std::cout);
+    } else if (cmd == "tui") {
+        if (!isatty(STDIN_FILENO) || !isatty(STDOUT_FILENO)) {
+            PrintErrAndExit("tui requires an interactive terminal");
+        }
+        steganography::TuiConfig config;
+        config.cover = (pos.size() > 0) ? pos[0] : "";
+        config.secret = (pos.size() > 1) ? pos[1] : "";
+        config.output = (pos.size() > 2) ? pos[2] : "";
+        config.describe_error = ErrorMessage;
+        return steganography::RunTui(config);
+    } else if (cmd == "gen-cover") {
+        return steganography::GenerateCover(pos[0], GetCoverGenConfig(args));
+    } else if (cmd == "rank-covers") {
+        std::vector<steganography::CoverScore> scores;
+        steganography::RetCode rc = steganography::RankCovers(
+            pos[0], GetSizeOption(args, "--payload-size"), scores);
+        if (rc == steganography::RetCode::kSuccess) {
+            PrintCoverRanking(scores);
+        }
+        return rc;
+    }
+#ifdef STEG_WITH_SERVER
+    if (cmd == "serve") {
+        steganography::ServerConfig config;
+        double port = GetNumericOption(args, "--port", config.port);
+        if ((port < 1) || (port > std::numeric_limits<uint16_t>::max())) {
+            PrintErrAndExit("invalid value for option '--port'");
+        }
+        config.port = static_cast<uint16_t>(port);
+        if (args.options.contains("--bind")) {
+            config.address = GetOption(args, "--bind");
+        }
+        config.image_limits.max_pixels = GetLimitOption(
+            args, "--max-pixels", config.image_limits.max_pixels);
+        config.image_limits.max_decoded_bytes =
+            GetLimitOption(args, "--max-image-bytes",
+                           config.image_limits.max_decoded_bytes);
+        config.threads = GetThreadsOption(args);
+        config.memory_budget = GetSizeOption(args, "--memory-budget");
+        return steganography::Serve(config);
+    }
+#endif
+    PrintUsage();
+    return steganography::RetCode::kSuccess;
+}
+
+char RoleOf(const Command& command, std::size_t arg) {
+    return command.roles[std::min(arg, command.roles.size() - 1)];
+}
+
+/* positional args playing the given role, inputs include key and decoy */
+std::vector<std::string> PathsWithRole(const ParsedArgs& args,
+                                       const Command& command, char role) {
+    std::vector<std::string> paths;
+    for (std::size_t i = 0; i < args.positional.size(); ++i) {
+        if (RoleOf(command, i) == role) {
+            paths.push_back(args.positional[i]);
+        }
+    }
+    if (role == 'i') {
+        for (const char* option :
+             {"--keyfile", "--decoy", "--in", "--secret", "--reference",
+              "--identity", "--recipient-passwords", "--layer-passwords",
+              "--red", "--green", "--blue", "--manifest"}) {
+            if (args.options.contains(option)) {
+                paths.push_back(GetOption(args, option));
+            }
+        }
+    } else if (role == 'o') {
+        for (const char* option : {"--out", "--restore-cover", "--mask"}) {
+            if (args.options.contains(option)) {
+                paths.push_back(GetOption(args, option));
+            }
+        }
+    }
+    return paths;
+}
+
+#if defined(STEG_WITH_HTTP) || defined(STEG_WITH_S3)
+/* last path segment of a URL, used to name the local copy */
+std::string UrlFileName(const std::string& url) {
+    std::string path = url.substr(0, url.find_first_of("?#"));
+    std::string name = path.substr(path.find_last_of('/') + 1);
+    return (name.empty() || (name == ".") || (name == "..")) ? "download"
+                                                             : name;
+}
+
+bool IsRemote(const std::string& path) {
+#ifdef STEG_WITH_HTTP
+    if (steganography::IsUrl(path)) {
+        return true;
+    }
+#endif
+#ifdef STEG_WITH_S3
+    if (steganography::IsS3Url(path)) {
+        return true;
+    }
+#endif
+    return false;
+}
+
+/* stages remote inputs and outputs in a scratch directory removed when
+ * done, inputs are downloaded up front and outputs uploaded afterwards */
+class RemotePaths {
+   public:
+    RemotePaths() = default;
+    ~RemotePaths() {
+        std::error_code ec;
+        if (!dir_.empty()) {
+            std::filesystem::remove_all(dir_, ec);
+        }
+    }
+    RemotePaths(const RemotePaths&) = delete;
+    RemotePaths& operator=(const RemotePaths&) = delete;
+
+    /* points every remote path in args at a local copy */
+    steganography::RetCode Stage(ParsedArgs& args, const Command& command) {
+        for (std::size_t i = 0; i < args.positional.size(); ++i) {
+            std::string& path = args.positional[i];
+            if (!IsRemote(path) || (RoleOf(command, i) == '-')) {
+                continue;
+            }
+
+            /* keep the file name so pack stores the file under it */
+            std::filesystem::path local = Scratch() / std::to_string(i);
+            std::error_code ec;
+            std::filesystem::create_directories(local, ec);
+            if (ec) {
+                return steganography::RetCode::kIoError;
+            }
+            local /= UrlFileName(path);
+
+            if (RoleOf(command, i) == 'o') {
+                outputs_.emplace_back(local, path);
+            } else {
+                steganography::RetCode rc = Download(path, local.string());
+                if (rc != steganography::RetCode::kSuccess) {
+                    return rc;
+                }
+            }
+            path = local.string();
+        }
+        return steganography::RetCode::kSuccess;
+    }
+
+    /* uploads staged outputs, a directory output is uploaded file by file
+     * under the remote path as a prefix */
+    steganography::RetCode Publish() {
+        for (const auto& [local, remote] : outputs_) {
+            if (!std::filesystem::is_directory(local)) {
+                steganography::RetCode rc = Upload(local.string(), remote);
+                if (rc != steganography::RetCode::kSuccess) {
+                    return rc;
+                }
+                continue;
+            }
+            for (const auto& entry :
+                 std::filesystem::recursive_directory_iterator(local)) {
+                if (!entry.is_regular_file()) {
+                    continue;
+                }
+                std::string relative =
+                    std::filesystem::relative(entry.path(), local)
+                        .generic_string();
+                steganography::RetCode rc =
+                    Upload(entry.path().string(), remote + "/" + relative);
+                if (rc != steganography::RetCode::kSuccess) {
+                    return rc;
+                }
+            }
+        }
+        return steganography::RetCode::kSuccess;
+    }
+
+   private:
+    std::filesystem::path Scratch() {
+        if (dir_.empty()) {
+            dir_ = std::filesystem::temp_directory_path() /
+                   ("steganography-remote-" +
+                    std::to_string(std::random_device{}()));
+        }
+        return dir_;
+    }
+
+    static steganography::RetCode Download(const std::string& remote,
+                                           const std::string& local) {
+#ifdef STEG_WITH_S3
+        if (steganography::IsS3Url(remote)) {
+            return steganography::S3Download(
+                remote, local, steganography::S3ConfigFromEnvironment());
+        }
+#endif
+#ifdef STEG_WITH_HTTP
+        if (steganography::IsUrl(remote)) {
+            return steganography::FetchUrl(remote, local,
+                                           steganography::FetchConfig{});
+        }
+#endif
+        return steganography::RetCode::kInvalidArgument;
+    }
+
+    static steganography::RetCode Upload(const std::string& local,
+                                         const std::string& remote) {
+#ifdef STEG_WITH_S3
+        if (steganography::IsS3Url(remote)) {
+            return steganography::S3Upload(
+                local, remote, steganography::S3ConfigFromEnvironment());
+        }
+#endif
+        /* plain URLs are read only */
+        (void)local;
+        (void)remote;
+        return steganography::RetCode::kInvalidArgument;
+    }
+
+    std::filesystem::path dir_;
+    std::vector<std::pair<std::filesystem::path, std::string>> outputs_;
+};
+#endif
+
+/* the first output that names one of the inputs, empty when none do */
+std::string OutputNamingInput(const ParsedArgs& args, const Command& command) {
+    for (const auto& output : PathsWithRole(args, command, 'o')) {
+        for (const auto& input : PathsWithRole(args, command, 'i')) {
+            if (steganography::SamePath(output, input)) {
+                return output;
+            }
+        }
+    }
+    return "";
+}
+
+steganography::RetCode RunWithRemotePaths(const std::string& cmd,
+                                          const ParsedArgs& args,
+                                          const Command& command) {
+    if (!OutputNamingInput(args, command).empty()) {
+        return steganography::RetCode::kOutputIsInput;
+    }
+#if defined(STEG_WITH_HTTP) || defined(STEG_WITH_S3)
+    RemotePaths remote;
+    ParsedArgs local_args = args;
+    steganography::RetCode rc = remote.Stage(local_args, command);
+    if (rc != steganography::RetCode::kSuccess) {
+        return rc;
+    }
+    rc = RunCommand(cmd, local_args);
+    if (rc != steganography::RetCode::kSuccess) {
+        return rc;
+    }
+    return remote.Publish();
+#else
+    (void)command;
+    return RunCommand(cmd, args);
+#endif
+}
+
+Diagnostic Diagnose(steganography::RetCode rc, const ParsedArgs& args,
+                    const Command& command) {
+    std::vector<std::string> inputs = PathsWithRole(args, command, 'i');
+    std::vector<std::string> outputs = PathsWithRole(args, command, 'o');
+    std::string first_input = inputs.empty() ? "" : inputs.front();
+    std::string first_output = outputs.empty() ? "" : outputs.front();
+    std::string keyfile = GetOption(args, "--keyfile");
+
+    Diagnostic diag;
+    diag.message = ErrorMessage(rc);
+    switch (rc) {
+        case steganography::RetCode::kSuccess:
+            break;
+        case steganography::RetCode::kInvalidFileFormat:
+            diag.phase = "reading input";
+#ifdef STEG_WITH_JPEG
+            diag.hint = "convert the image to PNG or JPEG";
+#else
+            diag.hint = "convert the image to PNG";
+#endif
+            for (const auto& input : inputs) {
+                if (std::filesystem::is_regular_file(input) &&
+                    (input != keyfile) &&
+                    (steganography::GetImageType(input) ==
+                     steganography::ImageType::kUnknown)) {
+                    diag.path = input;
+                    break;
+                }
+            }
+            /* the extension is never looked at, so name what the content
+             * turned out to be */
+            if (!diag.path.empty() &&
+                (std::filesystem::file_size(diag.path) == 0)) {
+                diag.message = "input file is empty";
+                diag.hint = "check the file downloaded completely";
+            } else if (!diag.path.empty()) {
+                const steganography::SniffedFormat kFormat =
+                    steganography::SniffImageFormat(diag.path);
+                if (!kFormat.name.empty()) {
+                    diag.message = "input contains " + kFormat.name +
+                                   " data, which this build cannot read";
+                }
+                if (!kFormat.feature.empty()) {
+                    diag.hint = kFormat.name + " inputs need a build with " +
+                                kFormat.feature;
+                }
+            }
+            break;
+        case steganography::RetCode::kFileNotFound:
+            diag.phase = "reading input";
+            diag.hint = "check the spelling of the path";
+            for (const auto& input : inputs) {
+#if defined(STEG_WITH_HTTP) || defined(STEG_WITH_S3)
+                if (IsRemote(input)) {
+                    continue; /* downloaded before the command ran */
+                }
+#endif
+                if (!std::filesystem::exists(input)) {
+                    diag.path = input;
+                    break;
+                }
+            }
+            if (diag.path.find("://") != std::string::npos) {
+                diag.hint = diag.path.starts_with("s3://")
+                                ? "s3:// paths need a build with STEG_WITH_S3"
+                                : "URL inputs need a build with STEG_WITH_HTTP";
+            }
+            if (!keyfile.empty() && (diag.path == keyfile)) {
+                diag.hint = "create a key with 'steganography keygen " +
+                            keyfile + "'";
+            }
+            if (diag.path.empty() && args.options.contains("--avoid-salient")) {
+                diag.phase = "finding salient regions";
+                diag.hint = "the face cascade set with STEG_FACE_CASCADE at "
+                            "build time could not be loaded";
+            }
+            break;
+        case steganography::RetCode::kInvalidDimensions:
+            diag.path = (inputs.size() > 1) ? inputs[1] : first_input;
+            diag.phase = "embedding";
+            diag.hint = "shrink the secret image, choose a larger cover, or "
+                        "pass --fit";
+            if (args.options.contains("--secret-size")) {
+                diag.hint = "--secret-size must fit inside the cover and "
+                            "leave its last row partly free";
+            }
+            if (args.options.contains("--strict")) {
+                diag.hint = "the secret would be shrunk by more than "
+                            "--max-shrink, choose a larger cover or raise it";
+            }
+            break;
+        case steganography::RetCode::kInsufficientCapacity:
+            diag.path = first_input;
+            diag.phase = "embedding";
+            diag.hint =
+                "choose a larger cover, pass --compress to pack-dir, or run "
+                "rank-covers to find one";
+            if (args.options.contains("--border") ||
+                args.options.contains("--avoid-salient")) {
+                diag.hint = "--border and --avoid-salient leave fewer pixels "
+                            "to hide in, widen the border or choose a larger "
+                            "cover";
+            }
+            break;
+        case steganography::RetCode::kPayloadNotFound:
+            diag.path = first_input;
+            diag.phase = "extracting";
+            diag.hint =
+                "make sure the image was produced by pack and never saved as "
+                "JPEG";
+            if (args.options.contains("--restore-cover")) {
+                diag.hint = "--restore-cover needs an image packed with "
+                            "--reversible";
+            }
+            if (command.options.contains("--signer")) {
+                diag.phase = "verifying seal";
+                diag.hint = "the image was never sealed, or its low bits "
+                            "holding the seal were overwritten";
+            }
+            break;
+        case steganography::RetCode::kInvalidPayload:
+            diag.path = first_input;
+            diag.phase = "extracting";
+            diag.hint = "the image was likely modified after it was packed";
+            break;
+        case steganography::RetCode::kIoError:
+            diag.path = first_output;
+            diag.phase = "writing output";
+            diag.hint = "check the output location is writable";
+            for (const auto& output : outputs) {
+                std::filesystem::path parent =
+                    std::filesystem::path(output).parent_path();
+                if (!parent.empty() && !std::filesystem::exists(parent) &&
+                    (output.find("://") == std::string::npos)) {
+                    diag.path = output;
+                    diag.hint = "output directory " + parent.string() +
+                                " does not exist, create it";
+                    diag.hint += command.options.contains("--create-dirs")
+                                     ? " or pass --create-dirs"
+                                     : " first";
+                    break;
+                }
+            }
+            if (command.options.contains("--port")) {
+                diag.message = "failed to listen for connections";
+                diag.phase = "binding socket";
+                diag.hint = "the port may be in use or require privileges";
+            }
+            break;
+        case steganography::RetCode::kUnsupportedFormat:
+            diag.path = first_input;
+            diag.phase = "extracting";
+            diag.hint = "upgrade steganography to read this image";
+            break;
+        case steganography::RetCode::kFileExists:
+            diag.phase = "writing output";
+            diag.hint = "remove the existing file or choose another name";
+            for (const auto& output : outputs) {
+                if (std::filesystem::exists(output)) {
+                    diag.path = output;
+                    break;
+                }
+            }
+            break;
+        case steganography::RetCode::kInvalidKey:
+            diag.path = keyfile;
+            diag.phase = "loading key";
+            diag.hint = "pass a key created by 'steganography keygen'";
+            if (args.options.contains("--identity")) {
+                diag.path = GetOption(args, "--identity");
+                diag.hint = "pass an identity created by 'steganography "
+                            "keygen-identity'";
+            }
+            break;
+        case steganography::RetCode::kInvalidArgument:
+            diag.phase = "parsing options";
+            diag.hint = "run 'steganography help' for usage";
+            /* the least specific cause first, any option below overrides it */
+            if (!first_output.empty() &&
+                (steganography::LosslessImageType(first_output) ==
+                 steganography::ImageType::kPnm) &&
+                (steganography::PnmKind(first_output) != '6')) {
+                diag.path = first_output;
+                diag.hint = "a .pgm or .pbm keeps one gray value per pixel, "
+                            "which cannot hold the hidden data, name the "
+                            "output .ppm";
+            }
+            if (args.options.contains("--password") && keyfile.empty()) {
+                diag.hint = "--password requires --keyfile for this command";
+            }
+            if (args.options.contains("--bind")) {
+                diag.hint = "--bind expects an IPv4 address such as 0.0.0.0";
+            }
+            if (args.options.contains("--in")) {
+                diag.hint = "--in and --out must be different directories";
+            }
+            if (args.options.contains("--threshold")) {
+                diag.hint = "--threshold expects a value between 0 and 1";
+            }
+            if ((args.options.contains("--checksums") ||
+                 args.options.contains("--ecc") ||
+                 args.options.contains("--interleave") ||
+                 args.options.contains("--copies") ||
+                 args.options.contains("--sync")) &&
+                args.options.contains("--password")) {
+                diag.hint = "--checksums, --ecc, --interleave, --copies, and "
+                            "--sync cannot be combined with --password";
+            }
+            if (args.options.contains("--sync") &&
+                args.options.contains("--copies")) {
+                diag.hint = "--sync cannot be combined with --copies";
+            }
+            if ((args.options.contains("--differential") ||
+                 args.options.contains("--reference") ||
+                 args.options.contains("--pair-swap") ||
+                 args.options.contains("--reversible") ||
+                 args.options.contains("--rotate-channels") ||
+                 args.options.contains("--restore-cover")) &&
+                args.options.contains("--password")) {
+                diag.hint = "--differential, --reference, --pair-swap, "
+                            "--reversible, --rotate-channels, and "
+                            "--restore-cover cannot be combined with "
+                            "--password";
+            }
+            if ((args.options.contains("--recipient") ||
+                 args.options.contains("--recipient-passwords")) &&
+                args.options.contains("--password")) {
+                diag.hint = "--recipient and --recipient-passwords cannot be "
+                            "combined with --password";
+            }
+            if (args.options.contains("--differential") +
+                    args.options.contains("--pair-swap") +
+                    args.options.contains("--reversible") +
+                    args.options.contains("--rotate-channels") >
+                1) {
+                diag.hint = "only one of --differential, --pair-swap, "
+                            "--reversible, and --rotate-channels can be given";
+            }
+            if (args.options.contains("--stride")) {
+                diag.hint = "--stride must share no factor with the number "
+                            "of pixels visited, try a prime that does not "
+                            "divide it";
+            }
+            if ((args.options.contains("--traversal") ||
+                 args.options.contains("--stride") ||
+                 args.options.contains("--border") ||
+                 args.options.contains("--avoid-salient")) &&
+                (args.options.contains("--differential") ||
+                 args.options.contains("--pair-swap") ||
+                 args.options.contains("--reversible") ||
+                 args.options.contains("--rotate-channels") ||
+                 args.options.contains("--password"))) {
+                diag.hint = "--traversal, --stride, --border, and "
+                            "--avoid-salient only reorder the usual layout, "
+                            "they cannot be combined with another layout or "
+                            "--password";
+            }
+            if (args.options.contains("--rotate-channels") && keyfile.empty()) {
+                diag.hint = "--rotate-channels draws its schedule from the "
+                            "--keyfile, pass one";
+            }
+            if (!command.options.contains("--copies") &&
+                command.options.contains("--keyfile") && keyfile.empty() &&
+                !args.options.contains("--password")) {
+                diag.hint = "the copies were scattered with a key, pass the "
+                            "--keyfile used to pack";
+            }
+            if (command.options.contains("--identity") &&
+                command.options.contains("--recipient")) {
+                diag.hint = "only images packed with --recipient or "
+                            "--recipient-passwords can be rekeyed";
+                if (command.max_args > 2) { /* append */
+                    diag.hint = "a name is already in the payload, or its "
+                                "layout or --recipient options do not allow "
+                                "appending, see 'steganography inspect'";
+                }
+            }
+            if (command.options.contains("--layer-passwords")) { /* pack */
+                /* each input is named after its last component */
+                std::set<std::string> names;
+                for (std::size_t i = 2; i < args.positional.size(); ++i) {
+                    std::filesystem::path input =
+                        std::filesystem::path(args.positional[i])
+                            .lexically_normal();
+                    if (!input.has_filename()) {
+                        input = input.parent_path();
+                    }
+                    if (!names.insert(input.filename().string()).second) {
+                        diag.path = args.positional[i];
+                        diag.hint = "two inputs would be packed under the "
+                                    "same name, rename one or pack the "
+                                    "directory holding both";
+                    }
+                }
+            }
+            break;
+        case steganography::RetCode::kKeyMismatch:
+            diag.path = keyfile;
+            diag.phase = "authenticating";
+            diag.hint = "use the key file the image was merged with";
+            if (command.options.contains("--identity")) {
+                diag.path = GetOption(args, "--identity");
+                diag.phase = "decrypting";
+                diag.hint =
+                    args.options.contains("--identity") ||
+                            args.options.contains("--recipient-password")
+                        ? "the image was not packed for this identity or "
+                          "password"
+                        : "the image was packed for recipients, pass one of "
+                          "their --identity files or a --recipient-password";
+            }
+            if (args.options.contains("--signer")) {
+                diag.path = first_input;
+                diag.phase = "verifying seal";
+                diag.hint = "the seal is intact but was made with another "
+                            "key than --signer";
+            }
+            break;
+        case steganography::RetCode::kSealBroken:
+            diag.path = first_input;
+            diag.phase = "verifying seal";
+            diag.hint = "the visible content no longer matches the signature, "
+                        "the image was edited or re-encoded";
+            break;
+        case steganography::RetCode::kConversionRefused:
+            diag.phase = "reading input";
+            diag.hint = args.options.contains("--strict")
+                            ? "drop --strict to convert the input, or convert "
+                              "it to 8 bit RGB first"
+                            : "convert the input to an 8 bit RGB PNG first";
+            break;
+        case steganography::RetCode::kIncompleteParts:
+            diag.phase = "joining parts";
+            diag.hint = "pass every part image split wrote, the manifest "
+                        "names the missing ones";
+            break;
+        case steganography::RetCode::kPasswordMismatch:
+            diag.path = first_input;
+            diag.phase = "authenticating";
+            diag.hint = "check the password passed with --password";
+            break;
+        case steganography::RetCode::kAuthenticationFailed:
+            diag.path = first_input;
+            diag.phase = "decrypting";
+            diag.hint = "check the password passed with --password";
+            break;
+        case steganography::RetCode::kNetworkError:
+            diag.phase = "transferring remote file";
+            for (const auto& path : args.positional) {
+                if (path.find("://") != std::string::npos) {
+                    diag.path = path;
+                    break;
+                }
+            }
+            diag.hint =
+                diag.path.starts_with("s3://")
+                    ? "check the bucket, key, and AWS_* credential variables"
+                    : "check the URL is reachable and the image is under 64 "
+                      "MB";
+            break;
+        case steganography::RetCode::kClipboardUnavailable:
+            diag.phase = "accessing clipboard";
+            diag.hint =
+                "install wl-clipboard, xclip, or xsel and run inside a "
+                "graphical session";
+            break;
+        case steganography::RetCode::kOutOfBandChanges:
+            diag.path = (inputs.size() > 1) ? inputs[1] : first_input;
+            diag.phase = "comparing images";
+            diag.hint = "the image was edited after embedding or used more "
+                        "low bits than --bits, the first changes are listed "
+                        "above";
+            break;
+        case steganography::RetCode::kSameInput:
+            diag.path = (inputs.size() > 1) ? inputs[1] : first_input;
+            diag.phase = "reading input";
+            diag.hint = "pass a different image as the secret";
+            break;
+        case steganography::RetCode::kImageTooLarge:
+            diag.phase = "reading input";
+            for (const auto& input : inputs) {
+                steganography::ImageType type =
+                    steganography::GetImageType(input);
+                if ((type != steganography::ImageType::kUnknown) &&
+                    (steganography::CheckImageLimits(input, type) ==
+                     steganography::RetCode::kImageTooLarge)) {
+                    diag.path = input;
+                    break;
+                }
+            }
+            diag.hint = "the image's dimensions would take too much memory "
+                        "to decode";
+            if (diag.path.empty() && args.options.contains("--memory-budget")) {
+                /* no single image is over the limits, only all of them */
+                diag.hint = "the images together would take more than "
+                            "--memory-budget, raise it or use smaller images";
+            }
+            break;
+        case steganography::RetCode::kOutputIsInput:
+            diag.path = OutputNamingInput(args, command);
+            if (diag.path.empty()) {
+                diag.path = first_output;
+            }
+            diag.phase = "checking paths";
+            diag.hint = "choose an output path that is not one of the inputs";
+            break;
+        case steganography::RetCode::kPayloadDamaged:
+            diag.path = first_input;
+            diag.phase = "verifying checksums";
+            diag.hint = "the image rows listed above were modified after it "
+                        "was packed";
+            for (const auto& output : outputs) {
+                std::filesystem::path report =
+                    std::filesystem::path(output) /
+                    steganography::kDamageReportName;
+                if (std::filesystem::exists(report)) {
+                    diag.hint = "the surviving files were written, " +
+                                report.string() + " lists what was lost";
+                    break;
+                }
+            }
+            break;
+    }
+    return diag;
+}
+
+int main(int argc, char** argv) {
+    const std::size_t kUnbounded = std::numeric_limits<std::size_t>::max();
+    const std::map<std::string, Command> kCommands = {
+        {"merge",
+         {3,
+          3,
+          {"--keyfile", "--password", "--stats", "--create-dirs", "--fit",
+           "--max-shrink", "--strict", "--linear", "--sharpen", "--secret-size",
+           "--color-space", "--dither", "--block-bits", "--output-format",
+           "--threads", "--memory-budget", "--method", "--mask"},
+          "iio"}},
+        {"unmerge",
+         {2,
+          2,
+          {"--keyfile", "--password", "--create-dirs", "--color-space",
+           "--block-bits", "--output-format", "--threads", "--memory-budget",
+           "--method", "--try-all", "--channel", "--bits"},
+          "io"}},
+        {"merge-channels",
+         {2,
+          2,
+          {"--red", "--green", "--blue", "--bits", "--create-dirs"},
+          "io"}},
+        {"merge-qr",
+         {3, 3, {"--keyfile", "--password", "--create-dirs"}, "io-"}},
+        {"unmerge-qr", {1, 1, {"--keyfile", "--password"}, "i"}},
+        {"merge-text",
+         {3, 3, {"--keyfile", "--password", "--create-dirs"}, "iio"}},
+#ifdef STEG_WITH_CLIPBOARD
+        /* the clipboard stands in for the FILE and OUT_DIR args */
+        {"pack",
+         {2,
+          kUnbounded,
+          {"--password", "--decoy", "--decoy-password", "--seed",
+           "--create-dirs", "--checksums", "--ecc", "--interleave", "--copies",
+           "--keyfile", "--sync", "--from-clipboard", "--differential",
+           "--pair-swap", "--reversible", "--rotate-channels", "--traversal",
+           "--stride", "--border", "--avoid-salient", "--recipient",
+           "--recipient-passwords", "--layer-passwords", "--output-format"},
+          "ioi"}},
+        {"unpack",
+         {1,
+          2,
+          {"--password", "--keyfile", "--reference", "--restore-cover",
+           "--identity", "--recipient-password", "--traversal", "--stride",
+           "--border", "--avoid-salient", "--to-clipboard"},
+          "io"}},
+#else
+        {"pack",
+         {3,
+          kUnbounded,
+          {"--password", "--decoy", "--decoy-password", "--seed",
+           "--create-dirs", "--checksums", "--ecc", "--interleave", "--copies",
+           "--keyfile", "--sync", "--differential", "--pair-swap",
+           "--reversible", "--rotate-channels", "--traversal", "--stride",
+           "--border", "--avoid-salient", "--recipient",
+           "--recipient-passwords", "--layer-passwords", "--output-format"},
+          "ioi"}},
+        {"unpack",
+         {2,
+          2,
+          {"--password", "--keyfile", "--reference", "--restore-cover",
+           "--identity", "--recipient-password", "--traversal", "--stride",
+           "--border", "--avoid-salient"},
+          "io"}},
+#endif
+        {"pack-dir",
+         {3,
+          3,
+          {"--compress", "--create-dirs", "--checksums", "--ecc",
+           "--interleave", "--copies", "--keyfile", "--sync", "--differential",
+           "--pair-swap", "--reversible", "--rotate-channels", "--traversal",
+           "--stride", "--border", "--avoid-salient", "--recipient",
+           "--recipient-passwords", "--output-format"},
+          "ioi"}},
+        {"unpack-dir",
+         {2,
+          2,
+          {"--keyfile", "--reference", "--restore-cover", "--identity",
+           "--recipient-password", "--traversal", "--stride", "--border",
+           "--avoid-salient"},
+          "io"}},
+        {"merge-data",
+         {3,
+          3,
+          {"--type", "--compress", "--create-dirs", "--checksums", "--ecc",
+           "--interleave", "--copies", "--keyfile", "--sync", "--differential",
+           "--pair-swap", "--reversible", "--rotate-channels", "--traversal",
+           "--stride", "--border", "--avoid-salient", "--recipient",
+           "--recipient-passwords", "--output-format"},
+          "ioi"}},
+        {"unmerge-data",
+         {1,
+          2,
+          {"--keyfile", "--reference", "--restore-cover", "--identity",
+           "--recipient-password", "--traversal", "--stride", "--border",
+           "--avoid-salient", "--inspect"},
+          "io"}},
+        {"append",
+         {3,
+          kUnbounded,
+          {"--keyfile", "--identity", "--recipient-password", "--recipient",
+           "--recipient-passwords", "--traversal", "--stride", "--border",
+           "--avoid-salient", "--create-dirs", "--output-format"},
+          "ioi"}},
+        {"nest",
+         {3, 3, {"--create-dirs", "--output-format"}, "iio"}},
+        {"unnest", {2, 2, {"--levels", "--create-dirs"}, "io"}},
+        {"split", {3, kUnbounded, {"--with-manifest"}, "ioi"}},
+        {"join", {2, kUnbounded, {"--manifest", "--create-dirs"}, "oi"}},
+        {"inspect",
+         {1,
+          1,
+          {"--password", "--keyfile", "--reference", "--traversal", "--stride",
+           "--border", "--avoid-salient"},
+          "i"}},
+        {"info", {1, 1, {}, "i"}},
+        {"keygen", {1, 1, {"--create-dirs"}, "o"}},
+        {"keygen-identity", {1, 1, {"--create-dirs"}, "o"}},
+        {"rekey",
+         {2,
+          2,
+          {"--keyfile", "--identity", "--recipient-password", "--recipient",
+           "--recipient-passwords", "--traversal", "--stride", "--border",
+           "--avoid-salient", "--create-dirs", "--output-format"},
+          "io"}},
+        {"attack", {1, 1, {"--jpeg-quality", "--scale", "--noise"}, "i"}},
+        {"detect", {1, 1, {"--format"}, "i"}},
+        {"visual-attack",
+         {2, 2, {"--plane", "--channel", "--create-dirs"}, "io"}},
+        {"verify-pair", {2, 2, {"--bits"}, "ii"}},
+        {"compare", {3, 3, {"--amplify", "--create-dirs"}, "iio"}},
+        {"scrub",
+         {2, 2, {"--bits", "--seed", "--create-dirs", "--output-format"},
+          "io"}},
+        {"seal", {2, 2, {"--keyfile", "--create-dirs"}, "io"}},
+        {"verify-seal", {1, 1, {"--signer"}, "i"}},
+        {"heatmap", {2, 2, {"--block", "--opacity", "--create-dirs"}, "io"}},
+        {"scan", {1, 1, {"--recursive", "--threshold", "--format"}, "i"}},
+        {"sanitize",
+         {1, 1, {"--out", "--recursive", "--bits", "--seed"}, "i"}},
+        {"rank-covers", {1, 1, {"--payload-size"}, "i"}},
+        {"batch",
+         {2,
+          2,
+          {"--create-dirs", "--threads", "--fail-fast", "--state",
+           "--max-rate", "--max-images", "--low-priority"},
+          "io"}},
+        {"gen-cover",
+         {1, 1, {"--size", "--style", "--seed", "--create-dirs"}, "o"}},
+        {"preview", {1, 1, {"--bits", "--protocol", "--width"}, "i"}},
+        {"tui", {0, 3, {}, "iio"}},
+        {"watch",
+         {0,
+          0,
+          {"--in", "--out", "--mode", "--secret", "--keyfile", "--password",
+           "--debounce"},
+          ""}},
+#ifdef STEG_WITH_SERVER
+        {"serve",
+         {0,
+          0,
+          {"--port", "--bind", "--max-pixels", "--max-image-bytes",
+           "--threads", "--memory-budget"},
+          ""}},
+#endif
+        {"help", {0, 0, {}, ""}},
+    };
+
+    if (argc < 2) { /* missing the program command arg */
+        PrintErrAndExit("missing command");
+    }
+
+    /* did the user specify a valid command? */
+    std::string cmd(argv[1]);
+    auto command = kCommands.find(cmd);
+    if (command == kCommands.end()) {
+        PrintErrAndExit("unknown CMD value");
+    }
+
+    /* execute the command with its positional args and options */
+    ParsedArgs args = ParseArgs(argc, argv, cmd, command->second);
+    steganography::RetCode rc = steganography::RetCode::kSuccess;
+    try {
+        rc = RunWithRemotePaths(cmd, args, command->second);
+    } catch (const std::exception&) {
+        /* image codecs throw when a file cannot be opened or written */
+        rc = steganography::RetCode::kIoError;
+    }
+
+    /* report errors with the offending path and a hint if there are any */
+    if (rc != steganography::RetCode::kSuccess) {
+        PrintDiagnosticAndExit(Diagnose(rc, args, command->second));
     }
     return 0;
 }
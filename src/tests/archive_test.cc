@@ -0,0 +1,139 @@
+#include <gtest/gtest.h>
+
+#include <cstddef>
+#include <filesystem>
+#include <fstream>
+#include <iterator>
+#include <string>
+#include <vector>
+
+#include "core/ret_code.hpp"
+#include "noise.hpp"
+#include "utils/archive.hpp"
+#include "utils/paths.hpp"
+
+namespace fs = std::filesystem;
+using steganography::RetCode;
+
+namespace {
+
+void WriteFile(const fs::path& path, const std::string& contents) {
+    std::ofstream out(path, std::ios::binary);
+    out << contents;
+}
+
+std::vector<char> ReadFile(const std::string& path) {
+    std::ifstream in(path, std::ios::binary);
+    return {std::istreambuf_iterator<char>(in),
+            std::istreambuf_iterator<char>()};
+}
+
+/* the same tree under root, its entries created in the listed order or in
+ * reverse so the directory order differs between the two */
+void WriteTree(const fs::path& root, bool reverse) {
+    const std::vector<std::string> kFiles = {"a.txt", "b.txt", "sub/c.txt",
+                                             "sub/d.txt", "z.txt"};
+    fs::create_directories(root / "sub");
+    for (std::size_t i = 0; i < kFiles.size(); ++i) {
+        const std::string& name =
+            reverse ? kFiles[kFiles.size() - 1 - i] : kFiles[i];
+        WriteFile(root / name, "contents of " + name);
+    }
+}
+
+class ArchiveTest : public ::testing::Test {
+   protected:
+    ArchiveTest() : scratch_("steganography-test") {}
+
+    void SetUp() override {
+        ASSERT_TRUE(scratch_.Valid());
+        WriteNoise(Cover(), 256, 1); /* holds the small trees below */
+        WriteTree(fs::path(File("first")) / "docs", false);
+        WriteTree(fs::path(File("second")) / "docs", true);
+    }
+
+    std::string Cover() const { return scratch_.File("cover.png"); }
+    std::string File(const std::string& name) const {
+        return scratch_.File(name);
+    }
+
+   private:
+    steganography::ScratchDir scratch_;
+};
+
+}  // namespace
+
+TEST_F(ArchiveTest, PackTwiceIsByteIdentical) {
+    const std::vector<std::string> kInputs = {File("first/docs")};
+    ASSERT_EQ(steganography::Pack(Cover(), File("one.png"), kInputs),
+              RetCode::kSuccess);
+    ASSERT_EQ(steganography::Pack(Cover(), File("two.png"), kInputs),
+              RetCode::kSuccess);
+    EXPECT_EQ(ReadFile(File("one.png")), ReadFile(File("two.png")));
+}
+
+TEST_F(ArchiveTest, PackIgnoresCreationOrder) {
+    ASSERT_EQ(steganography::Pack(Cover(), File("one.png"),
+                                  {File("first/docs")}),
+              RetCode::kSuccess);
+    ASSERT_EQ(steganography::Pack(Cover(), File("two.png"),
+                                  {File("second/docs")}),
+              RetCode::kSuccess);
+    EXPECT_EQ(ReadFile(File("one.png")), ReadFile(File("two.png")));
+}
+
+TEST_F(ArchiveTest, PackDirectoryIgnoresCreationOrder) {
+    for (bool compress : {false, true}) {
+        ASSERT_EQ(steganography::PackDirectory(Cover(), File("first/docs"),
+                                               File("one.png"), compress),
+                  RetCode::kSuccess);
+        ASSERT_EQ(steganography::PackDirectory(Cover(), File("first/docs"),
+                                               File("two.png"), compress),
+                  RetCode::kSuccess);
+        ASSERT_EQ(steganography::PackDirectory(Cover(), File("second/docs"),
+                                               File("three.png"), compress),
+                  RetCode::kSuccess);
+        const std::vector<char> kFirst = ReadFile(File("one.png"));
+        EXPECT_EQ(kFirst, ReadFile(File("two.png"))) << compress;
+        EXPECT_EQ(kFirst, ReadFile(File("three.png"))) << compress;
+    }
+}
+
+TEST_F(ArchiveTest, UnpackDirectoryKeepsReadOnlyDirectories) {
+    const fs::path kSub = fs::path(File("first/docs")) / "sub";
+    const fs::perms kReadOnly = fs::perms::owner_read | fs::perms::owner_exec;
+    fs::permissions(kSub, kReadOnly);
+    RetCode packed = steganography::PackDirectory(Cover(), File("first/docs"),
+                                                  File("one.png"), false);
+    fs::permissions(kSub, fs::perms::owner_all);
+    ASSERT_EQ(packed, RetCode::kSuccess);
+
+    ASSERT_EQ(steganography::UnpackDirectory(File("one.png"), File("out")),
+              RetCode::kSuccess);
+    const fs::path kOut = fs::path(File("out")) / "docs" / "sub";
+    EXPECT_EQ(fs::status(kOut).permissions() & fs::perms::all, kReadOnly);
+    EXPECT_TRUE(fs::exists(kOut / "c.txt"));
+    EXPECT_TRUE(fs::exists(kOut / "d.txt"));
+    /* let the scratch directory be removed */
+    fs::permissions(kOut, fs::perms::owner_all);
+}
+
+TEST_F(ArchiveTest, PackRefusesClashingNames) {
+    /* first/docs/a.txt and second/docs/a.txt are both docs/a.txt */
+    EXPECT_EQ(steganography::Pack(Cover(), File("one.png"),
+                                  {File("first/docs"), File("second/docs")}),
+              RetCode::kInvalidArgument);
+    EXPECT_EQ(steganography::Pack(Cover(), File("two.png"),
+                                  {File("first/docs/a.txt"),
+                                   File("second/docs/a.txt")}),
+              RetCode::kInvalidArgument);
+    EXPECT_EQ(steganography::Pack(Cover(), File("three.png"),
+                                  {File("first/docs/a.txt"),
+                                   File("first/docs/a.txt")}),
+              RetCode::kInvalidArgument);
+    EXPECT_FALSE(fs::exists(File("one.png")));
+    EXPECT_EQ(steganography::Pack(Cover(), File("four.png"),
+                                  {File("first/docs/a.txt"),
+                                   File("first/docs/sub/c.txt")}),
+              RetCode::kSuccess);
+}
@@ -0,0 +1,189 @@
+#include <gtest/gtest.h>
+
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <filesystem>
+#include <fstream>
+#include <iterator>
+#include <string>
+#include <vector>
+
+#include "core/ret_code.hpp"
+#include "noise.hpp"
+#include "utils/archive.hpp"
+#include "utils/image_io.hpp"
+#include "utils/paths.hpp"
+
+namespace fs = std::filesystem;
+using steganography::ImageType;
+using steganography::RetCode;
+
+namespace {
+
+void WriteFile(const std::string& path, const std::string& contents) {
+    std::ofstream out(path, std::ios::binary);
+    out << contents;
+}
+
+std::string ReadFile(const std::string& path) {
+    std::ifstream in(path, std::ios::binary);
+    return {std::istreambuf_iterator<char>(in),
+            std::istreambuf_iterator<char>()};
+}
+
+boost::gil::rgb8_image_t Read(const std::string& filename) {
+    return steganography::ReadImage(filename,
+                                    steganography::GetImageType(filename));
+}
+
+bool SamePixels(const boost::gil::rgb8_image_t& lhs,
+                const boost::gil::rgb8_image_t& rhs) {
+    return (lhs.dimensions() == rhs.dimensions()) &&
+           boost::gil::equal_pixels(boost::gil::const_view(lhs),
+                                    boost::gil::const_view(rhs));
+}
+
+/* every channel of the pixel holds value */
+void ExpectGray(const boost::gil::rgb8_image_t& image, std::ptrdiff_t x,
+                std::ptrdiff_t y, int value) {
+    const auto& pixel = boost::gil::const_view(image)(x, y);
+    for (int c = 0; c < 3; ++c) {
+        EXPECT_EQ(pixel[c], value) << x << "," << y;
+    }
+}
+
+class ImageIoTest : public ::testing::Test {
+   protected:
+    ImageIoTest() : scratch_("steganography-test") {}
+
+    void SetUp() override {
+        ASSERT_TRUE(scratch_.Valid());
+        WriteNoise(File("noise.png"), 128, 1);
+        WriteFile(File("flag.txt"), "flag{uncompressed}");
+    }
+
+    std::string File(const std::string& name) const {
+        return scratch_.File(name);
+    }
+
+   private:
+    steganography::ScratchDir scratch_;
+};
+
+}  // namespace
+
+TEST_F(ImageIoTest, BmpRoundTrip) {
+    const boost::gil::rgb8_image_t kNoise = Read(File("noise.png"));
+    steganography::WriteImage(kNoise, File("out.bmp"), ImageType::kBmp);
+    ASSERT_EQ(steganography::GetImageType(File("out.bmp")), ImageType::kBmp);
+    EXPECT_TRUE(SamePixels(kNoise, Read(File("out.bmp"))));
+}
+
+TEST_F(ImageIoTest, PpmRoundTrip) {
+    const boost::gil::rgb8_image_t kNoise = Read(File("noise.png"));
+    for (const char* name : {"out.ppm", "out.pnm"}) {
+        steganography::WriteImage(kNoise, File(name), ImageType::kPnm);
+        EXPECT_EQ(ReadFile(File(name)).substr(0, 3), "P6\n") << name;
+        EXPECT_TRUE(SamePixels(kNoise, Read(File(name)))) << name;
+    }
+}
+
+TEST_F(ImageIoTest, PgmRoundTrip) {
+    boost::gil::rgb8_image_t gray(17, 9);
+    auto view = boost::gil::view(gray);
+    for (std::ptrdiff_t y = 0; y < view.height(); ++y) {
+        for (std::ptrdiff_t x = 0; x < view.width(); ++x) {
+            const auto kValue = static_cast<uint8_t>(x * 15 + y);
+            view(x, y) = boost::gil::rgb8_pixel_t(kValue, kValue, kValue);
+        }
+    }
+    steganography::WriteImage(gray, File("out.pgm"), ImageType::kPnm);
+    EXPECT_EQ(ReadFile(File("out.pgm")).substr(0, 3), "P5\n");
+    EXPECT_TRUE(SamePixels(gray, Read(File("out.pgm"))));
+}
+
+TEST_F(ImageIoTest, PbmRoundTrip) {
+    /* a width that is not a whole number of bytes pads every row */
+    boost::gil::rgb8_image_t bitmap(13, 5);
+    auto view = boost::gil::view(bitmap);
+    for (std::ptrdiff_t y = 0; y < view.height(); ++y) {
+        for (std::ptrdiff_t x = 0; x < view.width(); ++x) {
+            const uint8_t kValue = ((x + y) % 3 == 0) ? 0 : 255;
+            view(x, y) = boost::gil::rgb8_pixel_t(kValue, kValue, kValue);
+        }
+    }
+    steganography::WriteImage(bitmap, File("out.pbm"), ImageType::kPnm);
+    const std::string kWritten = ReadFile(File("out.pbm"));
+    EXPECT_EQ(kWritten.substr(0, 3), "P4\n");
+    EXPECT_EQ(kWritten.size(), std::string("P4\n13 5\n").size() + 2 * 5);
+    EXPECT_TRUE(SamePixels(bitmap, Read(File("out.pbm"))));
+}
+
+TEST_F(ImageIoTest, PlainForms) {
+    /* set bitmap pixels are black, the digits need no separators */
+    WriteFile(File("plain.pbm"), "P1\n# a comment\n3 2\n1 0 1\n010\n");
+    ASSERT_EQ(steganography::GetImageType(File("plain.pbm")), ImageType::kPnm);
+    const boost::gil::rgb8_image_t kBitmap = Read(File("plain.pbm"));
+    ExpectGray(kBitmap, 0, 0, 0);
+    ExpectGray(kBitmap, 1, 0, 255);
+    ExpectGray(kBitmap, 1, 1, 0);
+    ExpectGray(kBitmap, 2, 1, 255);
+
+    WriteFile(File("plain.pgm"), "P2\n2 1\n15\n15 5\n");
+    const boost::gil::rgb8_image_t kGray = Read(File("plain.pgm"));
+    ExpectGray(kGray, 0, 0, 255);
+    ExpectGray(kGray, 1, 0, 85);
+
+    WriteFile(File("plain.ppm"), "P3\n1 1\n1000\n1000 500 0\n");
+    const boost::gil::rgb8_image_t kColor = Read(File("plain.ppm"));
+    const auto& kPixel = boost::gil::const_view(kColor)(0, 0);
+    EXPECT_EQ(kPixel[0], 255);
+    EXPECT_EQ(kPixel[1], 128);
+    EXPECT_EQ(kPixel[2], 0);
+}
+
+TEST_F(ImageIoTest, RawFormsBelowAndAbove255) {
+    /* 16 bit samples are big endian */
+    WriteFile(File("deep.pgm"),
+              std::string("P5\n2 1\n65535\n\xff\xff\x80\x00", 17));
+    const boost::gil::rgb8_image_t kDeep = Read(File("deep.pgm"));
+    ExpectGray(kDeep, 0, 0, 255);
+    ExpectGray(kDeep, 1, 0, 128);
+
+    WriteFile(File("shallow.ppm"),
+              std::string("P6\n1 1\n7\n\x07\x00\x03", 12));
+    const boost::gil::rgb8_image_t kShallow = Read(File("shallow.ppm"));
+    const auto& kPixel = boost::gil::const_view(kShallow)(0, 0);
+    EXPECT_EQ(kPixel[0], 255);
+    EXPECT_EQ(kPixel[1], 0);
+    EXPECT_EQ(kPixel[2], 109);
+}
+
+TEST_F(ImageIoTest, PackIntoUncompressedOutputs) {
+    /* a gray cover, as CTF challenges often hand out */
+    steganography::WriteImage(Read(File("noise.png")), File("cover.pgm"),
+                              ImageType::kPnm);
+    for (const char* name : {"out.bmp", "out.ppm"}) {
+        ASSERT_EQ(steganography::Pack(File("cover.pgm"), File(name),
+                                      {File("flag.txt")}),
+                  RetCode::kSuccess)
+            << name;
+        const std::string kOutdir = File(std::string(name) + ".d");
+        ASSERT_EQ(steganography::Unpack(File(name), kOutdir),
+                  RetCode::kSuccess)
+            << name;
+        EXPECT_EQ(ReadFile((fs::path(kOutdir) / "flag.txt").string()),
+                  "flag{uncompressed}")
+            << name;
+    }
+}
+
+TEST_F(ImageIoTest, GrayOutputsRefuseHiddenData) {
+    for (const char* name : {"out.pgm", "out.pbm"}) {
+        EXPECT_EQ(steganography::Pack(File("noise.png"), File(name),
+                                      {File("flag.txt")}),
+                  RetCode::kInvalidArgument)
+            << name;
+    }
+}
@@ -0,0 +1,74 @@
+#include <gtest/gtest.h>
+
+#include <boost/gil.hpp>
+#include <string>
+
+#include "core/ret_code.hpp"
+#include "noise.hpp"
+#include "utils/key.hpp"
+#include "utils/paths.hpp"
+#include "utils/steganography_util.hpp"
+
+using steganography::RetCode;
+
+namespace {
+
+class MergeTest : public ::testing::Test {
+   protected:
+    MergeTest() : scratch_("steganography-test") {}
+
+    void SetUp() override {
+        ASSERT_TRUE(scratch_.Valid());
+        WriteNoise(File("cover.png"), 256, 1);
+        WriteNoise(File("secret.png"), 128, 2);
+        ASSERT_EQ(steganography::GenerateKeyFile(File("key")),
+                  RetCode::kSuccess);
+    }
+
+    std::string File(const std::string& name) const {
+        return scratch_.File(name);
+    }
+
+   private:
+    steganography::ScratchDir scratch_;
+};
+
+}  // namespace
+
+/* the auth block is written as nibbles, dithering fewer bits than that must
+ * leave it readable */
+TEST_F(MergeTest, DitherKeepsTheAuthBlockBelowFourBits) {
+    for (int bits : {1, 2, 3}) {
+        steganography::MergeConfig merge;
+        merge.keyfile = File("key");
+        merge.password = "hunter2";
+        merge.bits = bits;
+        steganography::UnmergeConfig unmerge;
+        unmerge.keyfile = merge.keyfile;
+        unmerge.password = merge.password;
+        unmerge.bits = bits;
+
+        ASSERT_EQ(steganography::Merge(File("cover.png"), File("secret.png"),
+                                       File("plain.png"), merge),
+                  RetCode::kSuccess);
+        merge.dither = true;
+        ASSERT_EQ(steganography::Merge(File("cover.png"), File("secret.png"),
+                                       File("dithered.png"), merge),
+                  RetCode::kSuccess);
+
+        boost::gil::rgb8_image_t plain;
+        boost::gil::rgb8_image_t dithered;
+        ASSERT_EQ(steganography::Unmerge(File("plain.png"),
+                                         File("plain-out.png"), unmerge,
+                                         plain),
+                  RetCode::kSuccess);
+        ASSERT_EQ(steganography::Unmerge(File("dithered.png"),
+                                         File("dithered-out.png"), unmerge,
+                                         dithered),
+                  RetCode::kSuccess)
+            << bits;
+        EXPECT_TRUE(boost::gil::equal_pixels(boost::gil::const_view(plain),
+                                             boost::gil::const_view(dithered)))
+            << bits;
+    }
+}
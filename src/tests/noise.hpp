@@ -0,0 +1,30 @@
+#ifndef TESTS_NOISE_HPP_
+#define TESTS_NOISE_HPP_
+
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <random>
+#include <string>
+
+#include "utils/image_io.hpp"
+
+/* uniform noise drawn from seed, so every run writes the same image */
+inline void WriteNoise(const std::string& filename, std::ptrdiff_t side,
+                       uint32_t seed) {
+    std::mt19937 rng(seed);
+    std::uniform_int_distribution<int> channel(0, 255);
+    boost::gil::rgb8_image_t image(side, side);
+    auto view = boost::gil::view(image);
+    for (std::ptrdiff_t y = 0; y < side; ++y) {
+        for (std::ptrdiff_t x = 0; x < side; ++x) {
+            view(x, y) = boost::gil::rgb8_pixel_t(
+                static_cast<uint8_t>(channel(rng)),
+                static_cast<uint8_t>(channel(rng)),
+                static_cast<uint8_t>(channel(rng)));
+        }
+    }
+    steganography::WriteImage(image, filename, steganography::ImageType::kPng);
+}
+
+#endif
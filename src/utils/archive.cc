@@ -0,0 +1,1708 @@
+#include "utils/archive.hpp"
+
+#include <algorithm>
+#include <boost/gil.hpp>
+#include <cctype>
+#include <cstddef>
+#include <cstdint>
+#include <cstdio>
+#include <filesystem>
+#include <fstream>
+#include <iterator>
+#include <optional>
+#include <set>
+#include <string>
+#include <utility>
+#include <vector>
+
+#include "utils/bytes.hpp"
+#include "utils/compression.hpp"
+#include "utils/deniable.hpp"
+#include "utils/differential.hpp"
+#ifdef STEG_WITH_GIF
+#include "core/carrier.hpp"
+#include "utils/gif.hpp"
+#endif
+#include "utils/icc.hpp"
+#include "utils/ico.hpp"
+#include "utils/image_io.hpp"
+#include "utils/key.hpp"
+#include "utils/mime.hpp"
+#include "utils/pair_swap.hpp"
+#include "utils/paths.hpp"
+#include "utils/payload.hpp"
+#include "utils/pipeline.hpp"
+#include "utils/recipient.hpp"
+#include "utils/reversible.hpp"
+#include "utils/rotation.hpp"
+#include "utils/traversal.hpp"
+
+namespace steganography {
+
+/* archives start with this magic word followed by the manifest */
+static const uint32_t kArchiveMagic = 0x53415243; /* "SARC" */
+
+/* tar archives use 512 byte blocks with ustar headers */
+static const std::size_t kTarBlockSize = 512;
+static const std::size_t kTarNameSize = 100;
+static const std::size_t kTarPrefixSize = 155;
+static const char kTarFileType = '0';
+static const char kTarDirectoryType = '5';
+static const char kTarLongNameType = 'L';
+
+struct ArchiveEntry {
+    std::string name;
+    std::filesystem::perms perms;
+    std::vector<uint8_t> data;
+    bool is_directory = false;
+    std::size_t offset = 0; /* where data starts within the archive */
+    bool partial = false;   /* data was cut short by damage */
+};
+
+/* the file contents are the secret, so they are wiped once written out */
+static void WipeEntries(std::vector<ArchiveEntry>& entries) {
+    for (ArchiveEntry& entry : entries) {
+        Wipe(entry.data);
+    }
+}
+
+static bool ReadFile(const std::filesystem::path& path,
+                     std::vector<uint8_t>& data) {
+    std::ifstream ifs(path, std::ifstream::binary);
+    if (!ifs.is_open()) {
+        return false;
+    }
+    data.assign(std::istreambuf_iterator<char>(ifs),
+                std::istreambuf_iterator<char>());
+    return !ifs.bad();
+}
+
+/* kInvalidArgument when two inputs give the same name, a.txt and b/a.txt
+ * or one file passed twice, since unpacking would write one over the other */
+static RetCode CollectEntries(const std::vector<std::string>& inputs,
+                              std::vector<ArchiveEntry>& entries) {
+    std::set<std::string> names;
+    for (const ArchiveEntry& entry : entries) {
+        names.insert(entry.name);
+    }
+    for (const std::string& input : inputs) {
+        if (!std::filesystem::exists(input)) {
+            return RetCode::kFileNotFound;
+        }
+
+        /* entry names are relative to the directory containing the input */
+        std::filesystem::path root =
+            std::filesystem::absolute(input).lexically_normal();
+        if (!root.has_filename()) {
+            root = root.parent_path();
+        }
+        const std::filesystem::path kBase = root.parent_path();
+
+        /* directories are walked in whatever order the file system keeps
+         * them, sort so the same inputs always pack the same archive */
+        std::vector<std::filesystem::path> files;
+        if (std::filesystem::is_directory(root)) {
+            for (const auto& entry :
+                 std::filesystem::recursive_directory_iterator(root)) {
+                if (entry.is_regular_file()) {
+                    files.push_back(entry.path());
+                }
+            }
+            std::sort(files.begin(), files.end());
+        } else {
+            files.push_back(root);
+        }
+
+        for (const auto& file : files) {
+            ArchiveEntry entry;
+            entry.name = file.lexically_relative(kBase).generic_string();
+            if (!names.insert(entry.name).second) {
+                return RetCode::kInvalidArgument;
+            }
+            entry.perms = std::filesystem::status(file).permissions();
+            if (!ReadFile(file, entry.data)) {
+                return RetCode::kIoError;
+            }
+            entries.push_back(std::move(entry));
+        }
+    }
+    return RetCode::kSuccess;
+}
+
+static std::vector<uint8_t> Serialize(const std::vector<ArchiveEntry>& entries) {
+    std::vector<uint8_t> archive;
+    AppendInt<uint32_t>(archive, kArchiveMagic);
+    AppendInt<uint32_t>(archive, static_cast<uint32_t>(entries.size()));
+    for (const ArchiveEntry& entry : entries) {
+        AppendString(archive, entry.name);
+        AppendInt<uint32_t>(archive,
+                            static_cast<uint32_t>(entry.perms &
+                                                  std::filesystem::perms::all));
+        AppendInt<uint64_t>(archive, entry.data.size());
+    }
+    for (const ArchiveEntry& entry : entries) {
+        archive.insert(archive.end(), entry.data.cbegin(), entry.data.cend());
+    }
+    return archive;
+}
+
+static RetCode Deserialize(const std::vector<uint8_t>& archive,
+                           std::vector<ArchiveEntry>& entries) {
+    /* every read is bounds checked since the archive may be corrupt */
+    std::size_t offset = 0;
+    auto has_bytes = [&archive, &offset](std::size_t count) {
+        return (archive.size() >= offset) && (archive.size() - offset >= count);
+    };
+
+    if (!has_bytes(8) || (ReadInt<uint32_t>(archive, 0) != kArchiveMagic)) {
+        return RetCode::kInvalidPayload;
+    }
+    uint32_t count = ReadInt<uint32_t>(archive, 4);
+    offset = 8;
+
+    std::vector<uint64_t> sizes;
+    for (uint32_t i = 0; i < count; ++i) {
+        if (!has_bytes(2)) {
+            return RetCode::kInvalidPayload;
+        }
+        uint16_t name_len = ReadInt<uint16_t>(archive, offset);
+        offset += 2;
+        if (!has_bytes(name_len + 12U)) {
+            return RetCode::kInvalidPayload;
+        }
+
+        ArchiveEntry entry;
+        entry.name.assign(archive.cbegin() + static_cast<std::ptrdiff_t>(offset),
+                          archive.cbegin() +
+                              static_cast<std::ptrdiff_t>(offset + name_len));
+        offset += name_len;
+        /* only the rwx bits, an archive must not hand out setuid, setgid,
+         * or sticky files */
+        entry.perms =
+            static_cast<std::filesystem::perms>(ReadInt<uint32_t>(archive, offset)) &
+            std::filesystem::perms::all;
+        sizes.push_back(ReadInt<uint64_t>(archive, offset + 4));
+        offset += 12;
+        entries.push_back(std::move(entry));
+    }
+
+    for (std::size_t i = 0; i < entries.size(); ++i) {
+        if (!has_bytes(sizes[i])) {
+            return RetCode::kInvalidPayload;
+        }
+        auto begin = archive.cbegin() + static_cast<std::ptrdiff_t>(offset);
+        entries[i].offset = offset;
+        entries[i].data.assign(begin,
+                               begin + static_cast<std::ptrdiff_t>(sizes[i]));
+        offset += sizes[i];
+    }
+    return RetCode::kSuccess;
+}
+
+static bool IsSafeEntryName(const std::string& name) {
+    /* reject names that would escape the target directory */
+    std::filesystem::path path(name);
+    if (name.empty() || path.is_absolute() || path.has_root_name()) {
+        return false;
+    }
+    for (const auto& part : path) {
+        if (part == "..") {
+            return false;
+        }
+    }
+    return true;
+}
+
+static void WriteOctal(uint8_t* field, std::size_t width, uint64_t value) {
+    /* octal digits padded with zeros and terminated with a NUL */
+    std::snprintf(reinterpret_cast<char*>(field), width, "%0*llo",
+                  static_cast<int>(width - 1),
+                  static_cast<unsigned long long>(value));
+}
+
+static uint64_t ReadOctal(const uint8_t* field, std::size_t width) {
+    uint64_t value = 0;
+    for (std::size_t i = 0; i < width; ++i) {
+        if ((field[i] >= '0') && (field[i] <= '7')) {
+            value = (value << 3) | (field[i] - '0');
+        } else if (field[i] != ' ') {
+            break;
+        }
+    }
+    return value;
+}
+
+static uint32_t TarChecksum(const uint8_t* block) {
+    /* the checksum field itself is summed as if it held spaces */
+    const std::size_t kChecksumOffset = 148;
+    const std::size_t kChecksumSize = 8;
+    uint32_t sum = 0;
+    for (std::size_t i = 0; i < kTarBlockSize; ++i) {
+        bool in_field = (i >= kChecksumOffset) &&
+                        (i < kChecksumOffset + kChecksumSize);
+        sum += in_field ? ' ' : block[i];
+    }
+    return sum;
+}
+
+static void AppendTarBlock(std::vector<uint8_t>& tar, const std::string& name,
+                           char type, uint32_t mode, uint64_t size) {
+    std::vector<uint8_t> block(kTarBlockSize, 0);
+
+    /* split long names into the ustar prefix field where possible */
+    std::string prefix;
+    std::string base = name;
+    if (name.size() > kTarNameSize) {
+        std::size_t split = name.rfind('/', kTarPrefixSize);
+        if ((split != std::string::npos) &&
+            (name.size() - split - 1 <= kTarNameSize)) {
+            prefix = name.substr(0, split);
+            base = name.substr(split + 1);
+        }
+    }
+    std::copy_n(base.cbegin(), std::min(base.size(), kTarNameSize),
+                block.begin());
+    WriteOctal(&block[100], 8, mode);
+    WriteOctal(&block[108], 8, 0);  /* uid */
+    WriteOctal(&block[116], 8, 0);  /* gid */
+    WriteOctal(&block[124], 12, size);
+    WriteOctal(&block[136], 12, 0); /* timestamps are not preserved */
+    block[156] = static_cast<uint8_t>(type);
+    const std::string kMagic("ustar\0" "00", 8);
+    std::copy(kMagic.cbegin(), kMagic.cend(), block.begin() + 257);
+    std::copy_n(prefix.cbegin(), std::min(prefix.size(), kTarPrefixSize),
+                block.begin() + 345);
+
+    WriteOctal(&block[148], 7, TarChecksum(block.data()));
+    block[155] = ' ';
+    tar.insert(tar.end(), block.cbegin(), block.cend());
+}
+
+static void AppendTarData(std::vector<uint8_t>& tar,
+                          const std::vector<uint8_t>& data) {
+    tar.insert(tar.end(), data.cbegin(), data.cend());
+    std::size_t padding = (kTarBlockSize - data.size() % kTarBlockSize) %
+                          kTarBlockSize;
+    tar.insert(tar.end(), padding, 0);
+}
+
+static void AppendTarEntry(std::vector<uint8_t>& tar,
+                           const ArchiveEntry& entry) {
+    /* names that don't fit ustar use a GNU long name record */
+    if ((entry.name.size() > kTarNameSize + kTarPrefixSize) ||
+        ((entry.name.size() > kTarNameSize) &&
+         (entry.name.rfind('/', kTarPrefixSize) == std::string::npos))) {
+        std::vector<uint8_t> long_name(entry.name.cbegin(), entry.name.cend());
+        long_name.push_back(0);
+        AppendTarBlock(tar, "././@LongLink", kTarLongNameType, 0,
+                       long_name.size());
+        AppendTarData(tar, long_name);
+    }
+    const uint32_t kPermMask =
+        static_cast<uint32_t>(std::filesystem::perms::all);
+    AppendTarBlock(tar, entry.name,
+                   entry.is_directory ? kTarDirectoryType : kTarFileType,
+                   static_cast<uint32_t>(entry.perms) & kPermMask,
+                   entry.data.size());
+    AppendTarData(tar, entry.data);
+}
+
+static RetCode CollectTree(const std::string& dir,
+                           std::vector<ArchiveEntry>& entries) {
+    if (!std::filesystem::is_directory(dir)) {
+        return RetCode::kFileNotFound;
+    }
+    std::filesystem::path root =
+        std::filesystem::absolute(dir).lexically_normal();
+    if (!root.has_filename()) {
+        root = root.parent_path();
+    }
+    const std::filesystem::path kBase = root.parent_path();
+
+    /* sort the tree so the same directory always yields the same tar */
+    std::vector<std::filesystem::path> paths = {root};
+    for (const auto& entry :
+         std::filesystem::recursive_directory_iterator(root)) {
+        if (entry.is_directory() || entry.is_regular_file()) {
+            paths.push_back(entry.path());
+        }
+    }
+    std::sort(paths.begin(), paths.end());
+
+    for (const auto& path : paths) {
+        ArchiveEntry entry;
+        entry.name = path.lexically_relative(kBase).generic_string();
+        entry.perms = std::filesystem::status(path).permissions();
+        entry.is_directory = std::filesystem::is_directory(path);
+        if (entry.is_directory) {
+            entry.name += '/';
+        } else if (!ReadFile(path, entry.data)) {
+            return RetCode::kIoError;
+        }
+        entries.push_back(std::move(entry));
+    }
+    return RetCode::kSuccess;
+}
+
+static std::vector<uint8_t> SerializeTar(
+    const std::vector<ArchiveEntry>& entries) {
+    std::vector<uint8_t> tar;
+    for (const ArchiveEntry& entry : entries) {
+        AppendTarEntry(tar, entry);
+    }
+    /* a tar stream ends with two zero blocks */
+    tar.insert(tar.end(), 2 * kTarBlockSize, 0);
+    return tar;
+}
+
+static RetCode DeserializeTar(const std::vector<uint8_t>& tar,
+                              std::vector<ArchiveEntry>& entries) {
+    std::size_t offset = 0;
+    std::string long_name;
+    while (offset + kTarBlockSize <= tar.size()) {
+        const uint8_t* block = &tar[offset];
+        if (std::all_of(block, block + kTarBlockSize,
+                        [](uint8_t b) { return b == 0; })) {
+            return RetCode::kSuccess;
+        }
+        if (TarChecksum(block) != ReadOctal(block + 148, 8)) {
+            return RetCode::kInvalidPayload;
+        }
+
+        uint64_t size = ReadOctal(block + 124, 12);
+        std::size_t data_offset = offset + kTarBlockSize;
+        /* a truncated final entry is kept as partial for damage recovery */
+        bool partial = size > tar.size() - data_offset;
+        if (partial) {
+            size = tar.size() - data_offset;
+        }
+        auto data_begin = tar.cbegin() + static_cast<std::ptrdiff_t>(data_offset);
+        auto data_end = data_begin + static_cast<std::ptrdiff_t>(size);
+        offset = data_offset +
+                 (size + kTarBlockSize - 1) / kTarBlockSize * kTarBlockSize;
+
+        auto field = [block](std::size_t pos, std::size_t len) {
+            const char* str = reinterpret_cast<const char*>(block + pos);
+            return std::string(str, std::find(str, str + len, '\0'));
+        };
+        char type = static_cast<char>(block[156]);
+        if (type == kTarLongNameType) {
+            long_name.assign(data_begin, std::find(data_begin, data_end, 0));
+            continue;
+        }
+        if ((type != kTarFileType) && (type != '\0') &&
+            (type != kTarDirectoryType)) {
+            long_name.clear(); /* links and devices are skipped */
+            continue;
+        }
+
+        ArchiveEntry entry;
+        if (!long_name.empty()) {
+            entry.name = long_name;
+            long_name.clear();
+        } else {
+            std::string prefix = field(345, kTarPrefixSize);
+            entry.name = field(0, kTarNameSize);
+            if (!prefix.empty()) {
+                entry.name = prefix + "/" + entry.name;
+            }
+        }
+        /* as in Deserialize, setuid, setgid, and sticky bits are dropped */
+        entry.perms = static_cast<std::filesystem::perms>(
+                          ReadOctal(block + 100, 8)) &
+                      std::filesystem::perms::all;
+        entry.is_directory = (type == kTarDirectoryType);
+        if (!entry.is_directory) {
+            entry.offset = data_offset;
+            entry.data.assign(data_begin, data_end);
+            entry.partial = partial;
+        }
+        entries.push_back(std::move(entry));
+    }
+    /* ran off the end without seeing the terminating zero blocks */
+    return RetCode::kInvalidPayload;
+}
+
+static RetCode WriteEntries(const std::string& outdir,
+                            const std::vector<ArchiveEntry>& entries) {
+    /* validate every name up front so a bad entry leaves nothing behind */
+    for (const ArchiveEntry& entry : entries) {
+        if (!IsSafeEntryName(entry.name)) {
+            return RetCode::kInvalidPayload;
+        }
+    }
+
+    /* a read-only directory would refuse the files written into it, so the
+     * directories get their permissions only once everything is in place */
+    std::vector<const ArchiveEntry*> directories;
+    for (const ArchiveEntry& entry : entries) {
+        std::filesystem::path path = std::filesystem::path(outdir) / entry.name;
+        std::error_code ec;
+        if (entry.is_directory) {
+            std::filesystem::create_directories(path, ec);
+            if (ec) {
+                return RetCode::kIoError;
+            }
+            directories.push_back(&entry);
+            continue;
+        }
+        std::filesystem::create_directories(path.parent_path(), ec);
+        std::ofstream ofs(path, std::ofstream::binary | std::ofstream::trunc);
+        if (!ofs.is_open()) {
+            return RetCode::kIoError;
+        }
+        ofs.write(reinterpret_cast<const char*>(entry.data.data()),
+                  static_cast<std::streamsize>(entry.data.size()));
+        ofs.close();
+        if (!ofs) {
+            return RetCode::kIoError;
+        }
+        std::filesystem::permissions(path, entry.perms, ec);
+    }
+
+    /* deepest first, so closing off a parent cannot stop its children from
+     * being changed */
+    auto depth = [](const ArchiveEntry* entry) {
+        const std::filesystem::path kPath(entry->name);
+        return std::distance(kPath.begin(), kPath.end());
+    };
+    std::stable_sort(directories.begin(), directories.end(),
+                     [&depth](const ArchiveEntry* lhs,
+                              const ArchiveEntry* rhs) {
+                         return depth(lhs) > depth(rhs);
+                     });
+    for (const ArchiveEntry* entry : directories) {
+        const std::filesystem::path kPath =
+            std::filesystem::path(outdir) / entry->name;
+        std::error_code ec;
+        std::filesystem::permissions(kPath, entry->perms, ec);
+    }
+    return RetCode::kSuccess;
+}
+
+/* [begin, end) spans of the archive that failed their checksums */
+using ByteRange = std::pair<std::size_t, std::size_t>;
+
+static std::vector<ByteRange> DamagedRanges(const DamageReport& damage) {
+    std::vector<ByteRange> ranges;
+    for (const DamagedBlock& block : damage.blocks) {
+        ranges.emplace_back(block.offset, block.offset + block.length);
+    }
+    return ranges;
+}
+
+/* returns the first damaged byte in [begin, end) or end when it is intact */
+static std::size_t FirstDamaged(const std::vector<ByteRange>& ranges,
+                                std::size_t begin, std::size_t end) {
+    std::size_t first = end;
+    for (const auto& [range_begin, range_end] : ranges) {
+        if ((range_begin < end) && (range_end > begin)) {
+            first = std::min(first, std::max(range_begin, begin));
+        }
+    }
+    return first;
+}
+
+/* cuts every file short at its first damaged byte */
+static void TrimDamaged(std::vector<ArchiveEntry>& entries,
+                        const std::vector<ByteRange>& ranges) {
+    for (ArchiveEntry& entry : entries) {
+        std::size_t end = entry.offset + entry.data.size();
+        std::size_t first = FirstDamaged(ranges, entry.offset, end);
+        if (first < end) {
+            entry.data.resize(first - entry.offset);
+            entry.partial = true;
+        }
+    }
+}
+
+static RetCode WriteDamageReport(const std::string& outdir,
+                                 const DamageReport& damage) {
+    std::error_code ec;
+    std::filesystem::create_directories(outdir, ec);
+    std::ofstream ofs(std::filesystem::path(outdir) / kDamageReportName);
+    if (!ofs.is_open()) {
+        return RetCode::kIoError;
+    }
+    for (const DamagedBlock& block : damage.blocks) {
+        ofs << "damaged: payload bytes " << block.offset << "-"
+            << block.offset + block.length - 1 << ", image rows "
+            << block.first_row << "-" << block.last_row << "\n";
+    }
+    for (const std::string& name : damage.partial_files) {
+        ofs << "partial: " << name << "\n";
+    }
+    for (const std::string& name : damage.lost_files) {
+        ofs << "lost: " << name << "\n";
+    }
+    if (damage.truncated) {
+        ofs << "lost: any entries after the damage\n";
+    }
+    ofs.close();
+    return ofs ? RetCode::kPayloadDamaged : RetCode::kIoError;
+}
+
+/* writes whatever survived the damage along with a kDamageReportName */
+static RetCode WriteSalvaged(const std::string& outdir,
+                             std::vector<ArchiveEntry>& entries,
+                             DamageReport& damage) {
+    std::vector<ArchiveEntry> salvaged;
+    for (ArchiveEntry& entry : entries) {
+        if (entry.partial && entry.data.empty()) {
+            damage.lost_files.push_back(entry.name);
+            continue;
+        }
+        if (entry.partial) {
+            entry.name += ".partial";
+            damage.partial_files.push_back(entry.name);
+        }
+        salvaged.push_back(std::move(entry));
+    }
+    RetCode rc = WriteEntries(outdir, salvaged);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    return WriteDamageReport(outdir, damage);
+}
+
+/* translates the robustness options shared by PackConfig and PackDirConfig */
+template <typename Config>
+static RetCode GetEmbedConfig(const Config& config, uint8_t flags,
+                              EmbedConfig& embed) {
+    embed.flags = flags;
+    if (config.checksums) {
+        embed.flags |= kFlagChecksums;
+    }
+    if (config.ecc == EccScheme::kHamming) {
+        embed.flags |= kFlagHamming;
+    }
+    if (config.interleave) {
+        embed.flags |= kFlagInterleaved;
+    }
+    if (config.sync) {
+        embed.flags |= kFlagSync;
+    }
+    embed.copies = config.copies;
+    if (!config.keyfile.empty()) {
+        StegKey key;
+        RetCode rc = LoadKeyFile(config.keyfile, key);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+        embed.key = key;
+    }
+    return RetCode::kSuccess;
+}
+
+/* whether a cover of the given type packed into outfile is written back as
+ * that type, the payload going into its own structure rather than an RGB
+ * image it could no longer be stored as */
+static bool KeepsCoverType(const std::string& cover,
+                           const std::string& outfile, OutputFormat format,
+                           ImageType type, const std::string& extension) {
+    if (GetImageType(cover) != type) {
+        return false;
+    }
+    std::string lower = outfile;
+    std::transform(lower.begin(), lower.end(), lower.begin(),
+                   [](unsigned char c) { return std::tolower(c); });
+    return (format == OutputFormat::kSameAsCover) ||
+           ((format == OutputFormat::kAuto) && lower.ends_with(extension));
+}
+
+#ifdef STEG_WITH_GIF
+static RetCode LoadGif(const std::string& filename, IndexedImage& image) {
+    RetCode rc = CheckImageLimits(filename, ImageType::kGif);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    return ReadIndexedGif(filename, image) ? RetCode::kSuccess
+                                           : RetCode::kInvalidFileFormat;
+}
+
+/* a GIF keeps its palette, the payload goes into the parity of the palette
+ * indices instead of the low nibbles of RGB pixels no palette could hold */
+template <typename Config>
+static RetCode EmbedInGif(const std::string& cover, const std::string& outfile,
+                          const std::vector<uint8_t>& payload,
+                          const EmbedConfig& embed, const Config& config) {
+    IndexedImage image;
+    RetCode rc = LoadGif(cover, image);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    PaletteCarrier carrier(image.indices, image.palette, image.width,
+                           image.transparent);
+    rc = EmbedPayload(carrier, payload, embed);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    if (config.create_dirs) {
+        rc = CreateParentDirectories(outfile);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+    return WriteIndexedGif(image, outfile) ? RetCode::kSuccess
+                                           : RetCode::kIoError;
+}
+#endif
+
+/* content_type and filename describe the payload in its header */
+template <typename Config>
+static RetCode EmbedInCover(const std::string& cover,
+                            const std::string& outfile,
+                            const std::vector<uint8_t>& payload,
+                            uint8_t flags, const Config& config,
+                            const std::string& content_type = "",
+                            const std::string& filename = "") {
+    /* at most one layout can take the place of the usual one */
+    if (static_cast<int>(config.differential) +
+            static_cast<int>(config.pair_swap) +
+            static_cast<int>(config.reversible) +
+            static_cast<int>(config.rotate_channels) +
+            static_cast<int>(!IsRowMajor(config.traversal)) >
+        1) {
+        return RetCode::kInvalidArgument;
+    }
+    EmbedConfig embed;
+    RetCode rc = GetEmbedConfig(config, flags, embed);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    embed.content_type = content_type;
+    embed.filename = filename;
+    /* GIF and ICO covers only keep their type in the usual layout, visited
+     * row by row */
+    const bool kUsualLayout = !config.differential && !config.pair_swap &&
+                              !config.reversible && !config.rotate_channels &&
+                              IsRowMajor(config.traversal);
+#ifdef STEG_WITH_GIF
+    if (kUsualLayout &&
+        KeepsCoverType(cover, outfile, config.output_format, ImageType::kGif,
+                       ".gif")) {
+        return EmbedInGif(cover, outfile, payload, embed, config);
+    }
+#endif
+    if (kUsualLayout &&
+        KeepsCoverType(cover, outfile, config.output_format, ImageType::kIco,
+                       ".ico")) {
+        if (config.create_dirs) {
+            rc = CreateParentDirectories(outfile);
+            if (rc != RetCode::kSuccess) {
+                return rc;
+            }
+        }
+        return EmbedIco(cover, outfile, payload, embed);
+    }
+    ImageType output_img_t = ImageType::kPng;
+    rc = ResolveOutputType(config.output_format, outfile, GetImageType(cover),
+                           true, output_img_t);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    boost::gil::rgb8_image_t output_img;
+    rc = LoadImage(cover, output_img);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    if (config.differential) {
+        const boost::gil::rgb8_image_t kReference = std::move(output_img);
+        rc = EmbedDifferential(kReference, output_img, payload, embed);
+    } else if (config.pair_swap) {
+        rc = EmbedPairSwap(output_img, payload, embed);
+    } else if (config.reversible) {
+        rc = EmbedReversible(output_img, payload, embed);
+    } else if (config.rotate_channels) {
+        rc = EmbedRotation(output_img, payload, embed);
+    } else if (!IsRowMajor(config.traversal)) {
+        rc = EmbedTraversal(output_img, payload, config.traversal, embed);
+    } else {
+        rc = EmbedPayload(output_img, payload, embed);
+    }
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    if (config.create_dirs) {
+        rc = CreateParentDirectories(outfile);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+
+    /* the output type is always lossless to avoid loss of data */
+    WriteImage(output_img, outfile, output_img_t, ReadIccProfile(cover));
+
+    return RetCode::kSuccess;
+}
+
+/* layout, when given, is set to where the payload was looked for last */
+static RetCode ExtractFromImage(const std::string& image,
+                                const std::string& keyfile,
+                                const std::string& reference,
+                                const Traversal& traversal,
+                                std::vector<uint8_t>& payload,
+                                PayloadHeader& header, DamageReport& damage,
+                                PayloadLayout* layout = nullptr) {
+    PayloadLayout ignored = PayloadLayout::kUsual;
+    if (layout == nullptr) {
+        layout = &ignored;
+    }
+    std::optional<StegKey> key;
+    if (!keyfile.empty()) {
+        key.emplace();
+        RetCode rc = LoadKeyFile(keyfile, *key);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+    if (!reference.empty()) {
+        boost::gil::rgb8_image_t input_img;
+        boost::gil::rgb8_image_t reference_img;
+        RetCode rc = LoadImage(image, input_img);
+        if (rc == RetCode::kSuccess) {
+            rc = LoadImage(reference, reference_img);
+        }
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+        *layout = PayloadLayout::kDifferential;
+        return ExtractDifferential(input_img, reference_img, payload, header,
+                                   damage, key);
+    }
+    if (GetImageType(image) == ImageType::kIco) {
+        *layout = PayloadLayout::kIcon;
+        return ExtractIco(image, payload, header, damage, key);
+    }
+#ifdef STEG_WITH_GIF
+    if (GetImageType(image) == ImageType::kGif) {
+        IndexedImage indexed;
+        RetCode rc = LoadGif(image, indexed);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+        PaletteCarrier carrier(indexed.indices, indexed.palette,
+                               indexed.width, indexed.transparent);
+        *layout = PayloadLayout::kPalette;
+        return ExtractPayload(carrier, payload, header, damage, key);
+    }
+#endif
+    boost::gil::rgb8_image_t input_img;
+    RetCode rc = LoadImage(image, input_img);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    /* nothing tells one order from another, only the one packed with */
+    if (!IsRowMajor(traversal)) {
+        *layout = PayloadLayout::kReordered;
+        return ExtractTraversal(input_img, traversal, payload, header, damage,
+                                key);
+    }
+    *layout = PayloadLayout::kUsual;
+    rc = ExtractPayload(input_img, payload, header, damage, key);
+    /* packed with rotate_channels, pair_swap, or reversible, which leave no
+     * trace of the usual layout, only a key can tell a rotation */
+    if ((rc == RetCode::kPayloadNotFound) && key) {
+        *layout = PayloadLayout::kRotation;
+        rc = ExtractRotation(input_img, payload, header, damage, key);
+    }
+    if (rc == RetCode::kPayloadNotFound) {
+        *layout = PayloadLayout::kPairSwap;
+        rc = ExtractPairSwap(input_img, payload, header, damage, key);
+    }
+    if (rc == RetCode::kPayloadNotFound) {
+        *layout = PayloadLayout::kReversible;
+        rc = ExtractReversible(input_img, payload, header, damage, key);
+    }
+    return rc;
+}
+
+/* writes the cover a reversible pack was made from, lossless so it is the
+ * same bit for bit */
+static RetCode RestoreCover(const std::string& image,
+                            const std::string& outfile) {
+    boost::gil::rgb8_image_t input_img;
+    RetCode rc = LoadImage(image, input_img);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    boost::gil::rgb8_image_t cover;
+    rc = RestoreReversibleCover(input_img, cover);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    ImageType cover_t = ImageType::kPng;
+    rc = ResolveOutputType(OutputFormat::kAuto, outfile, GetImageType(image),
+                           true, cover_t);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    WriteImage(cover, outfile, cover_t, ReadIccProfile(image));
+    return RetCode::kSuccess;
+}
+
+/* hides the password protected payloads in the cover, as the halves of a
+ * deniable pack or as layers, and wipes them */
+static RetCode EmbedSealed(const std::string& cover,
+                           const std::string& outfile,
+                           std::vector<DeniablePayload>& payloads,
+                           bool layered, const PackConfig& config) {
+    ImageType output_img_t = ImageType::kPng;
+    RetCode rc = ResolveOutputType(config.output_format, outfile,
+                                   GetImageType(cover), true, output_img_t);
+    boost::gil::rgb8_image_t output_img;
+    if (rc == RetCode::kSuccess) {
+        rc = LoadImage(cover, output_img);
+    }
+    if (rc == RetCode::kSuccess) {
+        rc = layered ? EmbedLayered(output_img, payloads, config.seed)
+                     : EmbedDeniable(output_img, payloads, config.seed);
+    }
+    for (DeniablePayload& payload : payloads) {
+        Wipe(payload.data);
+    }
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    if (config.create_dirs) {
+        rc = CreateParentDirectories(outfile);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+    WriteImage(output_img, outfile, output_img_t, ReadIccProfile(cover));
+    return RetCode::kSuccess;
+}
+
+static RetCode PackDeniable(const std::string& cover,
+                            const std::string& outfile,
+                            const std::vector<ArchiveEntry>& entries,
+                            const PackConfig& config) {
+    if (config.decoy_inputs.empty() != config.decoy_password.empty()) {
+        return RetCode::kInvalidArgument;
+    }
+
+    /* the decoy, when present, comes first so it lands in the first half */
+    std::vector<DeniablePayload> payloads;
+    if (!config.decoy_inputs.empty()) {
+        std::vector<ArchiveEntry> decoy_entries;
+        RetCode rc = CollectEntries(config.decoy_inputs, decoy_entries);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+        payloads.push_back({Serialize(decoy_entries), config.decoy_password});
+    }
+    payloads.push_back({Serialize(entries), config.password});
+    return EmbedSealed(cover, outfile, payloads, false, config);
+}
+
+/* each input is an archive of its own in a layer of its own, under the
+ * layer password of the same index */
+static RetCode PackLayered(const std::string& cover,
+                           const std::string& outfile,
+                           const std::vector<std::string>& inputs,
+                           const PackConfig& config) {
+    if ((config.layer_passwords.size() != inputs.size()) ||
+        !config.password.empty() || !config.decoy_inputs.empty() ||
+        config.differential || config.pair_swap || config.reversible ||
+        config.rotate_channels || !IsRowMajor(config.traversal) ||
+        !config.recipients.empty() || !config.recipient_passwords.empty() ||
+        config.checksums || (config.ecc != EccScheme::kNone) ||
+        config.interleave || (config.copies != 1) ||
+        !config.keyfile.empty() || config.sync) {
+        return RetCode::kInvalidArgument;
+    }
+    std::vector<DeniablePayload> payloads;
+    for (std::size_t i = 0; i < inputs.size(); ++i) {
+        std::vector<ArchiveEntry> entries;
+        RetCode rc = CollectEntries({inputs[i]}, entries);
+        if (rc != RetCode::kSuccess) {
+            for (DeniablePayload& payload : payloads) {
+                Wipe(payload.data);
+            }
+            return rc;
+        }
+        payloads.push_back({Serialize(entries), config.layer_passwords[i]});
+        WipeEntries(entries);
+    }
+    return EmbedSealed(cover, outfile, payloads, true, config);
+}
+
+RetCode Pack(const std::string& cover, const std::string& outfile,
+             const std::vector<std::string>& inputs) {
+    return Pack(cover, outfile, inputs, PackConfig{});
+}
+
+/* encrypts the payload to the recipients and recipient passwords, if there
+ * are any */
+template <typename Config>
+static RetCode EncryptPayload(const Config& config,
+                              std::vector<uint8_t>& payload) {
+    if (config.recipients.empty() && config.recipient_passwords.empty()) {
+        return RetCode::kSuccess;
+    }
+    std::vector<uint8_t> envelope;
+    RetCode rc = EncryptToRecipients(config.recipients,
+                                     config.recipient_passwords, payload,
+                                     envelope);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    Wipe(payload);
+    payload = std::move(envelope);
+    return RetCode::kSuccess;
+}
+
+/* decrypts a payload sent to recipients with the identity file or the
+ * recipient password, others are left as they are */
+template <typename Config>
+static RetCode DecryptPayload(const Config& config,
+                              std::vector<uint8_t>& payload) {
+    if (!IsRecipientEnvelope(payload)) {
+        return RetCode::kSuccess;
+    }
+    std::vector<uint8_t> plaintext;
+    RetCode rc = RetCode::kKeyMismatch;
+    if (!config.identity.empty()) {
+        Identity identity;
+        rc = LoadIdentityFile(config.identity, identity);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+        rc = DecryptWithIdentity(identity, payload, plaintext);
+    }
+    if ((rc == RetCode::kKeyMismatch) && !config.recipient_password.empty()) {
+        rc = DecryptWithPassword(config.recipient_password, payload,
+                                 plaintext);
+    }
+    if (rc == RetCode::kSuccess) {
+        Wipe(payload);
+        payload = std::move(plaintext);
+    }
+    return rc;
+}
+
+static RetCode PackEntries(const std::string& cover, const std::string& outfile,
+                           const std::vector<ArchiveEntry>& entries,
+                           const PackConfig& config) {
+    if (!config.password.empty()) {
+        if (config.differential || config.pair_swap || config.reversible ||
+            config.rotate_channels || !IsRowMajor(config.traversal) ||
+            !config.recipients.empty() ||
+            !config.recipient_passwords.empty()) {
+            return RetCode::kInvalidArgument;
+        }
+        if (config.checksums || (config.ecc != EccScheme::kNone) ||
+            config.interleave || (config.copies != 1) ||
+            !config.keyfile.empty() || config.sync) {
+            return RetCode::kInvalidArgument; /* would reveal the archive */
+        }
+        return PackDeniable(cover, outfile, entries, config);
+    }
+    if (!config.decoy_inputs.empty()) {
+        return RetCode::kInvalidArgument;
+    }
+    std::vector<uint8_t> archive = Serialize(entries);
+    RetCode rc = EncryptPayload(config, archive);
+    if (rc == RetCode::kSuccess) {
+        rc = EmbedInCover(cover, outfile, archive, 0, config);
+    }
+    Wipe(archive);
+    return rc;
+}
+
+static RetCode UnpackEntries(const std::string& image,
+                             const UnpackConfig& config,
+                             std::vector<ArchiveEntry>& entries,
+                             DamageReport& damage) {
+    std::vector<uint8_t> archive;
+    RetCode rc = RetCode::kSuccess;
+    if (!config.restore_cover.empty()) {
+        if (!config.password.empty()) {
+            return RetCode::kInvalidArgument;
+        }
+        rc = RestoreCover(image, config.restore_cover);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+    if (config.password.empty()) {
+        PayloadHeader header;
+        rc = ExtractFromImage(image, config.keyfile, config.reference,
+                              config.traversal, archive, header, damage);
+        if (IsRecipientEnvelope(archive)) {
+            /* nothing of an encrypted archive can be salvaged */
+            if (rc == RetCode::kPayloadDamaged) {
+                damage.truncated = true;
+                return rc;
+            }
+            if (rc == RetCode::kSuccess) {
+                rc = DecryptPayload(config, archive);
+            }
+        }
+    } else if (!config.reference.empty()) {
+        rc = RetCode::kInvalidArgument;
+    } else {
+        boost::gil::rgb8_image_t input_img;
+        rc = LoadImage(image, input_img);
+        if (rc == RetCode::kSuccess) {
+            rc = ExtractDeniable(input_img, config.password, archive);
+        }
+        if (rc == RetCode::kAuthenticationFailed) {
+            rc = ExtractLayered(input_img, config.password, archive);
+        }
+    }
+    if (rc == RetCode::kPayloadDamaged) {
+        /* names and sizes can only be trusted with an intact manifest */
+        std::vector<ByteRange> ranges = DamagedRanges(damage);
+        if (Deserialize(archive, entries) != RetCode::kSuccess) {
+            entries.clear();
+        }
+        std::size_t manifest_end =
+            entries.empty() ? archive.size() : entries.front().offset;
+        if (FirstDamaged(ranges, 0, manifest_end) < manifest_end) {
+            entries.clear();
+            damage.truncated = true;
+        }
+        TrimDamaged(entries, ranges);
+        Wipe(archive);
+        return rc;
+    }
+    if (rc == RetCode::kSuccess) {
+        rc = Deserialize(archive, entries);
+    }
+    Wipe(archive);
+    return rc;
+}
+
+RetCode Pack(const std::string& cover, const std::string& outfile,
+             const std::vector<std::string>& inputs, const PackConfig& config) {
+    if (!config.layer_passwords.empty()) {
+        return PackLayered(cover, outfile, inputs, config);
+    }
+
+    /* bundle the input files into a single archive */
+    std::vector<ArchiveEntry> entries;
+    RetCode rc = CollectEntries(inputs, entries);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    rc = PackEntries(cover, outfile, entries, config);
+    WipeEntries(entries);
+    return rc;
+}
+
+RetCode Unpack(const std::string& image, const std::string& outdir) {
+    return Unpack(image, outdir, UnpackConfig{});
+}
+
+RetCode Unpack(const std::string& image, const std::string& outdir,
+               const UnpackConfig& config) {
+    DamageReport damage;
+    return Unpack(image, outdir, config, damage);
+}
+
+RetCode Unpack(const std::string& image, const std::string& outdir,
+               const UnpackConfig& config, DamageReport& damage) {
+    std::vector<ArchiveEntry> entries;
+    RetCode rc = UnpackEntries(image, config, entries, damage);
+    if (rc == RetCode::kPayloadDamaged) {
+        rc = WriteSalvaged(outdir, entries, damage);
+    } else if (rc == RetCode::kSuccess) {
+        rc = WriteEntries(outdir, entries);
+    }
+    WipeEntries(entries);
+    return rc;
+}
+
+RetCode PackMessage(const std::string& cover, const std::string& outfile,
+                    const std::string& message, const PackConfig& config) {
+    ArchiveEntry entry;
+    entry.name = kMessageName;
+    entry.perms = std::filesystem::perms::owner_read |
+                  std::filesystem::perms::owner_write |
+                  std::filesystem::perms::group_read |
+                  std::filesystem::perms::others_read;
+    entry.data.assign(message.cbegin(), message.cend());
+    std::vector<ArchiveEntry> entries = {std::move(entry)};
+    RetCode rc = PackEntries(cover, outfile, entries, config);
+    WipeEntries(entries);
+    return rc;
+}
+
+RetCode UnpackMessage(const std::string& image, std::string& message,
+                      const UnpackConfig& config) {
+    std::vector<ArchiveEntry> entries;
+    DamageReport damage;
+    RetCode rc = UnpackEntries(image, config, entries, damage);
+    if (rc != RetCode::kSuccess) {
+        WipeEntries(entries);
+        return rc;
+    }
+
+    /* any archive holding exactly one file reads as a message */
+    if ((entries.size() == 1) && !entries.front().is_directory) {
+        message.assign(entries.front().data.cbegin(),
+                       entries.front().data.cend());
+    } else {
+        rc = RetCode::kInvalidPayload;
+    }
+    WipeEntries(entries);
+    return rc;
+}
+
+RetCode PackDirectory(const std::string& cover, const std::string& dir,
+                      const std::string& outfile, bool compress) {
+    PackDirConfig config;
+    config.compress = compress;
+    return PackDirectory(cover, dir, outfile, config);
+}
+
+RetCode PackDirectory(const std::string& cover, const std::string& dir,
+                      const std::string& outfile, const PackDirConfig& config) {
+    /* stream the directory tree through an in-memory tar */
+    std::vector<ArchiveEntry> entries;
+    RetCode rc = CollectTree(dir, entries);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    std::vector<uint8_t> payload = SerializeTar(entries);
+    WipeEntries(entries);
+    if (config.compress) {
+        std::vector<uint8_t> compressed = Compress(payload);
+        Wipe(payload);
+        payload = std::move(compressed);
+    }
+    rc = EncryptPayload(config, payload);
+    if (rc == RetCode::kSuccess) {
+        rc = EmbedInCover(cover, outfile, payload,
+                          config.compress ? kFlagCompressed : 0, config);
+    }
+    Wipe(payload);
+    return rc;
+}
+
+RetCode UnpackDirectory(const std::string& image, const std::string& outdir) {
+    DamageReport damage;
+    return UnpackDirectory(image, outdir, UnpackDirConfig{}, damage);
+}
+
+RetCode UnpackDirectory(const std::string& image, const std::string& outdir,
+                        const UnpackDirConfig& config, DamageReport& damage) {
+    std::vector<uint8_t> payload;
+    PayloadHeader header;
+    if (!config.restore_cover.empty()) {
+        RetCode rc = RestoreCover(image, config.restore_cover);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+    RetCode rc = ExtractFromImage(image, config.keyfile, config.reference,
+                                  config.traversal, payload, header, damage);
+    bool damaged = (rc == RetCode::kPayloadDamaged);
+    if ((rc != RetCode::kSuccess) && !damaged) {
+        return rc;
+    }
+    if (IsRecipientEnvelope(payload)) {
+        /* nothing of an encrypted archive can be salvaged */
+        if (damaged) {
+            std::vector<ArchiveEntry> entries;
+            damage.truncated = true;
+            return WriteSalvaged(outdir, entries, damage);
+        }
+        rc = DecryptPayload(config, payload);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+
+    /* version 0 payloads had no flags so fall back to the gzip signature */
+    bool compressed = (header.version == 0) ? IsCompressed(payload)
+                                            : (header.flags & kFlagCompressed);
+    std::vector<ByteRange> ranges = DamagedRanges(damage);
+    std::vector<uint8_t> tar;
+    if (compressed) {
+        /* nothing past the first damaged byte of a gzip stream is usable,
+         * the tar parser then marks the entry it ends in as partial */
+        if (damaged) {
+            payload.resize(FirstDamaged(ranges, 0, payload.size()));
+            ranges.clear();
+        }
+        bool inflated = Decompress(payload, tar);
+        Wipe(payload);
+        if (!inflated && !damaged) {
+            Wipe(tar);
+            return RetCode::kInvalidPayload;
+        }
+    } else {
+        tar = std::move(payload);
+    }
+
+    std::vector<ArchiveEntry> entries;
+    rc = DeserializeTar(tar, entries);
+    Wipe(tar);
+    if (damaged) {
+        damage.truncated = (rc != RetCode::kSuccess);
+        TrimDamaged(entries, ranges);
+        rc = WriteSalvaged(outdir, entries, damage);
+    } else if (rc == RetCode::kSuccess) {
+        rc = WriteEntries(outdir, entries);
+    }
+    WipeEntries(entries);
+    return rc;
+}
+
+RetCode PackData(const std::string& cover, const std::string& input,
+                 const std::string& outfile, const PackDataConfig& config) {
+    if (!std::filesystem::is_regular_file(input)) {
+        return RetCode::kFileNotFound;
+    }
+    std::vector<uint8_t> data;
+    if (!ReadFile(input, data)) {
+        return RetCode::kIoError;
+    }
+    std::string content_type = config.content_type.empty()
+                                   ? GuessContentType(data, input)
+                                   : config.content_type;
+    std::string filename = std::filesystem::path(input).filename().string();
+    if ((content_type.size() > kMaxContentTypeSize) ||
+        (filename.size() > kMaxContentTypeSize)) {
+        Wipe(data);
+        return RetCode::kInvalidArgument;
+    }
+    if (config.compress) {
+        std::vector<uint8_t> compressed = Compress(data);
+        Wipe(data);
+        data = std::move(compressed);
+    }
+
+    /* the header is in the clear, an encrypted payload says nothing about
+     * itself and has its type guessed from the data once decrypted */
+    const bool kEncrypted =
+        !config.recipients.empty() || !config.recipient_passwords.empty();
+    RetCode rc = EncryptPayload(config, data);
+    if (rc == RetCode::kSuccess) {
+        rc = EmbedInCover(cover, outfile, data,
+                          config.compress ? kFlagCompressed : 0, config,
+                          kEncrypted ? "" : content_type,
+                          kEncrypted ? "" : filename);
+    }
+    Wipe(data);
+    return rc;
+}
+
+/* extracts, decrypts and decompresses a payload and describes it */
+static RetCode ExtractData(const std::string& image,
+                           const UnpackDataConfig& config,
+                           std::vector<uint8_t>& data, DataInfo& info) {
+    info = DataInfo{};
+    if (!config.restore_cover.empty()) {
+        RetCode rc = RestoreCover(image, config.restore_cover);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+    PayloadHeader header;
+    DamageReport damage;
+    RetCode rc = ExtractFromImage(image, config.keyfile, config.reference,
+                                  config.traversal, data, header, damage);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    rc = DecryptPayload(config, data);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    if (header.flags & kFlagCompressed) {
+        std::vector<uint8_t> inflated;
+        bool ok = Decompress(data, inflated);
+        Wipe(data);
+        if (!ok) {
+            return RetCode::kInvalidPayload;
+        }
+        data = std::move(inflated);
+    }
+
+    info.filename = header.filename;
+    info.content_type = header.content_type;
+    if (info.content_type.empty()) {
+        info.content_type = GuessContentType(data, info.filename);
+        info.guessed = true;
+    }
+    info.size = data.size();
+    return RetCode::kSuccess;
+}
+
+RetCode InspectData(const std::string& image, const UnpackDataConfig& config,
+                    DataInfo& info) {
+    std::vector<uint8_t> data;
+    RetCode rc = ExtractData(image, config, data, info);
+    Wipe(data);
+    return rc;
+}
+
+RetCode UnpackData(const std::string& image, const std::string& out,
+                   const UnpackDataConfig& config, DataInfo& info) {
+    std::vector<uint8_t> data;
+    RetCode rc = ExtractData(image, config, data, info);
+    if (rc != RetCode::kSuccess) {
+        Wipe(data);
+        return rc;
+    }
+
+    /* only the last component of the recorded name is used, it could
+     * otherwise point anywhere */
+    std::filesystem::path path(out);
+    if (std::filesystem::is_directory(path)) {
+        std::filesystem::path name =
+            std::filesystem::path(info.filename).filename();
+        path /= (IsSafeEntryName(name.string()) && (name != "."))
+                    ? name
+                    : std::filesystem::path("payload");
+    }
+    if (!path.has_extension()) {
+        path += ContentTypeExtension(info.content_type);
+    }
+    info.path = path.string();
+
+    std::ofstream ofs(path, std::ofstream::binary | std::ofstream::trunc);
+    if (!ofs.is_open()) {
+        Wipe(data);
+        return RetCode::kIoError;
+    }
+    ofs.write(reinterpret_cast<const char*>(data.data()),
+              static_cast<std::streamsize>(data.size()));
+    ofs.close();
+    Wipe(data);
+    return ofs ? RetCode::kSuccess : RetCode::kIoError;
+}
+
+const char* PayloadLayoutName(PayloadLayout layout) {
+    switch (layout) {
+        case PayloadLayout::kUsual:
+            return "usual";
+        case PayloadLayout::kDifferential:
+            return "differential";
+        case PayloadLayout::kPairSwap:
+            return "pair swap";
+        case PayloadLayout::kReversible:
+            return "reversible";
+        case PayloadLayout::kRotation:
+            return "channel rotation";
+        case PayloadLayout::kReordered:
+            return "reordered";
+        case PayloadLayout::kPalette:
+            return "palette";
+        case PayloadLayout::kIcon:
+            return "icon";
+        case PayloadLayout::kDeniable:
+            return "deniable";
+    }
+    return "unknown";
+}
+
+/* what the stored bytes are, as far as can be told without decrypting */
+static std::string DescribeContents(const std::vector<uint8_t>& payload,
+                                    const PayloadHeader& header) {
+    if (!header.content_type.empty()) {
+        return header.content_type;
+    }
+    if ((payload.size() >= 4) &&
+        (ReadInt<uint32_t>(payload, 0) == kArchiveMagic)) {
+        return "file archive";
+    }
+    const std::string kMagic("ustar");
+    if ((payload.size() >= kTarBlockSize) &&
+        std::equal(kMagic.cbegin(), kMagic.cend(), payload.cbegin() + 257)) {
+        return "directory archive";
+    }
+    return "";
+}
+
+/* the entry names of an archive, left empty for other payloads */
+static void ListEntries(const std::vector<uint8_t>& payload,
+                        const std::string& contents,
+                        std::vector<std::string>& names) {
+    std::vector<ArchiveEntry> entries;
+    RetCode rc = RetCode::kInvalidPayload;
+    if (contents == "file archive") {
+        rc = Deserialize(payload, entries);
+    } else if (contents == "directory archive") {
+        rc = DeserializeTar(payload, entries);
+    }
+    if (rc == RetCode::kSuccess) {
+        for (const ArchiveEntry& entry : entries) {
+            names.push_back(entry.name);
+        }
+    }
+    WipeEntries(entries);
+}
+
+RetCode Inspect(const std::string& image, const InspectConfig& config,
+                InspectReport& report) {
+    report = InspectReport{};
+    std::vector<uint8_t> payload;
+    PayloadLayout layout = PayloadLayout::kUsual;
+    RetCode rc =
+        ExtractFromImage(image, config.keyfile, config.reference,
+                         config.traversal, payload, report.header,
+                         report.damage, &layout);
+    if (rc == RetCode::kUnsupportedFormat) {
+        /* the header is there, only what follows it cannot be read */
+        report.found = true;
+        report.supported = false;
+        return RetCode::kSuccess;
+    }
+    if (((rc == RetCode::kPayloadNotFound) ||
+         (rc == RetCode::kInvalidPayload)) &&
+        !config.password.empty()) {
+        /* a password pack has no header to find, only the password can
+         * tell it from noise */
+        boost::gil::rgb8_image_t input_img;
+        rc = LoadImage(image, input_img);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+        rc = ExtractDeniable(input_img, config.password, payload);
+        if (rc == RetCode::kSuccess) {
+            report.found = true;
+            report.layout = PayloadLayout::kDeniable;
+            report.bits_per_channel = 4;
+            report.deniable = true;
+            report.payload_size = payload.size();
+            report.contents = DescribeContents(payload, report.header);
+            ListEntries(payload, report.contents, report.entries);
+        }
+        Wipe(payload);
+        return (rc == RetCode::kAuthenticationFailed) ? RetCode::kSuccess
+                                                      : rc;
+    }
+    if ((rc == RetCode::kPayloadNotFound) ||
+        (rc == RetCode::kInvalidPayload)) {
+        return RetCode::kSuccess;
+    }
+    if ((rc != RetCode::kSuccess) && (rc != RetCode::kPayloadDamaged)) {
+        return rc;
+    }
+
+    report.found = true;
+    report.layout = layout;
+    /* the others carry a bit in a pixel's palette index or the order of a
+     * pair rather than in the bits of a channel */
+    if ((layout == PayloadLayout::kUsual) ||
+        (layout == PayloadLayout::kIcon) ||
+        (layout == PayloadLayout::kRotation) ||
+        (layout == PayloadLayout::kReordered)) {
+        report.bits_per_channel = 4;
+    } else if ((layout == PayloadLayout::kDifferential) ||
+               (layout == PayloadLayout::kReversible)) {
+        report.bits_per_channel = 1;
+    }
+    report.payload_size = payload.size();
+    const PayloadHeader& header = report.header;
+    report.compressed =
+        (header.flags & kFlagCompressed) ||
+        ((header.version == 0) && IsCompressed(payload)) ||
+        (std::find(header.stages.cbegin(), header.stages.cend(),
+                   static_cast<uint8_t>(PipelineStage::kCompress)) !=
+         header.stages.cend());
+    report.pipeline_encrypted =
+        std::find(header.stages.cbegin(), header.stages.cend(),
+                  static_cast<uint8_t>(PipelineStage::kEncrypt)) !=
+        header.stages.cend();
+    CountRecipients(payload, report.recipients, report.recipient_passwords);
+    if (!report.compressed && !report.pipeline_encrypted) {
+        report.contents = DescribeContents(payload, header);
+        ListEntries(payload, report.contents, report.entries);
+    } else {
+        report.contents = header.content_type;
+    }
+    Wipe(payload);
+    return RetCode::kSuccess;
+}
+
+/* whether a payload can be packed again into its own image. The layouts
+ * other than these cannot take a second payload over the first, or lose
+ * what they were chosen for when they do, and the stages of a pipeline
+ * cannot be redone without its password. */
+static bool Repackable(PayloadLayout layout, const PayloadHeader& header) {
+    return ((layout == PayloadLayout::kUsual) ||
+            (layout == PayloadLayout::kIcon) ||
+            (layout == PayloadLayout::kPalette) ||
+            (layout == PayloadLayout::kRotation) ||
+            (layout == PayloadLayout::kReordered)) &&
+           !(header.flags & kFlagPipeline);
+}
+
+RetCode Append(const std::string& image, const std::string& outfile,
+               const std::vector<std::string>& inputs,
+               const AppendConfig& config) {
+    std::vector<ArchiveEntry> added;
+    RetCode rc = CollectEntries(inputs, added);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    std::vector<uint8_t> payload;
+    PayloadHeader header;
+    DamageReport damage;
+    PayloadLayout layout = PayloadLayout::kUsual;
+    rc = ExtractFromImage(image, config.keyfile, "", config.traversal, payload,
+                          header, damage, &layout);
+    if ((rc == RetCode::kSuccess) && !Repackable(layout, header)) {
+        rc = RetCode::kInvalidArgument;
+    }
+    /* new recipients only for a payload that had some */
+    const bool kEncrypted = IsRecipientEnvelope(payload);
+    if ((rc == RetCode::kSuccess) &&
+        (kEncrypted ==
+         (config.recipients.empty() && config.recipient_passwords.empty()))) {
+        rc = RetCode::kInvalidArgument;
+    }
+    if ((rc == RetCode::kSuccess) && kEncrypted) {
+        rc = DecryptPayload(config, payload);
+    }
+    const bool kCompressed = (header.version == 0)
+                                 ? IsCompressed(payload)
+                                 : (header.flags & kFlagCompressed);
+    if ((rc == RetCode::kSuccess) && kCompressed) {
+        std::vector<uint8_t> inflated;
+        if (!Decompress(payload, inflated)) {
+            rc = RetCode::kInvalidPayload;
+        }
+        Wipe(payload);
+        payload = std::move(inflated);
+    }
+    if (rc != RetCode::kSuccess) {
+        Wipe(payload);
+        WipeEntries(added);
+        return rc;
+    }
+
+    /* the existing entries keep their place at the front of the table of
+     * contents, a lone file is named as unmerge-data would name it */
+    std::vector<ArchiveEntry> entries;
+    bool is_tar = false;
+    if (!header.content_type.empty()) {
+        ArchiveEntry entry;
+        entry.name = header.filename.empty()
+                         ? "data" + ContentTypeExtension(header.content_type)
+                         : header.filename;
+        entry.perms = std::filesystem::perms::owner_read |
+                      std::filesystem::perms::owner_write;
+        entry.data = std::move(payload);
+        entries.push_back(std::move(entry));
+    } else if (DescribeContents(payload, header) == "file archive") {
+        rc = Deserialize(payload, entries);
+    } else if (DescribeContents(payload, header) == "directory archive") {
+        is_tar = true;
+        rc = DeserializeTar(payload, entries);
+    } else {
+        rc = RetCode::kInvalidPayload;
+    }
+    Wipe(payload);
+    for (std::size_t i = 0; (i < added.size()) && (rc == RetCode::kSuccess);
+         ++i) {
+        auto same_name = [&added, i](const ArchiveEntry& entry) {
+            return entry.name == added[i].name;
+        };
+        if (std::any_of(entries.cbegin(), entries.cend(), same_name)) {
+            rc = RetCode::kInvalidArgument;
+        } else {
+            entries.push_back(std::move(added[i]));
+        }
+    }
+    WipeEntries(added);
+    if (rc != RetCode::kSuccess) {
+        WipeEntries(entries);
+        return rc;
+    }
+    payload = is_tar ? SerializeTar(entries) : Serialize(entries);
+    WipeEntries(entries);
+    if (kCompressed) {
+        std::vector<uint8_t> compressed = Compress(payload);
+        Wipe(payload);
+        payload = std::move(compressed);
+    }
+    if (kEncrypted) {
+        rc = EncryptPayload(config, payload);
+        if (rc != RetCode::kSuccess) {
+            Wipe(payload);
+            return rc;
+        }
+    }
+
+    /* the image is its own cover, packed again with the options it was
+     * packed with */
+    PackConfig pack;
+    pack.create_dirs = config.create_dirs;
+    pack.checksums = (header.flags & kFlagChecksums);
+    pack.ecc = (header.flags & kFlagHamming) ? EccScheme::kHamming
+                                             : EccScheme::kNone;
+    pack.interleave = (header.flags & kFlagInterleaved);
+    pack.copies = header.copies;
+    pack.keyfile = config.keyfile;
+    pack.sync = (header.flags & kFlagSync);
+    pack.rotate_channels = (layout == PayloadLayout::kRotation);
+    pack.traversal = config.traversal;
+    pack.output_format = config.output_format;
+    rc = EmbedInCover(image, outfile, payload,
+                      kCompressed ? kFlagCompressed : 0, pack);
+    Wipe(payload);
+    return rc;
+}
+
+RetCode Rekey(const std::string& image, const std::string& outfile,
+             const RekeyConfig& config) {
+    /* a payload without recipients would be written out in the clear */
+    if (config.recipients.empty() && config.recipient_passwords.empty()) {
+        return RetCode::kInvalidArgument;
+    }
+    std::vector<uint8_t> payload;
+    PayloadHeader header;
+    DamageReport damage;
+    PayloadLayout layout = PayloadLayout::kUsual;
+    RetCode rc = ExtractFromImage(image, config.keyfile, "", config.traversal,
+                                  payload, header, damage, &layout);
+    if (rc != RetCode::kSuccess) {
+        Wipe(payload);
+        return rc;
+    }
+    if (!Repackable(layout, header) || !IsRecipientEnvelope(payload)) {
+        Wipe(payload);
+        return RetCode::kInvalidArgument;
+    }
+    rc = DecryptPayload(config, payload);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    /* a new file key, so a removed recipient who kept the old one learns
+     * nothing from later rotations, the plaintext is wiped on the way */
+    rc = EncryptPayload(config, payload);
+    if (rc != RetCode::kSuccess) {
+        Wipe(payload);
+        return rc;
+    }
+
+    /* the image is its own cover, packed again with the options it was
+     * packed with */
+    PackConfig pack;
+    pack.create_dirs = config.create_dirs;
+    pack.checksums = (header.flags & kFlagChecksums);
+    pack.ecc = (header.flags & kFlagHamming) ? EccScheme::kHamming
+                                             : EccScheme::kNone;
+    pack.interleave = (header.flags & kFlagInterleaved);
+    pack.copies = header.copies;
+    pack.keyfile = config.keyfile;
+    pack.sync = (header.flags & kFlagSync);
+    pack.rotate_channels = (layout == PayloadLayout::kRotation);
+    /* a reordered payload was only found with the order it was packed
+     * with, so packing with it again keeps the order */
+    pack.traversal = config.traversal;
+    pack.output_format = config.output_format;
+    rc = EmbedInCover(image, outfile, payload,
+                      header.flags & kFlagCompressed, pack);
+    Wipe(payload);
+    return rc;
+}
+
+}  // namespace steganography
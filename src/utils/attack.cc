@@ -0,0 +1,159 @@
+#include "utils/attack.hpp"
+
+#include <algorithm>
+#include <bit>
+#include <boost/gil.hpp>
+#ifdef STEG_WITH_JPEG
+#include <boost/gil/extension/io/jpeg.hpp>
+#endif
+#include <boost/gil/extension/numeric/resample.hpp>
+#include <boost/gil/extension/numeric/sampler.hpp>
+#include <cmath>
+#include <cstdint>
+#include <filesystem>
+#include <random>
+#include <string>
+#include <vector>
+
+#include "utils/image_io.hpp"
+#include "utils/metrics.hpp"
+#include "utils/payload.hpp"
+
+namespace steganography {
+
+static void ApplyScale(boost::gil::rgb8_image_t& image, double scale) {
+    /* shrink then restore the original size so pixel positions line up */
+    std::ptrdiff_t width = std::max<std::ptrdiff_t>(
+        1, std::lround(static_cast<double>(image.width()) * scale));
+    std::ptrdiff_t height = std::max<std::ptrdiff_t>(
+        1, std::lround(static_cast<double>(image.height()) * scale));
+    boost::gil::rgb8_image_t scaled(width, height);
+    boost::gil::resize_view(boost::gil::const_view(image),
+                            boost::gil::view(scaled),
+                            boost::gil::bilinear_sampler());
+    boost::gil::resize_view(boost::gil::const_view(scaled),
+                            boost::gil::view(image),
+                            boost::gil::bilinear_sampler());
+}
+
+#ifdef STEG_WITH_JPEG
+static void ApplyJpeg(boost::gil::rgb8_image_t& image, int quality) {
+    std::filesystem::path tmp = std::filesystem::temp_directory_path() /
+                                ("steganography-attack-" +
+                                 std::to_string(std::random_device{}()) +
+                                 ".jpg");
+    boost::gil::write_view(tmp.string(), boost::gil::const_view(image),
+                           boost::gil::image_write_info<boost::gil::jpeg_tag>(
+                               quality));
+    image = ReadImage(tmp.string(), ImageType::kJpeg);
+    std::error_code ec;
+    std::filesystem::remove(tmp, ec);
+}
+#endif
+
+static void ApplyNoise(boost::gil::rgb8_image_t& image, double stddev) {
+    /* fixed seed so repeated runs report the same numbers */
+    std::mt19937 rng(0);
+    std::normal_distribution<double> noise(0.0, stddev);
+    auto view = boost::gil::view(image);
+    for (std::ptrdiff_t row = 0; row < view.height(); ++row) {
+        for (std::ptrdiff_t col = 0; col < view.width(); ++col) {
+            for (int i = 0; i < 3; ++i) {
+                double value = view(col, row)[i] + noise(rng);
+                view(col, row)[i] = static_cast<uint8_t>(
+                    std::clamp(std::lround(value), 0L, 255L));
+            }
+        }
+    }
+}
+
+static boost::gil::rgb8_image_t LowNibbles(
+    const boost::gil::rgb8_image_t& image) {
+    const int kLowNibble = 0x0F;
+    boost::gil::rgb8_image_t nibbles(image.width(), image.height());
+    auto src = boost::gil::const_view(image);
+    auto dst = boost::gil::view(nibbles);
+    for (std::ptrdiff_t row = 0; row < src.height(); ++row) {
+        for (std::ptrdiff_t col = 0; col < src.width(); ++col) {
+            for (int i = 0; i < 3; ++i) {
+                dst(col, row)[i] =
+                    static_cast<uint8_t>((src(col, row)[i] & kLowNibble) << 4);
+            }
+        }
+    }
+    return nibbles;
+}
+
+RetCode SimulateAttack(const std::string& image, const AttackConfig& config,
+                       AttackReport& report) {
+    if ((config.jpeg_quality < 0) || (config.jpeg_quality > 100) ||
+        (config.scale <= 0.0) || (config.scale > 1.0) || (config.noise < 0.0)) {
+        return RetCode::kInvalidArgument;
+    }
+#ifndef STEG_WITH_JPEG
+    if (config.jpeg_quality > 0) {
+        return RetCode::kInvalidArgument;
+    }
+#endif
+    boost::gil::rgb8_image_t original;
+    RetCode rc = LoadImage(image, original);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+
+    boost::gil::rgb8_image_t attacked = original;
+    if (config.scale < 1.0) {
+        ApplyScale(attacked, config.scale);
+    }
+#ifdef STEG_WITH_JPEG
+    if (config.jpeg_quality > 0) {
+        ApplyJpeg(attacked, config.jpeg_quality);
+    }
+#endif
+    if (config.noise > 0.0) {
+        ApplyNoise(attacked, config.noise);
+    }
+
+    /* compare the embedded bits before and after the attack */
+    auto before = boost::gil::const_view(original);
+    auto after = boost::gil::const_view(attacked);
+    std::size_t bits_intact = 0;
+    for (std::ptrdiff_t row = 0; row < before.height(); ++row) {
+        for (std::ptrdiff_t col = 0; col < before.width(); ++col) {
+            for (int i = 0; i < 3; ++i) {
+                int diff = (before(col, row)[i] ^ after(col, row)[i]) & 0x0F;
+                bits_intact += 4 - static_cast<std::size_t>(std::popcount(
+                                       static_cast<unsigned>(diff)));
+            }
+        }
+    }
+    const std::size_t kChannels =
+        static_cast<std::size_t>(before.width() * before.height()) * 3;
+    report.bits_intact =
+        static_cast<double>(bits_intact) / static_cast<double>(kChannels * 4);
+
+    std::size_t bytes_intact = 0;
+    const std::size_t kCells = CellCount(original);
+    for (std::size_t cell = 0; cell < kCells; ++cell) {
+        bytes_intact += ReadCell(before, cell) == ReadCell(after, cell);
+    }
+    report.bytes_intact = (kCells == 0) ? 0.0
+                                        : static_cast<double>(bytes_intact) /
+                                              static_cast<double>(kCells);
+
+    report.secret_psnr =
+        Psnr(boost::gil::const_view(LowNibbles(original)),
+             boost::gil::const_view(LowNibbles(attacked)));
+
+    std::vector<uint8_t> payload_before;
+    std::vector<uint8_t> payload_after;
+    report.had_payload =
+        ExtractPayload(original, payload_before) == RetCode::kSuccess;
+    report.payload_intact =
+        report.had_payload &&
+        (ExtractPayload(attacked, payload_after) == RetCode::kSuccess) &&
+        (payload_before == payload_after);
+    return RetCode::kSuccess;
+}
+
+}  // namespace steganography
@@ -0,0 +1,83 @@
+#include "utils/avif.hpp"
+
+#include <avif/avif.h>
+
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <memory>
+#include <string>
+
+namespace steganography {
+
+struct DecoderDeleter {
+    void operator()(avifDecoder* decoder) const { avifDecoderDestroy(decoder); }
+};
+
+using DecoderPtr = std::unique_ptr<avifDecoder, DecoderDeleter>;
+
+/* a decoder with the file's image properties parsed, null on failure */
+static DecoderPtr ParseAvif(const std::string& filename) {
+    DecoderPtr decoder(avifDecoderCreate());
+    if (!decoder) {
+        return nullptr;
+    }
+    /* phone cameras write files missing properties the specification
+     * requires, which every viewer decodes anyway */
+    decoder->strictFlags = AVIF_STRICT_DISABLED;
+    if ((avifDecoderSetIOFile(decoder.get(), filename.c_str()) !=
+         AVIF_RESULT_OK) ||
+        (avifDecoderParse(decoder.get()) != AVIF_RESULT_OK)) {
+        return nullptr;
+    }
+    return decoder;
+}
+
+bool ReadAvif(const std::string& filename, boost::gil::rgb8_image_t& image) {
+    DecoderPtr decoder = ParseAvif(filename);
+    if (!decoder || (avifDecoderNextImage(decoder.get()) != AVIF_RESULT_OK)) {
+        return false;
+    }
+
+    /* libavif converts from YUV at any bit depth down to 8 bit RGB */
+    avifRGBImage rgb;
+    avifRGBImageSetDefaults(&rgb, decoder->image);
+    rgb.format = AVIF_RGB_FORMAT_RGB;
+    rgb.depth = 8;
+    if (avifRGBImageAllocatePixels(&rgb) != AVIF_RESULT_OK) {
+        return false;
+    }
+    const bool kConverted =
+        (avifImageYUVToRGB(decoder->image, &rgb) == AVIF_RESULT_OK);
+    if (kConverted) {
+        image = boost::gil::rgb8_image_t(rgb.width, rgb.height);
+        auto view = boost::gil::view(image);
+        for (uint32_t y = 0; y < rgb.height; ++y) {
+            const uint8_t* row = rgb.pixels + static_cast<std::size_t>(y) *
+                                                  rgb.rowBytes;
+            for (uint32_t x = 0; x < rgb.width; ++x) {
+                view(x, y) = boost::gil::rgb8_pixel_t(
+                    row[x * 3], row[x * 3 + 1], row[x * 3 + 2]);
+            }
+        }
+    }
+    avifRGBImageFreePixels(&rgb);
+    return kConverted;
+}
+
+bool ReadAvifHeader(const std::string& filename, uint64_t& width,
+                    uint64_t& height, uint64_t& pixel_bytes) {
+    DecoderPtr decoder = ParseAvif(filename);
+    if (!decoder) {
+        return false;
+    }
+    width = decoder->image->width;
+    height = decoder->image->height;
+
+    /* the YUV planes and any alpha plane at the image's bit depth */
+    const uint64_t kChannels = decoder->alphaPresent ? 4 : 3;
+    pixel_bytes = kChannels * ((decoder->image->depth > 8) ? 2 : 1);
+    return true;
+}
+
+}  // namespace steganography
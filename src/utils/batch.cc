@@ -0,0 +1,585 @@
+#include "utils/batch.hpp"
+
+#include <algorithm>
+#include <atomic>
+#include <chrono>
+#include <cstddef>
+#include <cstdint>
+#include <exception>
+#include <filesystem>
+#include <fstream>
+#include <iomanip>
+#include <map>
+#include <mutex>
+#include <set>
+#include <string>
+#include <system_error>
+#include <thread>
+#include <utility>
+#include <vector>
+
+#include <openssl/evp.h>
+
+#include "utils/archive.hpp"
+#include "utils/bytes.hpp"
+#include "utils/crypto.hpp"
+#include "utils/image_io.hpp"
+#include "utils/parallel.hpp"
+
+namespace steganography {
+
+/* splits a line on the separator, a field in double quotes may hold the
+ * separator and doubled quotes, false for an unterminated quote */
+static bool SplitFields(const std::string& line, char separator,
+                        std::vector<std::string>& fields) {
+    fields.assign(1, "");
+    bool quoted = false;
+    for (std::size_t i = 0; i < line.size(); ++i) {
+        const char kChar = line[i];
+        if (quoted) {
+            if ((kChar == '"') && (i + 1 < line.size()) &&
+                (line[i + 1] == '"')) {
+                fields.back() += '"';
+                ++i;
+            } else if (kChar == '"') {
+                quoted = false;
+            } else {
+                fields.back() += kChar;
+            }
+        } else if ((kChar == '"') && fields.back().empty()) {
+            quoted = true;
+        } else if (kChar == separator) {
+            fields.emplace_back();
+        } else {
+            fields.back() += kChar;
+        }
+    }
+    return !quoted;
+}
+
+/* quotes a field that would otherwise be split or misread */
+static std::string QuoteField(const std::string& field, char separator) {
+    if ((field.find(separator) == std::string::npos) &&
+        (field.find('"') == std::string::npos) &&
+        (field.find('\n') == std::string::npos)) {
+        return field;
+    }
+    std::string quoted = "\"";
+    for (char c : field) {
+        quoted += c;
+        if (c == '"') {
+            quoted += '"';
+        }
+    }
+    return quoted + "\"";
+}
+
+/* relative paths in a manifest are taken from its directory */
+static std::string ManifestPath(const std::filesystem::path& base,
+                                const std::string& path) {
+    if (path.empty() || std::filesystem::path(path).is_absolute()) {
+        return path;
+    }
+    return (base / path).lexically_normal().string();
+}
+
+/* fills in a column of the job, false for a value it cannot take */
+static bool SetColumn(BatchJob& job, const std::string& column,
+                      const std::string& value,
+                      const std::filesystem::path& base) {
+    if (column == "cover") {
+        job.cover = ManifestPath(base, value);
+    } else if (column == "secret") {
+        job.secret = ManifestPath(base, value);
+    } else if (column == "output") {
+        job.output = ManifestPath(base, value);
+    } else if (column == "keyfile") {
+        job.keyfile = ManifestPath(base, value);
+    } else if (column == "password") {
+        job.password = value;
+    } else if (value.empty()) {
+        /* the remaining columns keep their defaults when blank */
+    } else if (column == "action") {
+        if (value == "merge") {
+            job.action = BatchAction::kMerge;
+        } else if (value == "merge-data") {
+            job.action = BatchAction::kMergeData;
+        } else {
+            return false;
+        }
+    } else if (column == "bits") {
+        if ((value.size() != 1) || (value[0] < '1') || (value[0] > '8')) {
+            return false;
+        }
+        job.bits = value[0] - '0';
+    } else if (column == "method") {
+        job.method = value;
+    } else if (column == "compress") {
+        if ((value != "yes") && (value != "no")) {
+            return false;
+        }
+        job.compress = (value == "yes");
+    } else if (column == "format") {
+        /* every output carries data, so never JPEG */
+        const std::map<std::string, OutputFormat> kFormats = {
+            {"auto", OutputFormat::kAuto},
+            {"png", OutputFormat::kPng},
+            {"jxl", OutputFormat::kJxl},
+            {"same", OutputFormat::kSameAsCover},
+        };
+        auto format = kFormats.find(value);
+        if (format == kFormats.end()) {
+            return false;
+        }
+        job.output_format = format->second;
+    }
+    return true;
+}
+
+RetCode ReadManifest(const std::string& path, BatchManifest& manifest,
+                     std::size_t& line) {
+    manifest = BatchManifest{};
+    line = 0;
+    std::ifstream ifs(path);
+    if (!ifs.is_open()) {
+        return RetCode::kFileNotFound;
+    }
+    const std::filesystem::path kBase =
+        std::filesystem::path(path).parent_path();
+
+    std::string text;
+    std::vector<std::string> columns;
+    while (columns.empty() && std::getline(ifs, text)) {
+        ++line;
+        if (!text.empty() && (text.back() == '\r')) {
+            text.pop_back();
+        }
+        if (text.empty()) {
+            continue;
+        }
+        manifest.separator = (text.find('\t') != std::string::npos) ? '\t'
+                                                                    : ',';
+        if (!SplitFields(text, manifest.separator, columns)) {
+            return RetCode::kInvalidArgument;
+        }
+    }
+    const std::vector<std::string> kKnown = {
+        "cover",    "secret", "output", "action",   "keyfile",
+        "password", "bits",   "method", "compress", "format"};
+    std::map<std::string, int> seen;
+    for (const auto& column : columns) {
+        if (std::find(kKnown.begin(), kKnown.end(), column) == kKnown.end()) {
+            return RetCode::kInvalidArgument;
+        }
+        ++seen[column];
+    }
+    for (const char* required : {"cover", "secret", "output"}) {
+        if (!seen.contains(required)) {
+            return RetCode::kInvalidArgument;
+        }
+    }
+    for (const auto& [column, count] : seen) {
+        if (count > 1) {
+            return RetCode::kInvalidArgument;
+        }
+    }
+
+    std::vector<std::string> fields;
+    while (std::getline(ifs, text)) {
+        ++line;
+        if (!text.empty() && (text.back() == '\r')) {
+            text.pop_back();
+        }
+        if (text.empty()) {
+            continue;
+        }
+        if (!SplitFields(text, manifest.separator, fields) ||
+            (fields.size() != columns.size())) {
+            return RetCode::kInvalidArgument;
+        }
+        BatchJob job;
+        job.line = line;
+        bool has_action = false;
+        for (std::size_t i = 0; i < columns.size(); ++i) {
+            if (!SetColumn(job, columns[i], fields[i], kBase)) {
+                return RetCode::kInvalidArgument;
+            }
+            has_action |= (columns[i] == "action") && !fields[i].empty();
+        }
+        if (job.cover.empty() || job.secret.empty() || job.output.empty()) {
+            return RetCode::kInvalidArgument;
+        }
+        if (!has_action) {
+            job.action = (GetImageType(job.secret) != ImageType::kUnknown)
+                             ? BatchAction::kMerge
+                             : BatchAction::kMergeData;
+        }
+        manifest.jobs.push_back(job);
+    }
+    line = 0;
+    return RetCode::kSuccess;
+}
+
+/* a row done by an earlier run, as recorded in the state file */
+struct DoneRow {
+    std::string row;    /* RowDigest of the row */
+    std::string output; /* SHA-256 of what it wrote */
+};
+
+/* SHA-256 of the file in hex, empty when it cannot be read */
+static std::string FileDigest(const std::string& path) {
+    std::ifstream ifs(path, std::ios::binary);
+    if (!ifs.is_open()) {
+        return "";
+    }
+    EVP_MD_CTX* ctx = EVP_MD_CTX_new();
+    EVP_DigestInit_ex(ctx, EVP_sha256(), nullptr);
+    const std::streamsize kChunk = 1 << 16;
+    std::vector<char> buffer(kChunk);
+    while (ifs.read(buffer.data(), kChunk) || (ifs.gcount() > 0)) {
+        EVP_DigestUpdate(ctx, buffer.data(),
+                         static_cast<std::size_t>(ifs.gcount()));
+    }
+    std::vector<uint8_t> digest(EVP_MD_size(EVP_sha256()));
+    EVP_DigestFinal_ex(ctx, digest.data(), nullptr);
+    EVP_MD_CTX_free(ctx);
+    return ToHex(digest.data(), digest.size());
+}
+
+/* SHA-256 of everything a row asks for and of the files it reads, so an
+ * edited row or input is run again. The password only goes in stretched
+ * with PBKDF2, salted with the rest of the row, since the state file is
+ * kept on disk and must not make it cheap to guess. */
+static std::string RowDigest(const BatchJob& job) {
+    std::string fields;
+    for (const std::string& field :
+         {job.cover, job.secret, job.output, job.keyfile, job.method,
+          std::to_string(static_cast<int>(job.action)),
+          std::to_string(job.bits), std::to_string(job.compress),
+          std::to_string(static_cast<int>(job.output_format)),
+          FileDigest(job.cover), FileDigest(job.secret),
+          FileDigest(job.keyfile)}) {
+        fields += field;
+        fields += '\0';
+    }
+    std::vector<uint8_t> digest(EVP_MD_size(EVP_sha256()));
+    EVP_Digest(fields.data(), fields.size(), digest.data(), nullptr,
+               EVP_sha256(), nullptr);
+    if (job.password.empty()) {
+        return ToHex(digest.data(), digest.size());
+    }
+    const StegKey kKey = DerivePasswordKey(job.password, digest);
+    return ToHex(kKey.material.data(), kKey.material.size());
+}
+
+/* one '<row digest>\t<output digest>\t<output>' line per row done, a later
+ * line for the same output replaces an earlier one */
+static std::map<std::string, DoneRow> LoadState(const std::string& path) {
+    std::map<std::string, DoneRow> done;
+    std::ifstream ifs(path);
+    std::string line;
+    while (std::getline(ifs, line)) {
+        const std::size_t kFirst = line.find('\t');
+        const std::size_t kSecond = line.find('\t', kFirst + 1);
+        if ((kFirst == std::string::npos) ||
+            (kSecond == std::string::npos)) {
+            continue; /* cut short when the last run was interrupted */
+        }
+        done[line.substr(kSecond + 1)] = {
+            line.substr(0, kFirst),
+            line.substr(kFirst + 1, kSecond - kFirst - 1)};
+    }
+    return done;
+}
+
+/* spaces out the starts of the rows so they keep to the batch's limits,
+ * each row pushes the next start back by its share of both */
+class Throttle {
+   public:
+    explicit Throttle(const BatchConfig& config)
+        : per_image_((config.max_images_per_minute > 0.0)
+                         ? 60.0 / config.max_images_per_minute
+                         : 0.0),
+          per_byte_((config.max_bytes_per_second > 0)
+                        ? 1.0 / static_cast<double>(
+                                    config.max_bytes_per_second)
+                        : 0.0),
+          next_(std::chrono::steady_clock::now()) {}
+
+    /* blocks until a row reading bytes may start */
+    void Wait(uint64_t bytes) {
+        if ((per_image_ == 0.0) && (per_byte_ == 0.0)) {
+            return;
+        }
+        std::chrono::steady_clock::time_point start;
+        {
+            std::lock_guard<std::mutex> lock(mutex_);
+            start = std::max(next_, std::chrono::steady_clock::now());
+            next_ = start +
+                    std::chrono::duration_cast<
+                        std::chrono::steady_clock::duration>(
+                        std::chrono::duration<double>(
+                            per_image_ +
+                            per_byte_ * static_cast<double>(bytes)));
+        }
+        std::this_thread::sleep_until(start);
+    }
+
+   private:
+    const double per_image_; /* seconds between rows */
+    const double per_byte_;  /* seconds per byte read */
+    std::mutex mutex_;
+    std::chrono::steady_clock::time_point next_;
+};
+
+/* bytes a row reads, what the byte limit is counted in */
+static uint64_t JobBytes(const BatchJob& job) {
+    std::error_code ec;
+    uint64_t bytes = 0;
+    for (const std::string& path : {job.cover, job.secret}) {
+        const uintmax_t kSize = std::filesystem::file_size(path, ec);
+        bytes += ec ? 0 : kSize;
+    }
+    return bytes;
+}
+
+/* runs a single row, filling in the stats of its result */
+static RetCode RunJob(const BatchConfig& config, BatchResult& result) {
+    const BatchJob& job = result.job;
+    try {
+        if (job.action == BatchAction::kMergeData) {
+            PackDataConfig pack;
+            pack.keyfile = job.keyfile;
+            pack.compress = job.compress;
+            pack.create_dirs = config.create_dirs;
+            pack.output_format = job.output_format;
+            std::error_code ec;
+            result.bytes = std::filesystem::file_size(job.secret, ec);
+            return PackData(job.cover, job.secret, job.output, pack);
+        }
+        MergeConfig merge;
+        merge.keyfile = job.keyfile;
+        merge.password = job.password;
+        merge.bits = job.bits;
+        merge.method = job.method;
+        merge.create_dirs = config.create_dirs;
+        merge.output_format = job.output_format;
+        /* the rows already keep the cores busy between them */
+        merge.threads = (WorkerCount(config.threads) > 1) ? 1 : 0;
+        MergeReport report;
+        RetCode rc = Merge(job.cover, job.secret, job.output, merge, report);
+        result.psnr = report.psnr;
+        result.capacity_used = report.capacity_used;
+        result.pixels_modified = report.pixels_modified;
+        for (const auto& warning : report.warnings) {
+            result.warnings.push_back(warning.code);
+        }
+        return rc;
+    } catch (const std::exception&) {
+        /* image codecs throw on truncated or corrupt files */
+        return RetCode::kIoError;
+    }
+}
+
+RetCode RunBatch(const BatchManifest& manifest, const BatchConfig& config,
+                 std::vector<BatchResult>& results, BatchSummary& summary) {
+    results.clear();
+    summary = BatchSummary{};
+    std::set<std::string> outputs;
+    for (const auto& job : manifest.jobs) {
+        if (!outputs.insert(job.output).second) {
+            return RetCode::kInvalidArgument;
+        }
+    }
+
+    const std::map<std::string, DoneRow> kDone =
+        config.state.empty() ? std::map<std::string, DoneRow>{}
+                             : LoadState(config.state);
+    std::ofstream state;
+    if (!config.state.empty()) {
+        state.open(config.state, std::ofstream::app);
+        if (!state.is_open()) {
+            return RetCode::kIoError;
+        }
+    }
+    std::mutex state_mutex;
+    Throttle throttle(config);
+
+    /* each worker takes the next row not yet started, so a slow image holds
+     * up only its own worker */
+    const std::size_t kJobs = manifest.jobs.size();
+    std::vector<BatchResult> slots(kJobs);
+    std::vector<char> ran(kJobs, 0);
+    std::atomic<std::size_t> next = 0;
+    std::atomic<bool> failed = false;
+    auto work = [&]() {
+        if (config.low_priority) {
+            LowerPriority(); /* best effort, the rows run either way */
+        }
+        for (std::size_t i = next++; i < kJobs; i = next++) {
+            if (config.fail_fast && failed) {
+                return;
+            }
+            BatchResult& result = slots[i];
+            result.job = manifest.jobs[i];
+            ran[i] = 1;
+            const std::string kRow =
+                config.state.empty() ? "" : RowDigest(result.job);
+            auto done = kDone.find(result.job.output);
+            if ((done != kDone.end()) && (done->second.row == kRow) &&
+                (FileDigest(result.job.output) == done->second.output)) {
+                result.resumed = true;
+                continue;
+            }
+            throttle.Wait(JobBytes(result.job));
+            const auto kStart = std::chrono::steady_clock::now();
+            result.rc = RunJob(config, result);
+            result.milliseconds =
+                std::chrono::duration<double, std::milli>(
+                    std::chrono::steady_clock::now() - kStart)
+                    .count();
+            if (result.rc != RetCode::kSuccess) {
+                failed = true;
+            } else if (!config.state.empty()) {
+                const std::string kOutput = FileDigest(result.job.output);
+                std::lock_guard<std::mutex> lock(state_mutex);
+                /* flushed row by row, so an interrupted run loses at most
+                 * the rows still running */
+                state << kRow << '\t' << kOutput << '\t' << result.job.output
+                      << std::endl;
+            }
+        }
+    };
+    const std::size_t kWorkers = std::min<std::size_t>(
+        WorkerCount(config.threads), std::max<std::size_t>(kJobs, 1));
+    {
+        std::vector<std::jthread> workers;
+        for (std::size_t i = 1; i < kWorkers; ++i) {
+            workers.emplace_back(work);
+        }
+        work();
+    }
+
+    for (std::size_t i = 0; i < kJobs; ++i) {
+        if (!ran[i]) {
+            ++summary.skipped;
+            continue;
+        }
+        if (slots[i].rc == RetCode::kSuccess) {
+            ++summary.succeeded;
+            summary.resumed += slots[i].resumed;
+        } else {
+            ++summary.failed;
+            summary.errors.push_back(
+                {slots[i].job.line, slots[i].job.cover, slots[i].rc});
+        }
+        results.push_back(std::move(slots[i]));
+    }
+    if ((summary.failed == 0) && (summary.skipped == 0)) {
+        summary.outcome = BatchOutcome::kSuccess;
+    } else if (summary.succeeded > 0) {
+        summary.outcome = BatchOutcome::kPartial;
+    } else {
+        summary.outcome = BatchOutcome::kFailure;
+    }
+    return RetCode::kSuccess;
+}
+
+RetCode WriteBatchResults(const std::string& path, char separator,
+                          const std::vector<BatchResult>& results) {
+    std::ofstream ofs(path);
+    if (!ofs.is_open()) {
+        return RetCode::kIoError;
+    }
+    const char kSep = separator;
+    ofs << "line" << kSep << "cover" << kSep << "secret" << kSep << "output"
+        << kSep << "action" << kSep << "status" << kSep << "psnr" << kSep
+        << "capacity_used" << kSep << "pixels_modified" << kSep << "bytes"
+        << kSep << "milliseconds" << kSep << "warnings" << '\n';
+    ofs << std::fixed << std::setprecision(2);
+    for (const auto& result : results) {
+        const bool kMerged = (result.job.action == BatchAction::kMerge);
+        std::string warnings;
+        for (const auto& code : result.warnings) {
+            warnings += (warnings.empty() ? "" : ";") + code;
+        }
+        ofs << result.job.line << kSep
+            << QuoteField(result.job.cover, kSep) << kSep
+            << QuoteField(result.job.secret, kSep) << kSep
+            << QuoteField(result.job.output, kSep) << kSep
+            << (kMerged ? "merge" : "merge-data") << kSep
+            << BatchStatus(result) << kSep;
+        if (result.resumed) {
+            ofs << kSep << kSep << kSep << kSep;
+        } else if (kMerged && (result.rc == RetCode::kSuccess)) {
+            ofs << result.psnr << kSep << result.capacity_used << kSep
+                << result.pixels_modified << kSep << kSep;
+        } else {
+            ofs << kSep << kSep << kSep << result.bytes << kSep;
+        }
+        ofs << result.milliseconds << kSep << QuoteField(warnings, kSep)
+            << '\n';
+    }
+    return ofs.good() ? RetCode::kSuccess : RetCode::kIoError;
+}
+
+const char* BatchStatus(const BatchResult& result) {
+    if (result.resumed) {
+        return "resumed";
+    }
+    switch (result.rc) {
+        case RetCode::kSuccess:
+            return "ok";
+        case RetCode::kInvalidFileFormat:
+            return "invalid-file-format";
+        case RetCode::kFileNotFound:
+            return "file-not-found";
+        case RetCode::kInvalidDimensions:
+            return "invalid-dimensions";
+        case RetCode::kInsufficientCapacity:
+            return "insufficient-capacity";
+        case RetCode::kPayloadNotFound:
+            return "payload-not-found";
+        case RetCode::kInvalidPayload:
+            return "invalid-payload";
+        case RetCode::kIoError:
+            return "io-error";
+        case RetCode::kUnsupportedFormat:
+            return "unsupported-format";
+        case RetCode::kFileExists:
+            return "file-exists";
+        case RetCode::kInvalidKey:
+            return "invalid-key";
+        case RetCode::kInvalidArgument:
+            return "invalid-argument";
+        case RetCode::kKeyMismatch:
+            return "key-mismatch";
+        case RetCode::kPasswordMismatch:
+            return "password-mismatch";
+        case RetCode::kAuthenticationFailed:
+            return "authentication-failed";
+        case RetCode::kNetworkError:
+            return "network-error";
+        case RetCode::kClipboardUnavailable:
+            return "clipboard-unavailable";
+        case RetCode::kPayloadDamaged:
+            return "payload-damaged";
+        case RetCode::kOutOfBandChanges:
+            return "out-of-band-changes";
+        case RetCode::kSameInput:
+            return "same-input";
+        case RetCode::kOutputIsInput:
+            return "output-is-input";
+        case RetCode::kImageTooLarge:
+            return "image-too-large";
+        case RetCode::kSealBroken:
+            return "seal-broken";
+        case RetCode::kConversionRefused:
+            return "conversion-refused";
+        case RetCode::kIncompleteParts:
+            return "incomplete-parts";
+    }
+    return "error";
+}
+
+}  // namespace steganography
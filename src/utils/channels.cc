@@ -0,0 +1,177 @@
+#include "utils/channels.hpp"
+
+#include <array>
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <filesystem>
+#include <string>
+
+#include "core/lsb.hpp"
+#include "utils/image_io.hpp"
+#include "utils/lsb.hpp"
+#include "utils/paths.hpp"
+
+namespace steganography {
+
+/* the kept high bits of the secret's channels concatenated red first */
+static int PackChannels(const boost::gil::rgb8_pixel_t& secret_pix,
+                        const std::array<int, 3>& bits) {
+    int hidden = 0;
+    for (std::size_t i = 0; i < 3; ++i) {
+        hidden = (hidden << bits[i]) | (secret_pix[i] >> (8 - bits[i]));
+    }
+    return hidden;
+}
+
+static boost::gil::rgb8_pixel_t UnpackChannels(
+    int hidden, const std::array<int, 3>& bits) {
+    boost::gil::rgb8_pixel_t unmerged_pix(0, 0, 0);
+    for (std::size_t i = 3; i-- > 0;) {
+        const int kChannelMask = (1 << bits[i]) - 1;
+        unmerged_pix[i] =
+            static_cast<uint8_t>((hidden & kChannelMask) << (8 - bits[i]));
+        hidden >>= bits[i];
+    }
+    return unmerged_pix;
+}
+
+RetCode MergeChannels(const std::string& cover,
+                      const std::array<std::string, 3>& secrets,
+                      const std::string& outfile,
+                      const ChannelConfig& config) {
+    if ((config.bits < kMinBits) || (config.bits > kMaxBits) ||
+        (secrets[0].empty() && secrets[1].empty() && secrets[2].empty())) {
+        return RetCode::kInvalidArgument;
+    }
+    if (!std::filesystem::exists(cover)) {
+        return RetCode::kFileNotFound;
+    }
+    for (const std::string& secret : secrets) {
+        if (secret.empty()) {
+            continue;
+        }
+        if (!std::filesystem::exists(secret)) {
+            return RetCode::kFileNotFound;
+        }
+        if (SamePath(cover, secret) || SameContents(cover, secret)) {
+            return RetCode::kSameInput;
+        }
+        if (SamePath(outfile, secret)) {
+            return RetCode::kOutputIsInput;
+        }
+    }
+    if (SamePath(outfile, cover)) {
+        return RetCode::kOutputIsInput;
+    }
+
+    ImageType cover_img_t(GetImageType(cover));
+    if (cover_img_t == ImageType::kUnknown) {
+        return RetCode::kInvalidFileFormat;
+    }
+    ImageType output_img_t = ImageType::kPng;
+    RetCode rc = ResolveOutputType(OutputFormat::kAuto, outfile, cover_img_t,
+                                   true, output_img_t);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    boost::gil::rgb8_image_t merged;
+    rc = LoadImage(cover, merged);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+
+    /* each secret goes in the low bits of its channel alone, so the others
+     * are free to hold secrets of their own */
+    auto dst = boost::gil::view(merged);
+    const std::array<int, 3> kSecretBits =
+        SecretBits(config.bits, ColorSpace::kHsv);
+    const int kLowBits = 0xFF >> (8 - config.bits);
+    for (std::size_t channel = 0; channel < secrets.size(); ++channel) {
+        if (secrets[channel].empty()) {
+            continue;
+        }
+        boost::gil::rgb8_image_t secret_img;
+        rc = LoadImage(secrets[channel], secret_img);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+        if ((secret_img.width() > merged.width()) ||
+            (secret_img.height() > merged.height())) {
+            return RetCode::kInvalidDimensions;
+        }
+        auto src = boost::gil::const_view(secret_img);
+        for (std::ptrdiff_t y = 0; y < dst.height(); ++y) {
+            for (std::ptrdiff_t x = 0; x < dst.width(); ++x) {
+                const bool kInside = (x < src.width()) && (y < src.height());
+                const int kHidden =
+                    kInside ? PackChannels(src(x, y), kSecretBits) : 0;
+                uint8_t& value = dst(x, y)[channel];
+                value = static_cast<uint8_t>((value & ~kLowBits) | kHidden);
+            }
+        }
+    }
+
+    if (config.create_dirs) {
+        rc = CreateParentDirectories(outfile);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+    WriteImage(merged, outfile, output_img_t);
+    return RetCode::kSuccess;
+}
+
+RetCode UnmergeChannel(const std::string& image, Channel channel,
+                       const std::string& outfile,
+                       const ChannelConfig& config) {
+    if ((config.bits < kMinBits) || (config.bits > kMaxBits)) {
+        return RetCode::kInvalidArgument;
+    }
+    if (!std::filesystem::exists(image)) {
+        return RetCode::kFileNotFound;
+    }
+    if (SamePath(outfile, image)) {
+        return RetCode::kOutputIsInput;
+    }
+    ImageType image_t(GetImageType(image));
+    if (image_t == ImageType::kUnknown) {
+        return RetCode::kInvalidFileFormat;
+    }
+    ImageType output_img_t = ImageType::kPng;
+    RetCode rc = ResolveOutputType(OutputFormat::kAuto, outfile, image_t,
+                                   false, output_img_t);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    boost::gil::rgb8_image_t merged;
+    rc = LoadImage(image, merged);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+
+    auto src = boost::gil::const_view(merged);
+    boost::gil::rgb8_image_t unmerged(merged.width(), merged.height());
+    auto dst = boost::gil::view(unmerged);
+    const std::array<int, 3> kSecretBits =
+        SecretBits(config.bits, ColorSpace::kHsv);
+    const int kLowBits = 0xFF >> (8 - config.bits);
+    const std::size_t kChannel = static_cast<std::size_t>(channel);
+    for (std::ptrdiff_t y = 0; y < src.height(); ++y) {
+        for (std::ptrdiff_t x = 0; x < src.width(); ++x) {
+            dst(x, y) =
+                UnpackChannels(src(x, y)[kChannel] & kLowBits, kSecretBits);
+        }
+    }
+
+    if (config.create_dirs) {
+        rc = CreateParentDirectories(outfile);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+    WriteImage(unmerged, outfile, output_img_t);
+    return RetCode::kSuccess;
+}
+
+}  // namespace steganography
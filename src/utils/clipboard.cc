@@ -0,0 +1,107 @@
+#include "utils/clipboard.hpp"
+
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include <array>
+#include <cstdio>
+#include <cstdlib>
+#include <filesystem>
+#include <sstream>
+#include <string>
+#include <vector>
+
+namespace steganography {
+
+struct ClipboardTool {
+    std::string program; /* looked up on PATH */
+    std::string paste;   /* command printing the clipboard */
+    std::string copy;    /* command storing its stdin in the clipboard */
+};
+
+static bool HasEnv(const char* name) {
+    const char* value = std::getenv(name);
+    return (value != nullptr) && (*value != '\0');
+}
+
+static bool OnPath(const std::string& program) {
+    const char* path = std::getenv("PATH");
+    if (path == nullptr) {
+        return false;
+    }
+    std::stringstream dirs(path);
+    std::string dir;
+    while (std::getline(dirs, dir, ':')) {
+        std::filesystem::path candidate = std::filesystem::path(dir) / program;
+        if (!dir.empty() && (access(candidate.c_str(), X_OK) == 0)) {
+            return true;
+        }
+    }
+    return false;
+}
+
+/* picks the first tool matching the running display server */
+static bool FindTool(ClipboardTool& tool) {
+    std::vector<ClipboardTool> tools;
+    if (HasEnv("WAYLAND_DISPLAY")) {
+        tools.push_back({"wl-paste", "wl-paste --no-newline", "wl-copy"});
+    }
+    if (HasEnv("DISPLAY")) {
+        tools.push_back({"xclip", "xclip -selection clipboard -out",
+                         "xclip -selection clipboard -in"});
+        tools.push_back({"xsel", "xsel --clipboard --output",
+                         "xsel --clipboard --input"});
+    }
+#ifdef __APPLE__
+    tools.push_back({"pbpaste", "pbpaste", "pbcopy"});
+#endif
+    for (const auto& candidate : tools) {
+        if (OnPath(candidate.program)) {
+            tool = candidate;
+            return true;
+        }
+    }
+    return false;
+}
+
+RetCode ReadClipboard(std::string& text) {
+    ClipboardTool tool;
+    if (!FindTool(tool)) {
+        return RetCode::kClipboardUnavailable;
+    }
+    FILE* pipe = popen(tool.paste.c_str(), "r");
+    if (pipe == nullptr) {
+        return RetCode::kClipboardUnavailable;
+    }
+    text.clear();
+    std::array<char, 4096> buffer{};
+    std::size_t len = 0;
+    while ((len = std::fread(buffer.data(), 1, buffer.size(), pipe)) > 0) {
+        text.append(buffer.data(), len);
+    }
+    int status = pclose(pipe);
+    if (!WIFEXITED(status) || (WEXITSTATUS(status) != 0)) {
+        return RetCode::kClipboardUnavailable;
+    }
+    return RetCode::kSuccess;
+}
+
+RetCode WriteClipboard(const std::string& text) {
+    ClipboardTool tool;
+    if (!FindTool(tool)) {
+        return RetCode::kClipboardUnavailable;
+    }
+    FILE* pipe = popen(tool.copy.c_str(), "w");
+    if (pipe == nullptr) {
+        return RetCode::kClipboardUnavailable;
+    }
+    std::size_t written = std::fwrite(text.data(), 1, text.size(), pipe);
+    int status = pclose(pipe);
+    if ((written != text.size()) || !WIFEXITED(status) ||
+        (WEXITSTATUS(status) != 0)) {
+        return RetCode::kClipboardUnavailable;
+    }
+    return RetCode::kSuccess;
+}
+
+}  // namespace steganography
@@ -0,0 +1,104 @@
+#include "utils/compare.hpp"
+
+#include <algorithm>
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <cstdlib>
+#include <string>
+
+#include "utils/image_io.hpp"
+#include "utils/paths.hpp"
+
+namespace steganography {
+
+static const int kChannelCount = 3;
+
+/* the factor stretching the largest difference between the images to 255,
+ * 1 for identical images */
+static int AutoAmplify(const boost::gil::rgb8c_view_t& cover,
+                       const boost::gil::rgb8c_view_t& stego) {
+    int largest = 0;
+    for (std::ptrdiff_t y = 0; y < cover.height(); ++y) {
+        for (std::ptrdiff_t x = 0; x < cover.width(); ++x) {
+            for (int c = 0; c < kChannelCount; ++c) {
+                largest = std::max(largest,
+                                   std::abs(cover(x, y)[c] - stego(x, y)[c]));
+            }
+        }
+    }
+    return (largest == 0) ? 1 : 255 / largest;
+}
+
+RetCode RenderComparison(const boost::gil::rgb8_image_t& cover,
+                         const boost::gil::rgb8_image_t& stego,
+                         const CompareConfig& config,
+                         boost::gil::rgb8_image_t& rendered) {
+    if ((config.amplify < 0) || (config.gap < 0)) {
+        return RetCode::kInvalidArgument;
+    }
+    if ((cover.width() != stego.width()) ||
+        (cover.height() != stego.height())) {
+        return RetCode::kInvalidDimensions;
+    }
+
+    auto src_cover = boost::gil::const_view(cover);
+    auto src_stego = boost::gil::const_view(stego);
+    const int kAmplify = (config.amplify > 0)
+                             ? config.amplify
+                             : AutoAmplify(src_cover, src_stego);
+    const std::ptrdiff_t kWidth = cover.width();
+    const std::ptrdiff_t kHeight = cover.height();
+    rendered = boost::gil::rgb8_image_t(3 * kWidth + 2 * config.gap, kHeight);
+    auto dst = boost::gil::view(rendered);
+    boost::gil::fill_pixels(dst, boost::gil::rgb8_pixel_t(255, 255, 255));
+
+    const std::ptrdiff_t kStegoLeft = kWidth + config.gap;
+    const std::ptrdiff_t kDiffLeft = 2 * (kWidth + config.gap);
+    for (std::ptrdiff_t y = 0; y < kHeight; ++y) {
+        for (std::ptrdiff_t x = 0; x < kWidth; ++x) {
+            dst(x, y) = src_cover(x, y);
+            dst(kStegoLeft + x, y) = src_stego(x, y);
+            boost::gil::rgb8_pixel_t diff(0, 0, 0);
+            for (int c = 0; c < kChannelCount; ++c) {
+                const int kDiff =
+                    std::abs(src_cover(x, y)[c] - src_stego(x, y)[c]);
+                diff[c] = static_cast<uint8_t>(std::min(255, kDiff * kAmplify));
+            }
+            dst(kDiffLeft + x, y) = diff;
+        }
+    }
+    return RetCode::kSuccess;
+}
+
+RetCode Compare(const std::string& cover, const std::string& stego,
+                const std::string& outfile, const CompareConfig& config) {
+    boost::gil::rgb8_image_t cover_img;
+    RetCode rc = LoadImage(cover, cover_img);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    boost::gil::rgb8_image_t stego_img;
+    rc = LoadImage(stego, stego_img);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    boost::gil::rgb8_image_t rendered;
+    rc = RenderComparison(cover_img, stego_img, config, rendered);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    if (config.create_dirs) {
+        rc = CreateParentDirectories(outfile);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+
+    /* write the image out using the user's preferred format (default PNG) */
+    WriteImage(rendered, outfile,
+               HasJpegExtension(outfile) ? ImageType::kJpeg : ImageType::kPng);
+    return RetCode::kSuccess;
+}
+
+}  // namespace steganography
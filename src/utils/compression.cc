@@ -0,0 +1,57 @@
+#include "utils/compression.hpp"
+
+#include <zlib.h>
+
+#include <cstdint>
+#include <vector>
+
+namespace steganography {
+
+/* zlib window bits offset that selects the gzip wrapper */
+static const int kGzipWindowBits = 15 + 16;
+static const std::size_t kChunkSize = 64 * 1024;
+
+std::vector<uint8_t> Compress(const std::vector<uint8_t>& data) {
+    z_stream stream{};
+    deflateInit2(&stream, Z_BEST_COMPRESSION, Z_DEFLATED, kGzipWindowBits, 8,
+                 Z_DEFAULT_STRATEGY);
+
+    std::vector<uint8_t> out(deflateBound(&stream, data.size()));
+    stream.next_in = const_cast<Bytef*>(data.data());
+    stream.avail_in = static_cast<uInt>(data.size());
+    stream.next_out = out.data();
+    stream.avail_out = static_cast<uInt>(out.size());
+    deflate(&stream, Z_FINISH);
+    out.resize(stream.total_out);
+    deflateEnd(&stream);
+    return out;
+}
+
+bool Decompress(const std::vector<uint8_t>& data, std::vector<uint8_t>& out) {
+    z_stream stream{};
+    if (inflateInit2(&stream, kGzipWindowBits) != Z_OK) {
+        return false;
+    }
+    stream.next_in = const_cast<Bytef*>(data.data());
+    stream.avail_in = static_cast<uInt>(data.size());
+
+    out.clear();
+    int ret = Z_OK;
+    while (ret == Z_OK) {
+        out.resize(out.size() + kChunkSize);
+        stream.next_out = out.data() + stream.total_out;
+        stream.avail_out = static_cast<uInt>(kChunkSize);
+        ret = inflate(&stream, Z_NO_FLUSH);
+    }
+    out.resize(stream.total_out);
+    inflateEnd(&stream);
+    return ret == Z_STREAM_END;
+}
+
+bool IsCompressed(const std::vector<uint8_t>& data) {
+    const uint8_t kGzipMagic[] = {0x1F, 0x8B};
+    return (data.size() >= 2) && (data[0] == kGzipMagic[0]) &&
+           (data[1] == kGzipMagic[1]);
+}
+
+}  // namespace steganography
@@ -0,0 +1,196 @@
+#include "utils/cover_gen.hpp"
+
+#include <algorithm>
+#include <boost/gil.hpp>
+#include <cmath>
+#include <cstdint>
+#include <numbers>
+#include <random>
+#include <string>
+#include <vector>
+
+#include "utils/image_io.hpp"
+#include "utils/paths.hpp"
+
+namespace steganography {
+
+/* every style adds fine grain so the low bits carry real entropy */
+static const double kGrain = 3.0;
+
+static uint8_t ToChannel(double value) {
+    return static_cast<uint8_t>(std::clamp(std::lround(value), 0L, 255L));
+}
+
+/* smooth random field in [0, 1) built from octaves of value noise */
+class ValueNoise {
+   public:
+    ValueNoise(std::ptrdiff_t width, std::ptrdiff_t height,
+               std::mt19937_64& rng) {
+        const std::ptrdiff_t kLargestCell = 256;
+        std::uniform_real_distribution<double> dist(0.0, 1.0);
+        for (std::ptrdiff_t cell = kLargestCell; cell >= 4; cell /= 2) {
+            Octave octave;
+            octave.cell = cell;
+            octave.cols = width / cell + 2;
+            std::ptrdiff_t rows = height / cell + 2;
+            octave.lattice.resize(static_cast<std::size_t>(octave.cols * rows));
+            for (double& value : octave.lattice) {
+                value = dist(rng);
+            }
+            octaves_.push_back(std::move(octave));
+        }
+    }
+
+    double At(std::ptrdiff_t x, std::ptrdiff_t y) const {
+        double value = 0.0;
+        double amplitude = 0.5;
+        double total = 0.0;
+        for (const Octave& octave : octaves_) {
+            double fx = static_cast<double>(x) / static_cast<double>(octave.cell);
+            double fy = static_cast<double>(y) / static_cast<double>(octave.cell);
+            auto x0 = static_cast<std::ptrdiff_t>(fx);
+            auto y0 = static_cast<std::ptrdiff_t>(fy);
+            double tx = Smooth(fx - static_cast<double>(x0));
+            double ty = Smooth(fy - static_cast<double>(y0));
+            auto at = [&octave](std::ptrdiff_t col, std::ptrdiff_t row) {
+                return octave.lattice[static_cast<std::size_t>(
+                    row * octave.cols + col)];
+            };
+            double top = at(x0, y0) * (1 - tx) + at(x0 + 1, y0) * tx;
+            double bottom = at(x0, y0 + 1) * (1 - tx) + at(x0 + 1, y0 + 1) * tx;
+            value += amplitude * (top * (1 - ty) + bottom * ty);
+            total += amplitude;
+            amplitude *= 0.6;
+        }
+        return value / total;
+    }
+
+   private:
+    struct Octave {
+        std::ptrdiff_t cell = 0;
+        std::ptrdiff_t cols = 0;
+        std::vector<double> lattice;
+    };
+
+    static double Smooth(double t) { return t * t * (3 - 2 * t); }
+
+    std::vector<Octave> octaves_;
+};
+
+static void RenderNoise(const boost::gil::rgb8_view_t& view,
+                        std::mt19937_64& rng) {
+    std::vector<ValueNoise> channels;
+    for (int i = 0; i < 3; ++i) {
+        channels.emplace_back(view.width(), view.height(), rng);
+    }
+    std::normal_distribution<double> grain(0.0, kGrain);
+    for (std::ptrdiff_t row = 0; row < view.height(); ++row) {
+        for (std::ptrdiff_t col = 0; col < view.width(); ++col) {
+            for (int i = 0; i < 3; ++i) {
+                view(col, row)[i] =
+                    ToChannel(channels[i].At(col, row) * 255.0 + grain(rng));
+            }
+        }
+    }
+}
+
+static void RenderGradient(const boost::gil::rgb8_view_t& view,
+                           std::mt19937_64& rng) {
+    std::uniform_real_distribution<double> dist(0.0, 1.0);
+    double angle = dist(rng) * 2.0 * std::numbers::pi;
+    double dx = std::cos(angle);
+    double dy = std::sin(angle);
+    double from[3];
+    double to[3];
+    for (int i = 0; i < 3; ++i) {
+        from[i] = dist(rng) * 255.0;
+        to[i] = dist(rng) * 255.0;
+    }
+
+    /* project each pixel onto the gradient direction */
+    double span = std::abs(dx) * static_cast<double>(view.width()) +
+                  std::abs(dy) * static_cast<double>(view.height());
+    double origin = std::min(0.0, dx * static_cast<double>(view.width())) +
+                    std::min(0.0, dy * static_cast<double>(view.height()));
+    std::normal_distribution<double> grain(0.0, kGrain);
+    for (std::ptrdiff_t row = 0; row < view.height(); ++row) {
+        for (std::ptrdiff_t col = 0; col < view.width(); ++col) {
+            double t = (dx * static_cast<double>(col) +
+                        dy * static_cast<double>(row) - origin) /
+                       span;
+            for (int i = 0; i < 3; ++i) {
+                view(col, row)[i] =
+                    ToChannel(from[i] + (to[i] - from[i]) * t + grain(rng));
+            }
+        }
+    }
+}
+
+static void RenderPlasma(const boost::gil::rgb8_view_t& view,
+                         std::mt19937_64& rng) {
+    /* sum of sinusoids with random frequencies and phases per channel */
+    const int kWaves = 4;
+    std::uniform_real_distribution<double> freq(0.002, 0.02);
+    std::uniform_real_distribution<double> phase(0.0, 2.0 * std::numbers::pi);
+    double freqs[3][kWaves];
+    double phases[3][kWaves];
+    for (int i = 0; i < 3; ++i) {
+        for (int w = 0; w < kWaves; ++w) {
+            freqs[i][w] = freq(rng);
+            phases[i][w] = phase(rng);
+        }
+    }
+
+    std::normal_distribution<double> grain(0.0, kGrain);
+    for (std::ptrdiff_t row = 0; row < view.height(); ++row) {
+        for (std::ptrdiff_t col = 0; col < view.width(); ++col) {
+            double x = static_cast<double>(col);
+            double y = static_cast<double>(row);
+            for (int i = 0; i < 3; ++i) {
+                double v = std::sin(x * freqs[i][0] + phases[i][0]) +
+                           std::sin(y * freqs[i][1] + phases[i][1]) +
+                           std::sin((x + y) * freqs[i][2] + phases[i][2]) +
+                           std::sin(std::hypot(x, y) * freqs[i][3] +
+                                    phases[i][3]);
+                view(col, row)[i] =
+                    ToChannel((v / kWaves + 1.0) * 127.5 + grain(rng));
+            }
+        }
+    }
+}
+
+RetCode GenerateCover(const std::string& outfile,
+                      const CoverGenConfig& config) {
+    if ((config.width <= 0) || (config.height <= 0)) {
+        return RetCode::kInvalidDimensions;
+    }
+
+    std::mt19937_64 rng(config.seed.has_value() ? *config.seed
+                                                : std::random_device{}());
+    boost::gil::rgb8_image_t cover(config.width, config.height);
+    auto view = boost::gil::view(cover);
+    switch (config.style) {
+        case CoverStyle::kNoise:
+            RenderNoise(view, rng);
+            break;
+        case CoverStyle::kGradient:
+            RenderGradient(view, rng);
+            break;
+        case CoverStyle::kPlasma:
+            RenderPlasma(view, rng);
+            break;
+    }
+    if (config.create_dirs) {
+        RetCode rc = CreateParentDirectories(outfile);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+
+    /* write the image out using the user's preferred format (default PNG) */
+    WriteImage(cover, outfile,
+               HasJpegExtension(outfile) ? ImageType::kJpeg : ImageType::kPng);
+    return RetCode::kSuccess;
+}
+
+}  // namespace steganography
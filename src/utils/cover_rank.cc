@@ -0,0 +1,113 @@
+#include "utils/cover_rank.hpp"
+
+#include <algorithm>
+#include <array>
+#include <boost/gil.hpp>
+#include <cmath>
+#include <cstddef>
+#include <cstdlib>
+#include <filesystem>
+#include <string>
+#include <vector>
+
+#include "utils/image_io.hpp"
+#include "utils/payload.hpp"
+
+namespace steganography {
+
+/* mean gradient at which a cover counts as fully textured */
+static const double kFullTexture = 24.0;
+
+static int Luminance(const boost::gil::rgb8_pixel_t& pixel) {
+    return (299 * pixel[0] + 587 * pixel[1] + 114 * pixel[2]) / 1000;
+}
+
+static double NormalizedEntropy(const boost::gil::rgb8c_view_t& view) {
+    std::array<std::size_t, 256> histogram{};
+    for (std::ptrdiff_t row = 0; row < view.height(); ++row) {
+        for (std::ptrdiff_t col = 0; col < view.width(); ++col) {
+            ++histogram[Luminance(view(col, row))];
+        }
+    }
+    const double kTotal = static_cast<double>(view.width() * view.height());
+    double entropy = 0.0;
+    for (std::size_t count : histogram) {
+        if (count > 0) {
+            double p = static_cast<double>(count) / kTotal;
+            entropy -= p * std::log2(p);
+        }
+    }
+    return entropy / 8.0;
+}
+
+static double NormalizedTexture(const boost::gil::rgb8c_view_t& view) {
+    /* flat regions are where overwritten low bits stand out the most */
+    double gradient = 0.0;
+    std::size_t samples = 0;
+    for (std::ptrdiff_t row = 0; row + 1 < view.height(); ++row) {
+        for (std::ptrdiff_t col = 0; col + 1 < view.width(); ++col) {
+            int center = Luminance(view(col, row));
+            gradient += std::abs(center - Luminance(view(col + 1, row))) +
+                        std::abs(center - Luminance(view(col, row + 1)));
+            samples += 2;
+        }
+    }
+    if (samples == 0) {
+        return 0.0;
+    }
+    return std::min(1.0, gradient / static_cast<double>(samples) / kFullTexture);
+}
+
+RetCode RankCovers(const std::string& dir, std::size_t payload_size,
+                   std::vector<CoverScore>& scores) {
+    if (!std::filesystem::is_directory(dir)) {
+        return RetCode::kFileNotFound;
+    }
+
+    std::vector<std::filesystem::path> candidates;
+    for (const auto& entry : std::filesystem::directory_iterator(dir)) {
+        if (entry.is_regular_file() &&
+            (GetImageType(entry.path().string()) != ImageType::kUnknown)) {
+            candidates.push_back(entry.path());
+        }
+    }
+    std::sort(candidates.begin(), candidates.end());
+
+    scores.clear();
+    for (const auto& candidate : candidates) {
+        boost::gil::rgb8_image_t image;
+        if (LoadImage(candidate.string(), image) != RetCode::kSuccess) {
+            continue;
+        }
+        auto view = boost::gil::const_view(image);
+
+        CoverScore score;
+        score.path = candidate.string();
+        score.capacity = PayloadCapacity(image);
+        score.utilization =
+            (score.capacity == 0)
+                ? 1.0
+                : static_cast<double>(payload_size) /
+                      static_cast<double>(score.capacity);
+        score.entropy = NormalizedEntropy(view);
+        score.texture = NormalizedTexture(view);
+
+        /* filling more of a smoother, simpler cover is easier to detect */
+        score.detectability =
+            std::min(1.0, score.utilization) *
+            (1.0 - 0.5 * (score.texture + score.entropy));
+        if (score.utilization <= 1.0) {
+            score.score = (1.0 - score.detectability) *
+                          (0.5 + 0.25 * (score.texture + score.entropy));
+        }
+        scores.push_back(score);
+    }
+
+    std::stable_sort(scores.begin(), scores.end(),
+                     [](const CoverScore& lhs, const CoverScore& rhs) {
+                         return lhs.score > rhs.score;
+                     });
+    return RetCode::kSuccess;
+}
+
+}  // namespace steganography
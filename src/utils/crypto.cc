@@ -0,0 +1,98 @@
+#include "utils/crypto.hpp"
+
+#include <openssl/evp.h>
+#include <openssl/rand.h>
+
+#include <array>
+#include <cstdint>
+#include <string>
+#include <vector>
+
+namespace steganography {
+
+static const int kPbkdf2Iterations = 200000;
+
+std::vector<uint8_t> RandomBytes(std::size_t count) {
+    std::vector<uint8_t> bytes(count, 0);
+    if ((count > 0) &&
+        (RAND_bytes(bytes.data(), static_cast<int>(bytes.size())) != 1)) {
+        return {};
+    }
+    return bytes;
+}
+
+StegKey DerivePasswordKey(const std::string& password,
+                          const std::vector<uint8_t>& salt) {
+    StegKey key;
+    PKCS5_PBKDF2_HMAC(password.data(), static_cast<int>(password.size()),
+                      salt.data(), static_cast<int>(salt.size()),
+                      kPbkdf2Iterations, EVP_sha256(),
+                      static_cast<int>(key.material.size()),
+                      key.material.data());
+    return key;
+}
+
+void SpendPasswordKey(const std::string& password) {
+    DerivePasswordKey(password, std::vector<uint8_t>(kSaltSize, 0));
+}
+
+std::vector<uint8_t> Seal(const StegKey& key,
+                          const std::vector<uint8_t>& plaintext) {
+    std::vector<uint8_t> nonce = RandomBytes(kNonceSize);
+    if (nonce.size() != kNonceSize) {
+        return {};
+    }
+    return Seal(key, plaintext, nonce);
+}
+
+std::vector<uint8_t> Seal(const StegKey& key,
+                          const std::vector<uint8_t>& plaintext,
+                          const std::vector<uint8_t>& nonce) {
+    std::array<uint8_t, kKeySize> aead_key = DeriveSubkey(key, "aead");
+    std::vector<uint8_t> sealed = nonce;
+    sealed.resize(kSealOverhead + plaintext.size());
+
+    int len = 0;
+    EVP_CIPHER_CTX* ctx = EVP_CIPHER_CTX_new();
+    EVP_EncryptInit_ex(ctx, EVP_aes_256_gcm(), nullptr, aead_key.data(),
+                       sealed.data());
+    EVP_EncryptUpdate(ctx, sealed.data() + kNonceSize, &len, plaintext.data(),
+                      static_cast<int>(plaintext.size()));
+    EVP_EncryptFinal_ex(ctx, sealed.data() + kNonceSize + len, &len);
+    EVP_CIPHER_CTX_ctrl(ctx, EVP_CTRL_GCM_GET_TAG,
+                        static_cast<int>(kAeadTagSize),
+                        sealed.data() + kNonceSize + plaintext.size());
+    EVP_CIPHER_CTX_free(ctx);
+    Wipe(aead_key);
+    return sealed;
+}
+
+bool Open(const StegKey& key, const std::vector<uint8_t>& sealed,
+          std::vector<uint8_t>& plaintext) {
+    if (sealed.size() < kSealOverhead) {
+        return false;
+    }
+    std::array<uint8_t, kKeySize> aead_key = DeriveSubkey(key, "aead");
+    const std::size_t kCipherSize = sealed.size() - kSealOverhead;
+    std::vector<uint8_t> tag(sealed.cend() - kAeadTagSize, sealed.cend());
+    plaintext.assign(kCipherSize, 0);
+
+    int len = 0;
+    EVP_CIPHER_CTX* ctx = EVP_CIPHER_CTX_new();
+    EVP_DecryptInit_ex(ctx, EVP_aes_256_gcm(), nullptr, aead_key.data(),
+                       sealed.data());
+    EVP_DecryptUpdate(ctx, plaintext.data(), &len, sealed.data() + kNonceSize,
+                      static_cast<int>(kCipherSize));
+    EVP_CIPHER_CTX_ctrl(ctx, EVP_CTRL_GCM_SET_TAG,
+                        static_cast<int>(kAeadTagSize), tag.data());
+    bool ok = EVP_DecryptFinal_ex(ctx, plaintext.data() + len, &len) == 1;
+    EVP_CIPHER_CTX_free(ctx);
+    Wipe(aead_key);
+    if (!ok) {
+        /* unauthenticated plaintext must not linger in the caller's buffer */
+        Wipe(plaintext);
+    }
+    return ok;
+}
+
+}  // namespace steganography
@@ -0,0 +1,295 @@
+#include "utils/deniable.hpp"
+
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <optional>
+#include <string>
+#include <utility>
+#include <vector>
+
+#include "utils/bytes.hpp"
+#include "utils/carrier.hpp"
+#include "utils/crypto.hpp"
+#include "utils/key.hpp"
+#include "utils/payload.hpp"
+
+namespace steganography {
+
+static const std::size_t kHalfCount = 2;
+
+/* a sealed length record precedes the sealed payload */
+static const std::size_t kLengthRecordSize = kSealOverhead + 4;
+
+/* cells of one half or plane: a salt in natural order then the keyed body
+ * cells */
+struct HalfLayout {
+    std::vector<std::size_t> salt_cells;
+    std::vector<std::size_t> body_cells;
+};
+
+/* every step-th cell from first on */
+static HalfLayout SplitCells(std::size_t cell_count, std::size_t first,
+                             std::size_t step) {
+    HalfLayout layout;
+    for (std::size_t cell = first; cell < cell_count; cell += step) {
+        if (layout.salt_cells.size() < kSaltSize) {
+            layout.salt_cells.push_back(cell);
+        } else {
+            layout.body_cells.push_back(cell);
+        }
+    }
+    return layout;
+}
+
+/* one bit plane of a view, rows follow each other at the distance between
+ * the first two */
+template <typename View>
+static BitPlaneCarrier PlaneCarrier(const View& view, std::size_t plane) {
+    const std::size_t kWidth = static_cast<std::size_t>(view.width());
+    const std::size_t kHeight = static_cast<std::size_t>(view.height());
+    const int kPlane = static_cast<int>(plane);
+    if ((kWidth == 0) || (kHeight == 0)) {
+        return BitPlaneCarrier(static_cast<uint8_t*>(nullptr), 0, 0, 0,
+                               kPlane);
+    }
+    std::size_t stride = kWidth * 3;
+    if (kHeight > 1) {
+        stride = static_cast<std::size_t>(&view(0, 1)[0] - &view(0, 0)[0]);
+    }
+    return BitPlaneCarrier(&view(0, 0)[0], kWidth, kHeight, stride, kPlane);
+}
+
+static void PermuteBody(HalfLayout& layout, const StegKey& key) {
+    KeyStream stream(key, "deniable placement");
+    std::vector<std::size_t> perm =
+        KeyedPermutation(layout.body_cells.size(), stream);
+    std::vector<std::size_t> permuted(perm.size());
+    for (std::size_t i = 0; i < perm.size(); ++i) {
+        permuted[i] = layout.body_cells[perm[i]];
+    }
+    layout.body_cells = std::move(permuted);
+}
+
+/* the system's randomness, or the seeded stream standing in for it, empty
+ * when the system's generator fails */
+static std::vector<uint8_t> DrawBytes(KeyStream* seeded, std::size_t count) {
+    if (seeded == nullptr) {
+        return RandomBytes(count);
+    }
+    std::vector<uint8_t> bytes(count);
+    for (uint8_t& byte : bytes) {
+        byte = seeded->NextByte();
+    }
+    return bytes;
+}
+
+static RetCode FillHalf(Carrier& carrier, HalfLayout layout,
+                        const DeniablePayload* payload, KeyStream* seeded) {
+    if (layout.salt_cells.size() < kSaltSize) {
+        return RetCode::kInsufficientCapacity;
+    }
+    std::vector<uint8_t> salt = DrawBytes(seeded, kSaltSize);
+    std::vector<uint8_t> body = DrawBytes(seeded, layout.body_cells.size());
+    if ((salt.size() != kSaltSize) ||
+        (body.size() != layout.body_cells.size())) {
+        return RetCode::kIoError;
+    }
+    for (std::size_t i = 0; i < kSaltSize; ++i) {
+        carrier.WriteCell(layout.salt_cells[i], salt[i]);
+    }
+
+    /* start from noise, then overwrite a prefix with the sealed records.
+     * Nothing is written until every random byte was drawn, a failed draw
+     * would otherwise leave zeros for a salt or a reused nonce behind */
+    if (payload != nullptr) {
+        if (body.size() < kLengthRecordSize + kSealOverhead ||
+            payload->data.size() >
+                body.size() - kLengthRecordSize - kSealOverhead) {
+            return RetCode::kInsufficientCapacity;
+        }
+        StegKey key = DerivePasswordKey(payload->password, salt);
+        PermuteBody(layout, key);
+
+        std::vector<uint8_t> length;
+        AppendInt<uint32_t>(length, static_cast<uint32_t>(payload->data.size()));
+        std::vector<uint8_t> length_nonce = DrawBytes(seeded, kNonceSize);
+        std::vector<uint8_t> data_nonce = DrawBytes(seeded, kNonceSize);
+        if ((length_nonce.size() != kNonceSize) ||
+            (data_nonce.size() != kNonceSize)) {
+            return RetCode::kIoError;
+        }
+        std::vector<uint8_t> sealed = Seal(key, length, length_nonce);
+        std::vector<uint8_t> sealed_data =
+            Seal(key, payload->data, data_nonce);
+        sealed.insert(sealed.end(), sealed_data.cbegin(), sealed_data.cend());
+        std::copy(sealed.cbegin(), sealed.cend(), body.begin());
+    }
+
+    for (std::size_t i = 0; i < body.size(); ++i) {
+        carrier.WriteCell(layout.body_cells[i], body[i]);
+    }
+    return RetCode::kSuccess;
+}
+
+static bool OpenHalf(const Carrier& carrier, HalfLayout layout,
+                     const std::string& password,
+                     std::vector<uint8_t>& payload) {
+    if (layout.body_cells.size() < kLengthRecordSize + kSealOverhead) {
+        SpendPasswordKey(password);
+        return false;
+    }
+    std::vector<uint8_t> salt;
+    for (std::size_t cell : layout.salt_cells) {
+        salt.push_back(carrier.ReadCell(cell));
+    }
+    StegKey key = DerivePasswordKey(password, salt);
+    PermuteBody(layout, key);
+
+    auto read_cells = [&carrier, &layout](std::size_t offset,
+                                          std::size_t count) {
+        std::vector<uint8_t> bytes;
+        for (std::size_t i = offset; i < offset + count; ++i) {
+            bytes.push_back(carrier.ReadCell(layout.body_cells[i]));
+        }
+        return bytes;
+    };
+
+    std::vector<uint8_t> length;
+    if (!Open(key, read_cells(0, kLengthRecordSize), length)) {
+        return false;
+    }
+    std::size_t size = ReadInt<uint32_t>(length, 0);
+    if (size > layout.body_cells.size() - kLengthRecordSize - kSealOverhead) {
+        return false;
+    }
+    return Open(key, read_cells(kLengthRecordSize, size + kSealOverhead),
+                payload);
+}
+
+/* The payloads and passwords are part of the key so that different
+ * archives packed with one seed never share a salt or a nonce, each
+ * prefixed with its length so no two sets of them read the same. Nothing is
+ * seeded without a seed. */
+static std::optional<KeyStream> SeededFiller(
+    const std::vector<DeniablePayload>& payloads,
+    std::optional<uint64_t> seed, const char* label) {
+    std::optional<KeyStream> seeded;
+    if (seed.has_value()) {
+        std::vector<uint8_t> context;
+        for (const DeniablePayload& payload : payloads) {
+            AppendInt<uint64_t>(context, payload.password.size());
+            context.insert(context.end(), payload.password.cbegin(),
+                           payload.password.cend());
+            AppendInt<uint64_t>(context, payload.data.size());
+            context.insert(context.end(), payload.data.cbegin(),
+                           payload.data.cend());
+        }
+        seeded.emplace(SeededKey(*seed, context), label);
+        Wipe(context);
+    }
+    return seeded;
+}
+
+RetCode EmbedDeniable(boost::gil::rgb8_image_t& image,
+                      const std::vector<DeniablePayload>& payloads,
+                      std::optional<uint64_t> seed) {
+    if (payloads.empty() || (payloads.size() > kHalfCount)) {
+        return RetCode::kInvalidArgument;
+    }
+    /* two payloads under one password could never be told apart */
+    if ((payloads.size() == kHalfCount) &&
+        (payloads[0].password == payloads[1].password)) {
+        return RetCode::kInvalidArgument;
+    }
+
+    std::optional<KeyStream> seeded =
+        SeededFiller(payloads, seed, "deniable filler");
+    ImageCarrier carrier(image);
+    const std::size_t kCells = carrier.CellCount();
+    for (std::size_t half = 0; half < kHalfCount; ++half) {
+        const DeniablePayload* payload =
+            (half < payloads.size()) ? &payloads[half] : nullptr;
+        RetCode rc = FillHalf(carrier, SplitCells(kCells, half, kHalfCount),
+                              payload, seeded ? &*seeded : nullptr);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+    return RetCode::kSuccess;
+}
+
+RetCode ExtractDeniable(const boost::gil::rgb8_image_t& image,
+                        const std::string& password,
+                        std::vector<uint8_t>& payload) {
+    /* try every half, the password only ever unlocks one of them. The
+     * others are tried even after it opens so the time taken tells nothing
+     * about which half that was, an empty half fails like a wrong password
+     * since its noise never opens */
+    const ImageCarrier carrier(image);
+    const std::size_t kCells = carrier.CellCount();
+    bool opened = false;
+    for (std::size_t half = 0; half < kHalfCount; ++half) {
+        std::vector<uint8_t> candidate;
+        if (OpenHalf(carrier, SplitCells(kCells, half, kHalfCount), password,
+                     candidate) &&
+            !opened) {
+            payload = std::move(candidate);
+            opened = true;
+        }
+        Wipe(candidate);
+    }
+    return opened ? RetCode::kSuccess : RetCode::kAuthenticationFailed;
+}
+
+RetCode EmbedLayered(boost::gil::rgb8_image_t& image,
+                     const std::vector<DeniablePayload>& payloads,
+                     std::optional<uint64_t> seed) {
+    if (payloads.empty() || (payloads.size() > kMaxLayers)) {
+        return RetCode::kInvalidArgument;
+    }
+    for (std::size_t i = 0; i < payloads.size(); ++i) {
+        for (std::size_t j = i + 1; j < payloads.size(); ++j) {
+            if (payloads[i].password == payloads[j].password) {
+                return RetCode::kInvalidArgument;
+            }
+        }
+    }
+
+    std::optional<KeyStream> seeded =
+        SeededFiller(payloads, seed, "layered filler");
+    auto view = boost::gil::view(image);
+    for (std::size_t plane = 0; plane < kMaxLayers; ++plane) {
+        BitPlaneCarrier carrier = PlaneCarrier(view, plane);
+        const DeniablePayload* payload =
+            (plane < payloads.size()) ? &payloads[plane] : nullptr;
+        RetCode rc = FillHalf(carrier, SplitCells(carrier.CellCount(), 0, 1),
+                              payload, seeded ? &*seeded : nullptr);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+    return RetCode::kSuccess;
+}
+
+RetCode ExtractLayered(const boost::gil::rgb8_image_t& image,
+                       const std::string& password,
+                       std::vector<uint8_t>& payload) {
+    /* every plane is tried for the same reason every half is */
+    auto view = boost::gil::const_view(image);
+    bool opened = false;
+    for (std::size_t plane = 0; plane < kMaxLayers; ++plane) {
+        const BitPlaneCarrier carrier = PlaneCarrier(view, plane);
+        std::vector<uint8_t> candidate;
+        if (OpenHalf(carrier, SplitCells(carrier.CellCount(), 0, 1), password,
+                     candidate) &&
+            !opened) {
+            payload = std::move(candidate);
+            opened = true;
+        }
+        Wipe(candidate);
+    }
+    return opened ? RetCode::kSuccess : RetCode::kAuthenticationFailed;
+}
+
+}  // namespace steganography
@@ -0,0 +1,332 @@
+#include "utils/detect.hpp"
+
+#include <algorithm>
+#include <array>
+#include <boost/gil.hpp>
+#include <cmath>
+#include <cstddef>
+#include <cstdlib>
+#include <iomanip>
+#include <sstream>
+#include <string>
+#include <vector>
+
+#include "utils/image_io.hpp"
+
+namespace steganography {
+
+static const int kChannelCount = 3;
+
+/* the chi-square test is run on this many consecutive windows */
+static const int kChiSquareWindows = 100;
+
+/* pairs of values expected less often than this are too noisy to test */
+static const double kMinExpected = 5.0;
+
+/* RS groups are runs of pixels along a row, flipped where the mask is set */
+static const std::array<int, 4> kRsMask = {0, 1, 1, 0};
+
+/* RS and SPA estimate up to about 0.2 on clean photos, confidence passes
+ * 0.5 at this estimate and changes over about kEstimateSpread either way */
+static const double kCleanEstimate = 0.2;
+static const double kEstimateSpread = 0.05;
+
+/* a bit plane filled with random data has neighbors agreeing half the
+ * time, natural images agree more often even in their LSBs */
+static const double kRandomAgreement = 0.01;
+
+/* one channel of the image as rows of samples */
+using Plane = std::vector<std::vector<int>>;
+
+static Plane GetPlane(const boost::gil::rgb8_image_t& image, int channel) {
+    auto view = boost::gil::const_view(image);
+    Plane plane(static_cast<std::size_t>(view.height()),
+                std::vector<int>(static_cast<std::size_t>(view.width())));
+    for (std::ptrdiff_t row = 0; row < view.height(); ++row) {
+        for (std::ptrdiff_t col = 0; col < view.width(); ++col) {
+            plane[static_cast<std::size_t>(row)][static_cast<std::size_t>(
+                col)] = view(col, row)[channel];
+        }
+    }
+    return plane;
+}
+
+/* p-value of the values in [begin, end) having had their pairs of values
+ * 2i and 2i + 1 evened out, as LSB replacement does */
+static double ChiSquarePValue(const std::vector<int>& samples,
+                              std::size_t begin, std::size_t end) {
+    std::array<double, 256> histogram = {};
+    for (std::size_t i = begin; i < end; ++i) {
+        histogram[static_cast<std::size_t>(samples[i])] += 1.0;
+    }
+    double chi_square = 0.0;
+    int pairs = 0;
+    for (std::size_t value = 0; value < histogram.size(); value += 2) {
+        double expected = (histogram[value] + histogram[value + 1]) / 2.0;
+        if (expected < kMinExpected) {
+            continue;
+        }
+        double diff = histogram[value] - expected;
+        chi_square += diff * diff / expected;
+        ++pairs;
+    }
+    if (pairs < 2) {
+        return 0.0;
+    }
+
+    /* Wilson-Hilferty approximation of the chi-square distribution */
+    double dof = pairs - 1;
+    double variance = 2.0 / (9.0 * dof);
+    double z = (std::cbrt(chi_square / dof) - (1.0 - variance)) /
+               std::sqrt(variance);
+    return 0.5 * std::erfc(z / std::sqrt(2.0));
+}
+
+/* every channel in the order pack fills them */
+static std::vector<int> GetSamples(const boost::gil::rgb8_image_t& image) {
+    std::vector<int> samples;
+    samples.reserve(static_cast<std::size_t>(image.width() * image.height()) *
+                    kChannelCount);
+    auto view = boost::gil::const_view(image);
+    for (std::ptrdiff_t row = 0; row < view.height(); ++row) {
+        for (std::ptrdiff_t col = 0; col < view.width(); ++col) {
+            for (int i = 0; i < kChannelCount; ++i) {
+                samples.push_back(view(col, row)[i]);
+            }
+        }
+    }
+    return samples;
+}
+
+static double EstimateConfidence(double estimate) {
+    return 1.0 / (1.0 + std::exp(-(estimate - kCleanEstimate) /
+                                 kEstimateSpread));
+}
+
+DetectorScore ChiSquareDetector(const boost::gil::rgb8_image_t& image) {
+    std::vector<int> samples = GetSamples(image);
+
+    /* the embedded region looks random up to where the payload ends, the
+     * first window tells how likely it is there is a payload at all */
+    DetectorScore score;
+    score.name = "chi-square";
+    score.confidence =
+        ChiSquarePValue(samples, 0, samples.size() / kChiSquareWindows);
+    int windows = 0;
+    while (windows < kChiSquareWindows) {
+        std::size_t begin = samples.size() * static_cast<std::size_t>(windows) /
+                            kChiSquareWindows;
+        std::size_t end = samples.size() *
+                          static_cast<std::size_t>(windows + 1) /
+                          kChiSquareWindows;
+        if (ChiSquarePValue(samples, begin, end) <= 0.5) {
+            break;
+        }
+        ++windows;
+    }
+    score.payload_fraction = static_cast<double>(windows) / kChiSquareWindows;
+    return score;
+}
+
+/* flips 2i <-> 2i + 1 when positive, else 2i - 1 <-> 2i */
+static int Flip(int value, bool negative) {
+    return negative ? ((value + 1) ^ 1) - 1 : value ^ 1;
+}
+
+/* fraction of regular minus singular groups, i.e. groups whose smoothness
+ * gets worse rather than better when the masked samples are flipped */
+static double GroupBalance(const Plane& plane, bool negative) {
+    std::size_t regular_count = 0;
+    std::size_t singular_count = 0;
+    std::size_t groups = 0;
+    for (const auto& row : plane) {
+        for (std::size_t col = 0; col + kRsMask.size() <= row.size();
+             col += kRsMask.size()) {
+            int before = 0;
+            int after = 0;
+            for (std::size_t i = 0; i + 1 < kRsMask.size(); ++i) {
+                int a = row[col + i];
+                int b = row[col + i + 1];
+                before += std::abs(b - a);
+                a = kRsMask[i] ? Flip(a, negative) : a;
+                b = kRsMask[i + 1] ? Flip(b, negative) : b;
+                after += std::abs(b - a);
+            }
+            regular_count += (after > before);
+            singular_count += (after < before);
+            ++groups;
+        }
+    }
+    if (groups == 0) {
+        return 0.0;
+    }
+    return (static_cast<double>(regular_count) -
+            static_cast<double>(singular_count)) /
+           static_cast<double>(groups);
+}
+
+/* Fridrich's RS analysis compares the groups against those of the plane
+ * with every LSB flipped to solve for the fraction of embedded samples */
+static double RsPlane(const Plane& plane) {
+    Plane flipped = plane;
+    for (auto& row : flipped) {
+        for (int& value : row) {
+            value ^= 1;
+        }
+    }
+    double d0 = GroupBalance(plane, false);
+    double d1 = GroupBalance(flipped, false);
+    double d0_neg = GroupBalance(plane, true);
+    double d1_neg = GroupBalance(flipped, true);
+    double a = 2.0 * (d1 + d0);
+    double b = d0_neg - d1_neg - d1 - 3.0 * d0;
+    double c = d0 - d0_neg;
+    double z = 0.0;
+    if (std::fabs(a) < 1e-12) {
+        z = (std::fabs(b) < 1e-12) ? 0.0 : -c / b;
+    } else {
+        double root = std::sqrt(std::max(0.0, b * b - 4.0 * a * c));
+        double z1 = (-b + root) / (2.0 * a);
+        double z2 = (-b - root) / (2.0 * a);
+        z = (std::fabs(z1) < std::fabs(z2)) ? z1 : z2;
+    }
+    return (std::fabs(z - 0.5) < 1e-12) ? 1.0 : z / (z - 0.5);
+}
+
+DetectorScore RsDetector(const boost::gil::rgb8_image_t& image) {
+    double sum = 0.0;
+    for (int i = 0; i < kChannelCount; ++i) {
+        sum += RsPlane(GetPlane(image, i));
+    }
+    DetectorScore score;
+    score.name = "rs";
+    score.payload_fraction = std::clamp(sum / kChannelCount, 0.0, 1.0);
+    score.confidence = EstimateConfidence(score.payload_fraction);
+    return score;
+}
+
+/* Dumitrescu, Wu, and Wang's sample pair analysis over horizontally
+ * adjacent samples (r, s), the smaller root is the fraction of flipped LSBs
+ * which is half the fraction of embedded samples */
+static double SpaPlane(const Plane& plane) {
+    double x = 0.0; /* s even and r < s, or s odd and r > s */
+    double y = 0.0; /* s even and r > s, or s odd and r < s */
+    double k = 0.0; /* r and s differ at most in their LSB */
+    double pairs = 0.0;
+    for (const auto& row : plane) {
+        for (std::size_t col = 0; col + 1 < row.size(); ++col) {
+            int r = row[col];
+            int s = row[col + 1];
+            bool even = (s % 2 == 0);
+            x += (even && (r < s)) || (!even && (r > s));
+            y += (even && (r > s)) || (!even && (r < s));
+            k += (r / 2 == s / 2);
+            pairs += 1.0;
+        }
+    }
+    if (k == 0.0) {
+        return 0.0;
+    }
+    double a = 2.0 * k;
+    double b = 2.0 * (2.0 * x - pairs);
+    double c = y - x;
+    double root = std::sqrt(std::max(0.0, b * b - 4.0 * a * c));
+    double beta = std::min((-b + root) / (2.0 * a), (-b - root) / (2.0 * a));
+    return 2.0 * beta;
+}
+
+DetectorScore SpaDetector(const boost::gil::rgb8_image_t& image) {
+    double sum = 0.0;
+    for (int i = 0; i < kChannelCount; ++i) {
+        sum += SpaPlane(GetPlane(image, i));
+    }
+    DetectorScore score;
+    score.name = "spa";
+    score.payload_fraction = std::clamp(sum / kChannelCount, 0.0, 1.0);
+    score.confidence = EstimateConfidence(score.payload_fraction);
+    return score;
+}
+
+static double Median(std::array<double, 3> values) {
+    std::sort(values.begin(), values.end());
+    return values[1];
+}
+
+/* low bit planes that look random over the first fraction of the samples,
+ * pack replaces whole low nibbles while LSB tools replace a single bit */
+static int EstimateBitDepth(const std::vector<int>& samples, double fraction) {
+    std::size_t end = static_cast<std::size_t>(
+        fraction * static_cast<double>(samples.size()));
+    int depth = 0;
+    for (; depth < 8; ++depth) {
+        std::size_t agree = 0;
+        std::size_t pairs = 0;
+        /* horizontal neighbors are a pixel, i.e. kChannelCount samples, apart */
+        for (std::size_t i = 0; i + kChannelCount < end; ++i) {
+            agree += ((samples[i] >> depth) & 1) ==
+                     ((samples[i + kChannelCount] >> depth) & 1);
+            ++pairs;
+        }
+        if ((pairs == 0) ||
+            (std::fabs(static_cast<double>(agree) / static_cast<double>(pairs) -
+                       0.5) >= kRandomAgreement)) {
+            break;
+        }
+    }
+    return depth;
+}
+
+DetectionReport Detect(const boost::gil::rgb8_image_t& image) {
+    DetectionReport report;
+    report.detectors = {ChiSquareDetector(image), RsDetector(image),
+                        SpaDetector(image)};
+
+    /* any one detector is easily fooled by the content of an image, the
+     * median ignores the one furthest off */
+    const auto& d = report.detectors;
+    report.payload_fraction =
+        Median({d[0].payload_fraction, d[1].payload_fraction,
+                d[2].payload_fraction});
+    report.confidence =
+        Median({d[0].confidence, d[1].confidence, d[2].confidence});
+    if (report.confidence < 0.5) {
+        return report;
+    }
+    report.bit_depth =
+        EstimateBitDepth(GetSamples(image), report.payload_fraction);
+    report.estimated_bytes = static_cast<std::size_t>(std::lround(
+        report.payload_fraction *
+        static_cast<double>(image.width() * image.height() * kChannelCount) *
+        std::max(report.bit_depth, 1) / 8.0));
+    return report;
+}
+
+RetCode Detect(const std::string& image, DetectionReport& report) {
+    boost::gil::rgb8_image_t input_img;
+    RetCode rc = LoadImage(image, input_img);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    report = Detect(input_img);
+    return RetCode::kSuccess;
+}
+
+std::string DetectionReportJson(const DetectionReport& report) {
+    std::ostringstream json;
+    json << std::fixed << std::setprecision(4);
+    json << "{\"payload_fraction\": " << report.payload_fraction
+         << ", \"confidence\": " << report.confidence
+         << ", \"bit_depth\": " << report.bit_depth
+         << ", \"estimated_bytes\": " << report.estimated_bytes
+         << ", \"detectors\": [";
+    for (std::size_t i = 0; i < report.detectors.size(); ++i) {
+        const DetectorScore& score = report.detectors[i];
+        json << ((i == 0) ? "" : ", ") << "{\"name\": \"" << score.name
+             << "\", \"payload_fraction\": " << score.payload_fraction
+             << ", \"confidence\": " << score.confidence << "}";
+    }
+    json << "]}";
+    return json.str();
+}
+
+}  // namespace steganography
@@ -0,0 +1,76 @@
+#include "utils/differential.hpp"
+
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <optional>
+#include <vector>
+
+#include "core/carrier.hpp"
+#include "core/frame.hpp"
+#include "utils/payload.hpp"
+
+namespace steganography {
+
+/* rows of an image follow each other at the distance between the first
+ * two, the same for two images of the same size */
+template <typename View>
+static std::size_t Stride(const View& view) {
+    if (view.height() < 2) {
+        return static_cast<std::size_t>(view.width()) * 3;
+    }
+    return static_cast<std::size_t>(&view(0, 1)[0] - &view(0, 0)[0]);
+}
+
+std::size_t DifferentialCapacity(const boost::gil::rgb8_image_t& reference) {
+    if ((reference.width() == 0) || (reference.height() == 0)) {
+        return 0;
+    }
+    auto view = boost::gil::const_view(reference);
+    const DifferenceCarrier kCarrier(
+        &view(0, 0)[0], &view(0, 0)[0],
+        static_cast<std::size_t>(reference.width()),
+        static_cast<std::size_t>(reference.height()), Stride(view));
+    return PayloadCapacity(kCarrier);
+}
+
+RetCode EmbedDifferential(const boost::gil::rgb8_image_t& reference,
+                          boost::gil::rgb8_image_t& image,
+                          const std::vector<uint8_t>& payload,
+                          const EmbedConfig& config) {
+    image = reference;
+    if ((image.width() == 0) || (image.height() == 0)) {
+        return RetCode::kInsufficientCapacity;
+    }
+
+    auto view = boost::gil::view(image);
+    auto reference_view = boost::gil::const_view(reference);
+    DifferenceCarrier carrier(&view(0, 0)[0], &reference_view(0, 0)[0],
+                              static_cast<std::size_t>(image.width()),
+                              static_cast<std::size_t>(image.height()),
+                              Stride(view));
+    return EmbedPayload(carrier, payload, config);
+}
+
+RetCode ExtractDifferential(const boost::gil::rgb8_image_t& image,
+                            const boost::gil::rgb8_image_t& reference,
+                            std::vector<uint8_t>& payload,
+                            PayloadHeader& header, DamageReport& damage,
+                            const std::optional<StegKey>& key) {
+    if (image.dimensions() != reference.dimensions()) {
+        return RetCode::kInvalidDimensions;
+    }
+    if ((image.width() == 0) || (image.height() == 0)) {
+        return RetCode::kPayloadNotFound;
+    }
+
+    auto view = boost::gil::const_view(image);
+    auto reference_view = boost::gil::const_view(reference);
+    const DifferenceCarrier kCarrier(
+        &view(0, 0)[0], &reference_view(0, 0)[0],
+        static_cast<std::size_t>(image.width()),
+        static_cast<std::size_t>(image.height()), Stride(view));
+    return ExtractPayload(kCarrier, payload, header, damage, key);
+}
+
+}  // namespace steganography
@@ -0,0 +1,40 @@
+#include "utils/embed_method.hpp"
+
+#include <map>
+#include <memory>
+#include <string>
+#include <utility>
+#include <vector>
+
+namespace steganography {
+
+/* built on first use so registering from another translation unit's static
+ * initializer finds the built-in method already there */
+static std::map<std::string, std::shared_ptr<const EmbedMethod>>& Methods() {
+    static std::map<std::string, std::shared_ptr<const EmbedMethod>> methods =
+        {{kDefaultMethod, std::make_shared<LsbMethod>()}};
+    return methods;
+}
+
+bool RegisterEmbedMethod(const std::string& name,
+                         std::shared_ptr<const EmbedMethod> method) {
+    if (name.empty() || !method) {
+        return false;
+    }
+    return Methods().emplace(name, std::move(method)).second;
+}
+
+std::shared_ptr<const EmbedMethod> FindEmbedMethod(const std::string& name) {
+    auto method = Methods().find(name);
+    return (method == Methods().end()) ? nullptr : method->second;
+}
+
+std::vector<std::string> EmbedMethodNames() {
+    std::vector<std::string> names;
+    for (const auto& [name, method] : Methods()) {
+        names.push_back(name);
+    }
+    return names;
+}
+
+}  // namespace steganography
@@ -0,0 +1,366 @@
+#include "utils/exr.hpp"
+
+#include <ImfChannelList.h>
+#include <ImfFrameBuffer.h>
+#include <ImfHeader.h>
+#include <ImfInputFile.h>
+#include <ImfOutputFile.h>
+#include <ImfPartType.h>
+
+#include <array>
+#include <boost/gil.hpp>
+#include <cmath>
+#include <cstddef>
+#include <cstdint>
+#include <cstring>
+#include <fstream>
+#include <limits>
+#include <string>
+#include <vector>
+
+#include "utils/embed_method.hpp"
+#include "utils/image_io.hpp"
+#include "utils/lsb.hpp"
+#include "utils/paths.hpp"
+
+namespace steganography {
+
+static const uint32_t kExrMagic = 20000630;
+
+/* a channel at full resolution, each sample kept as the raw bits of the
+ * channel's own pixel type */
+struct ExrChannel {
+    std::string name;
+    Imf::PixelType type = Imf::FLOAT;
+    std::vector<char> samples;
+};
+
+struct ExrImage {
+    Imf::Header header;
+    int width = 0;
+    int height = 0;
+    std::vector<ExrChannel> channels;
+};
+
+bool IsExr(const std::string& filename) {
+    std::ifstream ifs(filename, std::ifstream::binary);
+    std::array<unsigned char, 4> magic = {0, 0, 0, 0};
+    ifs.read(reinterpret_cast<char*>(magic.data()), magic.size());
+    const uint32_t kValue = static_cast<uint32_t>(magic[0]) |
+                            (static_cast<uint32_t>(magic[1]) << 8) |
+                            (static_cast<uint32_t>(magic[2]) << 16) |
+                            (static_cast<uint32_t>(magic[3]) << 24);
+    return ifs && (kValue == kExrMagic);
+}
+
+static std::size_t SampleSize(Imf::PixelType type) {
+    return (type == Imf::HALF) ? 2 : 4;
+}
+
+/* slices addressing the channels' samples by data window coordinates, the
+ * samples are only written through when reading a file */
+static Imf::FrameBuffer MakeFrameBuffer(const ExrImage& image) {
+    const Imath::Box2i kWindow = image.header.dataWindow();
+    const std::ptrdiff_t kOrigin =
+        kWindow.min.x +
+        static_cast<std::ptrdiff_t>(kWindow.min.y) * image.width;
+    Imf::FrameBuffer frame;
+    for (const ExrChannel& channel : image.channels) {
+        const std::size_t kSize = SampleSize(channel.type);
+        char* base = const_cast<char*>(channel.samples.data()) -
+                     kOrigin * static_cast<std::ptrdiff_t>(kSize);
+        const std::size_t kRowSize =
+            kSize * static_cast<std::size_t>(image.width);
+        frame.insert(channel.name,
+                     Imf::Slice(channel.type, base, kSize, kRowSize));
+    }
+    return frame;
+}
+
+static RetCode ReadExr(const std::string& filename, ExrImage& image) {
+    Imf::InputFile file(filename.c_str());
+    image.header = file.header();
+    const Imath::Box2i kWindow = image.header.dataWindow();
+    image.width = kWindow.max.x - kWindow.min.x + 1;
+    image.height = kWindow.max.y - kWindow.min.y + 1;
+
+    /* the header alone tells how much memory decoding takes */
+    const uint64_t kPixels = static_cast<uint64_t>(image.width) *
+                             static_cast<uint64_t>(image.height);
+    uint64_t decoded_bytes = 0;
+    for (auto it = image.header.channels().begin();
+         it != image.header.channels().end(); ++it) {
+        if ((it.channel().xSampling != 1) || (it.channel().ySampling != 1)) {
+            return RetCode::kInvalidFileFormat; /* subsampled chroma */
+        }
+        decoded_bytes += kPixels * SampleSize(it.channel().type);
+        image.channels.push_back({it.name(), it.channel().type, {}});
+    }
+    const ImageLimits& kLimits = GetImageLimits();
+    if (((kLimits.max_pixels != 0) && (kPixels > kLimits.max_pixels)) ||
+        ((kLimits.max_decoded_bytes != 0) &&
+         (decoded_bytes > kLimits.max_decoded_bytes))) {
+        return RetCode::kImageTooLarge;
+    }
+
+    for (ExrChannel& channel : image.channels) {
+        channel.samples.resize(kPixels * SampleSize(channel.type));
+    }
+    file.setFrameBuffer(MakeFrameBuffer(image));
+    file.readPixels(kWindow.min.y, kWindow.max.y);
+    return RetCode::kSuccess;
+}
+
+static void WriteExr(const std::string& filename, const ExrImage& image) {
+    /* written back as scan lines whatever layout the cover used */
+    Imf::Header header = image.header;
+    if (header.hasTileDescription()) {
+        header.erase("tiles");
+    }
+    if (header.hasType()) {
+        header.setType(Imf::SCANLINEIMAGE);
+    }
+    Imf::OutputFile file(filename.c_str(), header);
+    file.setFrameBuffer(MakeFrameBuffer(image));
+    file.writePixels(image.height);
+}
+
+/* the red, green, and blue channels, false when one is missing or holds
+ * integers rather than floating point values */
+static bool FindRgb(ExrImage& image, std::array<ExrChannel*, 3>& rgb) {
+    const std::array<const char*, 3> kNames = {"R", "G", "B"};
+    for (std::size_t i = 0; i < kNames.size(); ++i) {
+        rgb[i] = nullptr;
+        for (ExrChannel& channel : image.channels) {
+            if ((channel.name == kNames[i]) && (channel.type != Imf::UINT)) {
+                rgb[i] = &channel;
+            }
+        }
+        if (rgb[i] == nullptr) {
+            return false;
+        }
+    }
+    return true;
+}
+
+/* the raw bits of one sample and the mask of its exponent */
+static uint32_t SampleBits(const ExrChannel& channel, std::size_t index,
+                           uint32_t& exponent_mask) {
+    const std::size_t kSize = SampleSize(channel.type);
+    exponent_mask = (kSize == 2) ? 0x7C00 : 0x7F800000;
+    uint32_t bits = 0;
+    if (kSize == 2) {
+        uint16_t half = 0;
+        std::memcpy(&half, &channel.samples[index * kSize], kSize);
+        bits = half;
+    } else {
+        std::memcpy(&bits, &channel.samples[index * kSize], kSize);
+    }
+    return bits;
+}
+
+static void SetSampleBits(ExrChannel& channel, std::size_t index,
+                          uint32_t bits) {
+    const std::size_t kSize = SampleSize(channel.type);
+    if (kSize == 2) {
+        uint16_t half = static_cast<uint16_t>(bits);
+        std::memcpy(&channel.samples[index * kSize], &half, kSize);
+    } else {
+        std::memcpy(&channel.samples[index * kSize], &bits, kSize);
+    }
+}
+
+static double SampleValue(const ExrChannel& channel, std::size_t index) {
+    uint32_t exponent_mask = 0;
+    uint32_t bits = SampleBits(channel, index, exponent_mask);
+    if (channel.type != Imf::HALF) {
+        float value = 0.0f;
+        std::memcpy(&value, &bits, sizeof(value));
+        return value;
+    }
+    const int kExponent = static_cast<int>((bits >> 10) & 0x1F);
+    const int kMantissa = static_cast<int>(bits & 0x3FF);
+    double magnitude = (kExponent == 0)
+                           ? std::ldexp(kMantissa, -24)
+                           : std::ldexp(kMantissa + 1024, kExponent - 25);
+    return (bits & 0x8000) ? -magnitude : magnitude;
+}
+
+/* float channels have the mantissa bits to spare for the whole secret */
+static int HiddenBits(const ExrChannel& channel, int bits) {
+    return (channel.type == Imf::HALF) ? bits : 8;
+}
+
+static bool ValidExrConfig(const MergeConfig& config) {
+    return (config.bits >= kMinBits) && (config.bits <= kMaxBits) &&
+           config.keyfile.empty() && config.password.empty() &&
+           (config.method == kDefaultMethod) &&
+           (config.color_space == ColorSpace::kRgb) && !config.dither &&
+           !config.fit && !config.linear_resize && (config.sharpen <= 0.0) &&
+           (config.secret_width <= 0) &&
+           ((config.output_format == OutputFormat::kAuto) ||
+            (config.output_format == OutputFormat::kSameAsCover));
+}
+
+RetCode MergeExr(const std::string& cover, const std::string& secret,
+                 const std::string& outfile, const MergeConfig& config,
+                 MergeReport& report) {
+    report = MergeReport{};
+    if (!ValidExrConfig(config)) {
+        return RetCode::kInvalidArgument;
+    }
+    boost::gil::rgb8_image_t secret_img;
+    RetCode rc = LoadImage(secret, secret_img);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    return MergeExr(cover, secret_img, outfile, config, report);
+}
+
+RetCode MergeExr(const std::string& cover,
+                 const boost::gil::rgb8_image_t& secret_img,
+                 const std::string& outfile, const MergeConfig& config,
+                 MergeReport& report) {
+    report = MergeReport{};
+    if (!ValidExrConfig(config)) {
+        return RetCode::kInvalidArgument;
+    }
+    ExrImage image;
+    RetCode rc = ReadExr(cover, image);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    std::array<ExrChannel*, 3> rgb;
+    if (!FindRgb(image, rgb)) {
+        return RetCode::kInvalidFileFormat;
+    }
+    if ((secret_img.width() > image.width) ||
+        (secret_img.height() > image.height)) {
+        return RetCode::kInvalidDimensions;
+    }
+
+    /* like merge the secret is padded with black to the cover's size, the
+     * error is measured against a display white of 1.0 */
+    double squared_error = 0.0;
+    std::size_t samples = 0;
+    const boost::gil::rgb8_pixel_t kBlackPixel(0, 0, 0);
+    auto secret_view = boost::gil::const_view(secret_img);
+    for (int row = 0; row < image.height; ++row) {
+        for (int col = 0; col < image.width; ++col) {
+            boost::gil::rgb8_pixel_t secret_pix = kBlackPixel;
+            if ((row < secret_img.height()) && (col < secret_img.width())) {
+                secret_pix = secret_view(col, row);
+            }
+            const std::size_t kIndex =
+                static_cast<std::size_t>(row) *
+                    static_cast<std::size_t>(image.width) +
+                static_cast<std::size_t>(col);
+            bool modified = false;
+            for (std::size_t i = 0; i < rgb.size(); ++i) {
+                uint32_t exponent_mask = 0;
+                uint32_t bits = SampleBits(*rgb[i], kIndex, exponent_mask);
+                if ((bits & exponent_mask) == exponent_mask) {
+                    continue; /* infinity or NaN, their mantissa has meaning */
+                }
+                const int kHidden = HiddenBits(*rgb[i], config.bits);
+                const uint32_t kMask = (1U << kHidden) - 1;
+                const double kBefore = SampleValue(*rgb[i], kIndex);
+                SetSampleBits(*rgb[i], kIndex,
+                              (bits & ~kMask) |
+                                  static_cast<uint32_t>(secret_pix[i] >>
+                                                        (8 - kHidden)));
+                const double kDiff = SampleValue(*rgb[i], kIndex) - kBefore;
+                squared_error += kDiff * kDiff;
+                ++samples;
+                modified = modified || (kDiff != 0.0);
+            }
+            report.pixels_modified += modified ? 1 : 0;
+        }
+    }
+
+    if (config.create_dirs) {
+        rc = CreateParentDirectories(outfile);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+    WriteExr(outfile, image);
+
+    const std::size_t kPixels = static_cast<std::size_t>(image.width) *
+                                static_cast<std::size_t>(image.height);
+    const std::size_t kSecretPixels =
+        static_cast<std::size_t>(secret_img.width() * secret_img.height());
+    for (const ExrChannel* channel : rgb) {
+        report.bits_embedded +=
+            kSecretPixels *
+            static_cast<std::size_t>(HiddenBits(*channel, config.bits));
+    }
+    report.capacity_used = (kPixels == 0)
+                               ? 0.0
+                               : 100.0 * static_cast<double>(kSecretPixels) /
+                                     static_cast<double>(kPixels);
+    report.psnr = (squared_error == 0.0)
+                      ? std::numeric_limits<double>::infinity()
+                      : 10.0 * std::log10(static_cast<double>(samples) /
+                                          squared_error);
+    return RetCode::kSuccess;
+}
+
+RetCode UnmergeExr(const std::string& secret, const std::string& outfile,
+                   const UnmergeConfig& config) {
+    if ((config.bits < kMinBits) || (config.bits > kMaxBits) ||
+        !config.keyfile.empty() || !config.password.empty() ||
+        (config.method != kDefaultMethod) ||
+        (config.color_space != ColorSpace::kRgb)) {
+        return RetCode::kInvalidArgument;
+    }
+    ExrImage image;
+    RetCode rc = ReadExr(secret, image);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    std::array<ExrChannel*, 3> rgb;
+    if (!FindRgb(image, rgb)) {
+        return RetCode::kInvalidFileFormat;
+    }
+
+    boost::gil::rgb8_image_t output_img(image.width, image.height);
+    auto output_view = boost::gil::view(output_img);
+    for (int row = 0; row < image.height; ++row) {
+        for (int col = 0; col < image.width; ++col) {
+            const std::size_t kIndex =
+                static_cast<std::size_t>(row) *
+                    static_cast<std::size_t>(image.width) +
+                static_cast<std::size_t>(col);
+            boost::gil::rgb8_pixel_t pixel(0, 0, 0);
+            for (std::size_t i = 0; i < rgb.size(); ++i) {
+                uint32_t exponent_mask = 0;
+                uint32_t bits = SampleBits(*rgb[i], kIndex, exponent_mask);
+                if ((bits & exponent_mask) == exponent_mask) {
+                    continue;
+                }
+                const int kHidden = HiddenBits(*rgb[i], config.bits);
+                pixel[i] = static_cast<uint8_t>((bits & ((1U << kHidden) - 1))
+                                                << (8 - kHidden));
+            }
+            output_view(col, row) = pixel;
+        }
+    }
+
+    ImageType output_t = ImageType::kPng;
+    rc = ResolveOutputType(config.output_format, outfile, ImageType::kUnknown,
+                           false, output_t);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    if (config.create_dirs) {
+        rc = CreateParentDirectories(outfile);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+    WriteImage(output_img, outfile, output_t);
+    return RetCode::kSuccess;
+}
+
+}  // namespace steganography
@@ -0,0 +1,144 @@
+#include "utils/fetch.hpp"
+
+#include <curl/curl.h>
+
+#include <algorithm>
+#include <cstddef>
+#include <cstring>
+#include <filesystem>
+#include <fstream>
+#include <memory>
+#include <string>
+#include <system_error>
+
+namespace steganography {
+
+static const long kConnectTimeoutSec = 10;
+static const long kMaxRedirects = 5;
+
+struct Download {
+    std::ofstream ofs;
+    std::size_t received = 0;
+    std::size_t max_bytes = 0;
+};
+
+struct Upload {
+    const std::string* body = nullptr;
+    std::size_t sent = 0;
+};
+
+using CurlHandle = std::unique_ptr<CURL, decltype(&curl_easy_cleanup)>;
+using HeaderList = std::unique_ptr<curl_slist, decltype(&curl_slist_free_all)>;
+
+static std::size_t WriteChunk(char* data, std::size_t size, std::size_t count,
+                              void* userdata) {
+    Download* download = static_cast<Download*>(userdata);
+    std::size_t bytes = size * count;
+    download->received += bytes;
+    if (download->received > download->max_bytes) {
+        return 0; /* makes curl abort the transfer with a write error */
+    }
+    download->ofs.write(data, static_cast<std::streamsize>(bytes));
+    return download->ofs ? bytes : 0;
+}
+
+static std::size_t DiscardChunk(char* /* data */, std::size_t size,
+                                std::size_t count, void* /* userdata */) {
+    return size * count;
+}
+
+static std::size_t ReadChunk(char* buffer, std::size_t size, std::size_t count,
+                             void* userdata) {
+    Upload* upload = static_cast<Upload*>(userdata);
+    std::size_t bytes =
+        std::min(size * count, upload->body->size() - upload->sent);
+    std::memcpy(buffer, upload->body->data() + upload->sent, bytes);
+    upload->sent += bytes;
+    return bytes;
+}
+
+/* options shared by every transfer, the header list must outlive it */
+static void SetCommonOptions(CURL* curl, const std::string& url,
+                             const FetchConfig& config, HeaderList& headers) {
+    for (const auto& header : config.headers) {
+        headers.reset(curl_slist_append(headers.release(), header.c_str()));
+    }
+    curl_easy_setopt(curl, CURLOPT_URL, url.c_str());
+    curl_easy_setopt(curl, CURLOPT_HTTPHEADER, headers.get());
+    curl_easy_setopt(curl, CURLOPT_PROTOCOLS_STR, "http,https");
+    curl_easy_setopt(curl, CURLOPT_REDIR_PROTOCOLS_STR, "http,https");
+    curl_easy_setopt(curl, CURLOPT_FAILONERROR, 1L);
+    curl_easy_setopt(curl, CURLOPT_NOSIGNAL, 1L);
+    curl_easy_setopt(curl, CURLOPT_CONNECTTIMEOUT, kConnectTimeoutSec);
+    curl_easy_setopt(curl, CURLOPT_TIMEOUT, config.timeout_seconds);
+    curl_easy_setopt(curl, CURLOPT_USERAGENT, "steganography");
+}
+
+bool IsUrl(const std::string& path) {
+    return path.starts_with("https://") || path.starts_with("http://");
+}
+
+RetCode FetchUrl(const std::string& url, const std::string& outfile,
+                 const FetchConfig& config) {
+    if (!IsUrl(url)) {
+        return RetCode::kInvalidArgument;
+    }
+
+    Download download;
+    download.max_bytes = config.max_bytes;
+    download.ofs.open(outfile, std::ofstream::binary);
+    if (!download.ofs.is_open()) {
+        return RetCode::kIoError;
+    }
+
+    CurlHandle curl(curl_easy_init(), curl_easy_cleanup);
+    if (!curl) {
+        return RetCode::kNetworkError;
+    }
+    HeaderList headers(nullptr, curl_slist_free_all);
+    SetCommonOptions(curl.get(), url, config, headers);
+    curl_easy_setopt(curl.get(), CURLOPT_FOLLOWLOCATION, 1L);
+    curl_easy_setopt(curl.get(), CURLOPT_MAXREDIRS, kMaxRedirects);
+    curl_easy_setopt(curl.get(), CURLOPT_MAXFILESIZE_LARGE,
+                     static_cast<curl_off_t>(config.max_bytes));
+    curl_easy_setopt(curl.get(), CURLOPT_WRITEFUNCTION, WriteChunk);
+    curl_easy_setopt(curl.get(), CURLOPT_WRITEDATA, &download);
+
+    CURLcode res = curl_easy_perform(curl.get());
+    download.ofs.close();
+    if ((res != CURLE_OK) || !download.ofs) {
+        std::error_code ec;
+        std::filesystem::remove(outfile, ec);
+        return RetCode::kNetworkError;
+    }
+    return RetCode::kSuccess;
+}
+
+RetCode PutUrl(const std::string& url, const std::string& body,
+               const FetchConfig& config) {
+    if (!IsUrl(url)) {
+        return RetCode::kInvalidArgument;
+    }
+
+    CurlHandle curl(curl_easy_init(), curl_easy_cleanup);
+    if (!curl) {
+        return RetCode::kNetworkError;
+    }
+    Upload upload;
+    upload.body = &body;
+    HeaderList headers(nullptr, curl_slist_free_all);
+    SetCommonOptions(curl.get(), url, config, headers);
+    curl_easy_setopt(curl.get(), CURLOPT_UPLOAD, 1L);
+    curl_easy_setopt(curl.get(), CURLOPT_INFILESIZE_LARGE,
+                     static_cast<curl_off_t>(body.size()));
+    curl_easy_setopt(curl.get(), CURLOPT_READFUNCTION, ReadChunk);
+    curl_easy_setopt(curl.get(), CURLOPT_READDATA, &upload);
+    curl_easy_setopt(curl.get(), CURLOPT_WRITEFUNCTION, DiscardChunk);
+
+    if (curl_easy_perform(curl.get()) != CURLE_OK) {
+        return RetCode::kNetworkError;
+    }
+    return RetCode::kSuccess;
+}
+
+}  // namespace steganography
@@ -0,0 +1,195 @@
+#include "utils/gif.hpp"
+
+#include <gif_lib.h>
+
+#include <algorithm>
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <cstdlib>
+#include <cstring>
+#include <fstream>
+#include <string>
+#include <vector>
+
+namespace steganography {
+
+bool ReadIndexedGif(const std::string& filename, IndexedImage& image) {
+    int error = 0;
+    GifFileType* gif = DGifOpenFileName(filename.c_str(), &error);
+    if (gif == nullptr) {
+        return false;
+    }
+    if ((DGifSlurp(gif) != GIF_OK) || (gif->ImageCount < 1)) {
+        DGifCloseFile(gif, &error);
+        return false;
+    }
+    const SavedImage& frame = gif->SavedImages[0];
+    const ColorMapObject* colors = (frame.ImageDesc.ColorMap != nullptr)
+                                       ? frame.ImageDesc.ColorMap
+                                       : gif->SColorMap;
+    if ((colors == nullptr) || (frame.ImageDesc.Width <= 0) ||
+        (frame.ImageDesc.Height <= 0)) {
+        DGifCloseFile(gif, &error);
+        return false;
+    }
+    image.width = static_cast<std::size_t>(frame.ImageDesc.Width);
+    image.height = static_cast<std::size_t>(frame.ImageDesc.Height);
+    image.palette.clear();
+    for (int i = 0; i < colors->ColorCount; ++i) {
+        image.palette.push_back(colors->Colors[i].Red);
+        image.palette.push_back(colors->Colors[i].Green);
+        image.palette.push_back(colors->Colors[i].Blue);
+    }
+
+    /* interlaced rows are stored every 8th from 0, every 8th from 4, every
+     * 4th from 2, then every 2nd from 1 */
+    std::vector<std::size_t> rows;
+    if (frame.ImageDesc.Interlace) {
+        const std::size_t kStarts[] = {0, 4, 2, 1};
+        const std::size_t kSteps[] = {8, 8, 4, 2};
+        for (int pass = 0; pass < 4; ++pass) {
+            for (std::size_t row = kStarts[pass]; row < image.height;
+                 row += kSteps[pass]) {
+                rows.push_back(row);
+            }
+        }
+    } else {
+        for (std::size_t row = 0; row < image.height; ++row) {
+            rows.push_back(row);
+        }
+    }
+    image.indices.assign(image.width * image.height, 0);
+    for (std::size_t i = 0; i < rows.size(); ++i) {
+        std::memcpy(image.indices.data() + rows[i] * image.width,
+                    frame.RasterBits + i * image.width, image.width);
+    }
+
+    GraphicsControlBlock gcb;
+    image.transparent = (DGifSavedExtensionToGCB(gif, 0, &gcb) == GIF_OK)
+                            ? gcb.TransparentColor
+                            : NO_TRANSPARENT_COLOR;
+    DGifCloseFile(gif, &error);
+    return true;
+}
+
+bool ReadGif(const std::string& filename, boost::gil::rgb8_image_t& image) {
+    IndexedImage indexed;
+    if (!ReadIndexedGif(filename, indexed)) {
+        return false;
+    }
+    image = boost::gil::rgb8_image_t(
+        static_cast<std::ptrdiff_t>(indexed.width),
+        static_cast<std::ptrdiff_t>(indexed.height));
+    auto view = boost::gil::view(image);
+    const std::size_t kColors = indexed.palette.size() / 3;
+    for (std::size_t y = 0; y < indexed.height; ++y) {
+        for (std::size_t x = 0; x < indexed.width; ++x) {
+            /* entries past the end of the palette read as black */
+            std::size_t entry = indexed.indices[y * indexed.width + x];
+            for (int c = 0; c < 3; ++c) {
+                view(static_cast<std::ptrdiff_t>(x),
+                     static_cast<std::ptrdiff_t>(y))[c] =
+                    (entry < kColors) ? indexed.palette[3 * entry + c] : 0;
+            }
+        }
+    }
+    return true;
+}
+
+bool ReadGifHeader(const std::string& filename, uint64_t& width,
+                   uint64_t& height, uint64_t& pixel_bytes) {
+    std::ifstream ifs(filename, std::ifstream::binary);
+    std::vector<uint8_t> screen(13, 0);
+    ifs.read(reinterpret_cast<char*>(screen.data()),
+             static_cast<std::streamsize>(screen.size()));
+    if (!ifs) {
+        return false;
+    }
+    width = screen[6] | (static_cast<uint64_t>(screen[7]) << 8);
+    height = screen[8] | (static_cast<uint64_t>(screen[9]) << 8);
+    pixel_bytes = 1;
+    const int kColorTableFlag = 0x80;
+    if (screen[10] & kColorTableFlag) {
+        ifs.seekg(3 << ((screen[10] & 0x07) + 1), std::ios::cur);
+    }
+
+    /* skip extensions up to the first frame, which may be larger than the
+     * screen it claims to sit on */
+    while (ifs) {
+        int block = ifs.get();
+        if (block == 0x21) {
+            ifs.get(); /* label */
+            for (int length = ifs.get(); ifs && (length > 0);
+                 length = ifs.get()) {
+                ifs.seekg(length, std::ios::cur);
+            }
+        } else if (block == 0x2C) {
+            std::vector<uint8_t> frame(8, 0);
+            ifs.read(reinterpret_cast<char*>(frame.data()),
+                     static_cast<std::streamsize>(frame.size()));
+            if (!ifs) {
+                return false;
+            }
+            width = std::max<uint64_t>(
+                width, frame[4] | (static_cast<uint64_t>(frame[5]) << 8));
+            height = std::max<uint64_t>(
+                height, frame[6] | (static_cast<uint64_t>(frame[7]) << 8));
+            return true;
+        } else {
+            return false;
+        }
+    }
+    return false;
+}
+
+bool WriteIndexedGif(const IndexedImage& image, const std::string& filename) {
+    const int kColors = static_cast<int>(image.palette.size() / 3);
+    if ((kColors < 2) || (kColors > 256) || (kColors & (kColors - 1)) ||
+        (image.indices.size() != image.width * image.height)) {
+        return false;
+    }
+    std::vector<GifColorType> colors(kColors);
+    for (int i = 0; i < kColors; ++i) {
+        colors[i].Red = image.palette[3 * i];
+        colors[i].Green = image.palette[3 * i + 1];
+        colors[i].Blue = image.palette[3 * i + 2];
+    }
+
+    int error = 0;
+    GifFileType* gif = EGifOpenFileName(filename.c_str(), false, &error);
+    if (gif == nullptr) {
+        return false;
+    }
+    gif->SWidth = static_cast<GifWord>(image.width);
+    gif->SHeight = static_cast<GifWord>(image.height);
+    gif->SColorResolution = 8;
+    gif->SBackGroundColor = 0;
+    gif->SColorMap = GifMakeMapObject(kColors, colors.data());
+    SavedImage* frame = GifMakeSavedImage(gif, nullptr);
+    if ((gif->SColorMap == nullptr) || (frame == nullptr)) {
+        EGifCloseFile(gif, &error);
+        return false;
+    }
+    frame->ImageDesc.Width = static_cast<GifWord>(image.width);
+    frame->ImageDesc.Height = static_cast<GifWord>(image.height);
+
+    /* giflib frees the raster with the file */
+    frame->RasterBits =
+        static_cast<GifByteType*>(std::malloc(image.indices.size()));
+    if (frame->RasterBits == nullptr) {
+        EGifCloseFile(gif, &error);
+        return false;
+    }
+    std::memcpy(frame->RasterBits, image.indices.data(), image.indices.size());
+    if (image.transparent >= 0) {
+        GraphicsControlBlock gcb = {DISPOSAL_UNSPECIFIED, false, 0,
+                                    image.transparent};
+        EGifGCBToSavedExtension(&gcb, gif, 0);
+    }
+
+    /* spewing closes the file once everything is written */
+    return EGifSpew(gif) == GIF_OK;
+}
+
+}  // namespace steganography
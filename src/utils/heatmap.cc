@@ -0,0 +1,171 @@
+#include "utils/heatmap.hpp"
+
+#include <algorithm>
+#include <array>
+#include <boost/gil.hpp>
+#include <cmath>
+#include <cstddef>
+#include <cstdint>
+#include <string>
+#include <vector>
+
+#include "utils/image_io.hpp"
+#include "utils/paths.hpp"
+
+namespace steganography {
+
+static const int kChannelCount = 3;
+
+/* median absolute deviations from the median that saturate the colors, the
+ * entropy of photos is itself close to 1 so only the contrast between blocks
+ * is telling */
+static const double kSaturation = 3.0;
+
+/* how far below 1 an entropy is on a log scale, which separates blocks of
+ * noise from blocks of photo grain that are both close to 1 */
+static double Randomness(double entropy) {
+    return -std::log(std::max(1.0 - entropy, 1e-9));
+}
+
+static double BinaryEntropy(double p) {
+    if ((p <= 0.0) || (p >= 1.0)) {
+        return 0.0;
+    }
+    return -p * std::log2(p) - (1.0 - p) * std::log2(1.0 - p);
+}
+
+/* entropy of an LSB given the LSB of the same channel in the pixel to its
+ * left, plain LSB entropy is near 1 even in untouched photos */
+static double LsbEntropy(const boost::gil::rgb8c_view_t& view,
+                         std::ptrdiff_t x0, std::ptrdiff_t y0,
+                         std::ptrdiff_t x1, std::ptrdiff_t y1) {
+    /* counts[previous][current] */
+    std::array<std::array<double, 2>, 2> counts = {};
+    for (std::ptrdiff_t y = y0; y < y1; ++y) {
+        for (std::ptrdiff_t x = x0 + 1; x < x1; ++x) {
+            for (int i = 0; i < kChannelCount; ++i) {
+                counts[view(x - 1, y)[i] & 1][view(x, y)[i] & 1] += 1.0;
+            }
+        }
+    }
+    double total = 0.0;
+    double entropy = 0.0;
+    for (const auto& row : counts) {
+        double n = row[0] + row[1];
+        if (n > 0.0) {
+            entropy += n * BinaryEntropy(row[1] / n);
+            total += n;
+        }
+    }
+    return (total > 0.0) ? entropy / total : 0.0;
+}
+
+EntropyMap BlockEntropy(const boost::gil::rgb8_image_t& image, int block) {
+    EntropyMap map;
+    if (block < 2) {
+        return map;
+    }
+    auto view = boost::gil::const_view(image);
+    map.cols = (view.width() + block - 1) / block;
+    map.rows = (view.height() + block - 1) / block;
+    map.entropy.reserve(static_cast<std::size_t>(map.cols * map.rows));
+    for (std::ptrdiff_t row = 0; row < map.rows; ++row) {
+        for (std::ptrdiff_t col = 0; col < map.cols; ++col) {
+            map.entropy.push_back(LsbEntropy(
+                view, col * block, row * block,
+                std::min(view.width(), (col + 1) * block),
+                std::min(view.height(), (row + 1) * block)));
+        }
+    }
+    return map;
+}
+
+static double Median(std::vector<double> values) {
+    if (values.empty()) {
+        return 0.0;
+    }
+    auto middle =
+        values.begin() + static_cast<std::ptrdiff_t>(values.size() / 2);
+    std::nth_element(values.begin(), middle, values.end());
+    return *middle;
+}
+
+/* blue through green to red as value goes from 0 to 1 */
+static std::array<double, 3> HeatColor(double value) {
+    value = std::clamp(value, 0.0, 1.0);
+    if (value < 0.5) {
+        return {0.0, 510.0 * value, 255.0 * (1.0 - 2.0 * value)};
+    }
+    return {510.0 * (value - 0.5), 255.0 * (2.0 - 2.0 * value), 0.0};
+}
+
+RetCode RenderHeatmap(const boost::gil::rgb8_image_t& image,
+                      const HeatmapConfig& config,
+                      boost::gil::rgb8_image_t& rendered) {
+    if ((config.block < 2) || (config.opacity < 0.0) ||
+        (config.opacity > 1.0)) {
+        return RetCode::kInvalidArgument;
+    }
+
+    EntropyMap map = BlockEntropy(image, config.block);
+    std::vector<double> randomness;
+    randomness.reserve(map.entropy.size());
+    for (double entropy : map.entropy) {
+        randomness.push_back(Randomness(entropy));
+    }
+    double median = Median(randomness);
+    std::vector<double> deviations;
+    deviations.reserve(randomness.size());
+    for (double value : randomness) {
+        deviations.push_back(std::fabs(value - median));
+    }
+    double spread = std::max(kSaturation * Median(deviations), 1e-6);
+
+    rendered = boost::gil::rgb8_image_t(image.width(), image.height());
+    auto src = boost::gil::const_view(image);
+    auto dst = boost::gil::view(rendered);
+    for (std::ptrdiff_t y = 0; y < src.height(); ++y) {
+        for (std::ptrdiff_t x = 0; x < src.width(); ++x) {
+            const auto& pixel = src(x, y);
+            double gray = 0.299 * pixel[0] + 0.587 * pixel[1] +
+                          0.114 * pixel[2];
+            double entropy = map.At(x / config.block, y / config.block);
+            std::array<double, 3> heat =
+                HeatColor(0.5 + 0.5 * (Randomness(entropy) - median) / spread);
+            for (std::size_t i = 0; i < heat.size(); ++i) {
+                double value =
+                    (1.0 - config.opacity) * gray + config.opacity * heat[i];
+                dst(x, y)[i] = static_cast<uint8_t>(
+                    std::clamp(std::lround(value), 0L, 255L));
+            }
+        }
+    }
+    return RetCode::kSuccess;
+}
+
+RetCode Heatmap(const std::string& image, const std::string& outfile,
+                const HeatmapConfig& config) {
+    boost::gil::rgb8_image_t input_img;
+    RetCode rc = LoadImage(image, input_img);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    boost::gil::rgb8_image_t rendered;
+    rc = RenderHeatmap(input_img, config, rendered);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    if (config.create_dirs) {
+        rc = CreateParentDirectories(outfile);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+
+    /* write the image out using the user's preferred format (default PNG) */
+    WriteImage(rendered, outfile,
+               HasJpegExtension(outfile) ? ImageType::kJpeg : ImageType::kPng);
+    return RetCode::kSuccess;
+}
+
+}  // namespace steganography
@@ -0,0 +1,104 @@
+#include "utils/heif.hpp"
+
+#include <libheif/heif.h>
+
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <memory>
+#include <string>
+
+namespace steganography {
+
+struct ContextDeleter {
+    void operator()(heif_context* context) const { heif_context_free(context); }
+};
+
+struct HandleDeleter {
+    void operator()(heif_image_handle* handle) const {
+        heif_image_handle_release(handle);
+    }
+};
+
+struct ImageDeleter {
+    void operator()(heif_image* image) const { heif_image_release(image); }
+};
+
+using ContextPtr = std::unique_ptr<heif_context, ContextDeleter>;
+using HandlePtr = std::unique_ptr<heif_image_handle, HandleDeleter>;
+using HeifImagePtr = std::unique_ptr<heif_image, ImageDeleter>;
+
+/* the handle of the file's primary image, the context has to outlive it */
+static HandlePtr PrimaryImage(const std::string& filename,
+                              ContextPtr& context) {
+    context.reset(heif_context_alloc());
+    if (!context || (heif_context_read_from_file(context.get(),
+                                                 filename.c_str(), nullptr)
+                         .code != heif_error_Ok)) {
+        return nullptr;
+    }
+    heif_image_handle* handle = nullptr;
+    if (heif_context_get_primary_image_handle(context.get(), &handle).code !=
+        heif_error_Ok) {
+        return nullptr;
+    }
+    return HandlePtr(handle);
+}
+
+bool ReadHeif(const std::string& filename, boost::gil::rgb8_image_t& image) {
+    ContextPtr context;
+    HandlePtr handle = PrimaryImage(filename, context);
+    if (!handle) {
+        return false;
+    }
+
+    /* libheif converts to interleaved 8 bit RGB and applies the rotation and
+     * mirroring stored in the file, as iPhones use them for orientation */
+    heif_image* decoded = nullptr;
+    if (heif_decode_image(handle.get(), &decoded, heif_colorspace_RGB,
+                          heif_chroma_interleaved_RGB, nullptr)
+            .code != heif_error_Ok) {
+        return false;
+    }
+    HeifImagePtr heif_image(decoded);
+    int stride = 0;
+    const uint8_t* plane = heif_image_get_plane_readonly(
+        heif_image.get(), heif_channel_interleaved, &stride);
+    if (plane == nullptr) {
+        return false;
+    }
+    const int kWidth =
+        heif_image_get_width(heif_image.get(), heif_channel_interleaved);
+    const int kHeight =
+        heif_image_get_height(heif_image.get(), heif_channel_interleaved);
+    image = boost::gil::rgb8_image_t(kWidth, kHeight);
+    auto view = boost::gil::view(image);
+    for (int y = 0; y < kHeight; ++y) {
+        const uint8_t* row = plane + static_cast<std::size_t>(y) * stride;
+        for (int x = 0; x < kWidth; ++x) {
+            view(x, y) = boost::gil::rgb8_pixel_t(row[x * 3], row[x * 3 + 1],
+                                                  row[x * 3 + 2]);
+        }
+    }
+    return true;
+}
+
+bool ReadHeifHeader(const std::string& filename, uint64_t& width,
+                    uint64_t& height, uint64_t& pixel_bytes) {
+    ContextPtr context;
+    HandlePtr handle = PrimaryImage(filename, context);
+    if (!handle) {
+        return false;
+    }
+    width = static_cast<uint64_t>(heif_image_handle_get_width(handle.get()));
+    height = static_cast<uint64_t>(heif_image_handle_get_height(handle.get()));
+
+    /* the YUV planes and any alpha plane at the image's bit depth */
+    const uint64_t kChannels =
+        heif_image_handle_has_alpha_channel(handle.get()) ? 4 : 3;
+    const int kBits = heif_image_handle_get_luma_bits_per_pixel(handle.get());
+    pixel_bytes = kChannels * ((kBits > 8) ? 2 : 1);
+    return true;
+}
+
+}  // namespace steganography
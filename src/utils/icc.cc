@@ -0,0 +1,223 @@
+#include "utils/icc.hpp"
+
+#include <zlib.h>
+
+#include <algorithm>
+#include <cstddef>
+#include <cstdint>
+#include <fstream>
+#include <iterator>
+#include <map>
+#include <string>
+#include <vector>
+
+#include "utils/bytes.hpp"
+
+namespace steganography {
+
+static const std::size_t kPngSignatureSize = 8;
+static const std::size_t kIccHeaderSize = 128;
+static const std::string kJpegIccMarker("ICC_PROFILE\0", 12);
+static const std::string kProfileName("ICC Profile");
+
+static bool ReadFile(const std::string& filename, std::vector<uint8_t>& data) {
+    std::ifstream ifs(filename, std::ifstream::binary);
+    if (!ifs.is_open()) {
+        return false;
+    }
+    data.assign(std::istreambuf_iterator<char>(ifs),
+                std::istreambuf_iterator<char>());
+    return true;
+}
+
+/* inflates a zlib stream, the iCCP chunk stores its profile as one */
+static bool Inflate(const uint8_t* data, std::size_t size,
+                    std::vector<uint8_t>& out) {
+    const std::size_t kChunkSize = 64 * 1024;
+    z_stream stream{};
+    if (inflateInit(&stream) != Z_OK) {
+        return false;
+    }
+    stream.next_in = const_cast<Bytef*>(data);
+    stream.avail_in = static_cast<uInt>(size);
+    out.clear();
+    int ret = Z_OK;
+    while (ret == Z_OK) {
+        out.resize(out.size() + kChunkSize);
+        stream.next_out = out.data() + stream.total_out;
+        stream.avail_out = static_cast<uInt>(kChunkSize);
+        ret = inflate(&stream, Z_NO_FLUSH);
+    }
+    out.resize(stream.total_out);
+    inflateEnd(&stream);
+    return ret == Z_STREAM_END;
+}
+
+static std::vector<uint8_t> PngProfile(const std::vector<uint8_t>& png) {
+    std::size_t offset = kPngSignatureSize;
+    while (offset + 8 <= png.size()) {
+        const std::size_t kLength = ReadInt<uint32_t>(png, offset);
+        const std::string kType(png.cbegin() + offset + 4,
+                                png.cbegin() + offset + 8);
+        const std::size_t kData = offset + 8;
+        if ((kType == "IDAT") || (kData + kLength > png.size())) {
+            break; /* the profile has to come before the image data */
+        }
+        if (kType == "iCCP") {
+            /* a profile name, its terminator, and the compression method */
+            auto name_end = std::find(png.cbegin() + kData,
+                                      png.cbegin() + kData + kLength, 0);
+            const std::size_t kCompressed =
+                static_cast<std::size_t>(name_end - png.cbegin()) + 2;
+            std::vector<uint8_t> profile;
+            if ((kCompressed <= kData + kLength) &&
+                Inflate(png.data() + kCompressed, kData + kLength - kCompressed,
+                        profile)) {
+                return profile;
+            }
+            break;
+        }
+        offset = kData + kLength + 4; /* past the CRC */
+    }
+    return {};
+}
+
+/* profiles too large for one segment are split over several APP2 segments,
+ * each numbered from 1 */
+static std::vector<uint8_t> JpegProfile(const std::vector<uint8_t>& jpeg) {
+    std::map<int, std::vector<uint8_t>> chunks;
+    std::size_t offset = 2;
+    while (offset + 4 <= jpeg.size()) {
+        if (jpeg[offset] != 0xFF) {
+            break;
+        }
+        const uint8_t kMarker = jpeg[offset + 1];
+        if ((kMarker == 0xD9) || (kMarker == 0xDA)) {
+            break; /* the end of the image or the start of the scan */
+        }
+        if ((kMarker == 0x01) || ((kMarker >= 0xD0) && (kMarker <= 0xD8)) ||
+            (kMarker == 0xFF)) {
+            offset += (kMarker == 0xFF) ? 1 : 2;
+            continue;
+        }
+        const std::size_t kLength = ReadInt<uint16_t>(jpeg, offset + 2);
+        const std::size_t kData = offset + 4;
+        const std::size_t kEnd = offset + 2 + kLength;
+        if ((kLength < 2) || (kEnd > jpeg.size())) {
+            break;
+        }
+        const std::size_t kHeader = kJpegIccMarker.size() + 2;
+        if ((kMarker == 0xE2) && (kEnd - kData > kHeader) &&
+            std::equal(kJpegIccMarker.cbegin(), kJpegIccMarker.cend(),
+                       jpeg.cbegin() + kData)) {
+            const int kSequence = jpeg[kData + kJpegIccMarker.size()];
+            chunks[kSequence].assign(jpeg.cbegin() + kData + kHeader,
+                                     jpeg.cbegin() + kEnd);
+        }
+        offset = kEnd;
+    }
+    std::vector<uint8_t> profile;
+    int expected = 1;
+    for (const auto& [sequence, chunk] : chunks) {
+        if (sequence != expected++) {
+            return {}; /* a missing chunk leaves the profile unusable */
+        }
+        profile.insert(profile.end(), chunk.cbegin(), chunk.cend());
+    }
+    return profile;
+}
+
+std::vector<uint8_t> ReadIccProfile(const std::string& filename) {
+    std::vector<uint8_t> data;
+    if (!ReadFile(filename, data)) {
+        return {};
+    }
+    const std::vector<uint8_t> kPngSignature = {0x89, 0x50, 0x4E, 0x47,
+                                                0x0D, 0x0A, 0x1A, 0x0A};
+    std::vector<uint8_t> profile;
+    if ((data.size() >= kPngSignatureSize) &&
+        std::equal(kPngSignature.cbegin(), kPngSignature.cend(),
+                   data.cbegin())) {
+        profile = PngProfile(data);
+    } else if ((data.size() >= 2) && (data[0] == 0xFF) && (data[1] == 0xD8)) {
+        profile = JpegProfile(data);
+    }
+
+    /* the data color space in the header, a gray or CMYK profile cannot
+     * describe the RGB pixels that were decoded */
+    const std::size_t kColorSpaceOffset = 16;
+    if ((profile.size() < kIccHeaderSize) ||
+        (std::string(profile.cbegin() + kColorSpaceOffset,
+                     profile.cbegin() + kColorSpaceOffset + 4) != "RGB ")) {
+        return {};
+    }
+    return profile;
+}
+
+static void AppendChunk(std::vector<uint8_t>& png, const std::string& type,
+                        const std::vector<uint8_t>& data) {
+    AppendInt<uint32_t>(png, static_cast<uint32_t>(data.size()));
+    const std::size_t kTypeOffset = png.size();
+    png.insert(png.end(), type.cbegin(), type.cend());
+    png.insert(png.end(), data.cbegin(), data.cend());
+    AppendInt<uint32_t>(
+        png, static_cast<uint32_t>(
+                 crc32(0, png.data() + kTypeOffset,
+                       static_cast<uInt>(png.size() - kTypeOffset))));
+}
+
+bool AttachIccProfile(const std::string& filename,
+                      const std::vector<uint8_t>& profile) {
+    std::vector<uint8_t> png;
+    if (!ReadFile(filename, png) || (png.size() < kPngSignatureSize)) {
+        return false;
+    }
+
+    std::vector<uint8_t> iccp(kProfileName.cbegin(), kProfileName.cend());
+    iccp.push_back(0); /* the name's terminator */
+    iccp.push_back(0); /* deflate, the only compression method */
+    uLongf compressed_size = compressBound(static_cast<uLong>(profile.size()));
+    std::vector<uint8_t> compressed(compressed_size);
+    if (compress2(compressed.data(), &compressed_size, profile.data(),
+                  static_cast<uLong>(profile.size()),
+                  Z_BEST_COMPRESSION) != Z_OK) {
+        return false;
+    }
+    iccp.insert(iccp.end(), compressed.cbegin(),
+                compressed.cbegin() + static_cast<std::ptrdiff_t>(
+                                          compressed_size));
+
+    /* the profile goes straight after the header chunk, and a profile the
+     * file already claimed would contradict it */
+    std::vector<uint8_t> output(png.cbegin(),
+                                png.cbegin() + kPngSignatureSize);
+    std::size_t offset = kPngSignatureSize;
+    bool header = true;
+    while (offset + 12 <= png.size()) {
+        const std::size_t kLength = ReadInt<uint32_t>(png, offset);
+        const std::size_t kEnd = offset + 12 + kLength;
+        if (kEnd > png.size()) {
+            return false;
+        }
+        const std::string kType(png.cbegin() + offset + 4,
+                                png.cbegin() + offset + 8);
+        if ((kType != "iCCP") && (kType != "sRGB")) {
+            output.insert(output.end(), png.cbegin() + offset,
+                          png.cbegin() + kEnd);
+        }
+        if (header) {
+            if (kType != "IHDR") {
+                return false;
+            }
+            AppendChunk(output, "iCCP", iccp);
+            header = false;
+        }
+        offset = kEnd;
+    }
+    std::ofstream ofs(filename, std::ofstream::binary);
+    ofs.write(reinterpret_cast<const char*>(output.data()),
+              static_cast<std::streamsize>(output.size()));
+    return static_cast<bool>(ofs);
+}
+
+}  // namespace steganography
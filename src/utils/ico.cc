@@ -0,0 +1,481 @@
+#include "utils/ico.hpp"
+
+#include <png.h>
+
+#include <algorithm>
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <fstream>
+#include <iterator>
+#include <optional>
+#include <string>
+#include <utility>
+#include <vector>
+
+#include "core/bytes.hpp"
+#include "core/carrier.hpp"
+#include "utils/image_io.hpp"
+#include "utils/payload.hpp"
+
+namespace steganography {
+
+static const std::size_t kIconHeaderSize = 6;
+static const std::size_t kIconEntrySize = 16;
+
+/* the BITMAPINFOHEADER of images stored as BMP */
+static const std::size_t kInfoHeaderSize = 40;
+
+/* one image of the icon, its directory entry and its data */
+struct IconImage {
+    std::vector<uint8_t> entry;
+    std::vector<uint8_t> data;
+};
+
+/* where the pixels of an image stored as BMP are, rows run bottom up */
+struct DibLayout {
+    std::size_t width = 0;
+    std::size_t height = 0;
+    std::size_t bits = 0;    /* per pixel */
+    std::size_t colors = 0;  /* BGRX entries of the color table */
+    std::size_t palette = 0; /* offset of the color table */
+    std::size_t pixels = 0;  /* offset of the bottom row */
+    std::size_t stride = 0;
+};
+
+/* ICO fields are little endian, unlike the payload's own */
+static uint32_t ReadLe(const std::vector<uint8_t>& data, std::size_t offset,
+                       std::size_t size) {
+    uint32_t value = 0;
+    for (std::size_t i = size; i-- > 0;) {
+        value = (value << 8) | data[offset + i];
+    }
+    return value;
+}
+
+static void WriteLe(std::vector<uint8_t>& data, std::size_t offset,
+                    std::size_t size, uint32_t value) {
+    for (std::size_t i = 0; i < size; ++i, value >>= 8) {
+        data[offset + i] = static_cast<uint8_t>(value);
+    }
+}
+
+static bool ReadIcon(const std::string& filename,
+                     std::vector<IconImage>& images) {
+    std::ifstream ifs(filename, std::ifstream::binary);
+    if (!ifs.is_open()) {
+        return false;
+    }
+    std::vector<uint8_t> file((std::istreambuf_iterator<char>(ifs)),
+                              std::istreambuf_iterator<char>());
+    if ((file.size() < kIconHeaderSize) || (ReadLe(file, 0, 2) != 0) ||
+        (ReadLe(file, 2, 2) != 1)) {
+        return false;
+    }
+    const std::size_t kCount = ReadLe(file, 4, 2);
+    if ((kCount == 0) ||
+        (file.size() < kIconHeaderSize + kCount * kIconEntrySize)) {
+        return false;
+    }
+    images.clear();
+    for (std::size_t i = 0; i < kCount; ++i) {
+        auto entry = file.cbegin() + kIconHeaderSize + i * kIconEntrySize;
+        IconImage image;
+        image.entry.assign(entry, entry + kIconEntrySize);
+        const std::size_t kSize = ReadLe(image.entry, 8, 4);
+        const std::size_t kOffset = ReadLe(image.entry, 12, 4);
+        if ((kOffset > file.size()) || (kSize > file.size() - kOffset)) {
+            return false;
+        }
+        image.data.assign(file.cbegin() + kOffset,
+                          file.cbegin() + kOffset + kSize);
+        images.push_back(std::move(image));
+    }
+    return true;
+}
+
+/* the images are stored one after another behind the directory */
+static bool WriteIcon(const std::string& filename,
+                      std::vector<IconImage>& images) {
+    std::vector<uint8_t> file(kIconHeaderSize, 0);
+    WriteLe(file, 2, 2, 1);
+    WriteLe(file, 4, 2, static_cast<uint32_t>(images.size()));
+    std::size_t offset = kIconHeaderSize + images.size() * kIconEntrySize;
+    for (IconImage& image : images) {
+        WriteLe(image.entry, 8, 4, static_cast<uint32_t>(image.data.size()));
+        WriteLe(image.entry, 12, 4, static_cast<uint32_t>(offset));
+        file.insert(file.end(), image.entry.cbegin(), image.entry.cend());
+        offset += image.data.size();
+    }
+    for (const IconImage& image : images) {
+        file.insert(file.end(), image.data.cbegin(), image.data.cend());
+    }
+    std::ofstream ofs(filename, std::ofstream::binary);
+    ofs.write(reinterpret_cast<const char*>(file.data()),
+              static_cast<std::streamsize>(file.size()));
+    return static_cast<bool>(ofs);
+}
+
+static bool IsPng(const std::vector<uint8_t>& data) {
+    const std::vector<uint8_t> kSignature = {0x89, 'P',  'N',  'G',
+                                             '\r', '\n', 0x1A, '\n'};
+    return (data.size() >= kSignature.size()) &&
+           std::equal(kSignature.cbegin(), kSignature.cend(), data.cbegin());
+}
+
+static bool ReadDibLayout(const std::vector<uint8_t>& data, DibLayout& dib) {
+    if (data.size() < kInfoHeaderSize) {
+        return false;
+    }
+    const std::size_t kHeaderSize = ReadLe(data, 0, 4);
+    const int32_t kWidth = static_cast<int32_t>(ReadLe(data, 4, 4));
+    const int32_t kHeight = static_cast<int32_t>(ReadLe(data, 8, 4));
+    const uint32_t kCompression = ReadLe(data, 16, 4);
+
+    /* the height counts the transparency mask below the color rows too */
+    const int32_t kMaxSide = 65535;
+    if ((kHeaderSize < kInfoHeaderSize) || (kHeaderSize > data.size()) ||
+        (kWidth <= 0) || (kWidth > kMaxSide) || (kHeight < 2) ||
+        (kHeight / 2 > kMaxSide) || (kCompression != 0)) {
+        return false;
+    }
+    dib.width = static_cast<std::size_t>(kWidth);
+    dib.height = static_cast<std::size_t>(kHeight / 2);
+    dib.bits = ReadLe(data, 14, 2);
+    dib.colors = 0;
+    if ((dib.bits == 1) || (dib.bits == 4) || (dib.bits == 8)) {
+        dib.colors = ReadLe(data, 32, 4);
+        if ((dib.colors == 0) || (dib.colors > (1U << dib.bits))) {
+            dib.colors = 1U << dib.bits;
+        }
+    }
+    dib.palette = kHeaderSize;
+    dib.pixels = kHeaderSize + 4 * dib.colors;
+    dib.stride = (dib.width * dib.bits + 31) / 32 * 4;
+    return (dib.pixels <= data.size()) &&
+           (dib.stride * dib.height <= data.size() - dib.pixels);
+}
+
+/* the color table as RGB triples */
+static std::vector<uint8_t> DibPalette(const std::vector<uint8_t>& data,
+                                       const DibLayout& dib) {
+    std::vector<uint8_t> palette;
+    for (std::size_t i = 0; i < dib.colors; ++i) {
+        const std::size_t kEntry = dib.palette + 4 * i;
+        palette.push_back(data[kEntry + 2]);
+        palette.push_back(data[kEntry + 1]);
+        palette.push_back(data[kEntry]);
+    }
+    return palette;
+}
+
+/* one index per pixel in the order the rows are stored, high bits first */
+static std::vector<uint8_t> UnpackIndices(const std::vector<uint8_t>& data,
+                                          const DibLayout& dib) {
+    const unsigned kMask = (1U << dib.bits) - 1;
+    std::vector<uint8_t> indices;
+    indices.reserve(dib.width * dib.height);
+    for (std::size_t row = 0; row < dib.height; ++row) {
+        for (std::size_t col = 0; col < dib.width; ++col) {
+            const std::size_t kBit = col * dib.bits;
+            const std::size_t kShift = 8 - dib.bits - kBit % 8;
+            indices.push_back(static_cast<uint8_t>(
+                (data[dib.pixels + row * dib.stride + kBit / 8] >> kShift) &
+                kMask));
+        }
+    }
+    return indices;
+}
+
+static void PackIndices(const std::vector<uint8_t>& indices,
+                        const DibLayout& dib, std::vector<uint8_t>& data) {
+    const unsigned kMask = (1U << dib.bits) - 1;
+    for (std::size_t row = 0; row < dib.height; ++row) {
+        for (std::size_t col = 0; col < dib.width; ++col) {
+            const std::size_t kBit = col * dib.bits;
+            const std::size_t kShift = 8 - dib.bits - kBit % 8;
+            uint8_t& byte = data[dib.pixels + row * dib.stride + kBit / 8];
+            byte = static_cast<uint8_t>(
+                (byte & ~(kMask << kShift)) |
+                (indices[row * dib.width + col] << kShift));
+        }
+    }
+}
+
+/* a PNG image decoded to 8 bit RGBA or RGB */
+static bool DecodePng(const std::vector<uint8_t>& data, uint32_t format,
+                      std::size_t& width, std::size_t& height,
+                      std::vector<uint8_t>& pixels) {
+    png_image png{};
+    png.version = PNG_IMAGE_VERSION;
+    if (!png_image_begin_read_from_memory(&png, data.data(), data.size())) {
+        return false;
+    }
+    png.format = format;
+    pixels.resize(PNG_IMAGE_SIZE(png));
+    if (!png_image_finish_read(&png, nullptr, pixels.data(), 0, nullptr)) {
+        png_image_free(&png);
+        return false;
+    }
+    width = png.width;
+    height = png.height;
+    return true;
+}
+
+static bool EncodePng(const std::vector<uint8_t>& pixels, std::size_t width,
+                      std::size_t height, std::vector<uint8_t>& data) {
+    png_image png{};
+    png.version = PNG_IMAGE_VERSION;
+    png.width = static_cast<png_uint_32>(width);
+    png.height = static_cast<png_uint_32>(height);
+    png.format = PNG_FORMAT_RGBA;
+    png_alloc_size_t size = 0;
+    if (!png_image_write_to_memory(&png, nullptr, &size, 0, pixels.data(), 0,
+                                   nullptr)) {
+        return false;
+    }
+    data.resize(size);
+    if (!png_image_write_to_memory(&png, data.data(), &size, 0, pixels.data(),
+                                   0, nullptr)) {
+        return false;
+    }
+    data.resize(size);
+    return true;
+}
+
+/* the dimensions an image's own header gives */
+static bool ImageSize(const std::vector<uint8_t>& data, std::size_t& width,
+                      std::size_t& height) {
+    if (IsPng(data)) {
+        const std::size_t kIhdrEnd = 24;
+        if (data.size() < kIhdrEnd) {
+            return false;
+        }
+        width = ReadInt<uint32_t>(data, 16);
+        height = ReadInt<uint32_t>(data, 20);
+        return true;
+    }
+    DibLayout dib;
+    if (!ReadDibLayout(data, dib)) {
+        return false;
+    }
+    width = dib.width;
+    height = dib.height;
+    return true;
+}
+
+/* runs use over a carrier on the pixels of one image, what it changes is
+ * written back into the image's data when store is set */
+template <typename Use>
+static RetCode WithCarrier(std::vector<uint8_t>& data, bool store, Use use) {
+    if (IsPng(data)) {
+        std::size_t width = 0;
+        std::size_t height = 0;
+        std::vector<uint8_t> pixels;
+        if (!DecodePng(data, PNG_FORMAT_RGBA, width, height, pixels)) {
+            return RetCode::kInvalidFileFormat;
+        }
+        FramebufferCarrier carrier(pixels.data(), width, height, width * 4,
+                                   4);
+        RetCode rc = use(carrier);
+        if ((rc == RetCode::kSuccess) && store &&
+            !EncodePng(pixels, width, height, data)) {
+            return RetCode::kIoError;
+        }
+        return rc;
+    }
+    DibLayout dib;
+    if (!ReadDibLayout(data, dib)) {
+        return RetCode::kInvalidFileFormat;
+    }
+    if ((dib.bits == 24) || (dib.bits == 32)) {
+        FramebufferCarrier carrier(data.data() + dib.pixels, dib.width,
+                                   dib.height, dib.stride, dib.bits / 8);
+        return use(carrier);
+    }
+    if (dib.colors == 0) {
+        return RetCode::kUnsupportedFormat;
+    }
+    std::vector<uint8_t> indices = UnpackIndices(data, dib);
+    PaletteCarrier carrier(indices, DibPalette(data, dib), dib.width);
+    RetCode rc = use(carrier);
+    if ((rc == RetCode::kSuccess) && store) {
+        PackIndices(indices, dib, data);
+    }
+    return rc;
+}
+
+bool ReadIco(const std::string& filename, boost::gil::rgb8_image_t& image) {
+    std::vector<IconImage> icon;
+    if (!ReadIcon(filename, icon)) {
+        return false;
+    }
+    const IconImage* largest = nullptr;
+    std::size_t largest_pixels = 0;
+    for (const IconImage& candidate : icon) {
+        std::size_t width = 0;
+        std::size_t height = 0;
+        if (ImageSize(candidate.data, width, height) &&
+            (width * height > largest_pixels)) {
+            largest = &candidate;
+            largest_pixels = width * height;
+        }
+    }
+    if (largest == nullptr) {
+        return false;
+    }
+
+    std::size_t width = 0;
+    std::size_t height = 0;
+    std::vector<uint8_t> rgb;
+    DibLayout dib;
+    if (IsPng(largest->data)) {
+        if (!DecodePng(largest->data, PNG_FORMAT_RGB, width, height, rgb)) {
+            return false;
+        }
+    } else if (ReadDibLayout(largest->data, dib)) {
+        width = dib.width;
+        height = dib.height;
+        const std::vector<uint8_t>& data = largest->data;
+        std::vector<uint8_t> palette = DibPalette(data, dib);
+        std::vector<uint8_t> indices;
+        if (dib.colors != 0) {
+            indices = UnpackIndices(data, dib);
+        } else if ((dib.bits != 24) && (dib.bits != 32)) {
+            return false;
+        }
+        rgb.resize(width * height * 3);
+        for (std::size_t row = 0; row < height; ++row) {
+            /* stored bottom up */
+            uint8_t* out = rgb.data() + (height - 1 - row) * width * 3;
+            for (std::size_t col = 0; col < width; ++col, out += 3) {
+                if (dib.colors != 0) {
+                    const std::size_t kIndex = std::min<std::size_t>(
+                        indices[row * width + col], dib.colors - 1);
+                    std::copy_n(palette.cbegin() + 3 * kIndex, 3, out);
+                    continue;
+                }
+                const uint8_t* in =
+                    data.data() + dib.pixels + row * dib.stride +
+                    col * (dib.bits / 8);
+                out[0] = in[2];
+                out[1] = in[1];
+                out[2] = in[0];
+            }
+        }
+    } else {
+        return false;
+    }
+
+    image = boost::gil::rgb8_image_t(static_cast<std::ptrdiff_t>(width),
+                                     static_cast<std::ptrdiff_t>(height));
+    auto view = boost::gil::view(image);
+    for (std::size_t y = 0; y < height; ++y) {
+        for (std::size_t x = 0; x < width; ++x) {
+            for (int c = 0; c < 3; ++c) {
+                view(static_cast<std::ptrdiff_t>(x),
+                     static_cast<std::ptrdiff_t>(y))[c] =
+                    rgb[(y * width + x) * 3 + c];
+            }
+        }
+    }
+    return true;
+}
+
+bool ReadIcoHeader(const std::string& filename, uint64_t& width,
+                   uint64_t& height, uint64_t& pixel_bytes) {
+    std::vector<IconImage> icon;
+    if (!ReadIcon(filename, icon)) {
+        return false;
+    }
+    width = 0;
+    height = 0;
+    for (const IconImage& image : icon) {
+        std::size_t image_width = 0;
+        std::size_t image_height = 0;
+        if (!ImageSize(image.data, image_width, image_height)) {
+            return false;
+        }
+        if (image_width * image_height > width * height) {
+            width = image_width;
+            height = image_height;
+        }
+    }
+
+    /* PNG images are decoded to RGBA */
+    pixel_bytes = 4;
+    return true;
+}
+
+RetCode EmbedIco(const std::string& cover, const std::string& outfile,
+                 const std::vector<uint8_t>& payload,
+                 const EmbedConfig& config) {
+    RetCode rc = CheckImageLimits(cover, ImageType::kIco);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    std::vector<IconImage> icon;
+    if (!ReadIcon(cover, icon)) {
+        return RetCode::kInvalidFileFormat;
+    }
+    for (IconImage& image : icon) {
+        rc = WithCarrier(image.data, true, [&](Carrier& carrier) {
+            return EmbedPayload(carrier, payload, config);
+        });
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+    return WriteIcon(outfile, icon) ? RetCode::kSuccess : RetCode::kIoError;
+}
+
+RetCode ExtractIco(const std::string& image, std::vector<uint8_t>& payload,
+                   PayloadHeader& header, DamageReport& damage,
+                   const std::optional<StegKey>& key) {
+    RetCode rc = CheckImageLimits(image, ImageType::kIco);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    std::vector<IconImage> icon;
+    if (!ReadIcon(image, icon)) {
+        return RetCode::kInvalidFileFormat;
+    }
+    auto pixels = [](const IconImage& candidate) {
+        std::size_t width = 0;
+        std::size_t height = 0;
+        return ImageSize(candidate.data, width, height) ? width * height : 0;
+    };
+    std::stable_sort(icon.begin(), icon.end(),
+                     [&pixels](const IconImage& a, const IconImage& b) {
+                         return pixels(a) > pixels(b);
+                     });
+
+    /* keep the largest image's result unless a smaller one does better */
+    RetCode best = RetCode::kPayloadNotFound;
+    for (IconImage& candidate : icon) {
+        std::vector<uint8_t> extracted;
+        PayloadHeader extracted_header;
+        DamageReport extracted_damage;
+        rc = WithCarrier(candidate.data, false, [&](Carrier& carrier) {
+            return ExtractPayload(carrier, extracted, extracted_header,
+                                  extracted_damage, key);
+        });
+        const bool kFirst = (&candidate == &icon.front());
+        const bool kBetter =
+            (rc == RetCode::kSuccess) ||
+            ((rc == RetCode::kPayloadDamaged) &&
+             (best != RetCode::kPayloadDamaged));
+        if (kFirst || kBetter) {
+            best = rc;
+            payload.swap(extracted);
+            header = extracted_header;
+            damage = extracted_damage;
+        }
+        if (rc == RetCode::kSuccess) {
+            break;
+        }
+    }
+    return best;
+}
+
+}  // namespace steganography
@@ -0,0 +1,45 @@
+#include "utils/image_info.hpp"
+
+#include <boost/gil.hpp>
+#include <cmath>
+#include <cstdint>
+#include <string>
+
+#include "utils/image_io.hpp"
+#include "utils/payload.hpp"
+
+namespace steganography {
+
+/* the low bits of cover and random data are independent and uniform, so
+ * their squared difference averages (4^bits - 1) / 6 */
+static double ExpectedPsnr(int bits) {
+    const double kMse = (std::pow(4.0, bits) - 1.0) / 6.0;
+    return 10.0 * std::log10(255.0 * 255.0 / kMse);
+}
+
+RetCode GetImageInfo(const std::string& filename, ImageInfo& info) {
+    info = ImageInfo{};
+    ImageType type = GetImageType(filename);
+    boost::gil::rgb8_image_t image;
+    RetCode rc = LoadImage(filename, image);
+    if (rc == RetCode::kSuccess) {
+        rc = ReadImageLayout(filename, type, info.layout);
+    }
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    info.format = SniffImageFormat(filename).name;
+    info.payload_capacity = PayloadCapacity(image);
+
+    /* as decoded, a raw file's orientation may have turned it */
+    const uint64_t kChannels = static_cast<uint64_t>(image.width()) *
+                               static_cast<uint64_t>(image.height()) * 3;
+    for (int bits = 1; bits <= 8; ++bits) {
+        info.capacities.push_back(
+            {bits, kChannels * static_cast<uint64_t>(bits) / 8,
+             ExpectedPsnr(bits)});
+    }
+    return RetCode::kSuccess;
+}
+
+}  // namespace steganography
@@ -0,0 +1,845 @@
+#include "utils/image_io.hpp"
+
+#include <algorithm>
+#include <boost/gil.hpp>
+#include <boost/gil/extension/io/bmp.hpp>
+#ifdef STEG_WITH_JPEG
+#include <boost/gil/extension/io/jpeg.hpp>
+#endif
+#include <boost/gil/extension/io/png.hpp>
+#include <cctype>
+#include <cstddef>
+#include <cstdint>
+#include <filesystem>
+#include <fstream>
+#include <stdexcept>
+#include <string>
+#include <vector>
+
+#ifdef STEG_WITH_AVIF
+#include "utils/avif.hpp"
+#endif
+#ifdef STEG_WITH_GIF
+#include "utils/gif.hpp"
+#endif
+#ifdef STEG_WITH_HEIF
+#include "utils/heif.hpp"
+#endif
+#include "utils/icc.hpp"
+#include "utils/ico.hpp"
+#ifdef STEG_WITH_JXL
+#include "utils/jxl.hpp"
+#endif
+#include "utils/normalize.hpp"
+#include "utils/pnm.hpp"
+#ifdef STEG_WITH_RAW
+#include "utils/raw.hpp"
+#endif
+#ifdef STEG_WITH_TIFF
+#include "utils/tiff.hpp"
+#endif
+
+namespace steganography {
+
+/* the major and compatible brands of an ISO BMFF file's leading ftyp box,
+ * empty for any other file */
+static std::vector<std::string> FtypBrands(const std::string& filename) {
+    /* the box size and type, the major brand, a minor version, and then the
+     * compatible brands */
+    const std::size_t kBrandsOffset = 16;
+    const std::size_t kMaxBoxSize = 256;
+    std::ifstream ifs(filename, std::ifstream::binary);
+    std::vector<char> box(kMaxBoxSize, 0);
+    ifs.read(box.data(), static_cast<std::streamsize>(box.size()));
+    const std::size_t kRead = static_cast<std::size_t>(ifs.gcount());
+    if ((kRead < kBrandsOffset) || (std::string(&box[4], 4) != "ftyp")) {
+        return {};
+    }
+    std::size_t size = 0;
+    for (std::size_t i = 0; i < 4; ++i) {
+        size = (size << 8) | static_cast<uint8_t>(box[i]);
+    }
+    size = std::min(size, kRead);
+    std::vector<std::string> brands = {std::string(&box[8], 4)};
+    for (std::size_t offset = kBrandsOffset; offset + 4 <= size; offset += 4) {
+        brands.emplace_back(&box[offset], 4);
+    }
+    return brands;
+}
+
+static bool HasAnyBrand(const std::vector<std::string>& brands,
+                        const std::vector<std::string>& wanted) {
+    return std::any_of(brands.cbegin(), brands.cend(),
+                       [&wanted](const std::string& brand) {
+                           return std::find(wanted.cbegin(), wanted.cend(),
+                                            brand) != wanted.cend();
+                       });
+}
+
+static bool HasRawExtension(const std::string& filename) {
+    const std::vector<std::string> kExtensions = {".dng", ".nef", ".nrw",
+                                                  ".cr2", ".arw", ".pef",
+                                                  ".srw"};
+    std::string extension =
+        std::filesystem::path(filename).extension().string();
+    std::transform(extension.begin(), extension.end(), extension.begin(),
+                   [](unsigned char c) { return std::tolower(c); });
+    return std::find(kExtensions.cbegin(), kExtensions.cend(), extension) !=
+           kExtensions.cend();
+}
+
+/* most camera raw formats are TIFF containers, a CR2 marks itself after the
+ * header and the others carry a DNG version or the sub IFDs holding the
+ * sensor data in their first IFD, which plain TIFFs seldom have */
+static bool IsRawTiff(const std::string& filename) {
+    const uint16_t kSubIfdsTag = 0x014A;
+    const uint16_t kDngVersionTag = 0xC612;
+    const std::size_t kEntrySize = 12;
+    std::ifstream ifs(filename, std::ifstream::binary);
+    std::vector<char> header(10, 0);
+    ifs.read(header.data(), static_cast<std::streamsize>(header.size()));
+    if (ifs.gcount() < 8) {
+        return false;
+    }
+    if ((header[8] == 'C') && (header[9] == 'R')) {
+        return true;
+    }
+    const bool kLittleEndian = (header[0] == 'I');
+    auto value = [kLittleEndian](const char* bytes, std::size_t size) {
+        uint32_t v = 0;
+        for (std::size_t i = 0; i < size; ++i) {
+            const uint32_t kByte = static_cast<uint8_t>(bytes[i]);
+            v = kLittleEndian ? (v | (kByte << (8 * i))) : ((v << 8) | kByte);
+        }
+        return v;
+    };
+
+    ifs.clear();
+    ifs.seekg(value(&header[4], 4));
+    std::vector<char> count(2, 0);
+    ifs.read(count.data(), static_cast<std::streamsize>(count.size()));
+    if (!ifs) {
+        return false;
+    }
+    std::vector<char> entries(value(count.data(), 2) * kEntrySize, 0);
+    ifs.read(entries.data(), static_cast<std::streamsize>(entries.size()));
+    if (!ifs) {
+        return false;
+    }
+    for (std::size_t offset = 0; offset < entries.size();
+         offset += kEntrySize) {
+        const uint32_t kTag = value(&entries[offset], 2);
+        if ((kTag == kSubIfdsTag) || (kTag == kDngVersionTag)) {
+            return true;
+        }
+    }
+    return false;
+}
+
+ImageType GetImageType(const std::string& filename) {
+    /* read the first 8 bytes of the file */
+    const int kHeaderSize = 8;
+    std::ifstream ifs(filename, std::ifstream::binary);
+    if (!ifs.is_open()) {
+        return ImageType::kUnknown;
+    }
+    std::vector<char> buffer(kHeaderSize, 0);
+    ifs.read(&buffer[0], static_cast<int64_t>(buffer.size()));
+
+    /* construct an unsigned 64-bit word using the 8 bytes in buffer */
+    const int kByteShift = 8;
+    uint64_t word = 0;
+    for (const char& c : buffer) {
+        word = (word << kByteShift) | static_cast<uint8_t>(c);
+    }
+
+    /* check if the word matches a known image file type signature */
+    const uint64_t kPngSignature = 0x89504E470D0A1A0A;
+    if (word == kPngSignature) {
+        return ImageType::kPng;
+    }
+
+    /* BM, and P1 to P6 followed by whitespace */
+    const uint64_t kBmpMask = 0xFFFF000000000000;
+    const uint64_t kBmpSignature = 0x424D000000000000;
+    if ((word & kBmpMask) == kBmpSignature) {
+        return ImageType::kBmp;
+    }
+    /* reserved, type 1, and a count of images that cannot be 0 */
+    const uint64_t kIcoMask = 0xFFFFFFFF00000000;
+    const uint64_t kIcoSignature = 0x0000010000000000;
+    if (((word & kIcoMask) == kIcoSignature) && ((word >> 16) & 0xFFFF)) {
+        return ImageType::kIco;
+    }
+    const char kPnmKind = static_cast<char>((word >> 48) & 0xFF);
+    if (((word >> 56) == 'P') && (kPnmKind >= '1') && (kPnmKind <= '6') &&
+        std::isspace(static_cast<int>((word >> 40) & 0xFF))) {
+        return ImageType::kPnm;
+    }
+#ifdef STEG_WITH_JPEG
+    const uint64_t kJpegSignature = 0xFFD8000000000000;
+    if ((word & kJpegSignature) == kJpegSignature) {
+        return ImageType::kJpeg;
+    }
+#endif
+#ifdef STEG_WITH_GIF
+    /* GIF87a and GIF89a */
+    const uint64_t kGifMask = 0xFFFFFFFFFFFF0000;
+    const uint64_t kGif87a = 0x4749463837610000;
+    const uint64_t kGif89a = 0x4749463839610000;
+    if (((word & kGifMask) == kGif87a) || ((word & kGifMask) == kGif89a)) {
+        return ImageType::kGif;
+    }
+#endif
+#if defined(STEG_WITH_TIFF) || defined(STEG_WITH_RAW)
+    /* little and big endian byte order marks followed by 42 */
+    const uint64_t kTiffMask = 0xFFFFFFFF00000000;
+    const uint64_t kTiffLittleEndian = 0x49492A0000000000;
+    const uint64_t kTiffBigEndian = 0x4D4D002A00000000;
+    const bool kTiffMagic = ((word & kTiffMask) == kTiffLittleEndian) ||
+                            ((word & kTiffMask) == kTiffBigEndian);
+#endif
+#ifdef STEG_WITH_RAW
+    /* the extension settles a raw file the tags leave in doubt */
+    if (kTiffMagic && (IsRawTiff(filename) || HasRawExtension(filename))) {
+        return ImageType::kRaw;
+    }
+#endif
+#ifdef STEG_WITH_TIFF
+    if (kTiffMagic) {
+        return ImageType::kTiff;
+    }
+#endif
+#ifdef STEG_WITH_JXL
+    /* a bare codestream or the start of the ISO BMFF container's signature
+     * box */
+    const uint64_t kJxlCodestreamMask = 0xFFFF000000000000;
+    const uint64_t kJxlCodestream = 0xFF0A000000000000;
+    const uint64_t kJxlContainer = 0x0000000C4A584C20;
+    if (((word & kJxlCodestreamMask) == kJxlCodestream) ||
+        (word == kJxlContainer)) {
+        return ImageType::kJxl;
+    }
+#endif
+#if defined(STEG_WITH_AVIF) || defined(STEG_WITH_HEIF)
+    const std::vector<std::string> kBrands = FtypBrands(filename);
+#endif
+#ifdef STEG_WITH_AVIF
+    if (HasAnyBrand(kBrands, {"avif", "avis"})) {
+        return ImageType::kAvif;
+    }
+#endif
+#ifdef STEG_WITH_HEIF
+    /* the HEVC coded brands, mif1 alone is shared with AVIF */
+    if (HasAnyBrand(kBrands,
+                    {"heic", "heix", "heim", "heis", "hevc", "hevx"})) {
+        return ImageType::kHeif;
+    }
+#endif
+    return ImageType::kUnknown;
+}
+
+std::string AcceptedFormats() {
+    std::vector<std::string> formats;
+#ifdef STEG_WITH_JPEG
+    formats.push_back("JPEG");
+#endif
+    formats.push_back("PNG");
+    formats.push_back("BMP");
+    formats.push_back("PNM");
+    formats.push_back("ICO");
+#ifdef STEG_WITH_TIFF
+    formats.push_back("TIFF");
+#endif
+#ifdef STEG_WITH_AVIF
+    formats.push_back("AVIF");
+#endif
+#ifdef STEG_WITH_HEIF
+    formats.push_back("HEIC");
+#endif
+#ifdef STEG_WITH_RAW
+    formats.push_back("camera raw");
+#endif
+#ifdef STEG_WITH_JXL
+    formats.push_back("JPEG XL");
+#endif
+#ifdef STEG_WITH_GIF
+    formats.push_back("GIF");
+#endif
+    if (formats.size() == 1) {
+        return formats[0];
+    }
+    if (formats.size() == 2) {
+        return formats[0] + " and " + formats[1];
+    }
+    std::string list;
+    for (std::size_t i = 0; i + 1 < formats.size(); ++i) {
+        list += formats[i] + ", ";
+    }
+    return list + "and " + formats.back();
+}
+
+SniffedFormat SniffImageFormat(const std::string& filename) {
+    const std::size_t kHeaderSize = 16;
+    std::ifstream ifs(filename, std::ifstream::binary);
+    if (!ifs.is_open()) {
+        return {};
+    }
+    std::vector<char> buffer(kHeaderSize, 0);
+    ifs.read(buffer.data(), static_cast<std::streamsize>(buffer.size()));
+    const std::size_t kRead = static_cast<std::size_t>(ifs.gcount());
+    if (kRead == 0) {
+        return {"an empty file", ""};
+    }
+    const std::string kHeader(buffer.data(), kRead);
+    auto starts_with = [&kHeader](const std::string& magic,
+                                  std::size_t offset = 0) {
+        return (offset + magic.size() <= kHeader.size()) &&
+               (kHeader.compare(offset, magic.size(), magic) == 0);
+    };
+
+    if (starts_with("\x89PNG\r\n\x1a\n")) {
+        return {"PNG", ""};
+    }
+    if (starts_with("\xff\xd8\xff")) {
+        return {"JPEG", "STEG_WITH_JPEG"};
+    }
+    if (starts_with("GIF87a") || starts_with("GIF89a")) {
+        return {"GIF", "STEG_WITH_GIF"};
+    }
+    if (starts_with(std::string("II*\0", 4)) ||
+        starts_with(std::string("MM\0*", 4))) {
+        return IsRawTiff(filename) || HasRawExtension(filename)
+                   ? SniffedFormat{"camera raw", "STEG_WITH_RAW"}
+                   : SniffedFormat{"TIFF", "STEG_WITH_TIFF"};
+    }
+    if (starts_with(std::string("\xff\x0a", 2)) ||
+        starts_with(std::string("\0\0\0\x0cJXL ", 8))) {
+        return {"JPEG XL", "STEG_WITH_JXL"};
+    }
+    const std::vector<std::string> kBrands = FtypBrands(filename);
+    if (HasAnyBrand(kBrands, {"avif", "avis"})) {
+        return {"AVIF", "STEG_WITH_AVIF"};
+    }
+    if (HasAnyBrand(kBrands,
+                    {"heic", "heix", "heim", "heis", "hevc", "hevx"})) {
+        return {"HEIC", "STEG_WITH_HEIF"};
+    }
+    if (starts_with("\x76\x2f\x31\x01")) {
+        return {"OpenEXR", "STEG_WITH_EXR"};
+    }
+    if (starts_with("RIFF") && starts_with("WEBP", 8)) {
+        return {"WebP", ""};
+    }
+    if (starts_with(std::string("\0\0\0\x0cjP  ", 8)) ||
+        starts_with(std::string("\xff\x4f\xff\x51", 4))) {
+        return {"JPEG 2000", ""};
+    }
+    if (starts_with("8BPS")) {
+        return {"Photoshop", ""};
+    }
+    if (starts_with("qoif")) {
+        return {"QOI", ""};
+    }
+    if (starts_with("%PDF-")) {
+        return {"PDF", ""};
+    }
+    if (starts_with(std::string("PK\x03\x04", 4))) {
+        return {"ZIP", ""};
+    }
+    /* BMP, ICO, and PNM have the weakest signatures, so they come last */
+    switch (GetImageType(filename)) {
+        case ImageType::kBmp:
+            return {"BMP", ""};
+        case ImageType::kPnm:
+            return {"PNM", ""};
+        case ImageType::kIco:
+            return {"ICO", ""};
+        default:
+            break;
+    }
+    if (starts_with("<svg") || starts_with("<?xml")) {
+        return {"SVG", "STEG_WITH_SVG"};
+    }
+    return {};
+}
+
+boost::gil::rgb8_image_t ReadImage(const std::string& filename,
+                                   [[maybe_unused]] ImageType type) {
+    boost::gil::rgb8_image_t image;
+#ifdef STEG_WITH_AVIF
+    if (type == ImageType::kAvif) {
+        if (!ReadAvif(filename, image)) {
+            throw std::runtime_error("failed to read " + filename);
+        }
+        return image;
+    }
+#endif
+#ifdef STEG_WITH_GIF
+    if (type == ImageType::kGif) {
+        if (!ReadGif(filename, image)) {
+            throw std::runtime_error("failed to read " + filename);
+        }
+        return image;
+    }
+#endif
+#ifdef STEG_WITH_HEIF
+    if (type == ImageType::kHeif) {
+        if (!ReadHeif(filename, image)) {
+            throw std::runtime_error("failed to read " + filename);
+        }
+        return image;
+    }
+#endif
+#ifdef STEG_WITH_JXL
+    if (type == ImageType::kJxl) {
+        if (!ReadJxl(filename, image)) {
+            throw std::runtime_error("failed to read " + filename);
+        }
+        return image;
+    }
+#endif
+#ifdef STEG_WITH_RAW
+    if (type == ImageType::kRaw) {
+        if (!ReadRaw(filename, image)) {
+            throw std::runtime_error("failed to read " + filename);
+        }
+        return image;
+    }
+#endif
+#ifdef STEG_WITH_TIFF
+    if (type == ImageType::kTiff) {
+        /* fail the way the GIL readers do */
+        if (!ReadTiff(filename, image)) {
+            throw std::runtime_error("failed to read " + filename);
+        }
+        return image;
+    }
+#endif
+    if (type == ImageType::kBmp) {
+        boost::gil::read_and_convert_image(filename, image,
+                                           boost::gil::bmp_tag{});
+        return image;
+    }
+    if (type == ImageType::kIco) {
+        if (!ReadIco(filename, image)) {
+            throw std::runtime_error("failed to read " + filename);
+        }
+        return image;
+    }
+    if (type == ImageType::kPnm) {
+        if (!ReadPnm(filename, image)) {
+            throw std::runtime_error("failed to read " + filename);
+        }
+        return image;
+    }
+    /* JPEGs and PNGs go through the default conversions, see
+     * ReadNormalizedImage */
+    std::vector<InputWarning> warnings;
+    if (ReadNormalizedImage(filename,
+                            (type == ImageType::kJpeg) ? type : ImageType::kPng,
+                            InputPolicy{}, image,
+                            warnings) != RetCode::kSuccess) {
+        throw std::runtime_error("failed to read " + filename);
+    }
+    return image;
+}
+
+void WriteImage(const boost::gil::rgb8_image_t& image,
+                const std::string& filename, ImageType type,
+                const std::vector<uint8_t>& icc_profile) {
+    if (type == ImageType::kBmp) {
+        boost::gil::write_view(filename, boost::gil::const_view(image),
+                               boost::gil::bmp_tag{});
+        return;
+    }
+    if (type == ImageType::kPnm) {
+        if (!WritePnm(image, filename)) {
+            throw std::runtime_error("failed to write " + filename);
+        }
+        return;
+    }
+#ifdef STEG_WITH_JXL
+    if (type == ImageType::kJxl) {
+        if (!WriteJxl(image, filename, icc_profile)) {
+            throw std::runtime_error("failed to write " + filename);
+        }
+        return;
+    }
+#endif
+#ifdef STEG_WITH_JPEG
+    if (type == ImageType::kJpeg) {
+        boost::gil::write_view(filename, boost::gil::const_view(image),
+                               boost::gil::jpeg_tag{});
+        return;
+    }
+#endif
+    /* without JPEG support a JPEG name gets a PNG, as data-carrying outputs
+     * already do */
+    boost::gil::write_view(filename, boost::gil::const_view(image),
+                           boost::gil::png_tag{});
+    if (!icc_profile.empty() && !AttachIccProfile(filename, icc_profile)) {
+        throw std::runtime_error("failed to write " + filename);
+    }
+}
+
+bool HasJpegExtension(const std::string& filename) {
+    const std::vector<std::string> kExtensions = {".jpg", ".jpeg", ".JPG",
+                                                  ".JPEG"};
+    return std::any_of(
+        kExtensions.cbegin(), kExtensions.cend(),
+        [&filename](const std::string& s) { return filename.ends_with(s); });
+}
+
+static bool HasAnyExtension(const std::string& filename,
+                            const std::vector<std::string>& extensions) {
+    std::string lower = filename;
+    std::transform(lower.begin(), lower.end(), lower.begin(),
+                   [](unsigned char c) { return std::tolower(c); });
+    return std::any_of(
+        extensions.cbegin(), extensions.cend(),
+        [&lower](const std::string& s) { return lower.ends_with(s); });
+}
+
+ImageType LosslessImageType(const std::string& filename) {
+    if (HasAnyExtension(filename, {".bmp"})) {
+        return ImageType::kBmp;
+    }
+    if (HasAnyExtension(filename, {".pbm", ".pgm", ".ppm", ".pnm"})) {
+        return ImageType::kPnm;
+    }
+#ifdef STEG_WITH_JXL
+    if (filename.ends_with(".jxl") || filename.ends_with(".JXL")) {
+        return ImageType::kJxl;
+    }
+#endif
+    return ImageType::kPng;
+}
+
+/* a bitmap or graymap keeps one value per pixel, hidden data needs three */
+static RetCode CheckPnmOutput(const std::string& outfile, ImageType type,
+                              bool carries_data) {
+    return (carries_data && (type == ImageType::kPnm) &&
+            (PnmKind(outfile) != '6'))
+               ? RetCode::kInvalidArgument
+               : RetCode::kSuccess;
+}
+
+RetCode ResolveOutputType(OutputFormat format, const std::string& outfile,
+                          ImageType cover_type, bool carries_data,
+                          ImageType& type) {
+    switch (format) {
+        case OutputFormat::kAuto:
+#ifdef STEG_WITH_JPEG
+            type = (!carries_data && HasJpegExtension(outfile))
+                       ? ImageType::kJpeg
+                       : LosslessImageType(outfile);
+#else
+            type = LosslessImageType(outfile);
+#endif
+            return CheckPnmOutput(outfile, type, carries_data);
+        case OutputFormat::kPng:
+            type = ImageType::kPng;
+            return RetCode::kSuccess;
+        case OutputFormat::kJpeg:
+#ifdef STEG_WITH_JPEG
+            /* compression would destroy the hidden data */
+            type = ImageType::kJpeg;
+            return carries_data ? RetCode::kInvalidArgument
+                                : RetCode::kSuccess;
+#else
+            return RetCode::kInvalidArgument;
+#endif
+        case OutputFormat::kJxl:
+#ifdef STEG_WITH_JXL
+            type = ImageType::kJxl;
+            return RetCode::kSuccess;
+#else
+            return RetCode::kInvalidArgument;
+#endif
+        case OutputFormat::kSameAsCover:
+            if ((cover_type == ImageType::kJpeg) && !carries_data) {
+                type = ImageType::kJpeg;
+            } else if ((cover_type == ImageType::kJxl) ||
+                       (cover_type == ImageType::kBmp) ||
+                       (cover_type == ImageType::kPnm)) {
+                type = cover_type;
+            } else {
+                type = ImageType::kPng;
+            }
+            return CheckPnmOutput(outfile, type, carries_data);
+    }
+    return RetCode::kInvalidArgument;
+}
+
+static ImageLimits image_limits;
+
+void SetImageLimits(const ImageLimits& limits) { image_limits = limits; }
+
+const ImageLimits& GetImageLimits() { return image_limits; }
+
+/* the dimensions and decoded bytes per pixel from a PNG's IHDR chunk */
+static bool ReadPngHeader(std::ifstream& ifs, uint64_t& width,
+                          uint64_t& height, uint64_t& pixel_bytes) {
+    std::vector<uint8_t> header(26, 0);
+    ifs.read(reinterpret_cast<char*>(header.data()),
+             static_cast<std::streamsize>(header.size()));
+    if (!ifs || !std::equal(header.cbegin() + 12, header.cbegin() + 16,
+                            std::string("IHDR").cbegin())) {
+        return false;
+    }
+    auto read_u32 = [&header](std::size_t offset) {
+        return (static_cast<uint64_t>(header[offset]) << 24) |
+               (static_cast<uint64_t>(header[offset + 1]) << 16) |
+               (static_cast<uint64_t>(header[offset + 2]) << 8) |
+               static_cast<uint64_t>(header[offset + 3]);
+    };
+    width = read_u32(16);
+    height = read_u32(20);
+
+    /* gray, unused, RGB, palette expanded to RGB, gray and alpha, RGBA */
+    const std::vector<uint64_t> kChannels = {1, 0, 3, 3, 2, 0, 4};
+    const uint64_t kBitDepth = header[24];
+    const std::size_t kColorType = header[25];
+    if ((kColorType >= kChannels.size()) || (kChannels[kColorType] == 0)) {
+        return false;
+    }
+    pixel_bytes = kChannels[kColorType] * ((kBitDepth > 8) ? 2 : 1);
+    return true;
+}
+
+/* the dimensions and decoded bytes per pixel from a BMP's info header, rows
+ * stored top down give a negative height */
+static bool ReadBmpHeader(std::ifstream& ifs, uint64_t& width,
+                          uint64_t& height, uint64_t& pixel_bytes) {
+    std::vector<uint8_t> header(30, 0);
+    ifs.read(reinterpret_cast<char*>(header.data()),
+             static_cast<std::streamsize>(header.size()));
+    if (!ifs) {
+        return false;
+    }
+    auto read_i32 = [&header](std::size_t offset) {
+        return static_cast<int32_t>(
+            static_cast<uint32_t>(header[offset]) |
+            (static_cast<uint32_t>(header[offset + 1]) << 8) |
+            (static_cast<uint32_t>(header[offset + 2]) << 16) |
+            (static_cast<uint32_t>(header[offset + 3]) << 24));
+    };
+    int64_t signed_width = read_i32(18);
+    int64_t signed_height = read_i32(22);
+    width = static_cast<uint64_t>(signed_width < 0 ? -signed_width
+                                                   : signed_width);
+    height = static_cast<uint64_t>(signed_height < 0 ? -signed_height
+                                                     : signed_height);
+    const uint64_t kBitsPerPixel = header[28] | (header[29] << 8);
+    pixel_bytes = std::max<uint64_t>(kBitsPerPixel / 8, 1);
+    return true;
+}
+
+#ifdef STEG_WITH_JPEG
+/* the dimensions and decoded bytes per pixel from a JPEG's start of frame
+ * segment, found by skipping over the segments before it */
+static bool ReadJpegHeader(std::ifstream& ifs, uint64_t& width,
+                           uint64_t& height, uint64_t& pixel_bytes) {
+    ifs.seekg(2);
+    while (ifs) {
+        int byte = ifs.get();
+        if (byte != 0xFF) {
+            return false;
+        }
+        int marker = ifs.get();
+        while (marker == 0xFF) { /* fill bytes */
+            marker = ifs.get();
+        }
+        if ((marker == 0x01) || ((marker >= 0xD0) && (marker <= 0xD8))) {
+            continue; /* markers without a segment */
+        }
+        uint64_t length = static_cast<uint64_t>(ifs.get()) << 8;
+        length |= static_cast<uint64_t>(ifs.get());
+        if (!ifs || (length < 2)) {
+            return false;
+        }
+        const bool kStartOfFrame = (marker >= 0xC0) && (marker <= 0xCF) &&
+                                   (marker != 0xC4) && (marker != 0xC8) &&
+                                   (marker != 0xCC);
+        if (!kStartOfFrame) {
+            ifs.seekg(static_cast<std::streamoff>(length - 2), std::ios::cur);
+            continue;
+        }
+        std::vector<uint8_t> frame(6, 0);
+        ifs.read(reinterpret_cast<char*>(frame.data()),
+                 static_cast<std::streamsize>(frame.size()));
+        if (!ifs) {
+            return false;
+        }
+        height = (static_cast<uint64_t>(frame[1]) << 8) | frame[2];
+        width = (static_cast<uint64_t>(frame[3]) << 8) | frame[4];
+        pixel_bytes = frame[5] * ((frame[0] > 8) ? 2ULL : 1ULL);
+        return true;
+    }
+    return false;
+}
+#endif
+
+/* the dimensions and decoded bytes per pixel from the file's header */
+static bool ReadImageHeader(const std::string& filename, ImageType type,
+                            uint64_t& width, uint64_t& height,
+                            uint64_t& pixel_bytes) {
+    std::ifstream ifs(filename, std::ifstream::binary);
+    bool read = false;
+    if (type == ImageType::kPng) {
+        read = ReadPngHeader(ifs, width, height, pixel_bytes);
+    }
+#ifdef STEG_WITH_JPEG
+    if (type == ImageType::kJpeg) {
+        read = ReadJpegHeader(ifs, width, height, pixel_bytes);
+    }
+#endif
+    if (type == ImageType::kBmp) {
+        read = ReadBmpHeader(ifs, width, height, pixel_bytes);
+    } else if (type == ImageType::kIco) {
+        read = ReadIcoHeader(filename, width, height, pixel_bytes);
+    } else if (type == ImageType::kPnm) {
+        read = ReadPnmHeader(filename, width, height, pixel_bytes);
+    }
+#ifdef STEG_WITH_AVIF
+    if (type == ImageType::kAvif) {
+        read = ReadAvifHeader(filename, width, height, pixel_bytes);
+    }
+#endif
+#ifdef STEG_WITH_GIF
+    if (type == ImageType::kGif) {
+        read = ReadGifHeader(filename, width, height, pixel_bytes);
+    }
+#endif
+#ifdef STEG_WITH_HEIF
+    if (type == ImageType::kHeif) {
+        read = ReadHeifHeader(filename, width, height, pixel_bytes);
+    }
+#endif
+#ifdef STEG_WITH_JXL
+    if (type == ImageType::kJxl) {
+        read = ReadJxlHeader(filename, width, height, pixel_bytes);
+    }
+#endif
+#ifdef STEG_WITH_RAW
+    if (type == ImageType::kRaw) {
+        read = ReadRawHeader(filename, width, height, pixel_bytes);
+    }
+#endif
+#ifdef STEG_WITH_TIFF
+    if (type == ImageType::kTiff) {
+        read = ReadTiffHeader(filename, width, height, pixel_bytes);
+    }
+#endif
+    return read;
+}
+
+/* lhs * rhs, or the largest uint64_t when that overflows so an image whose
+ * size does not fit is too large for any limit */
+static uint64_t SaturatingMultiply(uint64_t lhs, uint64_t rhs) {
+    if ((rhs != 0) && (lhs > UINT64_MAX / rhs)) {
+        return UINT64_MAX;
+    }
+    return lhs * rhs;
+}
+
+RetCode DecodedImageBytes(const std::string& filename, ImageType type,
+                          uint64_t& bytes) {
+    uint64_t width = 0;
+    uint64_t height = 0;
+    uint64_t pixel_bytes = 0;
+    if (!ReadImageHeader(filename, type, width, height, pixel_bytes)) {
+        return RetCode::kInvalidFileFormat;
+    }
+
+    /* a header may claim dimensions whose byte count wraps, it saturates */
+    bytes = SaturatingMultiply(SaturatingMultiply(width, height),
+                               std::max<uint64_t>(pixel_bytes, 3));
+    return RetCode::kSuccess;
+}
+
+RetCode ReadImageLayout(const std::string& filename, ImageType type,
+                        ImageLayout& layout) {
+    uint64_t pixel_bytes = 0;
+    if (!ReadImageHeader(filename, type, layout.width, layout.height,
+                         pixel_bytes)) {
+        return RetCode::kInvalidFileFormat;
+    }
+
+    /* most headers only give the bytes a pixel decodes to, from which the
+     * usual layout of that size is assumed */
+    const std::vector<std::string> kColorTypes = {"gray", "gray+alpha", "RGB",
+                                                  "RGBA"};
+    const uint64_t kSamples = (pixel_bytes > 4) ? pixel_bytes / 2 : pixel_bytes;
+    layout.color_type = kColorTypes[std::clamp<uint64_t>(kSamples, 1, 4) - 1];
+    layout.bit_depth = (pixel_bytes > 4) ? 16 : 8;
+    layout.interlaced = false;
+    if (type == ImageType::kPng) {
+        /* the IHDR chunk's bit depth, color type, and interlace method */
+        const std::vector<std::string> kPngColorTypes = {
+            "gray", "", "RGB", "palette", "gray+alpha", "", "RGBA"};
+        std::ifstream ifs(filename, std::ifstream::binary);
+        std::vector<uint8_t> header(29, 0);
+        ifs.read(reinterpret_cast<char*>(header.data()),
+                 static_cast<std::streamsize>(header.size()));
+        if (!ifs || (header[25] >= kPngColorTypes.size())) {
+            return RetCode::kInvalidFileFormat;
+        }
+        layout.color_type = kPngColorTypes[header[25]];
+        layout.bit_depth = header[24];
+        layout.interlaced = (header[28] != 0);
+    } else if (type == ImageType::kJpeg) {
+        /* one, three, or four components of 8 or 12 bits */
+        const bool kDeep = (pixel_bytes % 2 == 0) && (pixel_bytes != 4);
+        const uint64_t kComponents = kDeep ? pixel_bytes / 2 : pixel_bytes;
+        layout.color_type = (kComponents == 1)   ? "gray"
+                            : (kComponents == 4) ? "CMYK"
+                                                 : "RGB";
+        layout.bit_depth = kDeep ? 12 : 8;
+    } else if ((type == ImageType::kBmp) && (pixel_bytes == 1)) {
+        layout.color_type = "palette";
+    }
+    return RetCode::kSuccess;
+}
+
+RetCode CheckImageLimits(const std::string& filename, ImageType type) {
+    uint64_t width = 0;
+    uint64_t height = 0;
+    uint64_t pixel_bytes = 0;
+    if (!ReadImageHeader(filename, type, width, height, pixel_bytes)) {
+        return RetCode::kInvalidFileFormat;
+    }
+
+    /* saturated as in DecodedImageBytes, so a size that wraps stays over
+     * max_decoded_bytes even with max_pixels disabled */
+    const uint64_t kPixels = SaturatingMultiply(width, height);
+    const uint64_t kDecodedBytes =
+        SaturatingMultiply(kPixels, std::max<uint64_t>(pixel_bytes, 3));
+    if (((image_limits.max_pixels != 0) &&
+         (kPixels > image_limits.max_pixels)) ||
+        ((image_limits.max_decoded_bytes != 0) &&
+         (kDecodedBytes > image_limits.max_decoded_bytes))) {
+        return RetCode::kImageTooLarge;
+    }
+    return RetCode::kSuccess;
+}
+
+RetCode LoadImage(const std::string& filename,
+                  boost::gil::rgb8_image_t& image) {
+    if (!std::filesystem::exists(filename)) {
+        return RetCode::kFileNotFound;
+    }
+    ImageType type(GetImageType(filename));
+    if (type == ImageType::kUnknown) {
+        return RetCode::kInvalidFileFormat;
+    }
+    RetCode rc = CheckImageLimits(filename, type);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    image = ReadImage(filename, type);
+    return RetCode::kSuccess;
+}
+
+}  // namespace steganography
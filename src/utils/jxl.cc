@@ -0,0 +1,197 @@
+#include "utils/jxl.hpp"
+
+#include <jxl/decode_cxx.h>
+#include <jxl/encode_cxx.h>
+
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <fstream>
+#include <iterator>
+#include <string>
+#include <vector>
+
+namespace steganography {
+
+static bool ReadFile(const std::string& filename, std::vector<uint8_t>& data) {
+    std::ifstream ifs(filename, std::ifstream::binary);
+    if (!ifs.is_open()) {
+        return false;
+    }
+    data.assign(std::istreambuf_iterator<char>(ifs),
+                std::istreambuf_iterator<char>());
+    return true;
+}
+
+/* a decoder over the whole file reporting the given events */
+static JxlDecoderPtr MakeDecoder(const std::vector<uint8_t>& data,
+                                 int events) {
+    JxlDecoderPtr decoder = JxlDecoderMake(nullptr);
+    if (!decoder ||
+        (JxlDecoderSubscribeEvents(decoder.get(), events) !=
+         JXL_DEC_SUCCESS) ||
+        (JxlDecoderSetInput(decoder.get(), data.data(), data.size()) !=
+         JXL_DEC_SUCCESS)) {
+        return nullptr;
+    }
+    JxlDecoderCloseInput(decoder.get());
+    return decoder;
+}
+
+bool ReadJxl(const std::string& filename, boost::gil::rgb8_image_t& image) {
+    std::vector<uint8_t> data;
+    if (!ReadFile(filename, data)) {
+        return false;
+    }
+    JxlDecoderPtr decoder =
+        MakeDecoder(data, JXL_DEC_BASIC_INFO | JXL_DEC_FULL_IMAGE);
+    if (!decoder) {
+        return false;
+    }
+
+    /* gray images are decoded as one channel and spread over all three */
+    JxlBasicInfo info;
+    JxlPixelFormat format = {3, JXL_TYPE_UINT8, JXL_NATIVE_ENDIAN, 0};
+    std::vector<uint8_t> pixels;
+    while (true) {
+        JxlDecoderStatus status = JxlDecoderProcessInput(decoder.get());
+        if (status == JXL_DEC_BASIC_INFO) {
+            if (JxlDecoderGetBasicInfo(decoder.get(), &info) !=
+                JXL_DEC_SUCCESS) {
+                return false;
+            }
+            format.num_channels = (info.num_color_channels == 1) ? 1 : 3;
+        } else if (status == JXL_DEC_NEED_IMAGE_OUT_BUFFER) {
+            std::size_t size = 0;
+            if (JxlDecoderImageOutBufferSize(decoder.get(), &format, &size) !=
+                JXL_DEC_SUCCESS) {
+                return false;
+            }
+            pixels.resize(size);
+            if (JxlDecoderSetImageOutBuffer(decoder.get(), &format,
+                                            pixels.data(), pixels.size()) !=
+                JXL_DEC_SUCCESS) {
+                return false;
+            }
+        } else if (status == JXL_DEC_FULL_IMAGE) {
+            break; /* only the first frame of an animation is used */
+        } else {
+            return false;
+        }
+    }
+
+    const std::size_t kChannels = format.num_channels;
+    image = boost::gil::rgb8_image_t(info.xsize, info.ysize);
+    auto view = boost::gil::view(image);
+    for (uint32_t y = 0; y < info.ysize; ++y) {
+        for (uint32_t x = 0; x < info.xsize; ++x) {
+            const std::size_t kOffset =
+                (static_cast<std::size_t>(y) * info.xsize + x) * kChannels;
+            const std::size_t kStep = (kChannels == 1) ? 0 : 1;
+            view(x, y) = boost::gil::rgb8_pixel_t(
+                pixels[kOffset], pixels[kOffset + kStep],
+                pixels[kOffset + kStep * 2]);
+        }
+    }
+    return true;
+}
+
+bool ReadJxlHeader(const std::string& filename, uint64_t& width,
+                   uint64_t& height, uint64_t& pixel_bytes) {
+    std::vector<uint8_t> data;
+    if (!ReadFile(filename, data)) {
+        return false;
+    }
+    JxlDecoderPtr decoder = MakeDecoder(data, JXL_DEC_BASIC_INFO);
+    JxlBasicInfo info;
+    if (!decoder ||
+        (JxlDecoderProcessInput(decoder.get()) != JXL_DEC_BASIC_INFO) ||
+        (JxlDecoderGetBasicInfo(decoder.get(), &info) != JXL_DEC_SUCCESS)) {
+        return false;
+    }
+    width = info.xsize;
+    height = info.ysize;
+
+    /* libjxl decodes every channel into 32 bit float planes */
+    pixel_bytes = static_cast<uint64_t>(info.num_color_channels +
+                                        info.num_extra_channels) *
+                  4;
+    return true;
+}
+
+bool WriteJxl(const boost::gil::rgb8_image_t& image,
+              const std::string& filename,
+              const std::vector<uint8_t>& icc_profile) {
+    auto view = boost::gil::const_view(image);
+    std::vector<uint8_t> pixels;
+    pixels.reserve(static_cast<std::size_t>(view.width()) * view.height() *
+                   3);
+    for (std::ptrdiff_t y = 0; y < view.height(); ++y) {
+        for (std::ptrdiff_t x = 0; x < view.width(); ++x) {
+            for (int i = 0; i < 3; ++i) {
+                pixels.push_back(view(x, y)[i]);
+            }
+        }
+    }
+
+    /* lossless needs the original color space kept rather than XYB */
+    JxlEncoderPtr encoder = JxlEncoderMake(nullptr);
+    JxlBasicInfo info;
+    JxlEncoderInitBasicInfo(&info);
+    info.xsize = static_cast<uint32_t>(view.width());
+    info.ysize = static_cast<uint32_t>(view.height());
+    info.bits_per_sample = 8;
+    info.num_color_channels = 3;
+    info.uses_original_profile = JXL_TRUE;
+    if (!encoder ||
+        (JxlEncoderSetBasicInfo(encoder.get(), &info) != JXL_ENC_SUCCESS)) {
+        return false;
+    }
+    if (icc_profile.empty()) {
+        JxlColorEncoding color;
+        JxlColorEncodingSetToSRGB(&color, JXL_FALSE);
+        if (JxlEncoderSetColorEncoding(encoder.get(), &color) !=
+            JXL_ENC_SUCCESS) {
+            return false;
+        }
+    } else if (JxlEncoderSetICCProfile(encoder.get(), icc_profile.data(),
+                                       icc_profile.size()) !=
+               JXL_ENC_SUCCESS) {
+        return false;
+    }
+    JxlEncoderFrameSettings* settings =
+        JxlEncoderFrameSettingsCreate(encoder.get(), nullptr);
+    const JxlPixelFormat kFormat = {3, JXL_TYPE_UINT8, JXL_NATIVE_ENDIAN, 0};
+    if ((JxlEncoderSetFrameLossless(settings, JXL_TRUE) != JXL_ENC_SUCCESS) ||
+        (JxlEncoderAddImageFrame(settings, &kFormat, pixels.data(),
+                                 pixels.size()) != JXL_ENC_SUCCESS)) {
+        return false;
+    }
+    JxlEncoderCloseInput(encoder.get());
+
+    /* grow the output until the encoder has everything written out */
+    std::vector<uint8_t> compressed(64 * 1024);
+    uint8_t* next = compressed.data();
+    std::size_t available = compressed.size();
+    JxlEncoderStatus status = JXL_ENC_NEED_MORE_OUTPUT;
+    while (status == JXL_ENC_NEED_MORE_OUTPUT) {
+        status = JxlEncoderProcessOutput(encoder.get(), &next, &available);
+        if (status == JXL_ENC_NEED_MORE_OUTPUT) {
+            const std::size_t kWritten = next - compressed.data();
+            compressed.resize(compressed.size() * 2);
+            next = compressed.data() + kWritten;
+            available = compressed.size() - kWritten;
+        }
+    }
+    if (status != JXL_ENC_SUCCESS) {
+        return false;
+    }
+    compressed.resize(next - compressed.data());
+
+    std::ofstream ofs(filename, std::ofstream::binary);
+    ofs.write(reinterpret_cast<const char*>(compressed.data()),
+              static_cast<std::streamsize>(compressed.size()));
+    return static_cast<bool>(ofs);
+}
+
+}  // namespace steganography
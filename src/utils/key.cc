@@ -0,0 +1,224 @@
+#include "utils/key.hpp"
+
+#include <openssl/crypto.h>
+#include <openssl/evp.h>
+#include <openssl/hmac.h>
+#include <openssl/kdf.h>
+#include <openssl/rand.h>
+
+#include <algorithm>
+#include <array>
+#include <cstdint>
+#include <filesystem>
+#include <fstream>
+#include <iterator>
+#include <numeric>
+#include <string>
+#include <vector>
+
+#include "utils/bytes.hpp"
+#include "utils/paths.hpp"
+
+namespace steganography {
+
+/* key files hold a magic word, a version byte, and the raw key material */
+static const uint32_t kKeyFileMagic = 0x5354474B; /* "STGK" */
+static const uint8_t kKeyFileVersion = 1;
+static const std::size_t kKeyFileSize = 4 + 1 + kKeySize;
+static const std::size_t kKeyStreamBlock = 4096;
+
+StegKey::~StegKey() { WipeBytes(material.data(), material.size()); }
+
+void WipeBytes(uint8_t* bytes, std::size_t size) {
+    OPENSSL_cleanse(bytes, size);
+}
+
+void Wipe(std::vector<uint8_t>& bytes) {
+    /* the whole allocation, bytes past the size may have held data too */
+    OPENSSL_cleanse(bytes.data(), bytes.capacity());
+    bytes.clear();
+}
+
+RetCode GenerateKeyFile(const std::string& path) {
+    return GenerateKeyFile(path, false);
+}
+
+RetCode GenerateKeyFile(const std::string& path, bool create_dirs) {
+    /* never clobber an existing key, data hidden with it would be lost */
+    if (std::filesystem::exists(path)) {
+        return RetCode::kFileExists;
+    }
+    if (create_dirs) {
+        RetCode rc = CreateParentDirectories(path);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+
+    StegKey key;
+    if (RAND_bytes(key.material.data(), static_cast<int>(kKeySize)) != 1) {
+        return RetCode::kIoError;
+    }
+    std::vector<uint8_t> contents;
+    AppendInt<uint32_t>(contents, kKeyFileMagic);
+    AppendInt<uint8_t>(contents, kKeyFileVersion);
+    contents.insert(contents.end(), key.material.cbegin(),
+                    key.material.cend());
+
+    /* only the owner should ever be able to read the key */
+    RetCode rc = WritePrivateFile(path, contents);
+    Wipe(contents);
+    return rc;
+}
+
+RetCode LoadKeyFile(const std::string& path, StegKey& key) {
+    std::ifstream ifs(path, std::ifstream::binary);
+    if (!ifs.is_open()) {
+        return RetCode::kFileNotFound;
+    }
+    std::vector<uint8_t> contents((std::istreambuf_iterator<char>(ifs)),
+                                  std::istreambuf_iterator<char>());
+    if ((contents.size() != kKeyFileSize) ||
+        (ReadInt<uint32_t>(contents, 0) != kKeyFileMagic) ||
+        (contents[4] != kKeyFileVersion)) {
+        Wipe(contents);
+        return RetCode::kInvalidKey;
+    }
+    std::copy(contents.cbegin() + 5, contents.cend(), key.material.begin());
+    Wipe(contents);
+    return RetCode::kSuccess;
+}
+
+static std::array<uint8_t, kKeySize> Hkdf(const std::vector<uint8_t>& ikm,
+                                          const std::string& label) {
+    /* HKDF-SHA256 with the label as the info string */
+    std::array<uint8_t, kKeySize> okm{};
+    std::size_t okm_len = okm.size();
+    EVP_PKEY_CTX* ctx = EVP_PKEY_CTX_new_id(EVP_PKEY_HKDF, nullptr);
+    EVP_PKEY_derive_init(ctx);
+    EVP_PKEY_CTX_set_hkdf_md(ctx, EVP_sha256());
+    EVP_PKEY_CTX_set1_hkdf_key(ctx, ikm.data(), static_cast<int>(ikm.size()));
+    EVP_PKEY_CTX_add1_hkdf_info(
+        ctx, reinterpret_cast<const unsigned char*>(label.data()),
+        static_cast<int>(label.size()));
+    EVP_PKEY_derive(ctx, okm.data(), &okm_len);
+    EVP_PKEY_CTX_free(ctx);
+    return okm;
+}
+
+std::array<uint8_t, kKeySize> DeriveSubkey(const StegKey& key,
+                                           const std::string& label) {
+    std::vector<uint8_t> ikm(key.material.cbegin(), key.material.cend());
+    std::array<uint8_t, kKeySize> subkey = Hkdf(ikm, label);
+    Wipe(ikm);
+    return subkey;
+}
+
+StegKey CombineKey(const StegKey& key, const std::string& password) {
+    std::vector<uint8_t> ikm(key.material.cbegin(), key.material.cend());
+    ikm.insert(ikm.end(), password.cbegin(), password.cend());
+    StegKey combined;
+    combined.material = Hkdf(ikm, "two-factor");
+    Wipe(ikm);
+    return combined;
+}
+
+StegKey SeededKey(uint64_t seed, const std::vector<uint8_t>& context) {
+    std::vector<uint8_t> ikm;
+    for (int shift = 56; shift >= 0; shift -= 8) {
+        ikm.push_back(static_cast<uint8_t>(seed >> shift));
+    }
+    ikm.insert(ikm.end(), context.cbegin(), context.cend());
+    StegKey seeded;
+    seeded.material = Hkdf(ikm, "seeded");
+    Wipe(ikm);
+    return seeded;
+}
+
+std::array<uint8_t, kKeyTagSize> KeyTag(const StegKey& key,
+                                        const std::string& label) {
+    std::array<uint8_t, kKeySize> subkey = DeriveSubkey(key, "auth");
+    std::array<uint8_t, EVP_MAX_MD_SIZE> mac{};
+    unsigned int mac_len = 0;
+    HMAC(EVP_sha256(), subkey.data(), static_cast<int>(subkey.size()),
+         reinterpret_cast<const unsigned char*>(label.data()), label.size(),
+         mac.data(), &mac_len);
+    Wipe(subkey);
+
+    std::array<uint8_t, kKeyTagSize> tag{};
+    std::copy_n(mac.cbegin(), kKeyTagSize, tag.begin());
+    Wipe(mac);
+    return tag;
+}
+
+bool TagsEqual(const std::array<uint8_t, kKeyTagSize>& lhs,
+               const std::array<uint8_t, kKeyTagSize>& rhs) {
+    return CRYPTO_memcmp(lhs.data(), rhs.data(), kKeyTagSize) == 0;
+}
+
+KeyStream::KeyStream(const StegKey& key, const std::string& label)
+    : subkey_(DeriveSubkey(key, label)) {}
+
+/* the stream encrypts secret pixels, so it is as sensitive as the key */
+KeyStream::~KeyStream() {
+    Wipe(subkey_);
+    Wipe(buffer_);
+}
+
+void KeyStream::Refill() {
+    /* encrypt zeros under a fresh counter block to produce the next chunk */
+    std::array<uint8_t, 16> iv{};
+    for (int i = 0; i < 8; ++i) {
+        iv[15 - i] = static_cast<uint8_t>(counter_ >> (8 * i));
+    }
+    counter_ += kKeyStreamBlock / 16;
+
+    std::vector<uint8_t> zeros(kKeyStreamBlock, 0);
+    buffer_.assign(kKeyStreamBlock, 0);
+    int len = 0;
+    EVP_CIPHER_CTX* ctx = EVP_CIPHER_CTX_new();
+    EVP_EncryptInit_ex(ctx, EVP_aes_256_ctr(), nullptr, subkey_.data(),
+                       iv.data());
+    EVP_EncryptUpdate(ctx, buffer_.data(), &len, zeros.data(),
+                      static_cast<int>(zeros.size()));
+    EVP_CIPHER_CTX_free(ctx);
+    pos_ = 0;
+}
+
+uint8_t KeyStream::NextByte() {
+    if (pos_ >= buffer_.size()) {
+        Refill();
+    }
+    return buffer_[pos_++];
+}
+
+uint64_t KeyStream::NextWord() {
+    uint64_t word = 0;
+    for (int i = 0; i < 8; ++i) {
+        word = (word << 8) | NextByte();
+    }
+    return word;
+}
+
+uint64_t KeyStream::NextBelow(uint64_t bound) {
+    /* reject the tail of the range to avoid modulo bias */
+    const uint64_t kLimit = UINT64_MAX - (UINT64_MAX % bound);
+    uint64_t word = NextWord();
+    while (word >= kLimit) {
+        word = NextWord();
+    }
+    return word % bound;
+}
+
+std::vector<std::size_t> KeyedPermutation(std::size_t count,
+                                          KeyStream& stream) {
+    std::vector<std::size_t> perm(count);
+    std::iota(perm.begin(), perm.end(), 0);
+    for (std::size_t i = count; i > 1; --i) {
+        std::size_t j = stream.NextBelow(i);
+        std::swap(perm[i - 1], perm[j]);
+    }
+    return perm;
+}
+
+}  // namespace steganography
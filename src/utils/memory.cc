@@ -0,0 +1,33 @@
+#include "utils/memory.hpp"
+
+#include <cstdint>
+#include <fstream>
+#include <sstream>
+#include <string>
+
+namespace steganography {
+
+uint64_t PeakRss() {
+    /* the high water mark is listed in kB */
+    std::ifstream ifs("/proc/self/status");
+    std::string line;
+    while (std::getline(ifs, line)) {
+        if (line.starts_with("VmHWM:")) {
+            std::istringstream fields(line.substr(6));
+            uint64_t kilobytes = 0;
+            fields >> kilobytes;
+            return kilobytes * 1024;
+        }
+    }
+    return 0;
+}
+
+bool ResetPeakRss() {
+    /* 5 resets the high water mark, Linux 4.0 on */
+    std::ofstream ofs("/proc/self/clear_refs");
+    ofs << "5";
+    ofs.flush();
+    return ofs.good();
+}
+
+}  // namespace steganography
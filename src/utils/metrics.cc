@@ -0,0 +1,30 @@
+#include "utils/metrics.hpp"
+
+#include <boost/gil.hpp>
+#include <cmath>
+#include <limits>
+
+namespace steganography {
+
+double Psnr(const boost::gil::rgb8c_view_t& lhs,
+            const boost::gil::rgb8c_view_t& rhs) {
+    const double kMaxValue = 255.0;
+    double squared_error = 0.0;
+    for (std::ptrdiff_t row = 0; row < lhs.height(); ++row) {
+        for (std::ptrdiff_t col = 0; col < lhs.width(); ++col) {
+            for (int i = 0; i < 3; ++i) {
+                double diff = static_cast<double>(lhs(col, row)[i]) -
+                              static_cast<double>(rhs(col, row)[i]);
+                squared_error += diff * diff;
+            }
+        }
+    }
+    double mse = squared_error / static_cast<double>(lhs.width() *
+                                                     lhs.height() * 3);
+    if (mse == 0.0) {
+        return std::numeric_limits<double>::infinity();
+    }
+    return 10.0 * std::log10(kMaxValue * kMaxValue / mse);
+}
+
+}  // namespace steganography
@@ -0,0 +1,129 @@
+#include "utils/mime.hpp"
+
+#include <algorithm>
+#include <array>
+#include <cctype>
+#include <cstddef>
+#include <cstdint>
+#include <filesystem>
+#include <string>
+#include <utility>
+#include <vector>
+
+namespace steganography {
+
+struct ContentTypeInfo {
+    const char* type;
+    const char* extension; /* with its dot */
+    const char* magic;     /* leading bytes, nullptr when there are none */
+    std::size_t magic_size;
+    std::size_t magic_offset;
+};
+
+/* checked in order, the types only known by their extension come last */
+static const std::array<ContentTypeInfo, 24> kContentTypes = {{
+    {"image/png", ".png", "\x89PNG\r\n\x1a\n", 8, 0},
+    {"image/jpeg", ".jpg", "\xff\xd8\xff", 3, 0},
+    {"image/gif", ".gif", "GIF8", 4, 0},
+    {"image/bmp", ".bmp", "BM", 2, 0},
+    {"image/webp", ".webp", "WEBP", 4, 8},
+    {"image/tiff", ".tif", "II*\0", 4, 0},
+    {"image/tiff", ".tiff", "MM\0*", 4, 0},
+    {"audio/wav", ".wav", "WAVE", 4, 8},
+    {"audio/mpeg", ".mp3", "ID3", 3, 0},
+    {"audio/flac", ".flac", "fLaC", 4, 0},
+    {"audio/ogg", ".ogg", "OggS", 4, 0},
+    {"video/mp4", ".mp4", "ftyp", 4, 4},
+    {"application/pdf", ".pdf", "%PDF-", 5, 0},
+    {"application/zip", ".zip", "PK\x03\x04", 4, 0},
+    {"application/gzip", ".gz", "\x1f\x8b", 2, 0},
+    {"application/x-7z-compressed", ".7z", "7z\xbc\xaf\x27\x1c", 6, 0},
+    {"application/x-bzip2", ".bz2", "BZh", 3, 0},
+    {"application/x-xz", ".xz", "\xfd" "7zXZ\0", 6, 0},
+    {"application/x-tar", ".tar", "ustar", 5, 257},
+    {"application/json", ".json", nullptr, 0, 0},
+    {"text/html", ".html", nullptr, 0, 0},
+    {"text/markdown", ".md", nullptr, 0, 0},
+    {"text/csv", ".csv", nullptr, 0, 0},
+    {"text/plain", ".txt", nullptr, 0, 0},
+}};
+
+/* extensions of the types above that ContentTypeExtension never gives */
+static const std::array<std::pair<const char*, const char*>, 4>
+    kExtensionAliases = {{{".jpeg", "image/jpeg"},
+                          {".htm", "text/html"},
+                          {".markdown", "text/markdown"},
+                          {".tgz", "application/gzip"}}};
+
+static bool HasMagic(const std::vector<uint8_t>& data,
+                     const ContentTypeInfo& info) {
+    if ((info.magic == nullptr) ||
+        (data.size() < info.magic_offset + info.magic_size)) {
+        return false;
+    }
+    return std::equal(info.magic, info.magic + info.magic_size,
+                      data.cbegin() +
+                          static_cast<std::ptrdiff_t>(info.magic_offset),
+                      [](char expected, uint8_t actual) {
+                          return static_cast<uint8_t>(expected) == actual;
+                      });
+}
+
+/* no control characters other than whitespace in the first few kilobytes,
+ * bytes of UTF-8 sequences are let through */
+static bool LooksLikeText(const std::vector<uint8_t>& data) {
+    const std::size_t kSniffSize = 4096;
+    const std::size_t kEnd = std::min(data.size(), kSniffSize);
+    for (std::size_t i = 0; i < kEnd; ++i) {
+        const uint8_t kByte = data[i];
+        if ((kByte < 0x20) && (kByte != '\t') && (kByte != '\n') &&
+            (kByte != '\r') && (kByte != '\f')) {
+            return false;
+        }
+        if (kByte == 0x7f) {
+            return false;
+        }
+    }
+    return !data.empty();
+}
+
+std::string GuessContentType(const std::vector<uint8_t>& data,
+                             const std::string& filename) {
+    for (const ContentTypeInfo& info : kContentTypes) {
+        if (HasMagic(data, info)) {
+            return info.type;
+        }
+    }
+    std::string extension =
+        std::filesystem::path(filename).extension().string();
+    std::transform(extension.begin(), extension.end(), extension.begin(),
+                   [](unsigned char c) { return std::tolower(c); });
+    if (!extension.empty()) {
+        for (const ContentTypeInfo& info : kContentTypes) {
+            if (extension == info.extension) {
+                return info.type;
+            }
+        }
+        for (const auto& [alias, type] : kExtensionAliases) {
+            if (extension == alias) {
+                return type;
+            }
+        }
+    }
+    return LooksLikeText(data) ? "text/plain" : kDefaultContentType;
+}
+
+std::string ContentTypeExtension(const std::string& content_type) {
+    /* parameters such as a charset do not change the extension */
+    std::string type = content_type.substr(0, content_type.find(';'));
+    std::transform(type.begin(), type.end(), type.begin(),
+                   [](unsigned char c) { return std::tolower(c); });
+    for (const ContentTypeInfo& info : kContentTypes) {
+        if (type == info.type) {
+            return info.extension;
+        }
+    }
+    return ".bin";
+}
+
+}  // namespace steganography
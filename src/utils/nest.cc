@@ -0,0 +1,137 @@
+#include "utils/nest.hpp"
+
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <filesystem>
+#include <fstream>
+#include <iterator>
+#include <string>
+#include <vector>
+
+#include "utils/icc.hpp"
+#include "utils/image_io.hpp"
+#include "utils/paths.hpp"
+#include "utils/payload.hpp"
+
+namespace steganography {
+
+static bool ReadFile(const std::string& path, std::vector<uint8_t>& data) {
+    std::ifstream ifs(path, std::ifstream::binary);
+    if (!ifs.is_open()) {
+        return false;
+    }
+    data.assign(std::istreambuf_iterator<char>(ifs),
+                std::istreambuf_iterator<char>());
+    return !ifs.bad();
+}
+
+static RetCode WriteFile(const std::string& path,
+                         const std::vector<uint8_t>& data) {
+    std::ofstream ofs(path, std::ofstream::binary | std::ofstream::trunc);
+    if (!ofs.is_open()) {
+        return RetCode::kIoError;
+    }
+    ofs.write(reinterpret_cast<const char*>(data.data()),
+              static_cast<std::streamsize>(data.size()));
+    return ofs.good() ? RetCode::kSuccess : RetCode::kIoError;
+}
+
+RetCode Nest(const std::string& cover, const std::string& stego,
+             const std::string& outfile, const NestConfig& config) {
+    if (!std::filesystem::exists(cover) || !std::filesystem::exists(stego)) {
+        return RetCode::kFileNotFound;
+    }
+    if (SamePath(cover, stego) || SameContents(cover, stego)) {
+        return RetCode::kSameInput;
+    }
+    if (SamePath(outfile, cover) || SamePath(outfile, stego)) {
+        return RetCode::kOutputIsInput;
+    }
+    /* the file is kept whole, but it has to be an image to be unwrapped */
+    if (GetImageType(stego) == ImageType::kUnknown) {
+        return RetCode::kInvalidFileFormat;
+    }
+    std::vector<uint8_t> data;
+    if (!ReadFile(stego, data)) {
+        return RetCode::kIoError;
+    }
+
+    ImageType output_img_t = ImageType::kPng;
+    RetCode rc = ResolveOutputType(config.output_format, outfile,
+                                   GetImageType(cover), true, output_img_t);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    boost::gil::rgb8_image_t output_img;
+    rc = LoadImage(cover, output_img);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    EmbedConfig embed;
+    embed.content_type = kNestedContentType;
+    embed.filename = std::filesystem::path(stego).filename().string();
+    if (embed.filename.size() > kMaxContentTypeSize) {
+        embed.filename.clear();
+    }
+    rc = EmbedPayload(output_img, data, embed);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    if (config.create_dirs) {
+        rc = CreateParentDirectories(outfile);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+    WriteImage(output_img, outfile, output_img_t, ReadIccProfile(cover));
+    return RetCode::kSuccess;
+}
+
+RetCode Unnest(const std::string& image, const std::string& outfile,
+               const UnnestConfig& config, UnnestReport& report) {
+    report = UnnestReport{};
+    if (SamePath(outfile, image)) {
+        return RetCode::kOutputIsInput;
+    }
+    if (config.create_dirs) {
+        RetCode rc = CreateParentDirectories(outfile);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+
+    /* each level is written to outfile and read back from it, so only one
+     * image is held at a time however deep the nesting goes */
+    std::string current = image;
+    while ((config.levels == 0) || (report.levels < config.levels)) {
+        boost::gil::rgb8_image_t current_img;
+        RetCode rc = LoadImage(current, current_img);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+        std::vector<uint8_t> data;
+        PayloadHeader header;
+        rc = ExtractPayload(current_img, data, header);
+        if ((rc == RetCode::kSuccess) &&
+            (header.content_type != kNestedContentType)) {
+            rc = RetCode::kPayloadNotFound;
+        }
+        if (rc == RetCode::kPayloadNotFound) {
+            break; /* the innermost image */
+        } else if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+        rc = WriteFile(outfile, data);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+        ++report.levels;
+        report.filename = header.filename;
+        current = outfile;
+    }
+    return (report.levels == 0) ? RetCode::kPayloadNotFound
+                                : RetCode::kSuccess;
+}
+
+}  // namespace steganography
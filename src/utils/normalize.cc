@@ -0,0 +1,361 @@
+#include "utils/normalize.hpp"
+
+#include <png.h>
+
+#include <algorithm>
+#include <boost/gil.hpp>
+#include <csetjmp>
+#include <cstddef>
+#include <cstdint>
+#include <cstdio>
+#include <fstream>
+#include <iterator>
+#include <string>
+#include <vector>
+
+#ifdef STEG_WITH_JPEG
+#include <jpeglib.h>
+#endif
+
+namespace steganography {
+
+/* libjpeg-turbo 3 decodes 12 bit samples alongside 8 bit ones */
+#if defined(STEG_WITH_JPEG) && defined(LIBJPEG_TURBO_VERSION_NUMBER) && \
+    (LIBJPEG_TURBO_VERSION_NUMBER >= 3000000)
+#define STEG_JPEG12
+#endif
+
+/* 8 bit RGBA samples, kept by the callers of the decoders so libjpeg and
+ * libpng jumping out of an error leave nothing to unwind */
+struct DecodedSamples {
+    std::size_t width = 0;
+    std::size_t height = 0;
+    std::size_t components = 0; /* 1 for gray, 3 for RGB, 4 for the rest */
+    bool inverted = false; /* Adobe CMYK, stored with 255 for no ink */
+    std::vector<uint8_t> samples;
+    std::vector<png_bytep> rows;
+#ifdef STEG_JPEG12
+    std::vector<J12SAMPLE> wide_row;
+#endif
+};
+
+const char* InputConversionCode(InputConversion conversion) {
+    switch (conversion) {
+        case InputConversion::kCmyk:
+            return "cmyk-input";
+        case InputConversion::kDepth:
+            return "deep-input";
+        case InputConversion::kInterlace:
+            return "interlaced-input";
+    }
+    return "converted";
+}
+
+/* warns that the input needs a conversion and says whether it was made,
+ * false when policy refuses it */
+static bool Convert(ConversionPolicy policy, InputConversion conversion,
+                    const std::string& found, const std::string& done,
+                    std::vector<InputWarning>& warnings) {
+    if (policy == ConversionPolicy::kReject) {
+        warnings.push_back({conversion, found + ", left unconverted"});
+        return false;
+    }
+    warnings.push_back({conversion, found + ", " + done});
+    return true;
+}
+
+#ifdef STEG_WITH_JPEG
+struct JpegErrorManager {
+    jpeg_error_mgr manager;
+    std::jmp_buf jump;
+};
+
+[[noreturn]] static void ExitJpeg(j_common_ptr cinfo) {
+    std::longjmp(reinterpret_cast<JpegErrorManager*>(cinfo->err)->jump, 1);
+}
+
+static void SilenceJpeg(j_common_ptr) {}
+
+/* the sample precision in a JPEG's start of frame, 0 when there is none */
+static int JpegPrecision(const std::vector<uint8_t>& data) {
+    std::size_t offset = 2;
+    while (offset + 4 < data.size()) {
+        if (data[offset] != 0xFF) {
+            return 0;
+        }
+        const uint8_t kMarker = data[offset + 1];
+        if (kMarker == 0xFF) {
+            ++offset; /* fill byte */
+            continue;
+        }
+        if ((kMarker >= 0xC0) && (kMarker <= 0xCF) && (kMarker != 0xC4) &&
+            (kMarker != 0xC8) && (kMarker != 0xCC)) {
+            return data[offset + 4];
+        }
+        if (kMarker == 0xDA) {
+            return 0; /* a scan before any frame */
+        }
+        offset += 2 + ((static_cast<std::size_t>(data[offset + 2]) << 8) |
+                       data[offset + 3]);
+    }
+    return 0;
+}
+
+/* CMYK and YCCK come out as CMYK, gray as gray, and the rest as RGB */
+static bool DecodeJpeg(const std::vector<uint8_t>& data, int precision,
+                       DecodedSamples& decoded) {
+    jpeg_decompress_struct cinfo{};
+    JpegErrorManager error{};
+    cinfo.err = jpeg_std_error(&error.manager);
+    error.manager.error_exit = ExitJpeg;
+    error.manager.output_message = SilenceJpeg;
+    if (setjmp(error.jump)) {
+        jpeg_destroy_decompress(&cinfo);
+        return false;
+    }
+    jpeg_create_decompress(&cinfo);
+    jpeg_mem_src(&cinfo, data.data(), static_cast<unsigned long>(data.size()));
+    jpeg_read_header(&cinfo, TRUE);
+    if ((cinfo.jpeg_color_space == JCS_CMYK) ||
+        (cinfo.jpeg_color_space == JCS_YCCK)) {
+        cinfo.out_color_space = JCS_CMYK;
+    } else if (cinfo.jpeg_color_space == JCS_GRAYSCALE) {
+        cinfo.out_color_space = JCS_GRAYSCALE;
+    } else {
+        cinfo.out_color_space = JCS_RGB;
+    }
+    jpeg_start_decompress(&cinfo);
+    decoded.width = cinfo.output_width;
+    decoded.height = cinfo.output_height;
+    decoded.components = static_cast<std::size_t>(cinfo.output_components);
+    decoded.inverted = cinfo.saw_Adobe_marker;
+    const std::size_t kRowSize = decoded.width * decoded.components;
+    decoded.samples.assign(kRowSize * decoded.height, 0);
+#ifdef STEG_JPEG12
+    if (precision == 12) {
+        decoded.wide_row.assign(kRowSize, 0);
+        while (cinfo.output_scanline < cinfo.output_height) {
+            const std::size_t kOffset = cinfo.output_scanline * kRowSize;
+            J12SAMPROW row = decoded.wide_row.data();
+            jpeg12_read_scanlines(&cinfo, &row, 1);
+            for (std::size_t i = 0; i < kRowSize; ++i) {
+                decoded.samples[kOffset + i] = static_cast<uint8_t>(
+                    (decoded.wide_row[i] * 255 + 2047) / 4095);
+            }
+        }
+    }
+#endif
+    while ((precision != 12) && (cinfo.output_scanline < cinfo.output_height)) {
+        JSAMPROW row = &decoded.samples[cinfo.output_scanline * kRowSize];
+        jpeg_read_scanlines(&cinfo, &row, 1);
+    }
+    jpeg_finish_decompress(&cinfo);
+    jpeg_destroy_decompress(&cinfo);
+    return true;
+}
+
+static RetCode ReadJpeg(const std::string& filename,
+                        const std::vector<uint8_t>& data,
+                        const InputPolicy& policy,
+                        boost::gil::rgb8_image_t& image,
+                        std::vector<InputWarning>& warnings) {
+    const int kPrecision = JpegPrecision(data);
+    if (kPrecision > 8) {
+#ifdef STEG_JPEG12
+        if (!Convert(policy.depth, InputConversion::kDepth,
+                     filename + " has 12 bit samples",
+                     "rounded to 8 bits", warnings)) {
+            return RetCode::kConversionRefused;
+        }
+#else
+        warnings.push_back({InputConversion::kDepth,
+                            filename + " has " + std::to_string(kPrecision) +
+                                " bit samples, which this build's libjpeg "
+                                "cannot decode"});
+        return RetCode::kConversionRefused;
+#endif
+    }
+    DecodedSamples decoded;
+    if (!DecodeJpeg(data, kPrecision, decoded)) {
+        return RetCode::kInvalidFileFormat;
+    }
+    if ((decoded.components == 4) &&
+        !Convert(policy.cmyk, InputConversion::kCmyk,
+                 filename + " has CMYK samples",
+                 "converted to RGB without color management", warnings)) {
+        return RetCode::kConversionRefused;
+    }
+
+    image = boost::gil::rgb8_image_t(decoded.width, decoded.height);
+    auto view = boost::gil::view(image);
+    const uint8_t* sample = decoded.samples.data();
+    for (std::size_t y = 0; y < decoded.height; ++y) {
+        for (std::size_t x = 0; x < decoded.width; ++x) {
+            if (decoded.components == 1) {
+                view(x, y) = boost::gil::rgb8_pixel_t(sample[0], sample[0],
+                                                      sample[0]);
+            } else if (decoded.components == 3) {
+                view(x, y) = boost::gil::rgb8_pixel_t(sample[0], sample[1],
+                                                      sample[2]);
+            } else {
+                /* the naive conversion every viewer uses for untagged
+                 * CMYK, on ink amounts whichever way they are stored */
+                int ink[4];
+                for (std::size_t c = 0; c < 4; ++c) {
+                    ink[c] = decoded.inverted ? 255 - sample[c] : sample[c];
+                }
+                const int kBlack = 255 - ink[3];
+                view(x, y) = boost::gil::rgb8_pixel_t(
+                    static_cast<uint8_t>((255 - ink[0]) * kBlack / 255),
+                    static_cast<uint8_t>((255 - ink[1]) * kBlack / 255),
+                    static_cast<uint8_t>((255 - ink[2]) * kBlack / 255));
+            }
+            sample += decoded.components;
+        }
+    }
+    return RetCode::kSuccess;
+}
+#endif
+
+/* where libpng is in the file's bytes */
+struct PngSource {
+    const std::vector<uint8_t>* data = nullptr;
+    std::size_t offset = 0;
+};
+
+[[noreturn]] static void ExitPng(png_structp png, png_const_charp) {
+    png_longjmp(png, 1);
+}
+
+static void SilencePng(png_structp, png_const_charp) {}
+
+static void ReadPngData(png_structp png, png_bytep out, png_size_t size) {
+    PngSource* source = static_cast<PngSource*>(png_get_io_ptr(png));
+    if (source->offset + size > source->data->size()) {
+        png_error(png, "truncated");
+    }
+    std::copy_n(source->data->data() + source->offset, size, out);
+    source->offset += size;
+}
+
+/* every layout comes out as 8 bit RGBA, palettes and gray expanded, deep
+ * samples rounded, and an opaque alpha added where there is none */
+static bool DecodePng(const std::vector<uint8_t>& data,
+                      DecodedSamples& decoded) {
+    png_structp png = png_create_read_struct(PNG_LIBPNG_VER_STRING, nullptr,
+                                             ExitPng, SilencePng);
+    if (png == nullptr) {
+        return false;
+    }
+    png_infop info = png_create_info_struct(png);
+    if (info == nullptr) {
+        png_destroy_read_struct(&png, nullptr, nullptr);
+        return false;
+    }
+    PngSource source{&data, 0};
+    if (setjmp(png_jmpbuf(png))) {
+        png_destroy_read_struct(&png, &info, nullptr);
+        return false;
+    }
+    png_set_read_fn(png, &source, ReadPngData);
+    png_read_info(png, info);
+    png_set_expand(png);
+    png_set_scale_16(png);
+    png_set_gray_to_rgb(png);
+    png_set_add_alpha(png, 0xFF, PNG_FILLER_AFTER);
+    png_set_interlace_handling(png);
+    png_read_update_info(png, info);
+
+    decoded.width = png_get_image_width(png, info);
+    decoded.height = png_get_image_height(png, info);
+    decoded.components = 4;
+    const std::size_t kRowSize = png_get_rowbytes(png, info);
+    if (kRowSize != decoded.width * decoded.components) {
+        png_destroy_read_struct(&png, &info, nullptr);
+        return false;
+    }
+    decoded.samples.assign(kRowSize * decoded.height, 0);
+    decoded.rows.assign(decoded.height, nullptr);
+    for (std::size_t y = 0; y < decoded.height; ++y) {
+        decoded.rows[y] = &decoded.samples[y * kRowSize];
+    }
+    png_read_image(png, decoded.rows.data());
+    png_read_end(png, nullptr);
+    png_destroy_read_struct(&png, &info, nullptr);
+    return true;
+}
+
+static RetCode ReadPng(const std::string& filename,
+                       const std::vector<uint8_t>& data,
+                       const InputPolicy& policy,
+                       boost::gil::rgb8_image_t& image,
+                       std::vector<InputWarning>& warnings) {
+    /* the signature, then IHDR's length, type, width, height, bit depth,
+     * color type, compression, filter, and interlace method */
+    const std::size_t kIhdrEnd = 29;
+    if ((data.size() < kIhdrEnd) ||
+        !std::equal(data.cbegin() + 12, data.cbegin() + 16, "IHDR")) {
+        return RetCode::kInvalidFileFormat;
+    }
+    if ((data[24] == 16) &&
+        !Convert(policy.depth, InputConversion::kDepth,
+                 filename + " has 16 bit samples", "rounded to 8 bits",
+                 warnings)) {
+        return RetCode::kConversionRefused;
+    }
+    if ((data[28] != 0) &&
+        !Convert(policy.interlace, InputConversion::kInterlace,
+                 filename + " is interlaced",
+                 "outputs made from it are not", warnings)) {
+        return RetCode::kConversionRefused;
+    }
+    DecodedSamples decoded;
+    if (!DecodePng(data, decoded)) {
+        return RetCode::kInvalidFileFormat;
+    }
+
+    image = boost::gil::rgb8_image_t(decoded.width, decoded.height);
+    auto view = boost::gil::view(image);
+    const uint8_t* sample = decoded.samples.data();
+    for (std::size_t y = 0; y < decoded.height; ++y) {
+        for (std::size_t x = 0; x < decoded.width; ++x) {
+            uint8_t rgb[3];
+            for (std::size_t c = 0; c < 3; ++c) {
+                rgb[c] = static_cast<uint8_t>((sample[c] * sample[3] + 127) /
+                                              255);
+            }
+            view(x, y) = boost::gil::rgb8_pixel_t(rgb[0], rgb[1], rgb[2]);
+            sample += decoded.components;
+        }
+    }
+    return RetCode::kSuccess;
+}
+
+RetCode ReadNormalizedImage(const std::string& filename, ImageType type,
+                            const InputPolicy& policy,
+                            boost::gil::rgb8_image_t& image,
+                            std::vector<InputWarning>& warnings) {
+#ifdef STEG_WITH_JPEG
+    const bool kJpeg = (type == ImageType::kJpeg);
+#else
+    const bool kJpeg = false;
+#endif
+    if ((type != ImageType::kPng) && !kJpeg) {
+        image = ReadImage(filename, type);
+        return RetCode::kSuccess;
+    }
+    std::ifstream ifs(filename, std::ifstream::binary);
+    if (!ifs.is_open()) {
+        return RetCode::kFileNotFound;
+    }
+    const std::vector<uint8_t> kData((std::istreambuf_iterator<char>(ifs)),
+                                     std::istreambuf_iterator<char>());
+#ifdef STEG_WITH_JPEG
+    if (kJpeg) {
+        return ReadJpeg(filename, kData, policy, image, warnings);
+    }
+#endif
+    return ReadPng(filename, kData, policy, image, warnings);
+}
+
+}  // namespace steganography
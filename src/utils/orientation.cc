@@ -0,0 +1,92 @@
+#include "utils/orientation.hpp"
+
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <utility>
+
+namespace steganography {
+
+static bool IsMirrored(Orientation orientation) {
+    return static_cast<int>(orientation) >= 4;
+}
+
+static int QuarterTurns(Orientation orientation) {
+    return static_cast<int>(orientation) % 4;
+}
+
+/* where pixel (x, y) of a width by height image ends up once turned */
+static std::pair<std::ptrdiff_t, std::ptrdiff_t> Turn(std::ptrdiff_t x,
+                                                      std::ptrdiff_t y,
+                                                      std::ptrdiff_t width,
+                                                      std::ptrdiff_t height,
+                                                      Orientation orientation) {
+    if (IsMirrored(orientation)) {
+        x = width - 1 - x;
+    }
+    for (int i = 0; i < QuarterTurns(orientation); ++i) {
+        std::ptrdiff_t turned_x = height - 1 - y;
+        y = x;
+        x = turned_x;
+        std::swap(width, height);
+    }
+    return {x, y};
+}
+
+const char* OrientationName(Orientation orientation) {
+    switch (orientation) {
+        case Orientation::kNormal:
+            return "not turned";
+        case Orientation::kRotated90:
+            return "rotated 90 degrees clockwise";
+        case Orientation::kRotated180:
+            return "rotated 180 degrees";
+        case Orientation::kRotated270:
+            return "rotated 90 degrees counterclockwise";
+        case Orientation::kMirrored:
+            return "mirrored";
+        case Orientation::kMirroredRotated90:
+            return "mirrored and rotated 90 degrees clockwise";
+        case Orientation::kMirroredRotated180:
+            return "flipped upside down";
+        case Orientation::kMirroredRotated270:
+            return "mirrored and rotated 90 degrees counterclockwise";
+    }
+    return "";
+}
+
+boost::gil::rgb8_image_t Reorient(const boost::gil::rgb8_image_t& image,
+                                  Orientation orientation) {
+    const std::ptrdiff_t kWidth = image.width();
+    const std::ptrdiff_t kHeight = image.height();
+    bool sideways = QuarterTurns(orientation) % 2;
+    boost::gil::rgb8_image_t turned(sideways ? kHeight : kWidth,
+                                    sideways ? kWidth : kHeight);
+    auto src = boost::gil::const_view(image);
+    auto dst = boost::gil::view(turned);
+    for (std::ptrdiff_t y = 0; y < kHeight; ++y) {
+        for (std::ptrdiff_t x = 0; x < kWidth; ++x) {
+            auto [turned_x, turned_y] = Turn(x, y, kWidth, kHeight, orientation);
+            dst(turned_x, turned_y) = src(x, y);
+        }
+    }
+    return turned;
+}
+
+boost::gil::rgb8_image_t Normalize(const boost::gil::rgb8_image_t& image,
+                                   Orientation orientation) {
+    bool sideways = QuarterTurns(orientation) % 2;
+    const std::ptrdiff_t kWidth = sideways ? image.height() : image.width();
+    const std::ptrdiff_t kHeight = sideways ? image.width() : image.height();
+    boost::gil::rgb8_image_t normalized(kWidth, kHeight);
+    auto src = boost::gil::const_view(image);
+    auto dst = boost::gil::view(normalized);
+    for (std::ptrdiff_t y = 0; y < kHeight; ++y) {
+        for (std::ptrdiff_t x = 0; x < kWidth; ++x) {
+            auto [turned_x, turned_y] = Turn(x, y, kWidth, kHeight, orientation);
+            dst(x, y) = src(turned_x, turned_y);
+        }
+    }
+    return normalized;
+}
+
+}  // namespace steganography
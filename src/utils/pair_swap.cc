@@ -0,0 +1,51 @@
+#include "utils/pair_swap.hpp"
+
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <optional>
+#include <vector>
+
+#include "core/carrier.hpp"
+#include "core/frame.hpp"
+#include "utils/payload.hpp"
+
+namespace steganography {
+
+/* the pixels of a view as a pair swap carrier, rows follow each other at
+ * the distance between the first two */
+template <typename View>
+static PairSwapCarrier ViewCarrier(const View& view) {
+    const std::size_t kWidth = static_cast<std::size_t>(view.width());
+    const std::size_t kHeight = static_cast<std::size_t>(view.height());
+    if ((kWidth == 0) || (kHeight == 0)) {
+        return PairSwapCarrier(static_cast<uint8_t*>(nullptr), 0, 0, 0);
+    }
+    std::size_t stride = kWidth * 3;
+    if (kHeight > 1) {
+        stride = static_cast<std::size_t>(&view(0, 1)[0] - &view(0, 0)[0]);
+    }
+    return PairSwapCarrier(&view(0, 0)[0], kWidth, kHeight, stride);
+}
+
+std::size_t PairSwapCapacity(const boost::gil::rgb8_image_t& image) {
+    return PayloadCapacity(ViewCarrier(boost::gil::const_view(image)));
+}
+
+RetCode EmbedPairSwap(boost::gil::rgb8_image_t& image,
+                      const std::vector<uint8_t>& payload,
+                      const EmbedConfig& config) {
+    PairSwapCarrier carrier = ViewCarrier(boost::gil::view(image));
+    return EmbedPayload(carrier, payload, config);
+}
+
+RetCode ExtractPairSwap(const boost::gil::rgb8_image_t& image,
+                        std::vector<uint8_t>& payload, PayloadHeader& header,
+                        DamageReport& damage,
+                        const std::optional<StegKey>& key) {
+    const PairSwapCarrier kCarrier =
+        ViewCarrier(boost::gil::const_view(image));
+    return ExtractPayload(kCarrier, payload, header, damage, key);
+}
+
+}  // namespace steganography
@@ -0,0 +1,65 @@
+#include "utils/parallel.hpp"
+
+#include <sys/resource.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+
+#include <algorithm>
+#include <cstddef>
+#include <functional>
+#include <thread>
+#include <vector>
+
+namespace steganography {
+
+unsigned WorkerCount(unsigned threads) {
+    if (threads > 0) {
+        return threads;
+    }
+    /* the count is 0 when the platform cannot tell */
+    return std::max(std::thread::hardware_concurrency(), 1U);
+}
+
+void ParallelRows(std::ptrdiff_t rows, unsigned threads,
+                  const std::function<void(std::ptrdiff_t, std::ptrdiff_t)>&
+                      body) {
+    const std::ptrdiff_t kWorkers = std::min<std::ptrdiff_t>(
+        WorkerCount(threads), std::max<std::ptrdiff_t>(rows, 1));
+    if (kWorkers == 1) {
+        body(0, rows);
+        return;
+    }
+
+    /* the first rows % workers bands take one extra row */
+    const std::ptrdiff_t kBand = rows / kWorkers;
+    const std::ptrdiff_t kExtra = rows % kWorkers;
+    std::vector<std::jthread> workers;
+    workers.reserve(static_cast<std::size_t>(kWorkers - 1));
+    std::ptrdiff_t first = 0;
+    for (std::ptrdiff_t i = 0; i < kWorkers; ++i) {
+        const std::ptrdiff_t kLast = first + kBand + ((i < kExtra) ? 1 : 0);
+        if (i == kWorkers - 1) {
+            body(first, kLast); /* the calling thread takes the last band */
+        } else {
+            workers.emplace_back(body, first, kLast);
+        }
+        first = kLast;
+    }
+}
+
+bool LowerPriority() {
+    /* Linux keeps both per thread and hands them down to new threads, glibc
+     * wraps neither ioprio call, so these come from linux/ioprio.h */
+    const int kIoprioWhoProcess = 1;
+    const int kIoprioClassIdle = 3;
+    const int kIoprioClassShift = 13;
+    const int kLowestNice = 19;
+    bool lowered = setpriority(PRIO_PROCESS, 0, kLowestNice) == 0;
+#ifdef SYS_ioprio_set
+    lowered &= syscall(SYS_ioprio_set, kIoprioWhoProcess, 0,
+                       kIoprioClassIdle << kIoprioClassShift) == 0;
+#endif
+    return lowered;
+}
+
+}  // namespace steganography
@@ -0,0 +1,125 @@
+#include "utils/paths.hpp"
+
+#include <fcntl.h>
+#include <stdlib.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#include <algorithm>
+#include <array>
+#include <cerrno>
+#include <cstddef>
+#include <cstdint>
+#include <filesystem>
+#include <fstream>
+#include <string>
+#include <system_error>
+#include <vector>
+
+namespace steganography {
+
+RetCode CreateParentDirectories(const std::string& path) {
+    std::filesystem::path parent = std::filesystem::path(path).parent_path();
+    if (parent.empty()) {
+        return RetCode::kSuccess;
+    }
+    std::error_code ec;
+    std::filesystem::create_directories(parent, ec);
+    if (ec || !std::filesystem::is_directory(parent, ec)) {
+        return RetCode::kIoError;
+    }
+    return RetCode::kSuccess;
+}
+
+bool SamePath(const std::string& lhs, const std::string& rhs) {
+    std::error_code ec;
+    if (std::filesystem::exists(lhs, ec) && std::filesystem::exists(rhs, ec)) {
+        return std::filesystem::equivalent(lhs, rhs, ec);
+    }
+    std::filesystem::path lhs_path = std::filesystem::weakly_canonical(lhs, ec);
+    if (ec) {
+        lhs_path = std::filesystem::path(lhs).lexically_normal();
+    }
+    std::filesystem::path rhs_path = std::filesystem::weakly_canonical(rhs, ec);
+    if (ec) {
+        rhs_path = std::filesystem::path(rhs).lexically_normal();
+    }
+    return lhs_path == rhs_path;
+}
+
+bool SameContents(const std::string& lhs, const std::string& rhs) {
+    std::error_code ec;
+    std::uintmax_t size = std::filesystem::file_size(lhs, ec);
+    if (ec || (std::filesystem::file_size(rhs, ec) != size) || ec) {
+        return false;
+    }
+    std::ifstream lhs_file(lhs, std::ios::binary);
+    std::ifstream rhs_file(rhs, std::ios::binary);
+    if (!lhs_file || !rhs_file) {
+        return false;
+    }
+    std::array<char, 4096> lhs_chunk;
+    std::array<char, 4096> rhs_chunk;
+    while (lhs_file && rhs_file) {
+        lhs_file.read(lhs_chunk.data(), lhs_chunk.size());
+        rhs_file.read(rhs_chunk.data(), rhs_chunk.size());
+        if ((lhs_file.gcount() != rhs_file.gcount()) ||
+            !std::equal(lhs_chunk.cbegin(),
+                        lhs_chunk.cbegin() + lhs_file.gcount(),
+                        rhs_chunk.cbegin())) {
+            return false;
+        }
+    }
+    return true;
+}
+
+RetCode WritePrivateFile(const std::string& path,
+                         const std::vector<uint8_t>& contents) {
+    /* O_EXCL also closes the gap between checking for the file and
+     * creating it */
+    int fd = open(path.c_str(), O_WRONLY | O_CREAT | O_EXCL | O_CLOEXEC,
+                  S_IRUSR | S_IWUSR);
+    if (fd < 0) {
+        return (errno == EEXIST) ? RetCode::kFileExists : RetCode::kIoError;
+    }
+    /* the umask may have taken bits away, never added any */
+    bool ok = (fchmod(fd, S_IRUSR | S_IWUSR) == 0);
+    std::size_t written = 0;
+    while (ok && (written < contents.size())) {
+        ssize_t count =
+            write(fd, contents.data() + written, contents.size() - written);
+        if (count > 0) {
+            written += static_cast<std::size_t>(count);
+        } else if ((count < 0) && (errno == EINTR)) {
+            continue;
+        } else {
+            ok = false;
+        }
+    }
+    ok = (fsync(fd) == 0) && ok;
+    ok = (close(fd) == 0) && ok;
+    if (!ok) {
+        unlink(path.c_str());
+        return RetCode::kIoError;
+    }
+    return RetCode::kSuccess;
+}
+
+ScratchDir::ScratchDir(const std::string& prefix) {
+    std::error_code ec;
+    std::string tmpl =
+        (std::filesystem::temp_directory_path(ec) / (prefix + "-XXXXXX"))
+            .string();
+    if (!ec && (mkdtemp(tmpl.data()) != nullptr)) {
+        path_ = tmpl;
+    }
+}
+
+ScratchDir::~ScratchDir() {
+    std::error_code ec;
+    if (!path_.empty()) {
+        std::filesystem::remove_all(path_, ec);
+    }
+}
+
+}  // namespace steganography
@@ -0,0 +1,341 @@
+#include "utils/payload.hpp"
+
+#include <algorithm>
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <limits>
+#include <memory>
+#include <optional>
+#include <utility>
+#include <vector>
+
+#include "core/crc32.hpp"
+#include "utils/bytes.hpp"
+#include "utils/carrier.hpp"
+#include "utils/key.hpp"
+#include "utils/orientation.hpp"
+
+namespace steganography {
+
+static const char* const kCopiesLabel = "payload-copies";
+
+/* the bytes of a view as a framebuffer, rows follow each other at the
+ * distance between the first two */
+template <typename View>
+static FramebufferCarrier ViewCarrier(const View& view) {
+    const std::size_t kWidth = static_cast<std::size_t>(view.width());
+    const std::size_t kHeight = static_cast<std::size_t>(view.height());
+    if ((kWidth == 0) || (kHeight == 0)) {
+        return FramebufferCarrier(static_cast<uint8_t*>(nullptr), 0, 0, 0);
+    }
+    std::size_t stride = kWidth * 3;
+    if (kHeight > 1) {
+        stride = static_cast<std::size_t>(&view(0, 1)[0] - &view(0, 0)[0]);
+    }
+    return FramebufferCarrier(&view(0, 0)[0], kWidth, kHeight, stride);
+}
+
+/* keyed copies are placed with the key stream of the key file's subkey */
+class KeyedPlacement : public CopyPlacement {
+   public:
+    explicit KeyedPlacement(const StegKey& key) : stream_(key, kCopiesLabel) {}
+
+    uint64_t NextBelow(uint64_t bound) override {
+        return stream_.NextBelow(bound);
+    }
+
+   private:
+    KeyStream stream_;
+};
+
+std::size_t CellCount(const boost::gil::rgb8_image_t& image) {
+    return ImageCarrier(image).CellCount();
+}
+
+uint8_t ReadCell(const boost::gil::rgb8_image_t::const_view_t& view,
+                 std::size_t cell) {
+    return ViewCarrier(view).ReadCell(cell);
+}
+
+void WriteCell(const boost::gil::rgb8_image_t::view_t& view, std::size_t cell,
+               uint8_t byte) {
+    ViewCarrier(view).WriteCell(cell, byte);
+}
+
+ImageCarrier::ImageCarrier(boost::gil::rgb8_image_t& image)
+    : FramebufferCarrier(ViewCarrier(boost::gil::view(image))) {}
+
+ImageCarrier::ImageCarrier(const boost::gil::rgb8_image_t& image)
+    : FramebufferCarrier(ViewCarrier(boost::gil::const_view(image))) {}
+
+std::size_t PayloadCapacity(const boost::gil::rgb8_image_t& image) {
+    return PayloadCapacity(ImageCarrier(image));
+}
+
+RetCode EmbedPayload(boost::gil::rgb8_image_t& image,
+                     const std::vector<uint8_t>& payload, uint8_t flags) {
+    EmbedConfig config;
+    config.flags = flags;
+    return EmbedPayload(image, payload, config);
+}
+
+RetCode EmbedPayload(boost::gil::rgb8_image_t& image,
+                     const std::vector<uint8_t>& payload,
+                     const EmbedConfig& config) {
+    ImageCarrier carrier(image);
+    return EmbedPayload(carrier, payload, config);
+}
+
+RetCode EmbedPayload(Carrier& carrier, const std::vector<uint8_t>& payload,
+                     const EmbedConfig& config) {
+    std::optional<KeyedPlacement> placement;
+    FrameConfig frame;
+    frame.flags = config.flags;
+    frame.copies = config.copies;
+    frame.stages = config.stages;
+    frame.content_type = config.content_type;
+    frame.filename = config.filename;
+    if (config.key) {
+        frame.placement = &placement.emplace(*config.key);
+    }
+    return EmbedFrame(carrier, payload, frame);
+}
+
+Embedder::Embedder(boost::gil::rgb8_image_t& image, uint8_t flags)
+    : Embedder(std::make_unique<ImageCarrier>(image), flags) {}
+
+Embedder::Embedder(std::unique_ptr<Carrier> carrier, uint8_t flags)
+    : Embedder(*carrier, flags) {
+    owned_ = std::move(carrier);
+}
+
+/* the body starts after the header so it can be written as it arrives and
+ * the header, length included, last */
+Embedder::Embedder(Carrier& carrier, uint8_t flags)
+    : carrier_(&carrier),
+      flags_(flags),
+      cells_(carrier.CellCount()),
+      next_cell_(kPayloadHeaderSize) {
+    const uint8_t kStreamable = kFlagCompressed | kFlagChecksums;
+    if (flags & ~kStreamable) {
+        status_ = RetCode::kInvalidArgument;
+    } else if (cells_ < kPayloadHeaderSize) {
+        status_ = RetCode::kInsufficientCapacity;
+    }
+    block_.reserve(kChecksumBlockSize);
+}
+
+RetCode Embedder::Write(const std::vector<uint8_t>& data) {
+    return Write(data.data(), data.size());
+}
+
+RetCode Embedder::Write(const uint8_t* data, std::size_t size) {
+    if (finished_) {
+        return RetCode::kInvalidArgument;
+    }
+    if (status_ != RetCode::kSuccess) {
+        return status_;
+    }
+    length_ += size;
+    if (length_ > std::numeric_limits<uint32_t>::max()) {
+        status_ = RetCode::kInsufficientCapacity; /* too long for the header */
+        return status_;
+    }
+    if (!(flags_ & kFlagChecksums)) {
+        status_ = WriteBody(data, size);
+        return status_;
+    }
+    while ((size > 0) && (status_ == RetCode::kSuccess)) {
+        std::size_t len = std::min(kChecksumBlockSize - block_.size(), size);
+        block_.insert(block_.end(), data, data + len);
+        data += len;
+        size -= len;
+        if (block_.size() == kChecksumBlockSize) {
+            status_ = FlushBlock();
+        }
+    }
+    return status_;
+}
+
+RetCode Embedder::WriteBody(const uint8_t* data, std::size_t size) {
+    if (size > cells_ - next_cell_) {
+        return RetCode::kInsufficientCapacity;
+    }
+    for (std::size_t i = 0; i < size; ++i) {
+        carrier_->WriteCell(next_cell_++, data[i]);
+    }
+    return RetCode::kSuccess;
+}
+
+RetCode Embedder::FlushBlock() {
+    if (block_.empty()) {
+        return RetCode::kSuccess;
+    }
+    AppendInt<uint32_t>(block_, Crc32(block_.data(), block_.size()));
+    RetCode rc = WriteBody(block_.data(), block_.size());
+    block_.clear();
+    return rc;
+}
+
+RetCode Embedder::Finish() {
+    if (finished_) {
+        return RetCode::kInvalidArgument;
+    }
+    finished_ = true;
+    if ((status_ == RetCode::kSuccess) && (flags_ & kFlagChecksums)) {
+        status_ = FlushBlock();
+    }
+    if (status_ != RetCode::kSuccess) {
+        return status_;
+    }
+    std::vector<uint8_t> header;
+    AppendFrameHeader(header, flags_, kPayloadHeaderSize,
+                 static_cast<uint32_t>(length_));
+    for (std::size_t cell = 0; cell < header.size(); ++cell) {
+        carrier_->WriteCell(cell, header[cell]);
+    }
+    return RetCode::kSuccess;
+}
+
+RetCode ExtractPayload(const boost::gil::rgb8_image_t& image,
+                       std::vector<uint8_t>& payload) {
+    PayloadHeader header;
+    return ExtractPayload(image, payload, header);
+}
+
+RetCode ExtractPayload(const boost::gil::rgb8_image_t& image,
+                       std::vector<uint8_t>& payload, PayloadHeader& header) {
+    DamageReport damage;
+    return ExtractPayload(image, payload, header, damage);
+}
+
+RetCode ExtractPayload(const Carrier& carrier, std::vector<uint8_t>& payload) {
+    PayloadHeader header;
+    DamageReport damage;
+    return ExtractPayload(carrier, payload, header, damage);
+}
+
+RetCode ExtractPayload(const Carrier& carrier, std::vector<uint8_t>& payload,
+                       PayloadHeader& header, DamageReport& damage,
+                       const std::optional<StegKey>& key) {
+    std::optional<KeyedPlacement> placement;
+    if (key) {
+        placement.emplace(*key);
+    }
+    damage = DamageReport{};
+    return ExtractFrame(carrier, payload, header, damage,
+                        placement ? &*placement : nullptr);
+}
+
+RetCode ExtractPayload(const boost::gil::rgb8_image_t& image,
+                       std::vector<uint8_t>& payload, PayloadHeader& header,
+                       DamageReport& damage,
+                       const std::optional<StegKey>& key) {
+    RetCode rc =
+        ExtractPayload(ImageCarrier(image), payload, header, damage, key);
+    if (rc != RetCode::kPayloadNotFound) {
+        return rc;
+    }
+
+    /* the header magic doubles as an orientation marker, so the payload of
+     * a rotated or mirrored image turns up once that turn is undone */
+    for (int i = 1; i < kOrientationCount; ++i) {
+        Orientation orientation = static_cast<Orientation>(i);
+        const boost::gil::rgb8_image_t kTurned = Normalize(image, orientation);
+        RetCode turned_rc = ExtractPayload(ImageCarrier(kTurned), payload,
+                                           header, damage, key);
+        if (turned_rc != RetCode::kPayloadNotFound) {
+            damage.orientation = orientation;
+            return turned_rc;
+        }
+    }
+    return rc;
+}
+
+/* payload bytes read from the image at a time when there are no checksum
+ * blocks to go by */
+static const std::size_t kExtractChunkSize = 4096;
+
+Extractor::Extractor(const boost::gil::rgb8_image_t& image,
+                     const std::optional<StegKey>& key)
+    : owned_(std::make_unique<const ImageCarrier>(image)),
+      carrier_(owned_.get()),
+      image_(&image) {
+    Start(key);
+}
+
+Extractor::Extractor(const Carrier& carrier,
+                     const std::optional<StegKey>& key)
+    : carrier_(&carrier) {
+    Start(key);
+}
+
+void Extractor::Start(const std::optional<StegKey>& key) {
+    const uint8_t kStreamable = kFlagCompressed | kFlagChecksums;
+    const std::size_t kCapacity = carrier_->CellCount();
+    std::size_t header_size = 0;
+    if ((kCapacity >= kPayloadHeaderSize) &&
+        (ReadFrameHeader(*carrier_, 0, header_, header_size) ==
+         RetCode::kSuccess) &&
+        !(header_.flags & ~kStreamable)) {
+        if ((header_size > kCapacity) ||
+            (FrameBodySize(header_.length, header_.flags) >
+             kCapacity - header_size)) {
+            status_ = RetCode::kInvalidPayload;
+            return;
+        }
+        next_cell_ = header_size;
+        remaining_ = header_.length;
+        return;
+    }
+
+    /* copies, sync markers, and a turned image all need the whole picture */
+    DamageReport damage;
+    status_ = image_ ? ExtractPayload(*image_, buffer_, header_, damage, key)
+                     : ExtractPayload(*carrier_, buffer_, header_, damage, key);
+    if ((status_ != RetCode::kSuccess) &&
+        (status_ != RetCode::kPayloadDamaged)) {
+        buffer_.clear();
+    }
+    char* begin = reinterpret_cast<char*>(buffer_.data());
+    setg(begin, begin, begin + buffer_.size());
+}
+
+std::size_t Extractor::Read(uint8_t* data, std::size_t size) {
+    return static_cast<std::size_t>(
+        sgetn(reinterpret_cast<char*>(data),
+              static_cast<std::streamsize>(size)));
+}
+
+Extractor::int_type Extractor::underflow() {
+    if ((gptr() == egptr()) && !Fill()) {
+        return traits_type::eof();
+    }
+    return traits_type::to_int_type(*gptr());
+}
+
+/* loads the next checksum block or chunk of the payload into the buffer */
+bool Extractor::Fill() {
+    if ((remaining_ == 0) || ((status_ != RetCode::kSuccess) &&
+                              (status_ != RetCode::kPayloadDamaged))) {
+        return false;
+    }
+    if (header_.flags & kFlagChecksums) {
+        std::size_t len = std::min(kChecksumBlockSize, remaining_);
+        buffer_ = ReadCells(*carrier_, next_cell_, len + kChecksumSize);
+        if (Crc32(buffer_.data(), len) != ReadInt<uint32_t>(buffer_, len)) {
+            status_ = RetCode::kPayloadDamaged;
+        }
+        buffer_.resize(len);
+    } else {
+        buffer_ = ReadCells(*carrier_, next_cell_,
+                            std::min(kExtractChunkSize, remaining_));
+    }
+    remaining_ -= buffer_.size();
+    char* begin = reinterpret_cast<char*>(buffer_.data());
+    setg(begin, begin, begin + buffer_.size());
+    return true;
+}
+
+}  // namespace steganography
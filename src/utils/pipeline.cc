@@ -0,0 +1,192 @@
+#include "utils/pipeline.hpp"
+
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <optional>
+#include <string>
+#include <vector>
+
+#include "utils/compression.hpp"
+#include "utils/crypto.hpp"
+#include "core/ecc.hpp"
+#include "utils/key.hpp"
+#include "utils/payload.hpp"
+
+namespace steganography {
+
+Pipeline& Pipeline::Compress() {
+    stages_.push_back(PipelineStage::kCompress);
+    return *this;
+}
+
+Pipeline& Pipeline::Encrypt(const std::string& password) {
+    stages_.push_back(PipelineStage::kEncrypt);
+    password_ = password;
+    return *this;
+}
+
+Pipeline& Pipeline::Ecc(EccScheme scheme) {
+    if (scheme == EccScheme::kHamming) {
+        stages_.push_back(PipelineStage::kHamming);
+    }
+    return *this;
+}
+
+Pipeline& Pipeline::Interleave() {
+    stages_.push_back(PipelineStage::kInterleave);
+    return *this;
+}
+
+RetCode Pipeline::Apply(const std::vector<uint8_t>& data,
+                        std::vector<uint8_t>& out) const {
+    if (stages_.size() > kMaxPipelineStages) {
+        return RetCode::kInvalidArgument;
+    }
+    out = data;
+    for (PipelineStage stage : stages_) {
+        switch (stage) {
+            case PipelineStage::kCompress:
+                out = steganography::Compress(out);
+                break;
+            case PipelineStage::kEncrypt: {
+                /* the salt goes in front of the sealed data */
+                std::vector<uint8_t> salt = RandomBytes(kSaltSize);
+                std::vector<uint8_t> sealed;
+                if (salt.size() == kSaltSize) {
+                    sealed = Seal(DerivePasswordKey(password_, salt), out);
+                }
+                if (sealed.empty()) {
+                    Wipe(out);
+                    return RetCode::kIoError;
+                }
+                out = salt;
+                out.insert(out.end(), sealed.cbegin(), sealed.cend());
+                break;
+            }
+            case PipelineStage::kHamming:
+                out = HammingEncode(out);
+                break;
+            case PipelineStage::kInterleave:
+                out = steganography::Interleave(out, kInterleaveDepth);
+                break;
+        }
+    }
+    return RetCode::kSuccess;
+}
+
+RetCode Pipeline::Embed(Carrier& carrier, const std::vector<uint8_t>& payload,
+                        const EmbedConfig& config) const {
+    std::vector<uint8_t> data;
+    RetCode rc = Apply(payload, data);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    EmbedConfig staged = config;
+    staged.stages.clear();
+    for (PipelineStage stage : stages_) {
+        staged.stages.push_back(static_cast<uint8_t>(stage));
+    }
+    return EmbedPayload(carrier, data, staged);
+}
+
+RetCode Pipeline::Embed(boost::gil::rgb8_image_t& image,
+                        const std::vector<uint8_t>& payload,
+                        const EmbedConfig& config) const {
+    ImageCarrier carrier(image);
+    return Embed(carrier, payload, config);
+}
+
+RetCode UndoPipeline(const std::vector<uint8_t>& stages,
+                     const std::vector<uint8_t>& data,
+                     std::vector<uint8_t>& out, const std::string& password) {
+    std::vector<uint8_t> undone = data;
+    for (auto it = stages.crbegin(); it != stages.crend(); ++it) {
+        switch (static_cast<PipelineStage>(*it)) {
+            case PipelineStage::kCompress: {
+                std::vector<uint8_t> inflated;
+                if (!Decompress(undone, inflated)) {
+                    return RetCode::kInvalidPayload;
+                }
+                undone.swap(inflated);
+                break;
+            }
+            case PipelineStage::kEncrypt: {
+                if (undone.size() < kSaltSize + kSealOverhead) {
+                    return RetCode::kInvalidPayload;
+                }
+                std::vector<uint8_t> salt(undone.cbegin(),
+                                          undone.cbegin() + kSaltSize);
+                std::vector<uint8_t> sealed(undone.cbegin() + kSaltSize,
+                                            undone.cend());
+                if (!Open(DerivePasswordKey(password, salt), sealed, undone)) {
+                    return RetCode::kAuthenticationFailed;
+                }
+                break;
+            }
+            case PipelineStage::kHamming: {
+                /* the padding is always less than a codeword */
+                std::size_t corrected = 0;
+                undone = HammingDecode(
+                    undone, undone.size() * 8 / kHammingBitsPerByte,
+                    corrected);
+                break;
+            }
+            case PipelineStage::kInterleave:
+                undone = Deinterleave(undone, kInterleaveDepth);
+                break;
+            default:
+                return RetCode::kUnsupportedFormat;
+        }
+    }
+    out.swap(undone);
+    return RetCode::kSuccess;
+}
+
+RetCode ExtractPipeline(const Carrier& carrier, std::vector<uint8_t>& payload,
+                        const std::string& password) {
+    PayloadHeader header;
+    DamageReport damage;
+    return ExtractPipeline(carrier, payload, header, damage, password);
+}
+
+/* undoes the stages on a payload extracted with rc */
+static RetCode UndoExtracted(RetCode rc, const PayloadHeader& header,
+                             std::vector<uint8_t>& payload,
+                             const std::string& password) {
+    if ((rc != RetCode::kSuccess) && (rc != RetCode::kPayloadDamaged)) {
+        /* no payload fails as slowly as a wrong password would */
+        if (!password.empty()) {
+            SpendPasswordKey(password);
+        }
+        return rc;
+    }
+    RetCode undo_rc = UndoPipeline(header.stages, payload, payload, password);
+    return (undo_rc != RetCode::kSuccess) ? undo_rc : rc;
+}
+
+RetCode ExtractPipeline(const Carrier& carrier, std::vector<uint8_t>& payload,
+                        PayloadHeader& header, DamageReport& damage,
+                        const std::string& password,
+                        const std::optional<StegKey>& key) {
+    RetCode rc = ExtractPayload(carrier, payload, header, damage, key);
+    return UndoExtracted(rc, header, payload, password);
+}
+
+RetCode ExtractPipeline(const boost::gil::rgb8_image_t& image,
+                        std::vector<uint8_t>& payload,
+                        const std::string& password) {
+    PayloadHeader header;
+    DamageReport damage;
+    return ExtractPipeline(image, payload, header, damage, password);
+}
+
+RetCode ExtractPipeline(const boost::gil::rgb8_image_t& image,
+                        std::vector<uint8_t>& payload, PayloadHeader& header,
+                        DamageReport& damage, const std::string& password,
+                        const std::optional<StegKey>& key) {
+    RetCode rc = ExtractPayload(image, payload, header, damage, key);
+    return UndoExtracted(rc, header, payload, password);
+}
+
+}  // namespace steganography
@@ -0,0 +1,114 @@
+#include "utils/png_chunk.hpp"
+
+#include <zlib.h>
+
+#include <algorithm>
+#include <cstddef>
+#include <cstdint>
+#include <filesystem>
+#include <fstream>
+#include <iterator>
+#include <string>
+#include <vector>
+
+#include "utils/bytes.hpp"
+
+namespace steganography {
+
+static const std::vector<uint8_t> kPngSignature = {0x89, 0x50, 0x4E, 0x47,
+                                                   0x0D, 0x0A, 0x1A, 0x0A};
+
+static RetCode ReadPng(const std::string& filename, std::vector<uint8_t>& png) {
+    std::ifstream ifs(filename, std::ifstream::binary);
+    if (!ifs.is_open()) {
+        return std::filesystem::exists(filename) ? RetCode::kIoError
+                                                 : RetCode::kFileNotFound;
+    }
+    png.assign(std::istreambuf_iterator<char>(ifs),
+               std::istreambuf_iterator<char>());
+    if ((png.size() < kPngSignature.size()) ||
+        !std::equal(kPngSignature.cbegin(), kPngSignature.cend(),
+                    png.cbegin())) {
+        return RetCode::kInvalidFileFormat;
+    }
+    return RetCode::kSuccess;
+}
+
+static void AppendChunk(std::vector<uint8_t>& png, const std::string& type,
+                        const std::vector<uint8_t>& data) {
+    AppendInt<uint32_t>(png, static_cast<uint32_t>(data.size()));
+    const std::size_t kTypeOffset = png.size();
+    png.insert(png.end(), type.cbegin(), type.cend());
+    png.insert(png.end(), data.cbegin(), data.cend());
+    AppendInt<uint32_t>(
+        png, static_cast<uint32_t>(
+                 crc32(0, png.data() + kTypeOffset,
+                       static_cast<uInt>(png.size() - kTypeOffset))));
+}
+
+RetCode ReadCarrierChunk(const std::string& filename,
+                         std::vector<uint8_t>& data) {
+    std::vector<uint8_t> png;
+    RetCode rc = ReadPng(filename, png);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    std::size_t offset = kPngSignature.size();
+    while (offset + 12 <= png.size()) {
+        const std::size_t kLength = ReadInt<uint32_t>(png, offset);
+        const std::size_t kData = offset + 8;
+        if (kLength > png.size() - kData - 4) {
+            return RetCode::kInvalidFileFormat;
+        }
+        const std::string kType(png.cbegin() + offset + 4,
+                                png.cbegin() + offset + 8);
+        if (kType == kCarrierChunkType) {
+            data.assign(png.cbegin() + kData, png.cbegin() + kData + kLength);
+            return RetCode::kSuccess;
+        }
+        if (kType == "IEND") {
+            break;
+        }
+        offset = kData + kLength + 4; /* past the CRC */
+    }
+    return RetCode::kPayloadNotFound;
+}
+
+RetCode WriteCarrierChunk(const std::string& filename,
+                          const std::vector<uint8_t>& data) {
+    std::vector<uint8_t> png;
+    RetCode rc = ReadPng(filename, png);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    std::vector<uint8_t> output(kPngSignature.cbegin(), kPngSignature.cend());
+    std::size_t offset = kPngSignature.size();
+    bool ended = false;
+    while (!ended && (offset + 12 <= png.size())) {
+        const std::size_t kLength = ReadInt<uint32_t>(png, offset);
+        const std::size_t kEnd = offset + 12 + kLength;
+        if (kEnd > png.size()) {
+            return RetCode::kInvalidFileFormat;
+        }
+        const std::string kType(png.cbegin() + offset + 4,
+                                png.cbegin() + offset + 8);
+        if (kType == "IEND") {
+            AppendChunk(output, kCarrierChunkType, data);
+            ended = true;
+        }
+        if (kType != kCarrierChunkType) {
+            output.insert(output.end(), png.cbegin() + offset,
+                          png.cbegin() + kEnd);
+        }
+        offset = kEnd;
+    }
+    if (!ended) {
+        return RetCode::kInvalidFileFormat;
+    }
+    std::ofstream ofs(filename, std::ofstream::binary);
+    ofs.write(reinterpret_cast<const char*>(output.data()),
+              static_cast<std::streamsize>(output.size()));
+    return ofs ? RetCode::kSuccess : RetCode::kIoError;
+}
+
+}  // namespace steganography
@@ -0,0 +1,219 @@
+#include "utils/pnm.hpp"
+
+#include <algorithm>
+#include <boost/gil.hpp>
+#include <cctype>
+#include <cstddef>
+#include <cstdint>
+#include <fstream>
+#include <string>
+#include <vector>
+
+namespace steganography {
+
+/* the fields at the start of every Netpbm file */
+struct PnmHeader {
+    char kind = 0; /* the digit after the P, 1 to 6 */
+    uint64_t width = 0;
+    uint64_t height = 0;
+    uint64_t maxval = 1; /* the largest sample, 1 for bitmaps */
+};
+
+/* skips whitespace and comments, which run from # to the end of the line */
+static void SkipSpace(std::ifstream& ifs) {
+    while (ifs) {
+        int c = ifs.peek();
+        if (c == '#') {
+            std::string comment;
+            std::getline(ifs, comment);
+        } else if (std::isspace(c)) {
+            ifs.get();
+        } else {
+            return;
+        }
+    }
+}
+
+/* a decimal number after any whitespace, false when there is none */
+static bool ReadNumber(std::ifstream& ifs, uint64_t& value) {
+    SkipSpace(ifs);
+    if (!std::isdigit(ifs.peek())) {
+        return false;
+    }
+    value = 0;
+    while (std::isdigit(ifs.peek())) {
+        value = value * 10 + static_cast<uint64_t>(ifs.get() - '0');
+        if (value > UINT32_MAX) {
+            return false;
+        }
+    }
+    return true;
+}
+
+/* leaves the stream at the first raster byte of the raw forms */
+static bool ReadHeader(std::ifstream& ifs, PnmHeader& header) {
+    if ((ifs.get() != 'P') || !ifs) {
+        return false;
+    }
+    header.kind = static_cast<char>(ifs.get());
+    if ((header.kind < '1') || (header.kind > '6')) {
+        return false;
+    }
+    const bool kBitmap = (header.kind == '1') || (header.kind == '4');
+    if (!ReadNumber(ifs, header.width) || !ReadNumber(ifs, header.height) ||
+        (!kBitmap && !ReadNumber(ifs, header.maxval))) {
+        return false;
+    }
+    if ((header.width == 0) || (header.height == 0) || (header.maxval == 0) ||
+        (header.maxval > 65535)) {
+        return false;
+    }
+
+    /* exactly one whitespace byte separates the header from the raster */
+    return std::isspace(ifs.get()) && ifs;
+}
+
+/* the next sample of a graymap or pixmap, scaled to 8 bits */
+static bool ReadSample(std::ifstream& ifs, const PnmHeader& header,
+                       uint8_t& sample) {
+    uint64_t value = 0;
+    if (header.kind <= '3') {
+        if (!ReadNumber(ifs, value)) {
+            return false;
+        }
+    } else if (header.maxval < 256) {
+        value = static_cast<uint64_t>(ifs.get());
+    } else {
+        value = static_cast<uint64_t>(ifs.get()) << 8;
+        value |= static_cast<uint64_t>(ifs.get());
+    }
+    if (!ifs || (value > header.maxval)) {
+        return false;
+    }
+    sample = static_cast<uint8_t>((value * 255 + header.maxval / 2) /
+                                  header.maxval);
+    return true;
+}
+
+/* whether the pixel of a bitmap is set, set pixels are black */
+static bool ReadBit(std::ifstream& ifs, const PnmHeader& header,
+                    std::size_t col, uint8_t& byte, bool& set) {
+    if (header.kind == '1') {
+        /* the digits need not be separated by whitespace */
+        SkipSpace(ifs);
+        int c = ifs.get();
+        set = (c == '1');
+        return ifs && ((c == '0') || (c == '1'));
+    }
+    if (col % 8 == 0) {
+        byte = static_cast<uint8_t>(ifs.get());
+    }
+    set = (byte >> (7 - col % 8)) & 1;
+    return static_cast<bool>(ifs);
+}
+
+bool ReadPnm(const std::string& filename, boost::gil::rgb8_image_t& image) {
+    std::ifstream ifs(filename, std::ifstream::binary);
+    PnmHeader header;
+    if (!ReadHeader(ifs, header)) {
+        return false;
+    }
+    image = boost::gil::rgb8_image_t(static_cast<std::ptrdiff_t>(header.width),
+                                     static_cast<std::ptrdiff_t>(header.height));
+    auto view = boost::gil::view(image);
+    const bool kColor = (header.kind == '3') || (header.kind == '6');
+    const bool kBitmap = (header.kind == '1') || (header.kind == '4');
+    for (std::ptrdiff_t y = 0; y < view.height(); ++y) {
+        uint8_t byte = 0; /* raw bitmap rows start on a byte boundary */
+        for (std::ptrdiff_t x = 0; x < view.width(); ++x) {
+            uint8_t samples[3] = {0, 0, 0};
+            if (kBitmap) {
+                bool set = false;
+                if (!ReadBit(ifs, header, static_cast<std::size_t>(x), byte,
+                             set)) {
+                    return false;
+                }
+                samples[0] = set ? 0 : 255;
+            } else if (!ReadSample(ifs, header, samples[0]) ||
+                       (kColor && (!ReadSample(ifs, header, samples[1]) ||
+                                   !ReadSample(ifs, header, samples[2])))) {
+                return false;
+            }
+            for (int c = 0; c < 3; ++c) {
+                view(x, y)[c] = kColor ? samples[c] : samples[0];
+            }
+        }
+    }
+    return true;
+}
+
+bool ReadPnmHeader(const std::string& filename, uint64_t& width,
+                   uint64_t& height, uint64_t& pixel_bytes) {
+    std::ifstream ifs(filename, std::ifstream::binary);
+    PnmHeader header;
+    if (!ReadHeader(ifs, header)) {
+        return false;
+    }
+    width = header.width;
+    height = header.height;
+    const bool kColor = (header.kind == '3') || (header.kind == '6');
+    pixel_bytes = (kColor ? 3 : 1) * ((header.maxval > 255) ? 2 : 1);
+    return true;
+}
+
+char PnmKind(const std::string& filename) {
+    std::string lower = filename;
+    std::transform(lower.begin(), lower.end(), lower.begin(),
+                   [](unsigned char c) { return std::tolower(c); });
+    if (lower.ends_with(".pbm")) {
+        return '4';
+    }
+    return lower.ends_with(".pgm") ? '5' : '6';
+}
+
+/* the Rec. 601 luma of a pixel, rounded */
+static uint8_t Luma(const boost::gil::rgb8_pixel_t& pixel) {
+    return static_cast<uint8_t>(
+        (299 * pixel[0] + 587 * pixel[1] + 114 * pixel[2] + 500) / 1000);
+}
+
+bool WritePnm(const boost::gil::rgb8_image_t& image,
+              const std::string& filename) {
+    auto view = boost::gil::const_view(image);
+    const char kKind = PnmKind(filename);
+    const std::size_t kWidth = static_cast<std::size_t>(view.width());
+    std::ofstream ofs(filename, std::ofstream::binary);
+    ofs << 'P' << kKind << '\n' << view.width() << " " << view.height()
+        << '\n';
+    if (kKind != '4') {
+        ofs << "255\n";
+    }
+    /* bitmap rows are padded to whole bytes */
+    const std::size_t kRowBytes = (kKind == '4')   ? (kWidth + 7) / 8
+                                  : (kKind == '5') ? kWidth
+                                                   : kWidth * 3;
+    std::vector<char> row(kRowBytes);
+    for (std::ptrdiff_t y = 0; y < view.height(); ++y) {
+        std::fill(row.begin(), row.end(), 0);
+        for (std::size_t x = 0; x < kWidth; ++x) {
+            const auto& pixel = view(static_cast<std::ptrdiff_t>(x), y);
+            if (kKind == '4') {
+                /* set bits are black */
+                if (Luma(pixel) < 128) {
+                    row[x / 8] = static_cast<char>(row[x / 8] |
+                                                   (0x80 >> (x % 8)));
+                }
+            } else if (kKind == '5') {
+                row[x] = static_cast<char>(Luma(pixel));
+            } else {
+                for (std::size_t c = 0; c < 3; ++c) {
+                    row[x * 3 + c] = static_cast<char>(pixel[c]);
+                }
+            }
+        }
+        ofs.write(row.data(), static_cast<std::streamsize>(row.size()));
+    }
+    return static_cast<bool>(ofs);
+}
+
+}  // namespace steganography
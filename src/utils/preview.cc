@@ -0,0 +1,310 @@
+#include "utils/preview.hpp"
+
+#include <sys/ioctl.h>
+#include <unistd.h>
+#include <zlib.h>
+
+#include <algorithm>
+#include <boost/gil.hpp>
+#include <boost/gil/extension/numeric/resample.hpp>
+#include <boost/gil/extension/numeric/sampler.hpp>
+#include <cmath>
+#include <cstddef>
+#include <cstdint>
+#include <cstdlib>
+#include <string>
+#include <vector>
+
+#include "utils/bytes.hpp"
+#include "utils/image_io.hpp"
+#include "utils/lsb.hpp"
+
+namespace steganography {
+
+static const int kDefaultColumns = 80;
+static const int kCellPixels = 10; /* assumed cell width in pixels */
+static const std::size_t kKittyChunk = 4096;
+static const int kUnboundedRows = 1 << 20; /* halfblock output scrolls */
+
+static std::string GetEnv(const char* name) {
+    const char* value = std::getenv(name);
+    return (value == nullptr) ? "" : value;
+}
+
+static PreviewProtocol DetectProtocol() {
+    std::string term = GetEnv("TERM");
+    std::string program = GetEnv("TERM_PROGRAM");
+    if (!GetEnv("KITTY_WINDOW_ID").empty() ||
+        (term.find("kitty") != std::string::npos) || (program == "WezTerm")) {
+        return PreviewProtocol::kKitty;
+    }
+    if (program == "iTerm.app") {
+        return PreviewProtocol::kIterm;
+    }
+    for (const char* sixel : {"sixel", "mlterm", "foot", "yaft", "contour"}) {
+        if (term.find(sixel) != std::string::npos) {
+            return PreviewProtocol::kSixel;
+        }
+    }
+    return PreviewProtocol::kHalfblock;
+}
+
+static int TerminalColumns() {
+    winsize size{};
+    if ((ioctl(STDOUT_FILENO, TIOCGWINSZ, &size) == 0) && (size.ws_col > 0)) {
+        return size.ws_col;
+    }
+    int columns = std::atoi(GetEnv("COLUMNS").c_str());
+    return (columns > 0) ? columns : kDefaultColumns;
+}
+
+static boost::gil::rgb8_image_t ExtractBits(
+    const boost::gil::rgb8c_view_t& view, int bits) {
+    boost::gil::rgb8_image_t hidden(view.width(), view.height());
+    auto dst = boost::gil::view(hidden);
+    for (std::ptrdiff_t row = 0; row < view.height(); ++row) {
+        UnmergeRow(&view(0, row)[0], &dst(0, row)[0],
+                   static_cast<std::size_t>(view.width()) * 3, bits);
+    }
+    return hidden;
+}
+
+/* scales the image down to fit the given size keeping its aspect ratio,
+ * never up */
+static boost::gil::rgb8_image_t Downscale(const boost::gil::rgb8c_view_t& view,
+                                          std::ptrdiff_t max_width,
+                                          std::ptrdiff_t max_height) {
+    double scale = std::min({1.0,
+                             static_cast<double>(max_width) /
+                                 static_cast<double>(view.width()),
+                             static_cast<double>(max_height) /
+                                 static_cast<double>(view.height())});
+    std::ptrdiff_t width = std::max<std::ptrdiff_t>(
+        1, std::lround(static_cast<double>(view.width()) * scale));
+    std::ptrdiff_t height = std::max<std::ptrdiff_t>(
+        1, std::lround(static_cast<double>(view.height()) * scale));
+    boost::gil::rgb8_image_t scaled(width, height);
+    boost::gil::resize_view(view, boost::gil::view(scaled),
+                            boost::gil::bilinear_sampler());
+    return scaled;
+}
+
+static void AppendPngChunk(std::vector<uint8_t>& png, const char* type,
+                           const std::vector<uint8_t>& data) {
+    AppendInt<uint32_t>(png, static_cast<uint32_t>(data.size()));
+    std::vector<uint8_t> body(type, type + 4);
+    body.insert(body.end(), data.begin(), data.end());
+    png.insert(png.end(), body.begin(), body.end());
+    AppendInt<uint32_t>(
+        png, static_cast<uint32_t>(crc32(0, body.data(),
+                                         static_cast<uInt>(body.size()))));
+}
+
+/* kitty and iTerm take compressed image files rather than raw pixels */
+static std::vector<uint8_t> EncodePng(const boost::gil::rgb8c_view_t& view) {
+    std::vector<uint8_t> raw;
+    for (std::ptrdiff_t row = 0; row < view.height(); ++row) {
+        raw.push_back(0); /* no filter */
+        for (std::ptrdiff_t col = 0; col < view.width(); ++col) {
+            for (int i = 0; i < 3; ++i) {
+                raw.push_back(view(col, row)[i]);
+            }
+        }
+    }
+    uLongf len = compressBound(static_cast<uLong>(raw.size()));
+    std::vector<uint8_t> idat(len);
+    compress2(idat.data(), &len, raw.data(), static_cast<uLong>(raw.size()),
+              Z_BEST_SPEED);
+    idat.resize(len);
+
+    std::vector<uint8_t> header;
+    AppendInt<uint32_t>(header, static_cast<uint32_t>(view.width()));
+    AppendInt<uint32_t>(header, static_cast<uint32_t>(view.height()));
+    header.insert(header.end(), {8, 2, 0, 0, 0}); /* 8-bit truecolor */
+
+    std::vector<uint8_t> png = {0x89, 'P', 'N', 'G', '\r', '\n', 0x1A, '\n'};
+    AppendPngChunk(png, "IHDR", header);
+    AppendPngChunk(png, "IDAT", idat);
+    AppendPngChunk(png, "IEND", {});
+    return png;
+}
+
+static std::string Base64(const std::vector<uint8_t>& data) {
+    const char* kAlphabet =
+        "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    std::string encoded;
+    for (std::size_t i = 0; i < data.size(); i += 3) {
+        uint32_t group = static_cast<uint32_t>(data[i]) << 16;
+        if (i + 1 < data.size()) {
+            group |= static_cast<uint32_t>(data[i + 1]) << 8;
+        }
+        if (i + 2 < data.size()) {
+            group |= data[i + 2];
+        }
+        encoded += kAlphabet[(group >> 18) & 0x3F];
+        encoded += kAlphabet[(group >> 12) & 0x3F];
+        encoded +=
+            (i + 1 < data.size()) ? kAlphabet[(group >> 6) & 0x3F] : '=';
+        encoded += (i + 2 < data.size()) ? kAlphabet[group & 0x3F] : '=';
+    }
+    return encoded;
+}
+
+/* two pixels per cell, the upper as foreground and the lower as background */
+std::vector<std::string> RenderHalfblocks(const boost::gil::rgb8c_view_t& view,
+                                          int columns, int rows) {
+    boost::gil::rgb8_image_t scaled = Downscale(view, columns, rows * 2);
+    auto pixels = boost::gil::const_view(scaled);
+    auto color = [](const boost::gil::rgb8_pixel_t& pix) {
+        return std::to_string(pix[0]) + ";" + std::to_string(pix[1]) + ";" +
+               std::to_string(pix[2]);
+    };
+    std::vector<std::string> lines;
+    for (std::ptrdiff_t row = 0; row < pixels.height(); row += 2) {
+        std::string line;
+        for (std::ptrdiff_t col = 0; col < pixels.width(); ++col) {
+            line += "\x1b[38;2;" + color(pixels(col, row)) + "m";
+            if (row + 1 < pixels.height()) {
+                line += "\x1b[48;2;" + color(pixels(col, row + 1)) + "m";
+            } else {
+                line += "\x1b[49m";
+            }
+            line += "▀";
+        }
+        lines.push_back(line + "\x1b[0m");
+    }
+    return lines;
+}
+
+static void WriteKitty(const boost::gil::rgb8c_view_t& view, int columns,
+                       std::ostream& out) {
+    std::string data = Base64(EncodePng(view));
+    for (std::size_t offset = 0; offset < data.size(); offset += kKittyChunk) {
+        bool more = offset + kKittyChunk < data.size();
+        out << "\x1b_G";
+        if (offset == 0) {
+            out << "a=T,f=100,c=" << columns << ",";
+        }
+        out << "m=" << (more ? 1 : 0) << ";"
+            << data.substr(offset, kKittyChunk) << "\x1b\\";
+    }
+    out << "\n";
+}
+
+static void WriteIterm(const boost::gil::rgb8c_view_t& view, int columns,
+                       std::ostream& out) {
+    std::vector<uint8_t> png = EncodePng(view);
+    out << "\x1b]1337;File=inline=1;size=" << png.size()
+        << ";width=" << columns << ";preserveAspectRatio=1:" << Base64(png)
+        << "\a\n";
+}
+
+/* six pixel high bands, one pass per palette color in the band */
+static void WriteSixel(const boost::gil::rgb8c_view_t& view,
+                       std::ostream& out) {
+    /* a 6x6x6 color cube keeps the palette within the common 256 limit */
+    auto index = [](const boost::gil::rgb8_pixel_t& pix) {
+        return (pix[0] * 5 + 127) / 255 * 36 + (pix[1] * 5 + 127) / 255 * 6 +
+               (pix[2] * 5 + 127) / 255;
+    };
+    out << "\x1bPq\"1;1;" << view.width() << ";" << view.height();
+    for (int i = 0; i < 216; ++i) {
+        out << "#" << i << ";2;" << i / 36 * 20 << ";" << i / 6 % 6 * 20 << ";"
+            << i % 6 * 20;
+    }
+
+    for (std::ptrdiff_t band = 0; band < view.height(); band += 6) {
+        std::vector<std::vector<uint8_t>> sixels(
+            216, std::vector<uint8_t>(static_cast<std::size_t>(view.width())));
+        std::vector<bool> used(216, false);
+        for (std::ptrdiff_t bit = 0; (bit < 6) && (band + bit < view.height());
+             ++bit) {
+            for (std::ptrdiff_t col = 0; col < view.width(); ++col) {
+                int color = index(view(col, band + bit));
+                sixels[color][col] |= static_cast<uint8_t>(1 << bit);
+                used[color] = true;
+            }
+        }
+        for (int color = 0; color < 216; ++color) {
+            if (!used[color]) {
+                continue;
+            }
+            out << "#" << color;
+            const std::vector<uint8_t>& line = sixels[color];
+            for (std::size_t col = 0; col < line.size();) {
+                std::size_t run = 1;
+                while ((col + run < line.size()) &&
+                       (line[col + run] == line[col])) {
+                    ++run;
+                }
+                char sixel = static_cast<char>('?' + line[col]);
+                if (run > 3) {
+                    out << "!" << run << sixel;
+                } else {
+                    out << std::string(run, sixel);
+                }
+                col += run;
+            }
+            out << "$";
+        }
+        out << "-";
+    }
+    out << "\x1b\\\n";
+}
+
+RetCode Preview(const std::string& image, const PreviewConfig& config,
+                std::ostream& out) {
+    if ((config.bits < kMinBits) || (config.bits > kMaxBits) ||
+        (config.columns < 0)) {
+        return RetCode::kInvalidArgument;
+    }
+    boost::gil::rgb8_image_t input_img;
+    RetCode rc = LoadImage(image, input_img);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    boost::gil::rgb8_image_t hidden =
+        ExtractBits(boost::gil::const_view(input_img), config.bits);
+
+    int columns = (config.columns > 0) ? config.columns : TerminalColumns();
+    PreviewProtocol protocol = (config.protocol == PreviewProtocol::kAuto)
+                                   ? DetectProtocol()
+                                   : config.protocol;
+
+    if ((protocol == PreviewProtocol::kAuto) ||
+        (protocol == PreviewProtocol::kHalfblock)) {
+        for (const auto& line : RenderHalfblocks(boost::gil::const_view(hidden),
+                                                 columns, kUnboundedRows)) {
+            out << line << "\n";
+        }
+        out.flush();
+        return RetCode::kSuccess;
+    }
+    boost::gil::rgb8_image_t scaled =
+        Downscale(boost::gil::const_view(hidden), columns * kCellPixels,
+                  hidden.height());
+    auto view = boost::gil::const_view(scaled);
+
+    /* graphics are sized in cells, small images are not blown up */
+    int cells = std::min<int>(
+        columns, static_cast<int>((view.width() + kCellPixels - 1) /
+                                  kCellPixels));
+    switch (protocol) {
+        case PreviewProtocol::kSixel:
+            WriteSixel(view, out);
+            break;
+        case PreviewProtocol::kKitty:
+            WriteKitty(view, cells, out);
+            break;
+        case PreviewProtocol::kIterm:
+            WriteIterm(view, cells, out);
+            break;
+        case PreviewProtocol::kAuto:
+        case PreviewProtocol::kHalfblock:
+            break; /* handled above */
+    }
+    out.flush();
+    return RetCode::kSuccess;
+}
+
+}  // namespace steganography
@@ -0,0 +1,935 @@
+#include "utils/qr.hpp"
+
+#include <algorithm>
+#include <array>
+#include <bit>
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <cstdlib>
+#include <limits>
+#include <map>
+#include <string>
+#include <utility>
+#include <vector>
+
+#include "utils/image_io.hpp"
+
+namespace steganography {
+
+static const int kMinVersion = 1;
+static const int kMaxVersion = 40;
+static const int kQuietZone = 4;      /* light modules around the symbol */
+static const int kMinModulePixels = 2; /* smaller modules do not survive blur */
+static const int kByteMode = 0x4;
+
+/* per ISO/IEC 18004 table 9, indexed by QrEcc then version */
+static const int kEccCodewordsPerBlock[4][kMaxVersion + 1] = {
+    {0,  7,  10, 15, 20, 26, 18, 20, 24, 30, 18, 20, 24, 26,
+     30, 22, 24, 28, 30, 28, 28, 28, 28, 30, 30, 26, 28, 30,
+     30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30},
+    {0,  10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22,
+     24, 24, 28, 28, 26, 26, 26, 26, 28, 28, 28, 28, 28, 28,
+     28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28},
+    {0,  13, 22, 18, 26, 18, 24, 18, 22, 20, 24, 28, 26, 24,
+     20, 30, 24, 28, 28, 26, 30, 28, 30, 30, 30, 30, 28, 30,
+     30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30},
+    {0,  17, 28, 22, 16, 22, 28, 26, 26, 24, 28, 24, 28, 22,
+     24, 24, 30, 28, 28, 26, 28, 30, 24, 30, 30, 30, 30, 30,
+     30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30},
+};
+static const int kErrorCorrectionBlocks[4][kMaxVersion + 1] = {
+    {0,  1,  1,  1,  1,  1,  2,  2,  2,  2,  4,  4,  4,  4,
+     4,  6,  6,  6,  6,  7,  8,  8,  9,  9,  10, 12, 12, 12,
+     13, 14, 15, 16, 17, 18, 19, 19, 20, 21, 22, 24, 25},
+    {0,  1,  1,  1,  2,  2,  4,  4,  4,  5,  5,  5,  8,  9,
+     9,  10, 10, 11, 13, 14, 16, 17, 17, 18, 20, 21, 23, 25,
+     26, 28, 29, 31, 33, 35, 37, 38, 40, 43, 45, 47, 49},
+    {0,  1,  1,  2,  2,  4,  4,  6,  6,  8,  8,  8,  10, 12,
+     16, 12, 17, 16, 18, 21, 20, 23, 23, 25, 27, 29, 34, 34,
+     35, 38, 40, 43, 45, 48, 51, 53, 56, 59, 62, 65, 68},
+    {0,  1,  1,  2,  4,  4,  4,  5,  6,  8,  8,  11, 11, 16,
+     16, 18, 16, 19, 21, 25, 25, 25, 34, 30, 32, 35, 37, 40,
+     42, 45, 48, 51, 54, 57, 60, 63, 66, 70, 74, 77, 81},
+};
+
+/* the two bit level indicator stored in the format information */
+static const int kEccFormatBits[4] = {1, 0, 3, 2};
+
+/* GF(256) arithmetic over the QR polynomial x^8 + x^4 + x^3 + x^2 + 1 */
+class GaloisField {
+   public:
+    GaloisField() {
+        int x = 1;
+        for (int i = 0; i < 255; ++i) {
+            exp_[i] = static_cast<uint8_t>(x);
+            exp_[i + 255] = static_cast<uint8_t>(x);
+            log_[x] = i;
+            x <<= 1;
+            if (x & 0x100) {
+                x ^= 0x11D;
+            }
+        }
+    }
+
+    uint8_t Mul(uint8_t a, uint8_t b) const {
+        return ((a == 0) || (b == 0)) ? 0 : exp_[log_[a] + log_[b]];
+    }
+    uint8_t Div(uint8_t a, uint8_t b) const {
+        return (a == 0) ? 0 : exp_[log_[a] + 255 - log_[b]];
+    }
+    uint8_t Inverse(uint8_t a) const { return exp_[255 - log_[a]]; }
+    uint8_t Pow2(int e) const { return exp_[((e % 255) + 255) % 255]; }
+
+   private:
+    std::array<uint8_t, 510> exp_{};
+    std::array<int, 256> log_{};
+};
+
+static const GaloisField& Gf() {
+    static const GaloisField kField;
+    return kField;
+}
+
+/* polynomials hold their highest degree coefficient first */
+using Poly = std::vector<uint8_t>;
+
+static uint8_t PolyEval(const Poly& poly, uint8_t x) {
+    uint8_t y = 0;
+    for (uint8_t coef : poly) {
+        y = Gf().Mul(y, x) ^ coef;
+    }
+    return y;
+}
+
+static Poly PolyMul(const Poly& p, const Poly& q) {
+    Poly product(p.size() + q.size() - 1, 0);
+    for (std::size_t i = 0; i < p.size(); ++i) {
+        for (std::size_t j = 0; j < q.size(); ++j) {
+            product[i + j] ^= Gf().Mul(p[i], q[j]);
+        }
+    }
+    return product;
+}
+
+static Poly PolyScale(const Poly& p, uint8_t x) {
+    Poly scaled(p);
+    for (uint8_t& coef : scaled) {
+        coef = Gf().Mul(coef, x);
+    }
+    return scaled;
+}
+
+static Poly PolyAdd(const Poly& p, const Poly& q) {
+    Poly sum(std::max(p.size(), q.size()), 0);
+    for (std::size_t i = 0; i < p.size(); ++i) {
+        sum[i + sum.size() - p.size()] = p[i];
+    }
+    for (std::size_t i = 0; i < q.size(); ++i) {
+        sum[i + sum.size() - q.size()] ^= q[i];
+    }
+    return sum;
+}
+
+/* Reed-Solomon error correction codewords for one block */
+static Poly ReedSolomonEncode(const Poly& data, int ecc_len) {
+    Poly generator = {1};
+    for (int i = 0; i < ecc_len; ++i) {
+        generator = PolyMul(generator, {1, Gf().Pow2(i)});
+    }
+    Poly remainder(static_cast<std::size_t>(ecc_len), 0);
+    for (uint8_t byte : data) {
+        uint8_t factor = byte ^ remainder.front();
+        remainder.erase(remainder.begin());
+        remainder.push_back(0);
+        for (std::size_t i = 0; i < remainder.size(); ++i) {
+            remainder[i] ^= Gf().Mul(generator[i + 1], factor);
+        }
+    }
+    return remainder;
+}
+
+/* corrects up to ecc_len / 2 byte errors in place, returns false when the
+ * block is damaged beyond repair */
+static bool ReedSolomonCorrect(Poly& block, int ecc_len) {
+    Poly syndromes(static_cast<std::size_t>(ecc_len));
+    bool clean = true;
+    for (int i = 0; i < ecc_len; ++i) {
+        syndromes[i] = PolyEval(block, Gf().Pow2(i));
+        clean = clean && (syndromes[i] == 0);
+    }
+    if (clean) {
+        return true;
+    }
+
+    /* Berlekamp-Massey yields the error locator polynomial */
+    Poly locator = {1};
+    Poly previous = {1};
+    for (int i = 0; i < ecc_len; ++i) {
+        uint8_t delta = syndromes[i];
+        for (std::size_t j = 1; j < locator.size(); ++j) {
+            delta ^= Gf().Mul(locator[locator.size() - 1 - j],
+                              syndromes[i - static_cast<int>(j)]);
+        }
+        previous.push_back(0);
+        if (delta != 0) {
+            if (previous.size() > locator.size()) {
+                Poly next = PolyScale(previous, delta);
+                previous = PolyScale(locator, Gf().Inverse(delta));
+                locator = next;
+            }
+            locator = PolyAdd(locator, PolyScale(previous, delta));
+        }
+    }
+    while (!locator.empty() && (locator.front() == 0)) {
+        locator.erase(locator.begin());
+    }
+    std::size_t errors = locator.size() - 1;
+    if (locator.empty() || (errors * 2 > static_cast<std::size_t>(ecc_len))) {
+        return false;
+    }
+
+    /* Chien search for the error positions */
+    Poly reversed(locator.rbegin(), locator.rend());
+    std::vector<std::size_t> positions;
+    for (std::size_t i = 0; i < block.size(); ++i) {
+        if (PolyEval(reversed, Gf().Pow2(static_cast<int>(i))) == 0) {
+            positions.push_back(block.size() - 1 - i);
+        }
+    }
+    if (positions.size() != errors) {
+        return false;
+    }
+
+    /* Forney's algorithm yields the error magnitudes */
+    std::vector<uint8_t> roots;
+    Poly errata = {1};
+    for (std::size_t pos : positions) {
+        roots.push_back(Gf().Pow2(static_cast<int>(block.size() - 1 - pos)));
+        errata = PolyMul(errata, {roots.back(), 1});
+    }
+    Poly shifted(syndromes.rbegin(), syndromes.rend());
+    shifted.push_back(0);
+    Poly product = PolyMul(shifted, errata);
+    Poly evaluator(product.end() - static_cast<std::ptrdiff_t>(errata.size()),
+                   product.end());
+    for (std::size_t i = 0; i < roots.size(); ++i) {
+        uint8_t inverse = Gf().Inverse(roots[i]);
+        uint8_t derivative = 1;
+        for (std::size_t j = 0; j < roots.size(); ++j) {
+            if (j != i) {
+                derivative =
+                    Gf().Mul(derivative, 1 ^ Gf().Mul(inverse, roots[j]));
+            }
+        }
+        uint8_t y = Gf().Mul(roots[i], PolyEval(evaluator, inverse));
+        block[positions[i]] ^= Gf().Div(y, derivative);
+    }
+
+    for (int i = 0; i < ecc_len; ++i) {
+        if (PolyEval(block, Gf().Pow2(i)) != 0) {
+            return false;
+        }
+    }
+    return true;
+}
+
+struct QrSymbol {
+    int size = 0;
+    std::vector<bool> dark;
+    std::vector<bool> function; /* finder, timing, format, ... modules */
+
+    bool Dark(int x, int y) const { return dark[y * size + x]; }
+    bool Function(int x, int y) const { return function[y * size + x]; }
+    void SetFunction(int x, int y, bool is_dark) {
+        dark[y * size + x] = is_dark;
+        function[y * size + x] = true;
+    }
+};
+
+static int SymbolSize(int version) { return version * 4 + 17; }
+
+static int CharCountBits(int version) { return (version <= 9) ? 8 : 16; }
+
+static std::vector<int> AlignmentPositions(int version) {
+    if (version == 1) {
+        return {};
+    }
+    int count = version / 7 + 2;
+    int step = (version == 32)
+                   ? 26
+                   : (version * 4 + count * 2 + 1) / (count * 2 - 2) * 2;
+    std::vector<int> positions(static_cast<std::size_t>(count));
+    positions[0] = 6;
+    int pos = SymbolSize(version) - 7;
+    for (int i = count - 1; i >= 1; --i, pos -= step) {
+        positions[i] = pos;
+    }
+    return positions;
+}
+
+/* modules left for data and error correction once function patterns are
+ * placed, including the remainder bits */
+static int RawDataModules(int version) {
+    int modules = (16 * version + 128) * version + 64;
+    if (version >= 2) {
+        int align = version / 7 + 2;
+        modules -= (25 * align - 10) * align - 55;
+        if (version >= 7) {
+            modules -= 36;
+        }
+    }
+    return modules;
+}
+
+static int DataCodewords(int version, QrEcc ecc) {
+    int level = static_cast<int>(ecc);
+    return RawDataModules(version) / 8 -
+           kEccCodewordsPerBlock[level][version] *
+               kErrorCorrectionBlocks[level][version];
+}
+
+/* the 5 data bits protected by a BCH(15,5) code and masked */
+static int FormatCodeword(int data) {
+    int rem = data;
+    for (int i = 0; i < 10; ++i) {
+        rem = (rem << 1) ^ ((rem >> 9) * 0x537);
+    }
+    return ((data << 10) | rem) ^ 0x5412;
+}
+
+static void DrawFormatBits(QrSymbol& symbol, int bits) {
+    auto bit = [bits](int i) { return ((bits >> i) & 1) != 0; };
+    for (int i = 0; i <= 5; ++i) {
+        symbol.SetFunction(8, i, bit(i));
+    }
+    symbol.SetFunction(8, 7, bit(6));
+    symbol.SetFunction(8, 8, bit(7));
+    symbol.SetFunction(7, 8, bit(8));
+    for (int i = 9; i < 15; ++i) {
+        symbol.SetFunction(14 - i, 8, bit(i));
+    }
+    for (int i = 0; i < 8; ++i) {
+        symbol.SetFunction(symbol.size - 1 - i, 8, bit(i));
+    }
+    for (int i = 8; i < 15; ++i) {
+        symbol.SetFunction(8, symbol.size - 15 + i, bit(i));
+    }
+    symbol.SetFunction(8, symbol.size - 8, true); /* the dark module */
+}
+
+/* both copies of the format information, in the order DrawFormatBits
+ * places them */
+static std::pair<int, int> ReadFormatBits(const QrSymbol& symbol) {
+    int first = 0;
+    int second = 0;
+    auto set = [](int& bits, int i, bool dark) { bits |= dark ? (1 << i) : 0; };
+    for (int i = 0; i <= 5; ++i) {
+        set(first, i, symbol.Dark(8, i));
+    }
+    set(first, 6, symbol.Dark(8, 7));
+    set(first, 7, symbol.Dark(8, 8));
+    set(first, 8, symbol.Dark(7, 8));
+    for (int i = 9; i < 15; ++i) {
+        set(first, i, symbol.Dark(14 - i, 8));
+    }
+    for (int i = 0; i < 8; ++i) {
+        set(second, i, symbol.Dark(symbol.size - 1 - i, 8));
+    }
+    for (int i = 8; i < 15; ++i) {
+        set(second, i, symbol.Dark(8, symbol.size - 15 + i));
+    }
+    return {first, second};
+}
+
+static void DrawVersionBits(QrSymbol& symbol, int version) {
+    if (version < 7) {
+        return;
+    }
+    int rem = version;
+    for (int i = 0; i < 12; ++i) {
+        rem = (rem << 1) ^ ((rem >> 11) * 0x1F25);
+    }
+    int bits = (version << 12) | rem;
+    for (int i = 0; i < 18; ++i) {
+        bool dark = ((bits >> i) & 1) != 0;
+        int a = symbol.size - 11 + i % 3;
+        int b = i / 3;
+        symbol.SetFunction(a, b, dark);
+        symbol.SetFunction(b, a, dark);
+    }
+}
+
+/* an empty symbol holding only the function patterns of the version, the
+ * format information is reserved but left light */
+static QrSymbol BlankSymbol(int version) {
+    QrSymbol symbol;
+    symbol.size = SymbolSize(version);
+    std::size_t modules = static_cast<std::size_t>(symbol.size * symbol.size);
+    symbol.dark.assign(modules, false);
+    symbol.function.assign(modules, false);
+
+    for (int i = 0; i < symbol.size; ++i) {
+        symbol.SetFunction(6, i, i % 2 == 0);
+        symbol.SetFunction(i, 6, i % 2 == 0);
+    }
+
+    /* finder patterns along with their light separators */
+    const std::array<std::pair<int, int>, 3> kFinders = {
+        std::make_pair(3, 3), std::make_pair(symbol.size - 4, 3),
+        std::make_pair(3, symbol.size - 4)};
+    for (const auto& [cx, cy] : kFinders) {
+        for (int dy = -4; dy <= 4; ++dy) {
+            for (int dx = -4; dx <= 4; ++dx) {
+                int x = cx + dx;
+                int y = cy + dy;
+                if ((x < 0) || (x >= symbol.size) || (y < 0) ||
+                    (y >= symbol.size)) {
+                    continue;
+                }
+                int dist = std::max(std::abs(dx), std::abs(dy));
+                symbol.SetFunction(x, y, (dist != 2) && (dist != 4));
+            }
+        }
+    }
+
+    std::vector<int> align = AlignmentPositions(version);
+    const std::size_t kLast = align.empty() ? 0 : align.size() - 1;
+    for (std::size_t i = 0; i < align.size(); ++i) {
+        for (std::size_t j = 0; j < align.size(); ++j) {
+            if (((i == 0) && (j == 0)) || ((i == 0) && (j == kLast)) ||
+                ((i == kLast) && (j == 0))) {
+                continue; /* overlaps a finder pattern */
+            }
+            for (int dy = -2; dy <= 2; ++dy) {
+                for (int dx = -2; dx <= 2; ++dx) {
+                    symbol.SetFunction(
+                        align[i] + dx, align[j] + dy,
+                        std::max(std::abs(dx), std::abs(dy)) != 1);
+                }
+            }
+        }
+    }
+
+    DrawFormatBits(symbol, 0);
+    DrawVersionBits(symbol, version);
+    return symbol;
+}
+
+/* data modules in placement order, two columns at a time zig-zagging from
+ * the bottom right corner */
+static std::vector<std::pair<int, int>> DataPositions(const QrSymbol& symbol) {
+    std::vector<std::pair<int, int>> positions;
+    for (int right = symbol.size - 1; right >= 1; right -= 2) {
+        if (right == 6) {
+            right = 5; /* skip the vertical timing pattern */
+        }
+        for (int vert = 0; vert < symbol.size; ++vert) {
+            for (int j = 0; j < 2; ++j) {
+                int x = right - j;
+                bool upward = ((right + 1) & 2) == 0;
+                int y = upward ? symbol.size - 1 - vert : vert;
+                if (!symbol.Function(x, y)) {
+                    positions.emplace_back(x, y);
+                }
+            }
+        }
+    }
+    return positions;
+}
+
+static bool MaskBit(int mask, int x, int y) {
+    switch (mask) {
+        case 0:
+            return (x + y) % 2 == 0;
+        case 1:
+            return y % 2 == 0;
+        case 2:
+            return x % 3 == 0;
+        case 3:
+            return (x + y) % 3 == 0;
+        case 4:
+            return (x / 3 + y / 2) % 2 == 0;
+        case 5:
+            return x * y % 2 + x * y % 3 == 0;
+        case 6:
+            return (x * y % 2 + x * y % 3) % 2 == 0;
+        default:
+            return ((x + y) % 2 + x * y % 3) % 2 == 0;
+    }
+}
+
+static void ApplyMask(QrSymbol& symbol, int mask) {
+    for (int y = 0; y < symbol.size; ++y) {
+        for (int x = 0; x < symbol.size; ++x) {
+            if (!symbol.Function(x, y) && MaskBit(mask, x, y)) {
+                symbol.dark[y * symbol.size + x] =
+                    !symbol.dark[y * symbol.size + x];
+            }
+        }
+    }
+}
+
+/* runs of five or more and finder-like patterns within one row or column */
+static int LinePenalty(const std::vector<bool>& line) {
+    int penalty = 0;
+    int run = 1;
+    for (std::size_t i = 1; i <= line.size(); ++i) {
+        if ((i < line.size()) && (line[i] == line[i - 1])) {
+            ++run;
+            continue;
+        }
+        if (run >= 5) {
+            penalty += 3 + (run - 5);
+        }
+        run = 1;
+    }
+
+    /* a dark 1:1:3:1:1 run with four light modules on either side */
+    auto light = [&line](int begin, int end) {
+        for (int i = begin; i < end; ++i) {
+            if ((i >= 0) && (i < static_cast<int>(line.size())) && line[i]) {
+                return false;
+            }
+        }
+        return true;
+    };
+    const int kSize = static_cast<int>(line.size());
+    for (int i = 0; i + 7 <= kSize; ++i) {
+        bool match = true;
+        for (int j = 0; j < 7; ++j) {
+            match = match && (line[i + j] == (j != 1 && j != 5));
+        }
+        if (match) {
+            penalty += light(i - 4, i) ? 40 : 0;
+            penalty += light(i + 7, i + 11) ? 40 : 0;
+        }
+    }
+    return penalty;
+}
+
+static int Penalty(const QrSymbol& symbol) {
+    int penalty = 0;
+    int dark = 0;
+    for (int i = 0; i < symbol.size; ++i) {
+        std::vector<bool> row;
+        std::vector<bool> col;
+        for (int j = 0; j < symbol.size; ++j) {
+            row.push_back(symbol.Dark(j, i));
+            col.push_back(symbol.Dark(i, j));
+            dark += symbol.Dark(j, i) ? 1 : 0;
+        }
+        penalty += LinePenalty(row) + LinePenalty(col);
+    }
+    for (int y = 0; y + 1 < symbol.size; ++y) {
+        for (int x = 0; x + 1 < symbol.size; ++x) {
+            bool color = symbol.Dark(x, y);
+            if ((symbol.Dark(x + 1, y) == color) &&
+                (symbol.Dark(x, y + 1) == color) &&
+                (symbol.Dark(x + 1, y + 1) == color)) {
+                penalty += 3;
+            }
+        }
+    }
+    int total = symbol.size * symbol.size;
+    int k = (std::abs(dark * 20 - total * 10) + total - 1) / total - 1;
+    return penalty + k * 10;
+}
+
+/* splits data into blocks, appends their error correction codewords, and
+ * interleaves the result */
+static Poly AddErrorCorrection(const Poly& data, int version, QrEcc ecc) {
+    int level = static_cast<int>(ecc);
+    int blocks = kErrorCorrectionBlocks[level][version];
+    int ecc_len = kEccCodewordsPerBlock[level][version];
+    int raw = RawDataModules(version) / 8;
+    int short_blocks = blocks - raw % blocks;
+    int short_len = raw / blocks - ecc_len;
+
+    std::vector<Poly> data_blocks;
+    std::vector<Poly> ecc_blocks;
+    std::size_t offset = 0;
+    for (int i = 0; i < blocks; ++i) {
+        std::size_t len =
+            static_cast<std::size_t>(short_len + ((i < short_blocks) ? 0 : 1));
+        data_blocks.emplace_back(data.begin() + offset,
+                                 data.begin() + offset + len);
+        ecc_blocks.push_back(ReedSolomonEncode(data_blocks.back(), ecc_len));
+        offset += len;
+    }
+
+    Poly result;
+    for (std::size_t i = 0; i <= static_cast<std::size_t>(short_len); ++i) {
+        for (const auto& block : data_blocks) {
+            if (i < block.size()) {
+                result.push_back(block[i]);
+            }
+        }
+    }
+    for (int i = 0; i < ecc_len; ++i) {
+        for (const auto& block : ecc_blocks) {
+            result.push_back(block[i]);
+        }
+    }
+    return result;
+}
+
+static RetCode EncodeQr(const std::string& text, QrEcc ecc, QrSymbol& symbol) {
+    int version = kMinVersion;
+    const std::size_t kTextBits = text.size() * 8;
+    while ((version <= kMaxVersion) &&
+           (4 + CharCountBits(version) + kTextBits >
+            static_cast<std::size_t>(DataCodewords(version, ecc) * 8))) {
+        ++version;
+    }
+    if ((version > kMaxVersion) ||
+        (text.size() >> CharCountBits(version) != 0)) {
+        return RetCode::kInsufficientCapacity;
+    }
+
+    /* byte mode segment, terminator, and pad codewords */
+    std::vector<bool> bits;
+    auto append = [&bits](uint32_t value, int len) {
+        for (int i = len - 1; i >= 0; --i) {
+            bits.push_back(((value >> i) & 1) != 0);
+        }
+    };
+    append(kByteMode, 4);
+    append(static_cast<uint32_t>(text.size()), CharCountBits(version));
+    for (unsigned char c : text) {
+        append(c, 8);
+    }
+    const std::size_t kCapacity =
+        static_cast<std::size_t>(DataCodewords(version, ecc) * 8);
+    append(0, static_cast<int>(
+                  std::min<std::size_t>(4, kCapacity - bits.size())));
+    append(0, static_cast<int>((8 - bits.size() % 8) % 8));
+    for (uint8_t pad = 0xEC; bits.size() < kCapacity; pad ^= 0xEC ^ 0x11) {
+        append(pad, 8);
+    }
+
+    Poly data;
+    for (std::size_t i = 0; i < bits.size(); i += 8) {
+        uint8_t byte = 0;
+        for (std::size_t j = 0; j < 8; ++j) {
+            byte = static_cast<uint8_t>((byte << 1) | (bits[i + j] ? 1 : 0));
+        }
+        data.push_back(byte);
+    }
+    Poly codewords = AddErrorCorrection(data, version, ecc);
+
+    QrSymbol blank = BlankSymbol(version);
+    std::vector<std::pair<int, int>> positions = DataPositions(blank);
+    for (std::size_t i = 0; i < positions.size(); ++i) {
+        if (i / 8 < codewords.size()) {
+            auto [x, y] = positions[i];
+            blank.dark[y * blank.size + x] =
+                ((codewords[i / 8] >> (7 - i % 8)) & 1) != 0;
+        }
+    }
+
+    /* keep the mask scoring the lowest penalty */
+    int best_penalty = std::numeric_limits<int>::max();
+    for (int mask = 0; mask < 8; ++mask) {
+        QrSymbol candidate = blank;
+        ApplyMask(candidate, mask);
+        int format = (kEccFormatBits[static_cast<int>(ecc)] << 3) | mask;
+        DrawFormatBits(candidate, FormatCodeword(format));
+        int penalty = Penalty(candidate);
+        if (penalty < best_penalty) {
+            best_penalty = penalty;
+            symbol = candidate;
+        }
+    }
+    return RetCode::kSuccess;
+}
+
+static RetCode DecodeSymbol(const QrSymbol& symbol, int version,
+                            std::string& text) {
+    /* the format information is read from whichever copy is less damaged */
+    auto [first, second] = ReadFormatBits(symbol);
+    int format = -1;
+    int best_distance = 4; /* BCH(15,5) corrects up to three bit errors */
+    for (int data = 0; data < 32; ++data) {
+        int codeword = FormatCodeword(data);
+        int distance =
+            std::min(std::popcount(static_cast<unsigned>(codeword ^ first)),
+                     std::popcount(static_cast<unsigned>(codeword ^ second)));
+        if (distance < best_distance) {
+            best_distance = distance;
+            format = data;
+        }
+    }
+    if (format < 0) {
+        return RetCode::kInvalidPayload;
+    }
+    int mask = format & 7;
+    int level = static_cast<int>(std::find(std::begin(kEccFormatBits),
+                                           std::end(kEccFormatBits),
+                                           format >> 3) -
+                                 std::begin(kEccFormatBits));
+
+    QrSymbol unmasked = symbol;
+    ApplyMask(unmasked, mask);
+    std::vector<std::pair<int, int>> positions = DataPositions(unmasked);
+    Poly codewords(positions.size() / 8, 0);
+    for (std::size_t i = 0; i < codewords.size() * 8; ++i) {
+        auto [x, y] = positions[i];
+        if (unmasked.Dark(x, y)) {
+            codewords[i / 8] |= static_cast<uint8_t>(1 << (7 - i % 8));
+        }
+    }
+
+    /* undo the interleaving and correct each block */
+    int blocks = kErrorCorrectionBlocks[level][version];
+    int ecc_len = kEccCodewordsPerBlock[level][version];
+    int raw = static_cast<int>(codewords.size());
+    int short_blocks = blocks - raw % blocks;
+    int short_len = raw / blocks - ecc_len;
+    std::vector<Poly> block_data(static_cast<std::size_t>(blocks));
+    std::size_t next = 0;
+    for (int i = 0; i <= short_len; ++i) {
+        for (int b = 0; b < blocks; ++b) {
+            if ((i < short_len) || (b >= short_blocks)) {
+                block_data[b].push_back(codewords[next++]);
+            }
+        }
+    }
+    for (int i = 0; i < ecc_len; ++i) {
+        for (int b = 0; b < blocks; ++b) {
+            block_data[b].push_back(codewords[next++]);
+        }
+    }
+    Poly data;
+    for (Poly& block : block_data) {
+        if (!ReedSolomonCorrect(block, ecc_len)) {
+            return RetCode::kInvalidPayload;
+        }
+        data.insert(data.end(), block.begin(), block.end() - ecc_len);
+    }
+
+    /* only the byte mode segments written by EncodeQr are understood */
+    std::size_t bit = 0;
+    auto read = [&data, &bit](int len) {
+        uint32_t value = 0;
+        for (int i = 0; i < len; ++i, ++bit) {
+            value = (value << 1) | ((data[bit / 8] >> (7 - bit % 8)) & 1);
+        }
+        return value;
+    };
+    const std::size_t kHeaderBits =
+        4 + static_cast<std::size_t>(CharCountBits(version));
+    if ((data.size() * 8 < kHeaderBits) || (read(4) != kByteMode)) {
+        return RetCode::kInvalidPayload;
+    }
+    std::size_t count = read(CharCountBits(version));
+    if (bit + count * 8 > data.size() * 8) {
+        return RetCode::kInvalidPayload;
+    }
+    text.clear();
+    for (std::size_t i = 0; i < count; ++i) {
+        text += static_cast<char>(read(8));
+    }
+    return RetCode::kSuccess;
+}
+
+/* locates the symbol in an image, estimates its version from how well the
+ * timing and finder patterns line up, and samples the module centers */
+static RetCode DecodeQr(const boost::gil::rgb8c_view_t& view,
+                        std::string& text) {
+    const std::ptrdiff_t kWidth = view.width();
+    const std::ptrdiff_t kHeight = view.height();
+    std::vector<int> luma(static_cast<std::size_t>(kWidth * kHeight));
+    int lo = 255;
+    int hi = 0;
+    for (std::ptrdiff_t y = 0; y < kHeight; ++y) {
+        for (std::ptrdiff_t x = 0; x < kWidth; ++x) {
+            const auto& pix = view(x, y);
+            int value = (299 * pix[0] + 587 * pix[1] + 114 * pix[2]) / 1000;
+            luma[y * kWidth + x] = value;
+            lo = std::min(lo, value);
+            hi = std::max(hi, value);
+        }
+    }
+    if (hi - lo < 64) {
+        return RetCode::kPayloadNotFound;
+    }
+    const int kThreshold = (lo + hi) / 2;
+
+    /* the most common length of a horizontal dark run approximates the
+     * module size */
+    std::vector<int> rows(static_cast<std::size_t>(kHeight), 0);
+    std::vector<int> cols(static_cast<std::size_t>(kWidth), 0);
+    std::map<std::ptrdiff_t, int> run_lengths;
+    for (std::ptrdiff_t y = 0; y < kHeight; ++y) {
+        std::ptrdiff_t run = 0;
+        for (std::ptrdiff_t x = 0; x <= kWidth; ++x) {
+            if ((x < kWidth) && (luma[y * kWidth + x] < kThreshold)) {
+                ++rows[y];
+                ++cols[x];
+                ++run;
+            } else if (run > 0) {
+                ++run_lengths[run];
+                run = 0;
+            }
+        }
+    }
+    if (run_lengths.empty()) {
+        return RetCode::kPayloadNotFound;
+    }
+    const std::ptrdiff_t kModule =
+        std::max_element(run_lengths.begin(), run_lengths.end(),
+                         [](const auto& lhs, const auto& rhs) {
+                             return lhs.second < rhs.second;
+                         })
+            ->first;
+
+    /* the symbol spans the rows (columns) holding the most dark pixels
+     * without a gap as wide as the quiet zone, stray pixels such as the
+     * authentication block of a keyed merge lie beyond it */
+    auto extent = [kModule](const std::vector<int>& counts) {
+        int cutoff = *std::max_element(counts.begin(), counts.end()) / 32;
+        std::vector<std::pair<std::ptrdiff_t, std::ptrdiff_t>> spans;
+        std::vector<long> sums;
+        const auto kCount = static_cast<std::ptrdiff_t>(counts.size());
+        for (std::ptrdiff_t i = 0; i < kCount; ++i) {
+            if (counts[i] <= cutoff) {
+                continue;
+            }
+            if (spans.empty() ||
+                (i - spans.back().second - 1 >= 3 * kModule)) {
+                spans.emplace_back(i, i);
+                sums.push_back(0);
+            }
+            spans.back().second = i;
+            sums.back() += counts[i];
+        }
+        if (spans.empty()) {
+            return std::make_pair<std::ptrdiff_t, std::ptrdiff_t>(0, 0);
+        }
+        return spans[std::max_element(sums.begin(), sums.end()) - sums.begin()];
+    };
+    auto [top, bottom] = extent(rows);
+    auto [left, right] = extent(cols);
+    if ((bottom <= top) || (right <= left)) {
+        return RetCode::kPayloadNotFound;
+    }
+
+    auto sample = [&](int version, int mx, int my) {
+        int modules = SymbolSize(version);
+        double scale_x = static_cast<double>(right - left + 1) / modules;
+        double scale_y = static_cast<double>(bottom - top + 1) / modules;
+        auto cx = static_cast<std::ptrdiff_t>(left + (mx + 0.5) * scale_x);
+        auto cy = static_cast<std::ptrdiff_t>(top + (my + 0.5) * scale_y);
+        auto radius =
+            static_cast<std::ptrdiff_t>(std::min(scale_x, scale_y) / 4);
+        long sum = 0;
+        long count = 0;
+        for (std::ptrdiff_t y = cy - radius; y <= cy + radius; ++y) {
+            for (std::ptrdiff_t x = cx - radius; x <= cx + radius; ++x) {
+                if ((x >= 0) && (x < kWidth) && (y >= 0) && (y < kHeight)) {
+                    sum += luma[y * kWidth + x];
+                    ++count;
+                }
+            }
+        }
+        return (count > 0) && (sum < kThreshold * count);
+    };
+
+    int best_version = 0;
+    double best_score = 0.0;
+    for (int version = kMinVersion; version <= kMaxVersion; ++version) {
+        int size = SymbolSize(version);
+        if ((right - left + 1 < size) || (bottom - top + 1 < size)) {
+            break;
+        }
+        QrSymbol blank = BlankSymbol(version);
+        int matches = 0;
+        int total = 0;
+        auto check = [&](int x, int y) {
+            matches += (sample(version, x, y) == blank.Dark(x, y)) ? 1 : 0;
+            ++total;
+        };
+        for (int i = 0; i < 7; ++i) {
+            for (int j = 0; j < 7; ++j) {
+                check(i, j);
+                check(size - 7 + i, j);
+                check(i, size - 7 + j);
+            }
+        }
+        for (int i = 8; i < size - 8; ++i) {
+            check(i, 6);
+            check(6, i);
+        }
+        double score = static_cast<double>(matches) / total;
+        if (score > best_score) {
+            best_score = score;
+            best_version = version;
+        }
+    }
+    if (best_score < 0.9) {
+        return RetCode::kPayloadNotFound;
+    }
+
+    QrSymbol symbol = BlankSymbol(best_version);
+    for (int y = 0; y < symbol.size; ++y) {
+        for (int x = 0; x < symbol.size; ++x) {
+            symbol.dark[y * symbol.size + x] = sample(best_version, x, y);
+        }
+    }
+    return DecodeSymbol(symbol, best_version, text);
+}
+
+RetCode MergeQr(const std::string& cover, const std::string& outfile,
+                const std::string& text, const QrMergeConfig& config) {
+    boost::gil::rgb8_image_t cover_img;
+    RetCode rc = LoadImage(cover, cover_img);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    QrSymbol symbol;
+    rc = EncodeQr(text, config.ecc, symbol);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+
+    /* the code is centered on a light background covering the whole cover
+     * so nothing but the symbol is dark once unmerged */
+    const std::ptrdiff_t kModulePixels =
+        std::min(cover_img.width(), cover_img.height()) /
+        (symbol.size + 2 * kQuietZone);
+    if (kModulePixels < kMinModulePixels) {
+        return RetCode::kInsufficientCapacity;
+    }
+    boost::gil::rgb8_image_t qr_img(cover_img.width(), cover_img.height(),
+                                    boost::gil::rgb8_pixel_t(255, 255, 255));
+    auto qr_view = boost::gil::view(qr_img);
+    const std::ptrdiff_t kSymbolPixels = symbol.size * kModulePixels;
+    const std::ptrdiff_t kLeft = (qr_view.width() - kSymbolPixels) / 2;
+    const std::ptrdiff_t kTop = (qr_view.height() - kSymbolPixels) / 2;
+    for (std::ptrdiff_t y = 0; y < kSymbolPixels; ++y) {
+        for (std::ptrdiff_t x = 0; x < kSymbolPixels; ++x) {
+            if (symbol.Dark(static_cast<int>(x / kModulePixels),
+                            static_cast<int>(y / kModulePixels))) {
+                qr_view(kLeft + x, kTop + y) =
+                    boost::gil::rgb8_pixel_t(0, 0, 0);
+            }
+        }
+    }
+
+    /* merged straight from memory, the rendered code never touches the disk */
+    MergeReport report;
+    return Merge(cover, qr_img, outfile, config.merge, report);
+}
+
+RetCode UnmergeQr(const std::string& image, std::string& text,
+                  const UnmergeConfig& config) {
+    boost::gil::rgb8_image_t qr_img;
+    RetCode rc = Unmerge(image, "", config, qr_img);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    return DecodeQr(boost::gil::const_view(qr_img), text);
+}
+
+}  // namespace steganography
@@ -0,0 +1,82 @@
+#include "utils/raw.hpp"
+
+#include <libraw/libraw.h>
+
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <memory>
+#include <string>
+
+namespace steganography {
+
+struct ProcessedImageDeleter {
+    void operator()(libraw_processed_image_t* image) const {
+        LibRaw::dcraw_clear_mem(image);
+    }
+};
+
+using ProcessedImagePtr =
+    std::unique_ptr<libraw_processed_image_t, ProcessedImageDeleter>;
+
+/* LibRaw's state runs to hundreds of kilobytes, too much for the stack */
+static std::unique_ptr<LibRaw> OpenRaw(const std::string& filename) {
+    auto raw = std::make_unique<LibRaw>();
+    if (raw->open_file(filename.c_str()) != LIBRAW_SUCCESS) {
+        return nullptr;
+    }
+    return raw;
+}
+
+bool ReadRaw(const std::string& filename, boost::gil::rgb8_image_t& image) {
+    std::unique_ptr<LibRaw> raw = OpenRaw(filename);
+    if (!raw) {
+        return false;
+    }
+
+    /* develop it the way a camera's own JPEG would look, as a starting
+     * point rather than a finished edit */
+    raw->imgdata.params.use_camera_wb = 1;
+    raw->imgdata.params.output_color = 1; /* sRGB */
+    raw->imgdata.params.output_bps = 8;
+    if ((raw->unpack() != LIBRAW_SUCCESS) ||
+        (raw->dcraw_process() != LIBRAW_SUCCESS)) {
+        return false;
+    }
+    int error = LIBRAW_SUCCESS;
+    ProcessedImagePtr processed(raw->dcraw_make_mem_image(&error));
+    if (!processed || (error != LIBRAW_SUCCESS) ||
+        (processed->type != LIBRAW_IMAGE_BITMAP) || (processed->colors != 3) ||
+        (processed->bits != 8)) {
+        return false;
+    }
+
+    const std::size_t kWidth = processed->width;
+    image = boost::gil::rgb8_image_t(processed->width, processed->height);
+    auto view = boost::gil::view(image);
+    for (std::size_t y = 0; y < processed->height; ++y) {
+        const unsigned char* row = processed->data + y * kWidth * 3;
+        for (std::size_t x = 0; x < kWidth; ++x) {
+            view(x, y) = boost::gil::rgb8_pixel_t(row[x * 3], row[x * 3 + 1],
+                                                  row[x * 3 + 2]);
+        }
+    }
+    return true;
+}
+
+bool ReadRawHeader(const std::string& filename, uint64_t& width,
+                   uint64_t& height, uint64_t& pixel_bytes) {
+    std::unique_ptr<LibRaw> raw = OpenRaw(filename);
+    if (!raw) {
+        return false;
+    }
+    width = raw->imgdata.sizes.width;
+    height = raw->imgdata.sizes.height;
+
+    /* the sensor data at 16 bits and then four 16 bit samples per pixel
+     * while demosaicing */
+    pixel_bytes = 10;
+    return true;
+}
+
+}  // namespace steganography
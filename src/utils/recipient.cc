@@ -0,0 +1,401 @@
+#include "utils/recipient.hpp"
+
+#include <openssl/crypto.h>
+#include <openssl/evp.h>
+#include <openssl/rand.h>
+
+#include <algorithm>
+#include <array>
+#include <cstddef>
+#include <cstdint>
+#include <filesystem>
+#include <fstream>
+#include <iterator>
+#include <optional>
+#include <string>
+#include <vector>
+
+#include "utils/bytes.hpp"
+#include "utils/crypto.hpp"
+#include "utils/key.hpp"
+#include "utils/paths.hpp"
+
+namespace steganography {
+
+/* identity files hold a magic word, a version byte, and the private key */
+static const uint32_t kIdentityFileMagic = 0x53544749; /* "STGI" */
+static const uint8_t kIdentityFileVersion = 1;
+static const std::size_t kIdentityFileSize = 4 + 1 + kRecipientSize;
+
+/* The envelope is a magic word, a zero byte, a version byte, the stanza
+ * count, the stanzas, and the sealed plaintext. A stanza holds the file key
+ * sealed with a key only one recipient can rebuild, behind a byte telling
+ * how: a key agreed with an ephemeral key stored in front of it, or a
+ * password stretched with a stored salt. Version 1 envelopes only had
+ * ephemeral key stanzas, without the type byte. The zero byte followed by a
+ * nonzero one keeps a tar whose first name starts with the magic from
+ * passing for an envelope. */
+static const uint32_t kEnvelopeMagic = 0x53545243; /* "STRC" */
+static const uint8_t kEnvelopeVersion = 2;
+static const std::size_t kEnvelopeHeaderSize = 7;
+static const std::size_t kSealedKeySize = kSealOverhead + kKeySize;
+
+enum StanzaType : uint8_t {
+    kStanzaX25519 = 0,
+    kStanzaPassword = 1,
+};
+
+/* the X25519 private key as an OpenSSL key, null when it is unusable */
+static EVP_PKEY* PrivateKey(const std::array<uint8_t, kRecipientSize>& secret) {
+    return EVP_PKEY_new_raw_private_key(EVP_PKEY_X25519, nullptr,
+                                        secret.data(), secret.size());
+}
+
+static bool PublicKey(EVP_PKEY* pkey, Recipient& recipient) {
+    std::size_t size = recipient.size();
+    return (EVP_PKEY_get_raw_public_key(pkey, recipient.data(), &size) ==
+            1) &&
+           (size == recipient.size());
+}
+
+/* The key wrapping the file key for one recipient, derived from the shared
+ * secret and bound to both public keys. False when the agreement fails,
+ * such as for a low order point. */
+static bool WrapKey(const std::array<uint8_t, kRecipientSize>& secret,
+                    const Recipient& ephemeral, const Recipient& recipient,
+                    bool as_recipient, StegKey& wrap) {
+    EVP_PKEY* own = PrivateKey(secret);
+    EVP_PKEY* peer = EVP_PKEY_new_raw_public_key(
+        EVP_PKEY_X25519, nullptr,
+        as_recipient ? ephemeral.data() : recipient.data(), kRecipientSize);
+    StegKey shared;
+    std::size_t shared_size = shared.material.size();
+    EVP_PKEY_CTX* ctx =
+        (own != nullptr) ? EVP_PKEY_CTX_new(own, nullptr) : nullptr;
+    bool ok = (ctx != nullptr) && (peer != nullptr) &&
+              (EVP_PKEY_derive_init(ctx) == 1) &&
+              (EVP_PKEY_derive_set_peer(ctx, peer) == 1) &&
+              (EVP_PKEY_derive(ctx, shared.material.data(), &shared_size) ==
+               1);
+    EVP_PKEY_CTX_free(ctx);
+    EVP_PKEY_free(peer);
+    EVP_PKEY_free(own);
+    if (ok) {
+        std::string label = "recipient";
+        label.append(ephemeral.cbegin(), ephemeral.cend());
+        label.append(recipient.cbegin(), recipient.cend());
+        wrap.material = DeriveSubkey(shared, label);
+    }
+    OPENSSL_cleanse(shared.material.data(), shared.material.size());
+    return ok;
+}
+
+RetCode GenerateIdentityFile(const std::string& path, bool create_dirs,
+                             Recipient& recipient) {
+    /* never clobber an existing identity, payloads sent to it would be lost,
+     * WritePrivateFile refuses one that appears in the meantime */
+    if (std::filesystem::exists(path)) {
+        return RetCode::kFileExists;
+    }
+    if (create_dirs) {
+        RetCode rc = CreateParentDirectories(path);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+
+    std::array<uint8_t, kRecipientSize> secret{};
+    if (RAND_bytes(secret.data(), static_cast<int>(secret.size())) != 1) {
+        return RetCode::kIoError;
+    }
+    EVP_PKEY* pkey = PrivateKey(secret);
+    bool ok = (pkey != nullptr) && PublicKey(pkey, recipient);
+    EVP_PKEY_free(pkey);
+    if (!ok) {
+        return RetCode::kIoError;
+    }
+    std::vector<uint8_t> contents;
+    AppendInt<uint32_t>(contents, kIdentityFileMagic);
+    AppendInt<uint8_t>(contents, kIdentityFileVersion);
+    contents.insert(contents.end(), secret.cbegin(), secret.cend());
+    OPENSSL_cleanse(secret.data(), secret.size());
+
+    /* only the owner should ever be able to read the identity */
+    RetCode rc = WritePrivateFile(path, contents);
+    OPENSSL_cleanse(contents.data(), contents.size());
+    return rc;
+}
+
+RetCode LoadIdentityFile(const std::string& path, Identity& identity) {
+    std::ifstream ifs(path, std::ifstream::binary);
+    if (!ifs.is_open()) {
+        return RetCode::kFileNotFound;
+    }
+    std::vector<uint8_t> contents((std::istreambuf_iterator<char>(ifs)),
+                                  std::istreambuf_iterator<char>());
+    if ((contents.size() != kIdentityFileSize) ||
+        (ReadInt<uint32_t>(contents, 0) != kIdentityFileMagic) ||
+        (contents[4] != kIdentityFileVersion)) {
+        return RetCode::kInvalidKey;
+    }
+    std::copy(contents.cbegin() + 5, contents.cend(),
+              identity.secret.begin());
+    OPENSSL_cleanse(contents.data(), contents.size());
+    EVP_PKEY* pkey = PrivateKey(identity.secret);
+    bool ok = (pkey != nullptr) && PublicKey(pkey, identity.recipient);
+    EVP_PKEY_free(pkey);
+    return ok ? RetCode::kSuccess : RetCode::kInvalidKey;
+}
+
+/* seals the file key with the wrap key, at the end of the envelope, false
+ * when no nonce could be drawn */
+static bool AppendSealedKey(const StegKey& file_key, StegKey& wrap,
+                            std::vector<uint8_t>& envelope) {
+    std::vector<uint8_t> sealed_key =
+        Seal(wrap, std::vector<uint8_t>(file_key.material.cbegin(),
+                                        file_key.material.cend()));
+    OPENSSL_cleanse(wrap.material.data(), wrap.material.size());
+    envelope.insert(envelope.end(), sealed_key.cbegin(), sealed_key.cend());
+    return !sealed_key.empty();
+}
+
+RetCode EncryptToRecipients(const std::vector<Recipient>& recipients,
+                            const std::vector<std::string>& passwords,
+                            const std::vector<uint8_t>& plaintext,
+                            std::vector<uint8_t>& envelope) {
+    envelope.clear();
+    const std::size_t kCount = recipients.size() + passwords.size();
+    if ((kCount == 0) || (kCount > kMaxRecipients) ||
+        std::any_of(passwords.cbegin(), passwords.cend(),
+                    [](const std::string& p) { return p.empty(); })) {
+        return RetCode::kInvalidArgument;
+    }
+    StegKey file_key;
+    std::vector<uint8_t> random = RandomBytes(kKeySize);
+    if (random.size() != kKeySize) {
+        return RetCode::kIoError;
+    }
+    std::copy(random.cbegin(), random.cend(), file_key.material.begin());
+    OPENSSL_cleanse(random.data(), random.size());
+
+    /* the file key is wiped however encrypting ends */
+    auto fail = [&file_key, &envelope](RetCode rc) {
+        OPENSSL_cleanse(file_key.material.data(), file_key.material.size());
+        envelope.clear();
+        return rc;
+    };
+    AppendInt<uint32_t>(envelope, kEnvelopeMagic);
+    AppendInt<uint8_t>(envelope, 0);
+    AppendInt<uint8_t>(envelope, kEnvelopeVersion);
+    AppendInt<uint8_t>(envelope, static_cast<uint8_t>(kCount));
+    for (const Recipient& recipient : recipients) {
+        /* a fresh ephemeral key per recipient, as age does */
+        std::array<uint8_t, kRecipientSize> ephemeral_secret{};
+        random = RandomBytes(kRecipientSize);
+        if (random.size() != kRecipientSize) {
+            return fail(RetCode::kIoError);
+        }
+        std::copy(random.cbegin(), random.cend(), ephemeral_secret.begin());
+        OPENSSL_cleanse(random.data(), random.size());
+        Recipient ephemeral{};
+        EVP_PKEY* pkey = PrivateKey(ephemeral_secret);
+        bool ok = (pkey != nullptr) && PublicKey(pkey, ephemeral);
+        EVP_PKEY_free(pkey);
+
+        StegKey wrap;
+        ok = ok &&
+             WrapKey(ephemeral_secret, ephemeral, recipient, false, wrap);
+        OPENSSL_cleanse(ephemeral_secret.data(), ephemeral_secret.size());
+        if (!ok) {
+            return fail(RetCode::kInvalidArgument);
+        }
+        AppendInt<uint8_t>(envelope, kStanzaX25519);
+        envelope.insert(envelope.end(), ephemeral.cbegin(), ephemeral.cend());
+        if (!AppendSealedKey(file_key, wrap, envelope)) {
+            return fail(RetCode::kIoError);
+        }
+    }
+    for (const std::string& password : passwords) {
+        std::vector<uint8_t> salt = RandomBytes(kSaltSize);
+        if (salt.size() != kSaltSize) {
+            return fail(RetCode::kIoError);
+        }
+        StegKey wrap = DerivePasswordKey(password, salt);
+        AppendInt<uint8_t>(envelope, kStanzaPassword);
+        envelope.insert(envelope.end(), salt.cbegin(), salt.cend());
+        if (!AppendSealedKey(file_key, wrap, envelope)) {
+            return fail(RetCode::kIoError);
+        }
+    }
+    std::vector<uint8_t> body = Seal(file_key, plaintext);
+    if (body.empty()) {
+        return fail(RetCode::kIoError);
+    }
+    OPENSSL_cleanse(file_key.material.data(), file_key.material.size());
+    envelope.insert(envelope.end(), body.cbegin(), body.cend());
+    return RetCode::kSuccess;
+}
+
+bool IsRecipientEnvelope(const std::vector<uint8_t>& envelope) {
+    return (envelope.size() >= kEnvelopeHeaderSize) &&
+           (ReadInt<uint32_t>(envelope, 0) == kEnvelopeMagic) &&
+           (envelope[4] == 0) && (envelope[5] != 0);
+}
+
+bool CountRecipients(const std::vector<uint8_t>& envelope,
+                     std::size_t& recipients, std::size_t& passwords) {
+    recipients = 0;
+    passwords = 0;
+    if (!IsRecipientEnvelope(envelope) ||
+        (envelope[5] > kEnvelopeVersion)) {
+        return false;
+    }
+    const uint8_t kVersion = envelope[5];
+    std::size_t offset = kEnvelopeHeaderSize;
+    for (std::size_t i = 0; i < envelope[6]; ++i) {
+        uint8_t type = kStanzaX25519;
+        if (kVersion > 1) {
+            if (offset >= envelope.size()) {
+                return false;
+            }
+            type = envelope[offset++];
+        }
+        if (type == kStanzaX25519) {
+            offset += kRecipientSize;
+            ++recipients;
+        } else if (type == kStanzaPassword) {
+            offset += kSaltSize;
+            ++passwords;
+        } else {
+            return false;
+        }
+        offset += kSealedKeySize;
+    }
+    return offset <= envelope.size();
+}
+
+/* Tries unwrap on every stanza until one yields the wrap key of the file
+ * key, then opens the body with it. unwrap is given the stanza type and the
+ * bytes in front of the sealed file key. */
+template <typename Unwrap>
+static RetCode DecryptEnvelope(const std::vector<uint8_t>& envelope,
+                               const Unwrap& unwrap,
+                               std::vector<uint8_t>& plaintext) {
+    if (!IsRecipientEnvelope(envelope)) {
+        return RetCode::kInvalidPayload;
+    }
+    const uint8_t kVersion = envelope[5];
+    if (kVersion > kEnvelopeVersion) {
+        return RetCode::kUnsupportedFormat;
+    }
+    const std::size_t kCount = envelope[6];
+    if (kCount == 0) {
+        return RetCode::kInvalidPayload;
+    }
+
+    std::size_t offset = kEnvelopeHeaderSize;
+    std::optional<StegKey> file_key;
+    for (std::size_t i = 0; i < kCount; ++i) {
+        uint8_t type = kStanzaX25519;
+        if (kVersion > 1) {
+            if (offset >= envelope.size()) {
+                return RetCode::kInvalidPayload;
+            }
+            type = envelope[offset++];
+        }
+        std::size_t prefix_size = 0;
+        if (type == kStanzaX25519) {
+            prefix_size = kRecipientSize;
+        } else if (type == kStanzaPassword) {
+            prefix_size = kSaltSize;
+        } else {
+            return RetCode::kUnsupportedFormat;
+        }
+        if (envelope.size() < offset + prefix_size + kSealedKeySize) {
+            return RetCode::kInvalidPayload;
+        }
+        auto stanza =
+            envelope.cbegin() + static_cast<std::ptrdiff_t>(offset);
+        offset += prefix_size + kSealedKeySize;
+        /* the remaining stanzas are still walked to find the body */
+        if (file_key) {
+            continue;
+        }
+
+        StegKey wrap;
+        if (!unwrap(type, std::vector<uint8_t>(stanza, stanza + prefix_size),
+                    wrap)) {
+            continue;
+        }
+        std::vector<uint8_t> sealed_key(
+            stanza + static_cast<std::ptrdiff_t>(prefix_size),
+            stanza + static_cast<std::ptrdiff_t>(prefix_size +
+                                                 kSealedKeySize));
+        std::vector<uint8_t> key_bytes;
+        bool opened = Open(wrap, sealed_key, key_bytes);
+        OPENSSL_cleanse(wrap.material.data(), wrap.material.size());
+        if (opened && (key_bytes.size() == kKeySize)) {
+            file_key.emplace();
+            std::copy(key_bytes.cbegin(), key_bytes.cend(),
+                      file_key->material.begin());
+        }
+        OPENSSL_cleanse(key_bytes.data(), key_bytes.size());
+    }
+    if (!file_key) {
+        return RetCode::kKeyMismatch;
+    }
+    if (envelope.size() < offset + kSealOverhead) {
+        OPENSSL_cleanse(file_key->material.data(), file_key->material.size());
+        return RetCode::kInvalidPayload;
+    }
+    std::vector<uint8_t> body(
+        envelope.cbegin() + static_cast<std::ptrdiff_t>(offset),
+        envelope.cend());
+    bool opened = Open(*file_key, body, plaintext);
+    OPENSSL_cleanse(file_key->material.data(), file_key->material.size());
+    return opened ? RetCode::kSuccess : RetCode::kInvalidPayload;
+}
+
+RetCode DecryptWithIdentity(const Identity& identity,
+                            const std::vector<uint8_t>& envelope,
+                            std::vector<uint8_t>& plaintext) {
+    return DecryptEnvelope(
+        envelope,
+        [&identity](uint8_t type, const std::vector<uint8_t>& prefix,
+                    StegKey& wrap) {
+            if (type != kStanzaX25519) {
+                return false;
+            }
+            Recipient ephemeral{};
+            std::copy(prefix.cbegin(), prefix.cend(), ephemeral.begin());
+            return WrapKey(identity.secret, ephemeral, identity.recipient,
+                           true, wrap);
+        },
+        plaintext);
+}
+
+RetCode DecryptWithPassword(const std::string& password,
+                            const std::vector<uint8_t>& envelope,
+                            std::vector<uint8_t>& plaintext) {
+    return DecryptEnvelope(
+        envelope,
+        [&password](uint8_t type, const std::vector<uint8_t>& prefix,
+                    StegKey& wrap) {
+            if (type != kStanzaPassword) {
+                return false;
+            }
+            wrap = DerivePasswordKey(password, prefix);
+            return true;
+        },
+        plaintext);
+}
+
+std::string RecipientToHex(const Recipient& recipient) {
+    return ToHex(recipient.data(), recipient.size());
+}
+
+bool RecipientFromHex(const std::string& hex, Recipient& recipient) {
+    return FromHex(hex, recipient.data(), recipient.size());
+}
+
+}  // namespace steganography
@@ -0,0 +1,258 @@
+#include "utils/reversible.hpp"
+
+#include <array>
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <optional>
+#include <vector>
+
+#include "core/carrier.hpp"
+#include "core/frame.hpp"
+#include "utils/payload.hpp"
+
+namespace steganography {
+
+/* the peak and empty bin of each channel are kept in the low bits of the
+ * first kParamSlots channels, whose own low bits are carried in front of the
+ * payload */
+static const std::size_t kParamSlots = 48;
+static const std::size_t kSavedBytes = kParamSlots / 8;
+static const std::size_t kChannels = 3;
+
+struct HistogramShift {
+    std::array<uint8_t, kChannels> peaks{};
+    std::array<uint8_t, kChannels> empties{}; /* the peak when unused */
+};
+
+/* the cells of another carrier from first on, so the saved low bits stay
+ * out of the frame */
+class OffsetCarrier : public Carrier {
+   public:
+    OffsetCarrier(Carrier& carrier, std::size_t first)
+        : carrier_(carrier), first_(first) {}
+
+    std::size_t CellCount() const override {
+        const std::size_t kCells = carrier_.CellCount();
+        return (kCells > first_) ? kCells - first_ : 0;
+    }
+    uint8_t ReadCell(std::size_t cell) const override {
+        return carrier_.ReadCell(first_ + cell);
+    }
+    void WriteCell(std::size_t cell, uint8_t byte) override {
+        carrier_.WriteCell(first_ + cell, byte);
+    }
+    std::size_t CellRow(std::size_t cell) const override {
+        return carrier_.CellRow(first_ + cell);
+    }
+
+   private:
+    Carrier& carrier_;
+    std::size_t first_;
+};
+
+/* the interleaved bytes of an image and where its rows start */
+struct Pixels {
+    uint8_t* data = nullptr;
+    std::size_t width = 0;
+    std::size_t height = 0;
+    std::size_t stride = 0;
+
+    std::size_t Slots() const { return width * height * kChannels; }
+
+    uint8_t& operator[](std::size_t slot) const {
+        const std::size_t kPixel = slot / kChannels;
+        return data[kPixel / width * stride + kPixel % width * kChannels +
+                    slot % kChannels];
+    }
+};
+
+/* only handed on as const when the image is */
+template <typename View>
+static Pixels ViewPixels(const View& view) {
+    Pixels pixels;
+    pixels.width = static_cast<std::size_t>(view.width());
+    pixels.height = static_cast<std::size_t>(view.height());
+    if ((pixels.width == 0) || (pixels.height == 0)) {
+        return pixels;
+    }
+    pixels.data = const_cast<uint8_t*>(&view(0, 0)[0]);
+    pixels.stride = pixels.width * kChannels;
+    if (pixels.height > 1) {
+        pixels.stride =
+            static_cast<std::size_t>(&view(0, 1)[0] - &view(0, 0)[0]);
+    }
+    return pixels;
+}
+
+/* the most common value of each channel and the unused value closest to
+ * it, the parameter channels left out */
+static HistogramShift PlanShift(const Pixels& pixels) {
+    std::array<std::array<std::size_t, 256>, kChannels> counts{};
+    for (std::size_t slot = kParamSlots; slot < pixels.Slots(); ++slot) {
+        ++counts[slot % kChannels][pixels[slot]];
+    }
+
+    HistogramShift shift;
+    for (std::size_t c = 0; c < kChannels; ++c) {
+        int peak = 0;
+        for (int value = 1; value < 256; ++value) {
+            if (counts[c][value] > counts[c][peak]) {
+                peak = value;
+            }
+        }
+        shift.peaks[c] = static_cast<uint8_t>(peak);
+        shift.empties[c] = shift.peaks[c];
+        for (int distance = 1; distance < 256; ++distance) {
+            if ((peak + distance < 256) && (counts[c][peak + distance] == 0)) {
+                shift.empties[c] = static_cast<uint8_t>(peak + distance);
+                break;
+            }
+            if ((peak - distance >= 0) && (counts[c][peak - distance] == 0)) {
+                shift.empties[c] = static_cast<uint8_t>(peak - distance);
+                break;
+            }
+        }
+    }
+    return shift;
+}
+
+static HistogramShift ReadShift(const Pixels& pixels) {
+    std::array<uint8_t, 2 * kChannels> params{};
+    for (std::size_t slot = 0; slot < kParamSlots; ++slot) {
+        params[slot / 8] =
+            static_cast<uint8_t>((params[slot / 8] << 1) | (pixels[slot] & 1));
+    }
+    HistogramShift shift;
+    for (std::size_t c = 0; c < kChannels; ++c) {
+        shift.peaks[c] = params[2 * c];
+        shift.empties[c] = params[2 * c + 1];
+    }
+    return shift;
+}
+
+/* Moves the values between each peak and its empty bin one step towards the
+ * empty bin, freeing the value next to the peak, or with restore moves them
+ * back and returns the freed value to the peak. */
+static void ShiftValues(const Pixels& pixels, const HistogramShift& shift,
+                        bool restore) {
+    for (std::size_t slot = kParamSlots; slot < pixels.Slots(); ++slot) {
+        const int kPeak = shift.peaks[slot % kChannels];
+        const int kEmpty = shift.empties[slot % kChannels];
+        if (kPeak == kEmpty) {
+            continue;
+        }
+        const int kStep = (kEmpty > kPeak) ? 1 : -1;
+        uint8_t& value = pixels[slot];
+        const int kDistance = (value - kPeak) * kStep;
+        const int kRange = (kEmpty - kPeak) * kStep;
+        if (!restore && (kDistance > 0) && (kDistance < kRange)) {
+            value = static_cast<uint8_t>(value + kStep);
+        } else if (restore && (kDistance > 0) && (kDistance <= kRange)) {
+            value = static_cast<uint8_t>((kDistance == 1) ? kPeak
+                                                          : value - kStep);
+        }
+    }
+}
+
+static HistogramShiftCarrier ShiftCarrier(const Pixels& pixels,
+                                          const HistogramShift& shift) {
+    return HistogramShiftCarrier(pixels.data, pixels.width, pixels.height,
+                                 pixels.stride, shift.peaks, shift.empties,
+                                 kParamSlots);
+}
+
+std::size_t ReversibleCapacity(const boost::gil::rgb8_image_t& image) {
+    boost::gil::rgb8_image_t shifted = image;
+    const Pixels kPixels = ViewPixels(boost::gil::view(shifted));
+    if (kPixels.Slots() < kParamSlots) {
+        return 0;
+    }
+    const HistogramShift kShift = PlanShift(kPixels);
+    ShiftValues(kPixels, kShift, false);
+    HistogramShiftCarrier carrier = ShiftCarrier(kPixels, kShift);
+    return PayloadCapacity(OffsetCarrier(carrier, kSavedBytes));
+}
+
+RetCode EmbedReversible(boost::gil::rgb8_image_t& image,
+                        const std::vector<uint8_t>& payload,
+                        const EmbedConfig& config) {
+    const Pixels kPixels = ViewPixels(boost::gil::view(image));
+    if (kPixels.Slots() < kParamSlots) {
+        return RetCode::kInsufficientCapacity;
+    }
+    const HistogramShift kShift = PlanShift(kPixels);
+    if (kShift.peaks == kShift.empties) {
+        return RetCode::kInsufficientCapacity;
+    }
+
+    /* the parameters take the place of the low bits they save */
+    std::array<uint8_t, 2 * kChannels> params{};
+    for (std::size_t c = 0; c < kChannels; ++c) {
+        params[2 * c] = kShift.peaks[c];
+        params[2 * c + 1] = kShift.empties[c];
+    }
+    std::vector<uint8_t> saved(kSavedBytes, 0);
+    for (std::size_t slot = 0; slot < kParamSlots; ++slot) {
+        const int kShiftBits = 7 - static_cast<int>(slot % 8);
+        uint8_t& value = kPixels[slot];
+        saved[slot / 8] |= static_cast<uint8_t>((value & 1) << kShiftBits);
+        value = static_cast<uint8_t>((value & 0xFE) |
+                                     ((params[slot / 8] >> kShiftBits) & 1));
+    }
+
+    ShiftValues(kPixels, kShift, false);
+    HistogramShiftCarrier carrier = ShiftCarrier(kPixels, kShift);
+    if (carrier.CellCount() < kSavedBytes) {
+        return RetCode::kInsufficientCapacity;
+    }
+    for (std::size_t cell = 0; cell < kSavedBytes; ++cell) {
+        carrier.WriteCell(cell, saved[cell]);
+    }
+    OffsetCarrier framed(carrier, kSavedBytes);
+    return EmbedPayload(framed, payload, config);
+}
+
+RetCode ExtractReversible(const boost::gil::rgb8_image_t& image,
+                          std::vector<uint8_t>& payload,
+                          PayloadHeader& header, DamageReport& damage,
+                          const std::optional<StegKey>& key) {
+    const Pixels kPixels = ViewPixels(boost::gil::const_view(image));
+    if (kPixels.Slots() < kParamSlots) {
+        return RetCode::kPayloadNotFound;
+    }
+    HistogramShiftCarrier carrier = ShiftCarrier(kPixels, ReadShift(kPixels));
+    const OffsetCarrier kFramed(carrier, kSavedBytes);
+    return ExtractPayload(kFramed, payload, header, damage, key);
+}
+
+RetCode RestoreReversibleCover(const boost::gil::rgb8_image_t& image,
+                               boost::gil::rgb8_image_t& cover) {
+    const Pixels kImage = ViewPixels(boost::gil::const_view(image));
+    if (kImage.Slots() < kParamSlots) {
+        return RetCode::kPayloadNotFound;
+    }
+    const HistogramShift kShift = ReadShift(kImage);
+    HistogramShiftCarrier carrier = ShiftCarrier(kImage, kShift);
+    const OffsetCarrier kFramed(carrier, kSavedBytes);
+    PayloadHeader header;
+    std::size_t header_size = 0;
+    if ((kFramed.CellCount() < kPayloadHeaderSize) ||
+        (ReadFrameHeader(kFramed, 0, header, header_size) !=
+         RetCode::kSuccess)) {
+        return RetCode::kPayloadNotFound;
+    }
+
+    cover = image;
+    const Pixels kCover = ViewPixels(boost::gil::view(cover));
+    ShiftValues(kCover, kShift, true);
+    for (std::size_t slot = 0; slot < kParamSlots; ++slot) {
+        const uint8_t kSaved = carrier.ReadCell(slot / 8);
+        uint8_t& value = kCover[slot];
+        value = static_cast<uint8_t>((value & 0xFE) |
+                                     ((kSaved >> (7 - slot % 8)) & 1));
+    }
+    return RetCode::kSuccess;
+}
+
+}  // namespace steganography
@@ -0,0 +1,91 @@
+#include "utils/rotation.hpp"
+
+#include <algorithm>
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <optional>
+#include <utility>
+#include <vector>
+
+#include "core/carrier.hpp"
+#include "core/frame.hpp"
+#include "utils/key.hpp"
+#include "utils/payload.hpp"
+
+namespace steganography {
+
+/* the orders a run of three pixels can visit the channels in */
+static const uint8_t kRotations[6][3] = {{0, 1, 2}, {0, 2, 1}, {1, 0, 2},
+                                         {1, 2, 0}, {2, 0, 1}, {2, 1, 0}};
+
+/* the channel carrying data in each pixel, every run of three pixels takes
+ * each channel once */
+static std::vector<uint8_t> RotationSchedule(const StegKey& key,
+                                             std::size_t pixel_count) {
+    KeyStream stream(key, "channel rotation");
+    std::vector<uint8_t> channels(pixel_count);
+    for (std::size_t run = 0; run < pixel_count; run += 3) {
+        const uint8_t* rotation = kRotations[stream.NextBelow(6)];
+        for (std::size_t i = run; i < std::min(run + 3, pixel_count); ++i) {
+            channels[i] = rotation[i - run];
+        }
+    }
+    return channels;
+}
+
+/* the pixels of a view as a rotation carrier, rows follow each other at
+ * the distance between the first two */
+template <typename View>
+static RotationCarrier ViewCarrier(const View& view,
+                                   std::vector<uint8_t> channels) {
+    const std::size_t kWidth = static_cast<std::size_t>(view.width());
+    const std::size_t kHeight = static_cast<std::size_t>(view.height());
+    if ((kWidth == 0) || (kHeight == 0)) {
+        return RotationCarrier(static_cast<uint8_t*>(nullptr), 0, 0, 0, {});
+    }
+    std::size_t stride = kWidth * 3;
+    if (kHeight > 1) {
+        stride = static_cast<std::size_t>(&view(0, 1)[0] - &view(0, 0)[0]);
+    }
+    return RotationCarrier(&view(0, 0)[0], kWidth, kHeight, stride,
+                           std::move(channels));
+}
+
+/* the schedule only decides which channel, not how many, so any one gives
+ * the capacity */
+std::size_t RotationCapacity(const boost::gil::rgb8_image_t& image) {
+    const std::size_t kPixels =
+        static_cast<std::size_t>(image.width() * image.height());
+    return PayloadCapacity(ViewCarrier(boost::gil::const_view(image),
+                                       std::vector<uint8_t>(kPixels)));
+}
+
+RetCode EmbedRotation(boost::gil::rgb8_image_t& image,
+                      const std::vector<uint8_t>& payload,
+                      const EmbedConfig& config) {
+    if (!config.key) {
+        return RetCode::kInvalidArgument;
+    }
+    RotationCarrier carrier = ViewCarrier(
+        boost::gil::view(image),
+        RotationSchedule(*config.key, static_cast<std::size_t>(
+                                          image.width() * image.height())));
+    return EmbedPayload(carrier, payload, config);
+}
+
+RetCode ExtractRotation(const boost::gil::rgb8_image_t& image,
+                        std::vector<uint8_t>& payload, PayloadHeader& header,
+                        DamageReport& damage,
+                        const std::optional<StegKey>& key) {
+    if (!key) {
+        return RetCode::kInvalidArgument;
+    }
+    const RotationCarrier kCarrier = ViewCarrier(
+        boost::gil::const_view(image),
+        RotationSchedule(*key, static_cast<std::size_t>(image.width() *
+                                                        image.height())));
+    return ExtractPayload(kCarrier, payload, header, damage, key);
+}
+
+}  // namespace steganography
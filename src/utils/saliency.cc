@@ -0,0 +1,143 @@
+#include "utils/saliency.hpp"
+
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <opencv2/core.hpp>
+#include <opencv2/imgproc.hpp>
+#include <opencv2/objdetect.hpp>
+#include <vector>
+
+namespace steganography {
+
+/* the high bits the usual layout leaves alone */
+static const uint8_t kHighBits = 0xF0;
+
+/* pixels around a region that are avoided along with it */
+static const int kMargin = 8;
+
+/* side of the square the spectral residual is computed at */
+static const int kSaliencySide = 64;
+
+/* how many times the mean saliency a pixel needs to stand out */
+static const double kSaliencyThreshold = 3.0;
+
+/* finds faces with the Haar cascade given at build time */
+static RetCode MarkFaces(const cv::Mat& gray, cv::Mat& mask) {
+    cv::CascadeClassifier cascade;
+    if (!cascade.load(STEG_FACE_CASCADE)) {
+        return RetCode::kFileNotFound;
+    }
+    cv::Mat equalized;
+    cv::equalizeHist(gray, equalized);
+    std::vector<cv::Rect> faces;
+    cascade.detectMultiScale(equalized, faces, 1.1, 4, 0, cv::Size(24, 24));
+    for (const cv::Rect& face : faces) {
+        mask(face).setTo(255);
+    }
+    return RetCode::kSuccess;
+}
+
+/* text is dense with strong edges laid out in runs wider than they are
+ * tall, the edges are joined along rows and the runs that stay filled kept */
+static void MarkText(const cv::Mat& gray, cv::Mat& mask) {
+    cv::Mat gradient;
+    cv::morphologyEx(gray, gradient, cv::MORPH_GRADIENT,
+                     cv::getStructuringElement(cv::MORPH_ELLIPSE, {3, 3}));
+    cv::Mat edges;
+    cv::threshold(gradient, edges, 0, 255,
+                  cv::THRESH_BINARY | cv::THRESH_OTSU);
+    cv::Mat runs;
+    cv::morphologyEx(edges, runs, cv::MORPH_CLOSE,
+                     cv::getStructuringElement(cv::MORPH_RECT, {9, 1}));
+    std::vector<std::vector<cv::Point>> contours;
+    cv::findContours(runs, contours, cv::RETR_EXTERNAL,
+                     cv::CHAIN_APPROX_SIMPLE);
+    for (const auto& contour : contours) {
+        const cv::Rect kBox = cv::boundingRect(contour);
+        if ((kBox.height < 8) || (kBox.height > gray.rows / 4) ||
+            (kBox.width < 2 * kBox.height)) {
+            continue;
+        }
+        const double kFilled = static_cast<double>(cv::countNonZero(
+                                   edges(kBox))) /
+                               static_cast<double>(kBox.area());
+        if (kFilled > 0.45) {
+            mask(kBox).setTo(255);
+        }
+    }
+}
+
+/* the spectral residual, what is left of the log spectrum once its smooth
+ * trend is taken out, brought back into the image stands out to the eye */
+static void MarkSalient(const cv::Mat& gray, cv::Mat& mask) {
+    cv::Mat small;
+    cv::resize(gray, small, {kSaliencySide, kSaliencySide}, 0, 0,
+               cv::INTER_AREA);
+    small.convertTo(small, CV_32F, 1.0 / 255.0);
+    cv::Mat spectrum;
+    cv::dft(small, spectrum, cv::DFT_COMPLEX_OUTPUT);
+    cv::Mat planes[2];
+    cv::split(spectrum, planes);
+    cv::Mat magnitude;
+    cv::Mat phase;
+    cv::cartToPolar(planes[0], planes[1], magnitude, phase);
+    cv::Mat log_magnitude;
+    cv::log(magnitude + 1e-6, log_magnitude);
+    cv::Mat smooth;
+    cv::blur(log_magnitude, smooth, {3, 3});
+    cv::exp(log_magnitude - smooth, magnitude);
+    cv::polarToCart(magnitude, phase, planes[0], planes[1]);
+    cv::merge(planes, 2, spectrum);
+    cv::Mat restored;
+    cv::idft(spectrum, restored, cv::DFT_SCALE);
+    cv::split(restored, planes);
+    cv::Mat saliency = planes[0].mul(planes[0]) + planes[1].mul(planes[1]);
+    cv::GaussianBlur(saliency, saliency, {0, 0}, 2.5);
+    cv::resize(saliency, saliency, gray.size(), 0, 0, cv::INTER_LINEAR);
+    const double kThreshold = kSaliencyThreshold * cv::mean(saliency)[0];
+    mask.setTo(255, saliency > kThreshold);
+}
+
+RetCode AvoidanceMask(const boost::gil::rgb8_image_t& image,
+                      std::vector<bool>& mask) {
+    auto view = boost::gil::const_view(image);
+    const int kWidth = static_cast<int>(view.width());
+    const int kHeight = static_cast<int>(view.height());
+    mask.assign(static_cast<std::size_t>(kWidth) * kHeight, false);
+    if ((kWidth == 0) || (kHeight == 0)) {
+        return RetCode::kSuccess;
+    }
+
+    cv::Mat rgb(kHeight, kWidth, CV_8UC3);
+    for (int y = 0; y < kHeight; ++y) {
+        for (int x = 0; x < kWidth; ++x) {
+            for (int i = 0; i < 3; ++i) {
+                rgb.at<cv::Vec3b>(y, x)[i] = view(x, y)[i] & kHighBits;
+            }
+        }
+    }
+    cv::Mat gray;
+    cv::cvtColor(rgb, gray, cv::COLOR_RGB2GRAY);
+
+    cv::Mat avoid = cv::Mat::zeros(gray.size(), CV_8U);
+    RetCode rc = MarkFaces(gray, avoid);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    MarkText(gray, avoid);
+    MarkSalient(gray, avoid);
+    cv::dilate(avoid, avoid,
+               cv::getStructuringElement(
+                   cv::MORPH_RECT, {2 * kMargin + 1, 2 * kMargin + 1}));
+
+    for (int y = 0; y < kHeight; ++y) {
+        for (int x = 0; x < kWidth; ++x) {
+            mask[static_cast<std::size_t>(y) * kWidth + x] =
+                avoid.at<uint8_t>(y, x) != 0;
+        }
+    }
+    return RetCode::kSuccess;
+}
+
+}  // namespace steganography
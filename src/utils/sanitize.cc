@@ -0,0 +1,130 @@
+#include "utils/sanitize.hpp"
+
+#include <algorithm>
+#include <boost/gil.hpp>
+#include <cstdint>
+#include <filesystem>
+#include <string>
+#include <system_error>
+#include <vector>
+
+#include "utils/image_io.hpp"
+#include "utils/key.hpp"
+#include "utils/png_chunk.hpp"
+#include "utils/scrub.hpp"
+#include "utils/trailer.hpp"
+
+namespace steganography {
+
+RetCode SanitizeImage(const std::string& image, const std::string& outfile,
+                      const SanitizeConfig& config, SanitizeResult& result) {
+    result = SanitizeResult{};
+    result.path = image;
+    boost::gil::rgb8_image_t input_img;
+    result.rc = LoadImage(image, input_img);
+    if (result.rc != RetCode::kSuccess) {
+        return result.rc;
+    }
+    std::vector<uint8_t> data;
+    result.had_trailer = ReadTrailer(image, data) == RetCode::kSuccess;
+    Wipe(data);
+    result.had_carrier_chunk =
+        ReadCarrierChunk(image, data) == RetCode::kSuccess;
+    Wipe(data);
+
+    ScrubConfig scrub;
+    scrub.bits = config.bits;
+    scrub.seed = config.seed;
+    ScrubReport report;
+    result.rc = ScrubImage(input_img, scrub, report);
+    if (result.rc != RetCode::kSuccess) {
+        return result.rc;
+    }
+    result.had_payload = report.had_payload;
+
+    const ImageType kInputType = GetImageType(image);
+    ImageType output_img_t = ImageType::kPng;
+    result.rc = ResolveOutputType(OutputFormat::kSameAsCover, outfile,
+                                  kInputType, false, output_img_t);
+    if (result.rc != RetCode::kSuccess) {
+        return result.rc;
+    }
+    std::filesystem::path output(outfile);
+    if (output_img_t != kInputType) {
+        output.replace_extension(".png");
+        if (std::filesystem::exists(output)) {
+            result.rc = RetCode::kFileExists;
+            return result.rc;
+        }
+    }
+
+    /* written next to the output first so a failure leaves no half
+     * written image, in particular not in place of the original */
+    std::error_code ec;
+    std::filesystem::create_directories(output.parent_path(), ec);
+    std::filesystem::path staging = output;
+    staging += ".sanitizing";
+    WriteImage(input_img, staging.string(), output_img_t);
+    std::filesystem::rename(staging, output, ec);
+    if (ec) {
+        std::filesystem::remove(staging, ec);
+        result.rc = RetCode::kIoError;
+        return result.rc;
+    }
+    result.output = output.string();
+    return RetCode::kSuccess;
+}
+
+RetCode SanitizeDirectory(const std::string& dir, const SanitizeConfig& config,
+                          std::vector<SanitizeResult>& results) {
+    if ((config.bits < 1) || (config.bits > 8)) {
+        return RetCode::kInvalidArgument;
+    }
+    if (!std::filesystem::is_directory(dir)) {
+        return RetCode::kFileNotFound;
+    }
+
+    /* listed up front so images written into the tree are not visited */
+    const auto kOptions =
+        std::filesystem::directory_options::skip_permission_denied;
+    std::vector<std::filesystem::path> images;
+    auto add = [&images](const std::filesystem::directory_entry& entry) {
+        if (entry.is_regular_file() &&
+            (GetImageType(entry.path().string()) != ImageType::kUnknown)) {
+            images.push_back(entry.path());
+        }
+    };
+    if (config.recursive) {
+        for (const auto& entry :
+             std::filesystem::recursive_directory_iterator(dir, kOptions)) {
+            add(entry);
+        }
+    } else {
+        for (const auto& entry :
+             std::filesystem::directory_iterator(dir, kOptions)) {
+            add(entry);
+        }
+    }
+    std::sort(images.begin(), images.end());
+
+    results.clear();
+    for (const auto& path : images) {
+        std::filesystem::path outfile = path;
+        if (!config.outdir.empty()) {
+            outfile = std::filesystem::path(config.outdir) /
+                      path.lexically_relative(dir);
+        }
+        SanitizeResult result;
+        SanitizeImage(path.string(), outfile.string(), config, result);
+        /* the original would otherwise stay behind under its old name */
+        if (config.outdir.empty() && !result.output.empty() &&
+            (result.output != path.string())) {
+            std::error_code ec;
+            std::filesystem::remove(path, ec);
+        }
+        results.push_back(result);
+    }
+    return RetCode::kSuccess;
+}
+
+}  // namespace steganography
@@ -0,0 +1,151 @@
+#include "utils/scan.hpp"
+
+#include <algorithm>
+#include <boost/gil.hpp>
+#include <cstdio>
+#include <filesystem>
+#include <iomanip>
+#include <ostream>
+#include <string>
+#include <vector>
+
+#include "utils/detect.hpp"
+#include "utils/image_io.hpp"
+
+namespace steganography {
+
+static std::vector<std::filesystem::path> FindImages(const std::string& dir,
+                                                     bool recursive) {
+    const auto kOptions =
+        std::filesystem::directory_options::skip_permission_denied;
+    std::vector<std::filesystem::path> images;
+    auto add = [&images](const std::filesystem::directory_entry& entry) {
+        if (entry.is_regular_file() &&
+            (GetImageType(entry.path().string()) != ImageType::kUnknown)) {
+            images.push_back(entry.path());
+        }
+    };
+    if (recursive) {
+        for (const auto& entry :
+             std::filesystem::recursive_directory_iterator(dir, kOptions)) {
+            add(entry);
+        }
+    } else {
+        for (const auto& entry :
+             std::filesystem::directory_iterator(dir, kOptions)) {
+            add(entry);
+        }
+    }
+    std::sort(images.begin(), images.end());
+    return images;
+}
+
+RetCode ScanDirectory(const std::string& dir, const ScanConfig& config,
+                      std::vector<ScanResult>& results) {
+    if ((config.threshold < 0.0) || (config.threshold > 1.0)) {
+        return RetCode::kInvalidArgument;
+    }
+    if (!std::filesystem::is_directory(dir)) {
+        return RetCode::kFileNotFound;
+    }
+
+    results.clear();
+    for (const auto& path : FindImages(dir, config.recursive)) {
+        boost::gil::rgb8_image_t image;
+        if (LoadImage(path.string(), image) != RetCode::kSuccess) {
+            continue;
+        }
+        ScanResult result;
+        result.path = path.string();
+        result.report = Detect(image);
+        if (result.report.confidence >= config.threshold) {
+            results.push_back(result);
+        }
+    }
+
+    std::stable_sort(results.begin(), results.end(),
+                     [](const ScanResult& lhs, const ScanResult& rhs) {
+                         return lhs.report.confidence >
+                                rhs.report.confidence;
+                     });
+    return RetCode::kSuccess;
+}
+
+/* quotes a field if it holds a separator, quote, or line break */
+static std::string CsvField(const std::string& field) {
+    if (field.find_first_of(",\"\r\n") == std::string::npos) {
+        return field;
+    }
+    std::string quoted = "\"";
+    for (char c : field) {
+        quoted += (c == '"') ? "\"\"" : std::string(1, c);
+    }
+    return quoted + "\"";
+}
+
+static std::string JsonString(const std::string& str) {
+    std::string quoted = "\"";
+    for (char c : str) {
+        if ((c == '"') || (c == '\\')) {
+            quoted += '\\';
+            quoted += c;
+        } else if (static_cast<unsigned char>(c) < 0x20) {
+            char escaped[8];
+            std::snprintf(escaped, sizeof(escaped), "\\u%04x", c);
+            quoted += escaped;
+        } else {
+            quoted += c;
+        }
+    }
+    return quoted + "\"";
+}
+
+static void WriteCsv(const std::vector<ScanResult>& results,
+                     std::ostream& os) {
+    os << "rank,path,confidence,payload_fraction,bit_depth,estimated_bytes";
+    if (!results.empty()) {
+        for (const DetectorScore& score : results[0].report.detectors) {
+            os << "," << score.name << "_fraction," << score.name
+               << "_confidence";
+        }
+    }
+    os << "\n";
+    for (std::size_t i = 0; i < results.size(); ++i) {
+        const DetectionReport& report = results[i].report;
+        os << i + 1 << "," << CsvField(results[i].path) << ","
+           << report.confidence << "," << report.payload_fraction << ","
+           << report.bit_depth << "," << report.estimated_bytes;
+        for (const DetectorScore& score : report.detectors) {
+            os << "," << score.payload_fraction << "," << score.confidence;
+        }
+        os << "\n";
+    }
+}
+
+static void WriteJson(const std::vector<ScanResult>& results,
+                      std::ostream& os) {
+    os << "[";
+    for (std::size_t i = 0; i < results.size(); ++i) {
+        os << ((i == 0) ? "\n" : ",\n") << "  {\"rank\": " << i + 1
+           << ", \"path\": " << JsonString(results[i].path)
+           << ", \"detection\": " << DetectionReportJson(results[i].report)
+           << "}";
+    }
+    os << (results.empty() ? "]\n" : "\n]\n");
+}
+
+void WriteScanReport(const std::vector<ScanResult>& results, ScanFormat format,
+                     std::ostream& os) {
+    std::ios_base::fmtflags flags = os.flags();
+    std::streamsize precision = os.precision();
+    os << std::fixed << std::setprecision(4);
+    if (format == ScanFormat::kJson) {
+        WriteJson(results, os);
+    } else {
+        WriteCsv(results, os);
+    }
+    os.flags(flags);
+    os.precision(precision);
+}
+
+}  // namespace steganography
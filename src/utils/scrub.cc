@@ -0,0 +1,103 @@
+#include "utils/scrub.hpp"
+
+#include <algorithm>
+#include <boost/gil.hpp>
+#include <cmath>
+#include <cstddef>
+#include <cstdint>
+#include <random>
+#include <string>
+#include <vector>
+
+#include "utils/icc.hpp"
+#include "utils/image_io.hpp"
+#include "utils/key.hpp"
+#include "utils/paths.hpp"
+#include "utils/payload.hpp"
+
+namespace steganography {
+
+RetCode ScrubImage(boost::gil::rgb8_image_t& image, const ScrubConfig& config,
+                   ScrubReport& report) {
+    if ((config.bits < 1) || (config.bits > 8)) {
+        return RetCode::kInvalidArgument;
+    }
+    report = ScrubReport{};
+    std::vector<uint8_t> payload;
+    report.had_payload = ExtractPayload(image, payload) == RetCode::kSuccess;
+    Wipe(payload);
+
+    std::mt19937_64 rng(config.seed.has_value() ? *config.seed
+                                                : std::random_device{}());
+    std::uniform_real_distribution<double> noise(-1.0, 1.0);
+    const int kMask = (1 << config.bits) - 1;
+    /* the middle of the values a channel's high bits allow */
+    auto center = [kMask](uint8_t value) {
+        return (value & ~kMask) + kMask / 2.0;
+    };
+
+    /* only low bits change, so the neighbours' high bits read the same
+     * before and after they are scrubbed */
+    auto view = boost::gil::view(image);
+    std::size_t changed = 0;
+    for (std::ptrdiff_t row = 0; row < view.height(); ++row) {
+        for (std::ptrdiff_t col = 0; col < view.width(); ++col) {
+            for (int i = 0; i < 3; ++i) {
+                double sum = 0.0;
+                int count = 0;
+                for (std::ptrdiff_t y = std::max<std::ptrdiff_t>(row - 1, 0);
+                     y <= std::min(row + 1, view.height() - 1); ++y) {
+                    for (std::ptrdiff_t x =
+                             std::max<std::ptrdiff_t>(col - 1, 0);
+                         x <= std::min(col + 1, view.width() - 1); ++x) {
+                        sum += center(view(x, y)[i]);
+                        ++count;
+                    }
+                }
+                const uint8_t kValue = view(col, row)[i];
+                const long kHigh = kValue & ~kMask;
+                long scrubbed = std::clamp(
+                    std::lround(sum / count + noise(rng)), kHigh,
+                    kHigh + kMask);
+                view(col, row)[i] = static_cast<uint8_t>(scrubbed);
+                changed += (scrubbed != kValue);
+            }
+        }
+    }
+    const std::size_t kChannels =
+        static_cast<std::size_t>(view.width() * view.height()) * 3;
+    report.changed = (kChannels == 0) ? 0.0
+                                      : static_cast<double>(changed) /
+                                            static_cast<double>(kChannels);
+    return RetCode::kSuccess;
+}
+
+RetCode Scrub(const std::string& image, const std::string& outfile,
+              const ScrubConfig& config, ScrubReport& report) {
+    boost::gil::rgb8_image_t input_img;
+    RetCode rc = LoadImage(image, input_img);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    /* nothing is hidden in the output, so any format will do */
+    ImageType output_img_t = ImageType::kPng;
+    rc = ResolveOutputType(config.output_format, outfile, GetImageType(image),
+                           false, output_img_t);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    rc = ScrubImage(input_img, config, report);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    if (config.create_dirs) {
+        rc = CreateParentDirectories(outfile);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+    WriteImage(input_img, outfile, output_img_t, ReadIccProfile(image));
+    return RetCode::kSuccess;
+}
+
+}  // namespace steganography
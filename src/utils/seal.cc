@@ -0,0 +1,170 @@
+#include "utils/seal.hpp"
+
+#include <openssl/evp.h>
+
+#include <algorithm>
+#include <array>
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <string>
+#include <vector>
+
+#include "utils/bytes.hpp"
+#include "utils/image_io.hpp"
+#include "utils/key.hpp"
+#include "utils/paths.hpp"
+#include "utils/payload.hpp"
+
+namespace steganography {
+
+/* the embedded seal is a magic word, the signer, and the signature */
+static const uint32_t kSealMagic = 0x5354534C; /* "STSL" */
+static const std::size_t kSignatureSize = 64;
+static const std::size_t kSealSize = 4 + kSignerSize + kSignatureSize;
+
+/* keeps signatures over images from passing for anything else */
+static const char kSealDomain[] = "steganography seal v1";
+
+/* the embedded seal lives in the low nibble the signature leaves out */
+static const uint8_t kSignedMask = 0xF0;
+
+/* SHA-256 of the domain, the dimensions, and the signed bits of every
+ * channel row by row */
+static std::vector<uint8_t> SealDigest(const boost::gil::rgb8_image_t& image) {
+    std::vector<uint8_t> prefix(kSealDomain,
+                                kSealDomain + sizeof(kSealDomain) - 1);
+    AppendInt<uint32_t>(prefix, static_cast<uint32_t>(image.width()));
+    AppendInt<uint32_t>(prefix, static_cast<uint32_t>(image.height()));
+
+    EVP_MD_CTX* ctx = EVP_MD_CTX_new();
+    EVP_DigestInit_ex(ctx, EVP_sha256(), nullptr);
+    EVP_DigestUpdate(ctx, prefix.data(), prefix.size());
+    auto view = boost::gil::const_view(image);
+    std::vector<uint8_t> row(static_cast<std::size_t>(view.width()) * 3);
+    for (std::ptrdiff_t y = 0; y < view.height(); ++y) {
+        std::size_t i = 0;
+        for (auto it = view.row_begin(y); it != view.row_end(y); ++it) {
+            for (int c = 0; c < 3; ++c) {
+                row[i++] = static_cast<uint8_t>((*it)[c] & kSignedMask);
+            }
+        }
+        EVP_DigestUpdate(ctx, row.data(), row.size());
+    }
+    std::vector<uint8_t> digest(EVP_MD_size(EVP_sha256()));
+    EVP_DigestFinal_ex(ctx, digest.data(), nullptr);
+    EVP_MD_CTX_free(ctx);
+    return digest;
+}
+
+static bool Sign(const StegKey& key, const std::vector<uint8_t>& digest,
+                 SealSigner& signer,
+                 std::array<uint8_t, kSignatureSize>& signature) {
+    std::array<uint8_t, kKeySize> seed = DeriveSubkey(key, "seal");
+    EVP_PKEY* pkey = EVP_PKEY_new_raw_private_key(EVP_PKEY_ED25519, nullptr,
+                                                  seed.data(), seed.size());
+    Wipe(seed);
+    if (pkey == nullptr) {
+        return false;
+    }
+    std::size_t signer_size = signer.size();
+    std::size_t signature_size = signature.size();
+    EVP_MD_CTX* ctx = EVP_MD_CTX_new();
+    bool ok =
+        (EVP_PKEY_get_raw_public_key(pkey, signer.data(), &signer_size) ==
+         1) &&
+        (EVP_DigestSignInit(ctx, nullptr, nullptr, nullptr, pkey) == 1) &&
+        (EVP_DigestSign(ctx, signature.data(), &signature_size, digest.data(),
+                        digest.size()) == 1);
+    EVP_MD_CTX_free(ctx);
+    EVP_PKEY_free(pkey);
+    return ok;
+}
+
+static bool Verify(const SealSigner& signer, const std::vector<uint8_t>& digest,
+                   const uint8_t* signature) {
+    EVP_PKEY* pkey = EVP_PKEY_new_raw_public_key(EVP_PKEY_ED25519, nullptr,
+                                                 signer.data(), signer.size());
+    if (pkey == nullptr) {
+        return false;
+    }
+    EVP_MD_CTX* ctx = EVP_MD_CTX_new();
+    bool ok = (EVP_DigestVerifyInit(ctx, nullptr, nullptr, nullptr, pkey) ==
+               1) &&
+              (EVP_DigestVerify(ctx, signature, kSignatureSize, digest.data(),
+                                digest.size()) == 1);
+    EVP_MD_CTX_free(ctx);
+    EVP_PKEY_free(pkey);
+    return ok;
+}
+
+RetCode SealImage(const std::string& image, const std::string& outfile,
+                  const SealConfig& config, SealSigner& signer) {
+    StegKey key;
+    RetCode rc = LoadKeyFile(config.keyfile, key);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    boost::gil::rgb8_image_t sealed;
+    rc = LoadImage(image, sealed);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    ImageType sealed_t = ImageType::kPng;
+    rc = ResolveOutputType(OutputFormat::kAuto, outfile, GetImageType(image),
+                           true, sealed_t);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+
+    std::array<uint8_t, kSignatureSize> signature{};
+    if (!Sign(key, SealDigest(sealed), signer, signature)) {
+        return RetCode::kInvalidKey;
+    }
+    std::vector<uint8_t> seal;
+    AppendInt<uint32_t>(seal, kSealMagic);
+    seal.insert(seal.end(), signer.cbegin(), signer.cend());
+    seal.insert(seal.end(), signature.cbegin(), signature.cend());
+    rc = EmbedPayload(sealed, seal);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    if (config.create_dirs) {
+        rc = CreateParentDirectories(outfile);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+    WriteImage(sealed, outfile, sealed_t);
+    return RetCode::kSuccess;
+}
+
+RetCode VerifySeal(const std::string& image, SealSigner& signer) {
+    boost::gil::rgb8_image_t sealed;
+    RetCode rc = LoadImage(image, sealed);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    std::vector<uint8_t> seal;
+    rc = ExtractPayload(sealed, seal);
+    if ((rc != RetCode::kSuccess) || (seal.size() != kSealSize) ||
+        (ReadInt<uint32_t>(seal, 0) != kSealMagic)) {
+        return RetCode::kPayloadNotFound;
+    }
+    std::copy(seal.cbegin() + 4, seal.cbegin() + 4 + kSignerSize,
+              signer.begin());
+    if (!Verify(signer, SealDigest(sealed), seal.data() + 4 + kSignerSize)) {
+        return RetCode::kSealBroken;
+    }
+    return RetCode::kSuccess;
+}
+
+std::string SignerToHex(const SealSigner& signer) {
+    return ToHex(signer.data(), signer.size());
+}
+
+bool SignerFromHex(const std::string& hex, SealSigner& signer) {
+    return FromHex(hex, signer.data(), signer.size());
+}
+
+}  // namespace steganography
@@ -0,0 +1,504 @@
+#include "utils/server.hpp"
+
+#include <arpa/inet.h>
+#include <netinet/in.h>
+#include <sys/socket.h>
+#include <sys/time.h>
+#include <unistd.h>
+
+#include <algorithm>
+#include <cctype>
+#include <cerrno>
+#include <cstddef>
+#include <cstdint>
+#include <cstdlib>
+#include <exception>
+#include <filesystem>
+#include <fstream>
+#include <iostream>
+#include <iterator>
+#include <map>
+#include <string>
+#include <system_error>
+
+#include "utils/paths.hpp"
+
+namespace steganography {
+
+static const std::size_t kMaxHeaderSize = 16 * 1024;
+static const int kSocketTimeoutSec = 30;
+static const int kListenBacklog = 16;
+
+struct HttpRequest {
+    std::string method;
+    std::string path;
+    std::map<std::string, std::string> headers; /* names are lowercase */
+    std::string body;
+};
+
+struct HttpResponse {
+    int status = 200;
+    std::string content_type = "text/plain";
+    std::string body;
+};
+
+struct FormPart {
+    std::string filename; /* empty for plain text fields */
+    std::string data;
+};
+
+using FormData = std::map<std::string, FormPart>;
+
+/* closes the wrapped descriptor when it goes out of scope */
+class SocketGuard {
+   public:
+    explicit SocketGuard(int fd) : fd_(fd) {}
+    ~SocketGuard() {
+        if (fd_ >= 0) {
+            close(fd_);
+        }
+    }
+    SocketGuard(const SocketGuard&) = delete;
+    SocketGuard& operator=(const SocketGuard&) = delete;
+
+    int Get() const { return fd_; }
+
+   private:
+    int fd_;
+};
+
+static std::string ToLower(std::string str) {
+    std::transform(str.begin(), str.end(), str.begin(),
+                   [](unsigned char c) { return std::tolower(c); });
+    return str;
+}
+
+static std::string Trim(const std::string& str) {
+    const char* kWhitespace = " \t";
+    std::size_t begin = str.find_first_not_of(kWhitespace);
+    if (begin == std::string::npos) {
+        return "";
+    }
+    std::size_t end = str.find_last_not_of(kWhitespace);
+    return str.substr(begin, end - begin + 1);
+}
+
+/* value of a 'name=value' or 'name="value"' parameter in a header */
+static std::string HeaderParam(const std::string& header,
+                               const std::string& name) {
+    std::size_t pos = 0;
+    while ((pos = header.find(';', pos)) != std::string::npos) {
+        ++pos;
+        std::size_t eq = header.find('=', pos);
+        if (eq == std::string::npos) {
+            break;
+        }
+        if (ToLower(Trim(header.substr(pos, eq - pos))) != name) {
+            continue;
+        }
+        std::size_t end = header.find(';', eq);
+        std::string value = Trim(header.substr(
+            eq + 1, (end == std::string::npos) ? end : end - eq - 1));
+        if ((value.size() >= 2) && (value.front() == '"') &&
+            (value.back() == '"')) {
+            value = value.substr(1, value.size() - 2);
+        }
+        return value;
+    }
+    return "";
+}
+
+static bool SendAll(int fd, const std::string& data) {
+    std::size_t sent = 0;
+    while (sent < data.size()) {
+        ssize_t n =
+            send(fd, data.data() + sent, data.size() - sent, MSG_NOSIGNAL);
+        if (n < 0) {
+            if (errno == EINTR) {
+                continue;
+            }
+            return false;
+        }
+        sent += static_cast<std::size_t>(n);
+    }
+    return true;
+}
+
+/* reads more data from the socket into buffer, false on error or EOF */
+static bool Receive(int fd, std::string& buffer) {
+    char chunk[64 * 1024];
+    ssize_t n = 0;
+    do {
+        n = recv(fd, chunk, sizeof(chunk), 0);
+    } while ((n < 0) && (errno == EINTR));
+    if (n <= 0) {
+        return false;
+    }
+    buffer.append(chunk, static_cast<std::size_t>(n));
+    return true;
+}
+
+/* returns 0 once a complete request was read, otherwise the HTTP status
+ * that describes what is wrong with it */
+static int ReadRequest(int fd, std::size_t max_request, HttpRequest& request) {
+    std::string buffer;
+    std::size_t header_end = std::string::npos;
+    while ((header_end = buffer.find("\r\n\r\n")) == std::string::npos) {
+        if (buffer.size() > kMaxHeaderSize) {
+            return 431;
+        }
+        if (!Receive(fd, buffer)) {
+            return 400;
+        }
+    }
+
+    /* request line, e.g. 'POST /merge HTTP/1.1' */
+    std::size_t line_end = buffer.find("\r\n");
+    std::string line = buffer.substr(0, line_end);
+    std::size_t sp1 = line.find(' ');
+    std::size_t sp2 = line.rfind(' ');
+    if ((sp1 == std::string::npos) || (sp1 == sp2)) {
+        return 400;
+    }
+    request.method = line.substr(0, sp1);
+    request.path = line.substr(sp1 + 1, sp2 - sp1 - 1);
+    request.path = request.path.substr(0, request.path.find('?'));
+
+    std::size_t pos = line_end + 2;
+    while (pos < header_end) {
+        std::size_t end = buffer.find("\r\n", pos);
+        std::string header = buffer.substr(pos, end - pos);
+        std::size_t colon = header.find(':');
+        if (colon == std::string::npos) {
+            return 400;
+        }
+        request.headers[ToLower(Trim(header.substr(0, colon)))] =
+            Trim(header.substr(colon + 1));
+        pos = end + 2;
+    }
+
+    /* uploads must announce their size so they can be bounded up front */
+    if (request.headers.contains("transfer-encoding")) {
+        return 411;
+    }
+    std::size_t length = 0;
+    if (request.headers.contains("content-length")) {
+        try {
+            length = std::stoull(request.headers["content-length"]);
+        } catch (const std::exception&) {
+            return 400;
+        }
+    }
+    if (length > max_request) {
+        return 413;
+    }
+
+    request.body = buffer.substr(header_end + 4);
+    while (request.body.size() < length) {
+        if (!Receive(fd, request.body)) {
+            return 400;
+        }
+    }
+    request.body.resize(length);
+    return 0;
+}
+
+static bool ParseMultipart(const HttpRequest& request, FormData& form) {
+    auto content_type = request.headers.find("content-type");
+    if ((content_type == request.headers.end()) ||
+        !ToLower(content_type->second).starts_with("multipart/form-data")) {
+        return false;
+    }
+    std::string boundary = HeaderParam(content_type->second, "boundary");
+    if (boundary.empty()) {
+        return false;
+    }
+
+    const std::string& body = request.body;
+    const std::string kDelimiter = "--" + boundary;
+    std::size_t pos = body.find(kDelimiter);
+    while (pos != std::string::npos) {
+        pos += kDelimiter.size();
+        if (body.compare(pos, 2, "--") == 0) {
+            return true; /* closing delimiter */
+        }
+        if (body.compare(pos, 2, "\r\n") != 0) {
+            return false;
+        }
+        pos += 2;
+
+        std::size_t headers_end = body.find("\r\n\r\n", pos);
+        if (headers_end == std::string::npos) {
+            return false;
+        }
+        std::string name;
+        FormPart part;
+        while (pos < headers_end) {
+            std::size_t end = body.find("\r\n", pos);
+            std::string header = body.substr(pos, end - pos);
+            if (ToLower(header).starts_with("content-disposition:")) {
+                name = HeaderParam(header, "name");
+                part.filename = HeaderParam(header, "filename");
+            }
+            pos = end + 2;
+        }
+
+        std::size_t data_begin = headers_end + 4;
+        std::size_t next = body.find("\r\n" + kDelimiter, data_begin);
+        if (next == std::string::npos) {
+            return false;
+        }
+        part.data = body.substr(data_begin, next - data_begin);
+        if (!name.empty()) {
+            form[name] = std::move(part);
+        }
+        pos = next + 2;
+    }
+    return false;
+}
+
+static bool WriteFile(const std::string& path, const std::string& data) {
+    std::ofstream ofs(path, std::ofstream::binary);
+    ofs.write(data.data(), static_cast<std::streamsize>(data.size()));
+    return static_cast<bool>(ofs);
+}
+
+static bool ReadFile(const std::string& path, std::string& data) {
+    std::ifstream ifs(path, std::ifstream::binary);
+    if (!ifs.is_open()) {
+        return false;
+    }
+    data.assign(std::istreambuf_iterator<char>(ifs),
+                std::istreambuf_iterator<char>());
+    return true;
+}
+
+static HttpResponse TextResponse(int status, const std::string& message) {
+    HttpResponse response;
+    response.status = status;
+    response.body = message + "\n";
+    return response;
+}
+
+static HttpResponse ErrorResponse(RetCode rc) {
+    switch (rc) {
+        case RetCode::kSuccess:
+            break;
+        case RetCode::kInvalidFileFormat:
+            return TextResponse(415, "only " + AcceptedFormats() +
+                                         " images are accepted");
+        case RetCode::kFileNotFound:
+            return TextResponse(400, "missing image upload");
+        case RetCode::kInvalidDimensions:
+            return TextResponse(422, "secret does not fit inside the cover");
+        case RetCode::kInsufficientCapacity:
+            return TextResponse(422, "payload is too large for the cover");
+        case RetCode::kPayloadNotFound:
+        case RetCode::kInvalidPayload:
+        case RetCode::kUnsupportedFormat:
+        case RetCode::kPayloadDamaged:
+            return TextResponse(422, "no usable hidden payload in the image");
+        case RetCode::kIoError:
+        case RetCode::kFileExists:
+            return TextResponse(500, "failed to read or write a file");
+        case RetCode::kInvalidKey:
+            return TextResponse(400, "key file is malformed");
+        case RetCode::kInvalidArgument:
+            return TextResponse(400, "invalid combination of form fields");
+        case RetCode::kKeyMismatch:
+        case RetCode::kPasswordMismatch:
+        case RetCode::kAuthenticationFailed:
+            return TextResponse(403, "authentication failed");
+        case RetCode::kNetworkError:
+            return TextResponse(502, "failed to download an input");
+        case RetCode::kImageTooLarge:
+            return TextResponse(413, "image dimensions exceed the limit");
+        case RetCode::kSealBroken:
+            return TextResponse(422, "the image was altered after sealing");
+        case RetCode::kConversionRefused:
+            return TextResponse(415, "the image needs an unsupported "
+                                     "conversion");
+        case RetCode::kSameInput:
+            return TextResponse(422, "the cover and secret are the same");
+        case RetCode::kIncompleteParts:
+            return TextResponse(422, "parts of the split file are missing");
+        case RetCode::kClipboardUnavailable:
+        case RetCode::kOutOfBandChanges:
+        case RetCode::kOutputIsInput:
+            return TextResponse(500, "internal error");
+    }
+    return TextResponse(500, "internal error");
+}
+
+/* stores the named upload in the scratch directory, empty if absent */
+static std::string StoreUpload(const FormData& form, const std::string& name,
+                               const ScratchDir& scratch) {
+    auto part = form.find(name);
+    if (part == form.end()) {
+        return "";
+    }
+    std::string path = scratch.File(name);
+    return WriteFile(path, part->second.data) ? path : "";
+}
+
+static std::string FormField(const FormData& form, const std::string& name) {
+    auto part = form.find(name);
+    return (part == form.end()) ? "" : part->second.data;
+}
+
+static HttpResponse HandleForm(const std::string& path, const FormData& form,
+                               const ScratchDir& scratch,
+                               const ServerConfig& server) {
+    /* merge: cover + secret, unmerge: image, both: keyfile + password */
+    std::string outfile = scratch.File("result.png");
+    std::string keyfile = StoreUpload(form, "keyfile", scratch);
+    std::string password = FormField(form, "password");
+    if (!password.empty() && keyfile.empty()) {
+        return TextResponse(400, "a password requires a key file");
+    }
+    RetCode rc = RetCode::kSuccess;
+    if (path == "/merge") {
+        std::string cover = StoreUpload(form, "cover", scratch);
+        std::string secret = StoreUpload(form, "secret", scratch);
+        if (cover.empty() || secret.empty()) {
+            return TextResponse(400, "expected 'cover' and 'secret' uploads");
+        }
+        MergeConfig config;
+        config.keyfile = keyfile;
+        config.password = password;
+        config.threads = server.threads;
+        config.memory_budget = server.memory_budget;
+        rc = Merge(cover, secret, outfile, config);
+    } else {
+        std::string image = StoreUpload(form, "image", scratch);
+        if (image.empty()) {
+            return TextResponse(400, "expected an 'image' upload");
+        }
+        UnmergeConfig config;
+        config.keyfile = keyfile;
+        config.password = password;
+        config.threads = server.threads;
+        config.memory_budget = server.memory_budget;
+        rc = Unmerge(image, outfile, config);
+    }
+    if (rc != RetCode::kSuccess) {
+        return ErrorResponse(rc);
+    }
+
+    HttpResponse response;
+    response.content_type = "image/png";
+    if (!ReadFile(outfile, response.body)) {
+        return ErrorResponse(RetCode::kIoError);
+    }
+    return response;
+}
+
+static HttpResponse HandleRequest(const HttpRequest& request,
+                                  const ServerConfig& config) {
+    if ((request.path != "/merge") && (request.path != "/unmerge")) {
+        return TextResponse(404, "not found");
+    }
+    if (request.method != "POST") {
+        return TextResponse(405, "only POST is supported");
+    }
+    FormData form;
+    if (!ParseMultipart(request, form)) {
+        return TextResponse(400, "expected a multipart/form-data body");
+    }
+
+    ScratchDir scratch("steganography-serve");
+    if (!scratch.Valid()) {
+        return ErrorResponse(RetCode::kIoError);
+    }
+    try {
+        return HandleForm(request.path, form, scratch, config);
+    } catch (const std::exception&) {
+        /* image codecs throw on truncated or corrupt uploads */
+        return TextResponse(422, "could not decode the uploaded image");
+    }
+}
+
+static std::string StatusReason(int status) {
+    const std::map<int, std::string> kReasons = {
+        {200, "OK"},
+        {400, "Bad Request"},
+        {403, "Forbidden"},
+        {404, "Not Found"},
+        {405, "Method Not Allowed"},
+        {411, "Length Required"},
+        {413, "Payload Too Large"},
+        {415, "Unsupported Media Type"},
+        {422, "Unprocessable Entity"},
+        {431, "Request Header Fields Too Large"},
+        {500, "Internal Server Error"},
+        {502, "Bad Gateway"},
+    };
+    auto reason = kReasons.find(status);
+    return (reason == kReasons.end()) ? "Unknown" : reason->second;
+}
+
+static void SendResponse(int fd, const HttpResponse& response) {
+    std::string head = "HTTP/1.1 " + std::to_string(response.status) + " " +
+                       StatusReason(response.status) + "\r\n" +
+                       "Content-Type: " + response.content_type + "\r\n" +
+                       "Content-Length: " +
+                       std::to_string(response.body.size()) + "\r\n" +
+                       "Connection: close\r\n\r\n";
+    if (SendAll(fd, head)) {
+        SendAll(fd, response.body);
+    }
+}
+
+static void ServeConnection(int fd, const ServerConfig& config) {
+    /* a stalled client must not block the server forever */
+    timeval timeout{};
+    timeout.tv_sec = kSocketTimeoutSec;
+    setsockopt(fd, SOL_SOCKET, SO_RCVTIMEO, &timeout, sizeof(timeout));
+    setsockopt(fd, SOL_SOCKET, SO_SNDTIMEO, &timeout, sizeof(timeout));
+
+    HttpRequest request;
+    int status = ReadRequest(fd, config.max_request, request);
+    HttpResponse response = (status == 0)
+                                ? HandleRequest(request, config)
+                                : TextResponse(status, StatusReason(status));
+    SendResponse(fd, response);
+    std::clog << request.method << " " << request.path << " "
+              << response.status << std::endl;
+}
+
+RetCode Serve(const ServerConfig& config) {
+    SetImageLimits(config.image_limits);
+    sockaddr_in addr{};
+    addr.sin_family = AF_INET;
+    addr.sin_port = htons(config.port);
+    if (inet_pton(AF_INET, config.address.c_str(), &addr.sin_addr) != 1) {
+        return RetCode::kInvalidArgument;
+    }
+
+    SocketGuard listener(socket(AF_INET, SOCK_STREAM, 0));
+    if (listener.Get() < 0) {
+        return RetCode::kIoError;
+    }
+    int reuse = 1;
+    setsockopt(listener.Get(), SOL_SOCKET, SO_REUSEADDR, &reuse,
+               sizeof(reuse));
+    if ((bind(listener.Get(), reinterpret_cast<sockaddr*>(&addr),
+              sizeof(addr)) != 0) ||
+        (listen(listener.Get(), kListenBacklog) != 0)) {
+        return RetCode::kIoError;
+    }
+    std::clog << "listening on http://" << config.address << ":"
+              << config.port << std::endl;
+
+    /* requests are handled one at a time, scale out with more instances */
+    while (true) {
+        int fd = accept(listener.Get(), nullptr, nullptr);
+        if (fd < 0) {
+            continue;
+        }
+        SocketGuard connection(fd);
+        ServeConnection(connection.Get(), config);
+    }
+}
+
+}  // namespace steganography
@@ -0,0 +1,372 @@
+#include "utils/split.hpp"
+
+#include <openssl/evp.h>
+
+#include <algorithm>
+#include <boost/gil.hpp>
+#include <cmath>
+#include <cstddef>
+#include <cstdint>
+#include <filesystem>
+#include <fstream>
+#include <iterator>
+#include <map>
+#include <string>
+#include <system_error>
+#include <vector>
+
+#include "utils/bytes.hpp"
+#include "utils/image_io.hpp"
+#include "utils/paths.hpp"
+#include "utils/payload.hpp"
+
+namespace steganography {
+
+/* a part record, then the part's bytes, see docs/FORMAT.md */
+static const uint32_t kPartMagic = 0x53505254;     /* "SPRT" */
+static const uint32_t kManifestMagic = 0x534D414E; /* "SMAN" */
+static const std::size_t kDigestSize = 32;
+static const std::size_t kPartHeaderSize = 4 + 4 + 4 + 8 + kDigestSize;
+
+/* the smallest manifest image, grown until the listing fits */
+static const std::ptrdiff_t kManifestMinSide = 8;
+
+struct PartRecord {
+    uint32_t index = 0;
+    uint32_t count = 0;
+    uint64_t total = 0;           /* length of the whole file */
+    std::vector<uint8_t> digest;  /* SHA-256 of the whole file */
+    std::vector<uint8_t> data;
+};
+
+struct ManifestEntry {
+    std::string name;
+    uint64_t length = 0;
+    std::vector<uint8_t> digest; /* SHA-256 of the part's bytes */
+};
+
+static std::vector<uint8_t> Sha256(const uint8_t* data, std::size_t size) {
+    std::vector<uint8_t> digest(kDigestSize);
+    EVP_Digest(data, size, digest.data(), nullptr, EVP_sha256(), nullptr);
+    return digest;
+}
+
+static bool ReadFile(const std::string& path, std::vector<uint8_t>& data) {
+    std::ifstream ifs(path, std::ifstream::binary);
+    if (!ifs.is_open()) {
+        return false;
+    }
+    data.assign(std::istreambuf_iterator<char>(ifs),
+                std::istreambuf_iterator<char>());
+    return !ifs.bad();
+}
+
+/* part-001.png, part-002.png, and so on */
+static std::string PartName(std::size_t index) {
+    std::string number = std::to_string(index + 1);
+    if (number.size() < 3) {
+        number.insert(0, 3 - number.size(), '0');
+    }
+    return "part-" + number + ".png";
+}
+
+static bool ParsePart(const std::vector<uint8_t>& payload, PartRecord& part) {
+    if ((payload.size() < kPartHeaderSize) ||
+        (ReadInt<uint32_t>(payload, 0) != kPartMagic)) {
+        return false;
+    }
+    part.index = ReadInt<uint32_t>(payload, 4);
+    part.count = ReadInt<uint32_t>(payload, 8);
+    part.total = ReadInt<uint64_t>(payload, 12);
+    part.digest.assign(payload.cbegin() + 20,
+                       payload.cbegin() + kPartHeaderSize);
+    part.data.assign(payload.cbegin() + kPartHeaderSize, payload.cend());
+    return part.index < part.count;
+}
+
+static bool ParseManifest(const std::vector<uint8_t>& payload,
+                          uint64_t& total, std::vector<uint8_t>& digest,
+                          std::vector<ManifestEntry>& entries) {
+    const std::size_t kHeaderSize = 4 + 4 + 8 + kDigestSize;
+    if ((payload.size() < kHeaderSize) ||
+        (ReadInt<uint32_t>(payload, 0) != kManifestMagic)) {
+        return false;
+    }
+    const uint32_t kCount = ReadInt<uint32_t>(payload, 4);
+    total = ReadInt<uint64_t>(payload, 8);
+    digest.assign(payload.cbegin() + 16, payload.cbegin() + kHeaderSize);
+    std::size_t offset = kHeaderSize;
+    for (uint32_t i = 0; i < kCount; ++i) {
+        if (offset + 2 > payload.size()) {
+            return false;
+        }
+        const std::size_t kNameSize = ReadInt<uint16_t>(payload, offset);
+        offset += 2;
+        if (offset + kNameSize + 8 + kDigestSize > payload.size()) {
+            return false;
+        }
+        ManifestEntry entry;
+        entry.name.assign(payload.cbegin() + offset,
+                          payload.cbegin() + offset + kNameSize);
+        offset += kNameSize;
+        entry.length = ReadInt<uint64_t>(payload, offset);
+        offset += 8;
+        entry.digest.assign(payload.cbegin() + offset,
+                            payload.cbegin() + offset + kDigestSize);
+        offset += kDigestSize;
+        entries.push_back(std::move(entry));
+    }
+    return !entries.empty() && (offset == payload.size());
+}
+
+/* a plain gray image just large enough to hold the listing */
+static RetCode WriteManifest(const std::string& path,
+                             const std::vector<uint8_t>& data,
+                             const std::vector<SplitPart>& parts) {
+    std::vector<uint8_t> listing;
+    AppendInt<uint32_t>(listing, kManifestMagic);
+    AppendInt<uint32_t>(listing, static_cast<uint32_t>(parts.size()));
+    AppendInt<uint64_t>(listing, data.size());
+    std::vector<uint8_t> digest = Sha256(data.data(), data.size());
+    listing.insert(listing.end(), digest.cbegin(), digest.cend());
+    for (const SplitPart& part : parts) {
+        AppendString(listing, part.file);
+        AppendInt<uint64_t>(listing, part.length);
+        digest = Sha256(data.data() + part.offset, part.length);
+        listing.insert(listing.end(), digest.cbegin(), digest.cend());
+    }
+
+    /* a channel nibble holds half a byte, so each pixel holds 1.5 */
+    std::ptrdiff_t side = std::max(
+        kManifestMinSide,
+        static_cast<std::ptrdiff_t>(std::ceil(
+            std::sqrt(static_cast<double>(listing.size()) / 1.5))));
+    boost::gil::rgb8_image_t manifest;
+    while (true) {
+        manifest = boost::gil::rgb8_image_t(side, side);
+        boost::gil::fill_pixels(boost::gil::view(manifest),
+                                boost::gil::rgb8_pixel_t(128, 128, 128));
+        if (PayloadCapacity(manifest) >= listing.size()) {
+            break;
+        }
+        ++side;
+    }
+    RetCode rc = EmbedPayload(manifest, listing);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    WriteImage(manifest, path, ImageType::kPng);
+    return RetCode::kSuccess;
+}
+
+RetCode Split(const std::string& input, const std::string& outdir,
+              const std::vector<std::string>& covers,
+              const SplitConfig& config, std::vector<SplitPart>& parts) {
+    if (covers.empty()) {
+        return RetCode::kInvalidArgument;
+    }
+    if (!std::filesystem::exists(input)) {
+        return RetCode::kFileNotFound;
+    }
+    for (std::size_t i = 0; i < covers.size(); ++i) {
+        for (std::size_t j = i + 1; j < covers.size(); ++j) {
+            if (SamePath(covers[i], covers[j])) {
+                return RetCode::kSameInput;
+            }
+        }
+    }
+    std::vector<uint8_t> data;
+    if (!ReadFile(input, data)) {
+        return RetCode::kIoError;
+    }
+
+    /* every part records the count, so the covers are measured up front */
+    parts.clear();
+    uint64_t offset = 0;
+    for (const std::string& cover : covers) {
+        if (!parts.empty() && (offset == data.size())) {
+            break;
+        }
+        boost::gil::rgb8_image_t cover_img;
+        RetCode rc = LoadImage(cover, cover_img);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+        const std::size_t kCapacity = PayloadCapacity(cover_img);
+        if (kCapacity <= kPartHeaderSize) {
+            continue; /* too small to hold any of the file */
+        }
+        SplitPart part;
+        part.file = PartName(parts.size());
+        part.cover = cover;
+        part.offset = offset;
+        part.length = std::min<uint64_t>(kCapacity - kPartHeaderSize,
+                                         data.size() - offset);
+        offset += part.length;
+        parts.push_back(part);
+    }
+    if (parts.empty() || (offset < data.size())) {
+        parts.clear();
+        return RetCode::kInsufficientCapacity;
+    }
+
+    std::vector<std::string> outputs;
+    for (const SplitPart& part : parts) {
+        outputs.push_back((std::filesystem::path(outdir) / part.file).string());
+    }
+    if (config.manifest) {
+        outputs.push_back(
+            (std::filesystem::path(outdir) / kManifestName).string());
+    }
+    for (const std::string& output : outputs) {
+        if (SamePath(output, input) ||
+            std::any_of(covers.cbegin(), covers.cend(),
+                        [&output](const std::string& cover) {
+                            return SamePath(output, cover);
+                        })) {
+            return RetCode::kOutputIsInput;
+        }
+    }
+    std::error_code ec;
+    std::filesystem::create_directories(outdir, ec);
+    if (ec) {
+        return RetCode::kIoError;
+    }
+
+    const std::vector<uint8_t> kDigest = Sha256(data.data(), data.size());
+    for (std::size_t i = 0; i < parts.size(); ++i) {
+        SplitPart& part = parts[i];
+        std::vector<uint8_t> record;
+        AppendInt<uint32_t>(record, kPartMagic);
+        AppendInt<uint32_t>(record, static_cast<uint32_t>(i));
+        AppendInt<uint32_t>(record, static_cast<uint32_t>(parts.size()));
+        AppendInt<uint64_t>(record, data.size());
+        record.insert(record.end(), kDigest.cbegin(), kDigest.cend());
+        record.insert(record.end(), data.cbegin() + part.offset,
+                      data.cbegin() + part.offset + part.length);
+
+        boost::gil::rgb8_image_t cover_img;
+        RetCode rc = LoadImage(part.cover, cover_img);
+        if (rc == RetCode::kSuccess) {
+            rc = EmbedPayload(cover_img, record);
+        }
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+        WriteImage(cover_img, outputs[i], ImageType::kPng);
+        std::vector<uint8_t> digest =
+            Sha256(data.data() + part.offset, part.length);
+        part.sha256 = ToHex(digest.data(), digest.size());
+    }
+    if (config.manifest) {
+        return WriteManifest(outputs.back(), data, parts);
+    }
+    return RetCode::kSuccess;
+}
+
+RetCode Join(const std::vector<std::string>& parts, const std::string& outfile,
+             const JoinConfig& config, JoinReport& report) {
+    report = JoinReport{};
+    if (parts.empty()) {
+        return RetCode::kInvalidArgument;
+    }
+    for (const std::string& part : parts) {
+        if (SamePath(outfile, part) || SamePath(outfile, config.manifest)) {
+            return RetCode::kOutputIsInput;
+        }
+    }
+
+    /* the manifest, when given, decides what belongs, otherwise the first
+     * part found does */
+    uint32_t count = 0;
+    uint64_t total = 0;
+    std::vector<uint8_t> digest;
+    std::vector<ManifestEntry> listed;
+    if (!config.manifest.empty()) {
+        boost::gil::rgb8_image_t manifest;
+        RetCode rc = LoadImage(config.manifest, manifest);
+        std::vector<uint8_t> payload;
+        if (rc == RetCode::kSuccess) {
+            rc = ExtractPayload(manifest, payload);
+        }
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+        if (!ParseManifest(payload, total, digest, listed)) {
+            return RetCode::kInvalidPayload;
+        }
+        count = static_cast<uint32_t>(listed.size());
+    }
+
+    std::map<uint32_t, std::vector<uint8_t>> chunks;
+    for (const std::string& path : parts) {
+        boost::gil::rgb8_image_t image;
+        RetCode rc = LoadImage(path, image);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+        std::vector<uint8_t> payload;
+        PartRecord part;
+        if ((ExtractPayload(image, payload) != RetCode::kSuccess) ||
+            !ParsePart(payload, part)) {
+            report.unexpected.push_back(path);
+            continue;
+        }
+        if (digest.empty()) {
+            count = part.count;
+            total = part.total;
+            digest = part.digest;
+        }
+        bool belongs = (part.digest == digest) && (part.count == count) &&
+                       (part.total == total) && !chunks.contains(part.index);
+        if (belongs && !listed.empty()) {
+            const ManifestEntry& entry = listed[part.index];
+            belongs = (part.data.size() == entry.length) &&
+                      (Sha256(part.data.data(), part.data.size()) ==
+                       entry.digest);
+        }
+        if (!belongs) {
+            report.unexpected.push_back(path);
+            continue;
+        }
+        chunks[part.index] = std::move(part.data);
+    }
+    if (digest.empty()) {
+        return RetCode::kPayloadNotFound;
+    }
+    for (uint32_t i = 0; i < count; ++i) {
+        if (!chunks.contains(i)) {
+            report.missing.push_back(
+                listed.empty() ? "part " + std::to_string(i + 1) + " of " +
+                                     std::to_string(count)
+                               : listed[i].name);
+        }
+    }
+    if (!report.missing.empty()) {
+        return RetCode::kIncompleteParts;
+    }
+
+    std::vector<uint8_t> data;
+    for (const auto& [index, chunk] : chunks) {
+        data.insert(data.end(), chunk.cbegin(), chunk.cend());
+    }
+    if ((data.size() != total) ||
+        (Sha256(data.data(), data.size()) != digest)) {
+        return RetCode::kPayloadDamaged;
+    }
+    if (config.create_dirs) {
+        RetCode rc = CreateParentDirectories(outfile);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+    std::ofstream ofs(outfile, std::ofstream::binary | std::ofstream::trunc);
+    if (!ofs.is_open()) {
+        return RetCode::kIoError;
+    }
+    ofs.write(reinterpret_cast<const char*>(data.data()),
+              static_cast<std::streamsize>(data.size()));
+    return ofs.good() ? RetCode::kSuccess : RetCode::kIoError;
+}
+
+}  // namespace steganography
@@ -1,185 +1,1412 @@
 #include "utils/steganography_util.hpp"
 
+#include <zlib.h>
+
 #include <algorithm>
+#include <array>
 #include <boost/gil.hpp>
-#include <boost/gil/extension/io/jpeg.hpp>
-#include <boost/gil/extension/io/png.hpp>
+#include <boost/gil/extension/numeric/resample.hpp>
+#include <boost/gil/extension/numeric/sampler.hpp>
+#include <chrono>
+#include <cmath>
+#include <cstddef>
 #include <cstdint>
+#include <cstdlib>
 #include <filesystem>
-#include <fstream>
+#include <memory>
+#include <sstream>
 #include <string>
+#include <utility>
 #include <vector>
 
+#include "utils/bytes.hpp"
+#include "utils/embed_method.hpp"
+#include "utils/icc.hpp"
+#include "utils/image_io.hpp"
+#include "utils/key.hpp"
+#include "utils/lsb.hpp"
+#include "utils/memory.hpp"
+#include "utils/metrics.hpp"
+#include "utils/normalize.hpp"
+#include "utils/parallel.hpp"
+#include "utils/paths.hpp"
+
+#ifdef STEG_WITH_EXR
+#include "utils/exr.hpp"
+#endif
+#ifdef STEG_WITH_SVG
+#include "utils/svg.hpp"
+#endif
+
 namespace steganography {
 
-enum class ImageType {
-    kJpeg,
-    kPng,
-    kUnknown,
-};
+/* mask selecting the high bits of a channel that carry the secret */
+static int HighBits(int bits) { return (0xFF << (8 - bits)) & 0xFF; }
 
-static ImageType GetImageType(const std::string& filename) {
-    /* read the first 8 bytes of the file */
-    const int kHeaderSize = 8;
-    std::ifstream ifs(filename, std::ifstream::binary);
-    if (!ifs.is_open()) {
-        return ImageType::kUnknown;
+boost::gil::rgb8_pixel_t MergePixels(const boost::gil::rgb8_pixel_t& cover_pix,
+                                     const boost::gil::rgb8_pixel_t& secret_pix,
+                                     int bits) {
+    const int kLowBits = 0xFF >> (8 - bits);
+    boost::gil::rgb8_pixel_t merged_pix(0, 0, 0);
+    for (int i = 0; i < 3; ++i) {
+        merged_pix[i] = static_cast<uint8_t>((cover_pix[i] & ~kLowBits) |
+                                             (secret_pix[i] >> (8 - bits)));
     }
-    std::vector<char> buffer(kHeaderSize, 0);
-    ifs.read(&buffer[0], static_cast<int64_t>(buffer.size()));
+    return merged_pix;
+}
 
-    /* construct an unsigned 64-bit word using the 8 bytes in buffer */
-    const int kByteShift = 8;
-    uint64_t word = 0;
-    for (const char& c : buffer) {
-        word = (word << kByteShift) | static_cast<uint8_t>(c);
+boost::gil::rgb8_pixel_t UnmergePixels(const boost::gil::rgb8_pixel_t& pixel,
+                                       int bits) {
+    const int kLowBits = 0xFF >> (8 - bits);
+    boost::gil::rgb8_pixel_t unmerged_pix(0, 0, 0);
+    for (int i = 0; i < 3; ++i) {
+        unmerged_pix[i] =
+            static_cast<uint8_t>((pixel[i] & kLowBits) << (8 - bits));
     }
+    return unmerged_pix;
+}
 
-    /* check if the word matches a known image file type signature */
-    const uint64_t kPngSignature = 0x89504E470D0A1A0A;
-    const uint64_t kJpegSignature = 0xFFD8000000000000;
-    if (word == kPngSignature) {
-        return ImageType::kPng;
-    } else if ((word & kJpegSignature) == kJpegSignature) {
-        return ImageType::kJpeg;
+/* full range BT.601 as used by JPEG, rounded to whole values */
+static std::array<int, 3> ToYCbCr(const boost::gil::rgb8_pixel_t& pixel) {
+    const double kR = pixel[0];
+    const double kG = pixel[1];
+    const double kB = pixel[2];
+    auto round = [](double value) {
+        return static_cast<int>(std::clamp(std::lround(value), 0L, 255L));
+    };
+    return {round(0.299 * kR + 0.587 * kG + 0.114 * kB),
+            round(128.0 - 0.168736 * kR - 0.331264 * kG + 0.5 * kB),
+            round(128.0 + 0.5 * kR - 0.418688 * kG - 0.081312 * kB)};
+}
+
+static std::array<double, 3> FromYCbCr(const std::array<int, 3>& ycbcr) {
+    const double kY = ycbcr[0];
+    const double kCb = ycbcr[1] - 128.0;
+    const double kCr = ycbcr[2] - 128.0;
+    return {kY + 1.402 * kCr, kY - 0.344136 * kCb - 0.714136 * kCr,
+            kY + 1.772 * kCb};
+}
+
+/* the values nearest to channel, one either side, whose low bits are low */
+static std::vector<int> WithLowBits(int channel, int low, int bits) {
+    const int kStep = 1 << bits;
+    std::vector<int> values;
+    for (int value = (channel & ~(kStep - 1)) - kStep + low;
+         value < channel + kStep; value += kStep) {
+        if ((value >= 0) && (value <= 255)) {
+            values.push_back(value);
+        }
     }
-    return ImageType::kUnknown;
+    return values;
 }
 
-static boost::gil::rgb8_image_t ReadImage(const std::string& filename,
-                                          ImageType type) {
-    boost::gil::rgb8_image_t image;
-    if (type == ImageType::kJpeg) {
-        boost::gil::read_and_convert_image(filename, image,
-                                           boost::gil::jpeg_tag{});
-    } else {
-        boost::gil::read_and_convert_image(filename, image,
-                                           boost::gil::png_tag{});
+std::array<int, 3> SecretBits(int bits, ColorSpace space) {
+    if (space == ColorSpace::kRgb) {
+        return {bits, bits, bits};
     }
-    return image;
+    /* green gets any spare bit as the eye is most sensitive to it */
+    const int kTotal = (space == ColorSpace::kYCbCr) ? 2 * bits : bits;
+    return {(kTotal + 1) / 3, (kTotal + 2) / 3, kTotal / 3};
 }
 
-static void WriteImage(const boost::gil::rgb8_image_t& image,
-                       const std::string& filename, ImageType type) {
-    if (type == ImageType::kJpeg) {
-        boost::gil::write_view(filename, boost::gil::const_view(image),
-                               boost::gil::jpeg_tag{});
-    } else {
-        boost::gil::write_view(filename, boost::gil::const_view(image),
-                               boost::gil::png_tag{});
+/* the kept high bits of the secret's channels concatenated red first */
+static int PackSecret(const boost::gil::rgb8_pixel_t& secret_pix,
+                      const std::array<int, 3>& bits) {
+    int hidden = 0;
+    for (std::size_t i = 0; i < 3; ++i) {
+        hidden = (hidden << bits[i]) | (secret_pix[i] >> (8 - bits[i]));
     }
+    return hidden;
 }
 
-static boost::gil::rgb8_pixel_t MergePixels(
+static boost::gil::rgb8_pixel_t UnpackSecret(int hidden,
+                                             const std::array<int, 3>& bits) {
+    boost::gil::rgb8_pixel_t unmerged_pix(0, 0, 0);
+    for (std::size_t i = 3; i-- > 0;) {
+        const int kChannelMask = (1 << bits[i]) - 1;
+        unmerged_pix[i] =
+            static_cast<uint8_t>((hidden & kChannelMask) << (8 - bits[i]));
+        hidden >>= bits[i];
+    }
+    return unmerged_pix;
+}
+
+/* the secret's bits are concatenated red first and split evenly across the
+ * cover's chroma. Of the pixels whose chroma holds them once rounded back
+ * to RGB, the one closest to the cover wins, luma is free to move so dark
+ * and bright pixels stay in gamut */
+static boost::gil::rgb8_pixel_t MergeChroma(
     const boost::gil::rgb8_pixel_t& cover_pix,
-    const boost::gil::rgb8_pixel_t& secret_pix) {
-    const int kHighNibble = 0xF0;
+    const boost::gil::rgb8_pixel_t& secret_pix, int bits) {
+    int hidden =
+        PackSecret(secret_pix, SecretBits(bits, ColorSpace::kYCbCr));
+    const int kMask = (1 << bits) - 1;
+    const int kCbLow = (hidden >> bits) & kMask;
+    const int kCrLow = hidden & kMask;
+    std::array<int, 3> cover = ToYCbCr(cover_pix);
+
+    boost::gil::rgb8_pixel_t best = cover_pix;
+    double best_error = -1.0;
+    bool best_holds = false;
+    for (int cb : WithLowBits(cover[1], kCbLow, bits)) {
+        for (int cr : WithLowBits(cover[2], kCrLow, bits)) {
+            /* keep luma where every channel stays within 0-255 if possible */
+            std::array<double, 3> offsets = FromYCbCr({0, cb, cr});
+            double low = -std::min({offsets[0], offsets[1], offsets[2]});
+            double high =
+                255.0 - std::max({offsets[0], offsets[1], offsets[2]});
+            int y = cover[0];
+            if (low <= high) {
+                y = static_cast<int>(std::clamp(
+                    static_cast<double>(y), std::ceil(low), std::floor(high)));
+            }
+            std::array<double, 3> exact = FromYCbCr({y, cb, cr});
+            for (int d = 0; d < 27; ++d) {
+                const std::array<int, 3> kDelta = {d / 9 - 1, d / 3 % 3 - 1,
+                                                   d % 3 - 1};
+                boost::gil::rgb8_pixel_t candidate(0, 0, 0);
+                double error = 0.0;
+                for (std::size_t i = 0; i < 3; ++i) {
+                    candidate[i] = static_cast<uint8_t>(std::clamp(
+                        std::lround(exact[i]) + kDelta[i], 0L, 255L));
+                    error += std::pow(candidate[i] - cover_pix[i], 2.0);
+                }
+                std::array<int, 3> check = ToYCbCr(candidate);
+                bool holds = ((check[1] & kMask) == kCbLow) &&
+                             ((check[2] & kMask) == kCrLow);
+                if ((best_error < 0.0) || (holds && !best_holds) ||
+                    ((holds == best_holds) && (error < best_error))) {
+                    best = candidate;
+                    best_error = error;
+                    best_holds = holds;
+                }
+            }
+        }
+    }
+    return best;
+}
+
+static boost::gil::rgb8_pixel_t UnmergeChroma(
+    const boost::gil::rgb8_pixel_t& pixel, int bits) {
+    const int kMask = (1 << bits) - 1;
+    std::array<int, 3> ycbcr = ToYCbCr(pixel);
+    int hidden = ((ycbcr[1] & kMask) << bits) | (ycbcr[2] & kMask);
+    return UnpackSecret(hidden, SecretBits(bits, ColorSpace::kYCbCr));
+}
+
+/* moves the cover's value, its brightest channel, to the nearest one with
+ * the secret's bits and scales the other channels with it so hue and
+ * saturation are kept up to rounding */
+static boost::gil::rgb8_pixel_t MergeValue(
+    const boost::gil::rgb8_pixel_t& cover_pix,
+    const boost::gil::rgb8_pixel_t& secret_pix, int bits) {
+    const int kLow = PackSecret(secret_pix, SecretBits(bits, ColorSpace::kHsv));
+    const int kValue = std::max({cover_pix[0], cover_pix[1], cover_pix[2]});
+    int value = -1;
+    for (int candidate : WithLowBits(kValue, kLow, bits)) {
+        if ((value < 0) ||
+            (std::abs(candidate - kValue) < std::abs(value - kValue))) {
+            value = candidate;
+        }
+    }
+
     boost::gil::rgb8_pixel_t merged_pix(0, 0, 0);
-    for (int i = 0; i < 3; ++i) {
-        merged_pix[i] =
-            (cover_pix[i] & kHighNibble) | ((secret_pix[i] & kHighNibble) >> 4);
+    for (std::size_t i = 0; i < 3; ++i) {
+        /* a black cover pixel has no hue to keep so it turns gray */
+        long scaled = (kValue == 0)
+                          ? value
+                          : std::lround(static_cast<double>(cover_pix[i]) *
+                                        value / kValue);
+        merged_pix[i] = static_cast<uint8_t>(std::min(scaled, 255L));
     }
     return merged_pix;
 }
 
-static boost::gil::rgb8_pixel_t UnmergePixels(
-    const boost::gil::rgb8_pixel_t& pixel) {
-    const int kLowNibble = 0x0F;
-    boost::gil::rgb8_pixel_t unmerged_pix(0, 0, 0);
+static boost::gil::rgb8_pixel_t UnmergeValue(
+    const boost::gil::rgb8_pixel_t& pixel, int bits) {
+    const int kValue = std::max({pixel[0], pixel[1], pixel[2]});
+    return UnpackSecret(kValue & ((1 << bits) - 1),
+                        SecretBits(bits, ColorSpace::kHsv));
+}
+
+boost::gil::rgb8_pixel_t MergePixels(const boost::gil::rgb8_pixel_t& cover_pix,
+                                     const boost::gil::rgb8_pixel_t& secret_pix,
+                                     int bits, ColorSpace space) {
+    if (space == ColorSpace::kYCbCr) {
+        return MergeChroma(cover_pix, secret_pix, bits);
+    }
+    if (space == ColorSpace::kHsv) {
+        return MergeValue(cover_pix, secret_pix, bits);
+    }
+    return MergePixels(cover_pix, secret_pix, bits);
+}
+
+boost::gil::rgb8_pixel_t UnmergePixels(const boost::gil::rgb8_pixel_t& pixel,
+                                       int bits, ColorSpace space) {
+    if (space == ColorSpace::kYCbCr) {
+        return UnmergeChroma(pixel, bits);
+    }
+    if (space == ColorSpace::kHsv) {
+        return UnmergeValue(pixel, bits);
+    }
+    return UnmergePixels(pixel, bits);
+}
+
+/* every ordering of the three color channels, selected per pixel by key */
+static const int kChannelOrders[6][3] = {{0, 1, 2}, {0, 2, 1}, {1, 0, 2},
+                                         {1, 2, 0}, {2, 0, 1}, {2, 1, 0}};
+
+/* a password reserves the last placed pixels for a key and password tag */
+static const std::size_t kAuthBlockSize = 2 * kKeyTagSize;
+static const std::size_t kAuthPixels = (kAuthBlockSize * 2 + 2) / 3;
+
+/* the side of the square blocks block bits draws a bit depth for */
+static const std::size_t kBitsBlockSize = 16;
+
+/* keystreams driving the keyed layout, consumed in secret pixel order */
+struct KeyedLayout {
+    std::vector<std::size_t> placement;
+    KeyStream channels;
+    KeyStream pad;
+    std::size_t reserved = 0;
+    std::vector<uint8_t> auth_block;
+    /* with block bits, the bits of each block of the cover row by row,
+     * empty when every pixel holds the same */
+    std::vector<int> block_bits;
+    std::size_t width = 0;
+    std::size_t block_columns = 0;
+    /* the size record's pixels hold all the bits wherever they land, so
+     * unmerge reads the record the same whether or not there is one */
+    std::size_t full_bits_from = 0;
+
+    /* placement only depends on the key file, the cipher key may also
+     * depend on a password */
+    KeyedLayout(const StegKey& key, const StegKey& cipher_key,
+                std::size_t pixel_count)
+        : channels(cipher_key, "channels"), pad(cipher_key, "encryption") {
+        KeyStream permutation(key, "permutation");
+        placement = KeyedPermutation(pixel_count, permutation);
+    }
+
+    std::size_t SecretPixels() const { return placement.size() - reserved; }
+
+    /* the bits the cover pixel holding the secret pixel keeps */
+    int BitsAt(std::size_t secret_index, int bits) const {
+        if (block_bits.empty() || (secret_index >= full_bits_from)) {
+            return bits;
+        }
+        const std::size_t kIndex = placement[secret_index];
+        return block_bits[kIndex / width / kBitsBlockSize * block_columns +
+                          kIndex % width / kBitsBlockSize];
+    }
+};
+
+/* every block gets from half of bits, rounded up, to bits */
+static void DrawBlockBits(const StegKey& key, std::size_t width,
+                          std::size_t height, int bits, KeyedLayout& layout) {
+    const int kFewest = (bits + 1) / 2;
+    layout.width = width;
+    layout.block_columns = (width + kBitsBlockSize - 1) / kBitsBlockSize;
+    const std::size_t kBlockRows =
+        (height + kBitsBlockSize - 1) / kBitsBlockSize;
+    KeyStream stream(key, "block bits");
+    layout.block_bits.resize(layout.block_columns * kBlockRows);
+    for (int& block : layout.block_bits) {
+        block = kFewest + static_cast<int>(stream.NextBelow(
+                              static_cast<uint64_t>(bits - kFewest + 1)));
+    }
+}
+
+static boost::gil::rgb8_pixel_t ScramblePixel(
+    const boost::gil::rgb8_pixel_t& pixel, KeyedLayout& layout, int bits) {
+    const int* order = kChannelOrders[layout.channels.NextBelow(6)];
+    boost::gil::rgb8_pixel_t scrambled(0, 0, 0);
     for (int i = 0; i < 3; ++i) {
-        unmerged_pix[i] = (pixel[i] & kLowNibble) << 4;
+        scrambled[i] =
+            pixel[order[i]] ^ (layout.pad.NextByte() & HighBits(bits));
     }
-    return unmerged_pix;
+    return scrambled;
+}
+
+static boost::gil::rgb8_pixel_t UnscramblePixel(
+    const boost::gil::rgb8_pixel_t& pixel, KeyedLayout& layout, int bits) {
+    const int* order = kChannelOrders[layout.channels.NextBelow(6)];
+    boost::gil::rgb8_pixel_t unscrambled(0, 0, 0);
+    for (int i = 0; i < 3; ++i) {
+        unscrambled[order[i]] =
+            pixel[i] ^ (layout.pad.NextByte() & HighBits(bits));
+    }
+    return unscrambled;
+}
+
+/* block_bits is the most bits a block of the cover holds when they are
+ * drawn per block, 0 when all pixels hold the same */
+static RetCode LoadLayout(const std::string& keyfile,
+                          const std::string& password, std::size_t width,
+                          std::size_t height, int block_bits,
+                          std::unique_ptr<KeyedLayout>& layout) {
+    if (keyfile.empty()) {
+        /* a password is only ever used as a second factor, and block bits
+         * are drawn with the key */
+        return (password.empty() && (block_bits == 0))
+                   ? RetCode::kSuccess
+                   : RetCode::kInvalidArgument;
+    }
+    StegKey key;
+    RetCode rc = LoadKeyFile(keyfile, key);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    const std::size_t kPixelCount = width * height;
+    if (password.empty()) {
+        layout = std::make_unique<KeyedLayout>(key, key, kPixelCount);
+        if (block_bits > 0) {
+            DrawBlockBits(key, width, height, block_bits, *layout);
+        }
+        return RetCode::kSuccess;
+    }
+
+    if (kPixelCount < kAuthPixels) {
+        return RetCode::kInvalidDimensions;
+    }
+    StegKey combined = CombineKey(key, password);
+    layout = std::make_unique<KeyedLayout>(key, combined, kPixelCount);
+    if (block_bits > 0) {
+        DrawBlockBits(key, width, height, block_bits, *layout);
+    }
+    layout->reserved = kAuthPixels;
+    std::array<uint8_t, kKeyTagSize> key_tag = KeyTag(key, "key file");
+    std::array<uint8_t, kKeyTagSize> password_tag = KeyTag(combined, "password");
+    layout->auth_block.assign(key_tag.cbegin(), key_tag.cend());
+    layout->auth_block.insert(layout->auth_block.end(), password_tag.cbegin(),
+                              password_tag.cend());
+    return RetCode::kSuccess;
 }
 
-static bool HasJpegExtension(const std::string& filename) {
-    const std::vector<std::string> kExtensions = {".jpg", ".jpeg", ".JPG",
-                                                  ".JPEG"};
-    return std::any_of(
-        kExtensions.cbegin(), kExtensions.cend(),
-        [&filename](const std::string& s) { return filename.ends_with(s); });
+static void WriteAuthBlock(const boost::gil::rgb8_view_t& view,
+                           const KeyedLayout& layout) {
+    const int kHighNibble = 0xF0;
+    const std::size_t kWidth = static_cast<std::size_t>(view.width());
+    for (std::size_t i = 0; i < 2 * kAuthBlockSize; ++i) {
+        std::size_t index = layout.placement[layout.SecretPixels() + i / 3];
+        auto& channel = view(index % kWidth, index / kWidth)[i % 3];
+        uint8_t byte = layout.auth_block[i / 2];
+        uint8_t nibble = (i % 2 == 0) ? (byte >> 4) : (byte & 0x0F);
+        channel = static_cast<uint8_t>((channel & kHighNibble) | nibble);
+    }
+}
+
+/* marks the pixels WriteAuthBlock wrote to, empty without an auth block */
+static std::vector<bool> AuthBlockPixels(const KeyedLayout* layout,
+                                         std::size_t pixel_count) {
+    std::vector<bool> pixels;
+    if ((layout == nullptr) || layout->auth_block.empty()) {
+        return pixels;
+    }
+    pixels.assign(pixel_count, false);
+    for (std::size_t i = 0; i < kAuthPixels; ++i) {
+        pixels[layout->placement[layout->SecretPixels() + i]] = true;
+    }
+    return pixels;
+}
+
+static RetCode VerifyAuthBlock(const boost::gil::rgb8c_view_t& view,
+                               const KeyedLayout& layout) {
+    const int kLowNibble = 0x0F;
+    const std::size_t kWidth = static_cast<std::size_t>(view.width());
+    std::array<uint8_t, kKeyTagSize> key_tag{};
+    std::array<uint8_t, kKeyTagSize> password_tag{};
+    for (std::size_t i = 0; i < 2 * kAuthBlockSize; ++i) {
+        std::size_t index = layout.placement[layout.SecretPixels() + i / 3];
+        uint8_t nibble = view(index % kWidth, index / kWidth)[i % 3] & kLowNibble;
+        uint8_t& byte = (i / 2 < kKeyTagSize) ? key_tag[i / 2]
+                                              : password_tag[i / 2 - kKeyTagSize];
+        byte = static_cast<uint8_t>((byte << 4) | nibble);
+    }
+
+    /* both tags are always compared so the time taken does not tell them
+     * apart, the key file is reported first since the password tag depends
+     * on it */
+    std::array<uint8_t, kKeyTagSize> expected_key{};
+    std::array<uint8_t, kKeyTagSize> expected_password{};
+    std::copy_n(layout.auth_block.cbegin(), kKeyTagSize, expected_key.begin());
+    std::copy_n(layout.auth_block.cbegin() + kKeyTagSize, kKeyTagSize,
+                expected_password.begin());
+    const bool kKeyMatches = TagsEqual(key_tag, expected_key);
+    const bool kPasswordMatches = TagsEqual(password_tag, expected_password);
+    if (!kKeyMatches) {
+        return RetCode::kKeyMismatch;
+    }
+    return kPasswordMatches ? RetCode::kSuccess : RetCode::kPasswordMismatch;
 }
 
 RetCode Merge(const std::string& cover, const std::string& secret,
               const std::string& outfile) {
+    return Merge(cover, secret, outfile, MergeConfig{});
+}
+
+/* records the time elapsed since the previous phase ended and the peak
+ * memory by its end */
+class PhaseTimer {
+   public:
+    explicit PhaseTimer(std::vector<MergePhase>& phases)
+        : phases_(phases), start_(std::chrono::steady_clock::now()) {}
+
+    void Finish(const std::string& name) {
+        auto now = std::chrono::steady_clock::now();
+        std::chrono::duration<double, std::milli> elapsed = now - start_;
+        phases_.push_back({name, elapsed.count(), PeakRss()});
+        start_ = now;
+    }
+
+    /* starts the next phase now, leaving the time since the last one out */
+    void Restart() { start_ = std::chrono::steady_clock::now(); }
+
+   private:
+    std::vector<MergePhase>& phases_;
+    std::chrono::steady_clock::time_point start_;
+};
+
+RetCode Merge(const std::string& cover, const std::string& secret,
+              const std::string& outfile, const MergeConfig& config) {
+    MergeReport report;
+    return Merge(cover, secret, outfile, config, report);
+}
+
+static float SrgbToLinear(uint8_t value) {
+    double v = value / 255.0;
+    return static_cast<float>((v <= 0.04045) ? v / 12.92
+                                             : std::pow((v + 0.055) / 1.055,
+                                                        2.4));
+}
+
+static uint8_t LinearToSrgb(float value) {
+    double v = std::clamp(static_cast<double>(value), 0.0, 1.0);
+    v = (v <= 0.0031308) ? v * 12.92 : 1.055 * std::pow(v, 1.0 / 2.4) - 0.055;
+    return static_cast<uint8_t>(std::lround(v * 255.0));
+}
+
+/* averaging sRGB values darkens the mix of bright and dark pixels, linear
+ * light averages them the way the eye would see them blended */
+static void ResizeLinear(const boost::gil::rgb8_image_t& image,
+                         boost::gil::rgb8_image_t& resized) {
+    std::array<float, 256> to_linear;
+    for (std::size_t i = 0; i < to_linear.size(); ++i) {
+        to_linear[i] = SrgbToLinear(static_cast<uint8_t>(i));
+    }
+    boost::gil::rgb32f_image_t linear(image.dimensions());
+    auto src = boost::gil::const_view(image);
+    auto linear_view = boost::gil::view(linear);
+    for (std::ptrdiff_t y = 0; y < src.height(); ++y) {
+        for (std::ptrdiff_t x = 0; x < src.width(); ++x) {
+            for (int i = 0; i < 3; ++i) {
+                linear_view(x, y)[i] = to_linear[src(x, y)[i]];
+            }
+        }
+    }
+    boost::gil::rgb32f_image_t linear_resized(resized.dimensions());
+    boost::gil::resize_view(boost::gil::const_view(linear),
+                            boost::gil::view(linear_resized),
+                            boost::gil::bilinear_sampler());
+    auto resized_src = boost::gil::const_view(linear_resized);
+    auto dst = boost::gil::view(resized);
+    for (std::ptrdiff_t y = 0; y < dst.height(); ++y) {
+        for (std::ptrdiff_t x = 0; x < dst.width(); ++x) {
+            for (int i = 0; i < 3; ++i) {
+                dst(x, y)[i] = LinearToSrgb(resized_src(x, y)[i]);
+            }
+        }
+    }
+}
+
+/* resizes secret to exactly width x height, returns the largest factor
+ * either of its dimensions was divided by */
+static double ResizeSecret(boost::gil::rgb8_image_t& secret,
+                           std::ptrdiff_t width, std::ptrdiff_t height,
+                           bool linear) {
+    double shrink = std::max(
+        static_cast<double>(secret.width()) / static_cast<double>(width),
+        static_cast<double>(secret.height()) / static_cast<double>(height));
+    if (secret.dimensions() == boost::gil::point_t(width, height)) {
+        return shrink;
+    }
+    boost::gil::rgb8_image_t resized(width, height);
+    if (linear) {
+        ResizeLinear(secret, resized);
+    } else {
+        boost::gil::resize_view(boost::gil::const_view(secret),
+                                boost::gil::view(resized),
+                                boost::gil::bilinear_sampler());
+    }
+    secret = std::move(resized);
+    return shrink;
+}
+
+/* shrinks secret to fit within width x height keeping its aspect ratio,
+ * returns the factor its dimensions were divided by */
+static double FitSecret(boost::gil::rgb8_image_t& secret, std::ptrdiff_t width,
+                        std::ptrdiff_t height, bool linear) {
+    double shrink = std::max(
+        {1.0,
+         static_cast<double>(secret.width()) / static_cast<double>(width),
+         static_cast<double>(secret.height()) / static_cast<double>(height)});
+    if (shrink == 1.0) {
+        return shrink;
+    }
+    return ResizeSecret(
+        secret,
+        std::clamp<std::ptrdiff_t>(
+            std::lround(static_cast<double>(secret.width()) / shrink), 1,
+            width),
+        std::clamp<std::ptrdiff_t>(
+            std::lround(static_cast<double>(secret.height()) / shrink), 1,
+            height),
+        linear);
+}
+
+/* the secret's size stored in the high bits of the hidden pixels that come
+ * right before the password's auth block, see docs/FORMAT.md */
+static const uint32_t kSizeRecordMagic = 0x5344494D; /* "SDIM" */
+static const std::size_t kSizeRecordSize = 16;
+
+static std::size_t SizeRecordPixels(const std::array<int, 3>& bits) {
+    const std::size_t kBitsPerPixel =
+        static_cast<std::size_t>(bits[0] + bits[1] + bits[2]);
+    return (kSizeRecordSize * 8 + kBitsPerPixel - 1) / kBitsPerPixel;
+}
+
+/* the pixel, channel, and bit within it holding each bit of the record,
+ * bits are handed out most significant first and channel by channel */
+struct RecordBit {
+    std::size_t pixel = 0;
+    std::size_t channel = 0;
+    int shift = 0;
+};
+
+static std::vector<RecordBit> RecordBits(const std::array<int, 3>& bits) {
+    std::vector<RecordBit> positions;
+    for (std::size_t pixel = 0; positions.size() < kSizeRecordSize * 8;
+         ++pixel) {
+        for (std::size_t channel = 0; channel < 3; ++channel) {
+            for (int i = 0; i < bits[channel]; ++i) {
+                positions.push_back({pixel, channel, 7 - i});
+            }
+        }
+    }
+    positions.resize(kSizeRecordSize * 8);
+    return positions;
+}
+
+static std::vector<boost::gil::rgb8_pixel_t> SizeRecord(
+    std::ptrdiff_t width, std::ptrdiff_t height,
+    const std::array<int, 3>& bits) {
+    std::vector<uint8_t> record;
+    AppendInt<uint32_t>(record, kSizeRecordMagic);
+    AppendInt<uint32_t>(record, static_cast<uint32_t>(width));
+    AppendInt<uint32_t>(record, static_cast<uint32_t>(height));
+    AppendInt<uint32_t>(record, static_cast<uint32_t>(crc32(
+                                    0, record.data(),
+                                    static_cast<uInt>(record.size()))));
+
+    std::vector<boost::gil::rgb8_pixel_t> pixels(
+        SizeRecordPixels(bits), boost::gil::rgb8_pixel_t(0, 0, 0));
+    std::vector<RecordBit> positions = RecordBits(bits);
+    for (std::size_t i = 0; i < positions.size(); ++i) {
+        int bit = (record[i / 8] >> (7 - i % 8)) & 1;
+        auto& channel = pixels[positions[i].pixel][positions[i].channel];
+        channel = static_cast<uint8_t>(channel | (bit << positions[i].shift));
+    }
+    return pixels;
+}
+
+/* reads the record from the pixels starting at first, false when they do
+ * not hold one */
+static bool ReadSizeRecord(const boost::gil::rgb8c_view_t& view,
+                           std::size_t first, const std::array<int, 3>& bits,
+                           std::ptrdiff_t& width, std::ptrdiff_t& height) {
+    const std::size_t kWidth = static_cast<std::size_t>(view.width());
+    std::vector<uint8_t> record(kSizeRecordSize, 0);
+    std::vector<RecordBit> positions = RecordBits(bits);
+    for (std::size_t i = 0; i < positions.size(); ++i) {
+        std::size_t index = first + positions[i].pixel;
+        const auto& pixel = view(index % kWidth, index / kWidth);
+        int bit = (pixel[positions[i].channel] >> positions[i].shift) & 1;
+        record[i / 8] =
+            static_cast<uint8_t>(record[i / 8] | (bit << (7 - i % 8)));
+    }
+    if ((ReadInt<uint32_t>(record, 0) != kSizeRecordMagic) ||
+        (ReadInt<uint32_t>(record, 12) !=
+         static_cast<uint32_t>(crc32(0, record.data(), 12)))) {
+        return false;
+    }
+    /* checked as read, their product in 64 bits, so a forged record can
+     * neither overflow it, claim more pixels than precede the record, nor
+     * hand back dimensions past the view's */
+    const uint32_t kRecordWidth = ReadInt<uint32_t>(record, 4);
+    const uint32_t kRecordHeight = ReadInt<uint32_t>(record, 8);
+    if ((kRecordWidth == 0) || (kRecordHeight == 0) ||
+        (kRecordWidth > static_cast<std::size_t>(view.width())) ||
+        (kRecordHeight > static_cast<std::size_t>(view.height())) ||
+        (static_cast<uint64_t>(kRecordWidth) * kRecordHeight > first)) {
+        return false;
+    }
+    width = static_cast<std::ptrdiff_t>(kRecordWidth);
+    height = static_cast<std::ptrdiff_t>(kRecordHeight);
+    return true;
+}
+
+/* adds amount times the difference between the image and a 3x3 gaussian
+ * blur of it back in, undoing some of the softening a resize causes */
+static void UnsharpMask(boost::gil::rgb8_image_t& image, double amount) {
+    const std::array<int, 3> kKernel = {1, 2, 1};
+    const double kKernelSum = 16.0;
+    boost::gil::rgb8_image_t original = image;
+    auto src = boost::gil::const_view(original);
+    auto dst = boost::gil::view(image);
+    const std::ptrdiff_t kWidth = src.width();
+    const std::ptrdiff_t kHeight = src.height();
+    for (std::ptrdiff_t y = 0; y < kHeight; ++y) {
+        for (std::ptrdiff_t x = 0; x < kWidth; ++x) {
+            for (int i = 0; i < 3; ++i) {
+                double blurred = 0.0;
+                for (int dy = -1; dy <= 1; ++dy) {
+                    for (int dx = -1; dx <= 1; ++dx) {
+                        /* edges repeat the outermost pixels */
+                        std::ptrdiff_t sx = std::clamp<std::ptrdiff_t>(
+                            x + dx, 0, kWidth - 1);
+                        std::ptrdiff_t sy = std::clamp<std::ptrdiff_t>(
+                            y + dy, 0, kHeight - 1);
+                        blurred += kKernel[static_cast<std::size_t>(dx + 1)] *
+                                   kKernel[static_cast<std::size_t>(dy + 1)] *
+                                   src(sx, sy)[i];
+                    }
+                }
+                double value = src(x, y)[i];
+                value += amount * (value - blurred / kKernelSum);
+                dst(x, y)[i] = static_cast<uint8_t>(
+                    std::clamp(std::lround(value), 0L, 255L));
+            }
+        }
+    }
+}
+
+/* Floyd-Steinberg over the merged image, each channel keeps the low bits
+ * holding the secret and moves to whichever value with them lies nearest
+ * the cover plus the error carried over from its neighbors. The auth block
+ * is written as nibbles, so its pixels keep at least their low 4 bits. */
+static void DitherLowBits(const boost::gil::rgb8c_view_t& cover,
+                          const boost::gil::rgb8_view_t& output, int bits,
+                          const std::vector<bool>& auth_pixels) {
+    const int kAuthBits = 4;
+    const std::size_t kWidth = static_cast<std::size_t>(cover.width());
+    /* error owed to each pixel of this row and the next, padded by one
+     * pixel either side so the edges need no special casing */
+    std::vector<std::array<double, 3>> current(kWidth + 2, {0.0, 0.0, 0.0});
+    std::vector<std::array<double, 3>> next(kWidth + 2, {0.0, 0.0, 0.0});
+    for (std::ptrdiff_t y = 0; y < cover.height(); ++y) {
+        for (std::size_t x = 0; x < kWidth; ++x) {
+            const auto& cover_pix = cover(x, y);
+            auto& output_pix = output(x, y);
+            const std::size_t kIndex =
+                static_cast<std::size_t>(y) * kWidth + x;
+            const int kBits = (!auth_pixels.empty() && auth_pixels[kIndex])
+                                  ? std::max(bits, kAuthBits)
+                                  : bits;
+            const int kMask = (1 << kBits) - 1;
+            for (std::size_t i = 0; i < 3; ++i) {
+                double target = cover_pix[i] + current[x + 1][i];
+                int nearest = output_pix[i];
+                for (int value : WithLowBits(
+                         static_cast<int>(
+                             std::clamp(std::lround(target), 0L, 255L)),
+                         output_pix[i] & kMask, kBits)) {
+                    if (std::abs(value - target) <
+                        std::abs(nearest - target)) {
+                        nearest = value;
+                    }
+                }
+                output_pix[i] = static_cast<uint8_t>(nearest);
+                double error = target - nearest;
+                current[x + 2][i] += error * 7.0 / 16.0;
+                next[x][i] += error * 3.0 / 16.0;
+                next[x + 1][i] += error * 5.0 / 16.0;
+                next[x + 2][i] += error * 1.0 / 16.0;
+            }
+        }
+        std::swap(current, next);
+        std::fill(next.begin(), next.end(),
+                  std::array<double, 3>{0.0, 0.0, 0.0});
+    }
+}
+
+/* the cover is decoded and copied into the output, and an SVG secret is
+ * drawn at its recorded size or the cover's */
+static RetCode CheckMergeBudget(const std::string& cover, ImageType cover_t,
+                                const std::string& secret, ImageType secret_t,
+                                bool svg_secret, const MergeConfig& config) {
+    uint64_t cover_bytes = 0;
+    uint64_t secret_bytes = 0;
+    RetCode rc = DecodedImageBytes(cover, cover_t, cover_bytes);
+    if ((rc == RetCode::kSuccess) && !svg_secret) {
+        rc = DecodedImageBytes(secret, secret_t, secret_bytes);
+    } else if (config.secret_width > 0) {
+        secret_bytes = static_cast<uint64_t>(config.secret_width) *
+                       static_cast<uint64_t>(config.secret_height) * 4;
+    } else {
+        secret_bytes = cover_bytes;
+    }
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    /* 2 * cover_bytes + secret_bytes > memory_budget without the sum
+     * wrapping, the byte counts saturate for absurd headers */
+    return ((cover_bytes > config.memory_budget / 2) ||
+            (secret_bytes > config.memory_budget - 2 * cover_bytes))
+               ? RetCode::kImageTooLarge
+               : RetCode::kSuccess;
+}
+
+/* reads the cover or secret under the merge's input policy, the
+ * conversions it needed joining the report's warnings */
+static RetCode ReadMergeInput(const std::string& filename, ImageType type,
+                              const MergeConfig& config,
+                              boost::gil::rgb8_image_t& image,
+                              MergeReport& report) {
+    InputPolicy policy = config.input;
+    if (config.strict) {
+        policy = {ConversionPolicy::kReject, ConversionPolicy::kReject,
+                  ConversionPolicy::kReject};
+    }
+    std::vector<InputWarning> warnings;
+    RetCode rc = ReadNormalizedImage(filename, type, policy, image, warnings);
+    for (const InputWarning& warning : warnings) {
+        report.warnings.push_back(
+            {InputConversionCode(warning.conversion), warning.message});
+    }
+    return rc;
+}
+
+RetCode Merge(const std::string& cover, const std::string& secret,
+              const std::string& outfile, const MergeConfig& config,
+              MergeReport& report) {
+    boost::gil::rgb8_image_t merged;
+    return Merge(cover, secret, outfile, config, report, merged);
+}
+
+RetCode LsbMethod::Embed(const boost::gil::rgb8c_view_t& cover,
+                         const boost::gil::rgb8c_view_t& secret,
+                         const boost::gil::rgb8_view_t& merged,
+                         const MergeConfig& config,
+                         MergeReport& report) const {
+    /* a key file scatters and encrypts the secret's pixels */
+    PhaseTimer timer(report.phases);
+    const std::size_t kWidth = static_cast<std::size_t>(cover.width());
+    std::unique_ptr<KeyedLayout> layout;
+    if (config.block_bits && config.dither) {
+        return RetCode::kInvalidArgument;
+    }
+    RetCode rc = LoadLayout(config.keyfile, config.password, kWidth,
+                            static_cast<std::size_t>(cover.height()),
+                            config.block_bits ? config.bits : 0, layout);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    timer.Finish("key derivation");
+
+    /* the size record goes in hidden pixels the secret leaves black */
+    const std::size_t kCoverPixels =
+        kWidth * static_cast<std::size_t>(cover.height());
+    const std::array<int, 3> kSecretBits =
+        SecretBits(config.bits, config.color_space);
+    if (layout) {
+        layout->full_bits_from =
+            layout->SecretPixels() -
+            std::min(layout->SecretPixels(), SizeRecordPixels(kSecretBits));
+    }
+    std::vector<boost::gil::rgb8_pixel_t> size_record;
+    std::size_t record_first = 0;
+    if (config.secret_width > 0) {
+        size_record = SizeRecord(secret.width(), secret.height(), kSecretBits);
+        std::size_t hidden_pixels =
+            layout ? layout->SecretPixels() : kCoverPixels;
+        std::size_t secret_end =
+            static_cast<std::size_t>(secret.height() - 1) * kWidth +
+            static_cast<std::size_t>(secret.width());
+        if (secret_end + size_record.size() > hidden_pixels) {
+            return RetCode::kInvalidDimensions;
+        }
+        record_first = hidden_pixels - size_record.size();
+    }
+
+    /* merge the secret image's pixels into the output image. The keyed
+     * layout draws from its keystreams in pixel order and so runs on one
+     * thread, without it bands of rows are independent. RGB without a key
+     * works on whole rows, the black past the secret included, and the size
+     * record is merged over the cleared pixels afterwards. */
+    const boost::gil::rgb8_pixel_t kBlackPixel(0, 0, 0);
+    const bool kRowKernels =
+        !layout && (config.color_space == ColorSpace::kRgb);
+    const std::size_t kSecretRowBytes =
+        static_cast<std::size_t>(secret.width()) * 3;
+    auto embed_rows = [&](std::ptrdiff_t first, std::ptrdiff_t last) {
+        for (std::ptrdiff_t row = first; row < last; ++row) {
+            if (kRowKernels) {
+                uint8_t* output_row = &merged(0, row)[0];
+                std::size_t merged_bytes = 0;
+                if (row < secret.height()) {
+                    MergeRow(output_row, &secret(0, row)[0], kSecretRowBytes,
+                             config.bits);
+                    merged_bytes = kSecretRowBytes;
+                }
+                ClearLowBits(output_row + merged_bytes,
+                             kWidth * 3 - merged_bytes, config.bits);
+                continue;
+            }
+            for (std::ptrdiff_t col = 0; col < merged.width(); ++col) {
+                boost::gil::rgb8_pixel_t secret_pix = kBlackPixel;
+                std::size_t secret_index =
+                    static_cast<std::size_t>(row) * kWidth +
+                    static_cast<std::size_t>(col);
+                if ((row < secret.height()) && (col < secret.width())) {
+                    secret_pix = secret(col, row);
+                } else if (!size_record.empty() &&
+                           (secret_index >= record_first) &&
+                           (secret_index - record_first <
+                            size_record.size())) {
+                    secret_pix = size_record[secret_index - record_first];
+                }
+                if (!layout) {
+                    merged(col, row) =
+                        MergePixels(merged(col, row), secret_pix,
+                                    config.bits, config.color_space);
+                    continue;
+                }
+
+                if (secret_index >= layout->SecretPixels()) {
+                    continue; /* displaced by the authentication block */
+                }
+                std::size_t index = layout->placement[secret_index];
+                auto& output_pix = merged(index % kWidth, index / kWidth);
+                const int kBits = layout->BitsAt(secret_index, config.bits);
+                output_pix = MergePixels(
+                    output_pix, ScramblePixel(secret_pix, *layout, kBits),
+                    kBits, config.color_space);
+            }
+        }
+    };
+    ParallelRows(merged.height(), layout ? 1 : config.threads, embed_rows);
+    if (kRowKernels) {
+        for (std::size_t i = 0; i < size_record.size(); ++i) {
+            const std::size_t kIndex = record_first + i;
+            auto& output_pix = merged(kIndex % kWidth, kIndex / kWidth);
+            output_pix = MergePixels(output_pix, size_record[i], config.bits);
+        }
+    }
+    if (layout && !layout->auth_block.empty()) {
+        WriteAuthBlock(merged, *layout);
+    }
+    timer.Finish("embed");
+    if (config.dither) {
+        DitherLowBits(cover, merged, config.bits,
+                      AuthBlockPixels(layout.get(), kCoverPixels));
+        timer.Finish("dither");
+    }
+    return RetCode::kSuccess;
+}
+
+int LsbMethod::Capacity(const MergeConfig& config) const {
+    const std::array<int, 3> kSecretBits =
+        SecretBits(config.bits, config.color_space);
+    return kSecretBits[0] + kSecretBits[1] + kSecretBits[2];
+}
+
+/* everything after loading the images, shared by the file and in-memory
+ * secrets */
+static RetCode MergeImages(const std::string& cover,
+                           const boost::gil::rgb8_image_t& cover_img,
+                           boost::gil::rgb8_image_t& secret_img,
+                           const std::string& outfile, ImageType output_img_t,
+                           const EmbedMethod& method, const MergeConfig& config,
+                           PhaseTimer& timer, MergeReport& report,
+                           boost::gil::rgb8_image_t& merged) {
+    boost::gil::rgb8_image_t output_img = cover_img;
+
+    /* verify secret fits within cover, resizing it if asked to */
+    const bool kRecordSize = (config.secret_width > 0);
+    if (kRecordSize) {
+        if ((config.secret_width > cover_img.width()) ||
+            (config.secret_height > cover_img.height())) {
+            return RetCode::kInvalidDimensions;
+        }
+        report.shrink =
+            ResizeSecret(secret_img, config.secret_width,
+                         config.secret_height, config.linear_resize);
+    } else if (config.fit) {
+        report.shrink = FitSecret(secret_img, cover_img.width(),
+                                  cover_img.height(), config.linear_resize);
+    }
+    if ((secret_img.height() > cover_img.height()) ||
+        (secret_img.width() > cover_img.width())) {
+        return RetCode::kInvalidDimensions;
+    }
+    if (report.shrink > config.max_shrink) {
+        if (config.strict) {
+            return RetCode::kInvalidDimensions;
+        }
+        std::ostringstream message;
+        message.precision(3);
+        message << "the secret was shrunk by a factor of " << report.shrink
+                << " to " << secret_img.width() << "x" << secret_img.height()
+                << ", the extracted image will be of little use";
+        report.warnings.push_back({"secret-shrunk", message.str()});
+    }
+    if (config.sharpen > 0.0) {
+        UnsharpMask(secret_img, config.sharpen);
+    }
+    timer.Finish("load");
+
+    /* the method hides the secret in a copy of the cover, timed as a
+     * whole unless it times its own phases */
+    const std::size_t kPhases = report.phases.size();
+    RetCode rc = method.Embed(boost::gil::const_view(cover_img),
+                              boost::gil::const_view(secret_img),
+                              boost::gil::view(output_img), config, report);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    if (report.phases.size() == kPhases) {
+        timer.Finish("embed");
+    } else {
+        timer.Restart();
+    }
+    if (config.create_dirs && !outfile.empty()) {
+        rc = CreateParentDirectories(outfile);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+
+    /* the output type is always lossless to avoid loss of data, and the
+     * pixels are still in the cover's color space */
+    if (!outfile.empty()) {
+        WriteImage(output_img, outfile, output_img_t, ReadIccProfile(cover));
+        timer.Finish("write");
+    }
+
+    /* each changed bit lights its mirror in the mask, so a changed least
+     * significant bit reads 128 rather than an invisible 1 */
+    auto cover_view = boost::gil::const_view(cover_img);
+    auto output_view = boost::gil::const_view(output_img);
+    boost::gil::rgb8_image_t mask_img;
+    if (!config.mask.empty()) {
+        mask_img = boost::gil::rgb8_image_t(output_view.width(),
+                                            output_view.height());
+    }
+    auto mask_view = boost::gil::view(mask_img);
+    for (int row = 0; row < output_view.height(); ++row) {
+        for (int col = 0; col < output_view.width(); ++col) {
+            const auto& cover_pix = cover_view(col, row);
+            const auto& output_pix = output_view(col, row);
+            if (output_pix != cover_pix) {
+                ++report.pixels_modified;
+            }
+            for (int i = 0; i < 3; ++i) {
+                const uint8_t kChanged =
+                    static_cast<uint8_t>(cover_pix[i] ^ output_pix[i]);
+                uint8_t mirrored = 0;
+                for (int bit = 0; bit < 8; ++bit) {
+                    if (kChanged & (1 << bit)) {
+                        ++report.bits_modified[bit];
+                        mirrored |= static_cast<uint8_t>(0x80 >> bit);
+                    }
+                }
+                if (!config.mask.empty()) {
+                    mask_view(col, row)[i] = mirrored;
+                }
+            }
+        }
+    }
+    if (!config.mask.empty()) {
+        if (config.create_dirs) {
+            rc = CreateParentDirectories(config.mask);
+            if (rc != RetCode::kSuccess) {
+                return rc;
+            }
+        }
+        WriteImage(mask_img, config.mask, ImageType::kPng);
+    }
+    const std::size_t kSecretPixels =
+        static_cast<std::size_t>(secret_img.width() * secret_img.height());
+    const std::size_t kCoverPixels =
+        static_cast<std::size_t>(cover_img.width() * cover_img.height());
+    report.bits_embedded =
+        kSecretPixels * static_cast<std::size_t>(method.Capacity(config));
+    report.capacity_used = (kCoverPixels == 0)
+                               ? 0.0
+                               : 100.0 * static_cast<double>(kSecretPixels) /
+                                     static_cast<double>(kCoverPixels);
+    report.psnr = Psnr(cover_view, output_view);
+    const uint64_t kChannelBytes = 3;
+    report.image_bytes =
+        kChannelBytes *
+        (2 * static_cast<uint64_t>(kCoverPixels) +
+         static_cast<uint64_t>(kSecretPixels));
+    timer.Finish("statistics");
+    report.peak_rss = report.phases.back().peak_rss;
+
+    merged = std::move(output_img);
+    return RetCode::kSuccess;
+}
+
+RetCode Merge(const std::string& cover, const std::string& secret,
+              const std::string& outfile, const MergeConfig& config,
+              MergeReport& report, boost::gil::rgb8_image_t& merged) {
+    report = MergeReport{};
+    report.rss_since_merge = ResetPeakRss();
+    PhaseTimer timer(report.phases);
+    if ((config.bits < kMinBits) || (config.bits > kMaxBits) ||
+        (config.max_shrink < 1.0) || (config.sharpen < 0.0) ||
+        (config.secret_width < 0) || (config.secret_height < 0) ||
+        ((config.secret_width > 0) != (config.secret_height > 0)) ||
+        (config.dither && (config.color_space != ColorSpace::kRgb))) {
+        return RetCode::kInvalidArgument;
+    }
+    std::shared_ptr<const EmbedMethod> method = FindEmbedMethod(config.method);
+    if (!method) {
+        return RetCode::kInvalidArgument;
+    }
+
     /* verify the input image files exists */
     if (!std::filesystem::exists(cover) || !std::filesystem::exists(secret)) {
         return RetCode::kFileNotFound;
     }
 
+    /* hiding an image in itself is a mistake, as is writing over an input */
+    if (SamePath(cover, secret) || SameContents(cover, secret)) {
+        return RetCode::kSameInput;
+    }
+    if (!outfile.empty() &&
+        (SamePath(outfile, cover) || SamePath(outfile, secret))) {
+        return RetCode::kOutputIsInput;
+    }
+    if (!config.mask.empty() &&
+        (SamePath(config.mask, cover) || SamePath(config.mask, secret) ||
+         SamePath(config.mask, outfile))) {
+        return RetCode::kOutputIsInput;
+    }
+#ifdef STEG_WITH_EXR
+    /* float covers are written back out in their own format */
+    if (IsExr(cover)) {
+        /* nor is there an 8 bit mask of their changes */
+        if (outfile.empty() || !config.mask.empty()) {
+            return RetCode::kUnsupportedFormat;
+        }
+        return MergeExr(cover, secret, outfile, config, report);
+    }
+#endif
+
     /* verify the input image files have a valid file type */
     ImageType cover_img_t(GetImageType(cover));
     ImageType secret_img_t(GetImageType(secret));
+#ifdef STEG_WITH_SVG
+    const bool kSvgSecret =
+        (secret_img_t == ImageType::kUnknown) && IsSvg(secret);
+#else
+    const bool kSvgSecret = false;
+#endif
     if ((cover_img_t == ImageType::kUnknown) ||
-        (secret_img_t == ImageType::kUnknown)) {
+        ((secret_img_t == ImageType::kUnknown) && !kSvgSecret)) {
         return RetCode::kInvalidFileFormat;
     }
+    RetCode rc = CheckImageLimits(cover, cover_img_t);
+    if ((rc == RetCode::kSuccess) && !kSvgSecret) {
+        rc = CheckImageLimits(secret, secret_img_t);
+    }
+    if ((rc == RetCode::kSuccess) && (config.memory_budget != 0)) {
+        rc = CheckMergeBudget(cover, cover_img_t, secret, secret_img_t,
+                              kSvgSecret, config);
+    }
+    ImageType output_img_t = ImageType::kPng;
+    if (rc == RetCode::kSuccess) {
+        rc = ResolveOutputType(config.output_format, outfile, cover_img_t,
+                               true, output_img_t);
+    }
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
 
     /* load images into GIL image type */
-    boost::gil::rgb8_image_t cover_img(ReadImage(cover, cover_img_t));
-    boost::gil::rgb8_image_t secret_img(ReadImage(secret, secret_img_t));
-    boost::gil::rgb8_image_t output_img = cover_img;
+    boost::gil::rgb8_image_t cover_img;
+    rc = ReadMergeInput(cover, cover_img_t, config, cover_img, report);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    boost::gil::rgb8_image_t secret_img;
+#ifdef STEG_WITH_SVG
+    /* vector secrets are drawn at the size they are embedded at rather than
+     * resampled afterwards */
+    if (kSvgSecret) {
+        const bool kSized = (config.secret_width > 0);
+        if (!RasterizeSvg(
+                secret, kSized ? config.secret_width : cover_img.width(),
+                kSized ? config.secret_height : cover_img.height(),
+                secret_img)) {
+            return RetCode::kInvalidFileFormat;
+        }
+    }
+#endif
+    if (!kSvgSecret) {
+        rc = ReadMergeInput(secret, secret_img_t, config, secret_img, report);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+    return MergeImages(cover, cover_img, secret_img, outfile, output_img_t,
+                       *method, config, timer, report, merged);
+}
 
-    /* verify secret fits within cover */
-    if ((secret_img.height() > cover_img.height()) ||
-        (secret_img.width() > cover_img.width())) {
-        return RetCode::kInvalidDimensions;
+RetCode Merge(const std::string& cover, const boost::gil::rgb8_image_t& secret,
+              const std::string& outfile, const MergeConfig& config,
+              MergeReport& report) {
+    report = MergeReport{};
+    report.rss_since_merge = ResetPeakRss();
+    PhaseTimer timer(report.phases);
+    if ((config.bits < kMinBits) || (config.bits > kMaxBits) ||
+        (config.max_shrink < 1.0) || (config.sharpen < 0.0) ||
+        (config.secret_width < 0) || (config.secret_height < 0) ||
+        ((config.secret_width > 0) != (config.secret_height > 0)) ||
+        (config.dither && (config.color_space != ColorSpace::kRgb))) {
+        return RetCode::kInvalidArgument;
+    }
+    std::shared_ptr<const EmbedMethod> method = FindEmbedMethod(config.method);
+    if (!method) {
+        return RetCode::kInvalidArgument;
+    }
+    if (!std::filesystem::exists(cover)) {
+        return RetCode::kFileNotFound;
+    }
+    if ((!outfile.empty() && SamePath(outfile, cover)) ||
+        (!config.mask.empty() &&
+         (SamePath(config.mask, cover) || SamePath(config.mask, outfile)))) {
+        return RetCode::kOutputIsInput;
     }
+#ifdef STEG_WITH_EXR
+    if (IsExr(cover)) {
+        /* nor is there an 8 bit mask of their changes */
+        if (outfile.empty() || !config.mask.empty()) {
+            return RetCode::kUnsupportedFormat;
+        }
+        return MergeExr(cover, secret, outfile, config, report);
+    }
+#endif
 
-    /* merge the secret image's pixels into the output image */
-    const boost::gil::rgb8_pixel_t kBlackPixel(0, 0, 0);
-    auto secret_view = boost::gil::const_view(secret_img);
+    /* the secret is already decoded, only the cover counts against limits */
+    ImageType cover_img_t(GetImageType(cover));
+    if (cover_img_t == ImageType::kUnknown) {
+        return RetCode::kInvalidFileFormat;
+    }
+    RetCode rc = CheckImageLimits(cover, cover_img_t);
+    if ((rc == RetCode::kSuccess) && (config.memory_budget != 0)) {
+        uint64_t cover_bytes = 0;
+        rc = DecodedImageBytes(cover, cover_img_t, cover_bytes);
+        const uint64_t kSecretBytes = static_cast<uint64_t>(secret.width()) *
+                                      static_cast<uint64_t>(secret.height()) *
+                                      3;
+        if ((rc == RetCode::kSuccess) &&
+            ((cover_bytes > config.memory_budget / 2) ||
+             (kSecretBytes > config.memory_budget - 2 * cover_bytes))) {
+            rc = RetCode::kImageTooLarge;
+        }
+    }
+    ImageType output_img_t = ImageType::kPng;
+    if (rc == RetCode::kSuccess) {
+        rc = ResolveOutputType(config.output_format, outfile, cover_img_t,
+                               true, output_img_t);
+    }
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+
+    boost::gil::rgb8_image_t cover_img;
+    rc = ReadMergeInput(cover, cover_img_t, config, cover_img, report);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    /* resizing and sharpening work on a copy, the caller's stays as it is */
+    boost::gil::rgb8_image_t secret_img = secret;
+    boost::gil::rgb8_image_t merged;
+    return MergeImages(cover, cover_img, secret_img, outfile, output_img_t,
+                       *method, config, timer, report, merged);
+}
+
+RetCode LsbMethod::Extract(const boost::gil::rgb8c_view_t& merged,
+                           const UnmergeConfig& config,
+                           boost::gil::rgb8_image_t& secret) const {
+    /* undo the keyed layout if the secret was merged with a key file */
+    const std::size_t kWidth = static_cast<std::size_t>(merged.width());
+    std::unique_ptr<KeyedLayout> layout;
+    RetCode rc = LoadLayout(config.keyfile, config.password, kWidth,
+                            static_cast<std::size_t>(merged.height()),
+                            config.block_bits ? config.bits : 0, layout);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    const std::array<int, 3> kSecretBits =
+        SecretBits(config.bits, config.color_space);
+    const std::size_t kRecordPixels = SizeRecordPixels(kSecretBits);
+    if (layout) {
+        layout->full_bits_from =
+            layout->SecretPixels() -
+            std::min(layout->SecretPixels(), kRecordPixels);
+    }
+    if (layout && !layout->auth_block.empty()) {
+        rc = VerifyAuthBlock(merged, *layout);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+
+    /* extract the hidden image into the output image, on one thread when
+     * keyed for the same reason as merging */
+    boost::gil::rgb8_image_t output_img(merged.width(), merged.height());
     auto output_view = boost::gil::view(output_img);
-    for (int row = 0; row < output_view.height(); ++row) {
-        for (int col = 0; col < output_view.width(); ++col) {
-            if ((row >= secret_img.height()) || (col >= secret_img.width())) {
-                output_view(col, row) =
-                    MergePixels(output_view(col, row), kBlackPixel);
-            } else {
-                output_view(col, row) =
-                    MergePixels(output_view(col, row), secret_view(col, row));
+    auto extract_rows = [&](std::ptrdiff_t first, std::ptrdiff_t last) {
+        for (std::ptrdiff_t row = first; row < last; ++row) {
+            if (!layout && (config.color_space == ColorSpace::kRgb)) {
+                UnmergeRow(&merged(0, row)[0], &output_view(0, row)[0],
+                           kWidth * 3, config.bits);
+                continue;
+            }
+            for (std::ptrdiff_t col = 0; col < output_view.width(); ++col) {
+                if (!layout) {
+                    output_view(col, row) =
+                        UnmergePixels(merged(col, row), config.bits,
+                                      config.color_space);
+                    continue;
+                }
+
+                std::size_t secret_index =
+                    static_cast<std::size_t>(row) * kWidth +
+                    static_cast<std::size_t>(col);
+                if (secret_index >= layout->SecretPixels()) {
+                    output_view(col, row) = boost::gil::rgb8_pixel_t(0, 0, 0);
+                    continue;
+                }
+                std::size_t index = layout->placement[secret_index];
+                const int kBits = layout->BitsAt(secret_index, config.bits);
+                output_view(col, row) = UnscramblePixel(
+                    UnmergePixels(merged(index % kWidth, index / kWidth),
+                                  kBits, config.color_space),
+                    *layout, kBits);
             }
         }
-    }
+    };
+    ParallelRows(output_view.height(), layout ? 1 : config.threads,
+                 extract_rows);
 
-    /* avoid loss of data by using a lossless image format in this case PNG */
-    WriteImage(output_img, outfile, ImageType::kPng);
+    /* a size record means everything past the secret is padding */
+    std::ptrdiff_t width = 0;
+    std::ptrdiff_t height = 0;
+    std::size_t hidden_pixels = layout ? layout->SecretPixels()
+                                       : kWidth * static_cast<std::size_t>(
+                                                      merged.height());
+    if ((hidden_pixels >= kRecordPixels) &&
+        ReadSizeRecord(boost::gil::const_view(output_img),
+                       hidden_pixels - kRecordPixels, kSecretBits, width,
+                       height)) {
+        boost::gil::rgb8_image_t cropped(width, height);
+        boost::gil::copy_pixels(
+            boost::gil::subimage_view(boost::gil::const_view(output_img), 0, 0,
+                                      width, height),
+            boost::gil::view(cropped));
+        output_img = std::move(cropped);
+    }
 
+    secret = std::move(output_img);
     return RetCode::kSuccess;
 }
 
 RetCode Unmerge(const std::string& secret, const std::string& outfile) {
+    return Unmerge(secret, outfile, UnmergeConfig{});
+}
+
+RetCode Unmerge(const std::string& secret, const std::string& outfile,
+                const UnmergeConfig& config) {
+    boost::gil::rgb8_image_t unmerged;
+    return Unmerge(secret, outfile, config, unmerged);
+}
+
+RetCode Unmerge(const std::string& secret, const std::string& outfile,
+                const UnmergeConfig& config,
+                boost::gil::rgb8_image_t& unmerged) {
+    std::shared_ptr<const EmbedMethod> method = FindEmbedMethod(config.method);
+    if ((config.bits < kMinBits) || (config.bits > kMaxBits) || !method) {
+        return RetCode::kInvalidArgument;
+    }
+
     /* verify the image containing the secret exists */
     if (!std::filesystem::exists(secret)) {
         return RetCode::kFileNotFound;
     }
+    if (!outfile.empty() && SamePath(outfile, secret)) {
+        return RetCode::kOutputIsInput;
+    }
+#ifdef STEG_WITH_EXR
+    if (IsExr(secret)) {
+        if (outfile.empty()) {
+            return RetCode::kUnsupportedFormat;
+        }
+        return UnmergeExr(secret, outfile, config);
+    }
+#endif
 
     /* verify the input image has a valid file type */
     ImageType secret_img_t(GetImageType(secret));
     if (secret_img_t == ImageType::kUnknown) {
         return RetCode::kInvalidFileFormat;
     }
+    RetCode rc = CheckImageLimits(secret, secret_img_t);
+    if ((rc == RetCode::kSuccess) && (config.memory_budget != 0)) {
+        /* the image is decoded and copied into the output */
+        uint64_t secret_bytes = 0;
+        rc = DecodedImageBytes(secret, secret_img_t, secret_bytes);
+        if ((rc == RetCode::kSuccess) &&
+            (secret_bytes > config.memory_budget / 2)) {
+            rc = RetCode::kImageTooLarge;
+        }
+    }
+    ImageType output_img_t = ImageType::kPng;
+    if (rc == RetCode::kSuccess) {
+        rc = ResolveOutputType(config.output_format, outfile, secret_img_t,
+                               false, output_img_t);
+    }
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
 
     /* load images into GIL image type */
     boost::gil::rgb8_image_t secret_img(ReadImage(secret, secret_img_t));
-    boost::gil::rgb8_image_t output_img = secret_img;
+    boost::gil::rgb8_image_t output_img;
+    rc = method->Extract(boost::gil::const_view(secret_img), config,
+                         output_img);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
 
-    /* extract the hidden image into the output image */
-    auto secret_view = boost::gil::const_view(secret_img);
-    auto output_view = boost::gil::view(output_img);
-    for (int row = 0; row < output_view.height(); ++row) {
-        for (int col = 0; col < output_view.width(); ++col) {
-            output_view(col, row) = UnmergePixels(secret_view(col, row));
+    if (!outfile.empty()) {
+        if (config.create_dirs) {
+            rc = CreateParentDirectories(outfile);
+            if (rc != RetCode::kSuccess) {
+                return rc;
+            }
         }
-    }
 
-    /* write the image out using the user's preferred format (default PNG) */
-    if (HasJpegExtension(outfile)) {
-        WriteImage(output_img, outfile, ImageType::kJpeg);
-    } else {
-        WriteImage(output_img, outfile, ImageType::kPng);
+        /* write the image out using the user's preferred format (default
+         * PNG) */
+        WriteImage(output_img, outfile, output_img_t);
     }
 
+    unmerged = std::move(output_img);
     return RetCode::kSuccess;
 }
 
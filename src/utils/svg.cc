@@ -0,0 +1,93 @@
+#include "utils/svg.hpp"
+
+#include <cairo.h>
+#include <librsvg/rsvg.h>
+
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <fstream>
+#include <memory>
+#include <string>
+#include <vector>
+
+namespace steganography {
+
+struct HandleDeleter {
+    void operator()(RsvgHandle* handle) const { g_object_unref(handle); }
+};
+
+struct SurfaceDeleter {
+    void operator()(cairo_surface_t* surface) const {
+        cairo_surface_destroy(surface);
+    }
+};
+
+struct CairoDeleter {
+    void operator()(cairo_t* cairo) const { cairo_destroy(cairo); }
+};
+
+using HandlePtr = std::unique_ptr<RsvgHandle, HandleDeleter>;
+using SurfacePtr = std::unique_ptr<cairo_surface_t, SurfaceDeleter>;
+using CairoPtr = std::unique_ptr<cairo_t, CairoDeleter>;
+
+bool IsSvg(const std::string& filename) {
+    /* room for an XML declaration, a doctype, and comments before it */
+    const std::size_t kSniffSize = 4096;
+    std::ifstream ifs(filename, std::ifstream::binary);
+    std::vector<char> head(kSniffSize, 0);
+    ifs.read(head.data(), static_cast<std::streamsize>(head.size()));
+    return std::string(head.data(), static_cast<std::size_t>(ifs.gcount()))
+               .find("<svg") != std::string::npos;
+}
+
+bool RasterizeSvg(const std::string& filename, std::ptrdiff_t width,
+                  std::ptrdiff_t height, boost::gil::rgb8_image_t& image) {
+    GError* error = nullptr;
+    HandlePtr handle(rsvg_handle_new_from_file(filename.c_str(), &error));
+    if (!handle) {
+        g_clear_error(&error);
+        return false;
+    }
+    SurfacePtr surface(cairo_image_surface_create(CAIRO_FORMAT_ARGB32,
+                                                  static_cast<int>(width),
+                                                  static_cast<int>(height)));
+    if (cairo_surface_status(surface.get()) != CAIRO_STATUS_SUCCESS) {
+        return false;
+    }
+
+    /* the viewport keeps the document's aspect ratio and centers it */
+    CairoPtr cairo(cairo_create(surface.get()));
+    const RsvgRectangle kViewport = {0.0, 0.0, static_cast<double>(width),
+                                     static_cast<double>(height)};
+    const bool kRendered = rsvg_handle_render_document(
+        handle.get(), cairo.get(), &kViewport, &error);
+    g_clear_error(&error);
+    if (!kRendered) {
+        return false;
+    }
+    cairo_surface_flush(surface.get());
+
+    /* cairo pixels are premultiplied native endian ARGB words, adding the
+     * uncovered part of white composites them over a white background */
+    const unsigned char* data = cairo_image_surface_get_data(surface.get());
+    const std::size_t kStride =
+        static_cast<std::size_t>(cairo_image_surface_get_stride(surface.get()));
+    image = boost::gil::rgb8_image_t(width, height);
+    auto view = boost::gil::view(image);
+    for (std::ptrdiff_t y = 0; y < height; ++y) {
+        const uint32_t* row = reinterpret_cast<const uint32_t*>(
+            data + static_cast<std::size_t>(y) * kStride);
+        for (std::ptrdiff_t x = 0; x < width; ++x) {
+            const uint32_t kPixel = row[x];
+            const uint32_t kUncovered = 255 - (kPixel >> 24);
+            view(x, y) = boost::gil::rgb8_pixel_t(
+                static_cast<uint8_t>(((kPixel >> 16) & 0xFF) + kUncovered),
+                static_cast<uint8_t>(((kPixel >> 8) & 0xFF) + kUncovered),
+                static_cast<uint8_t>((kPixel & 0xFF) + kUncovered));
+        }
+    }
+    return true;
+}
+
+}  // namespace steganography
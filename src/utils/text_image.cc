@@ -0,0 +1,241 @@
+#include "utils/text_image.hpp"
+
+#include <algorithm>
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <filesystem>
+#include <fstream>
+#include <iterator>
+#include <string>
+#include <vector>
+
+#include "utils/image_io.hpp"
+
+namespace steganography {
+
+static const int kGlyphSize = 8;  /* glyphs are 8x8 pixels */
+static const int kLineHeight = 10; /* two pixels of leading between lines */
+static const int kTabStop = 4;
+static const char kFirstGlyph = ' ';
+static const char kLastGlyph = '~';
+
+/* the public domain font8x8_basic set for printable ASCII, one byte per row
+ * from the top with the least significant bit the leftmost pixel */
+static const uint8_t kGlyphs[kLastGlyph - kFirstGlyph + 1][kGlyphSize] = {
+    {0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00}, /* ' ' */
+    {0x18, 0x3C, 0x3C, 0x18, 0x18, 0x00, 0x18, 0x00}, /* '!' */
+    {0x36, 0x36, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00}, /* '"' */
+    {0x36, 0x36, 0x7F, 0x36, 0x7F, 0x36, 0x36, 0x00}, /* '#' */
+    {0x0C, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x0C, 0x00}, /* '$' */
+    {0x00, 0x63, 0x33, 0x18, 0x0C, 0x66, 0x63, 0x00}, /* '%' */
+    {0x1C, 0x36, 0x1C, 0x6E, 0x3B, 0x33, 0x6E, 0x00}, /* '&' */
+    {0x06, 0x06, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00}, /* '\'' */
+    {0x18, 0x0C, 0x06, 0x06, 0x06, 0x0C, 0x18, 0x00}, /* '(' */
+    {0x06, 0x0C, 0x18, 0x18, 0x18, 0x0C, 0x06, 0x00}, /* ')' */
+    {0x00, 0x66, 0x3C, 0xFF, 0x3C, 0x66, 0x00, 0x00}, /* '*' */
+    {0x00, 0x0C, 0x0C, 0x3F, 0x0C, 0x0C, 0x00, 0x00}, /* '+' */
+    {0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x06}, /* ',' */
+    {0x00, 0x00, 0x00, 0x3F, 0x00, 0x00, 0x00, 0x00}, /* '-' */
+    {0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x00}, /* '.' */
+    {0x60, 0x30, 0x18, 0x0C, 0x06, 0x03, 0x01, 0x00}, /* '/' */
+    {0x3E, 0x63, 0x73, 0x7B, 0x6F, 0x67, 0x3E, 0x00}, /* '0' */
+    {0x0C, 0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x3F, 0x00}, /* '1' */
+    {0x1E, 0x33, 0x30, 0x1C, 0x06, 0x33, 0x3F, 0x00}, /* '2' */
+    {0x1E, 0x33, 0x30, 0x1C, 0x30, 0x33, 0x1E, 0x00}, /* '3' */
+    {0x38, 0x3C, 0x36, 0x33, 0x7F, 0x30, 0x78, 0x00}, /* '4' */
+    {0x3F, 0x03, 0x1F, 0x30, 0x30, 0x33, 0x1E, 0x00}, /* '5' */
+    {0x1C, 0x06, 0x03, 0x1F, 0x33, 0x33, 0x1E, 0x00}, /* '6' */
+    {0x3F, 0x33, 0x30, 0x18, 0x0C, 0x0C, 0x0C, 0x00}, /* '7' */
+    {0x1E, 0x33, 0x33, 0x1E, 0x33, 0x33, 0x1E, 0x00}, /* '8' */
+    {0x1E, 0x33, 0x33, 0x3E, 0x30, 0x18, 0x0E, 0x00}, /* '9' */
+    {0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x00}, /* ':' */
+    {0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x06}, /* ';' */
+    {0x18, 0x0C, 0x06, 0x03, 0x06, 0x0C, 0x18, 0x00}, /* '<' */
+    {0x00, 0x00, 0x3F, 0x00, 0x00, 0x3F, 0x00, 0x00}, /* '=' */
+    {0x06, 0x0C, 0x18, 0x30, 0x18, 0x0C, 0x06, 0x00}, /* '>' */
+    {0x1E, 0x33, 0x30, 0x18, 0x0C, 0x00, 0x0C, 0x00}, /* '?' */
+    {0x3E, 0x63, 0x7B, 0x7B, 0x7B, 0x03, 0x1E, 0x00}, /* '@' */
+    {0x0C, 0x1E, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x00}, /* 'A' */
+    {0x3F, 0x66, 0x66, 0x3E, 0x66, 0x66, 0x3F, 0x00}, /* 'B' */
+    {0x3C, 0x66, 0x03, 0x03, 0x03, 0x66, 0x3C, 0x00}, /* 'C' */
+    {0x1F, 0x36, 0x66, 0x66, 0x66, 0x36, 0x1F, 0x00}, /* 'D' */
+    {0x7F, 0x46, 0x16, 0x1E, 0x16, 0x46, 0x7F, 0x00}, /* 'E' */
+    {0x7F, 0x46, 0x16, 0x1E, 0x16, 0x06, 0x0F, 0x00}, /* 'F' */
+    {0x3C, 0x66, 0x03, 0x03, 0x73, 0x66, 0x7C, 0x00}, /* 'G' */
+    {0x33, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x33, 0x00}, /* 'H' */
+    {0x1E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00}, /* 'I' */
+    {0x78, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E, 0x00}, /* 'J' */
+    {0x67, 0x66, 0x36, 0x1E, 0x36, 0x66, 0x67, 0x00}, /* 'K' */
+    {0x0F, 0x06, 0x06, 0x06, 0x46, 0x66, 0x7F, 0x00}, /* 'L' */
+    {0x63, 0x77, 0x7F, 0x7F, 0x6B, 0x63, 0x63, 0x00}, /* 'M' */
+    {0x63, 0x67, 0x6F, 0x7B, 0x73, 0x63, 0x63, 0x00}, /* 'N' */
+    {0x1C, 0x36, 0x63, 0x63, 0x63, 0x36, 0x1C, 0x00}, /* 'O' */
+    {0x3F, 0x66, 0x66, 0x3E, 0x06, 0x06, 0x0F, 0x00}, /* 'P' */
+    {0x1E, 0x33, 0x33, 0x33, 0x3B, 0x1E, 0x38, 0x00}, /* 'Q' */
+    {0x3F, 0x66, 0x66, 0x3E, 0x36, 0x66, 0x67, 0x00}, /* 'R' */
+    {0x1E, 0x33, 0x07, 0x0E, 0x38, 0x33, 0x1E, 0x00}, /* 'S' */
+    {0x3F, 0x2D, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00}, /* 'T' */
+    {0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x3F, 0x00}, /* 'U' */
+    {0x33, 0x33, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00}, /* 'V' */
+    {0x63, 0x63, 0x63, 0x6B, 0x7F, 0x77, 0x63, 0x00}, /* 'W' */
+    {0x63, 0x63, 0x36, 0x1C, 0x1C, 0x36, 0x63, 0x00}, /* 'X' */
+    {0x33, 0x33, 0x33, 0x1E, 0x0C, 0x0C, 0x1E, 0x00}, /* 'Y' */
+    {0x7F, 0x63, 0x31, 0x18, 0x4C, 0x66, 0x7F, 0x00}, /* 'Z' */
+    {0x1E, 0x06, 0x06, 0x06, 0x06, 0x06, 0x1E, 0x00}, /* '[' */
+    {0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x40, 0x00}, /* '\' */
+    {0x1E, 0x18, 0x18, 0x18, 0x18, 0x18, 0x1E, 0x00}, /* ']' */
+    {0x08, 0x1C, 0x36, 0x63, 0x00, 0x00, 0x00, 0x00}, /* '^' */
+    {0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF}, /* '_' */
+    {0x0C, 0x0C, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00}, /* '`' */
+    {0x00, 0x00, 0x1E, 0x30, 0x3E, 0x33, 0x6E, 0x00}, /* 'a' */
+    {0x07, 0x06, 0x06, 0x3E, 0x66, 0x66, 0x3B, 0x00}, /* 'b' */
+    {0x00, 0x00, 0x1E, 0x33, 0x03, 0x33, 0x1E, 0x00}, /* 'c' */
+    {0x38, 0x30, 0x30, 0x3E, 0x33, 0x33, 0x6E, 0x00}, /* 'd' */
+    {0x00, 0x00, 0x1E, 0x33, 0x3F, 0x03, 0x1E, 0x00}, /* 'e' */
+    {0x1C, 0x36, 0x06, 0x0F, 0x06, 0x06, 0x0F, 0x00}, /* 'f' */
+    {0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x1F}, /* 'g' */
+    {0x07, 0x06, 0x36, 0x6E, 0x66, 0x66, 0x67, 0x00}, /* 'h' */
+    {0x0C, 0x00, 0x0E, 0x0C, 0x0C, 0x0C, 0x1E, 0x00}, /* 'i' */
+    {0x30, 0x00, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E}, /* 'j' */
+    {0x07, 0x06, 0x66, 0x36, 0x1E, 0x36, 0x67, 0x00}, /* 'k' */
+    {0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00}, /* 'l' */
+    {0x00, 0x00, 0x33, 0x7F, 0x7F, 0x6B, 0x63, 0x00}, /* 'm' */
+    {0x00, 0x00, 0x1F, 0x33, 0x33, 0x33, 0x33, 0x00}, /* 'n' */
+    {0x00, 0x00, 0x1E, 0x33, 0x33, 0x33, 0x1E, 0x00}, /* 'o' */
+    {0x00, 0x00, 0x3B, 0x66, 0x66, 0x3E, 0x06, 0x0F}, /* 'p' */
+    {0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x78}, /* 'q' */
+    {0x00, 0x00, 0x3B, 0x6E, 0x66, 0x06, 0x0F, 0x00}, /* 'r' */
+    {0x00, 0x00, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x00}, /* 's' */
+    {0x08, 0x0C, 0x3E, 0x0C, 0x0C, 0x2C, 0x18, 0x00}, /* 't' */
+    {0x00, 0x00, 0x33, 0x33, 0x33, 0x33, 0x6E, 0x00}, /* 'u' */
+    {0x00, 0x00, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00}, /* 'v' */
+    {0x00, 0x00, 0x63, 0x6B, 0x7F, 0x7F, 0x36, 0x00}, /* 'w' */
+    {0x00, 0x00, 0x63, 0x36, 0x1C, 0x36, 0x63, 0x00}, /* 'x' */
+    {0x00, 0x00, 0x33, 0x33, 0x33, 0x3E, 0x30, 0x1F}, /* 'y' */
+    {0x00, 0x00, 0x3F, 0x19, 0x0C, 0x26, 0x3F, 0x00}, /* 'z' */
+    {0x38, 0x0C, 0x0C, 0x07, 0x0C, 0x0C, 0x38, 0x00}, /* '{' */
+    {0x18, 0x18, 0x18, 0x00, 0x18, 0x18, 0x18, 0x00}, /* '|' */
+    {0x07, 0x0C, 0x0C, 0x38, 0x0C, 0x0C, 0x07, 0x00}, /* '}' */
+    {0x6E, 0x3B, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00}, /* '~' */
+};
+
+/* the text as lines of printable ASCII with tabs expanded, each UTF-8 code
+ * point outside ASCII becomes a single '?' */
+static std::vector<std::string> SplitLines(const std::string& text) {
+    std::vector<std::string> lines(1);
+    for (char c : text) {
+        const uint8_t kByte = static_cast<uint8_t>(c);
+        if (c == '\n') {
+            lines.emplace_back();
+        } else if (c == '\t') {
+            lines.back().append(kTabStop - lines.back().size() % kTabStop,
+                                ' ');
+        } else if ((kByte >= 0x80) && (kByte < 0xC0)) {
+            continue; /* UTF-8 continuation byte */
+        } else if ((c >= kFirstGlyph) && (c <= kLastGlyph)) {
+            lines.back().push_back(c);
+        } else if (c != '\r') {
+            lines.back().push_back('?');
+        }
+    }
+    /* a final newline ends the last line rather than starting another */
+    if ((lines.size() > 1) && lines.back().empty()) {
+        lines.pop_back();
+    }
+    return lines;
+}
+
+/* wraps lines longer than columns at their last space that fits, or
+ * mid-word when a word is longer than a line */
+static std::vector<std::string> WrapLines(const std::vector<std::string>& lines,
+                                          std::size_t columns) {
+    std::vector<std::string> wrapped;
+    for (std::string line : lines) {
+        while (line.size() > columns) {
+            std::size_t space = line.rfind(' ', columns);
+            if ((space == std::string::npos) || (space == 0)) {
+                wrapped.push_back(line.substr(0, columns));
+                line.erase(0, columns);
+            } else {
+                wrapped.push_back(line.substr(0, space));
+                line.erase(0, space + 1);
+            }
+        }
+        wrapped.push_back(line);
+    }
+    return wrapped;
+}
+
+static void DrawGlyph(const boost::gil::rgb8_view_t& view, char c,
+                      std::ptrdiff_t left, std::ptrdiff_t top,
+                      std::ptrdiff_t scale) {
+    const uint8_t* glyph = kGlyphs[c - kFirstGlyph];
+    for (std::ptrdiff_t row = 0; row < kGlyphSize * scale; ++row) {
+        for (std::ptrdiff_t col = 0; col < kGlyphSize * scale; ++col) {
+            if ((glyph[row / scale] >> (col / scale)) & 1) {
+                view(left + col, top + row) = boost::gil::rgb8_pixel_t(0, 0, 0);
+            }
+        }
+    }
+}
+
+RetCode MergeText(const std::string& cover, const std::string& textfile,
+                  const std::string& outfile, const MergeConfig& config) {
+    if (!std::filesystem::exists(textfile)) {
+        return RetCode::kFileNotFound;
+    }
+    std::ifstream ifs(textfile, std::ifstream::binary);
+    if (!ifs.is_open()) {
+        return RetCode::kIoError;
+    }
+    const std::string kText((std::istreambuf_iterator<char>(ifs)),
+                            std::istreambuf_iterator<char>());
+    boost::gil::rgb8_image_t cover_img;
+    RetCode rc = LoadImage(cover, cover_img);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+
+    /* the largest scale at which the wrapped text fits inside a margin of
+     * one character on every side */
+    const std::vector<std::string> kLines = SplitLines(kText);
+    std::vector<std::string> wrapped;
+    std::ptrdiff_t scale = std::min(cover_img.width() / kGlyphSize,
+                                    cover_img.height() / kLineHeight);
+    for (; scale > 0; --scale) {
+        const std::ptrdiff_t kColumns =
+            cover_img.width() / (kGlyphSize * scale) - 2;
+        const std::ptrdiff_t kRows =
+            cover_img.height() / (kLineHeight * scale) - 2;
+        if ((kColumns < 1) || (kRows < 1)) {
+            continue;
+        }
+        wrapped = WrapLines(kLines, static_cast<std::size_t>(kColumns));
+        if (wrapped.size() <= static_cast<std::size_t>(kRows)) {
+            break;
+        }
+    }
+    if (scale == 0) {
+        return RetCode::kInsufficientCapacity;
+    }
+
+    boost::gil::rgb8_image_t text_img(cover_img.width(), cover_img.height(),
+                                      boost::gil::rgb8_pixel_t(255, 255, 255));
+    auto text_view = boost::gil::view(text_img);
+    for (std::size_t row = 0; row < wrapped.size(); ++row) {
+        const std::ptrdiff_t kTop =
+            (static_cast<std::ptrdiff_t>(row) + 1) * kLineHeight * scale;
+        for (std::size_t col = 0; col < wrapped[row].size(); ++col) {
+            const std::ptrdiff_t kLeft =
+                (static_cast<std::ptrdiff_t>(col) + 1) * kGlyphSize * scale;
+            DrawGlyph(text_view, wrapped[row][col], kLeft, kTop, scale);
+        }
+    }
+
+    /* merged straight from memory, the rendered text never touches the disk */
+    MergeReport report;
+    return Merge(cover, text_img, outfile, config, report);
+}
+
+}  // namespace steganography
@@ -0,0 +1,180 @@
+#include "utils/tiff.hpp"
+
+#include <lcms2.h>
+#include <tiffio.h>
+
+#include <algorithm>
+#include <boost/gil.hpp>
+#include <cstdint>
+#include <memory>
+#include <string>
+#include <vector>
+
+namespace steganography {
+
+struct TiffCloser {
+    void operator()(TIFF* tiff) const { TIFFClose(tiff); }
+};
+
+struct TransformDeleter {
+    void operator()(void* transform) const { cmsDeleteTransform(transform); }
+};
+
+using TiffPtr = std::unique_ptr<TIFF, TiffCloser>;
+using TransformPtr = std::unique_ptr<void, TransformDeleter>;
+
+static TiffPtr OpenTiff(const std::string& filename) {
+    /* libtiff prints every problem to stderr unless told otherwise, errors
+     * reach the caller through the return values instead */
+    TIFFSetErrorHandler(nullptr);
+    TIFFSetWarningHandler(nullptr);
+    return TiffPtr(TIFFOpen(filename.c_str(), "r"));
+}
+
+/* 8 bit CMYK with the samples of each pixel stored together in strips,
+ * anything else goes through libtiff's own conversion to RGBA */
+static bool IsCmyk(TIFF* tiff) {
+    uint16_t photometric = 0;
+    uint16_t inkset = INKSET_CMYK;
+    uint16_t samples = 0;
+    uint16_t bits = 0;
+    uint16_t planar = PLANARCONFIG_CONTIG;
+    TIFFGetField(tiff, TIFFTAG_PHOTOMETRIC, &photometric);
+    TIFFGetFieldDefaulted(tiff, TIFFTAG_INKSET, &inkset);
+    TIFFGetFieldDefaulted(tiff, TIFFTAG_SAMPLESPERPIXEL, &samples);
+    TIFFGetFieldDefaulted(tiff, TIFFTAG_BITSPERSAMPLE, &bits);
+    TIFFGetFieldDefaulted(tiff, TIFFTAG_PLANARCONFIG, &planar);
+    return (photometric == PHOTOMETRIC_SEPARATED) &&
+           (inkset == INKSET_CMYK) && (samples >= 4) && (bits == 8) &&
+           (planar == PLANARCONFIG_CONTIG) && !TIFFIsTiled(tiff);
+}
+
+/* a transform from the embedded CMYK profile to sRGB, null when the file
+ * carries no usable profile */
+static TransformPtr CmykTransform(TIFF* tiff) {
+    uint32_t size = 0;
+    void* data = nullptr;
+    if (!TIFFGetField(tiff, TIFFTAG_ICCPROFILE, &size, &data)) {
+        return nullptr;
+    }
+    cmsHPROFILE cmyk = cmsOpenProfileFromMem(data, size);
+    if (cmyk == nullptr) {
+        return nullptr;
+    }
+    TransformPtr transform;
+    if (cmsGetColorSpace(cmyk) == cmsSigCmykData) {
+        cmsHPROFILE srgb = cmsCreate_sRGBProfile();
+        transform.reset(cmsCreateTransform(cmyk, TYPE_CMYK_8, srgb,
+                                           TYPE_RGB_8, INTENT_PERCEPTUAL, 0));
+        cmsCloseProfile(srgb);
+    }
+    cmsCloseProfile(cmyk);
+    return transform;
+}
+
+static bool ReadCmyk(TIFF* tiff, boost::gil::rgb8_image_t& image) {
+    uint32_t width = 0;
+    uint32_t height = 0;
+    uint16_t samples = 0;
+    TIFFGetField(tiff, TIFFTAG_IMAGEWIDTH, &width);
+    TIFFGetField(tiff, TIFFTAG_IMAGELENGTH, &height);
+    TIFFGetFieldDefaulted(tiff, TIFFTAG_SAMPLESPERPIXEL, &samples);
+
+    TransformPtr transform = CmykTransform(tiff);
+    std::vector<uint8_t> scanline(static_cast<std::size_t>(
+        std::max<tmsize_t>(TIFFScanlineSize(tiff), 0)));
+    if (scanline.size() < static_cast<std::size_t>(width) * samples) {
+        return false;
+    }
+    std::vector<uint8_t> cmyk(static_cast<std::size_t>(width) * 4, 0);
+    std::vector<uint8_t> rgb(static_cast<std::size_t>(width) * 3, 0);
+    image = boost::gil::rgb8_image_t(width, height);
+    auto view = boost::gil::view(image);
+    for (uint32_t y = 0; y < height; ++y) {
+        if (TIFFReadScanline(tiff, scanline.data(), y, 0) < 0) {
+            return false;
+        }
+        for (std::size_t x = 0; x < width; ++x) {
+            std::copy_n(scanline.cbegin() + x * samples, 4,
+                        cmyk.begin() + x * 4);
+        }
+        if (transform) {
+            cmsDoTransform(transform.get(), cmyk.data(), rgb.data(), width);
+        } else {
+            /* without a profile fall back to the naive conversion every
+             * viewer uses for untagged CMYK */
+            for (std::size_t i = 0; i < width; ++i) {
+                const int kBlack = 255 - cmyk[i * 4 + 3];
+                for (std::size_t c = 0; c < 3; ++c) {
+                    rgb[i * 3 + c] = static_cast<uint8_t>(
+                        (255 - cmyk[i * 4 + c]) * kBlack / 255);
+                }
+            }
+        }
+        for (std::size_t x = 0; x < width; ++x) {
+            view(x, y) = boost::gil::rgb8_pixel_t(rgb[x * 3], rgb[x * 3 + 1],
+                                                  rgb[x * 3 + 2]);
+        }
+    }
+    return true;
+}
+
+static bool ReadRgba(TIFF* tiff, boost::gil::rgb8_image_t& image) {
+    uint32_t width = 0;
+    uint32_t height = 0;
+    TIFFGetField(tiff, TIFFTAG_IMAGEWIDTH, &width);
+    TIFFGetField(tiff, TIFFTAG_IMAGELENGTH, &height);
+    std::vector<uint32_t> raster(static_cast<std::size_t>(width) * height);
+    if (!TIFFReadRGBAImageOriented(tiff, width, height, raster.data(),
+                                   ORIENTATION_TOPLEFT, 0)) {
+        return false;
+    }
+    image = boost::gil::rgb8_image_t(width, height);
+    auto view = boost::gil::view(image);
+    for (uint32_t y = 0; y < height; ++y) {
+        for (uint32_t x = 0; x < width; ++x) {
+            uint32_t pixel = raster[static_cast<std::size_t>(y) * width + x];
+            view(x, y) = boost::gil::rgb8_pixel_t(
+                static_cast<uint8_t>(TIFFGetR(pixel)),
+                static_cast<uint8_t>(TIFFGetG(pixel)),
+                static_cast<uint8_t>(TIFFGetB(pixel)));
+        }
+    }
+    return true;
+}
+
+bool ReadTiff(const std::string& filename, boost::gil::rgb8_image_t& image) {
+    TiffPtr tiff = OpenTiff(filename);
+    if (!tiff) {
+        return false;
+    }
+    return IsCmyk(tiff.get()) ? ReadCmyk(tiff.get(), image)
+                              : ReadRgba(tiff.get(), image);
+}
+
+bool ReadTiffHeader(const std::string& filename, uint64_t& width,
+                    uint64_t& height, uint64_t& pixel_bytes) {
+    TiffPtr tiff = OpenTiff(filename);
+    if (!tiff) {
+        return false;
+    }
+    uint32_t tiff_width = 0;
+    uint32_t tiff_height = 0;
+    uint16_t samples = 0;
+    uint16_t bits = 0;
+    if (!TIFFGetField(tiff.get(), TIFFTAG_IMAGEWIDTH, &tiff_width) ||
+        !TIFFGetField(tiff.get(), TIFFTAG_IMAGELENGTH, &tiff_height)) {
+        return false;
+    }
+    TIFFGetFieldDefaulted(tiff.get(), TIFFTAG_SAMPLESPERPIXEL, &samples);
+    TIFFGetFieldDefaulted(tiff.get(), TIFFTAG_BITSPERSAMPLE, &bits);
+    width = tiff_width;
+    height = tiff_height;
+
+    /* libtiff's own conversion decodes into 4 bytes per pixel */
+    pixel_bytes = std::max<uint64_t>(
+        static_cast<uint64_t>(samples) * ((bits + 7U) / 8U), 4);
+    return true;
+}
+
+}  // namespace steganography
@@ -0,0 +1,75 @@
+#include "utils/trailer.hpp"
+
+#include <cstddef>
+#include <cstdint>
+#include <filesystem>
+#include <fstream>
+#include <iterator>
+#include <string>
+#include <vector>
+
+#include "utils/bytes.hpp"
+
+namespace steganography {
+
+static const uint32_t kTrailerMagic = 0x5354524C; /* "STRL" */
+static const std::size_t kTrailerFooterSize = 8;
+
+static RetCode ReadFile(const std::string& filename,
+                        std::vector<uint8_t>& data) {
+    std::ifstream ifs(filename, std::ifstream::binary);
+    if (!ifs.is_open()) {
+        return std::filesystem::exists(filename) ? RetCode::kIoError
+                                                 : RetCode::kFileNotFound;
+    }
+    data.assign(std::istreambuf_iterator<char>(ifs),
+                std::istreambuf_iterator<char>());
+    return RetCode::kSuccess;
+}
+
+/* where the trailer starts, the file's size when it has none */
+static std::size_t TrailerStart(const std::vector<uint8_t>& file) {
+    if ((file.size() < kTrailerFooterSize) ||
+        (ReadInt<uint32_t>(file, file.size() - 4) != kTrailerMagic)) {
+        return file.size();
+    }
+    const std::size_t kLength = ReadInt<uint32_t>(file, file.size() - 8);
+    if (kLength > file.size() - kTrailerFooterSize) {
+        return file.size();
+    }
+    return file.size() - kTrailerFooterSize - kLength;
+}
+
+RetCode ReadTrailer(const std::string& filename, std::vector<uint8_t>& data) {
+    std::vector<uint8_t> file;
+    RetCode rc = ReadFile(filename, file);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    const std::size_t kStart = TrailerStart(file);
+    if (kStart == file.size()) {
+        return RetCode::kPayloadNotFound;
+    }
+    data.assign(file.cbegin() + static_cast<std::ptrdiff_t>(kStart),
+                file.cend() - kTrailerFooterSize);
+    return RetCode::kSuccess;
+}
+
+RetCode WriteTrailer(const std::string& filename,
+                     const std::vector<uint8_t>& data) {
+    std::vector<uint8_t> file;
+    RetCode rc = ReadFile(filename, file);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    file.resize(TrailerStart(file));
+    file.insert(file.end(), data.cbegin(), data.cend());
+    AppendInt<uint32_t>(file, static_cast<uint32_t>(data.size()));
+    AppendInt<uint32_t>(file, kTrailerMagic);
+    std::ofstream ofs(filename, std::ofstream::binary);
+    ofs.write(reinterpret_cast<const char*>(file.data()),
+              static_cast<std::streamsize>(file.size()));
+    return ofs ? RetCode::kSuccess : RetCode::kIoError;
+}
+
+}  // namespace steganography
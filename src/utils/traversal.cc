@@ -0,0 +1,211 @@
+#include "utils/traversal.hpp"
+
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <numeric>
+#include <optional>
+#include <utility>
+#include <vector>
+
+#include "core/carrier.hpp"
+#include "core/frame.hpp"
+#include "utils/payload.hpp"
+#ifdef STEG_WITH_SALIENCY
+#include "utils/saliency.hpp"
+#endif
+
+namespace steganography {
+
+/* the pixels in the order itself, before any stride */
+static std::vector<std::size_t> OrderPixels(std::size_t width,
+                                            std::size_t height,
+                                            TraversalOrder order) {
+    std::vector<std::size_t> pixels;
+    pixels.reserve(width * height);
+    switch (order) {
+        case TraversalOrder::kRowMajor:
+            for (std::size_t pixel = 0; pixel < width * height; ++pixel) {
+                pixels.push_back(pixel);
+            }
+            break;
+        case TraversalOrder::kSerpentine:
+            for (std::size_t y = 0; y < height; ++y) {
+                for (std::size_t x = 0; x < width; ++x) {
+                    pixels.push_back(y * width +
+                                     ((y % 2 == 0) ? x : width - 1 - x));
+                }
+            }
+            break;
+        case TraversalOrder::kColumnMajor:
+            for (std::size_t x = 0; x < width; ++x) {
+                for (std::size_t y = 0; y < height; ++y) {
+                    pixels.push_back(y * width + x);
+                }
+            }
+            break;
+        case TraversalOrder::kSpiral: {
+            /* peels one ring at a time, the top row, the right column, then
+             * the bottom row and left column backwards when there are any */
+            std::size_t top = 0;
+            std::size_t left = 0;
+            std::size_t bottom = height;
+            std::size_t right = width;
+            while ((top < bottom) && (left < right)) {
+                for (std::size_t x = left; x < right; ++x) {
+                    pixels.push_back(top * width + x);
+                }
+                for (std::size_t y = top + 1; y < bottom; ++y) {
+                    pixels.push_back(y * width + right - 1);
+                }
+                if (bottom - top > 1) {
+                    for (std::size_t x = right - 1; x-- > left;) {
+                        pixels.push_back((bottom - 1) * width + x);
+                    }
+                }
+                if ((right - left > 1) && (bottom - top > 1)) {
+                    for (std::size_t y = bottom - 1; --y > top;) {
+                        pixels.push_back(y * width + left);
+                    }
+                }
+                ++top;
+                ++left;
+                --bottom;
+                --right;
+            }
+            break;
+        }
+    }
+    return pixels;
+}
+
+/* whether the pixel lies in the frame border pixels wide around the image */
+static bool InBorder(std::size_t pixel, std::size_t width, std::size_t height,
+                     std::size_t border) {
+    const std::size_t kX = pixel % width;
+    const std::size_t kY = pixel / width;
+    return (kX < border) || (kY < border) || (width - kX <= border) ||
+           (height - kY <= border);
+}
+
+bool IsRowMajor(const Traversal& traversal) {
+    return (traversal.order == TraversalOrder::kRowMajor) &&
+           (traversal.stride == 1) && (traversal.border == 0) &&
+           !traversal.avoid_salient;
+}
+
+RetCode TraversalPixels(std::size_t width, std::size_t height,
+                        const Traversal& traversal,
+                        std::vector<std::size_t>& order,
+                        const std::vector<bool>& avoid) {
+    if (traversal.stride == 0) {
+        return RetCode::kInvalidArgument;
+    }
+    std::vector<std::size_t> pixels =
+        OrderPixels(width, height, traversal.order);
+    if (traversal.border > 0) {
+        std::erase_if(pixels, [&](std::size_t pixel) {
+            return !InBorder(pixel, width, height, traversal.border);
+        });
+    }
+    if (!avoid.empty()) {
+        std::erase_if(pixels, [&avoid](std::size_t pixel) {
+            return avoid[pixel];
+        });
+    }
+    const std::size_t kPixels = pixels.size();
+    if ((kPixels > 0) && (std::gcd(traversal.stride, kPixels) != 1)) {
+        return RetCode::kInvalidArgument;
+    }
+    if (traversal.stride == 1) {
+        order = std::move(pixels);
+        return RetCode::kSuccess;
+    }
+    order.resize(kPixels);
+    std::size_t position = 0;
+    for (std::size_t i = 0; i < kPixels; ++i) {
+        order[i] = pixels[position];
+        position = (position + traversal.stride) % kPixels;
+    }
+    return RetCode::kSuccess;
+}
+
+/* the pixels of a view as an ordered carrier, rows follow each other at the
+ * distance between the first two */
+template <typename View>
+static RetCode ViewCarrier(const View& view, const Traversal& traversal,
+                           const std::vector<bool>& avoid,
+                           std::optional<OrderedCarrier>& carrier) {
+    const std::size_t kWidth = static_cast<std::size_t>(view.width());
+    const std::size_t kHeight = static_cast<std::size_t>(view.height());
+    std::vector<std::size_t> order;
+    RetCode rc = TraversalPixels(kWidth, kHeight, traversal, order, avoid);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    if ((kWidth == 0) || (kHeight == 0)) {
+        carrier.emplace(static_cast<uint8_t*>(nullptr), 0, 0,
+                        std::vector<std::size_t>{});
+        return RetCode::kSuccess;
+    }
+    std::size_t stride = kWidth * 3;
+    if (kHeight > 1) {
+        stride = static_cast<std::size_t>(&view(0, 1)[0] - &view(0, 0)[0]);
+    }
+    carrier.emplace(&view(0, 0)[0], kWidth, stride, std::move(order));
+    return RetCode::kSuccess;
+}
+
+/* the salient regions to leave out of the image, none unless asked for */
+static RetCode TraversalAvoidance(const boost::gil::rgb8_image_t& image,
+                                  const Traversal& traversal,
+                                  std::vector<bool>& avoid) {
+    avoid.clear();
+    if (!traversal.avoid_salient) {
+        return RetCode::kSuccess;
+    }
+#ifdef STEG_WITH_SALIENCY
+    return AvoidanceMask(image, avoid);
+#else
+    (void)image;
+    return RetCode::kUnsupportedFormat;
+#endif
+}
+
+RetCode EmbedTraversal(boost::gil::rgb8_image_t& image,
+                       const std::vector<uint8_t>& payload,
+                       const Traversal& traversal,
+                       const EmbedConfig& config) {
+    std::vector<bool> avoid;
+    RetCode rc = TraversalAvoidance(image, traversal, avoid);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    std::optional<OrderedCarrier> carrier;
+    rc = ViewCarrier(boost::gil::view(image), traversal, avoid, carrier);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    return EmbedPayload(*carrier, payload, config);
+}
+
+RetCode ExtractTraversal(const boost::gil::rgb8_image_t& image,
+                         const Traversal& traversal,
+                         std::vector<uint8_t>& payload, PayloadHeader& header,
+                         DamageReport& damage,
+                         const std::optional<StegKey>& key) {
+    std::vector<bool> avoid;
+    RetCode rc = TraversalAvoidance(image, traversal, avoid);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    std::optional<OrderedCarrier> carrier;
+    rc = ViewCarrier(boost::gil::const_view(image), traversal, avoid,
+                     carrier);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    return ExtractPayload(*carrier, payload, header, damage, key);
+}
+
+}  // namespace steganography
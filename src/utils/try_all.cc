@@ -0,0 +1,193 @@
+#include "utils/try_all.hpp"
+
+#include <algorithm>
+#include <boost/gil.hpp>
+#include <boost/gil/extension/numeric/resample.hpp>
+#include <boost/gil/extension/numeric/sampler.hpp>
+#include <cmath>
+#include <cstddef>
+#include <filesystem>
+#include <memory>
+#include <string>
+#include <system_error>
+#include <vector>
+
+#include "core/lsb.hpp"
+#include "utils/embed_method.hpp"
+#include "utils/image_io.hpp"
+
+namespace steganography {
+
+static const char* kContactSheetName = "contact-sheet.png";
+static const ColorSpace kColorSpaces[] = {ColorSpace::kRgb, ColorSpace::kYCbCr,
+                                          ColorSpace::kHsv};
+
+static const char* ColorSpaceName(ColorSpace space) {
+    switch (space) {
+        case ColorSpace::kYCbCr:
+            return "ycbcr";
+        case ColorSpace::kHsv:
+            return "hsv";
+        default:
+            return "rgb";
+    }
+}
+
+std::vector<std::string> ContactSheetRows() {
+    std::vector<std::string> rows;
+    for (const std::string& method : EmbedMethodNames()) {
+        for (ColorSpace space : kColorSpaces) {
+            rows.push_back(method + " " + ColorSpaceName(space));
+        }
+    }
+    return rows;
+}
+
+/* scales the image down to fit a size x size square keeping its aspect
+ * ratio, never up */
+static boost::gil::rgb8_image_t Thumbnail(const boost::gil::rgb8c_view_t& view,
+                                          std::ptrdiff_t size) {
+    double scale =
+        std::min({1.0,
+                  static_cast<double>(size) / static_cast<double>(view.width()),
+                  static_cast<double>(size) /
+                      static_cast<double>(view.height())});
+    std::ptrdiff_t width = std::max<std::ptrdiff_t>(
+        1, std::lround(static_cast<double>(view.width()) * scale));
+    std::ptrdiff_t height = std::max<std::ptrdiff_t>(
+        1, std::lround(static_cast<double>(view.height()) * scale));
+    boost::gil::rgb8_image_t scaled(width, height);
+    boost::gil::resize_view(view, boost::gil::view(scaled),
+                            boost::gil::bilinear_sampler());
+    return scaled;
+}
+
+RetCode RenderContactSheet(
+    const std::vector<TryAllCandidate>& candidates,
+    const std::vector<boost::gil::rgb8_image_t>& extracted,
+    const TryAllConfig& config, boost::gil::rgb8_image_t& sheet) {
+    const std::ptrdiff_t kColumns = kMaxBits - kMinBits + 1;
+    if ((config.thumbnail_size < 1) || (config.gap < 0) ||
+        candidates.empty() || (candidates.size() % kColumns != 0) ||
+        (extracted.size() != candidates.size())) {
+        return RetCode::kInvalidArgument;
+    }
+
+    const std::ptrdiff_t kRows =
+        static_cast<std::ptrdiff_t>(candidates.size()) / kColumns;
+    const std::ptrdiff_t kCell = config.thumbnail_size;
+    const std::ptrdiff_t kPitch = kCell + config.gap;
+    sheet = boost::gil::rgb8_image_t(kColumns * kPitch - config.gap,
+                                     kRows * kPitch - config.gap);
+    auto dst = boost::gil::view(sheet);
+    boost::gil::fill_pixels(dst, boost::gil::rgb8_pixel_t(255, 255, 255));
+
+    for (std::size_t i = 0; i < candidates.size(); ++i) {
+        const std::ptrdiff_t kLeft =
+            static_cast<std::ptrdiff_t>(i) % kColumns * kPitch;
+        const std::ptrdiff_t kTop =
+            static_cast<std::ptrdiff_t>(i) / kColumns * kPitch;
+        if (candidates[i].status != RetCode::kSuccess) {
+            for (std::ptrdiff_t y = 0; y < kCell; ++y) {
+                for (std::ptrdiff_t x = 0; x < kCell; ++x) {
+                    dst(kLeft + x, kTop + y) =
+                        boost::gil::rgb8_pixel_t(128, 128, 128);
+                }
+            }
+            continue;
+        }
+
+        /* center the thumbnail in its cell */
+        boost::gil::rgb8_image_t thumb =
+            Thumbnail(boost::gil::const_view(extracted[i]), kCell);
+        auto src = boost::gil::const_view(thumb);
+        const std::ptrdiff_t kOffsetX = kLeft + (kCell - thumb.width()) / 2;
+        const std::ptrdiff_t kOffsetY = kTop + (kCell - thumb.height()) / 2;
+        for (std::ptrdiff_t y = 0; y < thumb.height(); ++y) {
+            for (std::ptrdiff_t x = 0; x < thumb.width(); ++x) {
+                dst(kOffsetX + x, kOffsetY + y) = src(x, y);
+            }
+        }
+    }
+    return RetCode::kSuccess;
+}
+
+RetCode TryAll(const std::string& image, const std::string& outdir,
+               const TryAllConfig& config,
+               std::vector<TryAllCandidate>& candidates) {
+    if ((config.thumbnail_size < 1) || (config.gap < 0)) {
+        return RetCode::kInvalidArgument;
+    }
+    boost::gil::rgb8_image_t merged;
+    RetCode rc = LoadImage(image, merged);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    std::error_code ec;
+    std::filesystem::create_directories(outdir, ec);
+    if (ec) {
+        return RetCode::kIoError;
+    }
+
+    /* decode the image once and run every extraction over it */
+    candidates.clear();
+    std::vector<boost::gil::rgb8_image_t> extracted;
+    RetCode default_rc = RetCode::kSuccess;
+    bool any = false;
+    for (const std::string& name : EmbedMethodNames()) {
+        std::shared_ptr<const EmbedMethod> method = FindEmbedMethod(name);
+        for (ColorSpace space : kColorSpaces) {
+            for (int bits = kMinBits; bits <= kMaxBits; ++bits) {
+                UnmergeConfig unmerge = config.unmerge;
+                unmerge.method = name;
+                unmerge.color_space = space;
+                unmerge.bits = bits;
+
+                TryAllCandidate candidate{name, space, bits,
+                                          RetCode::kSuccess, ""};
+                boost::gil::rgb8_image_t secret;
+                candidate.status = method->Extract(
+                    boost::gil::const_view(merged), unmerge, secret);
+                if (candidate.status == RetCode::kSuccess) {
+                    candidate.file = name + "-" + ColorSpaceName(space) + "-" +
+                                     std::to_string(bits) + ".png";
+                    WriteImage(secret,
+                               (std::filesystem::path(outdir) / candidate.file)
+                                   .string(),
+                               ImageType::kPng);
+                    any = true;
+                } else if ((name == "lsb") && (space == ColorSpace::kRgb) &&
+                           (bits == kDefaultBits)) {
+                    default_rc = candidate.status;
+                }
+                candidates.push_back(candidate);
+
+                /* keep only the thumbnail, the full images could add up to
+                 * more than a hundred times the size of the input */
+                extracted.push_back(
+                    (candidate.status == RetCode::kSuccess)
+                        ? Thumbnail(boost::gil::const_view(secret),
+                                    config.thumbnail_size)
+                        : boost::gil::rgb8_image_t());
+            }
+        }
+    }
+
+    /* with nothing to show, fail as a plain unmerge would have */
+    if (!any) {
+        return (default_rc != RetCode::kSuccess)
+                   ? default_rc
+                   : RetCode::kInvalidFileFormat;
+    }
+    boost::gil::rgb8_image_t sheet;
+    rc = RenderContactSheet(candidates, extracted, config, sheet);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    WriteImage(sheet,
+               (std::filesystem::path(outdir) / kContactSheetName).string(),
+               ImageType::kPng);
+    return RetCode::kSuccess;
+}
+
+}  // namespace steganography
@@ -0,0 +1,623 @@
+#include "utils/tui.hpp"
+
+#include <poll.h>
+#include <sys/ioctl.h>
+#include <termios.h>
+#include <unistd.h>
+
+#include <algorithm>
+#include <boost/gil.hpp>
+#include <cmath>
+#include <cstddef>
+#include <exception>
+#include <filesystem>
+#include <iomanip>
+#include <map>
+#include <optional>
+#include <sstream>
+#include <string>
+#include <system_error>
+#include <vector>
+
+#include "utils/image_io.hpp"
+#include "utils/lsb.hpp"
+#include "utils/metrics.hpp"
+#include "utils/preview.hpp"
+
+namespace steganography {
+
+static const int kEscapeTimeoutMs = 30; /* to tell ESC from an arrow key */
+static const int kResizePollMs = 250;
+static const int kFormRows = 14;        /* title, fields, readout, and help */
+
+enum class KeyType {
+    kNone, /* nothing pressed before the poll timed out */
+    kUp,
+    kDown,
+    kLeft,
+    kRight,
+    kEnter,
+    kBackspace,
+    kEscape,
+    kChar,
+};
+
+struct Key {
+    KeyType type = KeyType::kNone;
+    char ch = 0;
+};
+
+enum class Field {
+    kMode,
+    kInput, /* the cover when merging, the merged image when unmerging */
+    kSecret,
+    kOutput,
+    kBits,
+    kRun,
+};
+
+/* switches the terminal to raw mode on the alternate screen and restores
+ * it on destruction, even when the UI exits through an exception */
+class RawTerminal {
+   public:
+    RawTerminal() {
+        if (tcgetattr(STDIN_FILENO, &saved_) != 0) {
+            return;
+        }
+        termios raw = saved_;
+        raw.c_lflag &= ~static_cast<tcflag_t>(ECHO | ICANON | ISIG | IEXTEN);
+        raw.c_iflag &= ~static_cast<tcflag_t>(IXON | ICRNL);
+        raw.c_cc[VMIN] = 1;
+        raw.c_cc[VTIME] = 0;
+        if (tcsetattr(STDIN_FILENO, TCSAFLUSH, &raw) != 0) {
+            return;
+        }
+        active_ = true;
+        Write("\x1b[?1049h\x1b[?25l");
+    }
+    ~RawTerminal() {
+        if (active_) {
+            Write("\x1b[?25h\x1b[?1049l");
+            tcsetattr(STDIN_FILENO, TCSAFLUSH, &saved_);
+        }
+    }
+    RawTerminal(const RawTerminal&) = delete;
+    RawTerminal& operator=(const RawTerminal&) = delete;
+
+    bool Active() const { return active_; }
+
+    static void Write(const std::string& str) {
+        std::size_t written = 0;
+        while (written < str.size()) {
+            ssize_t len =
+                write(STDOUT_FILENO, str.data() + written, str.size() - written);
+            if (len <= 0) {
+                return;
+            }
+            written += static_cast<std::size_t>(len);
+        }
+    }
+
+    static std::pair<int, int> Size() {
+        winsize size{};
+        if ((ioctl(STDOUT_FILENO, TIOCGWINSZ, &size) != 0) ||
+            (size.ws_col == 0)) {
+            return {80, 24};
+        }
+        return {size.ws_col, size.ws_row};
+    }
+
+    /* waits up to timeout_ms for a key, arrow keys arrive as escape
+     * sequences */
+    static Key Read(int timeout_ms) {
+        pollfd pfd = {STDIN_FILENO, POLLIN, 0};
+        char c = 0;
+        if ((poll(&pfd, 1, timeout_ms) <= 0) ||
+            (read(STDIN_FILENO, &c, 1) != 1)) {
+            return {};
+        }
+        switch (c) {
+            case '\r':
+            case '\n':
+                return {KeyType::kEnter, c};
+            case 127:
+            case '\b':
+                return {KeyType::kBackspace, c};
+            case 3: /* ctrl-c */
+                return {KeyType::kEscape, c};
+            case '\x1b':
+                break;
+            default:
+                return {KeyType::kChar, c};
+        }
+
+        char seq[2] = {0, 0};
+        if ((poll(&pfd, 1, kEscapeTimeoutMs) <= 0) ||
+            (read(STDIN_FILENO, &seq[0], 1) != 1) || (seq[0] != '[') ||
+            (read(STDIN_FILENO, &seq[1], 1) != 1)) {
+            return {KeyType::kEscape, c};
+        }
+        switch (seq[1]) {
+            case 'A':
+                return {KeyType::kUp, 0};
+            case 'B':
+                return {KeyType::kDown, 0};
+            case 'C':
+                return {KeyType::kRight, 0};
+            case 'D':
+                return {KeyType::kLeft, 0};
+            default:
+                return {};
+        }
+    }
+
+   private:
+    termios saved_{};
+    bool active_ = false;
+};
+
+/* the live readout, recomputed whenever a path or the bit depth changes */
+struct Readout {
+    std::string error;                 /* why nothing can be shown */
+    std::optional<double> cover_psnr;  /* stego image vs. cover */
+    std::optional<double> secret_psnr; /* recovered vs. original secret */
+    boost::gil::rgb8_image_t stego;
+    boost::gil::rgb8_image_t recovered;
+};
+
+class Tui {
+   public:
+    explicit Tui(const TuiConfig& config) : config_(config) {
+        paths_[Field::kInput] = config.cover;
+        paths_[Field::kSecret] = config.secret;
+        paths_[Field::kOutput] = config.output;
+        Recompute();
+    }
+
+    void Run() {
+        std::pair<int, int> size = RawTerminal::Size();
+        Draw();
+        while (true) {
+            Key key = RawTerminal::Read(kResizePollMs);
+            if (key.type == KeyType::kNone) {
+                if (RawTerminal::Size() != size) {
+                    size = RawTerminal::Size();
+                    Draw();
+                }
+                continue;
+            }
+            if ((key.type == KeyType::kEscape) ||
+                ((key.type == KeyType::kChar) && (key.ch == 'q'))) {
+                return;
+            }
+            HandleKey(key);
+            Draw();
+        }
+    }
+
+   private:
+    bool Merging() const { return merge_; }
+
+    std::vector<Field> Fields() const {
+        if (Merging()) {
+            return {Field::kMode,   Field::kInput, Field::kSecret,
+                    Field::kOutput, Field::kBits,  Field::kRun};
+        }
+        return {Field::kMode, Field::kInput, Field::kOutput, Field::kBits,
+                Field::kRun};
+    }
+
+    Field Focused() const {
+        std::vector<Field> fields = Fields();
+        return fields[std::min(focus_, fields.size() - 1)];
+    }
+
+    void HandleKey(const Key& key) {
+        std::vector<Field> fields = Fields();
+        Field field = Focused();
+        switch (key.type) {
+            case KeyType::kUp:
+                focus_ = (focus_ + fields.size() - 1) % fields.size();
+                break;
+            case KeyType::kDown:
+                focus_ = (focus_ + 1) % fields.size();
+                break;
+            case KeyType::kLeft:
+            case KeyType::kRight:
+                if (field == Field::kMode) {
+                    merge_ = !merge_;
+                    focus_ = 0;
+                    Recompute();
+                } else if (field == Field::kBits) {
+                    int delta = (key.type == KeyType::kLeft) ? -1 : 1;
+                    bits_ = std::clamp(bits_ + delta, kMinBits, kMaxBits);
+                    Recompute();
+                }
+                break;
+            case KeyType::kEnter:
+                if (field == Field::kMode) {
+                    merge_ = !merge_;
+                    focus_ = 0;
+                    Recompute();
+                } else if (field == Field::kRun) {
+                    Execute();
+                } else if (field == Field::kOutput) {
+                    Edit(field);
+                } else if (field != Field::kBits) {
+                    Pick(field);
+                }
+                break;
+            case KeyType::kChar:
+                if (key.ch == 'r') {
+                    Execute();
+                } else if ((key.ch == 'e') && paths_.contains(field)) {
+                    Edit(field);
+                } else if ((key.ch >= '1') && (key.ch <= '8')) {
+                    bits_ = key.ch - '0';
+                    Recompute();
+                }
+                break;
+            default:
+                break;
+        }
+    }
+
+    /* loads an image once per path, errors are kept for the readout */
+    const boost::gil::rgb8_image_t* Image(const std::string& path,
+                                          std::string& error) {
+        auto cached = images_.find(path);
+        if (cached == images_.end()) {
+            boost::gil::rgb8_image_t img;
+            RetCode rc = RetCode::kFileNotFound;
+            try {
+                rc = LoadImage(path, img);
+            } catch (const std::exception&) {
+                rc = RetCode::kInvalidFileFormat;
+            }
+            if (rc != RetCode::kSuccess) {
+                error = path + ": " + Describe(rc);
+                return nullptr;
+            }
+            cached = images_.emplace(path, std::move(img)).first;
+        }
+        return &cached->second;
+    }
+
+    void Recompute() {
+        readout_ = Readout{};
+        const std::string& input = paths_[Field::kInput];
+        if (input.empty()) {
+            readout_.error = Merging() ? "pick a cover image" : "pick an image";
+            return;
+        }
+        const boost::gil::rgb8_image_t* input_img =
+            Image(input, readout_.error);
+        if (input_img == nullptr) {
+            return;
+        }
+        auto input_view = boost::gil::const_view(*input_img);
+
+        if (!Merging()) {
+            readout_.recovered = boost::gil::rgb8_image_t(input_view.width(),
+                                                          input_view.height());
+            auto recovered = boost::gil::view(readout_.recovered);
+            for (std::ptrdiff_t row = 0; row < input_view.height(); ++row) {
+                UnmergeRow(&input_view(0, row)[0], &recovered(0, row)[0],
+                           static_cast<std::size_t>(input_view.width()) * 3,
+                           bits_);
+            }
+            return;
+        }
+
+        const std::string& secret = paths_[Field::kSecret];
+        if (secret.empty()) {
+            readout_.error = "pick a secret image";
+            return;
+        }
+        const boost::gil::rgb8_image_t* secret_img =
+            Image(secret, readout_.error);
+        if (secret_img == nullptr) {
+            return;
+        }
+        auto secret_view = boost::gil::const_view(*secret_img);
+        if ((secret_view.width() > input_view.width()) ||
+            (secret_view.height() > input_view.height())) {
+            readout_.error = Describe(RetCode::kInvalidDimensions);
+            return;
+        }
+
+        /* the same pixel mapping merge and unmerge use without a key */
+        readout_.stego = *input_img;
+        readout_.recovered = boost::gil::rgb8_image_t(secret_view.width(),
+                                                      secret_view.height());
+        auto stego = boost::gil::view(readout_.stego);
+        auto recovered = boost::gil::view(readout_.recovered);
+        const boost::gil::rgb8_pixel_t kBlackPixel(0, 0, 0);
+        for (std::ptrdiff_t row = 0; row < stego.height(); ++row) {
+            for (std::ptrdiff_t col = 0; col < stego.width(); ++col) {
+                bool inside = (row < secret_view.height()) &&
+                              (col < secret_view.width());
+                stego(col, row) = MergePixels(
+                    stego(col, row),
+                    inside ? secret_view(col, row) : kBlackPixel, bits_);
+                if (inside) {
+                    recovered(col, row) = UnmergePixels(stego(col, row), bits_);
+                }
+            }
+        }
+        readout_.cover_psnr =
+            Psnr(input_view, boost::gil::const_view(readout_.stego));
+        readout_.secret_psnr =
+            Psnr(secret_view, boost::gil::const_view(readout_.recovered));
+    }
+
+    void Execute() {
+        const std::string& output = paths_[Field::kOutput];
+        if (output.empty()) {
+            status_ = "set an output path first";
+            return;
+        }
+        RetCode rc = RetCode::kSuccess;
+        try {
+            if (Merging()) {
+                MergeConfig merge;
+                merge.bits = bits_;
+                rc = Merge(paths_[Field::kInput], paths_[Field::kSecret],
+                           output, merge);
+            } else {
+                UnmergeConfig unmerge;
+                unmerge.bits = bits_;
+                rc = Unmerge(paths_[Field::kInput], output, unmerge);
+            }
+        } catch (const std::exception&) {
+            rc = RetCode::kIoError;
+        }
+        status_ = (rc == RetCode::kSuccess) ? "wrote " + output
+                                            : "error: " + Describe(rc);
+    }
+
+    std::string Describe(RetCode rc) const {
+        return config_.describe_error ? config_.describe_error(rc)
+                                      : "error " +
+                                            std::to_string(static_cast<int>(rc));
+    }
+
+    /* line editing in place of the field value, ESC keeps the old value */
+    void Edit(Field field) {
+        std::string buffer = paths_[field];
+        while (true) {
+            editing_ = buffer;
+            Draw();
+            Key key = RawTerminal::Read(-1);
+            if (key.type == KeyType::kEscape) {
+                break;
+            }
+            if (key.type == KeyType::kEnter) {
+                paths_[field] = buffer;
+                Recompute();
+                break;
+            }
+            if ((key.type == KeyType::kBackspace) && !buffer.empty()) {
+                buffer.pop_back();
+            } else if ((key.type == KeyType::kChar) &&
+                       (static_cast<unsigned char>(key.ch) >= ' ')) {
+                buffer += key.ch;
+            }
+        }
+        editing_.reset();
+    }
+
+    /* browses directories starting next to the current value */
+    void Pick(Field field) {
+        std::error_code ec;
+        std::filesystem::path dir =
+            std::filesystem::path(paths_[field]).parent_path();
+        if (dir.empty() || !std::filesystem::is_directory(dir, ec)) {
+            dir = std::filesystem::current_path(ec);
+        }
+        std::size_t selected = 0;
+        while (true) {
+            std::vector<std::filesystem::path> entries = {dir / ".."};
+            std::vector<std::filesystem::path> files;
+            for (const auto& entry :
+                 std::filesystem::directory_iterator(dir, ec)) {
+                if (entry.path().filename().string().starts_with(".")) {
+                    continue;
+                }
+                if (entry.is_directory(ec)) {
+                    entries.push_back(entry.path());
+                } else if (GetImageType(entry.path().string()) !=
+                           ImageType::kUnknown) {
+                    files.push_back(entry.path());
+                }
+            }
+            std::sort(entries.begin() + 1, entries.end());
+            std::sort(files.begin(), files.end());
+            entries.insert(entries.end(), files.begin(), files.end());
+            selected = std::min(selected, entries.size() - 1);
+            DrawPicker(dir, entries, selected);
+
+            Key key = RawTerminal::Read(-1);
+            if (key.type == KeyType::kEscape) {
+                return;
+            } else if (key.type == KeyType::kUp) {
+                selected = (selected + entries.size() - 1) % entries.size();
+            } else if (key.type == KeyType::kDown) {
+                selected = (selected + 1) % entries.size();
+            } else if ((key.type == KeyType::kLeft) ||
+                       (key.type == KeyType::kBackspace)) {
+                dir = std::filesystem::weakly_canonical(dir / "..", ec);
+                selected = 0;
+            } else if ((key.type == KeyType::kEnter) ||
+                       (key.type == KeyType::kRight)) {
+                const std::filesystem::path& entry = entries[selected];
+                if (std::filesystem::is_directory(entry, ec)) {
+                    dir = std::filesystem::weakly_canonical(entry, ec);
+                    selected = 0;
+                } else if (key.type == KeyType::kEnter) {
+                    paths_[field] = entry.lexically_proximate(
+                                             std::filesystem::current_path(ec))
+                                        .string();
+                    Recompute();
+                    return;
+                }
+            }
+        }
+    }
+
+    void DrawPicker(const std::filesystem::path& dir,
+                    const std::vector<std::filesystem::path>& entries,
+                    std::size_t selected) const {
+        auto [cols, rows] = RawTerminal::Size();
+        std::size_t visible =
+            static_cast<std::size_t>(std::max(1, rows - 4));
+        std::size_t first = (selected >= visible) ? selected - visible + 1 : 0;
+
+        std::string screen = "\x1b[H\x1b[2J\x1b[1m " +
+                             Clip(dir.string(), cols - 2) + "\x1b[0m\r\n\r\n";
+        for (std::size_t i = first;
+             (i < entries.size()) && (i < first + visible); ++i) {
+            std::error_code ec;
+            std::string name = entries[i].filename().string();
+            if (std::filesystem::is_directory(entries[i], ec)) {
+                name += "/";
+            }
+            screen += (i == selected) ? "\x1b[7m> " : "  ";
+            screen += Clip(name, cols - 3) + "\x1b[0m\r\n";
+        }
+        screen += "\x1b[" + std::to_string(rows) + ";1H\x1b[2m" +
+                  Clip(" up/down move, enter select, left parent, esc cancel",
+                       cols) +
+                  "\x1b[0m";
+        RawTerminal::Write(screen);
+    }
+
+    static std::string Clip(const std::string& str, int width) {
+        if (width <= 0) {
+            return "";
+        }
+        std::size_t max = static_cast<std::size_t>(width);
+        return (str.size() <= max) ? str
+                                   : "..." + str.substr(str.size() - max + 3);
+    }
+
+    std::string FieldLine(Field field, int cols) const {
+        const std::map<Field, std::string> kLabels = {
+            {Field::kMode, "Mode"},
+            {Field::kInput, Merging() ? "Cover" : "Image"},
+            {Field::kSecret, "Secret"},
+            {Field::kOutput, "Output"},
+            {Field::kBits, "Bits"},
+            {Field::kRun, ""}};
+        bool focused = (field == Focused());
+        std::string value;
+        if (field == Field::kMode) {
+            value = Merging() ? "< merge >" : "< unmerge >";
+        } else if (field == Field::kBits) {
+            value = "< " + std::string(static_cast<std::size_t>(bits_), '#') +
+                    std::string(static_cast<std::size_t>(kMaxBits - bits_),
+                                '.') +
+                    " > " + std::to_string(bits_);
+        } else if (field == Field::kRun) {
+            value = Merging() ? "[ Merge ]" : "[ Unmerge ]";
+        } else if (focused && editing_) {
+            value = *editing_ + "_";
+        } else {
+            value = paths_.at(field).empty() ? "(none)" : paths_.at(field);
+        }
+
+        std::ostringstream line;
+        line << (focused ? "\x1b[7m> " : "  ") << std::left << std::setw(8)
+             << kLabels.at(field) << Clip(value, cols - 12) << "\x1b[0m";
+        return line.str();
+    }
+
+    void Draw() const {
+        auto [cols, rows] = RawTerminal::Size();
+        std::string screen = "\x1b[H\x1b[2J\x1b[1m steganography\x1b[0m\r\n\r\n";
+        for (Field field : Fields()) {
+            screen += FieldLine(field, cols) + "\r\n";
+        }
+        if (!Merging()) {
+            screen += "\r\n";
+        }
+        screen += "\r\n";
+
+        std::ostringstream readout;
+        readout << std::fixed << std::setprecision(2);
+        if (!readout_.error.empty()) {
+            readout << "  " << readout_.error;
+        } else if (Merging()) {
+            readout << "  PSNR vs. cover " << *readout_.cover_psnr
+                    << " dB, recovered secret " << *readout_.secret_psnr
+                    << " dB";
+        } else {
+            readout << "  extracting the low " << bits_ << " bits of "
+                    << readout_.recovered.width() << "x"
+                    << readout_.recovered.height() << " pixels";
+        }
+        screen += Clip(readout.str(), cols) + "\r\n";
+        screen += Clip("  " + status_, cols) + "\r\n\r\n";
+
+        /* the previews fill whatever space is left above the help line */
+        int preview_rows = rows - kFormRows;
+        if (readout_.error.empty() && (preview_rows > 2)) {
+            std::vector<std::string> left;
+            std::vector<std::string> right;
+            int width = Merging() ? (cols - 3) / 2 : cols - 2;
+            if (Merging()) {
+                left = RenderHalfblocks(boost::gil::const_view(readout_.stego),
+                                        width, preview_rows);
+                right = RenderHalfblocks(
+                    boost::gil::const_view(readout_.recovered), width,
+                    preview_rows);
+            } else {
+                left = RenderHalfblocks(
+                    boost::gil::const_view(readout_.recovered), width,
+                    preview_rows);
+            }
+            int right_col = width + 3;
+            int top = kFormRows - 2;
+            for (std::size_t i = 0; i < std::max(left.size(), right.size());
+                 ++i) {
+                std::string row = std::to_string(top + static_cast<int>(i));
+                if (i < left.size()) {
+                    screen += "\x1b[" + row + ";2H" + left[i];
+                }
+                if (i < right.size()) {
+                    screen += "\x1b[" + row + ";" + std::to_string(right_col) +
+                              "H" + right[i];
+                }
+            }
+        }
+
+        screen += "\x1b[" + std::to_string(rows) + ";1H\x1b[2m" +
+                  Clip(" up/down select, left/right or 1-8 change, enter pick "
+                       "or run, e edit path, r run, q quit",
+                       cols) +
+                  "\x1b[0m";
+        RawTerminal::Write(screen);
+    }
+
+    TuiConfig config_;
+    bool merge_ = true;
+    int bits_ = kDefaultBits;
+    std::size_t focus_ = 1;
+    std::map<Field, std::string> paths_;
+    std::optional<std::string> editing_;
+    std::string status_;
+    Readout readout_;
+    std::map<std::string, boost::gil::rgb8_image_t> images_;
+};
+
+RetCode RunTui(const TuiConfig& config) {
+    RawTerminal terminal;
+    if (!terminal.Active()) {
+        return RetCode::kIoError;
+    }
+    Tui tui(config);
+    tui.Run();
+    return RetCode::kSuccess;
+}
+
+}  // namespace steganography
@@ -0,0 +1,73 @@
+#include "utils/verify.hpp"
+
+#include <algorithm>
+#include <bit>
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <string>
+
+#include "utils/image_io.hpp"
+#include "utils/metrics.hpp"
+
+namespace steganography {
+
+static const int kChannelCount = 3;
+
+/* enough to locate the damage without flooding the terminal */
+static const std::size_t kMaxExamples = 10;
+
+RetCode VerifyPair(const std::string& cover, const std::string& stego,
+                   int bits, PairReport& report) {
+    if ((bits < 1) || (bits > 8)) {
+        return RetCode::kInvalidArgument;
+    }
+    boost::gil::rgb8_image_t cover_img;
+    RetCode rc = LoadImage(cover, cover_img);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    boost::gil::rgb8_image_t stego_img;
+    rc = LoadImage(stego, stego_img);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    if (cover_img.dimensions() != stego_img.dimensions()) {
+        return RetCode::kInvalidDimensions;
+    }
+
+    report = PairReport();
+    const unsigned kHighMask = 0xFFU << bits;
+    auto cover_view = boost::gil::const_view(cover_img);
+    auto stego_view = boost::gil::const_view(stego_img);
+    for (std::ptrdiff_t y = 0; y < cover_view.height(); ++y) {
+        for (std::ptrdiff_t x = 0; x < cover_view.width(); ++x) {
+            bool out_of_band = false;
+            for (int i = 0; i < kChannelCount; ++i) {
+                unsigned diff = static_cast<unsigned>(cover_view(x, y)[i] ^
+                                                      stego_view(x, y)[i]);
+                if (diff == 0) {
+                    continue;
+                }
+                ++report.changed_channels;
+                report.bits_needed = std::max(
+                    report.bits_needed, static_cast<int>(std::bit_width(diff)));
+                if ((diff & kHighMask) == 0) {
+                    continue;
+                }
+                ++report.out_of_band_channels;
+                out_of_band = true;
+                if (report.examples.size() < kMaxExamples) {
+                    report.examples.push_back({x, y, i, cover_view(x, y)[i],
+                                               stego_view(x, y)[i]});
+                }
+            }
+            report.out_of_band_pixels += out_of_band;
+        }
+    }
+    report.psnr = Psnr(cover_view, stego_view);
+    return (report.out_of_band_channels == 0) ? RetCode::kSuccess
+                                              : RetCode::kOutOfBandChanges;
+}
+
+}  // namespace steganography
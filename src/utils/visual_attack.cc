@@ -0,0 +1,95 @@
+#include "utils/visual_attack.hpp"
+
+#include <boost/gil.hpp>
+#include <cstddef>
+#include <cstdint>
+#include <string>
+#include <vector>
+
+#include "utils/image_io.hpp"
+#include "utils/paths.hpp"
+
+namespace steganography {
+
+static const int kChannelCount = 3;
+
+static bool IsValid(const VisualAttackConfig& config) {
+    if (config.planes.empty() || (config.planes.size() > 8) ||
+        config.channels.empty()) {
+        return false;
+    }
+    for (int plane : config.planes) {
+        if ((plane < 0) || (plane > 7)) {
+            return false;
+        }
+    }
+    for (int channel : config.channels) {
+        if ((channel < 0) || (channel >= kChannelCount)) {
+            return false;
+        }
+    }
+    return true;
+}
+
+RetCode RenderBitPlanes(const boost::gil::rgb8_image_t& image,
+                        const VisualAttackConfig& config,
+                        boost::gil::rgb8_image_t& rendered) {
+    if (!IsValid(config)) {
+        return RetCode::kInvalidArgument;
+    }
+
+    const int kMaxValue = (1 << config.planes.size()) - 1;
+    auto render = [&config, kMaxValue](uint8_t sample) {
+        int value = 0;
+        for (int plane : config.planes) {
+            value = (value << 1) | ((sample >> plane) & 1);
+        }
+        return static_cast<uint8_t>(value * 255 / kMaxValue);
+    };
+
+    rendered = boost::gil::rgb8_image_t(image.width(), image.height());
+    auto src = boost::gil::const_view(image);
+    auto dst = boost::gil::view(rendered);
+    for (std::ptrdiff_t y = 0; y < src.height(); ++y) {
+        for (std::ptrdiff_t x = 0; x < src.width(); ++x) {
+            boost::gil::rgb8_pixel_t pixel(0, 0, 0);
+            if (config.channels.size() == 1) {
+                uint8_t gray = render(src(x, y)[config.channels[0]]);
+                pixel = boost::gil::rgb8_pixel_t(gray, gray, gray);
+            } else {
+                for (int channel : config.channels) {
+                    pixel[channel] = render(src(x, y)[channel]);
+                }
+            }
+            dst(x, y) = pixel;
+        }
+    }
+    return RetCode::kSuccess;
+}
+
+RetCode VisualAttack(const std::string& image, const std::string& outfile,
+                     const VisualAttackConfig& config) {
+    boost::gil::rgb8_image_t input_img;
+    RetCode rc = LoadImage(image, input_img);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    boost::gil::rgb8_image_t rendered;
+    rc = RenderBitPlanes(input_img, config, rendered);
+    if (rc != RetCode::kSuccess) {
+        return rc;
+    }
+    if (config.create_dirs) {
+        rc = CreateParentDirectories(outfile);
+        if (rc != RetCode::kSuccess) {
+            return rc;
+        }
+    }
+
+    /* write the image out using the user's preferred format (default PNG) */
+    WriteImage(rendered, outfile,
+               HasJpegExtension(outfile) ? ImageType::kJpeg : ImageType::kPng);
+    return RetCode::kSuccess;
+}
+
+}  // namespace steganography
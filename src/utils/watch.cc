@@ -0,0 +1,170 @@
+#include "utils/watch.hpp"
+
+#include <poll.h>
+#include <sys/inotify.h>
+#include <unistd.h>
+
+#include <algorithm>
+#include <cerrno>
+#include <chrono>
+#include <climits>
+#include <cstdint>
+#include <exception>
+#include <filesystem>
+#include <fstream>
+#include <map>
+#include <set>
+#include <string>
+#include <system_error>
+
+namespace steganography {
+
+using Clock = std::chrono::steady_clock;
+
+/* kept in the output directory, one '<status>\t<file name>' line per file */
+static const char* kLedgerName = ".steganography-ledger";
+
+static std::set<std::string> LoadLedger(const std::filesystem::path& ledger) {
+    std::set<std::string> processed;
+    std::ifstream ifs(ledger);
+    std::string line;
+    while (std::getline(ifs, line)) {
+        std::size_t tab = line.find('\t');
+        if (tab != std::string::npos) {
+            processed.insert(line.substr(tab + 1));
+        }
+    }
+    return processed;
+}
+
+static RetCode ProcessFile(const WatchConfig& config,
+                           const std::filesystem::path& infile,
+                           const std::filesystem::path& outfile) {
+    try {
+        if (config.mode == WatchMode::kMerge) {
+            MergeConfig merge;
+            merge.keyfile = config.keyfile;
+            merge.password = config.password;
+            return Merge(infile.string(), config.secret, outfile.string(),
+                         merge);
+        }
+        UnmergeConfig unmerge;
+        unmerge.keyfile = config.keyfile;
+        unmerge.password = config.password;
+        return Unmerge(infile.string(), outfile.string(), unmerge);
+    } catch (const std::exception&) {
+        /* image codecs throw on truncated or corrupt files */
+        return RetCode::kIoError;
+    }
+}
+
+RetCode Watch(const WatchConfig& config) {
+    if (!std::filesystem::is_directory(config.indir)) {
+        return RetCode::kFileNotFound;
+    }
+    if ((config.mode == WatchMode::kMerge) &&
+        !std::filesystem::exists(config.secret)) {
+        return RetCode::kFileNotFound;
+    }
+    std::error_code ec;
+    std::filesystem::create_directories(config.outdir, ec);
+    if (ec) {
+        return RetCode::kIoError;
+    }
+    if (std::filesystem::equivalent(config.indir, config.outdir, ec)) {
+        return RetCode::kInvalidArgument; /* outputs would be reprocessed */
+    }
+
+    std::filesystem::path ledger_path =
+        std::filesystem::path(config.outdir) / kLedgerName;
+    std::set<std::string> processed = LoadLedger(ledger_path);
+    std::ofstream ledger(ledger_path, std::ofstream::app);
+    if (!ledger.is_open()) {
+        return RetCode::kIoError;
+    }
+
+    int fd = inotify_init1(IN_CLOEXEC);
+    if (fd < 0) {
+        return RetCode::kIoError;
+    }
+    const uint32_t kEvents = IN_CREATE | IN_MODIFY | IN_CLOSE_WRITE |
+                             IN_MOVED_TO | IN_ATTRIB;
+    if (inotify_add_watch(fd, config.indir.c_str(), kEvents) < 0) {
+        close(fd);
+        return RetCode::kIoError;
+    }
+
+    /* files are processed once they have been quiet for the debounce time,
+     * files present before the watch started are treated as just seen */
+    const auto kDebounce = std::chrono::milliseconds(config.debounce_ms);
+    std::map<std::string, Clock::time_point> pending;
+    for (const auto& entry :
+         std::filesystem::directory_iterator(config.indir, ec)) {
+        pending[entry.path().filename().string()] = Clock::now();
+    }
+
+    alignas(inotify_event) char buffer[4096];
+    while (true) {
+        int timeout = -1;
+        if (!pending.empty()) {
+            auto next = std::min_element(
+                pending.cbegin(), pending.cend(),
+                [](const auto& lhs, const auto& rhs) {
+                    return lhs.second < rhs.second;
+                });
+            auto wait = std::chrono::duration_cast<std::chrono::milliseconds>(
+                next->second + kDebounce - Clock::now());
+            timeout = static_cast<int>(
+                std::clamp<int64_t>(wait.count(), 0, INT_MAX));
+        }
+
+        pollfd pfd = {fd, POLLIN, 0};
+        int ready = poll(&pfd, 1, timeout);
+        if ((ready < 0) && (errno != EINTR)) {
+            close(fd);
+            return RetCode::kIoError;
+        }
+        if (ready > 0) {
+            ssize_t len = read(fd, buffer, sizeof(buffer));
+            for (ssize_t offset = 0; offset < len;) {
+                const inotify_event* event =
+                    reinterpret_cast<const inotify_event*>(buffer + offset);
+                if ((event->len > 0) && !(event->mask & IN_ISDIR)) {
+                    pending[event->name] = Clock::now();
+                }
+                offset += static_cast<ssize_t>(sizeof(inotify_event) +
+                                               event->len);
+            }
+        }
+
+        auto now = Clock::now();
+        for (auto it = pending.begin(); it != pending.end();) {
+            if (now - it->second < kDebounce) {
+                ++it;
+                continue;
+            }
+            std::string name = it->first;
+            it = pending.erase(it);
+
+            std::filesystem::path infile =
+                std::filesystem::path(config.indir) / name;
+            if (name.starts_with(".") || processed.contains(name) ||
+                !std::filesystem::is_regular_file(infile, ec)) {
+                continue;
+            }
+            /* the whole name is kept, photo.jpg and photo.png would
+             * otherwise both write photo.png and one would be lost */
+            std::filesystem::path outfile =
+                std::filesystem::path(config.outdir) / (name + ".png");
+            RetCode rc = ProcessFile(config, infile, outfile);
+            processed.insert(name);
+            ledger << ((rc == RetCode::kSuccess) ? "ok" : "failed") << "\t"
+                   << name << std::endl;
+            if (config.on_processed) {
+                config.on_processed(infile.string(), outfile.string(), rc);
+            }
+        }
+    }
+}
+
+}  // namespace steganography
@@ -0,0 +1,118 @@
+#include "utils/wav.hpp"
+
+#include <cstddef>
+#include <cstdint>
+#include <filesystem>
+#include <fstream>
+#include <iterator>
+#include <string>
+#include <vector>
+
+namespace steganography {
+
+static const uint16_t kPcmFormat = 1;
+static const uint16_t kBitsPerSample = 16;
+static const std::size_t kFormatSize = 16;
+
+/* unlike the payload's own fields, RIFF integers are little endian */
+template <typename T>
+static T ReadLittle(const std::vector<uint8_t>& buffer, std::size_t offset) {
+    T value = 0;
+    for (std::size_t i = sizeof(T); i > 0; --i) {
+        value = static_cast<T>((value << 8) | buffer[offset + i - 1]);
+    }
+    return value;
+}
+
+template <typename T>
+static void AppendLittle(std::vector<uint8_t>& buffer, T value) {
+    for (std::size_t i = 0; i < sizeof(T); ++i) {
+        buffer.push_back(static_cast<uint8_t>(value >> (8 * i)));
+    }
+}
+
+static void AppendTag(std::vector<uint8_t>& buffer, const std::string& tag) {
+    buffer.insert(buffer.end(), tag.cbegin(), tag.cend());
+}
+
+RetCode ReadWav(const std::string& filename, WavAudio& audio) {
+    std::ifstream ifs(filename, std::ifstream::binary);
+    if (!ifs.is_open()) {
+        return std::filesystem::exists(filename) ? RetCode::kIoError
+                                                 : RetCode::kFileNotFound;
+    }
+    std::vector<uint8_t> data((std::istreambuf_iterator<char>(ifs)),
+                              std::istreambuf_iterator<char>());
+    if ((data.size() < 12) ||
+        (std::string(data.cbegin(), data.cbegin() + 4) != "RIFF") ||
+        (std::string(data.cbegin() + 8, data.cbegin() + 12) != "WAVE")) {
+        return RetCode::kInvalidFileFormat;
+    }
+
+    /* chunks are padded to an even size */
+    bool has_format = false;
+    std::size_t offset = 12;
+    while (offset + 8 <= data.size()) {
+        const std::string kId(reinterpret_cast<const char*>(&data[offset]),
+                              4);
+        const std::size_t kSize = ReadLittle<uint32_t>(data, offset + 4);
+        const std::size_t kData = offset + 8;
+        if (kSize > data.size() - kData) {
+            return RetCode::kInvalidFileFormat;
+        }
+        if (kId == "fmt ") {
+            if ((kSize < kFormatSize) ||
+                (ReadLittle<uint16_t>(data, kData) != kPcmFormat) ||
+                (ReadLittle<uint16_t>(data, kData + 14) != kBitsPerSample)) {
+                return RetCode::kUnsupportedFormat;
+            }
+            audio.channels = ReadLittle<uint16_t>(data, kData + 2);
+            audio.sample_rate = ReadLittle<uint32_t>(data, kData + 4);
+            has_format = (audio.channels > 0);
+        } else if (kId == "data") {
+            if (!has_format) {
+                return RetCode::kInvalidFileFormat;
+            }
+            audio.samples.resize(kSize / 2);
+            for (std::size_t i = 0; i < audio.samples.size(); ++i) {
+                audio.samples[i] = static_cast<int16_t>(
+                    ReadLittle<uint16_t>(data, kData + 2 * i));
+            }
+            return RetCode::kSuccess;
+        }
+        offset = kData + kSize + (kSize & 1);
+    }
+    return RetCode::kInvalidFileFormat;
+}
+
+RetCode WriteWav(const WavAudio& audio, const std::string& filename) {
+    const uint32_t kDataSize =
+        static_cast<uint32_t>(audio.samples.size() * 2);
+    const uint16_t kBlockAlign =
+        static_cast<uint16_t>(audio.channels * kBitsPerSample / 8);
+    std::vector<uint8_t> data;
+    data.reserve(44 + kDataSize);
+    AppendTag(data, "RIFF");
+    AppendLittle<uint32_t>(data, static_cast<uint32_t>(36 + kDataSize));
+    AppendTag(data, "WAVE");
+    AppendTag(data, "fmt ");
+    AppendLittle<uint32_t>(data, static_cast<uint32_t>(kFormatSize));
+    AppendLittle<uint16_t>(data, kPcmFormat);
+    AppendLittle<uint16_t>(data, audio.channels);
+    AppendLittle<uint32_t>(data, audio.sample_rate);
+    AppendLittle<uint32_t>(data, audio.sample_rate * kBlockAlign);
+    AppendLittle<uint16_t>(data, kBlockAlign);
+    AppendLittle<uint16_t>(data, kBitsPerSample);
+    AppendTag(data, "data");
+    AppendLittle<uint32_t>(data, kDataSize);
+    for (int16_t sample : audio.samples) {
+        AppendLittle<uint16_t>(data, static_cast<uint16_t>(sample));
+    }
+
+    std::ofstream ofs(filename, std::ofstream::binary);
+    ofs.write(reinterpret_cast<const char*>(data.data()),
+              static_cast<std::streamsize>(data.size()));
+    return ofs ? RetCode::kSuccess : RetCode::kIoError;
+}
+
+}  // namespace steganography